@@ -0,0 +1,174 @@
+use itertools::Itertools;
+
+use crate::expr::context::Context;
+use crate::value::Value;
+
+/// Shortest common substring reported as copied; single characters shared by chance are left as inserted.
+const MIN_COPY: usize = 2;
+
+/// Colors of the copied segments, cycled by segment.
+const COLORS: [&str; 6] = ["\u{001b}[32m", "\u{001b}[33m", "\u{001b}[34m", "\u{001b}[35m", "\u{001b}[36m", "\u{001b}[31m"];
+const RESET: &str = "\u{001b}[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+/// How `--showex` displays the examples.
+pub enum ShowEx {
+    /// One row per example, one column per argument and the output
+    #[default]
+    Table,
+    /// Each output aligned with the inputs, the substrings copied from them highlighted
+    Align,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A part of an output, in order: copied from an input, or inserted.
+///
+/// Positions and lengths count characters.
+pub enum Segment {
+    /// `len` characters at `start` of the output, copied from position `from` of argument `arg`
+    Copied { start: usize, len: usize, arg: usize, from: usize },
+    /// `len` characters at `start` of the output found in no input
+    Inserted { start: usize, len: usize },
+}
+
+impl Segment {
+    fn range(&self) -> (usize, usize) {
+        match self {
+            Self::Copied { start, len, .. } | Self::Inserted { start, len } => (*start, *len),
+        }
+    }
+}
+
+/// The longest substring of `output` occurring in one of the `inputs`, as `(start in output, length, argument, start in input)`; the first argument wins ties.
+fn longest_match(output: &[char], inputs: &[Option<Vec<char>>]) -> Option<(usize, usize, usize, usize)> {
+    let mut best: Option<(usize, usize, usize, usize)> = None;
+    for (arg, input) in inputs.iter().enumerate() {
+        let Some(input) = input else { continue };
+        let mut prev = vec![0; input.len() + 1];
+        for (i, o) in output.iter().enumerate() {
+            let mut cur = vec![0; input.len() + 1];
+            for (j, c) in input.iter().enumerate() {
+                if o == c {
+                    cur[j + 1] = prev[j] + 1;
+                    let len = cur[j + 1];
+                    if best.is_none_or(|b| len > b.1) {
+                        best = Some((i + 1 - len, len, arg, j + 1 - len));
+                    }
+                }
+            }
+            prev = cur;
+        }
+    }
+    best
+}
+
+/// Aligns the characters `offset..offset + output.len()` of an output, pushing its segments in order.
+fn align_rec(output: &[char], offset: usize, inputs: &[Option<Vec<char>>], result: &mut Vec<Segment>) {
+    if output.is_empty() { return; }
+    match longest_match(output, inputs) {
+        Some((i, len, arg, from)) if len >= MIN_COPY => {
+            align_rec(&output[..i], offset, inputs, result);
+            result.push(Segment::Copied { start: offset + i, len, arg, from });
+            align_rec(&output[i + len..], offset + i + len, inputs, result);
+        }
+        _ => match result.last_mut() {
+            Some(Segment::Inserted { start, len }) if *start + *len == offset => *len += output.len(),
+            _ => result.push(Segment::Inserted { start: offset, len: output.len() }),
+        }
+    }
+}
+
+/// Aligns an output with the inputs of its example: the output is split around its longest substring found in an input, recursively on both sides, chaining the common substrings.
+///
+/// Inputs that are not strings are `None`.
+pub fn align(inputs: &[Option<&str>], output: &str) -> Vec<Segment> {
+    let inputs = inputs.iter().map(|s| s.map(|s| s.chars().collect_vec())).collect_vec();
+    let mut result = Vec::new();
+    align_rec(&output.chars().collect_vec(), 0, &inputs, &mut result);
+    result
+}
+
+/// Writes `s` with the character ranges `(start, len, color)` highlighted, in color or between brackets.
+fn highlight(s: &str, spans: &[(usize, usize, usize)], color: bool) -> String {
+    let chars = s.chars().collect_vec();
+    let mut result = String::new();
+    let mut i = 0;
+    for &(start, len, k) in spans.iter().sorted() {
+        if start < i { continue; }
+        result.extend(&chars[i..start]);
+        let text = chars[start..start + len].iter().collect::<String>();
+        if color { result += &format!("{}{}{}", COLORS[k % COLORS.len()], text, RESET); } else { result += &format!("[{}]", text); }
+        i = start + len;
+    }
+    result.extend(&chars[i..]);
+    result
+}
+
+/// Displays, for each example of a string task, the inputs and the output with the copied segments highlighted alike (colors, or brackets without `color`), followed by where each segment of the output comes from.
+///
+/// `header` names the arguments then the function, as for [`Context::format_table`]. Returns `None` if the outputs are not strings.
+pub fn format_alignment(ctx: &Context, header: &[String], color: bool) -> Option<String> {
+    let Value::Str(outputs) = ctx.output else { return None };
+    let width = header.iter().map(|h| h.chars().count()).max().unwrap_or(0);
+    let mut result = String::new();
+    for (i, output) in outputs.iter().enumerate() {
+        let inputs = ctx.p.iter().map(|v| if let Value::Str(a) = v { Some(a[i]) } else { None }).collect_vec();
+        let segments = align(&inputs, output);
+        result += &format!("# example {}\n", i + 1);
+        for (arg, v) in ctx.p.iter().enumerate() {
+            let spans = segments.iter().enumerate().filter_map(|(k, s)| match s {
+                Segment::Copied { len, arg: a, from, .. } if *a == arg => Some((*from, *len, k)),
+                _ => None,
+            }).collect_vec();
+            let text = match inputs[arg] {
+                Some(s) => format!("\"{}\"", highlight(s, &spans, color)),
+                None => v.to_display_string(i),
+            };
+            result += &format!("  {:<width$} = {}\n", header[arg], text);
+        }
+        let spans = segments.iter().enumerate().filter_map(|(k, s)| matches!(s, Segment::Copied { .. }).then(|| (s.range().0, s.range().1, k))).collect_vec();
+        result += &format!("  {:<width$} = \"{}\"\n", header[ctx.p.len()], highlight(output, &spans, color));
+        let chars = output.chars().collect_vec();
+        let parts = segments.iter().map(|s| {
+            let (start, len) = s.range();
+            let text = chars[start..start + len].iter().collect::<String>();
+            match s {
+                Segment::Copied { arg, from, .. } => format!("{:?} from {}[{}..{}]", text, header[*arg], from, from + len),
+                Segment::Inserted { .. } => format!("{:?} inserted", text),
+            }
+        }).join(", ");
+        result += &format!("  ; {}\n", if parts.is_empty() { "empty output".to_string() } else { parts });
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr::context::Context, galloc::AllocForExactSizeIter, value::Value};
+
+    use super::{align, format_alignment, Segment};
+
+    #[test]
+    fn test_align() {
+        assert_eq!(align(&[Some("John Smith")], "Smith, J."), vec![
+            Segment::Copied { start: 0, len: 5, arg: 0, from: 5 },
+            Segment::Inserted { start: 5, len: 4 },
+        ]);
+        assert_eq!(align(&[None, Some("2021-05-03")], "05/03"), vec![
+            Segment::Copied { start: 0, len: 2, arg: 1, from: 5 },
+            Segment::Inserted { start: 2, len: 1 },
+            Segment::Copied { start: 3, len: 2, arg: 1, from: 8 },
+        ]);
+
+        let name: Value = ["John Smith"].into_iter().galloc_scollect().into();
+        let output: Value = ["Smith, Jo"].into_iter().galloc_scollect().into();
+        let ctx = Context::new(1, vec![name], vec![], output);
+        let header = ["name", "f"].map(String::from);
+        assert_eq!(format_alignment(&ctx, &header, false).unwrap(), concat!(
+            "# example 1\n",
+            "  name = \"[Jo]hn [Smith]\"\n",
+            "  f    = \"[Smith], [Jo]\"\n",
+            "  ; \"Smith\" from name[5..10], \", \" inserted, \"Jo\" from name[0..2]\n",
+        ));
+    }
+}
@@ -0,0 +1,179 @@
+use itertools::Itertools;
+
+use crate::expr::{cfg::{Cfg, ProdRule}, context::Context, ops::{Op1Enum, Op2Enum, Op3Enum}, Expr};
+use crate::galloc::{AllocForAny, AllocForExactSizeIter};
+use crate::parser::{config::Config, problem::FunSig};
+use crate::value::{ConstValue, Type, Value};
+
+/// Delimiters of the fields of delimited inputs, in order of preference: TSV, CSV, then other common separators.
+const DELIMITERS: [&str; 4] = ["\t", ",", ";", "|"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// How the fields of a string input are laid out.
+pub enum Layout {
+    /// Fields separated by a delimiter; `trim` removes the spaces around them (`a, b`).
+    Delimited { delimiter: &'static str, trim: bool },
+    /// Fields at fixed byte ranges `(start, length)`, padded with spaces.
+    FixedWidth(Vec<(usize, usize)>),
+}
+
+impl Layout {
+    /// Detects the layout shared by all `inputs`, with its number of columns, if any.
+    ///
+    /// Inputs are delimited if they all have the same number of fields, at least two, separated by one of [`DELIMITERS`]. Otherwise, ASCII inputs of the same length are fixed-width if some positions hold a space in all of them, separating at least two columns.
+    /// A single input is not enough to tell a structure from plain text.
+    pub fn detect(inputs: &[&str]) -> Option<(Self, usize)> {
+        if inputs.len() < 2 { return None; }
+        for delimiter in DELIMITERS {
+            let counts = inputs.iter().map(|s| s.split(delimiter).count()).collect_vec();
+            if counts[0] >= 2 && counts.iter().all_equal() {
+                let trim = inputs.iter().flat_map(|s| s.split(delimiter)).any(|f| f.trim() != f);
+                return Some((Self::Delimited { delimiter, trim }, counts[0]));
+            }
+        }
+        let len = inputs[0].len();
+        if len == 0 || !inputs.iter().all(|s| s.len() == len && s.is_ascii()) { return None; }
+        let blank = (0..len).map(|i| inputs.iter().all(|s| s.as_bytes()[i] == b' ')).collect_vec();
+        let mut ranges = Vec::new();
+        let mut start = None;
+        for (i, b) in blank.iter().chain([&true]).enumerate() {
+            match (start, b) {
+                (None, false) => start = Some(i),
+                (Some(s), true) => { ranges.push((s, i - s)); start = None; }
+                _ => (),
+            }
+        }
+        (ranges.len() >= 2).then(|| { let n = ranges.len(); (Self::FixedWidth(ranges), n) })
+    }
+    /// The `i`-th field of `s`.
+    pub fn field(&self, s: &'static str, i: usize) -> &'static str {
+        match self {
+            Self::Delimited { delimiter, trim } => {
+                let f = s.split(delimiter).nth(i).unwrap_or("");
+                if *trim { f.trim() } else { f }
+            }
+            Self::FixedWidth(ranges) => s.get(ranges[i].0..ranges[i].0 + ranges[i].1).unwrap_or("").trim(),
+        }
+    }
+    /// An expression computing the `i`-th field from the raw string `arg`.
+    pub fn accessor(&self, arg: &'static Expr, i: usize) -> &'static Expr {
+        let op1 = |name: &str| Op1Enum::from_name(name, &Config::new()).galloc();
+        let op2 = |name: &str| Op2Enum::from_name(name, &Config::new()).galloc();
+        let int = |n: usize| Expr::Const(ConstValue::Int(n as i64)).galloc();
+        match self {
+            Self::Delimited { delimiter, trim } => {
+                let split = Expr::Op2(op2("str.split"), arg, Expr::Const(ConstValue::Str(delimiter)).galloc()).galloc();
+                let f = Expr::Op2(op2("list.at"), split, int(i)).galloc();
+                if *trim { Expr::Op1(op1("str.trim"), f).galloc() } else { f }
+            }
+            Self::FixedWidth(ranges) => {
+                let substr = Op3Enum::from_name("str.substr", &Config::new()).galloc();
+                let f = Expr::Op3(substr, arg, int(ranges[i].0), int(ranges[i].1)).galloc();
+                Expr::Op1(op1("str.trim"), f).galloc()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The columns of a string argument, added to the context as pseudo-variables.
+pub struct ColumnSet {
+    /// Index of the argument
+    pub arg: usize,
+    pub layout: Layout,
+    /// Index of the variable of the first column; the others follow
+    pub first: usize,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+/// Columns detected in the string arguments of a problem, and the mapping from their pseudo-variables back to the arguments.
+pub struct Columns(pub Vec<ColumnSet>);
+
+impl Columns {
+    /// Detects the column structure of each string argument in the examples of `ctx`.
+    pub fn detect(ctx: &Context) -> Self {
+        let mut first = ctx.p.len();
+        let mut result = Vec::new();
+        for (arg, v) in ctx.p.iter().enumerate() {
+            let Value::Str(inputs) = v else { continue };
+            if let Some((layout, count)) = Layout::detect(inputs) {
+                result.push(ColumnSet { arg, layout, first, count });
+                first += count;
+            }
+        }
+        Self(result)
+    }
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+    /// Adds the columns to the inputs of `ctx`, and as variables to the non-terminals of `cfg` producing their argument.
+    pub fn extend(&self, ctx: &mut Context, cfg: &mut Cfg) {
+        for set in self.0.iter() {
+            let Value::Str(inputs) = ctx.p[set.arg] else { continue };
+            for i in 0..set.count {
+                ctx.p.push(Value::Str(inputs.iter().map(|&s| set.layout.field(s, i)).galloc_scollect()));
+            }
+            for nt in cfg.iter_mut() {
+                if nt.rules.iter().any(|r| matches!(r, ProdRule::Var(a) if *a == set.arg as i64)) {
+                    nt.rules.extend((0..set.count).map(|i| ProdRule::Var((set.first + i) as i64)));
+                }
+            }
+        }
+    }
+    /// The signature of the function over its arguments and their columns, named after their argument (`line_0`, `line_1`, ...).
+    pub fn signature(&self, sig: &FunSig) -> FunSig {
+        let mut result = sig.clone();
+        for set in self.0.iter() {
+            let name = &sig.args[set.arg].0;
+            result.args.extend((0..set.count).map(|i| (format!("{name}_{i}"), Type::Str)));
+        }
+        result
+    }
+    /// Rewrites the columns in `e` as accessors of the raw strings of their arguments, so that it only refers to the arguments of the function.
+    pub fn expand(&self, e: &'static Expr) -> &'static Expr {
+        if self.is_empty() { return e; }
+        match e {
+            Expr::Var(v) => self.0.iter()
+                .find(|set| (set.first..set.first + set.count).contains(&(*v as usize)))
+                .map_or(e, |set| set.layout.accessor(Expr::Var(set.arg as i64).galloc(), *v as usize - set.first)),
+            Expr::Const(_) => e,
+            Expr::Op1(op, a1) => Expr::Op1(op, self.expand(a1)).galloc(),
+            Expr::Op2(op, a1, a2) => Expr::Op2(op, self.expand(a1), self.expand(a2)).galloc(),
+            Expr::Op3(op, a1, a2, a3) => Expr::Op3(op, self.expand(a1), self.expand(a2), self.expand(a3)).galloc(),
+            Expr::Let(v, a1, body) => Expr::Let(*v, self.expand(a1), self.expand(body)).galloc(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr::{cfg::{Cfg, CfgConfig, NonTerminal, ProdRule}, context::Context, Expr}, galloc::{AllocForAny, AllocForExactSizeIter}, parser::config::Config, value::{Type, Value}};
+
+    use super::{Columns, Layout};
+
+    #[test]
+    fn test_layout() {
+        assert_eq!(Layout::detect(&["a,b,c", "d,,f"]), Some((Layout::Delimited { delimiter: ",", trim: false }, 3)));
+        assert_eq!(Layout::detect(&["a\t1,5", "b\t2"]), Some((Layout::Delimited { delimiter: "\t", trim: false }, 2)));
+        assert_eq!(Layout::detect(&["a, b", "c, d"]).map(|l| l.0.field("x,  y", 1)), Some("y"));
+        assert_eq!(Layout::detect(&["John   42", "Ann    7 "]), Some((Layout::FixedWidth(vec![(0, 4), (7, 2)]), 2)));
+        assert_eq!(Layout::detect(&["a,b"]), None);
+        assert_eq!(Layout::detect(&["a b c", "long text"]), None);
+    }
+
+    #[test]
+    fn test_columns() {
+        let input: Value = ["Smith,John,42", "Doe,Jane,7"].into_iter().galloc_scollect().into();
+        let mut ctx = Context::new(2, vec![input], vec![], input);
+        let columns = Columns::detect(&ctx);
+        let nt = NonTerminal { name: "S".into(), ty: Type::Str, rules: vec![ProdRule::Var(0)], config: Config::new() };
+        let mut cfg = Cfg::from_single_nt(nt, CfgConfig::from(Config::new()));
+        columns.extend(&mut ctx, &mut cfg);
+        assert_eq!(ctx.p.len(), 4);
+        assert_eq!(cfg[0].rules.len(), 4);
+        let e = Expr::Var(2).galloc();
+        assert_eq!(e.eval(&ctx), Value::Str(&["John", "Jane"]));
+        let raw = columns.expand(e);
+        assert_eq!(format!("{:?}", raw), "(list.at (str.split <0> \",\") 1)");
+        assert_eq!(raw.eval(&ctx), e.eval(&ctx));
+    }
+}
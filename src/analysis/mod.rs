@@ -0,0 +1,11 @@
+/// Column structure of delimited or fixed-width string inputs
+pub mod columns;
+pub use columns::*;
+
+/// Relevance of the arguments to the outputs, to enumerate the likely ones first
+pub mod relevance;
+pub use relevance::*;
+
+/// Alignment of the outputs with the substrings of the inputs they copy, shown by `--showex align`
+pub mod alignment;
+pub use alignment::*;
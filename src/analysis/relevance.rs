@@ -0,0 +1,88 @@
+use itertools::Itertools;
+
+use crate::expr::{cfg::{Cfg, ProdRule}, context::Context};
+use crate::value::ConstValue;
+
+/// Length in characters of the longest string occurring in both `a` and `b`.
+fn common_substring_len(a: &str, b: &str) -> usize {
+    let (a, b) = (a.chars().collect_vec(), b.chars().collect_vec());
+    let mut prev = vec![0; b.len() + 1];
+    let mut best = 0;
+    for x in &a {
+        let mut cur = vec![0; b.len() + 1];
+        for (j, y) in b.iter().enumerate() {
+            if x == y {
+                cur[j + 1] = prev[j] + 1;
+                best = best.max(cur[j + 1]);
+            }
+        }
+        prev = cur;
+    }
+    best
+}
+
+/// The text of an input or output as it may appear in the output: strings as they are, numbers as written in decimal.
+fn text(c: ConstValue) -> Option<String> {
+    match c {
+        ConstValue::Str(s) => Some(s.to_owned()),
+        ConstValue::Int(i) => Some(i.to_string()),
+        ConstValue::Float(_) => c.as_f64().map(|f| f.to_string()),
+        ConstValue::Char(ch) => Some(ch.to_string()),
+        _ => None,
+    }
+}
+
+/// How much of the outputs argument `arg` may account for: the mean, over the examples, of the fraction of the output covered by its longest substring shared with the argument.
+pub fn relevance(ctx: &Context, arg: usize) -> f64 {
+    if ctx.len == 0 { return 0.0; }
+    let total: f64 = (0..ctx.len).map(|i| {
+        let (Some(input), Some(output)) = (text(ctx.p[arg].get_const(i)), text(ctx.output.get_const(i))) else { return 0.0 };
+        let n = output.chars().count();
+        if n == 0 { 0.0 } else { common_substring_len(&input, &output) as f64 / n as f64 }
+    }).sum();
+    total / ctx.len as f64
+}
+
+/// The arguments of the examples of `ctx`, most relevant first (see [`relevance`]); ties keep the order of the signature.
+pub fn arg_priority(ctx: &Context) -> Vec<usize> {
+    let scores = (0..ctx.p.len()).map(|i| relevance(ctx, i)).collect_vec();
+    (0..ctx.p.len()).sorted_by(|a, b| scores[*b].total_cmp(&scores[*a])).collect_vec()
+}
+
+/// Reorders the variables of each non-terminal of `cfg` by [`arg_priority`], so that the most relevant arguments are enumerated first.
+///
+/// The variables keep the positions of the variable rules among the other rules; only which argument comes at each of them changes.
+pub fn prioritize_args(cfg: &mut Cfg, ctx: &Context) {
+    let priority = arg_priority(ctx);
+    let rank = |v: i64| priority.iter().position(|a| *a as i64 == v).unwrap_or(usize::MAX);
+    for nt in cfg.iter_mut() {
+        let slots = nt.rules.iter().positions(|r| matches!(r, ProdRule::Var(v) if *v >= 0)).collect_vec();
+        let vars = slots.iter().map(|i| nt.rules[*i].clone()).sorted_by_key(|r| match r { ProdRule::Var(v) => rank(*v), _ => usize::MAX }).collect_vec();
+        for (i, r) in slots.into_iter().zip(vars) {
+            nt.rules[i] = r;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr::{cfg::{Cfg, CfgConfig, NonTerminal, ProdRule}, context::Context}, galloc::AllocForExactSizeIter, parser::config::Config, value::{Type, Value}};
+
+    use super::{arg_priority, common_substring_len, prioritize_args};
+
+    #[test]
+    fn test_arg_priority() {
+        assert_eq!(common_substring_len("Jane Doe", "Doe, J."), 3);
+        let city: Value = ["Paris", "Rome"].into_iter().galloc_scollect().into();
+        let name: Value = ["Smith", "Doe"].into_iter().galloc_scollect().into();
+        let age: Value = [42i64, 7].into_iter().galloc_scollect().into();
+        let output: Value = ["Smith (42)", "Doe (7)"].into_iter().galloc_scollect().into();
+        let ctx = Context::new(2, vec![city, age, name], vec![], output);
+        assert_eq!(arg_priority(&ctx), vec![2, 1, 0]);
+
+        let nt = NonTerminal { name: "S".into(), ty: Type::Str, rules: vec![ProdRule::Var(0), ProdRule::Nt(1), ProdRule::Var(2)], config: Config::new() };
+        let mut cfg = Cfg::from_single_nt(nt, CfgConfig::from(Config::new()));
+        prioritize_args(&mut cfg, &ctx);
+        assert!(matches!(cfg[0].rules.as_slice(), [ProdRule::Var(2), ProdRule::Nt(1), ProdRule::Var(0)]));
+    }
+}
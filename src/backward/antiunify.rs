@@ -0,0 +1,72 @@
+use itertools::Itertools;
+
+use crate::{galloc::AllocForExactSizeIter, value::Value};
+
+#[derive(Debug, Clone, PartialEq)]
+/// A part of a template shared by the outputs of all examples.
+pub enum Part {
+    /// A constant common to all outputs
+    Const(&'static str),
+    /// The differing parts of the outputs, one string per example
+    Hole(Value),
+}
+
+/// Returns the longest substring of at least `min_len` characters that occurs in every string.
+fn longest_common_substring(strs: &[&'static str], min_len: usize) -> Option<&'static str> {
+    let shortest = *strs.iter().min_by_key(|s| s.len())?;
+    let bounds = shortest.char_indices().map(|(i, _)| i).chain(std::iter::once(shortest.len())).collect_vec();
+    for len in (min_len..bounds.len()).rev() {
+        for w in bounds.windows(len + 1) {
+            let candidate = &shortest[w[0]..w[len]];
+            if strs.iter().all(|s| s.contains(candidate)) {
+                return Some(candidate);
+            }
+        }
+    }
+    None
+}
+
+/// Splits every string around the first occurrence of the longest common substring, recursively on both sides.
+fn template(strs: Vec<&'static str>, min_len: usize, result: &mut Vec<Part>) {
+    if let Some(c) = longest_common_substring(&strs, min_len) {
+        let (left, right): (Vec<_>, Vec<_>) = strs.iter().map(|s| {
+            let i = s.find(c).unwrap();
+            (&s[..i], &s[i + c.len()..])
+        }).unzip();
+        template(left, min_len, result);
+        result.push(Part::Const(c));
+        template(right, min_len, result);
+    } else if strs.iter().any(|s| !s.is_empty()) {
+        result.push(Part::Hole(strs.into_iter().galloc_scollect().into()));
+    }
+}
+
+/// Anti-unifies the outputs of the examples: finds a template, i.e. a sequence of constants (of at least `min_len` characters) common to all outputs, separated by holes.
+///
+/// Each constant is the longest common substring of the part of the outputs it is taken from, e.g. `Dear Ann, your order 12 shipped` and `Dear Bob, your order 7 shipped` give `"Dear " [Ann Bob] ", your order " [12 7] " shipped"`.
+/// Returns an empty template when the outputs have no constant in common, or when there are fewer than two examples to generalize from.
+pub fn anti_unify(outputs: &'static [&'static str], min_len: usize) -> Vec<Part> {
+    let mut result = Vec::new();
+    if outputs.len() < 2 || min_len == 0 { return result; }
+    template(outputs.to_vec(), min_len, &mut result);
+    if result.iter().any(|p| matches!(p, Part::Const(_))) { result } else { Vec::new() }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{galloc::AllocForExactSizeIter, value::Value};
+
+    use super::{anti_unify, Part};
+
+    #[test]
+    fn test_anti_unify() {
+        let outputs = ["Dear Ann, your order 12 shipped", "Dear Bob, your order 7 shipped"].into_iter().galloc_scollect();
+        let names: Value = ["Ann", "Bob"].into_iter().galloc_scollect().into();
+        let numbers: Value = ["12", "7"].into_iter().galloc_scollect().into();
+        assert_eq!(anti_unify(outputs, 4), vec![Part::Const("Dear "), Part::Hole(names), Part::Const(", your order "), Part::Hole(numbers), Part::Const(" shipped")]);
+
+        let outputs = ["John Smith", "Jane Doe"].into_iter().galloc_scollect();
+        assert!(anti_unify(outputs, 4).is_empty());
+        assert!(anti_unify(&outputs[..1], 4).is_empty());
+    }
+}
@@ -0,0 +1,108 @@
+use std::pin::pin;
+
+use itertools::Itertools;
+use simple_rc_async::task;
+
+use crate::{debg, expr::{cfg::{NonTerminal, ProdRule}, ops::{mask, Op2Enum}, Expr}, forward::executor::Executor, galloc::{AllocForAny, AllocForExactSizeIter}, utils::select_ret, value::{ConstValue, Value}};
+
+use super::{str::HandleRcVec, Deducer, Problem};
+
+/// Subproblems at least this costly are not deduced through inverse semantics.
+const INVERSE_COST_LIMIT: usize = 5;
+
+/// Deduction for bit-vectors, through the inverse semantics of operators applied to a constant of the grammar.
+///
+/// For a rule `(op A B)` where `A` may be a constant `c`, the output `v` determines the value of `B`: `v - c` for `bvadd`, `v ^ c` for `bvxor`, and `v` itself for `bvand` if `v` has no bit outside of `c`.
+/// The same holds with the constant in `B`, as these operators are commutative.
+pub struct BvDeducer {
+    pub nt: usize,
+    /// The `bvadd`, `bvxor` and `bvand` rules of the non-terminal
+    pub rules: Vec<(&'static Op2Enum, usize, usize)>,
+}
+
+impl std::fmt::Debug for BvDeducer {
+    /// Formats the deducer like a derived `Debug`, with the operators of the rules written by `Display`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BvDeducer")
+            .field("nt", &self.nt)
+            .field("rules", &self.rules.iter().map(|(op, n1, n2)| (op.to_string(), n1, n2)).collect_vec())
+            .finish()
+    }
+}
+
+/// Returns the value of the other argument of `op` producing `output` when one argument is the constant `c`, if any.
+pub fn inverse(op: &Op2Enum, c: u64, output: Value) -> Option<Value> {
+    let Value::BitVector(w, v) = output else { return None };
+    let result = match op {
+        Op2Enum::BvAdd(_) => v.iter().map(|x| x.wrapping_sub(c) & mask(w)).galloc_scollect(),
+        Op2Enum::BvXor(_) => v.iter().map(|x| x ^ c).galloc_scollect(),
+        Op2Enum::BvAnd(_) if v.iter().all(|x| x & !c == 0) => v,
+        _ => return None,
+    };
+    Some(Value::BitVector(w, result))
+}
+
+/// Returns the bit-vector constants of width `w` produced by the non-terminal.
+fn constants(nt: &NonTerminal, w: usize) -> Vec<u64> {
+    nt.rules.iter().filter_map(|r| match r {
+        ProdRule::Const(ConstValue::BitVector(cw, c)) if *cw == w => Some(*c),
+        _ => None,
+    }).unique().collect()
+}
+
+impl BvDeducer {
+    /// Creates the deducer from the invertible rules of the non-terminal `nt`.
+    pub fn new(nt: &NonTerminal, index: usize) -> Self {
+        let rules = ["bvadd", "bvxor", "bvand"].into_iter().filter_map(|name| match nt.get_op2(name) {
+            Some(ProdRule::Op2(op, n1, n2)) => Some((op, n1, n2)),
+            _ => None,
+        }).collect();
+        Self { nt: index, rules }
+    }
+}
+
+impl Deducer for BvDeducer {
+    /// Races the enumeration against the subproblems given by inverse semantics, for every constant of an argument of every invertible rule.
+    async fn deduce(&'static self, exec: &'static Executor, problem: Problem) -> &'static Expr {
+        debg!("Deducing subproblem: {} {:?}", exec.cfg[self.nt].name, problem.value);
+        let task = pin!(exec.data[self.nt].all_eq.acquire(problem.value));
+        let Value::BitVector(w, _) = problem.value else { return task.await };
+        if self.rules.is_empty() || problem.used_cost >= INVERSE_COST_LIMIT { return task.await; }
+
+        let futures = HandleRcVec::new();
+        for &(op, n1, n2) in self.rules.iter() {
+            for (const_nt, nt, const_first) in [(n1, n2, true), (n2, n1, false)] {
+                for c in constants(&exec.cfg[const_nt], w) {
+                    let Some(v) = inverse(op, c, problem.value) else { continue };
+                    if nt == self.nt && v == problem.value { continue; }
+                    let mut prob = problem;
+                    futures.extend_iter(std::iter::once(task::spawn(async move {
+                        exec.waiting_tasks().inc_cost(&mut prob, 1).await;
+                        debg!("BvDeducer: {} {:?} -> {:?}", op, problem.value, v);
+                        let e = exec.solve_task(prob.with_nt(nt, v)).await;
+                        let c = Expr::Const(ConstValue::BitVector(w, c)).galloc();
+                        if const_first { Expr::Op2(op, c, e).galloc() } else { Expr::Op2(op, e, c).galloc() }
+                    })));
+                }
+            }
+        }
+        if futures.len() == 0 { return task.await; }
+        select_ret(task, futures).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr::ops::{BvAdd, BvAnd, BvXor, Op2Enum}, value::Value};
+
+    use super::inverse;
+
+    #[test]
+    fn test_inverse() {
+        let output = Value::BitVector(8, &[0x01, 0x10]);
+        assert_eq!(inverse(&Op2Enum::from(BvAdd::default()), 0x02, output), Some(Value::BitVector(8, &[0xff, 0x0e])));
+        assert_eq!(inverse(&Op2Enum::from(BvXor::default()), 0x11, output), Some(Value::BitVector(8, &[0x10, 0x01])));
+        assert_eq!(inverse(&Op2Enum::from(BvAnd::default()), 0x11, output), Some(output));
+        assert_eq!(inverse(&Op2Enum::from(BvAnd::default()), 0x0f, output), None);
+    }
+}
@@ -29,7 +29,7 @@ impl Deducer for IntDeducer {
         }
         let len_task = task::spawn(async move {
             let a = exec.data[self.len].len().unwrap();
-            let v = a.listen_for_each(v.iter().map(|a| *a as usize).collect(), |result| Some(result)).await;
+            let v = a.listen_for_each(v.iter().map(|a| *a as usize).collect(), Some).await;
             debg!("IntDeducer: len task result: {:?}", v);
             let result = exec.data[self.len].all_eq.get(v);
             expr!(Len {result}).galloc()
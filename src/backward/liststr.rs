@@ -4,7 +4,9 @@ use std::{collections::{HashMap, HashSet}, pin::pin};
 
 use simple_rc_async::task::{self, JoinHandle};
 
-use crate::{backward::str::HandleRcVec, closure, debg, expr::{cfg::Cfg, context::Context, ops::{self, Op1Enum}, Expr}, forward::executor::Executor, galloc::{self, AllocForAny, AllocForExactSizeIter}, never, solutions::new_thread_with_limit, utils::{select_ret, select_ret3, select_ret4}, value::Value};
+use itertools::Itertools;
+
+use crate::{backward::str::HandleRcVec, closure, debg, expr, expr::{cfg::Cfg, context::Context, ops::{self, Op1Enum, Op2Enum}, Expr}, forward::executor::Executor, galloc::{self, AllocForAny, AllocForExactSizeIter, AllocForIter, AllocForStr}, never, provenance::{self, Provenance}, solutions::{new_thread_in_region, new_thread_with_limit}, utils::{select_ret, select_ret3, select_ret4, select_ret5}, value::Value};
 
 use super::{Deducer, Problem};
 
@@ -16,6 +18,8 @@ pub struct ListDeducer {
     pub nt: usize,
     pub map: Option<Cfg>,
     pub filter: Option<Cfg>,
+    /// Deduction of `list.zip_with` producing this non-terminal, if the grammar allows it.
+    pub zip: Option<ZipDeducer>,
 }
 
 impl Deducer for ListDeducer {
@@ -42,7 +46,15 @@ impl Deducer for ListDeducer {
                 None::<&'static Expr>
         }});
 
-        select_ret4(pin!(map_event), pin!(filter_event), pin!(task), pin!(futures)).await
+        let zip_futures = futures.clone();
+        let zip_event = async move {
+            match &self.zip {
+                Some(zip) => zip.listen(exec, prob, zip_futures).await,
+                None => never!(&'static Expr),
+            }
+        };
+
+        select_ret5(pin!(map_event), pin!(filter_event), pin!(zip_event), pin!(task), pin!(futures)).await
     }
 }
 
@@ -57,15 +69,17 @@ impl ListDeducer {
         assert!(p.iter().zip(l.iter()).all(|(a, b)| a.len() == b.len()));
 
 
-        let p = prob.value.flatten_leak();
-        let l = list.flatten_leak();
+        // The flattened lists are only read by the thread solving the inner function, which pops their region when done
+        let region = galloc::Region::push();
+        let p = prob.value.flatten_in(&region);
+        let l = list.flatten_in(&region);
         Some(task::spawn(async move {
 
             let mut cfg = self.map.as_ref().unwrap().clone();
             let ctx = Context::new(p.len(), vec![l.into()], vec![], p.into());
             cfg.config.size_limit = 10;
             cfg.config.time_limit = 1000;
-            let handle = new_thread_with_limit(cfg, ctx);
+            let handle = new_thread_in_region(cfg, ctx, region);
             debg!("ListDeducer::map {:?} {:?} new thread {}", prob.value, list, handle.id());
             let inner = exec.bridge.wait(handle).await;
             let mut result = exec.data[prob.nt].all_eq.get(list);
@@ -90,8 +104,8 @@ impl ListDeducer {
                 }
             }
         }
-        let outputs = io.iter().map(|(_, o)| *o).galloc_scollect();
-        let inputs = io.into_iter().map(|(i, _)| i).galloc_scollect();
+        let outputs = io.values().copied().galloc_scollect();
+        let inputs = io.into_keys().galloc_scollect();
 
         Some(task::spawn(async move {
             let mut cfg = self.filter.as_ref().unwrap().clone();
@@ -107,6 +121,192 @@ impl ListDeducer {
     }
 }
 
+#[derive(Debug)]
+/// Deduction of `list.zip_with` combining two lists of strings into the output list.
+///
+/// Holds the non-terminals of both lists, and the grammar of the combining function (variable `0` is the element of the first list, `1` the element of the second).
+pub struct ZipDeducer {
+    pub left_nt: usize,
+    pub right_nt: usize,
+    pub cfg: Cfg,
+}
+
+impl ZipDeducer {
+    /// Listens for the lists of either non-terminal whose elements line up with those of the output, deducing a zip of each with the lists of the other non-terminal found so far.
+    pub async fn listen(&'static self, exec: &'static Executor, prob: Problem, futures: HandleRcVec<&'static Expr>) -> &'static Expr {
+        let lengths = prob.value.length_inside().unwrap();
+        let on_list = |is_left: bool| closure! { clone futures, clone lengths; move |list: Value| {
+            let other_nt = if is_left { self.right_nt } else { self.left_nt };
+            for &other in exec.data[other_nt].len().unwrap().found(&lengths) {
+                if self.left_nt == self.right_nt || is_left {
+                    futures.extend_iter(self.zip(exec, prob, list, other).into_iter());
+                }
+                if self.left_nt == self.right_nt || !is_left {
+                    futures.extend_iter(self.zip(exec, prob, other, list).into_iter());
+                }
+            }
+            None::<&'static Expr>
+        }};
+        let left = exec.data[self.left_nt].len().unwrap().listen_for_each(lengths.clone(), on_list(true));
+        if self.left_nt == self.right_nt {
+            return left.await;
+        }
+        let right = exec.data[self.right_nt].len().unwrap().listen_for_each(lengths.clone(), on_list(false));
+        select_ret(pin!(left), pin!(right)).await
+    }
+
+    #[inline]
+    /// Deduce a zip of `left` and `right`: the pairs of elements at each position, with the output element at that position, give examples of the combining function, synthesized in a separate thread.
+    pub fn zip(&'static self, exec: &'static Executor, prob: Problem, left: Value, right: Value) -> Option<JoinHandle<&'static Expr>> {
+        if prob.used_cost >= 6 || exec.size() > 7 { return None; }
+        if left == right || left == prob.value || right == prob.value { return None; }
+        let p = prob.value.to_liststr();
+        if p.iter().all(|x| x.len() < 2) { return None; }
+        let (l, r) = (left.to_liststr(), right.to_liststr());
+
+        let mut io = HashMap::<(&'static str, &'static str), &'static str>::new();
+        for ((p, l), r) in p.iter().zip(l.iter()).zip(r.iter()) {
+            for ((out, a), b) in p.iter().zip(l.iter()).zip(r.iter()) {
+                if *io.entry((a, b)).or_insert(out) != *out { return None; }
+            }
+        }
+        let (inputs, outputs): (Vec<_>, Vec<_>) = io.into_iter().unzip();
+        let lefts = inputs.iter().map(|x| x.0).galloc_scollect();
+        let rights = inputs.iter().map(|x| x.1).galloc_scollect();
+        let outputs = outputs.into_iter().galloc_scollect();
+
+        Some(task::spawn(async move {
+            let mut cfg = self.cfg.clone();
+            let ctx = Context::new(outputs.len(), vec![lefts.into(), rights.into()], vec![], outputs.into());
+            cfg.config.size_limit = 10;
+            cfg.config.time_limit = 1000;
+            let handle = new_thread_with_limit(cfg, ctx);
+            debg!("ZipDeducer::zip {:?} {:?} {:?} new thread {}", prob.value, left, right, handle.id());
+            let inner = exec.bridge.wait(handle).await;
+            let left = exec.data[self.left_nt].all_eq.get(left);
+            let right = exec.data[self.right_nt].all_eq.get(right);
+            let e = Expr::Op2(Op2Enum::ZipWith(ops::ZipWith(Some(inner.alloc_local()))).galloc(), left, right).galloc();
+            provenance::record(e, Provenance::Deduced("ListDeducer::zip"))
+        }))
+    }
+}
+
+#[derive(Debug)]
+/// Deduction of `list.foldl` over lists of strings, producing a string.
+///
+/// Holds the non-terminals of the list and of the initial value, and the grammar of the folded function (variable `0` is the accumulator, `1` the element).
+pub struct FoldDeducer {
+    pub list_nt: usize,
+    pub init_nt: usize,
+    pub cfg: Cfg,
+}
+
+impl FoldDeducer {
+    /// Returns the alphanumeric words of each string, used to find lists sharing an element with the output.
+    pub fn words(value: Value) -> Value {
+        value.to_str().iter().map(|s| {
+            s.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).galloc_collect()
+        }).galloc_scollect().into()
+    }
+
+    #[inline]
+    /// Deduce a fold of `list` producing the output of `prob`.
+    ///
+    /// The output is split into the successive values of the accumulator (see [`split_fold`]), which gives examples of the folded function, synthesized in a separate thread, while the initial values are deduced as a subproblem.
+    pub fn fold(&'static self, exec: &'static Executor, prob: Problem, list: Value) -> Option<JoinHandle<&'static Expr>> {
+        if prob.used_cost >= 6 { return None; }
+        let p = prob.value.to_str();
+        let l = list.to_liststr();
+        if l.iter().all(|x| x.len() < 2) { return None; }
+        let splits = [true, false].into_iter().find_map(|wrap| {
+            p.iter().zip(l.iter()).map(|(p, l)| split_fold(p, l, wrap)).collect::<Option<Vec<_>>>()
+        })?;
+
+        let mut io = HashMap::<(&'static str, &'static str), &'static str>::new();
+        for ((init, accs), l) in splits.iter().zip(l.iter()) {
+            for (i, elem) in l.iter().enumerate() {
+                let acc = if i == 0 { *init } else { accs[i - 1] };
+                if *io.entry((acc, elem)).or_insert(accs[i]) != accs[i] { return None; }
+            }
+        }
+        let init = splits.iter().map(|(init, _)| *init).galloc_scollect();
+        let (inputs, outputs): (Vec<_>, Vec<_>) = io.into_iter().unzip();
+        let accs = inputs.iter().map(|x| x.0).galloc_scollect();
+        let elems = inputs.iter().map(|x| x.1).galloc_scollect();
+        let outputs = outputs.into_iter().galloc_scollect();
+
+        Some(task::spawn(async move {
+            let mut cfg = self.cfg.clone();
+            let ctx = Context::new(outputs.len(), vec![accs.into(), elems.into()], vec![], outputs.into());
+            cfg.config.size_limit = 10;
+            cfg.config.time_limit = 1000;
+            let handle = new_thread_with_limit(cfg, ctx);
+            debg!("FoldDeducer::fold {:?} {:?} {:?} {:?} {:?} new thread {}", p, l, accs, elems, outputs, handle.id());
+            let inner = exec.bridge.wait(handle).await;
+            let init = exec.solve_task(prob.with_nt(self.init_nt, init.into())).await;
+            let list = exec.data[self.list_nt].all_eq.get(list);
+            Expr::Op2(Op2Enum::FoldL(ops::FoldL(Some(inner.alloc_local()))).galloc(), list, init).galloc()
+        }))
+    }
+}
+
+/// Splits `output` into the initial value and the successive values of the accumulator of a fold over `list`, all prefixes of `output`.
+///
+/// The elements must occur in order in `output`. With `wrap`, each element is assumed to be wrapped in the same text (e.g. `<li>a</li><li>b</li>`), so the fold starts from `""` and each step appends a wrapped element; this fails unless the text between two elements is the text after the last one followed by the text before the first one.
+/// Otherwise, the fold starts from the text before the first element and each step ends right after its element (e.g. `a, b, c`), except the last one which ends the output.
+pub fn split_fold(output: &'static str, list: &[&'static str], wrap: bool) -> Option<(&'static str, Vec<&'static str>)> {
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    for x in list {
+        if x.is_empty() { return None; }
+        let start = pos + output[pos..].find(x)?;
+        pos = start + x.len();
+        spans.push((start, pos));
+    }
+    let (Some(first), Some(last)) = (spans.first(), spans.last()) else { return Some((output, Vec::new())) };
+    let (before, after) = (&output[..first.0], &output[last.1..]);
+    if wrap {
+        let between = format!("{after}{before}");
+        spans.iter().tuple_windows().all(|(a, b)| output[a.1..b.0] == between).then(|| {
+            ("", spans.iter().map(|s| &output[..s.1 + after.len()]).collect())
+        })
+    } else {
+        let mut accs = spans.iter().map(|s| &output[..s.1]).collect_vec();
+        *accs.last_mut().unwrap() = output;
+        Some((before, accs))
+    }
+}
+
+/// Common delimiters, tried by the witness of `str.split` besides the ones found in the inputs.
+const SPLIT_DELIMITERS: [&str; 5] = [",", ";", "\t", "|", " "];
+
+/// Returns the delimiters by which a string may be split into the output lists: the text found between consecutive elements of the outputs in an input, when it is the same throughout the examples, then the common delimiters.
+///
+/// Nothing is returned if no output has two elements, since any delimiter would do.
+pub fn split_delimiters(inputs: impl Iterator<Item = &'static [&'static str]>, output: &[&[&str]]) -> Vec<&'static str> {
+    if output.iter().all(|l| l.len() < 2) { return Vec::new(); }
+    let mut result = Vec::new();
+    for input in inputs {
+        let mut gaps = HashSet::new();
+        for (&s, l) in input.iter().zip(output.iter()) {
+            let Some(mut pos) = l.first().and_then(|x| s.find(x).map(|i| i + x.len())) else { continue };
+            for x in l.iter().skip(1) {
+                let Some(start) = s[pos..].find(x).map(|i| pos + i) else { break };
+                gaps.insert(&s[pos..start]);
+                pos = start + x.len();
+            }
+        }
+        if gaps.len() == 1 {
+            let gap = gaps.into_iter().next().unwrap();
+            if !gap.is_empty() && !result.contains(&gap) { result.push(gap); }
+        }
+    }
+    for d in SPLIT_DELIMITERS {
+        if !result.contains(&d) { result.push(d); }
+    }
+    result
+}
+
 fn subseq_test(sublist: Value, list: Value) -> bool {
     let Value::ListStr(sublist) = sublist else { return false; };
     let Value::ListStr(list) = list else { return false; };
@@ -127,4 +327,43 @@ fn subseq_test(sublist: Value, list: Value) -> bool {
     }
     
     true
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use crate::{galloc::AllocForExactSizeIter, parser::problem::PBEProblem, value::Value};
+
+    use super::{split_delimiters, split_fold};
+
+    #[test]
+    fn test_split_fold() {
+        assert_eq!(split_fold("<li>a</li><li>b</li>", &["a", "b"], true), Some(("", vec!["<li>a</li>", "<li>a</li><li>b</li>"])));
+        assert_eq!(split_fold("a, b, c", &["a", "b", "c"], true), None);
+        assert_eq!(split_fold("a, b, c.", &["a", "b", "c"], false), Some(("", vec!["a", "a, b", "a, b, c."])));
+        assert_eq!(split_fold("b a", &["a", "b"], false), None);
+    }
+
+    #[test]
+    fn test_split_delimiters() {
+        let inputs = ["Smith :: John :: 42", "Doe :: Jane"].into_iter().galloc_scollect();
+        let output: &[&[&str]] = &[&["Smith", "John", "42"], &["Doe", "Jane"]];
+        assert_eq!(split_delimiters(std::iter::once(inputs), output), vec![" :: ", ",", ";", "\t", "|", " "]);
+        let output: &[&[&str]] = &[&["Smith"], &["Doe"]];
+        assert!(split_delimiters(std::iter::once(inputs), output).is_empty());
+    }
+
+    #[test]
+    fn test_list_examples() {
+        let s = r#"
+            (set-logic SLIA)
+            (synth-fun f ((row String)) (List String) ((Start (List String) ((str.split ntString ntString))) (ntString String (row ";"))))
+            (constraint (= (f "Smith;John;42") (list "Smith" "John" "42")))
+            (constraint (= (f "Doe") (list "Doe")))
+            (check-synth)
+        "#;
+        let problem = PBEProblem::parse(s).unwrap();
+        let Value::ListStr(output) = problem.examples.output else { panic!("expected a list output") };
+        // Duplicate examples are removed through a set, so the order of the examples is not kept.
+        assert_eq!(output.len(), 2);
+        assert!(output.contains(&&["Smith", "John", "42"][..]) && output.contains(&&["Doe"][..]));
+    }
+}
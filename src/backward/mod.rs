@@ -1,12 +1,12 @@
-use std::{cmp::{max, min}, future::Future, usize};
+use std::{cmp::{max, min}, future::Future, pin::pin};
 
-use crate::{debg, expr::{cfg::{Cfg, NonTerminal, ProdRule}, context::Context, Expr}, forward::executor::Executor, info, parser::problem, utils::select_all, value::{Type, Value}};
+use crate::{debg, expr::{cfg::{Cfg, NonTerminal, ProdRule}, context::Context, Expr}, forward::executor::Executor, info, parser::problem, utils::{select_all, select_ret}, value::{Type, Value}};
 
 
 use futures::{future::Either, select, FutureExt};
 use itertools::Itertools;
 
-use self::{liststr::ListDeducer, simple::SimpleDeducer, str::StrDeducer, int::IntDeducer};
+use self::{liststr::{FoldDeducer, ListDeducer, ZipDeducer}, simple::SimpleDeducer, str::StrDeducer, int::IntDeducer, tuple::TupleDeducer, bv::BvDeducer};
 use derive_more::DebugCustom;
 /// Deduction for string
 pub mod str;
@@ -19,6 +19,18 @@ pub mod int;
 /// Deduction for list of strings
 pub mod liststr;
 
+/// Anti-unification of example outputs
+pub mod antiunify;
+
+/// Deduction for pairs of strings
+pub mod tuple;
+
+/// Deduction for bit-vectors
+pub mod bv;
+
+/// Deduction through the inverse semantics of operators
+pub mod witness;
+
 use derive_more::Constructor;
 #[derive(Constructor, Clone, Debug, Copy)]
 /// A struct represents a synthesis problem within the backward deduction process of the string synthesis algorithm. 
@@ -84,6 +96,10 @@ pub enum DeducerEnum {
     List(ListDeducer),
     #[debug(fmt = "{:?}", _0)]
     Int(IntDeducer),
+    #[debug(fmt = "{:?}", _0)]
+    Tuple(TupleDeducer),
+    #[debug(fmt = "{:?}", _0)]
+    Bv(BvDeducer),
 }
 
 impl DeducerEnum {
@@ -91,8 +107,15 @@ impl DeducerEnum {
     /// 
     /// 
     /// Selects a deduction approach by first checking whether deduction is disabled in the configuration and then matching on the non-terminal's type. 
+    /// Single rules of the string strategy (joining, formatters, conditional concatenation) may also be disabled by the configuration; splits, joins and concatenations around a delimiter are deduced by the witness functions of `str.++`, `str.join` and `str.split`.
     /// For string types, it initializes a strategy that fine-tunes parameters such as splitting operations, conditional (ite) concatenation, and join operations based on specific production rules; it also sets a decay rate and appends formatters retrieved from the grammar. 
+    /// The start non-terminal additionally gets a sketch anti-unified from the outputs (constants of at least `#str.anti_unify` characters, 4 by default, 0 to disable).
+    /// Padding operators let outputs of a common width be deduced as a padding of narrower strings.
+    /// A `list.foldl` rule folding a list of strings gets a fold deduction, whose function is searched in the grammar nested at this non-terminal.
+    /// Pairs of strings built by `tuple.make` are decomposed into their components.
+    /// Bit-vectors are deduced through the inverse of `bvadd`, `bvxor` and `bvand` applied to a constant.
     /// For list-of-string types, it configures an alternative strategy that conditionally leverages a modified grammar when a list mapping operation is present. 
+    /// A `list.zip_with` rule over two lists of strings gets a zip deduction, whose combining function is searched in the grammar nested at the first string non-terminal.
     /// In all other cases, it falls back to a simple deduction strategy.
     pub fn from_nt(cfg: &Cfg, ctx: &Context, nt: usize) -> Self {
         if cfg.config.no_deduction {
@@ -101,6 +124,7 @@ impl DeducerEnum {
         match cfg[nt].ty {
             crate::value::Type::Str => {
                 let mut result = StrDeducer::new(nt);
+                result.no_ite_concat = cfg.config.no_ite_concat;
                 if let Some(ProdRule::Op2(_, n1, n2)) = cfg[nt].get_op2("str.++") {
                     if n1 == n2 && n1 == nt {
                         result.split_once = (ctx.len() / 3, 5);
@@ -112,20 +136,53 @@ impl DeducerEnum {
                     }
                 }
                 if let Some(ProdRule::Op2(_, n1, n2)) = cfg[nt].get_op2("str.join") {
-                    if n2 == nt && (cfg[n1].get_op1("list.map").is_some() || cfg[n1].get_op1("list.filter").is_some()) {
+                    if n2 == nt && !cfg.config.no_join && (cfg[n1].get_op1("list.map").is_some() || cfg[n1].get_op1("list.filter").is_some()) {
                         result.join = (2, n1)
                     } 
                 }
+                if let Some(ProdRule::Op2(_, n1, n2)) = cfg[nt].get_op2("list.foldl") {
+                    if cfg[n1].ty == Type::ListStr && cfg[n2].ty == Type::Str {
+                        let cfg2 = cfg.nested(nt, &[Type::Str, Type::Str]);
+                        info!("Fold Cfg {:?}", cfg2);
+                        result.fold = Some(FoldDeducer { list_nt: n1, init_nt: n2, cfg: cfg2 });
+                    }
+                }
                 if let Some(ProdRule::Op2(_, n1, n2)) = cfg[nt].get_op2("list.at") {
                     result.index = (n1 , n2)
                 }
+                if let Some(ProdRule::Op2(op, n1, n2)) = cfg[nt].get_op2("str.initials") {
+                    if cfg[n1].ty == Type::Str && cfg[n2].ty == Type::Str { result.initials = Some((op, n1, n2)); }
+                }
+                if let Some(ProdRule::Op1(op, n1)) = cfg[nt].get_op1("str.trim") {
+                    if cfg[n1].ty == Type::Str { result.trim = Some((op, n1)); }
+                }
+                if let Some(ProdRule::Op3(op, n1, n2, n3)) = cfg[nt].get_op3("str.replace_all") {
+                    if [n1, n2, n3].iter().all(|&n| cfg[n].ty == Type::Str) { result.replace_all = Some((op, n1, n2, n3)); }
+                }
+                for op in ["str.pad_left", "str.pad_right"] {
+                    if let Some(ProdRule::Op3(op, n1, n2, n3)) = cfg[nt].get_op3(op) {
+                        result.pad.push((op, n1, n2, n3));
+                    }
+                }
                 result.decay_rate = cfg[nt].config.get_usize("str.decay_rate").unwrap_or(900);
-                result.formatter.append(&mut cfg[nt].get_all_formatter());
+                if nt == 0 && ctx.output.ty() == Type::Str && cfg[nt].get_op2("str.++").is_some() {
+                    result.sketch = antiunify::anti_unify(ctx.output.to_str(), cfg[nt].config.get_usize("str.anti_unify").unwrap_or(4));
+                }
+                if !cfg.config.no_format_deduce {
+                    result.formatter.append(&mut cfg[nt].get_all_formatter());
+                }
                 info!("Deduction: {result:?}");
                 Self::Str(result)
             }
             crate::value::Type::ListStr => {
-                let mut result = ListDeducer { nt, map: None, filter: None};
+                let mut result = ListDeducer { nt, map: None, filter: None, zip: None };
+                if let Some(ProdRule::Op2(_, n1, n2)) = cfg[nt].get_op2("list.zip_with") {
+                    if let (Type::ListStr, Type::ListStr, Some(str_nt)) = (cfg[n1].ty, cfg[n2].ty, cfg.find_by_type(Type::Str)) {
+                        let cfg2 = cfg.nested(str_nt, &[Type::Str, Type::Str]);
+                        info!("Zip Cfg {:?}", cfg2);
+                        result.zip = Some(ZipDeducer { left_nt: n1, right_nt: n2, cfg: cfg2 });
+                    }
+                }
                 if cfg[nt].get_op1("list.map").is_some() {
                     let mut cfg2 = cfg.clone();
                     for nt in cfg2.iter_mut() {
@@ -153,6 +210,14 @@ impl DeducerEnum {
                 }
                 Self::Int(result)
             }
+            crate::value::Type::Tuple => {
+                let make = match cfg[nt].get_op2("tuple.make") {
+                    Some(ProdRule::Op2(op, n1, n2)) => Some((op, n1, n2)),
+                    _ => None,
+                };
+                Self::Tuple(TupleDeducer { nt, make })
+            }
+            crate::value::Type::BitVector(_) => Self::Bv(BvDeducer::new(&cfg[nt], nt)),
             _ => Self::Simple(SimpleDeducer{ nt }),
         }
     }
@@ -163,17 +228,24 @@ impl Deducer for DeducerEnum {
     /// 
     /// 
     /// This method first checks if the solution for the subproblem is pending in the executor's cache. 
-    /// If it is, the method awaits and returns the pending result; otherwise, it delegates the deduction task to the underlying strategy implementation corresponding to the subproblem's type. 
+    /// If it is, the method awaits and returns the pending result; otherwise, it delegates the deduction task to the underlying strategy implementation corresponding to the subproblem's type, racing it against the witness functions of the operators of the non-terminal. 
     /// After obtaining the result, it logs the solved subproblem and records the expression back into the executor's cache for future reuse.
     async fn deduce(&'static self, exec: &'static Executor, problem: Problem) -> &'static Expr {
         let is_pending = exec.data[problem.nt].all_eq.is_pending(problem.value);
         if is_pending { return exec.data[problem.nt].all_eq.acquire(problem.value).await; }
 
-        let result = match self {
-            DeducerEnum::Str(a) => a.deduce(exec, problem).await,
-            DeducerEnum::Simple(a) => a.deduce(exec, problem).await,
-            DeducerEnum::List(a) => a.deduce(exec, problem).await,
-            DeducerEnum::Int(a) => a.deduce(exec, problem).await,
+        let deduced = async move {
+            match self {
+                DeducerEnum::Str(a) => a.deduce(exec, problem).await,
+                DeducerEnum::Simple(a) => a.deduce(exec, problem).await,
+                DeducerEnum::List(a) => a.deduce(exec, problem).await,
+                DeducerEnum::Int(a) => a.deduce(exec, problem).await,
+                DeducerEnum::Tuple(a) => a.deduce(exec, problem).await,
+                DeducerEnum::Bv(a) => a.deduce(exec, problem).await,
+            }
+        };
+        let result = if exec.cfg.config.no_deduction { deduced.await } else {
+            select_ret(pin!(deduced), pin!(witness::deduce(exec, problem))).await
         };
         debg!("Subproblem {:?} solved", problem.value);
         exec.data[problem.nt].add_ev(result, problem.value);
@@ -209,3 +281,28 @@ impl Deducer for DeducerEnum {
 //     }
 // }
 
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{expr::{cfg::Cfg, context::Context}, galloc::AllocForExactSizeIter, parser::problem::PBEProblem, value::Value};
+
+    use super::DeducerEnum;
+
+    #[test]
+    fn test_ablation_flags() {
+        let s = fs::read_to_string("test/test.sl").unwrap();
+        let problem = PBEProblem::parse(s.as_str()).unwrap();
+        let mut cfg = Cfg::from_synthfun(problem.synthfun());
+        let output: Value = ["a", "b"].into_iter().galloc_scollect().into();
+        let ctx = Context::new(2, vec![output], vec![], output);
+        let nt = cfg.iter().position(|nt| nt.get_op2("str.join").is_some()).unwrap();
+        let DeducerEnum::Str(d) = DeducerEnum::from_nt(&cfg, &ctx, nt) else { panic!() };
+        assert!(!d.formatter.is_empty());
+
+        cfg.config.no_format_deduce = true;
+        let DeducerEnum::Str(d) = DeducerEnum::from_nt(&cfg, &ctx, nt) else { panic!() };
+        assert!(d.formatter.is_empty() && !d.no_ite_concat);
+    }
+}
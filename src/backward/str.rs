@@ -7,11 +7,12 @@ use futures_core::Future;
 use itertools::Itertools;
 use simple_rc_async::task::{self, JoinHandle};
 
-use crate::{async_closure, closure, debg, expr::{ context::Context, ops::Op1Enum, Expr}, forward::executor::Executor, info, utils::select_ret5, value::Type, DEBUG};
-use crate::{galloc::{self, AllocForAny, AllocForExactSizeIter, AllocForIter}, never, utils::{pending_if, select_all, select_ret, select_ret3, select_ret4, UnsafeCellExt}, value::Value};
+use crate::{async_closure, closure, debg, expr::{ context::Context, ops::{Op1Enum, Op2Enum, Op3Enum}, Expr}, forward::executor::Executor, info, value::{ConstValue, Type}, DEBUG};
+use crate::{galloc::{self, AllocForAny, AllocForExactSizeIter, AllocForIter, AllocForStr}, never, utils::{pending_if, select_all, select_ret, select_ret3, select_ret4, UnsafeCellExt}, value::Value};
 
 use crate::expr;
-use super::{Deducer, Problem};
+use crate::provenance::{self, Provenance};
+use super::{antiunify::Part, liststr::FoldDeducer, Deducer, Problem};
 
 /// A container for managing a collection of asynchronous task join handles. 
 /// 
@@ -78,7 +79,6 @@ impl<T: Unpin> HandleRcVec<T> {
     }
 }
 
-#[derive(Debug)]
 /// A struct hold configuration for string deduction tasks.
 pub struct StrDeducer {
     /// The non-terminal identifier of this deducer.
@@ -94,12 +94,48 @@ pub struct StrDeducer {
     pub formatter: Vec<(Op1Enum, usize)>,
     /// No longer used
     pub decay_rate: usize,
+    /// Template anti-unified from the outputs of the examples, whose holes are deduced instead of the full output (empty if none).
+    pub sketch: Vec<Part>,
+    /// Deduction of `list.foldl` producing this non-terminal, if the grammar allows it.
+    pub fold: Option<FoldDeducer>,
+    /// Padding operators (`str.pad_left` or `str.pad_right`) with the non-terminals of the padded string, the width and the padding character.
+    pub pad: Vec<(&'static Op3Enum, usize, usize, usize)>,
+    /// The `str.trim` operator with the non-terminal of the trimmed string, if the grammar allows it.
+    pub trim: Option<(&'static Op1Enum, usize)>,
+    /// The `str.initials` operator with the non-terminals of the string and of the separator, if the grammar allows it.
+    pub initials: Option<(&'static Op2Enum, usize, usize)>,
+    /// The `str.replace_all` operator with the non-terminals of the string, the pattern and the replacement, if the grammar allows it.
+    pub replace_all: Option<(&'static Op3Enum, usize, usize, usize)>,
+    /// Whether the conditional concatenation of a prefix is disabled (`--no-ite-concat`); its conditions are still used by the other rules.
+    pub no_ite_concat: bool,
 }
 
 impl StrDeducer {
     /// Creates a new instance of the associated type with a specified non-terminal identifier, using the default setting. 
     pub fn new(nt: usize) -> Self {
-        Self { nt, split_once: (usize::MAX, 0), join: (usize::MAX, 0), ite_concat: (usize::MAX, usize::MAX), index: (usize::MAX, usize::MAX), formatter: Vec::new(), decay_rate: usize::MAX }
+        Self { nt, split_once: (usize::MAX, 0), join: (usize::MAX, 0), ite_concat: (usize::MAX, usize::MAX), index: (usize::MAX, usize::MAX), formatter: Vec::new(), decay_rate: usize::MAX, sketch: Vec::new(), fold: None, pad: Vec::new(), trim: None, initials: None, replace_all: None, no_ite_concat: false }
+    }
+}
+
+impl std::fmt::Debug for StrDeducer {
+    /// Formats the deducer like a derived `Debug`, with the binary and ternary operators of its rules written by `Display`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StrDeducer")
+            .field("nt", &self.nt)
+            .field("split_once", &self.split_once)
+            .field("join", &self.join)
+            .field("ite_concat", &self.ite_concat)
+            .field("index", &self.index)
+            .field("formatter", &self.formatter)
+            .field("decay_rate", &self.decay_rate)
+            .field("sketch", &self.sketch)
+            .field("fold", &self.fold)
+            .field("pad", &self.pad.iter().map(|(op, n, w, c)| (op.to_string(), n, w, c)).collect_vec())
+            .field("trim", &self.trim)
+            .field("initials", &self.initials.map(|(op, n, s)| (op.to_string(), n, s)))
+            .field("replace_all", &self.replace_all.map(|(op, n, p, r)| (op.to_string(), n, p, r)))
+            .field("no_ite_concat", &self.no_ite_concat)
+            .finish()
     }
 }
 
@@ -112,20 +148,14 @@ impl Deducer for StrDeducer {
         let mut eq = pin!(exec.data[self.nt].all_eq.acquire(prob.value));
         debg!("Deducing subproblem: {} {:?}", self.nt, prob.value);
         if let Poll::Ready(r) = futures::poll!(&mut eq) { return r; }
+        // Each deduction step adds at least one operator to the result
+        if prob.used_cost >= exec.cfg.config.max_size { return eq.await; }
 
         // let mut delimiterset = HashSet::<Vec<&'static str>>::new();
         let futures = HandleRcVec::new();
 
-        let substr_event = closure! { clone futures, clone prob; async move {
-            if exec.data[self.nt].substr().is_some() {
-                exec.data[self.nt].substr().unwrap().listen_for_each(prob.value, closure! { clone futures, clone prob; move |delimiter: Value| {
-                    futures.extend_iter(this.split1(exec, prob, delimiter).into_iter());
-                    futures.extend_iter(this.join(exec, prob, delimiter).into_iter());
-                    None::<&'static Expr>
-                }}).await
-            } else { never!(&'static Expr) }
-        }};
-        
+        futures.extend_iter(self.replace_all(exec, prob).into_iter());
+
         let prefix_event = closure! { clone futures, clone prob; async move {
             if exec.data[self.nt].prefix().is_some() {
                 exec.data[self.nt].prefix().unwrap().listen_for_each(prob.value, move |prefix: Value| {
@@ -144,6 +174,18 @@ impl Deducer for StrDeducer {
             } else { never!(&'static Expr) }
         }};
 
+        let fold_event = closure! { clone futures, clone prob; async move {
+            match &self.fold {
+                Some(fold) if prob.used_cost < 3 && exec.data[fold.list_nt].contains.is_some() => {
+                    exec.data[fold.list_nt].contains.as_ref().unwrap().listen_for_each(FoldDeducer::words(prob.value), move |list: Value| {
+                        futures.extend_iter(fold.fold(exec, prob, list).into_iter());
+                        None::<&'static Expr>
+                    }).await
+                }
+                _ => never!(&'static Expr),
+            }
+        }};
+
         let join_empty_str_cond = self.join.0 < usize::MAX && prob.used_cost <= 8 &&
             prob.value.to_str().iter().all(|x| x.chars().all(|c| c.is_alphanumeric())) &&
             prob.value.to_str().iter().any(|x| x.len() > 2);
@@ -155,12 +197,21 @@ impl Deducer for StrDeducer {
             } 
             never!(&'static Expr)
         }});
+        // Tried first, as trimmed input fields are otherwise only reached by enumerating the trimming
+        futures.extend_iter(self.trim(exec, prob).into_iter());
+        futures.extend_iter(self.initials(exec, prob).into_iter());
+        if prob.value == exec.ctx.output {
+            futures.extend_iter(self.sketch(exec, prob).into_iter());
+        }
+        for pad in self.pad.iter() {
+            futures.extend_iter(self.pad(exec, prob, pad).into_iter());
+        }
         let iter = self.formatter.iter().map(|x| self.fmt(prob, x, exec));
 
-        let substr_event = pin!(substr_event);
         let prefix_event = pin!(prefix_event);
         let index_event = pin!(index_event);
-        let events = select_ret4(prefix_event, substr_event, map_event, index_event);
+        let fold_event = pin!(fold_event);
+        let events = select_ret4(prefix_event, map_event, index_event, fold_event);
 
         let result = select_ret4(eq, events, futures, pin!(select_all(iter))).await;
         result
@@ -171,36 +222,89 @@ impl Deducer for StrDeducer {
 
 impl StrDeducer {
     
+    /// Deduce the output through the anti-unified sketch: the holes are solved as subproblems and concatenated with the constants of the template. 
+    fn sketch(&'static self, exec: &'static Executor, mut prob: Problem) -> Option<JoinHandle<&'static Expr>> {
+        if self.sketch.is_empty() { return None; }
+        Some(task::spawn(async move {
+            exec.waiting_tasks().inc_cost(&mut prob, 1).await;
+            debg!("StrDeducer::sketch {:?}", self.sketch);
+            let mut result: Option<&'static Expr> = None;
+            for part in self.sketch.iter() {
+                let e = match part {
+                    Part::Const(c) => Expr::Const(ConstValue::Str(c)).galloc(),
+                    Part::Hole(v) => exec.solve_task(prob.with_value(*v)).await,
+                };
+                result = Some(match result {
+                    Some(r) => provenance::record(expr!(Concat {r} {e}).galloc(), Provenance::Deduced("StrDeducer::sketch")),
+                    None => e,
+                });
+            }
+            result.unwrap()
+        }))
+    }
     #[inline]
-    /// Deduce a string splitting by a specified delimiter. 
-     fn split1(&'static self, exec: &'static Executor, mut prob: Problem, delimiter: Value) -> Option<JoinHandle<&'static Expr>> {
-        let delimiter = delimiter.to_str();
+    /// Deduce a padding of the outputs to a common width, instead of concatenating the padding character to itself.
+    fn pad(&'static self, exec: &'static Executor, mut prob: Problem, pad: &'static (&'static Op3Enum, usize, usize, usize)) -> Option<JoinHandle<&'static Expr>> {
+        if prob.used_cost >= 5 { return None; }
+        let &(op, nt, width_nt, char_nt) = pad;
         let v = prob.value.to_str();
-        let contain_count: usize = v.iter().zip(delimiter.iter()).filter(|(x, y)| if !y.is_empty() { x.contains(*y) } else { false }).count();
-        // if !(contain_count >= self.split_once_count(exec) && prob.used_cost < 15) { return None; }
-
-        
+        let (c, width, rest) = strip_padding(v, matches!(op, Op3Enum::PadLeft(_)))?;
         Some(task::spawn(async move {
-            let (a, b, cases) = split_once(v, delimiter);
-            if !cases.is_all_true() || self.ite_concat.1 == usize::MAX { return never!() }
             exec.waiting_tasks().inc_cost(&mut prob, 1).await;
-
-            debg!("StrDeducer::split1 {v:?} {delimiter:?}");
-
-            let left = exec.solve_task(prob.with_value(a)).await;
-            let right = exec.solve_task(prob.with_value(b)).await;
-            
-            let mut result = exec.data[prob.nt].all_eq.get(delimiter.into());
-            if self.ite_concat.1 != usize::MAX {
-                result = self.generate_condition(exec, prob.with_nt(self.ite_concat.1, cases), result).await;
-            }
-            if !a.is_all_empty() {
-                result = expr!(Concat {left} {result}).galloc();
-            }
-            if !b.is_all_empty() {
-                return expr!(Concat {result} {right}).galloc();
-            }
-            result
+            debg!("StrDeducer::pad {v:?} {c:?} {width}");
+            let inner = exec.solve_task(prob.with_nt(nt, rest)).await;
+            let width = exec.solve_task(prob.with_nt(width_nt, ConstValue::Int(width as i64).value(v.len()))).await;
+            let c = exec.solve_task(prob.with_nt(char_nt, ConstValue::Str(c.to_string().galloc_str()).value(v.len()))).await;
+            provenance::record(Expr::Op3(op, inner, width, c).galloc(), Provenance::Deduced("StrDeducer::pad"))
+        }))
+    }
+    #[inline]
+    /// Deduce a trimming of the outputs out of the input fields where they occur surrounded by whitespace: the outputs widened with this whitespace are solved instead, then trimmed.
+    fn trim(&'static self, exec: &'static Executor, mut prob: Problem) -> Option<JoinHandle<&'static Expr>> {
+        let (op, nt) = self.trim?;
+        if prob.used_cost >= 5 { return None; }
+        let v = prob.value.to_str();
+        let widened = widen_whitespace(v, &str_inputs(exec))?;
+        Some(task::spawn(async move {
+            exec.waiting_tasks().inc_cost(&mut prob, 1).await;
+            debg!("StrDeducer::trim {v:?} {widened:?}");
+            let inner = exec.solve_task(prob.with_nt(nt, widened)).await;
+            provenance::record(Expr::Op1(op, inner).galloc(), Provenance::Deduced("StrDeducer::trim"))
+        }))
+    }
+    #[inline]
+    /// Deduce the initials of the words of an input field, when each output is made of the first letters of the words of the field, followed by a common separator.
+    fn initials(&'static self, exec: &'static Executor, mut prob: Problem) -> Option<JoinHandle<&'static Expr>> {
+        let (op, nt, sep_nt) = self.initials?;
+        if prob.used_cost >= 5 { return None; }
+        let v = prob.value.to_str();
+        let inputs = str_inputs(exec);
+        let (c, sep) = detect_initials(v, &inputs)?;
+        let field = inputs[c];
+        Some(task::spawn(async move {
+            exec.waiting_tasks().inc_cost(&mut prob, 1).await;
+            debg!("StrDeducer::initials {v:?} {field:?} {sep:?}");
+            let s = exec.solve_task(prob.with_nt(nt, field.into())).await;
+            let sep = exec.solve_task(prob.with_nt(sep_nt, ConstValue::Str(sep).value(v.len()))).await;
+            provenance::record(Expr::Op2(op, s, sep).galloc(), Provenance::Deduced("StrDeducer::initials"))
+        }))
+    }
+    #[inline]
+    /// Deduce a replacement of every occurrence of a delimiter of an input field by a common string.
+    fn replace_all(&'static self, exec: &'static Executor, mut prob: Problem) -> Option<JoinHandle<&'static Expr>> {
+        let (op, nt, pat_nt, by_nt) = self.replace_all?;
+        if prob.used_cost >= 5 { return None; }
+        let v = prob.value.to_str();
+        let inputs = str_inputs(exec);
+        let (c, pat, by) = detect_replace_all(v, &inputs)?;
+        let field = inputs[c];
+        Some(task::spawn(async move {
+            exec.waiting_tasks().inc_cost(&mut prob, 1).await;
+            debg!("StrDeducer::replace_all {v:?} {field:?} {pat:?} {by:?}");
+            let s = exec.solve_task(prob.with_nt(nt, field.into())).await;
+            let pat = exec.solve_task(prob.with_nt(pat_nt, ConstValue::Str(pat).value(v.len()))).await;
+            let by = exec.solve_task(prob.with_nt(by_nt, ConstValue::Str(by).value(v.len()))).await;
+            provenance::record(Expr::Op3(op, s, pat, by).galloc(), Provenance::Deduced("StrDeducer::replace_all"))
         }))
     }
     #[inline]
@@ -212,14 +316,15 @@ impl StrDeducer {
         let cond = futures::future::select(left, right).await;
         match cond {
             futures::future::Either::Left((c, _)) => 
-                expr!(Ite {c} {result} "").galloc(),
+                provenance::record(expr!(Ite {c} {result} "").galloc(), Provenance::Deduced("StrDeducer::generate_condition")),
             futures::future::Either::Right((c, _)) => 
-                expr!(Ite {c} "" {result}).galloc(),
+                provenance::record(expr!(Ite {c} "" {result}).galloc(), Provenance::Deduced("StrDeducer::generate_condition")),
         }
     }
     #[inline]
     /// Deduce conditional concatenation deduction for string synthesis problems. 
     pub fn ite_concat(&'static self, exec: &'static Executor, mut prob: Problem, prefix: Value) -> Option<JoinHandle<&'static Expr>> {
+        if self.no_ite_concat { return None; }
         let v: &[&str] = prob.value.to_str();
         let prefix: &[&str] = prefix.to_str();
         let start_count: usize = v.iter().zip(prefix.iter()).map(|(x, y)| if x.starts_with(*y) { y.len() } else { 0 }).sum();
@@ -239,7 +344,7 @@ impl StrDeducer {
             let mut result = exec.data[prob.nt].all_eq.get(prefix.into());
             result = self.generate_condition(exec, prob.with_nt(self.ite_concat.1, a), result).await;
             if !b.is_all_empty() {
-                result = expr!(Concat {result} {right}).galloc();
+                result = provenance::record(expr!(Concat {result} {right}).galloc(), Provenance::Deduced("StrDeducer::ite_concat"));
             }
             result
         }))
@@ -259,33 +364,10 @@ impl StrDeducer {
 
             let indices = exec.data[self.index.1].all_eq.acquire(indices.into()).await;
             let mut result = exec.data[self.index.0].all_eq.get(list.into());
-            expr!(At {result} {indices}).galloc()
+            provenance::record(expr!(At {result} {indices}).galloc(), Provenance::Deduced("StrDeducer::index"))
         }))
     }
 
-    #[inline]
-    /// Deduce a string joining operation based on a specified delimiter. 
-    fn join(&'static self, exec: &'static Executor, mut prob: Problem, delimiter: Value) -> Option<JoinHandle<&'static Expr>> {
-        let delimiter = delimiter.to_str();
-        let v = prob.value.to_str();
-        if prob.used_cost >= 5 { return None; }
-        
-        let contain_count: usize = v.iter().zip(delimiter.iter()).map(|(x, y)| x.matches(y).count() + 1).max().unwrap_or(10000);
-        if contain_count < self.join.0 { return None; }
-        
-        
-        Some(task::spawn(async move {
-            debg!("StrDeducer::join {v:?} {delimiter:?} {} {}", prob.used_cost, contain_count);
-            // exec.waiting_tasks().inc_cost(&mut prob, 1).await;
-
-            let a = value_split(v, delimiter);
-
-            let list = exec.solve_task(prob.with_nt(self.join.1, a)).await;
-            
-            let mut delim = exec.data[prob.nt].all_eq.get(delimiter.into());
-            expr!(Join {list} {delim}).galloc()
-        }))
-    }
     #[inline]
     /// Deduce to list of strings using join
     fn join_empty_str(&'static self, exec: &'static Executor, mut prob: Problem) -> Option<JoinHandle<&'static Expr>> {
@@ -296,7 +378,7 @@ impl StrDeducer {
             let v = prob.value.to_str();
             let li = v.iter().map(|x| (0..x.len()).map(|i| &x[i..i+1]).galloc_scollect() ).galloc_scollect();
             let list = exec.solve_task(prob.with_nt(self.join.1, li.into())).await;
-            expr!(Join {list} "").galloc()
+            provenance::record(expr!(Join {list} "").galloc(), Provenance::Deduced("StrDeducer::join_empty_str"))
         }))
     }
     // #[inline]
@@ -314,17 +396,16 @@ impl StrDeducer {
         let v = problem.value.to_str();
         if let Some((op, a, b, cond)) = formatter.0.format_all(v) {
             debg!("StrDeducer::fmt {v:?} {formatter:?}");
-            if !cond.is_all_true() { exec.waiting_tasks().inc_cost(&mut problem, 1).await; }
-            else { exec.waiting_tasks().inc_cost(&mut problem, 1).await; }
+            exec.waiting_tasks().inc_cost(&mut problem, 1).await;
 
             let inner = exec.solve_task(problem.with_nt(formatter.1, a)).await;
             let rest = exec.solve_task(problem.with_nt(self.nt, b)).await;
             
-            let mut result = Expr::Op1(op.clone().galloc(), inner).galloc();
+            let mut result = provenance::record(Expr::Op1(op.clone().galloc(), inner).galloc(), Provenance::Formatter(op.name()));
             if self.ite_concat.1 != usize::MAX {
                 result = exec.generate_condition(problem.with_nt(self.ite_concat.1, cond), result).await;
             }
-            result = expr!(Concat {result} {rest}).galloc();
+            result = provenance::record(expr!(Concat {result} {rest}).galloc(), Provenance::Deduced("StrDeducer::fmt"));
             if DEBUG.get() {
                 assert_eq!(result.eval(&exec.ctx), Value::Str(v), "Expression: {:?} {:?}", result, a);
             }
@@ -333,6 +414,79 @@ impl StrDeducer {
     }
 }
 
+/// Recognizes outputs padded to a common width: all outputs have the same number of characters, and at least two of them (or the only one) start (`left`) or end with a repeated character.
+///
+/// Returns the padding character, the width and the outputs stripped from every leading (resp. trailing) occurrence of the character.
+/// The character is the one padding the most outputs; outputs that are already as wide as the others may be left unpadded.
+pub fn strip_padding(v: &'static [&'static str], left: bool) -> Option<(char, usize, Value)> {
+    let width = v.first()?.chars().count();
+    if v.iter().any(|s| s.chars().count() != width) { return None; }
+    let edge = |s: &str| if left { s.chars().next() } else { s.chars().last() };
+    let strip = |s: &'static str, c: char| if left { s.trim_start_matches(c) } else { s.trim_end_matches(c) };
+    let (c, padded) = v.iter().filter_map(|s| edge(s)).unique()
+        .map(|c| (c, v.iter().filter(|s| edge(s) == Some(c)).count()))
+        .max_by_key(|(_, n)| *n)?;
+    if padded < v.len().min(2) || v.iter().all(|s| strip(s, c).is_empty()) { return None; }
+    Some((c, width, v.iter().map(|s| strip(s, c)).galloc_scollect().into()))
+}
+
+/// Returns the string inputs of the examples.
+fn str_inputs(exec: &Executor) -> Vec<&'static [&'static str]> {
+    exec.ctx.p.iter().filter_map(|x| if let Value::Str(s) = x { Some(*s) } else { None }).collect_vec()
+}
+
+/// Finds an input field whose initials, followed by a common separator, are the outputs; returns the index of the field among `inputs` and the separator.
+///
+/// The separator is read in the example where the field has the most words, between the first two initials.
+pub fn detect_initials(v: &'static [&'static str], inputs: &[&'static [&'static str]]) -> Option<(usize, &'static str)> {
+    if v.iter().all(|o| o.chars().count() < 2) { return None; }
+    inputs.iter().enumerate().find_map(|(c, field)| {
+        let i = (0..v.len()).max_by_key(|&i| field[i].split_whitespace().count())?;
+        let mut words = field[i].split_whitespace().filter_map(|w| w.chars().next());
+        let (first, second) = (words.next()?, words.next()?);
+        let rest = v[i].strip_prefix(first)?;
+        let sep = &rest[..rest.find(second)?];
+        field.iter().zip(v.iter()).all(|(f, o)| crate::expr::ops::initials(f, sep) == *o).then_some((c, sep))
+    })
+}
+
+/// Finds an input field in which every occurrence of a delimiter is replaced by a common string in the outputs; returns the index of the field among `inputs`, the delimiter and the replacement.
+///
+/// Delimiters are the characters of the field other than letters and digits. Some example must contain the delimiter twice, as a single occurrence is already covered by `str.replace`; the replacement is read in the example with the most occurrences.
+pub fn detect_replace_all(v: &'static [&'static str], inputs: &[&'static [&'static str]]) -> Option<(usize, &'static str, &'static str)> {
+    inputs.iter().enumerate().find_map(|(c, field)| {
+        let delimiters = field.iter().flat_map(|f| f.char_indices().filter(|(_, ch)| !ch.is_alphanumeric()).map(|(k, ch)| &f[k..k + ch.len_utf8()])).unique().collect_vec();
+        delimiters.into_iter().find_map(|pat| {
+            let i = (0..v.len()).max_by_key(|&i| field[i].matches(pat).count())?;
+            let count = field[i].matches(pat).count();
+            if count < 2 { return None; }
+            let (first, last) = (field[i].split(pat).next()?, field[i].rsplit(pat).next()?);
+            let fixed = field[i].len() - count * pat.len();
+            if v[i].len() < fixed || (v[i].len() - fixed) % count != 0 || !v[i].starts_with(first) || !v[i].ends_with(last) { return None; }
+            let by = v[i].get(first.len()..first.len() + (v[i].len() - fixed) / count)?;
+            if by == pat { return None; }
+            field.iter().zip(v.iter()).all(|(f, o)| f.replace(pat, by) == *o).then_some((c, pat, by))
+        })
+    })
+}
+
+/// Widens each output with the whitespace around its first occurrence in an input field of the same example.
+///
+/// Returns `None` unless every output is non-empty, has no surrounding whitespace, and occurs in an input field, with whitespace around at least one occurrence.
+pub fn widen_whitespace(v: &'static [&'static str], inputs: &[&'static [&'static str]]) -> Option<Value> {
+    let widened = v.iter().enumerate().map(|(i, s)| {
+        if s.is_empty() || s.trim() != *s { return None; }
+        inputs.iter().find_map(|field| {
+            let field = field[i];
+            let start = field.find(s)?;
+            let end = start + s.len();
+            Some(&field[field[..start].trim_end().len()..field.len() - field[end..].trim_start().len()])
+        })
+    }).collect::<Option<Vec<_>>>()?;
+    if widened.iter().zip(v.iter()).all(|(w, s)| w == s) { return None; }
+    Some(widened.into_iter().galloc_scollect().into())
+}
+
 /// Deduce splits for each string in the input slice once over the corresponding delimiter, resulting in two separate string parts and a boolean indicating successful splits. 
 pub fn split_once(s: &'static [&'static str], delimiter: &'static [&'static str]) -> (Value, Value, Value) {
     assert!(s.len() == delimiter.len());
@@ -385,4 +539,56 @@ pub fn ite_concat_split(s: &'static [&'static str], delimiter: &'static [&'stati
 /// Processes two slices of static string slices by pairing each input string with its associated delimiter, performing a split operation on the string, and then collecting each resultant iterator of substrings into a nested list structure conforming to the Value type.
 pub fn value_split(s: &'static [&'static str], delimiter: &'static [&'static str]) -> Value {
     Value::ListStr(s.iter().zip(delimiter.iter()).map(|(x, y)| x.split(y).galloc_collect()).galloc_collect())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::galloc::AllocForExactSizeIter;
+
+    use super::{detect_initials, detect_replace_all, strip_padding, widen_whitespace};
+
+    #[test]
+    fn test_detect_initials() {
+        let ids = ["a1", "b2", "c3"].into_iter().galloc_scollect();
+        let names = ["John Ronald Tolkien", "Ada Lovelace", "Alan M. Turing"].into_iter().galloc_scollect();
+        let v = ["J.R.T.", "A.L.", "A.M.T."].into_iter().galloc_scollect();
+        assert_eq!(detect_initials(v, &[ids, names]), Some((1, ".")));
+        let v = ["JRT", "AL", "AMT"].into_iter().galloc_scollect();
+        assert_eq!(detect_initials(v, &[ids, names]), Some((1, "")));
+        let v = ["J.R.T.", "A.L.", "A.T."].into_iter().galloc_scollect();
+        assert_eq!(detect_initials(v, &[ids, names]), None);
+    }
+
+    #[test]
+    fn test_detect_replace_all() {
+        let ids = ["a1", "b2", "c3"].into_iter().galloc_scollect();
+        let phones = ["555-123-4567", "555-987-6543", "800-555-0199"].into_iter().galloc_scollect();
+        let v = ["555.123.4567", "555.987.6543", "800.555.0199"].into_iter().galloc_scollect();
+        assert_eq!(detect_replace_all(v, &[ids, phones]), Some((1, "-", ".")));
+        let v = ["5551234567", "5559876543", "8005550199"].into_iter().galloc_scollect();
+        assert_eq!(detect_replace_all(v, &[ids, phones]), Some((1, "-", "")));
+        // Only the first dash changes: `str.replace` is enough
+        let v = ["555.123-4567", "555.987-6543", "800.555-0199"].into_iter().galloc_scollect();
+        assert_eq!(detect_replace_all(v, &[ids, phones]), None);
+    }
+
+    #[test]
+    fn test_widen_whitespace() {
+        let fields = ["id:  John Smith ;", "\tJane Doe"].into_iter().galloc_scollect();
+        let v = ["John Smith", "Jane"].into_iter().galloc_scollect();
+        assert_eq!(widen_whitespace(v, &[fields]).unwrap().to_str(), &["  John Smith ", "\tJane "]);
+        assert!(widen_whitespace(["John", "Bob"].into_iter().galloc_scollect(), &[fields]).is_none());
+        let plain = ["a-b", "c"].into_iter().galloc_scollect();
+        assert!(widen_whitespace(["b", "c"].into_iter().galloc_scollect(), &[plain]).is_none());
+    }
+
+    #[test]
+    fn test_strip_padding() {
+        let v = ["00042", "12345", "00007"].into_iter().galloc_scollect();
+        let (c, width, rest) = strip_padding(v, true).unwrap();
+        assert_eq!((c, width), ('0', 5));
+        assert_eq!(rest.to_str(), &["42", "12345", "7"]);
+        assert!(strip_padding(v, false).is_none());
+        assert!(strip_padding(["0042", "7"].into_iter().galloc_scollect(), true).is_none());
+    }
+}
@@ -0,0 +1,65 @@
+use std::pin::pin;
+
+use simple_rc_async::task;
+
+use crate::{debg, expr::{ops::Op2Enum, Expr}, forward::executor::Executor, galloc::{AllocForAny, AllocForExactSizeIter}, utils::select_ret, value::Value};
+
+use super::{Deducer, Problem};
+
+/// Deduction for pairs of strings.
+///
+/// A pair is built by a `tuple.make` rule, whose components do not depend on each other: the problem is decomposed into one problem per component, solved independently.
+pub struct TupleDeducer {
+    pub nt: usize,
+    /// The `tuple.make` rule of the non-terminal, with the non-terminals of both components
+    pub make: Option<(&'static Op2Enum, usize, usize)>,
+}
+
+impl std::fmt::Debug for TupleDeducer {
+    /// Formats the deducer like a derived `Debug`, with the `tuple.make` operator written by `Display`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TupleDeducer")
+            .field("nt", &self.nt)
+            .field("make", &self.make.map(|(op, n1, n2)| (op.to_string(), n1, n2)))
+            .finish()
+    }
+}
+
+/// Splits a value of pairs into the values of its first and second components.
+pub fn split_tuple(value: Value) -> (Value, Value) {
+    let Value::Tuple(t) = value else { panic!("Mismatched type: split_tuple") };
+    (Value::Str(t.iter().map(|p| p.0).galloc_scollect()), Value::Str(t.iter().map(|p| p.1).galloc_scollect()))
+}
+
+impl Deducer for TupleDeducer {
+    /// Races the enumeration of the whole pair against the deduction of each component.
+    async fn deduce(&'static self, exec: &'static Executor, problem: Problem) -> &'static Expr {
+        debg!("Deducing subproblem: {} {:?}", exec.cfg[self.nt].name, problem.value);
+        let task = pin!(exec.data[self.nt].all_eq.acquire(problem.value));
+        let Some((op, n1, n2)) = self.make else { return task.await };
+        let (first, second) = split_tuple(problem.value);
+        let make_task = task::spawn(async move {
+            let mut prob = problem;
+            exec.waiting_tasks().inc_cost(&mut prob, 1).await;
+            let a = exec.solve_task(prob.with_nt(n1, first)).await;
+            let b = exec.solve_task(prob.with_nt(n2, second)).await;
+            debg!("TupleDeducer: {:?} {:?}", a, b);
+            Expr::Op2(op, a, b).galloc()
+        });
+        select_ret(task, make_task).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value::Value;
+
+    use super::split_tuple;
+
+    #[test]
+    fn test_split_tuple() {
+        let (a, b) = split_tuple(Value::Tuple(&[("John", "Smith"), ("Jane", "Doe")]));
+        assert_eq!(a, Value::Str(&["John", "Jane"]));
+        assert_eq!(b, Value::Str(&["Smith", "Doe"]));
+    }
+}
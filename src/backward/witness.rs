@@ -0,0 +1,272 @@
+use std::pin::pin;
+
+use itertools::Itertools;
+use simple_rc_async::task::{self, JoinHandle};
+
+use crate::{debg, expr::{cfg::{CfgConfig, ProdRule}, ops::{Concat, Join, ListRev, Neg, Op1Enum, Op2Enum, Op3Enum, Split, ToInt, ToStr}, Expr}, forward::executor::Executor, galloc::{AllocForAny, AllocForExactSizeIter, AllocForStr, TryAllocForExactSizeIter}, never, provenance::{self, Provenance}, utils::select_ret, value::{ConstValue, Value}};
+
+use super::{liststr::split_delimiters, str::{split_once, value_split, HandleRcVec}, Problem};
+
+/// Subproblems cheaper than this are deduced through witness functions.
+const WITNESS_COST_LIMIT: usize = 4;
+
+/// Subproblems cheaper than this are deduced through `str.join` around a delimiter.
+const JOIN_COST_LIMIT: usize = 5;
+
+/// Inverse semantics of an operator with `N` arguments.
+///
+/// Given the expected output on every example, a witness function returns candidate arguments such that the operator produces this output.
+/// Deduction then solves the arguments as subproblems of the non-terminals of the rule.
+/// To add a deduction rule for an operator, implement this trait for it and dispatch to it from the `impl` for its operator enum below; the deducers need no change.
+pub trait WitnessFn<const N: usize> {
+    /// Returns candidate arguments for `output`, the empty vector if none (the default).
+    fn witness(&self, _output: Value) -> Vec<[Value; N]> { Vec::new() }
+    /// Returns candidate arguments for `output` around `delimiter`, a string on every example, the empty vector if none (the default).
+    ///
+    /// Delimiters are the enumerated substrings of string outputs, and the gaps between the elements of list outputs in the inputs (see `split_delimiters`).
+    /// `used_cost` is the cost of the subproblem, and `config` disables rules as the command line asks (`--no-split`, `--no-join`).
+    fn witness_around(&self, _output: Value, _delimiter: Value, _used_cost: usize, _config: &CfgConfig) -> Vec<[Value; N]> { Vec::new() }
+}
+
+impl WitnessFn<1> for Neg {
+    fn witness(&self, output: Value) -> Vec<[Value; 1]> {
+        let Value::Int(v) = output else { return Vec::new() };
+        vec![[v.iter().map(|x| x.wrapping_neg()).galloc_scollect().into()]]
+    }
+}
+
+impl WitnessFn<1> for ToStr {
+    /// Only outputs written as `int.to.str` writes integers (no sign `+`, no leading zero) have a witness.
+    fn witness(&self, output: Value) -> Vec<[Value; 1]> {
+        let Value::Str(v) = output else { return Vec::new() };
+        let ints = v.iter().map(|s| s.parse::<i64>().ok().filter(|i| i.to_string() == *s)).galloc_try_scollect();
+        ints.map(|ints| [ints.into()]).into_iter().collect()
+    }
+}
+
+impl WitnessFn<1> for ToInt {
+    /// Returns the canonical representation of each integer; other strings parsing to the same integer are not considered.
+    fn witness(&self, output: Value) -> Vec<[Value; 1]> {
+        let Value::Int(v) = output else { return Vec::new() };
+        vec![[v.iter().map(|i| i.to_string().galloc_str()).galloc_scollect().into()]]
+    }
+}
+
+impl WitnessFn<1> for ListRev {
+    fn witness(&self, output: Value) -> Vec<[Value; 1]> {
+        match output {
+            Value::ListInt(v) => vec![[v.iter().map(|l| l.iter().rev().copied().galloc_scollect()).galloc_scollect().into()]],
+            Value::ListStr(v) => vec![[v.iter().map(|l| l.iter().rev().copied().galloc_scollect()).galloc_scollect().into()]],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl WitnessFn<1> for Op1Enum {
+    fn witness(&self, output: Value) -> Vec<[Value; 1]> {
+        match self {
+            Op1Enum::Neg(op) => op.witness(output),
+            Op1Enum::ToStr(op) => op.witness(output),
+            Op1Enum::ToInt(op) => op.witness(output),
+            Op1Enum::ListRev(op) => op.witness(output),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl WitnessFn<2> for Concat {
+    /// Splits the outputs around the first occurrence of the delimiter, which must occur in every output: `[left, delimiter ++ right]`, or `[delimiter, right]` if nothing precedes it.
+    fn witness_around(&self, output: Value, delimiter: Value, _used_cost: usize, config: &CfgConfig) -> Vec<[Value; 2]> {
+        if config.no_split { return Vec::new(); }
+        let (Value::Str(v), Value::Str(d)) = (output, delimiter) else { return Vec::new() };
+        if d.iter().all(|d| d.is_empty()) { return Vec::new(); }
+        let (a, b, cases) = split_once(v, d);
+        if !cases.is_all_true() { return Vec::new(); }
+        if !a.is_all_empty() {
+            let rest = d.iter().zip(b.to_str()).map(|(d, b)| format!("{d}{b}").galloc_str()).galloc_scollect();
+            vec![[a, rest.into()]]
+        } else if !b.is_all_empty() {
+            vec![[delimiter, b]]
+        } else { Vec::new() }
+    }
+}
+
+impl WitnessFn<2> for Join {
+    /// Splits the outputs by the delimiter, which must occur in some output: `[parts, delimiter]`.
+    fn witness_around(&self, output: Value, delimiter: Value, used_cost: usize, config: &CfgConfig) -> Vec<[Value; 2]> {
+        if config.no_join || used_cost >= JOIN_COST_LIMIT { return Vec::new(); }
+        let (Value::Str(v), Value::Str(d)) = (output, delimiter) else { return Vec::new() };
+        if d.iter().any(|d| d.is_empty()) || !v.iter().zip(d.iter()).any(|(x, &d)| x.contains(d)) { return Vec::new(); }
+        vec![[value_split(v, d), delimiter]]
+    }
+}
+
+impl WitnessFn<2> for Split {
+    /// Joins the output lists by the delimiter: `[joined, delimiter]`. The split of a string never gives an empty list, nor elements containing the delimiter.
+    fn witness_around(&self, output: Value, delimiter: Value, used_cost: usize, _config: &CfgConfig) -> Vec<[Value; 2]> {
+        if used_cost >= WITNESS_COST_LIMIT { return Vec::new(); }
+        let (Value::ListStr(lists), Value::Str(d)) = (output, delimiter) else { return Vec::new() };
+        if lists.iter().zip(d.iter()).any(|(l, &d)| d.is_empty() || l.is_empty() || l.iter().any(|x| x.contains(d))) { return Vec::new(); }
+        let joined = lists.iter().zip(d.iter()).map(|(l, &d)| l.join(d).galloc_str()).galloc_scollect();
+        vec![[joined.into(), delimiter]]
+    }
+}
+
+impl WitnessFn<2> for Op2Enum {
+    fn witness_around(&self, output: Value, delimiter: Value, used_cost: usize, config: &CfgConfig) -> Vec<[Value; 2]> {
+        match self {
+            Op2Enum::Concat(op) => op.witness_around(output, delimiter, used_cost, config),
+            Op2Enum::Join(op) => op.witness_around(output, delimiter, used_cost, config),
+            Op2Enum::Split(op) => op.witness_around(output, delimiter, used_cost, config),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// No ternary operator has a witness function yet; `ite`, the padding and `str.replace_all` are deduced by `StrDeducer`.
+impl WitnessFn<3> for Op3Enum {}
+
+/// Deduce `prob` through the witness functions of the rules of its non-terminal, returning the first expression found.
+///
+/// Binary rules are also tried around each delimiter of the outputs (see [`WitnessFn::witness_around`]), as long as the subproblem is within the size limit.
+/// Candidates whose type does not match the non-terminal of the argument are ignored. Never returns if no witness applies.
+pub async fn deduce(exec: &'static Executor, prob: Problem) -> &'static Expr {
+    let futures = HandleRcVec::new();
+    if prob.used_cost < exec.cfg.config.max_size {
+        if let Value::ListStr(lists) = prob.value {
+            let inputs = exec.ctx.inputs().filter_map(|v| if let Value::Str(a) = v { Some(a) } else { None });
+            for delimiter in split_delimiters(inputs, lists) {
+                futures.extend_iter(around(exec, prob, ConstValue::Str(delimiter).value(lists.len())).into_iter());
+            }
+        }
+    }
+    let substr_futures = futures.clone();
+    let substr_event = async move {
+        match exec.data[prob.nt].substr() {
+            Some(substr) if prob.used_cost < exec.cfg.config.max_size => {
+                substr.listen_for_each(prob.value, move |delimiter: Value| {
+                    substr_futures.extend_iter(around(exec, prob, delimiter).into_iter());
+                    None::<&'static Expr>
+                }).await
+            }
+            _ => never!(&'static Expr),
+        }
+    };
+    if prob.used_cost >= WITNESS_COST_LIMIT { return select_ret(pin!(futures), pin!(substr_event)).await; }
+    for rule in exec.cfg[prob.nt].rules.iter() {
+        match *rule {
+            ProdRule::Op1(op, nt) => {
+                futures.extend_iter(op.witness(prob.value).into_iter().filter_map(|args| {
+                    spawn(exec, prob, args, [nt], move |[a]| Expr::Op1(op, a))
+                }));
+            }
+            ProdRule::Op2(op, nt1, nt2) => {
+                futures.extend_iter(op.witness(prob.value).into_iter().filter_map(|args| {
+                    spawn(exec, prob, args, [nt1, nt2], move |[a, b]| Expr::Op2(op, a, b))
+                }));
+            }
+            ProdRule::Op3(op, nt1, nt2, nt3) => {
+                futures.extend_iter(op.witness(prob.value).into_iter().filter_map(|args| {
+                    spawn(exec, prob, args, [nt1, nt2, nt3], move |[a, b, c]| Expr::Op3(op, a, b, c))
+                }));
+            }
+            _ => (),
+        }
+    }
+    select_ret(pin!(futures), pin!(substr_event)).await
+}
+
+/// Spawns the witnesses around `delimiter` of the binary rules of the non-terminal of `prob`.
+fn around(exec: &'static Executor, prob: Problem, delimiter: Value) -> Vec<JoinHandle<&'static Expr>> {
+    let rules = exec.cfg[prob.nt].rules.iter().filter_map(|rule| if let ProdRule::Op2(op, nt1, nt2) = *rule { Some((op, nt1, nt2)) } else { None });
+    rules.flat_map(|(op, nt1, nt2)| {
+        op.witness_around(prob.value, delimiter, prob.used_cost, &exec.cfg.config).into_iter().filter_map(move |args| {
+            spawn(exec, prob, args, [nt1, nt2], move |[a, b]| Expr::Op2(op, a, b))
+        })
+    }).collect_vec()
+}
+
+/// Solves the arguments given by a witness function as subproblems of the non-terminals `nts`, then builds the expression with `build`.
+fn spawn<const N: usize>(exec: &'static Executor, mut prob: Problem, args: [Value; N], nts: [usize; N], build: impl FnOnce([&'static Expr; N]) -> Expr + 'static) -> Option<JoinHandle<&'static Expr>> {
+    if args.iter().zip(nts.iter()).any(|(a, nt)| a.ty() != exec.cfg[*nt].ty) { return None; }
+    Some(task::spawn(async move {
+        exec.waiting_tasks().inc_cost(&mut prob, 1).await;
+        debg!("Witness {:?} -> {:?}", prob.value, args);
+        let mut exprs = Vec::with_capacity(N);
+        for (a, nt) in args.into_iter().zip(nts) {
+            exprs.push(exec.solve_task(prob.with_nt(nt, a)).await);
+        }
+        provenance::record(build(exprs.try_into().unwrap()).galloc(), Provenance::Deduced("witness"))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{backward::{DeducerEnum, Problem}, expr::{cfg::{CfgConfig, NonTerminal, ProdRule}, context::Context, ops::{Op1Enum, Op2Enum, ToStr}, Expr}, forward::executor::Executor, galloc::{AllocForAny, AllocForExactSizeIter}, parser::config::Config, value::{ConstValue, Type, Value}};
+
+    use super::WitnessFn;
+
+    #[test]
+    fn test_witness() {
+        let op = Op1Enum::ToStr(ToStr::default());
+        let output: Value = ["42", "-7"].into_iter().galloc_scollect().into();
+        let args = op.witness(output);
+        assert_eq!(args.len(), 1);
+        assert_eq!(args[0][0].to_int(), &[42, -7]);
+        let output: Value = ["042", "-7"].into_iter().galloc_scollect().into();
+        assert!(op.witness(output).is_empty());
+    }
+
+    #[test]
+    fn test_witness_around() {
+        let config = CfgConfig::from(Config::new());
+        let concat = Op2Enum::from_name("str.++", &Config::new());
+        let output: Value = ["Smith, John", "Doe, Jane"].into_iter().galloc_scollect().into();
+        let delimiter: Value = [", ", ", "].into_iter().galloc_scollect().into();
+        let args = concat.witness_around(output, delimiter, 0, &config);
+        assert_eq!(args.len(), 1);
+        assert_eq!(args[0][0].to_str(), &["Smith", "Doe"]);
+        assert_eq!(args[0][1].to_str(), &[", John", ", Jane"]);
+        let args = concat.witness_around(args[0][1], delimiter, 0, &config);
+        assert_eq!(args[0][0], delimiter);
+        assert_eq!(args[0][1].to_str(), &["John", "Jane"]);
+
+        let join = Op2Enum::from_name("str.join", &Config::new());
+        let args = join.witness_around(output, delimiter, 0, &config);
+        assert_eq!(args[0][0].to_liststr(), &[&["Smith", "John"][..], &["Doe", "Jane"][..]]);
+        let split = Op2Enum::from_name("str.split", &Config::new());
+        assert_eq!(split.witness_around(args[0][0], delimiter, 0, &config)[0][0], output);
+
+        let mut config = config;
+        config.no_split = true;
+        assert!(concat.witness_around(output, delimiter, 0, &config).is_empty());
+    }
+
+    #[test]
+    fn test_split_two_fields() {
+        let first: Value = ["John", "Jane"].into_iter().galloc_scollect().into();
+        let last: Value = ["Smith", "Doe"].into_iter().galloc_scollect().into();
+        let output: Value = ["Smith, John", "Doe, Jane"].into_iter().galloc_scollect().into();
+        let ctx = Context::new(2, vec![first, last], vec![], output);
+        let nt = NonTerminal {
+            name: "S".into(),
+            ty: Type::Str,
+            rules: vec![
+                ProdRule::Var(0),
+                ProdRule::Var(1),
+                ProdRule::Const(ConstValue::Str(", ")),
+                ProdRule::Op2(Op2Enum::from_name("str.++", &Config::new()).galloc(), 0, 0),
+            ],
+            config: Config::new(),
+        };
+        let mut config = CfgConfig::from(Config::new());
+        config.time_limit = 10000;
+        let exec = Executor::for_single_nt(ctx.clone(), nt, config);
+
+        // The split around ", " comes from the witness of `str.++`; the sketch keeps no constant this short
+        let deducer = DeducerEnum::from_nt(&exec.cfg, &ctx, 0).galloc();
+        let result = exec.deduce_with(deducer, Problem::root(0, output)).expect("deduction did not finish");
+        assert!(matches!(result, Expr::Op2(op, _, _) if op.name() == "str.++"));
+        assert_eq!(result.eval(&ctx), output);
+    }
+}
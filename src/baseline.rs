@@ -0,0 +1,52 @@
+use crate::{expr::{cfg::Cfg, context::Context, Expr}, forward::executor::Executor, info};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+/// Baseline synthesizers run in place of Synthphonia, as points of comparison in experiments.
+pub enum Baseline {
+    /// Plain bottom-up enumeration with observational equivalence, returning the first term giving all the outputs
+    Enum,
+}
+
+/// Disables in `cfg` every mechanism the baseline goes without.
+///
+/// For `enum`, these are the deducers (and witness functions), the text object scanners, and the pruning of dominated strings; case-splitting is skipped by solving with a single executor instead of tree learning.
+pub fn configure(baseline: Baseline, cfg: &mut Cfg) {
+    match baseline {
+        Baseline::Enum => {
+            cfg.config.no_deduction = true;
+            cfg.config.no_text_objects = true;
+            cfg.config.dominance_pruning = false;
+        }
+    }
+}
+
+/// Solves the examples of `ctx` with the baseline, within the size and time limits of `cfg`.
+pub fn solve(baseline: Baseline, mut cfg: Cfg, ctx: Context) -> Option<&'static Expr> {
+    configure(baseline, &mut cfg);
+    info!("Running the {:?} baseline", baseline);
+    Executor::new(ctx, cfg).solve_top_with_limit()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{backward::DeducerEnum, expr::{cfg::Cfg, context::Context}, forward::executor::Executor, galloc::AllocForExactSizeIter, parser::problem::PBEProblem, value::Value};
+
+    use super::{configure, Baseline};
+
+    #[test]
+    fn test_enum_baseline() {
+        let s = fs::read_to_string("test/test.sl").unwrap();
+        let problem = PBEProblem::parse(s.as_str()).unwrap();
+        let mut cfg = Cfg::from_synthfun(problem.synthfun());
+        configure(Baseline::Enum, &mut cfg);
+        let input: Value = ["ab", "cde"].into_iter().galloc_scollect().into();
+        let output: Value = ["abab", "cdecde"].into_iter().galloc_scollect().into();
+        let ctx = Context::new(2, vec![input], vec![], output);
+        let exec = Executor::new(ctx.clone(), cfg);
+        assert!(exec.deducers.iter().all(|d| matches!(d, DeducerEnum::Simple(_))));
+        let result = exec.solve_top_with_limit().unwrap();
+        assert_eq!(result.eval(&ctx), ctx.output);
+    }
+}
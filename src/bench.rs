@@ -0,0 +1,180 @@
+use std::{fs, io::Read, path::{Path, PathBuf}, process::{Command, Stdio}, sync::atomic::{AtomicUsize, Ordering}, time::{Duration, Instant}};
+
+use derive_more::{Display, From};
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{expr::context::Context, parser::check::CheckProblem, tuning::parse_solution};
+
+/// Time given to a run after its `--timeout` to print its partial solution, before it is killed.
+const GRACE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Display, From)]
+/// Errors raised while running a benchmark.
+pub enum BenchError {
+    #[display(fmt = "{}", _0)]
+    Io(std::io::Error),
+    #[display(fmt = "{}", _0)]
+    Json(serde_json::Error),
+    #[display(fmt = "No .sl file found in {}", _0)]
+    Empty(String),
+}
+
+impl std::error::Error for BenchError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+/// Format of the benchmark summary.
+pub enum Format {
+    /// One line per problem, with a header
+    Csv,
+    /// An array of objects
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Display)]
+#[serde(rename_all = "snake_case")]
+/// Outcome of the run on one problem.
+pub enum Status {
+    /// The solution is correct on the examples of the paired `.smt2` file
+    #[display(fmt = "solved")]
+    Solved,
+    /// A solution was found, but there is no `.smt2` file to check it against
+    #[display(fmt = "unchecked")]
+    Unchecked,
+    /// The solution is wrong on some examples of the paired `.smt2` file
+    #[display(fmt = "wrong")]
+    Wrong,
+    /// The run ended without printing a solution
+    #[display(fmt = "unsolved")]
+    Unsolved,
+    /// The run was killed after the timeout
+    #[display(fmt = "timeout")]
+    Timeout,
+    /// The run failed, or the paired `.smt2` file could not be read
+    #[display(fmt = "error")]
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Result of the run on one problem.
+pub struct BenchResult {
+    /// File name of the problem
+    pub name: String,
+    pub status: Status,
+    /// Whether a solution was found that is not contradicted by the paired `.smt2` file
+    pub solved: bool,
+    /// Wall-clock time of the run, in seconds
+    pub time: f64,
+    /// Number of nodes of the solution, if any
+    pub size: Option<usize>,
+}
+
+/// Checks the output of a run against the examples of the reference `.smt2` file, if any, returning the status and the size of the solution.
+pub fn check(output: &str, reference: Option<&str>) -> (Status, Option<usize>) {
+    let Ok(solution) = parse_solution(output) else { return (Status::Unsolved, None) };
+    let size = Some(solution.expr.size());
+    let Some(reference) = reference else { return (Status::Unchecked, size) };
+    let Ok(check) = CheckProblem::parse(reference) else { return (Status::Error, size) };
+    let ctx = Context::from_examples(&check.examples);
+    let result = solution.expr.eval(&ctx);
+    if result.eq_count(&check.examples.output) == check.examples.len() {
+        (Status::Solved, size)
+    } else {
+        (Status::Wrong, size)
+    }
+}
+
+/// Solves `path` in a new process of this executable with the arguments `args`, killing it `GRACE` after `timeout`.
+fn run_one(path: &Path, timeout: u64, args: &[String]) -> BenchResult {
+    let name = path.file_name().unwrap().to_string_lossy().to_string();
+    let start = Instant::now();
+    let result = |status, size| BenchResult { name: name.clone(), status, solved: matches!(status, Status::Solved | Status::Unchecked), time: start.elapsed().as_secs_f64(), size };
+    let child = std::env::current_exe().and_then(|exe| {
+        Command::new(exe).arg(path).arg("--timeout").arg(timeout.to_string()).args(args)
+            .stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn()
+    });
+    let Ok(mut child) = child else { return result(Status::Error, None) };
+    // Read while the child runs, lest it block on a full pipe and time out
+    let mut stdout = child.stdout.take().unwrap();
+    let reader = std::thread::spawn(move || {
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).map(|_| output)
+    });
+    let deadline = start + Duration::from_secs(timeout) + GRACE;
+    let exit = loop {
+        match child.try_wait() {
+            Ok(Some(exit)) => break exit,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(20)),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return result(Status::Timeout, None);
+            }
+            Err(_) => return result(Status::Error, None),
+        }
+    };
+    let Ok(Ok(output)) = reader.join() else { return result(Status::Error, None) };
+    if !exit.success() { return result(Status::Error, None); }
+    let reference = fs::read_to_string(path.with_extension("smt2")).ok();
+    let (status, size) = check(&output, reference.as_deref());
+    result(status, size)
+}
+
+/// Runs every `.sl` file of `dir` with a timeout of `timeout` seconds, on `jobs` problems at a time.
+///
+/// Each problem is solved in its own process, since the solver keeps global state for the duration of a run; `args` are passed to every run.
+/// Results are returned in the order of the file names.
+pub fn run(dir: impl AsRef<Path>, timeout: u64, jobs: usize, args: &[String]) -> Result<Vec<BenchResult>, BenchError> {
+    let paths: Vec<PathBuf> = fs::read_dir(dir.as_ref())?.map_ok(|e| e.path()).try_collect::<_, Vec<_>, _>()?
+        .into_iter().filter(|p| p.extension().is_some_and(|e| e == "sl")).sorted().collect();
+    if paths.is_empty() {
+        return Err(BenchError::Empty(dir.as_ref().display().to_string()));
+    }
+    let next = AtomicUsize::new(0);
+    let results = spin::Mutex::new(Vec::with_capacity(paths.len()));
+    std::thread::scope(|s| {
+        for _ in 0..jobs.max(1) {
+            s.spawn(|| {
+                while let Some(path) = paths.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let r = run_one(path, timeout, args);
+                    eprintln!("{}: {} ({:.2}s)", r.name, r.status, r.time);
+                    results.lock().push(r);
+                }
+            });
+        }
+    });
+    Ok(results.into_inner().into_iter().sorted_by(|a, b| a.name.cmp(&b.name)).collect())
+}
+
+/// Formats the results as CSV or JSON.
+pub fn format(results: &[BenchResult], format: Format) -> Result<String, BenchError> {
+    match format {
+        Format::Json => Ok(serde_json::to_string_pretty(results)?),
+        Format::Csv => {
+            let mut csv = String::from("name,status,solved,time,size\n");
+            for r in results {
+                let name = if r.name.contains([',', '"']) { format!("\"{}\"", r.name.replace('"', "\"\"")) } else { r.name.clone() };
+                csv += &format!("{},{},{},{:.3},{}\n", name, r.status, r.solved, r.time, r.size.map(|s| s.to_string()).unwrap_or_default());
+            }
+            Ok(csv)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, format, BenchResult, Format, Status};
+
+    #[test]
+    fn test_check() {
+        let reference = "(set-logic SLIA)\n(define-fun f ((name String)) String name)\n(assert (= (f \"b\") \"ba\"))\n(check-sat)\n";
+        let solution = "(define-fun f ((name String)) String (str.++ name \"a\"))\n";
+        assert_eq!(check(solution, Some(reference)), (Status::Solved, Some(3)));
+        assert_eq!(check(solution, None), (Status::Unchecked, Some(3)));
+        assert_eq!(check("(define-fun f ((name String)) String name)", Some(reference)), (Status::Wrong, Some(1)));
+        assert_eq!(check("", Some(reference)), (Status::Unsolved, None));
+
+        let results = [BenchResult { name: "a,b.sl".into(), status: Status::Solved, solved: true, time: 1.5, size: Some(3) }];
+        assert_eq!(format(&results, Format::Csv).unwrap(), "name,status,solved,time,size\n\"a,b.sl\",solved,true,1.500,3\n");
+    }
+}
@@ -0,0 +1,234 @@
+use std::{fs::{self, File}, io::{BufReader, BufWriter, Write}, path::{Path, PathBuf}, time::{Duration, Instant}};
+
+use derive_more::{Display, From};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{expr::{cfg::Cfg, Expr, ExprData, OpTable}, forward::executor::Executor, info, parser::problem::FunSig, value::Value, warn};
+
+/// Version of the checkpoint format, to be bumped on incompatible changes.
+const VERSION: u32 = 1;
+
+/// Minimum interval between two periodic checkpoints.
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Display, From)]
+/// Errors raised while reading a checkpoint.
+pub enum CheckpointError {
+    #[display(fmt = "{}", _0)]
+    Io(std::io::Error),
+    #[display(fmt = "{}", _0)]
+    Format(bincode::Error),
+    #[display(fmt = "Checkpoint does not match the problem: {}", _0)]
+    Mismatch(String),
+}
+
+impl std::error::Error for CheckpointError {}
+
+#[derive(Serialize, Deserialize)]
+/// Saved state of a synthesis run, written with `--checkpoint` and read back with `--resume`.
+///
+/// Expressions are `&'static Expr` when writing and `ExprData` when reading, both having the same serialized form.
+pub struct Checkpoint<E> {
+    version: u32,
+    /// Signature of the synthesized function
+    signature: String,
+    /// Names of the non-terminals of the grammar
+    nts: Vec<String>,
+    /// Number of examples
+    examples: usize,
+    /// Solutions kept by `Solutions`, each solving part of the examples
+    solutions: Vec<E>,
+    /// Term bank of an executor enumerating on all examples: `bank[size - 1][nt]` lists the terms of size `size` of the non-terminal `nt`
+    bank: Vec<Vec<Vec<(E, Value)>>>,
+}
+
+impl<E> Checkpoint<E> {
+    /// Creates an empty checkpoint for a problem.
+    fn new(sig: &FunSig, cfg: &Cfg, examples: usize) -> Self {
+        Self { version: VERSION, signature: sig.to_string(), nts: cfg.iter().map(|nt| nt.name.clone()).collect(), examples, solutions: Vec::new(), bank: Vec::new() }
+    }
+    /// Checks that a checkpoint was written for the same problem and grammar.
+    fn check(&self, other: &Checkpoint<impl Sized>) -> Result<(), CheckpointError> {
+        let mismatch = |s: String| Err(CheckpointError::Mismatch(s));
+        if self.version != other.version {
+            mismatch(format!("format version {} instead of {}", self.version, other.version))
+        } else if self.signature != other.signature {
+            mismatch(format!("signature {} instead of {}", self.signature, other.signature))
+        } else if self.nts != other.nts {
+            mismatch(format!("non-terminals {:?} instead of {:?}", self.nts, other.nts))
+        } else if self.examples != other.examples {
+            mismatch(format!("{} examples instead of {}", self.examples, other.examples))
+        } else { Ok(()) }
+    }
+}
+
+/// Checkpoint being written, with the path of its file.
+struct Writer {
+    path: PathBuf,
+    last_save: Instant,
+    data: Checkpoint<&'static Expr>,
+}
+
+impl Writer {
+    /// Writes the checkpoint to a temporary file, then renames it, so that an interruption never leaves a truncated checkpoint.
+    fn save(&mut self) {
+        let tmp = self.path.with_extension("tmp");
+        let result = (|| -> bincode::Result<()> {
+            let mut file = BufWriter::new(File::create(&tmp)?);
+            bincode::serialize_into(&mut file, &self.data)?;
+            file.flush()?;
+            Ok(fs::rename(&tmp, &self.path)?)
+        })();
+        match result {
+            Ok(()) => info!("Checkpoint saved to {} ({} sizes, {} solutions)", self.path.display(), self.data.bank.len(), self.data.solutions.len()),
+            Err(e) => warn!("Unable to save checkpoint to {}: {}", self.path.display(), e),
+        }
+        self.last_save = Instant::now();
+    }
+    fn save_if_due(&mut self) {
+        if self.last_save.elapsed() >= SAVE_INTERVAL { self.save(); }
+    }
+}
+
+static WRITER: spin::Mutex<Option<Writer>> = spin::Mutex::new(None);
+
+/// Term bank read by `--resume`, replayed by executors instead of enumerating the sizes it covers.
+pub struct Restored {
+    /// Number of examples the values of the bank are computed on
+    pub examples: usize,
+    /// `bank[size - 1][nt]` lists the terms of size `size` of the non-terminal `nt`
+    pub bank: Vec<Vec<Vec<(&'static Expr, Value)>>>,
+}
+
+static RESTORED: spin::Mutex<Option<&'static Restored>> = spin::Mutex::new(None);
+
+/// Starts writing checkpoints of the synthesis of `sig` to `path`.
+pub fn enable(path: impl AsRef<Path>, sig: &FunSig, cfg: &Cfg, examples: usize) {
+    *WRITER.lock() = Some(Writer { path: path.as_ref().to_path_buf(), last_save: Instant::now(), data: Checkpoint::new(sig, cfg, examples) });
+}
+
+/// Returns whether checkpoints are written.
+pub fn enabled() -> bool {
+    WRITER.lock().is_some()
+}
+
+/// Reads a checkpoint, makes its term bank available to executors through [`restored`], and returns its solutions.
+///
+/// Operators are taken from `cfg`, which must be the grammar the checkpoint was written with.
+pub fn resume(path: impl AsRef<Path>, sig: &FunSig, cfg: &Cfg, examples: usize) -> Result<Vec<&'static Expr>, CheckpointError> {
+    let data: Checkpoint<ExprData> = bincode::deserialize_from(BufReader::new(File::open(path.as_ref())?))?;
+    data.check(&Checkpoint::<ExprData>::new(sig, cfg, examples))?;
+    let ops = OpTable::new(cfg);
+    let alloc = |e: &ExprData| e.alloc(&ops).map_err(CheckpointError::Mismatch);
+    let bank: Vec<Vec<Vec<_>>> = data.bank.iter().map(|level| {
+        level.iter().map(|terms| terms.iter().map(|(e, v)| alloc(e).map(|e| (e, *v))).try_collect()).try_collect()
+    }).try_collect()?;
+    let solutions: Vec<_> = data.solutions.iter().map(alloc).try_collect()?;
+    info!("Resuming from {} ({} sizes, {} solutions)", path.as_ref().display(), bank.len(), solutions.len());
+    *RESTORED.lock() = Some(Box::leak(Box::new(Restored { examples, bank })));
+    Ok(solutions)
+}
+
+/// Returns the term bank read by `--resume`, if any.
+pub fn restored() -> Option<&'static Restored> {
+    *RESTORED.lock()
+}
+
+/// Records that the examples were extended to `examples` (e.g. by CEGIS), which invalidates the saved and restored term banks.
+pub fn set_examples(examples: usize) {
+    if let Some(w) = WRITER.lock().as_mut() {
+        w.data.examples = examples;
+        w.data.bank.clear();
+        w.data.solutions.clear();
+    }
+    *RESTORED.lock() = None;
+}
+
+/// Records the terms `exec` enumerated of size `size`.
+///
+/// Only executors enumerating on all examples are recorded: their term bank is complete for any subset of the examples, so it can seed every executor on resume.
+/// `Solutions::create_threads` starts one whenever checkpoints are written.
+pub fn record_level(exec: &Executor, size: usize) {
+    let mut lock = WRITER.lock();
+    let Some(w) = lock.as_mut() else { return };
+    if exec.ctx.len != w.data.examples || w.data.bank.len() + 1 != size { return; }
+    w.data.bank.push(exec.data.iter().map(|d| d.size.get_all(size).to_vec()).collect());
+    w.save_if_due();
+}
+
+/// Records the current solutions of `Solutions`.
+pub fn record_solutions(solutions: impl Iterator<Item = &'static Expr>) {
+    if let Some(w) = WRITER.lock().as_mut() {
+        w.data.solutions = solutions.collect();
+        w.save_if_due();
+    }
+}
+
+/// Writes the checkpoint if the last one is older than the save interval.
+pub fn save_if_due() {
+    if let Some(w) = WRITER.lock().as_mut() { w.save_if_due(); }
+}
+
+/// Writes the checkpoint now.
+pub fn save() {
+    if let Some(w) = WRITER.lock().as_mut() { w.save(); }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::{self, File}, io::BufReader, time::Duration};
+
+    use crate::{expr::{cfg::Cfg, context::Context, ExprData}, galloc::AllocForAny, parser::problem::PBEProblem, solutions::{Solutions, CONDITIONS, SOLUTIONS_LOCK}, value::ConstValue};
+    use crate::expr;
+
+    use super::{enable, save, Checkpoint, WRITER};
+
+    #[test]
+    fn test_checkpoint_format() {
+        let problem = PBEProblem::parse_json(r#"{"inputs": [["a", 1]], "outputs": ["a1"]}"#).unwrap();
+        let cfg = Cfg::from_synthfun(problem.synthfun());
+        let mut data = Checkpoint::new(&problem.synthfun().sig, &cfg, 1);
+        let e = expr!(Concat [0] "1").galloc();
+        data.solutions.push(e);
+        data.bank.push(vec![vec![(e, ConstValue::Str("a1").value(1))]]);
+
+        let bytes = bincode::serialize(&data).unwrap();
+        let read: Checkpoint<ExprData> = bincode::deserialize(&bytes).unwrap();
+        assert!(read.check(&Checkpoint::<ExprData>::new(&problem.synthfun().sig, &cfg, 1)).is_ok());
+        assert!(read.check(&Checkpoint::<ExprData>::new(&problem.synthfun().sig, &cfg, 2)).is_err());
+        assert_eq!(read.solutions[0].alloc(&Default::default()).unwrap(), e);
+        assert_eq!(read.bank[0][0][0].1, data.bank[0][0][0].1);
+    }
+
+    #[tokio::test]
+    async fn test_default_checkpoint() {
+        let _guard = SOLUTIONS_LOCK.lock().await;
+        let problem = PBEProblem::parse(r#"
+            (set-logic SLIA)
+            (synth-fun f ((x String)) String ((Start String (ntString)) (ntString String (x (str.from_int ntInt))) (ntInt Int ((str.len ntString)))))
+            (constraint (= (f "a") "1"))
+            (constraint (= (f "bc") "2"))
+            (constraint (= (f "d") "1"))
+            (check-synth)
+        "#).unwrap();
+        let cfg = Cfg::from_synthfun(problem.synthfun());
+        let ctx = Context::from_examples(&problem.examples);
+        let path = std::env::temp_dir().join(format!("synthphonia-checkpoint-{}.bin", std::process::id()));
+        enable(&path, &problem.synthfun().sig, &cfg, ctx.len);
+
+        // Threads on example sets only, as by default
+        let mut solutions = Solutions::new(cfg, ctx);
+        solutions.set_timeout(Duration::from_secs(10));
+        solutions.create_threads(4, false);
+        assert!(solutions.solve_loop().await.is_some());
+        solutions.stop_threads();
+        save();
+        *WRITER.lock() = None;
+        *CONDITIONS.lock() = None;
+
+        let read: Checkpoint<ExprData> = bincode::deserialize_from(BufReader::new(File::open(&path).unwrap())).unwrap();
+        let _ = fs::remove_file(&path);
+        assert!(!read.bank.is_empty());
+    }
+}
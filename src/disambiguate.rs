@@ -0,0 +1,140 @@
+use std::io::{self, BufRead, Write};
+
+use itertools::Itertools;
+
+use crate::{expr::{context::Context, Expr}, galloc::AllocForStr, parser::{ioexamples::IOExamples, problem::FunSig}, smt::{literal, parse_literal, SExpr}, value::{ConstValue, Value}};
+
+/// Maximum number of generated inputs on which the candidates are compared.
+const MAX_INPUTS: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq)]
+/// An input on which candidate solutions disagree, to be labeled by the user.
+pub struct Query {
+    /// Arguments of the synthesized function
+    pub inputs: Vec<ConstValue>,
+    /// Output of each candidate on `inputs`, in the order of the candidates
+    pub outputs: Vec<ConstValue>,
+}
+
+/// Returns small variations of an argument: for strings, dropping or replacing a character, changing the case, repeating it or emptying it; for integers, nearby and opposite values.
+fn mutations(c: ConstValue) -> Vec<ConstValue> {
+    match c {
+        ConstValue::Str(s) => {
+            let chars = s.chars().collect_vec();
+            let mut result = vec![String::new(), s.to_uppercase(), s.to_lowercase(), format!("{s} {s}")];
+            for i in 0..chars.len() {
+                let replacement = match chars[i] {
+                    '9' => '0',
+                    c if c.is_ascii_digit() => (c as u8 + 1) as char,
+                    c if c.is_uppercase() => if c == 'X' { 'Y' } else { 'X' },
+                    c if c.is_alphabetic() => if c == 'x' { 'y' } else { 'x' },
+                    _ => '-',
+                };
+                result.push(chars.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, c)| c).collect());
+                result.push(chars.iter().enumerate().map(|(j, c)| if j == i { replacement } else { *c }).collect());
+            }
+            result.into_iter().filter(|r| r != s).unique().map(|r| ConstValue::Str(r.galloc_str())).collect()
+        }
+        ConstValue::Int(i) => [0, i.saturating_add(1), i.saturating_sub(1), i.saturating_neg(), i.saturating_mul(10)]
+            .into_iter().filter(|j| *j != i).unique().map(ConstValue::Int).collect(),
+        ConstValue::Bool(b) => vec![ConstValue::Bool(!b)],
+        _ => Vec::new(),
+    }
+}
+
+/// Searches for an input on which the `candidates` do not all agree, by mutating one argument of an example of `ctx` at a time.
+///
+/// Inputs closest to the examples come first, and inputs of the examples themselves are skipped. Returns `None` if fewer than two candidates are given, or if they agree on all generated inputs.
+pub fn distinguishing_input(candidates: &[&'static Expr], ctx: &Context) -> Option<Query> {
+    if candidates.len() < 2 || ctx.p.is_empty() { return None; }
+    let rows = (0..ctx.len).map(|i| ctx.p.iter().map(|v| v.get_const(i)).collect_vec()).collect_vec();
+    let generated = rows.iter()
+        .flat_map(|row| (0..row.len()).flat_map(move |j| mutations(row[j]).into_iter().map(move |m| {
+            let mut r = row.clone();
+            r[j] = m;
+            r
+        })))
+        .filter(|r| !rows.contains(r))
+        .unique().take(MAX_INPUTS).collect_vec();
+    if generated.is_empty() { return None; }
+
+    let inputs = ctx.p.iter().enumerate().map(|(j, v)| Value::from_const(v.ty(), generated.iter().map(|r| r[j]))).collect_vec();
+    let gctx = Context::new(generated.len(), inputs, Vec::new(), Value::Null);
+    let values = candidates.iter().map(|e| e.eval(&gctx)).collect_vec();
+    if values.iter().any(|v| matches!(v, Value::Null) || v.len() != generated.len()) { return None; }
+    (0..generated.len()).find_map(|r| {
+        let outputs = values.iter().map(|v| v.get_const(r)).collect_vec();
+        outputs.iter().any(|o| *o != outputs[0]).then(|| Query { inputs: generated[r].clone(), outputs })
+    })
+}
+
+/// Prints a constant as an SMT-LIB literal when possible.
+fn show(c: &ConstValue) -> String {
+    literal(c).unwrap_or_else(|_| c.to_string())
+}
+
+impl Query {
+    /// Formats the query as a comment, e.g. `; Distinguishing input: (f "ab") is "a" or "b"?`, listing each distinct output once.
+    pub fn format(&self, sig: &FunSig) -> String {
+        let args = self.inputs.iter().map(show).join(" ");
+        let outputs = self.outputs.iter().unique().map(show).join(" or ");
+        format!("; Distinguishing input: ({} {}) is {}?", sig.name, args, outputs)
+    }
+}
+
+/// Prints `query` on stdout and reads its expected output as an SMT-LIB literal from `input`, adding the labeled input to `examples`.
+///
+/// Literals that cannot be read as the return type of `sig` are asked again. Returns `false` without changing `examples` on an empty line or at the end of `input`.
+pub fn ask(query: &Query, sig: &FunSig, examples: &mut IOExamples, input: impl BufRead) -> io::Result<bool> {
+    println!("{}", query.format(sig));
+    io::stdout().flush()?;
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() { return Ok(false); }
+        match SExpr::parse(&line).and_then(|e| parse_literal(&e, sig.rettype)) {
+            Ok(output) => {
+                examples.add_example(&query.inputs, output);
+                return Ok(true);
+            }
+            Err(e) => {
+                println!("; {e}: expecting a {:?} literal, or an empty line to stop", sig.rettype);
+                io::stdout().flush()?;
+            }
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr, expr::context::Context, galloc::{AllocForAny, AllocForExactSizeIter}, parser::problem::PBEProblem, value::{ConstValue, Value}};
+
+    use super::{ask, distinguishing_input};
+
+    #[test]
+    fn test_distinguishing_input() {
+        let input: Value = ["ab", "cd"].into_iter().galloc_scollect().into();
+        let output: Value = ["a", "c"].into_iter().galloc_scollect().into();
+        let ctx = Context::new(2, vec![input], vec![], output);
+        let e1 = expr!(SubStr [0] 0 1).galloc();
+        let e2 = expr!(StrAt [0] 0).galloc();
+        assert_eq!(distinguishing_input(&[e1, e2], &ctx), None);
+
+        let e3 = expr!(Replace [0] "b" "").galloc();
+        let query = distinguishing_input(&[e1, e3], &ctx).unwrap();
+        assert_ne!(query.outputs[0], query.outputs[1]);
+        assert_eq!(e1.eval(&Context::new(1, vec![Value::from_const(crate::value::Type::Str, query.inputs.iter().copied())], vec![], Value::Null)).get_const(0), query.outputs[0]);
+
+        let s = r#"(set-logic SLIA)
+(synth-fun f ((name String)) String ((Start String (name "a" (str.++ Start Start)))))
+(constraint (= (f "ab") "a"))
+(check-synth)"#;
+        let mut problem = PBEProblem::parse(s).unwrap();
+        let sig = problem.synthfun().sig.clone();
+        assert!(query.format(&sig).starts_with("; Distinguishing input: (f \""));
+        assert!(ask(&query, &sig, &mut problem.examples, "\"x\"\n".as_bytes()).unwrap());
+        assert_eq!(problem.examples.output.get_const(1), ConstValue::Str("x"));
+        assert!(!ask(&query, &sig, &mut problem.examples, "\n".as_bytes()).unwrap());
+        assert_eq!(problem.examples.len(), 2);
+    }
+}
@@ -0,0 +1,161 @@
+use itertools::Itertools;
+
+use crate::expr::Expr;
+use crate::parser::problem::FunSig;
+use crate::tree_learning::{bits::BoxSliceExt, recorded_tree, Bits, DecisionNode, DecisionTree};
+
+/// Escapes `s` for a double-quoted Graphviz label.
+pub fn label(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+/// The examples in `bits` as a string of `0` and `1`, the first example first.
+fn bit_string(bits: &Bits, size: usize) -> String {
+    (0..size).map(|i| if bits.get(i) { '1' } else { '0' }).collect()
+}
+
+/// Writes the graph of an expression or a decision tree, numbering its nodes `{prefix}{n}`.
+struct Graph<'a> {
+    sig: &'a FunSig,
+    prefix: &'static str,
+    count: usize,
+    lines: Vec<String>,
+}
+
+impl<'a> Graph<'a> {
+    fn new(sig: &'a FunSig, prefix: &'static str) -> Self {
+        Self { sig, prefix, count: 0, lines: Vec::new() }
+    }
+    fn node(&mut self, label: &str, attrs: &str) -> String {
+        let id = format!("{}{}", self.prefix, self.count);
+        self.count += 1;
+        self.lines.push(format!("{id} [label={}{attrs}];", self::label(label)));
+        id
+    }
+    fn edge(&mut self, from: &str, to: &str, label: Option<&str>) {
+        match label {
+            Some(l) => self.lines.push(format!("{from} -> {to} [label={}];", self::label(l))),
+            None => self.lines.push(format!("{from} -> {to};")),
+        }
+    }
+    /// Adds the subtree of `e` and returns the id of its root; operators are boxes, constants and variables ellipses.
+    fn expr(&mut self, e: &Expr) -> String {
+        match e {
+            Expr::Const(c) => self.node(&format!("{:?}", c), ", shape=ellipse"),
+            Expr::Var(v) if *v < 0 => self.node(&Expr::let_name(*v), ", shape=ellipse"),
+            Expr::Var(v) => {
+                let name = self.sig.args.get(*v as usize).map_or(format!("<{v}>"), |a| a.0.clone());
+                self.node(&name, ", shape=ellipse, style=bold")
+            }
+            Expr::Let(v, a, body) => {
+                let id = self.node(&format!("let {}", Expr::let_name(*v)), "");
+                let a = self.expr(a);
+                let body = self.expr(body);
+                self.edge(&id, &a, Some("="));
+                self.edge(&id, &body, Some("in"));
+                id
+            }
+            Expr::Op1(op, a1) => {
+                let id = self.node(&op.to_string(), "");
+                let a1 = self.expr(a1);
+                self.edge(&id, &a1, None);
+                id
+            }
+            Expr::Op2(op, a1, a2) => {
+                let id = self.node(&op.to_string(), "");
+                for a in [a1, a2] {
+                    let a = self.expr(a);
+                    self.edge(&id, &a, None);
+                }
+                id
+            }
+            Expr::Op3(op, a1, a2, a3) => {
+                let id = self.node(&op.to_string(), "");
+                for a in [a1, a2, a3] {
+                    let a = self.expr(a);
+                    self.edge(&id, &a, None);
+                }
+                id
+            }
+        }
+    }
+    /// Adds the subtree of `node` of a decision tree over `size` examples and returns the id of its root.
+    ///
+    /// Conditions are diamonds labeled with the conditional entropy of their split, and every node with the examples reaching it.
+    fn tree(&mut self, node: &DecisionNode, size: usize) -> String {
+        match node {
+            DecisionNode::Unsolved { entropy, examples } => {
+                self.node(&format!("??\nentropy {:.3}\nexamples {}", entropy, bit_string(examples, size)), ", style=dashed")
+            }
+            DecisionNode::Leaf { expr, abstain, examples } => {
+                let kind = if *abstain { "abstain " } else { "" };
+                self.node(&format!("{kind}{}\nexamples {}", expr.format(self.sig), bit_string(examples, size)), ", shape=ellipse")
+            }
+            DecisionNode::Ite { cond, entropy, examples, t, f } => {
+                let id = self.node(&format!("{}\nentropy {:.3}\nexamples {}", cond.format(self.sig), entropy, bit_string(examples, size)), ", shape=diamond");
+                let t = self.tree(t, size);
+                let f = self.tree(f, size);
+                self.edge(&id, &t, Some("true"));
+                self.edge(&id, &f, Some("false"));
+                id
+            }
+        }
+    }
+    fn cluster(self, name: &str, title: &str) -> String {
+        format!("  subgraph {name} {{\n    label={};\n    {}\n  }}\n", label(title), self.lines.iter().join("\n    "))
+    }
+}
+
+/// Renders a Graphviz graph of `e`: the expression tree and, if `e` was built by tree learning (see `tree_learning::record_trees`), the decision tree it was built from.
+pub fn graph(e: &'static Expr, sig: &FunSig) -> String {
+    graph_with_tree(e, sig, recorded_tree(e).as_ref())
+}
+
+/// Renders a Graphviz graph of `e` and of its decision tree, if any.
+pub fn graph_with_tree(e: &Expr, sig: &FunSig, tree: Option<&DecisionTree>) -> String {
+    let mut result = format!("digraph {} {{\n  node [shape=box, fontname=\"monospace\"];\n", label(&sig.name));
+    let mut g = Graph::new(sig, "e");
+    g.expr(e);
+    result += &g.cluster("cluster_expr", &format!("{}({})", sig.name, sig.args.iter().map(|a| a.0.as_str()).join(", ")));
+    if let Some(tree) = tree {
+        let mut g = Graph::new(sig, "t");
+        g.tree(&tree.root, tree.size);
+        result += &g.cluster("cluster_tree", "decision tree");
+    }
+    result.push('}');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr, galloc::AllocForAny, parser::problem::FunSig, tree_learning::{bits::BoxSliceExt, Bits, DecisionNode, DecisionTree}, value::Type};
+
+    use super::graph_with_tree;
+
+    #[test]
+    fn test_dot() {
+        let sig = FunSig { name: "f".into(), args: vec![("name".into(), Type::Str)], rettype: Type::Str };
+        let e = expr!(Concat [0] "\"").galloc();
+        assert_eq!(graph_with_tree(e, &sig, None), concat!(
+            "digraph \"f\" {\n",
+            "  node [shape=box, fontname=\"monospace\"];\n",
+            "  subgraph cluster_expr {\n",
+            "    label=\"f(name)\";\n",
+            "    e0 [label=\"str.++\"];\n",
+            "    e1 [label=\"name\", shape=ellipse, style=bold];\n",
+            "    e0 -> e1;\n",
+            "    e2 [label=\"\\\"\\\\\\\"\\\"\", shape=ellipse];\n",
+            "    e0 -> e2;\n",
+            "  }\n",
+            "}",
+        ));
+
+        let bits = |v: [bool; 2]| Bits::from_bit_siter(v.into_iter());
+        let leaf = |e, b| Box::new(DecisionNode::Leaf { expr: e, abstain: false, examples: bits(b) });
+        let cond = expr!(PrefixOf "a" [0]).galloc();
+        let root = DecisionNode::Ite { cond, entropy: 0.0, examples: bits([true, true]), t: leaf(e, [true, false]), f: leaf(e, [false, true]) };
+        let dot = graph_with_tree(e, &sig, Some(&DecisionTree { size: 2, root }));
+        assert!(dot.contains("t0 [label=\"(str.prefixof \\\"a\\\" name)\\nentropy 0.000\\nexamples 11\", shape=diamond];"));
+        assert!(dot.contains("t0 -> t1 [label=\"true\"];\n    t0 -> t2 [label=\"false\"];"));
+    }
+}
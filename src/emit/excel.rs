@@ -0,0 +1,256 @@
+use crate::{expr::{ops::{Op1Enum, Op2Enum, Op3Enum}, Expr}, parser::problem::FunSig, value::ConstValue};
+
+use super::EmitError;
+
+const TARGET: &str = "Excel";
+
+/// Row holding the first example: row 1 is expected to be a header with the argument names.
+pub const FIRST_ROW: usize = 2;
+
+/// Difference between `chrono`'s `num_days_from_ce`, used by date values, and spreadsheet date serials (day 0 is 1899-12-30).
+const SERIAL_OFFSET: i64 = 693594;
+
+/// Returns the spreadsheet column name of the `i`-th argument: `A`, `B`, ..., `Z`, `AA`, ...
+pub fn column_name(mut i: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (i % 26) as u8);
+        if i < 26 { break; }
+        i = i / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap()
+}
+
+/// Quotes a string literal, doubling inner quotes.
+pub fn string_literal(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+/// Translates an expression into a spreadsheet formula, e.g. `=CONCAT(LEFT(A2, 1), ". ", B2)`.
+///
+/// The `i`-th argument of `sig` is read from column `column_name(i)` in row [`FIRST_ROW`], so that the formula can be filled down along the examples.
+/// String indices are 0-based in expressions and 1-based in formulas; constant indices are shifted directly.
+pub fn formula(e: &Expr, sig: &FunSig) -> Result<String, EmitError> {
+    Ok(format!("={}", term(e, sig)?))
+}
+
+fn unsupported(name: impl std::fmt::Display) -> EmitError {
+    EmitError::Unsupported(name.to_string(), TARGET)
+}
+
+fn literal(c: &ConstValue) -> Result<String, EmitError> {
+    match c {
+        ConstValue::Bool(b) => Ok(if *b { "TRUE" } else { "FALSE" }.to_string()),
+        ConstValue::Int(i) => Ok(i.to_string()),
+        ConstValue::Float(f) => Ok((**f).to_string()),
+        ConstValue::Str(s) => Ok(string_literal(s)),
+        c => Err(unsupported(format!("Constant {:?}", c))),
+    }
+}
+
+/// Prints `e + 1`, folding the addition for constants.
+fn one_based(e: &Expr, sig: &FunSig) -> Result<String, EmitError> {
+    match e {
+        Expr::Const(ConstValue::Int(i)) => Ok((i + 1).to_string()),
+        e => Ok(format!("{}+1", term(e, sig)?)),
+    }
+}
+
+/// Prints a date value as a date serial.
+fn date_serial(e: &Expr, sig: &FunSig) -> Result<String, EmitError> {
+    match e {
+        Expr::Op1(Op1Enum::ParseDate(_), a) => Ok(format!("DATEVALUE({})", term(a, sig)?)),
+        e => Ok(format!("({}-{})", term(e, sig)?, SERIAL_OFFSET)),
+    }
+}
+
+/// Prints a time value (seconds since midnight) as a fraction of a day.
+fn day_fraction(e: &Expr, sig: &FunSig) -> Result<String, EmitError> {
+    match e {
+        Expr::Op1(Op1Enum::ParseTime(_), a) => Ok(format!("TIMEVALUE({})", term(a, sig)?)),
+        e => Ok(format!("{}/86400", term(e, sig)?)),
+    }
+}
+
+/// Collects the operands of nested `str.++`.
+fn concat_operands<'a>(e: &'a Expr, result: &mut Vec<&'a Expr>) {
+    if let Expr::Op2(Op2Enum::Concat(_), a1, a2) = e {
+        concat_operands(a1, result);
+        concat_operands(a2, result);
+    } else { result.push(e) }
+}
+
+fn term(e: &Expr, sig: &FunSig) -> Result<String, EmitError> {
+    match e {
+        Expr::Const(c) => literal(c),
+        Expr::Var(i) if *i < 0 => Ok(Expr::let_name(*i)),
+        Expr::Var(i) => Ok(format!("{}{}", column_name(*i as usize), FIRST_ROW)),
+        Expr::Op1(op, a1) => op1(op, a1, sig),
+        Expr::Op2(Op2Enum::Concat(_), _, _) => {
+            let mut operands = Vec::new();
+            concat_operands(e, &mut operands);
+            Ok(format!("CONCAT({})", operands.into_iter().map(|e| term(e, sig)).collect::<Result<Vec<_>, _>>()?.join(", ")))
+        }
+        Expr::Op2(op, a1, a2) => op2(op, a1, a2, sig),
+        Expr::Op3(op, a1, a2, a3) => op3(op, a1, a2, a3, sig),
+        Expr::Let(v, e, body) => Ok(format!("LET({}, {}, {})", Expr::let_name(*v), term(e, sig)?, term(body, sig)?)),
+    }
+}
+
+fn op1(op: &Op1Enum, a1: &Expr, sig: &FunSig) -> Result<String, EmitError> {
+    let a = || term(a1, sig);
+    Ok(match op {
+        Op1Enum::Len(_) => format!("LEN({})", a()?),
+        Op1Enum::ToInt(_) => format!("IFERROR(VALUE({}), 0)", a()?),
+        Op1Enum::ToStr(_) => format!("TEXT({}, \"0\")", a()?),
+        Op1Enum::Neg(_) | Op1Enum::FNeg(_) => format!("(-{})", a()?),
+        Op1Enum::FAbs(_) | Op1Enum::Abs(_) => format!("ABS({})", a()?),
+        Op1Enum::IsPos(_) | Op1Enum::FIsPos(_) => format!("({}>0)", a()?),
+        Op1Enum::IsZero(_) | Op1Enum::FIsZero(_) => format!("({}=0)", a()?),
+        Op1Enum::IsNatural(_) | Op1Enum::FNotNeg(_) => format!("({}>=0)", a()?),
+        Op1Enum::Uppercase(_) => format!("UPPER({})", a()?),
+        Op1Enum::Lowercase(_) => format!("LOWER({})", a()?),
+        Op1Enum::TitleCase(_) => format!("PROPER({})", a()?),
+        Op1Enum::Capitalize(_) => format!("(UPPER(LEFT({0}, 1))&LOWER(MID({0}, 2, LEN({0}))))", a()?),
+        Op1Enum::IsEmpty(_) => format!("(LEN({})=0)", a()?),
+        Op1Enum::UrlEncode(_) => format!("ENCODEURL({})", a()?),
+        Op1Enum::IntToFloat(_) => a()?,
+        Op1Enum::FloatToInt(_) => format!("TRUNC({})", a()?),
+        Op1Enum::ParseInt(_) | Op1Enum::ParseFloat(_) | Op1Enum::StrToFloat(_) => format!("VALUE({})", a()?),
+        Op1Enum::ParseDate(_) => format!("(DATEVALUE({})+{})", a()?, SERIAL_OFFSET),
+        Op1Enum::ParseTime(_) => format!("ROUND(TIMEVALUE({})*86400, 0)", a()?),
+        Op1Enum::ParseMonth(_) => format!("MONTH(DATEVALUE(\"1 \" & {} & \" 2000\"))", a()?),
+        Op1Enum::AsYear(_) => format!("YEAR({})", date_serial(a1, sig)?),
+        Op1Enum::AsMonth(_) => format!("MONTH({})", date_serial(a1, sig)?),
+        Op1Enum::AsDay(_) => format!("DAY({})", date_serial(a1, sig)?),
+        Op1Enum::AsWeekDay(_) => format!("WEEKDAY({})", date_serial(a1, sig)?),
+        Op1Enum::AsQuarter(_) => format!("ROUNDUP(MONTH({})/3, 0)", date_serial(a1, sig)?),
+        Op1Enum::DurationToHms(_) => format!("TEXT({}/86400, \"[hh]:mm:ss\")", a()?),
+        Op1Enum::AsWeekOfYear(_) => format!("ISOWEEKNUM({})", date_serial(a1, sig)?),
+        Op1Enum::FormatQuarter(_) => {
+            let d = date_serial(a1, sig)?;
+            format!("(\"Q\" & ROUNDUP(MONTH({d})/3, 0) & \" \" & YEAR({d}))")
+        }
+        // `TEXT` writes numbers with the separators of the spreadsheet, not of the format code
+        Op1Enum::FormatInt(f) if !f.locale().is_plain() => return Err(unsupported(op.name())),
+        Op1Enum::FormatFloat(f) if f.exponent().is_none() && !f.locale().is_plain() => return Err(unsupported(op.name())),
+        Op1Enum::FormatInt(f) => format!("TEXT({}, {})", a()?, string_literal(&"0".repeat(f.width().max(1)))),
+        // Spreadsheets always write the sign of the exponent
+        Op1Enum::FormatFloat(f) if f.exponent().is_some() => match f.exponent() {
+            Some(e) if e.plus == Some(true) => {
+                let code = format!("0{}{}+{}", if e.digits > 0 { ".".to_string() + &"0".repeat(e.digits) } else { String::new() }, if e.upper { 'E' } else { 'e' }, "0".repeat(e.width.max(1)));
+                format!("TEXT({}, {})", a()?, string_literal(&code))
+            }
+            _ => return Err(unsupported(op.name())),
+        },
+        Op1Enum::FormatFloat(f) => {
+            let (left, right) = f.padding();
+            let code = "0".repeat(left.max(1)) + &if right > 0 { ".".to_string() + &"0".repeat(right) } else { String::new() };
+            format!("TEXT({}, {})", a()?, string_literal(&code))
+        }
+        Op1Enum::FormatTime(f) if f.zone().is_none() => format!("TEXT({}, {})", day_fraction(a1, sig)?, string_literal(&f.excel_format())),
+        Op1Enum::FormatMonth(f) => format!("TEXT(DATE(2000, {}, 1), {})", a()?, string_literal(if f.abbreviated() { "mmm" } else { "mmmm" })),
+        // Serial days 1 to 7 are Sunday to Saturday, matching `date.weekday`.
+        Op1Enum::FormatWeekday(f) => format!("TEXT({}, {})", a()?, string_literal(if f.abbreviated() { "ddd" } else { "dddd" })),
+        Op1Enum::FormatDigits(f) => {
+            let code: String = f.pattern().chars().map(|c| if c == '#' { "0".to_string() } else { format!("\\{c}") }).collect();
+            format!("TEXT(VALUE({}), {})", a()?, string_literal(&code))
+        }
+        Op1Enum::FormatRoman(f) if f.lowercase() => format!("LOWER(ROMAN({}))", a()?),
+        Op1Enum::FormatRoman(_) => format!("ROMAN({})", a()?),
+        Op1Enum::FormatOrdinal(f) => {
+            let (x, (th, st, nd, rd)) = (a()?, if f.uppercase() { ("TH", "ST", "ND", "RD") } else { ("th", "st", "nd", "rd") });
+            format!("({x} & IF(AND(MOD(ABS({x}), 100)>=11, MOD(ABS({x}), 100)<=13), \"{th}\", CHOOSE(MIN(MOD(ABS({x}), 10), 4)+1, \"{th}\", \"{st}\", \"{nd}\", \"{rd}\", \"{th}\")))")
+        }
+        Op1Enum::FormatPercent(f) => format!("TEXT({}, {})", a()?, string_literal(&format!("0{}%", if f.digits() > 0 { ".".to_string() + &"0".repeat(f.digits()) } else { String::new() }))),
+        Op1Enum::FormatCurrency(f) if matches!(f.separators(), ('.', None | Some(','))) => format!("TEXT({}, {})", a()?, string_literal(&f.excel_format())),
+        op => return Err(unsupported(op.name())),
+    })
+}
+
+fn op2(op: &Op2Enum, a1: &Expr, a2: &Expr, sig: &FunSig) -> Result<String, EmitError> {
+    let (a, b) = (|| term(a1, sig), || term(a2, sig));
+    Ok(match op {
+        Op2Enum::Eq(_) => format!("({}={})", a()?, b()?),
+        Op2Enum::PrefixOf(_) => format!("EXACT(LEFT({1}, LEN({0})), {0})", a()?, b()?),
+        Op2Enum::SuffixOf(_) => format!("EXACT(RIGHT({1}, LEN({0})), {0})", a()?, b()?),
+        Op2Enum::Contains(_) => format!("ISNUMBER(FIND({1}, {0}))", a()?, b()?),
+        Op2Enum::Count(_) => format!("(LEN({0})-LEN(SUBSTITUTE({0}, {1}, \"\")))/LEN({1})", a()?, b()?),
+        Op2Enum::Add(_) | Op2Enum::FAdd(_) => format!("({}+{})", a()?, b()?),
+        Op2Enum::Sub(_) | Op2Enum::FSub(_) => format!("({}-{})", a()?, b()?),
+        Op2Enum::Mul(_) => format!("({}*{})", a()?, b()?),
+        Op2Enum::Div(_) => format!("(({0}-MOD({0}, ABS({1})))/{1})", a()?, b()?),
+        Op2Enum::Mod(_) => format!("MOD({}, ABS({}))", a()?, b()?),
+        Op2Enum::Min(_) => format!("MIN({}, {})", a()?, b()?),
+        Op2Enum::Max(_) => format!("MAX({}, {})", a()?, b()?),
+        Op2Enum::Lt(_) | Op2Enum::FLt(_) => format!("({}<{})", a()?, b()?),
+        Op2Enum::Le(_) | Op2Enum::FLe(_) => format!("({}<={})", a()?, b()?),
+        Op2Enum::Gt(_) | Op2Enum::FGt(_) => format!("({}>{})", a()?, b()?),
+        Op2Enum::Ge(_) | Op2Enum::FGe(_) => format!("({}>={})", a()?, b()?),
+        Op2Enum::StrAt(_) => format!("MID({}, {}, 1)", a()?, one_based(a2, sig)?),
+        Op2Enum::Head(_) if matches!(a2, Expr::Const(ConstValue::Int(i)) if *i > 0) => format!("LEFT({}, {})", a()?, b()?),
+        Op2Enum::Tail(_) if matches!(a2, Expr::Const(ConstValue::Int(i)) if *i > 0) => format!("MID({0}, {1}, LEN({0}))", a()?, one_based(a2, sig)?),
+        Op2Enum::Floor(_) | Op2Enum::FFloor(_) | Op2Enum::TimeFloor(_) => format!("FLOOR.MATH({}, {})", a()?, b()?),
+        Op2Enum::Ceil(_) | Op2Enum::FCeil(_) => format!("CEILING.MATH({}, {})", a()?, b()?),
+        Op2Enum::Round(_) | Op2Enum::FRound(_) => format!("MROUND({}, {})", a()?, b()?),
+        Op2Enum::FRoundTo(_) => format!("ROUND({}, {})", a()?, b()?),
+        Op2Enum::TimeAdd(_) => format!("MOD({}+{}, 216000)", a()?, b()?),
+        Op2Enum::DiffDays(_) => format!("({}-{})", b()?, a()?),
+        Op2Enum::TimeDiff(_) => format!("MOD({}-{}, 86400)", b()?, a()?),
+        Op2Enum::AddMonths(_) => format!("(EDATE({}, {})+{})", date_serial(a1, sig)?, b()?, SERIAL_OFFSET),
+        Op2Enum::DateTruncate(_) => match a2 {
+            Expr::Const(ConstValue::Str("year")) => format!("(DATE(YEAR({0}), 1, 1)+{1})", date_serial(a1, sig)?, SERIAL_OFFSET),
+            Expr::Const(ConstValue::Str("month")) => format!("(DATE(YEAR({0}), MONTH({0}), 1)+{1})", date_serial(a1, sig)?, SERIAL_OFFSET),
+            Expr::Const(ConstValue::Str("week")) => format!("({}-WEEKDAY({})+1)", a()?, date_serial(a1, sig)?),
+            _ => return Err(unsupported(op.name())),
+        },
+        Op2Enum::Match(_) => format!("REGEXTEST({}, {})", a()?, b()?),
+        Op2Enum::ExtractRe(_) => format!("IFNA(REGEXEXTRACT({}, {}), \"\")", a()?, b()?),
+        // `=` on text is case-insensitive in Excel.
+        Op2Enum::EqIgnoreCase(_) => format!("({}={})", a()?, b()?),
+        Op2Enum::NumericCmp(_) => format!("IFERROR(VALUE({})<VALUE({}), FALSE)", a()?, b()?),
+        // Unlike `FIND`, `SEARCH` is case-insensitive.
+        Op2Enum::ContainsCi(_) => format!("ISNUMBER(SEARCH({1}, {0}))", a()?, b()?),
+        Op2Enum::PrefixOfCi(_) => format!("(LEFT({1}, LEN({0}))={0})", a()?, b()?),
+        op => return Err(unsupported(op.name())),
+    })
+}
+
+fn op3(op: &Op3Enum, a1: &Expr, a2: &Expr, a3: &Expr, sig: &FunSig) -> Result<String, EmitError> {
+    let [a, b, c] = [a1, a2, a3].map(|e| term(e, sig));
+    Ok(match op {
+        Op3Enum::Ite(_) => format!("IF({}, {}, {})", a?, b?, c?),
+        Op3Enum::SubStr(_) => format!("MID({}, {}, {})", a?, one_based(a2, sig)?, c?),
+        Op3Enum::IndexOf(_) => format!("IFERROR(FIND({}, {}, {})-1, -1)", b?, a?, one_based(a3, sig)?),
+        Op3Enum::Replace(_) => format!("SUBSTITUTE({}, {}, {}, 1)", a?, b?, c?),
+        Op3Enum::Between(_) => format!("AND({1}<={0}, {0}<={2})", a?, b?, c?),
+        Op3Enum::ReplaceAll(_) => format!("SUBSTITUTE({}, {}, {})", a?, b?, c?),
+        Op3Enum::ReplaceRe(_) => format!("REGEXREPLACE({}, {}, {}, 1)", a?, b?, c?),
+        Op3Enum::ReplaceCi(_) => format!("IFERROR(REPLACE({0}, SEARCH({1}, {0}), LEN({1}), {2}), {0})", a?, b?, c?),
+        Op3Enum::ReplaceNth(r) if r.1 > 0 => format!("SUBSTITUTE({}, {}, {}, {})", a?, b?, c?, r.1),
+        op => return Err(unsupported(op.name())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr, parser::problem::FunSig, value::Type};
+
+    use super::{column_name, formula};
+
+    #[test]
+    fn test_formula() {
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(25), "Z");
+        assert_eq!(column_name(26), "AA");
+        assert_eq!(column_name(27), "AB");
+
+        let sig = FunSig { name: "f".into(), args: vec![("first".into(), Type::Str), ("last".into(), Type::Str)], rettype: Type::Str };
+        let e = expr!(Concat (SubStr [0] 0 1) (Concat ". \"" [1]));
+        assert_eq!(formula(&e, &sig).unwrap(), r#"=CONCAT(MID(A2, 1, 1), ". """, B2)"#);
+        let e = expr!(Ite (PrefixOf "Dr" [0]) (IndexOf [1] " " 0) (Len [1]));
+        assert_eq!(formula(&e, &sig).unwrap(), r#"=IF(EXACT(LEFT(A2, LEN("Dr")), "Dr"), IFERROR(FIND(" ", B2, 1)-1, -1), LEN(B2))"#);
+        assert!(formula(&expr!(RetainN [0]), &sig).is_err());
+    }
+}
@@ -0,0 +1,44 @@
+use derive_more::Display;
+
+use crate::{expr::Expr, parser::{check::DefineFun, problem::FunSig}};
+
+/// Excel formula emission
+pub mod excel;
+
+/// Python code generation
+pub mod python;
+
+/// Graphviz graphs of the solution and of its decision tree
+pub mod dot;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+/// Output language of the synthesized function.
+pub enum Target {
+    /// SyGuS `define-fun` (the default)
+    Sygus,
+    /// Spreadsheet formula
+    Excel,
+    /// Standalone Python function
+    Python,
+    /// Graphviz graph of the expression, and of the decision tree it was learned from, if any
+    Dot,
+}
+
+#[derive(Debug, Clone, Display)]
+/// Errors raised when an expression cannot be translated to a target language.
+pub enum EmitError {
+    #[display(fmt = "{} is not supported by the {} emitter", _0, _1)]
+    Unsupported(String, &'static str),
+}
+
+impl std::error::Error for EmitError {}
+
+/// Renders a solution of the synthesis problem with signature `sig` in the given output language.
+pub fn render(target: Target, expr: &'static Expr, sig: &FunSig) -> Result<String, EmitError> {
+    Ok(match target {
+        Target::Sygus => DefineFun { sig: sig.clone(), expr }.to_string(),
+        Target::Excel => excel::formula(expr, sig)?,
+        Target::Python => python::function(expr, sig)?.trim_end().to_string(),
+        Target::Dot => dot::graph(expr, sig),
+    })
+}
@@ -0,0 +1,931 @@
+use std::collections::BTreeSet;
+
+use crate::{expr::{ops::{Op1Enum, Op2Enum, Op3Enum}, Expr}, parser::problem::FunSig, text::formatting::Locale, value::{ConstValue, Type}};
+
+use super::EmitError;
+
+const TARGET: &str = "Python";
+
+/// A Python function the generated code depends on, emitted once before the synthesized function.
+struct Helper {
+    name: &'static str,
+    imports: &'static [&'static str],
+    deps: &'static [&'static Helper],
+    code: &'static str,
+}
+
+static TO_INDEX: Helper = Helper { name: "_to_index", imports: &[], deps: &[], code: r#"
+def _to_index(n, i):
+    return min(i, n - 1) if i >= 0 else max(n + i, 0)
+"# };
+
+static HEAD: Helper = Helper { name: "_head", imports: &[], deps: &[&TO_INDEX], code: r#"
+def _head(s, i):
+    k = _to_index(len(s), int(i)) if len(s) > 1 else 0
+    if k == 0 or k == len(s):
+        raise ValueError("str.head")
+    return s[:k]
+"# };
+
+static TAIL: Helper = Helper { name: "_tail", imports: &[], deps: &[&TO_INDEX], code: r#"
+def _tail(s, i):
+    k = _to_index(len(s), int(i)) if len(s) > 1 else 0
+    if k == 0 or k == len(s):
+        raise ValueError("str.tail")
+    return s[k:]
+"# };
+
+static AT: Helper = Helper { name: "_at", imports: &[], deps: &[&TO_INDEX], code: r#"
+def _at(s, i):
+    if len(s) == 0:
+        raise ValueError("list.at")
+    return s[_to_index(len(s), int(i))]
+"# };
+
+static STR_AT: Helper = Helper { name: "_str_at", imports: &[], deps: &[], code: r#"
+def _str_at(s, i):
+    return s[i] if 0 <= i < len(s) else ""
+"# };
+
+static SUBSTR: Helper = Helper { name: "_substr", imports: &[], deps: &[], code: r#"
+def _substr(s, i, n):
+    return s[i:i + n] if 0 <= i < len(s) and n >= 0 else ""
+"# };
+
+static INDEXOF: Helper = Helper { name: "_indexof", imports: &[], deps: &[], code: r#"
+def _indexof(s, t, i):
+    return s.find(t, i) if 0 <= i <= len(s) else -1
+"# };
+
+static COUNT: Helper = Helper { name: "_count", imports: &[], deps: &[], code: r#"
+def _count(s, t):
+    return len(s) + 1 if t == "" else s.count(t)
+"# };
+
+static TO_INT: Helper = Helper { name: "_to_int", imports: &["re"], deps: &[], code: r#"
+def _to_int(s):
+    return int(s) if re.fullmatch(r"[+-]?[0-9]+", s) else 0
+"# };
+
+static INT_ROUND: Helper = Helper { name: "_int_round", imports: &[], deps: &[], code: r#"
+def _int_round(a, b):
+    r = abs(a) % abs(b) * (1 if a >= 0 else -1)
+    return -(-a // b) * b if r * 2 >= b else a // b * b
+"# };
+
+static FLOAT_ROUND: Helper = Helper { name: "_float_round", imports: &["math"], deps: &[], code: r#"
+def _float_round(a, b):
+    x = a / b
+    return math.copysign(math.floor(abs(x) + 0.5), x) * b
+"# };
+
+static ROUND_TO: Helper = Helper { name: "_round_to", imports: &["math"], deps: &[], code: r#"
+def _round_to(a, d):
+    s = 10.0 ** d
+    return math.copysign(math.floor(abs(a) * s + 0.5), a) / s
+"# };
+
+static NUMERIC_LT: Helper = Helper { name: "_numeric_lt", imports: &["math"], deps: &[], code: r#"
+def _numeric_lt(a, b):
+    try:
+        x, y = float(a), float(b)
+    except ValueError:
+        return False
+    return math.isfinite(x) and math.isfinite(y) and x < y
+"# };
+
+static REPLACE_RE: Helper = Helper { name: "_replace_re", imports: &["re"], deps: &[], code: r#"
+def _replace_re(s, p, t):
+    return re.sub(p, lambda _: t, s, count=1)
+"# };
+
+static REPLACE_CI: Helper = Helper { name: "_replace_ci", imports: &["re"], deps: &[], code: r#"
+def _replace_ci(s, p, t):
+    return re.sub(re.escape(p), lambda _: t, s, count=1, flags=re.IGNORECASE)
+"# };
+
+static NTH_MATCH: Helper = Helper { name: "_nth_match", imports: &["re"], deps: &[], code: r#"
+def _nth_match(s, p, n):
+    found = [m.start() for m in re.finditer(re.escape(p), s)] if p else []
+    return found[n - 1] if 0 < n <= len(found) else found[n] if -len(found) <= n < 0 else -1
+"# };
+
+static REPLACE_NTH: Helper = Helper { name: "_replace_nth", imports: &[], deps: &[&NTH_MATCH], code: r#"
+def _replace_nth(s, p, t, n):
+    i = _nth_match(s, p, n)
+    return s if i < 0 else s[:i] + t + s[i + len(p):]
+"# };
+
+static EXTRACT_RE: Helper = Helper { name: "_extract_re", imports: &["re"], deps: &[], code: r#"
+def _extract_re(s, p):
+    m = re.search(p, s)
+    return m.group(0) if m else ""
+"# };
+
+static URL_DECODE: Helper = Helper { name: "_url_decode", imports: &["re", "urllib.parse"], deps: &[], code: r#"
+def _url_decode(s):
+    if re.search(r"%(?![0-9A-Fa-f]{2})", s):
+        raise ValueError("str.url_decode")
+    return urllib.parse.unquote(s, errors="strict")
+"# };
+
+static MONTHS: Helper = Helper { name: "_MONTHS", imports: &[], deps: &[], code: r#"
+_MONTHS = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"]
+_MONTH_LITERAL = r"(?P<month>Jan(?:uary)?|Feb(?:ruary)?|Mar(?:ch)?|Apr(?:il)?|May|Jun(?:e)?|Jul(?:y)?|Aug(?:ust)?|Sep(?:tember)?|Oct(?:ober)?|(Nov|Dec)(?:ember)?)"
+"# };
+
+static PARSE_DATE: Helper = Helper { name: "_parse_date", imports: &["datetime", "re"], deps: &[&MONTHS], code: r#"
+_MONTH = r"((?P<m>\d{1,2})|" + _MONTH_LITERAL + ")"
+_DAY = r"((?P<d>\d{1,2})(st|nd|rd|th)?)"
+_YEAR = r"(?P<y>\d{2,4})"
+_DATE_REGEXES = [
+    re.compile(_MONTH + r"[\- /.,]*" + _DAY + r"?[\- /.,]*" + _YEAR + "?"),
+    re.compile(_YEAR + r"[ \-/.,]+" + _MONTH + r"[\- /.,]*" + _DAY + "?"),
+    re.compile(_DAY + r"[ \-/.,]*" + _MONTH + r"[\- /.,]*" + _YEAR + "?"),
+    re.compile(_MONTH + r"[\- /.,]+" + _YEAR + "?"),
+    re.compile(_MONTH_LITERAL),
+]
+
+def _parse_date(s, pivot=69, century=None, day_first=None):
+    found = []
+    for i, regex in enumerate(_DATE_REGEXES):
+        for m in regex.finditer(s):
+            g = m.groupdict()
+            if g.get("m") is None and g.get("month") is None:
+                continue
+            if day_first is not None and g.get("m") is not None and g.get("d") is not None and i == (0 if day_first else 2):
+                continue
+            if g.get("d") is None and g.get("y") is None:
+                continue
+            month = int(g["m"]) if g.get("m") is not None else _MONTHS.index(g["month"][:3]) + 1
+            year = int(g.get("y") or 2000)
+            if g.get("y") is not None and len(g["y"]) == 2:
+                year += century * 100 if century is not None else (2000 if year < pivot else 1900)
+            try:
+                date = datetime.date(year, month, int(g.get("d") or 1))
+            except ValueError:
+                continue
+            found.append((m.group(0), date.toordinal()))
+    found.sort(key=lambda x: -len(x[0]))
+    return found[0][1] if found else 0
+"# };
+
+static ZONES: Helper = Helper { name: "_ZONES", imports: &[], deps: &[], code: r#"
+_ZONES = {"UTC": 0, "GMT": 0, "EST": -300, "EDT": -240, "CST": -360, "CDT": -300, "MST": -420, "MDT": -360,
+          "PST": -480, "PDT": -420, "CET": 60, "CEST": 120, "BST": 60, "JST": 540}
+_ZONE_REGEX = r"(?:Z|UTC|GMT|CEST|CET|BST|JST|[ECMP][SD]T|[+-]\d{2}(?::?\d{2})?)\b"
+
+def _zone_offset(z):
+    if z == "Z" or z in _ZONES:
+        return _ZONES.get(z, 0)
+    digits = z[1:].replace(":", "")
+    h, m = int(digits[:2]), int(digits[2:4] or 0)
+    return None if h > 14 or m >= 60 else (h * 60 + m) * (-1 if z[0] == "-" else 1)
+"# };
+
+static PARSE_TIME: Helper = Helper { name: "_parse_time", imports: &["re"], deps: &[&ZONES], code: r#"
+_TIME_REGEX = re.compile(r"(?P<h>\d{1,2})(:(?P<m>\d{1,2}))?(:(?P<s>\d{1,2}))?(\s*(?P<pm>p\.?m\.?|P\.?M\.?|a\.?m\.?|A\.?M\.?))?(\s*(?P<tz>" + _ZONE_REGEX + "))?")
+
+def _parse_time(s):
+    for m in _TIME_REGEX.finditer(s):
+        if m["m"] is None and m["s"] is None and m["pm"] is None:
+            continue
+        h, mi, sec = int(m["h"]), int(m["m"] or 0), int(m["s"] or 0)
+        if m["pm"] is not None:
+            if m["pm"][0] in "pP":
+                h = h if h == 12 else h + 12
+            elif h == 12:
+                h = 0
+        if h < 24 and mi < 60 and sec < 60:
+            t = h * 3600 + mi * 60 + sec
+            tz = m["tz"] if m["tz"] is not None and (m["s"] is not None or m["tz"][0] not in "+-") else None
+            offset = _zone_offset(tz) if tz is not None else None
+            return t if offset is None else t + 86400 * (offset + 1440)
+    raise ValueError("time.parse")
+"# };
+
+static TIME_TO_UTC: Helper = Helper { name: "_time_to_utc", imports: &[], deps: &[], code: r#"
+def _time_to_utc(t):
+    return t if 0 <= t < 86400 else (t % 86400 - (t // 86400 - 1440) * 60) % 86400
+"# };
+
+static PARSE_INT: Helper = Helper { name: "_parse_int", imports: &["re"], deps: &[], code: r#"
+def _parse_int(s):
+    m = re.search(r"[-+]?\d+", s)
+    if m is None:
+        raise ValueError("int.parse")
+    return int(m.group(0))
+"# };
+
+static PARSE_FLOAT: Helper = Helper { name: "_parse_float", imports: &["re"], deps: &[], code: r#"
+def _parse_float(s):
+    for m in re.finditer(r"[-+]?[\d,]+(\.[\d,]+([eE][-+]?\d+)?)?", s):
+        try:
+            return float(m.group(0))
+        except ValueError:
+            pass
+    raise ValueError("float.parse")
+"# };
+
+static PARSE_CURRENCY: Helper = Helper { name: "_parse_currency", imports: &["re"], deps: &[], code: r#"
+def _parse_currency(s):
+    amount = r"-?[$€£¥]\s?-?\d(?:[\d,.]*\d)?|-?\d(?:[\d,.]*\d)?\s?[$€£¥]"
+    for m in re.finditer(r"\((?:%s)\)|%s" % (amount, amount), s):
+        a = m.group(0)
+        number = re.search(r"\d(?:[\d,.]*\d)?", a).group(0)
+        i = max(number.rfind(","), number.rfind("."))
+        whole, frac = number, "0"
+        if i >= 0 and (("." if number[i] == "," else ",") in number or len(number) - i - 1 != 3):
+            whole, frac = number[:i], number[i + 1:]
+        value = float(re.sub(r"\D", "", whole) + "." + frac)
+        return -value if a.startswith("(") or "-" in a else value
+    raise ValueError("currency.parse")
+"# };
+
+static EMAIL_PARTS: Helper = Helper { name: "_email_parts", imports: &["re"], deps: &[], code: r#"
+def _email_parts(s):
+    for m in re.finditer(r"([\w.%+-]*)@((?:[^\W_]|[.-])*)", s):
+        user, domain = m.group(1), m.group(2).rstrip(".-")
+        if user and "." in domain:
+            return user, domain
+    raise ValueError("str.email_user")
+"# };
+
+static URL_PARTS: Helper = Helper { name: "_url_parts", imports: &["re"], deps: &[], code: r#"
+def _url_parts(s):
+    i = s.find("://")
+    if i < 0:
+        raise ValueError("str.url_host")
+    rest = re.match(r'[^\s?#"<>]*', s[i + 3:]).group(0)
+    authority, slash, path = rest.partition("/")
+    host = authority.rpartition("@")[2].partition(":")[0]
+    if not host:
+        raise ValueError("str.url_host")
+    return host, slash + path
+"# };
+
+static PARSE_DIGITS: Helper = Helper { name: "_parse_digits", imports: &["re"], deps: &[], code: r#"
+def _parse_digits(s):
+    for m in re.finditer(r"(?:\+\d{1,3} ?)?(?:\(\d{2,5}\) ?|\d{2,5}[-. /])(?:\d{2,5}[-. /])*\d{2,5}\b", s):
+        digits = re.sub(r"\D", "", m.group(0))
+        if len(digits) >= 7:
+            return digits
+    raise ValueError("digits.parse")
+"# };
+
+static FORMAT_DIGITS: Helper = Helper { name: "_format_digits", imports: &[], deps: &[], code: r#"
+def _format_digits(s, pattern):
+    if not s.isdigit() or len(s) != pattern.count('#'):
+        return ""
+    digits = iter(s)
+    return "".join(next(digits) if c == '#' else c for c in pattern)
+"# };
+
+static ROMAN: Helper = Helper { name: "_ROMAN", imports: &[], deps: &[], code: r#"
+_ROMAN = [(1000, "M"), (900, "CM"), (500, "D"), (400, "CD"), (100, "C"), (90, "XC"),
+          (50, "L"), (40, "XL"), (10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I")]
+"# };
+
+static FORMAT_ROMAN: Helper = Helper { name: "_format_roman", imports: &[], deps: &[&ROMAN], code: r#"
+def _format_roman(n, lower):
+    if not 1 <= n <= 3999:
+        return ""
+    result = ""
+    for v, s in _ROMAN:
+        while n >= v:
+            result += s
+            n -= v
+    return result.lower() if lower else result
+"# };
+
+static PARSE_ROMAN: Helper = Helper { name: "_parse_roman", imports: &["re"], deps: &[&FORMAT_ROMAN], code: r#"
+def _parse_roman(s):
+    for m in re.finditer(r"\b[MDCLXVI]+\b|\b[mdclxvi]+\b", s):
+        word, rest, value = m.group(0).upper(), m.group(0).upper(), 0
+        for v, numeral in _ROMAN:
+            while rest.startswith(numeral):
+                rest, value = rest[len(numeral):], value + v
+        if not rest and _format_roman(value, False) == word:
+            return value
+    raise ValueError("roman.parse")
+"# };
+
+static FORMAT_ORDINAL: Helper = Helper { name: "_format_ordinal", imports: &[], deps: &[], code: r#"
+def _format_ordinal(n, upper):
+    suffix = "th" if abs(n) % 100 in (11, 12, 13) else {1: "st", 2: "nd", 3: "rd"}.get(abs(n) % 10, "th")
+    return str(n) + (suffix.upper() if upper else suffix)
+"# };
+
+static PARSE_ORDINAL: Helper = Helper { name: "_parse_ordinal", imports: &["re"], deps: &[&FORMAT_ORDINAL], code: r#"
+def _parse_ordinal(s):
+    for m in re.finditer(r"\b(\d+)(st|nd|rd|th|ST|ND|RD|TH)\b", s):
+        n = int(m.group(1))
+        if _format_ordinal(n, m.group(2).isupper()) == m.group(0):
+            return n
+    raise ValueError("ordinal.parse")
+"# };
+
+static PARSE_QUARTER: Helper = Helper { name: "_parse_quarter", imports: &["re", "datetime"], deps: &[], code: r#"
+def _parse_quarter(s):
+    m = re.search(r"\bQ([1-4])[ /-]?(\d{4})\b|\b(\d{4})[ /-]?Q([1-4])\b", s)
+    if m is None:
+        raise ValueError("quarter.parse")
+    quarter, year = int(m.group(1) or m.group(4)), int(m.group(2) or m.group(3))
+    return datetime.date(year, quarter * 3 - 2, 1).toordinal()
+"# };
+
+static FORMAT_DURATION: Helper = Helper { name: "_format_duration", imports: &[], deps: &[], code: r#"
+def _format_duration(v, units, space):
+    rest, parts = abs(v), []
+    for u, n in (("h", 3600), ("m", 60), ("s", 1)):
+        if u in units:
+            parts.append("%d%s" % (rest // n, u))
+            rest %= n
+    body = (" " if space else "").join(parts)
+    return "-" + body if v < 0 else body
+"# };
+
+static PARSE_DURATION: Helper = Helper { name: "_parse_duration", imports: &["re"], deps: &[], code: r#"
+def _parse_duration(s):
+    unit = r"(hours?|hrs?|h|minutes?|mins?|m|seconds?|secs?|s)\b"
+    part = r"\d+\s?" + unit.replace("(", "(?:", 1)
+    m = re.search(r"\b(\d+):([0-5]\d):([0-5]\d)\b|\b" + part + r"(?:,?\s*(?:and\s+)?" + part + ")*", s)
+    if m is None:
+        raise ValueError("duration.parse")
+    if m.group(1) is not None:
+        return int(m.group(1)) * 3600 + int(m.group(2)) * 60 + int(m.group(3))
+    factors = {"h": 3600, "m": 60, "s": 1}
+    return sum(int(n) * factors[u[0]] for n, u in re.findall(r"(\d+)\s?" + unit, m.group(0)))
+"# };
+
+static PARSE_ISO8601: Helper = Helper { name: "_parse_iso8601", imports: &["re", "datetime"], deps: &[], code: r#"
+def _parse_iso8601(s):
+    m = re.search(r"\b(\d{4})-(\d{2})-(\d{2})(?:([T ])(\d{2}):(\d{2})(?::(\d{2})(?:[.,](\d+))?)?(Z|[+-]\d{2}(?::?\d{2})?)?)?", s)
+    if m is None:
+        raise ValueError("iso.parse")
+    y, mo, d, _, h, mi, sec, frac, tz = m.groups()
+    days = datetime.date(int(y), int(mo), int(d)).toordinal() - 719163
+    millis = ((int(h or 0) * 60 + int(mi or 0)) * 60 + int(sec or 0)) * 1000 + int((frac or "")[:3].ljust(3, "0"))
+    if tz and tz != "Z":
+        digits = tz[1:].replace(":", "")
+        offset = int(digits[:2]) * 60 + int(digits[2:4] or 0)
+        millis -= (offset if tz[0] == "+" else -offset) * 60000
+    return days * 86400000 + millis
+"# };
+
+static FORMAT_ISO8601: Helper = Helper { name: "_format_iso8601", imports: &["datetime"], deps: &[], code: r#"
+def _format_iso8601(v, sep, seconds, frac, zone):
+    t = datetime.datetime(1970, 1, 1) + datetime.timedelta(milliseconds=v)
+    if sep is None:
+        return t.strftime("%Y-%m-%d")
+    s = t.strftime("%Y-%m-%d") + sep + t.strftime("%H:%M:%S" if seconds else "%H:%M")
+    if frac > 0:
+        s += ("." + "%03d" % (v % 1000))[:frac + 1]
+    return s + "Z" if zone else s
+"# };
+
+static SCAN: Helper = Helper { name: "_scan", imports: &["re"], deps: &[], code: r#"
+def _scan(s, regex, ty):
+    m = re.search(regex, s)
+    if m is None:
+        raise ValueError("scan")
+    return ty(m.group(1) if m.re.groups > 0 else m.group(0))
+"# };
+
+static SIZE_UNITS: Helper = Helper { name: "_size_factor", imports: &[], deps: &[], code: r#"
+_DATA_UNITS = ["B", "KB", "MB", "GB", "TB"]
+_LENGTH_UNITS = {"mm": 0.001, "cm": 0.01, "m": 1.0, "km": 1000.0, "ft": 0.3048, "yd": 0.9144, "mi": 1609.344}
+
+def _size_factor(unit, binary):
+    if unit in _LENGTH_UNITS:
+        return _LENGTH_UNITS[unit]
+    if unit == "B":
+        return 1.0
+    base = 1024.0 if binary or "i" in unit else 1000.0
+    return base ** ("KMGT".index(unit[0].upper()) + 1)
+"# };
+
+static PARSE_SIZE: Helper = Helper { name: "_parse_size", imports: &["re"], deps: &[&SIZE_UNITS], code: r#"
+def _parse_size(s, binary):
+    m = re.search(r"(\d+(?:\.\d+)?)\s?([kKMGT]i?B|B|km|cm|mm|mi|m|ft|yd)\b", s)
+    if m is None:
+        raise ValueError("size.parse")
+    return float(m.group(1)) * _size_factor(m.group(2), binary)
+"# };
+
+static FORMAT_SIZE: Helper = Helper { name: "_format_size", imports: &[], deps: &[&SIZE_UNITS], code: r#"
+def _format_size(v, unit, binary, digits, space):
+    if unit is None:
+        base = 1024.0 if binary else 1000.0
+        unit = next((u for i, u in reversed(list(enumerate(_DATA_UNITS))) if abs(v) >= base ** i), "B")
+    number = "%.*f" % (digits, v / _size_factor(unit, binary))
+    if "." in number:
+        number = number.rstrip("0").rstrip(".")
+    return number + (" " if space else "") + unit
+"# };
+
+static PARSE_MONTH: Helper = Helper { name: "_parse_month", imports: &["re"], deps: &[&MONTHS], code: r#"
+def _parse_month(s):
+    m = re.search(_MONTH_LITERAL, s)
+    if m is None:
+        raise ValueError("month.parse")
+    return _MONTHS.index(m.group(0)[:3]) + 1
+"# };
+
+static PARSE_WEEKDAY: Helper = Helper { name: "_parse_weekday", imports: &["re"], deps: &[], code: r#"
+def _parse_weekday(s):
+    m = re.search(r"Sun(day)?|Mon(day)?|Tue(sday)?|Wed(nesday)?|Thu(r|rsday)?|Fri(day)?|Sat(urday)?", s)
+    if m is None:
+        raise ValueError("weekday.parse")
+    return ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"].index(m.group(0)[:3]) + 1
+"# };
+
+static FORMAT_FLOAT: Helper = Helper { name: "_format_float", imports: &["math"], deps: &[], code: r#"
+def _format_float(v, left, right):
+    n = math.trunc(v)
+    result = format(n, "0%dd" % left) if left > 0 else str(n)
+    s = repr(float(v))
+    s = s[:-2] if s.endswith(".0") else s
+    if "." in s:
+        return result + "." + s.split(".", 1)[1].ljust(right, "0")
+    return result + "." + "0" * right if right > 0 else result
+"# };
+
+static LOCALIZE: Helper = Helper { name: "_localize", imports: &[], deps: &[], code: r#"
+def _localize(s, decimal, thousands):
+    sign = s[0] if s[:1] in ("-", "+") else ""
+    whole, dot, frac = s[len(sign):].partition(".")
+    if thousands:
+        groups = []
+        while len(whole) > 3:
+            groups.insert(0, whole[-3:])
+            whole = whole[:-3]
+        whole = thousands.join([whole] + groups)
+    return sign + whole + (decimal + frac if dot else "")
+"# };
+
+static FORMAT_SCIENTIFIC: Helper = Helper { name: "_format_scientific", imports: &[], deps: &[], code: r#"
+def _format_scientific(v, digits, upper, plus, width):
+    m, e = format(v, ".%de" % digits).split("e")
+    sign = "-" if e[0] == "-" else ("+" if plus else "")
+    return m + ("E" if upper else "e") + sign + (e[1:].lstrip("0") or "0").rjust(width, "0")
+"# };
+
+static FORMAT_CURRENCY: Helper = Helper { name: "_format_currency", imports: &[], deps: &[], code: r#"
+def _format_currency(v, symbol, suffix, space, decimal, thousands, digits, parens):
+    whole, _, frac = ("%.*f" % (digits, abs(v))).partition(".")
+    if thousands:
+        whole = format(int(whole), ",").replace(",", thousands)
+    number = whole + (decimal + frac if frac else "")
+    sp = " " if space else ""
+    body = number + sp + symbol if suffix else symbol + sp + number
+    if v < 0:
+        return "(" + body + ")" if parens else "-" + body
+    return body
+"# };
+
+static FORMAT_TIME: Helper = Helper { name: "_format_time", imports: &[], deps: &[&ZONES], code: r#"
+def _format_time(t, h, m, s, pm, tz=None, tzsp=False):
+    offset = None if 0 <= t < 86400 else t // 86400 - 1440
+    t = t % 86400
+    hour, minute, second = t // 3600, t // 60 % 60, t % 60
+    is_pm = hour >= 12
+    if pm is not None:
+        hour = 12 if hour % 12 == 0 else hour % 12
+    def number(f, n, sep):
+        return "" if f == "none" else sep + ("%02d" % n if f == "padding" else str(n))
+    result = number(h, hour, "") + number(m, minute, ":") + number(s, second, ":")
+    if pm is not None:
+        suffix = "PM" if is_pm else "AM"
+        result += suffix if pm else suffix.lower()
+    if tz is not None and offset is not None:
+        sign, hh, mm = "-" if offset < 0 else "+", abs(offset) // 60, abs(offset) % 60
+        names = [z for z, o in _ZONES.items() if o == offset]
+        if tz == "iso" and offset == 0:
+            zone = "Z"
+        elif tz == "abbrev" and names:
+            zone = names[0]
+        else:
+            zone = "%s%02d%s%02d" % (sign, hh, "" if tz == "compact" else ":", mm)
+        result += (" " if tzsp else "") + zone
+    return result
+"# };
+
+static FORMAT_MONTH: Helper = Helper { name: "_format_month", imports: &["datetime"], deps: &[], code: r#"
+def _format_month(m, abbv):
+    return datetime.date(2000, m, 1).strftime("%b" if abbv else "%B") if 1 <= m <= 12 else ""
+"# };
+
+static FORMAT_WEEKDAY: Helper = Helper { name: "_format_weekday", imports: &["datetime"], deps: &[], code: r#"
+def _format_weekday(w, abbv):
+    # 2000-01-02 is a Sunday, the first day of the week for `date.weekday`.
+    return datetime.date(2000, 1, 1 + w).strftime("%a" if abbv else "%A") if 1 <= w <= 7 else ""
+"# };
+
+static ADD_MONTHS: Helper = Helper { name: "_add_months", imports: &["calendar", "datetime"], deps: &[], code: r#"
+def _add_months(d, n):
+    d = datetime.date.fromordinal(d)
+    y, m = divmod(d.year * 12 + d.month - 1 + n, 12)
+    return datetime.date(y, m + 1, min(d.day, calendar.monthrange(y, m + 1)[1])).toordinal()
+"# };
+
+static DATE_TRUNCATE: Helper = Helper { name: "_date_truncate", imports: &["datetime"], deps: &[], code: r#"
+def _date_truncate(d, unit):
+    date = datetime.date.fromordinal(d)
+    if unit == "year":
+        return datetime.date(date.year, 1, 1).toordinal()
+    if unit == "month":
+        return datetime.date(date.year, date.month, 1).toordinal()
+    if unit == "week":
+        return d - date.isoweekday() % 7
+    raise ValueError("date.truncate")
+"# };
+
+const KEYWORDS: &[&str] = &["False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif", "else", "except",
+    "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield"];
+
+/// Turns a SyGuS symbol into a valid Python identifier.
+pub fn identifier(name: &str) -> String {
+    let mut s: String = name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect();
+    if s.is_empty() || s.starts_with(|c: char| c.is_ascii_digit()) { s.insert(0, '_'); }
+    if KEYWORDS.contains(&s.as_str()) { s.push('_'); }
+    s
+}
+
+/// Prints a Python string literal.
+pub fn string_literal(s: &str) -> String {
+    let mut result = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            c if c.is_control() => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Translates an expression into a standalone Python module defining the synthesized function.
+///
+/// The function is named after `sig` and returns the value of `e`; helpers mirroring the semantics of the operators (index clamping, date and time scanners, ...) are emitted before it when needed.
+/// Operators that fail on some input (e.g. `str.head` at an out-of-range index) raise `ValueError`.
+/// String positions follow Python's character indices, which agree with the byte offsets used by the synthesizer on ASCII strings.
+pub fn function(e: &Expr, sig: &FunSig) -> Result<String, EmitError> {
+    let mut emitter = Emitter { sig, imports: BTreeSet::new(), helpers: Vec::new() };
+    let body = emitter.term(e)?;
+    let mut result = String::new();
+    for i in emitter.imports.iter() {
+        result += &format!("import {i}\n");
+    }
+    for h in emitter.helpers.iter() {
+        result += h.code;
+    }
+    if !result.is_empty() { result += "\n"; }
+    let args = sig.args.iter().map(|(name, _)| identifier(name)).collect::<Vec<_>>().join(", ");
+    result += &format!("def {}({}):\n    return {}\n", identifier(&sig.name), args, body);
+    Ok(result)
+}
+
+struct Emitter<'a> {
+    sig: &'a FunSig,
+    imports: BTreeSet<&'static str>,
+    helpers: Vec<&'static Helper>,
+}
+
+fn unsupported(name: impl std::fmt::Display) -> EmitError {
+    EmitError::Unsupported(name.to_string(), TARGET)
+}
+
+impl<'a> Emitter<'a> {
+    fn need(&mut self, h: &'static Helper) {
+        for d in h.deps { self.need(d); }
+        if !self.helpers.iter().any(|x| x.name == h.name) {
+            self.imports.extend(h.imports.iter().copied());
+            self.helpers.push(h);
+        }
+    }
+    fn call(&mut self, h: &'static Helper, args: &[String]) -> String {
+        self.need(h);
+        format!("{}({})", h.name, args.join(", "))
+    }
+    /// Rewrites a number formatted by Python with the separators of `locale`.
+    fn localize(&mut self, s: String, locale: &Locale) -> String {
+        let thousands = locale.thousands.map(String::from).unwrap_or_default();
+        self.call(&LOCALIZE, &[s, string_literal(&locale.decimal.to_string()), string_literal(&thousands)])
+    }
+    fn literal(&self, c: &ConstValue) -> Result<String, EmitError> {
+        match c {
+            ConstValue::Bool(b) => Ok(if *b { "True" } else { "False" }.to_string()),
+            ConstValue::Int(i) if *i < 0 => Ok(format!("({})", i)),
+            ConstValue::Int(i) => Ok(i.to_string()),
+            ConstValue::Float(f) if f.is_finite() && **f < 0.0 => Ok(format!("({:?})", **f)),
+            ConstValue::Float(f) if f.is_finite() => Ok(format!("{:?}", **f)),
+            ConstValue::Float(f) => Ok(format!("float(\"{}\")", **f)),
+            ConstValue::Str(s) => Ok(string_literal(s)),
+            c => Err(unsupported(format!("Constant {:?}", c))),
+        }
+    }
+    fn term(&mut self, e: &Expr) -> Result<String, EmitError> {
+        match e {
+            Expr::Const(c) => self.literal(c),
+            Expr::Var(i) if *i < 0 => Ok(Expr::let_name(*i)),
+            Expr::Var(i) => Ok(identifier(&self.sig.args[*i as usize].0)),
+            Expr::Op1(op, a1) => self.op1(op, a1),
+            Expr::Op2(op, a1, a2) => self.op2(op, a1, a2),
+            Expr::Op3(op, a1, a2, a3) => self.op3(op, a1, a2, a3),
+            Expr::Let(v, e, body) => {
+                let e = self.term(e)?;
+                Ok(format!("(lambda {}: {})({})", Expr::let_name(*v), self.term(body)?, e))
+            }
+        }
+    }
+    fn op1(&mut self, op: &Op1Enum, a1: &Expr) -> Result<String, EmitError> {
+        let a = self.term(a1)?;
+        let retain = |a: &str, cond: &str| format!("\"\".join(c for c in {a} if {cond})");
+        Ok(match op {
+            Op1Enum::Len(_) => format!("len({a})"),
+            Op1Enum::ToInt(_) => self.call(&TO_INT, &[a]),
+            Op1Enum::ToStr(_) => format!("str({a})"),
+            Op1Enum::Neg(_) | Op1Enum::FNeg(_) => format!("(-{a})"),
+            Op1Enum::FAbs(_) | Op1Enum::Abs(_) => format!("abs({a})"),
+            Op1Enum::IsPos(_) | Op1Enum::FIsPos(_) => format!("({a} > 0)"),
+            Op1Enum::IsZero(_) | Op1Enum::FIsZero(_) => format!("({a} == 0)"),
+            Op1Enum::IsNatural(_) | Op1Enum::FNotNeg(_) => format!("({a} >= 0)"),
+            Op1Enum::RetainLl(_) => retain(&a, "c.islower()"),
+            Op1Enum::RetainLc(_) => retain(&a, "c.isupper()"),
+            Op1Enum::RetainN(_) => retain(&a, "\"0\" <= c <= \"9\""),
+            Op1Enum::RetainL(_) => retain(&a, "c.isalpha()"),
+            Op1Enum::RetainLN(_) => retain(&a, "c.isalnum()"),
+            Op1Enum::Uppercase(_) => format!("{a}.upper()"),
+            Op1Enum::Lowercase(_) => format!("{a}.lower()"),
+            Op1Enum::TitleCase(_) => format!("{a}.title()"),
+            Op1Enum::Capitalize(_) => format!("{a}.capitalize()"),
+            Op1Enum::Trim(_) => format!("{a}.strip()"),
+            Op1Enum::TrimStart(_) => format!("{a}.lstrip()"),
+            Op1Enum::TrimEnd(_) => format!("{a}.rstrip()"),
+            Op1Enum::SqueezeWs(_) => { self.imports.insert("re"); format!("re.sub(r\"\\s+\", \" \", {a})") }
+            Op1Enum::IsEmpty(_) => format!("(len({a}) == 0)"),
+            Op1Enum::UrlEncode(_) => { self.imports.insert("urllib.parse"); format!("urllib.parse.quote({a}, safe=\"\")") }
+            Op1Enum::UrlDecode(_) => self.call(&URL_DECODE, &[a]),
+            Op1Enum::EmailUser(_) => format!("{}[0]", self.call(&EMAIL_PARTS, &[a])),
+            Op1Enum::EmailDomain(_) => format!("{}[1]", self.call(&EMAIL_PARTS, &[a])),
+            Op1Enum::UrlHost(_) => format!("{}[0]", self.call(&URL_PARTS, &[a])),
+            Op1Enum::UrlPath(_) => format!("{}[1]", self.call(&URL_PARTS, &[a])),
+            Op1Enum::FExp10(_) => format!("(10.0 ** {a})"),
+            Op1Enum::FLen(_) => format!("float(len({a}))"),
+            Op1Enum::IntToFloat(_) | Op1Enum::StrToFloat(_) => format!("float({a})"),
+            Op1Enum::FloatToInt(_) => format!("int({a})"),
+            Op1Enum::ParseDate(p) => {
+                let (pivot, century) = p.pivot();
+                let day_first = match p.day_first() { Some(true) => "True", Some(false) => "False", None => "None" };
+                self.call(&PARSE_DATE, &[a, pivot.to_string(), century.map_or("None".to_string(), |c| c.to_string()), day_first.to_string()])
+            }
+            Op1Enum::ParseTime(_) => self.call(&PARSE_TIME, &[a]),
+            Op1Enum::ToUtc(_) => self.call(&TIME_TO_UTC, &[a]),
+            Op1Enum::ParseInt(_) => self.call(&PARSE_INT, &[a]),
+            Op1Enum::ParseFloat(_) => self.call(&PARSE_FLOAT, &[a]),
+            Op1Enum::ParseMonth(_) => self.call(&PARSE_MONTH, &[a]),
+            Op1Enum::ParseWeekday(_) => self.call(&PARSE_WEEKDAY, &[a]),
+            Op1Enum::ParseCurrency(_) => self.call(&PARSE_CURRENCY, &[a]),
+            Op1Enum::ParseDigits(_) => self.call(&PARSE_DIGITS, &[a]),
+            Op1Enum::ParseRoman(_) => self.call(&PARSE_ROMAN, &[a]),
+            Op1Enum::ParseOrdinal(_) => self.call(&PARSE_ORDINAL, &[a]),
+            Op1Enum::ParseSize(p) => self.call(&PARSE_SIZE, &[a, if p.binary() { "True" } else { "False" }.to_string()]),
+            Op1Enum::AsYear(_) => { self.imports.insert("datetime"); format!("datetime.date.fromordinal({a}).year") }
+            Op1Enum::AsMonth(_) => { self.imports.insert("datetime"); format!("datetime.date.fromordinal({a}).month") }
+            Op1Enum::AsDay(_) => { self.imports.insert("datetime"); format!("datetime.date.fromordinal({a}).day") }
+            Op1Enum::AsWeekDay(_) => { self.imports.insert("datetime"); format!("(datetime.date.fromordinal({a}).isoweekday() % 7 + 1)") }
+            Op1Enum::AsQuarter(_) => { self.imports.insert("datetime"); format!("((datetime.date.fromordinal({a}).month - 1) // 3 + 1)") }
+            Op1Enum::AsWeekOfYear(_) => { self.imports.insert("datetime"); format!("datetime.date.fromordinal({a}).isocalendar()[1]") }
+            Op1Enum::FormatQuarter(_) => { self.imports.insert("datetime"); format!("(lambda d: \"Q%d %d\" % ((d.month - 1) // 3 + 1, d.year))(datetime.date.fromordinal({a}))") }
+            Op1Enum::ParseQuarter(_) => self.call(&PARSE_QUARTER, &[a]),
+            Op1Enum::ParseDuration(_) => self.call(&PARSE_DURATION, &[a]),
+            Op1Enum::DurationToHms(_) => format!("(lambda t: (\"-\" if t < 0 else \"\") + \"%02d:%02d:%02d\" % (abs(t) // 3600, abs(t) // 60 % 60, abs(t) % 60))({a})"),
+            Op1Enum::ParseIso8601(_) => self.call(&PARSE_ISO8601, &[a]),
+            Op1Enum::ParseCustom(p) => {
+                let scanner = p.scanner().ok_or_else(|| unsupported(op.name()))?;
+                let ty = match scanner.ty { Type::Int => "int", Type::Float => "float", _ => "str" };
+                self.call(&SCAN, &[a, string_literal(scanner.regex.as_str()), ty.to_string()])
+            }
+            Op1Enum::FormatIso8601(f) => {
+                let (sep, seconds, frac, zone) = f.parts();
+                let flag = |b: bool| if b { "True" } else { "False" }.to_string();
+                self.call(&FORMAT_ISO8601, &[a, sep.map_or("None".to_string(), |c| string_literal(&c.to_string())), flag(seconds), frac.to_string(), flag(zone)])
+            }
+            Op1Enum::FormatDuration(f) => self.call(&FORMAT_DURATION, &[a, string_literal(&f.units()), if f.space() { "True" } else { "False" }.to_string()]),
+            Op1Enum::FormatInt(f) if !f.locale().is_plain() => {
+                let plain = if f.width() > 0 { format!("format({a}, \"0{}d\")", f.width()) } else { format!("str({a})") };
+                self.localize(plain, f.locale())
+            }
+            Op1Enum::FormatInt(f) if f.width() > 0 => format!("format({a}, \"0{}d\")", f.width()),
+            Op1Enum::FormatInt(_) => format!("str({a})"),
+            Op1Enum::FormatFloat(f) if f.exponent().is_some() => {
+                let e = f.exponent().unwrap();
+                let flag = |b: bool| if b { "True" } else { "False" }.to_string();
+                self.call(&FORMAT_SCIENTIFIC, &[a, e.digits.to_string(), flag(e.upper), flag(e.plus == Some(true)), e.width.to_string()])
+            }
+            Op1Enum::FormatFloat(f) => {
+                let (left, right) = f.padding();
+                let plain = self.call(&FORMAT_FLOAT, &[a, left.to_string(), right.to_string()]);
+                if f.locale().is_plain() { plain } else { self.localize(plain, f.locale()) }
+            }
+            Op1Enum::FormatTime(f) => {
+                let ([h, m, s], pm) = f.formats();
+                let pm = match pm { Some(true) => "True", Some(false) => "False", None => "None" };
+                let mut args = vec![a, string_literal(h), string_literal(m), string_literal(s), pm.to_string()];
+                if let Some((tz, space)) = f.zone() {
+                    args.extend([string_literal(tz), if space { "True" } else { "False" }.to_string()]);
+                }
+                self.call(&FORMAT_TIME, &args)
+            }
+            Op1Enum::FormatMonth(f) => self.call(&FORMAT_MONTH, &[a, if f.abbreviated() { "True" } else { "False" }.to_string()]),
+            Op1Enum::FormatWeekday(f) => self.call(&FORMAT_WEEKDAY, &[a, if f.abbreviated() { "True" } else { "False" }.to_string()]),
+            Op1Enum::FormatDigits(f) => self.call(&FORMAT_DIGITS, &[a, string_literal(f.pattern())]),
+            Op1Enum::FormatRoman(f) => self.call(&FORMAT_ROMAN, &[a, if f.lowercase() { "True" } else { "False" }.to_string()]),
+            Op1Enum::FormatOrdinal(f) => self.call(&FORMAT_ORDINAL, &[a, if f.uppercase() { "True" } else { "False" }.to_string()]),
+            Op1Enum::FormatSize(f) => {
+                let unit = f.unit().map_or("None".to_string(), string_literal);
+                let flag = |b: bool| if b { "True" } else { "False" }.to_string();
+                self.call(&FORMAT_SIZE, &[a, unit, flag(f.binary()), f.digits().to_string(), flag(f.space())])
+            }
+            Op1Enum::FormatCurrency(f) => {
+                let ((decimal, thousands), (symbol, suffix, space)) = (f.separators(), f.symbol());
+                let flag = |b: bool| if b { "True" } else { "False" }.to_string();
+                let thousands = thousands.map(String::from).unwrap_or_default();
+                self.call(&FORMAT_CURRENCY, &[a, string_literal(symbol), flag(suffix), flag(space), string_literal(&decimal.to_string()), string_literal(&thousands), f.digits().to_string(), flag(f.parens())])
+            }
+            Op1Enum::FormatPercent(f) => format!("(format({a} * 100, \".{}f\") + \"%\")", f.digits()),
+            op => return Err(unsupported(op.name())),
+        })
+    }
+    fn op2(&mut self, op: &Op2Enum, a1: &Expr, a2: &Expr) -> Result<String, EmitError> {
+        let (a, b) = (self.term(a1)?, self.term(a2)?);
+        Ok(match op {
+            Op2Enum::Concat(_) => format!("({a} + {b})"),
+            Op2Enum::Eq(_) => format!("({a} == {b})"),
+            Op2Enum::At(_) => self.call(&AT, &[a, b]),
+            Op2Enum::StrAt(_) => self.call(&STR_AT, &[a, b]),
+            Op2Enum::PrefixOf(_) => format!("{b}.startswith({a})"),
+            Op2Enum::SuffixOf(_) => format!("{b}.endswith({a})"),
+            Op2Enum::Contains(_) => format!("({b} in {a})"),
+            Op2Enum::Split(_) => format!("{a}.split({b})"),
+            Op2Enum::Join(_) => format!("{b}.join({a})"),
+            // As `matches("")` in Rust, the empty pattern occurs between every two characters and at both ends
+            Op2Enum::Count(_) => self.call(&COUNT, &[a, b]),
+            Op2Enum::FCount(_) => format!("float({})", self.call(&COUNT, &[a, b])),
+            Op2Enum::Add(_) | Op2Enum::FAdd(_) => format!("({a} + {b})"),
+            Op2Enum::Sub(_) | Op2Enum::FSub(_) => format!("({a} - {b})"),
+            Op2Enum::Head(_) => self.call(&HEAD, &[a, b]),
+            Op2Enum::Tail(_) => self.call(&TAIL, &[a, b]),
+            Op2Enum::Floor(_) | Op2Enum::TimeFloor(_) => format!("({a} // {b} * {b})"),
+            Op2Enum::Ceil(_) => format!("(-(-{a} // {b}) * {b})"),
+            Op2Enum::Round(_) => self.call(&INT_ROUND, &[a, b]),
+            Op2Enum::FFloor(_) => { self.imports.insert("math"); format!("(math.floor({a} / {b}) * {b})") }
+            Op2Enum::FCeil(_) => { self.imports.insert("math"); format!("(math.ceil({a} / {b}) * {b})") }
+            Op2Enum::FRound(_) => self.call(&FLOAT_ROUND, &[a, b]),
+            Op2Enum::FRoundTo(_) => self.call(&ROUND_TO, &[a, b]),
+            Op2Enum::FShl10(_) => format!("({a} * 10.0 ** {b})"),
+            Op2Enum::TimeAdd(_) => format!("(({a} + {b}) % 216000)"),
+            Op2Enum::TimeMul(_) => format!("(({a} * {b}) % 216000)"),
+            Op2Enum::DiffDays(_) => format!("({b} - {a})"),
+            Op2Enum::TimeDiff(_) => {
+                self.need(&TIME_TO_UTC);
+                format!("((_time_to_utc({b}) - _time_to_utc({a})) % 86400)")
+            }
+            Op2Enum::AddMonths(_) => self.call(&ADD_MONTHS, &[a, b]),
+            Op2Enum::DateTruncate(_) => self.call(&DATE_TRUNCATE, &[a, b]),
+            Op2Enum::Match(_) => { self.imports.insert("re"); format!("(re.search({b}, {a}) is not None)") }
+            Op2Enum::ExtractRe(_) => self.call(&EXTRACT_RE, &[a, b]),
+            Op2Enum::EqIgnoreCase(_) => format!("({a}.lower() == {b}.lower())"),
+            Op2Enum::NumericCmp(_) => self.call(&NUMERIC_LT, &[a, b]),
+            Op2Enum::ContainsCi(_) => format!("({b}.lower() in {a}.lower())"),
+            Op2Enum::PrefixOfCi(_) => format!("{b}.lower().startswith({a}.lower())"),
+            Op2Enum::Initials(_) => format!("\"\".join(w[0] + {b} for w in {a}.split())"),
+            Op2Enum::Mul(_) => format!("({a} * {b})"),
+            Op2Enum::Div(_) => format!("({a} // {b} + (1 if {b} < 0 and {a} % {b} else 0))"),
+            Op2Enum::Mod(_) => format!("({a} % abs({b}))"),
+            Op2Enum::Min(_) => format!("min({a}, {b})"),
+            Op2Enum::Max(_) => format!("max({a}, {b})"),
+            Op2Enum::Lt(_) | Op2Enum::FLt(_) => format!("({a} < {b})"),
+            Op2Enum::Le(_) | Op2Enum::FLe(_) => format!("({a} <= {b})"),
+            Op2Enum::Gt(_) | Op2Enum::FGt(_) => format!("({a} > {b})"),
+            Op2Enum::Ge(_) | Op2Enum::FGe(_) => format!("({a} >= {b})"),
+            Op2Enum::ListTake(_) => format!("{a}[:max({b}, 0)]"),
+            Op2Enum::ListDrop(_) => format!("{a}[max({b}, 0):]"),
+            op => return Err(unsupported(op.name())),
+        })
+    }
+    fn op3(&mut self, op: &Op3Enum, a1: &Expr, a2: &Expr, a3: &Expr) -> Result<String, EmitError> {
+        let (a, b, c) = (self.term(a1)?, self.term(a2)?, self.term(a3)?);
+        Ok(match op {
+            Op3Enum::Ite(_) => format!("({b} if {a} else {c})"),
+            Op3Enum::SubStr(_) => self.call(&SUBSTR, &[a, b, c]),
+            Op3Enum::IndexOf(_) => self.call(&INDEXOF, &[a, b, c]),
+            Op3Enum::Replace(_) => format!("{a}.replace({b}, {c}, 1)"),
+            Op3Enum::Between(_) => format!("({b} <= {a} <= {c})"),
+            Op3Enum::ReplaceAll(_) => format!("({a}.replace({b}, {c}) if {b} else {a})"),
+            Op3Enum::ReplaceRe(_) => self.call(&REPLACE_RE, &[a, b, c]),
+            Op3Enum::ReplaceCi(_) => self.call(&REPLACE_CI, &[a, b, c]),
+            Op3Enum::ListSlice(_) => format!("{a}[max({b}, 0):max({c}, 0)]"),
+            Op3Enum::IndexOfNth(_) => self.call(&NTH_MATCH, &[a, b, c]),
+            Op3Enum::ReplaceNth(r) => self.call(&REPLACE_NTH, &[a, b, c, r.1.to_string()]),
+            op => return Err(unsupported(op.name())),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Write, process::{Command, Stdio}};
+
+    use itertools::Itertools;
+
+    use crate::{baseline::{self, Baseline}, expr::{cfg::Cfg, context::Context, Expr}, expr, galloc::AllocForExactSizeIter, parser::{check::CheckProblem, problem::{FunSig, PBEProblem}}, value::{Type, Value}};
+
+    use super::{function, identifier, string_literal};
+
+    /// Runs the generated function on every example with `python3`, which the tests need.
+    fn run_python(code: &str, sig: &FunSig, inputs: &[Value], len: usize) -> Vec<String> {
+        let rows = (0..len).map(|i| {
+            inputs.iter().map(|v| string_literal(v.to_str()[i])).collect::<Vec<_>>().join(", ")
+        }).collect::<Vec<_>>();
+        let script = format!("{code}\nimport json\nprint(json.dumps([{}(*row) for row in [{}]]))\n",
+            identifier(&sig.name), rows.iter().map(|r| format!("({r},)")).collect::<Vec<_>>().join(", "));
+        let mut child = Command::new("python3").stdin(Stdio::piped()).stdout(Stdio::piped()).spawn().expect("python3 is needed to run the emitted code");
+        child.stdin.take().unwrap().write_all(script.as_bytes()).unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success(), "{script}");
+        serde_json::from_slice(&output.stdout).unwrap()
+    }
+
+    /// The sample benchmarks of `test/` with a solution: the reference solutions of the `.smt2` files, and those found for the `.sl` files by plain enumeration within a second.
+    fn sample_solutions() -> Vec<(FunSig, &'static Expr, Context)> {
+        let mut result = Vec::new();
+        for path in fs::read_dir("test").unwrap().map(|e| e.unwrap().path()).sorted() {
+            match path.extension().and_then(|e| e.to_str()) {
+                Some("sl") => {
+                    let problem = PBEProblem::parse(&fs::read_to_string(&path).unwrap()).unwrap();
+                    let mut cfg = Cfg::from_synthfun(problem.synthfun());
+                    cfg.config.time_limit = 1000;
+                    let ctx = Context::from_examples(&problem.examples);
+                    if let Some(e) = baseline::solve(Baseline::Enum, cfg, ctx.clone()) {
+                        result.push((problem.synthfun().sig.clone(), e, ctx));
+                    }
+                }
+                Some("smt2") => {
+                    let check = CheckProblem::parse(&fs::read_to_string(&path).unwrap()).unwrap();
+                    result.push((check.definefun.sig.clone(), check.definefun.expr, Context::from_examples(&check.examples)));
+                }
+                _ => (),
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn test_python_round_trip() {
+        let sig = FunSig { name: "full-name".into(), args: vec![("first".into(), Type::Str), ("last".into(), Type::Str)], rettype: Type::Str };
+        let first: Value = ["John", "jane", "Al"].into_iter().galloc_scollect().into();
+        let last: Value = ["Smith", "Doe", "Lee-Ray"].into_iter().galloc_scollect().into();
+        let ctx = Context::new(3, vec![first, last], vec![], Value::Null);
+        let exprs: Vec<Expr> = vec![
+            expr!(Concat (Concat (Uppercase (SubStr [0] 0 1)) ". ") [1]),
+            expr!(Ite (PrefixOf "J" [0]) (Replace [1] "-" " ") (Tail [1] 1)),
+            expr!(Concat (StrAt [1] (IndexOf [1] "e" 0)) (Head [0] (-1))),
+            expr!(Concat (ToStr (Len [1])) "\"\\"),
+            expr!(Concat (ToStr (Count [0] "")) (ToStr (Count [1] "e"))),
+        ];
+        for e in exprs {
+            let code = function(&e, &sig).unwrap();
+            assert!(code.contains("def full_name(first, last):"));
+            let result = run_python(&code, &sig, &ctx.inputs().collect::<Vec<_>>(), ctx.len);
+            assert_eq!(result, e.eval(&ctx).to_str(), "{code}");
+        }
+        assert!(function(&expr!(BvNot [0]), &sig).is_err());
+    }
+
+    #[test]
+    fn test_python_benchmarks() {
+        let solutions = sample_solutions();
+        // At least the reference solution of `test/a.smt2` and the trivial `test/common-ops.sl`
+        assert!(solutions.len() >= 2);
+        for (sig, e, ctx) in solutions {
+            let code = function(e, &sig).unwrap();
+            let result = run_python(&code, &sig, &ctx.inputs().collect_vec(), ctx.len);
+            assert_eq!(result, e.eval(&ctx).to_str(), "{code}");
+        }
+    }
+
+    #[test]
+    fn test_identifier() {
+        assert_eq!(identifier("name"), "name");
+        assert_eq!(identifier("first.name"), "first_name");
+        assert_eq!(identifier("1st"), "_1st");
+        assert_eq!(identifier("lambda"), "lambda_");
+    }
+}
@@ -0,0 +1,133 @@
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{expr::context::Context, galloc::AllocForStr, parser::{check::DefineFun, ioexamples::IOExamples}, utils::F64, value::{ConstValue, Type, Value}};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An input on which two programs give different outputs.
+pub struct Disagreement {
+    /// The arguments, as displayed by [`Value::to_display_string`]
+    pub inputs: Vec<String>,
+    pub left: String,
+    pub right: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Result of comparing two programs on a set of inputs.
+pub struct Report {
+    /// Number of inputs the programs were evaluated on
+    pub tested: usize,
+    /// The first input on which they disagree, if any
+    pub disagreement: Option<Disagreement>,
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.disagreement {
+            None => write!(f, "equivalent on {} tested inputs", self.tested),
+            Some(d) => write!(f, "differ on ({}): {} vs {}", d.inputs.join(" "), d.left, d.right),
+        }
+    }
+}
+
+/// Rows of arguments of the examples, as constants.
+fn rows(examples: &IOExamples) -> Vec<Vec<ConstValue>> {
+    (0..examples.len()).map(|i| examples.inputs.iter().map(|v| v.get_const(i)).collect()).collect()
+}
+
+/// Draws a random argument of type `ty`, in the neighbourhood of the arguments of the same column in `seen`.
+///
+/// Strings are edits of a seen string (a character removed, inserted or replaced, or a substring) or random strings over the seen characters; numbers are small or close to seen ones.
+/// Other types are only drawn among the seen arguments.
+fn random_const(rng: &mut StdRng, ty: Type, seen: &[ConstValue]) -> Option<ConstValue> {
+    let pick = |rng: &mut StdRng| seen.choose(rng).copied();
+    Some(match ty {
+        Type::Str => {
+            let strs = seen.iter().filter_map(|c| c.as_str()).collect_vec();
+            let mut alphabet = strs.iter().flat_map(|s| s.chars()).unique().collect_vec();
+            if alphabet.is_empty() { alphabet = "abcXYZ019 -,./".chars().collect(); }
+            let mut chars: Vec<char> = strs.choose(rng).map(|s| s.chars().collect()).unwrap_or_default();
+            match rng.gen_range(0..5) {
+                0 if !chars.is_empty() => { chars.remove(rng.gen_range(0..chars.len())); }
+                1 => { chars.insert(rng.gen_range(0..=chars.len()), *alphabet.choose(rng).unwrap()); }
+                2 if !chars.is_empty() => { let i = rng.gen_range(0..chars.len()); chars[i] = *alphabet.choose(rng).unwrap(); }
+                3 if !chars.is_empty() => {
+                    let i = rng.gen_range(0..chars.len());
+                    chars = chars[i..rng.gen_range(i..=chars.len())].to_vec();
+                }
+                _ => { chars = (0..rng.gen_range(0..12)).map(|_| *alphabet.choose(rng).unwrap()).collect(); }
+            }
+            ConstValue::Str(chars.into_iter().collect::<String>().galloc_str())
+        }
+        Type::Int => match pick(rng).and_then(|c| c.as_i64()) {
+            Some(i) if rng.gen_bool(0.5) => ConstValue::Int(i + rng.gen_range(-3..=3)),
+            _ => ConstValue::Int(rng.gen_range(-100..=100)),
+        },
+        Type::Float => ConstValue::Float(F64::new((rng.gen_range(-100000..=100000) as f64) / 100.0)),
+        Type::Bool => ConstValue::Bool(rng.gen_bool(0.5)),
+        Type::Char => match pick(rng).and_then(|c| c.as_char()) {
+            Some(c) if rng.gen_bool(0.5) => ConstValue::Char(c),
+            _ => ConstValue::Char(*"abcXYZ019 -,./".chars().collect_vec().choose(rng).unwrap()),
+        },
+        _ => pick(rng)?,
+    })
+}
+
+/// Evaluates `left` and `right` on the arguments of `examples` (and of the assertions of both files), then on `samples` random arguments drawn around them with `seed`, returning the first disagreement.
+pub fn check(left: &DefineFun, right: &DefineFun, examples: &[&IOExamples], samples: usize, seed: u64) -> Result<Report, String> {
+    let types = left.sig.args.iter().map(|(_, ty)| *ty).collect_vec();
+    if types != right.sig.args.iter().map(|(_, ty)| *ty).collect_vec() || left.sig.rettype != right.sig.rettype {
+        return Err(format!("the signatures differ: {} and {}", left.sig, right.sig));
+    }
+    if let Some(ty) = types.iter().find(|ty| matches!(ty, Type::ListInt | Type::ListStr)) {
+        return Err(format!("arguments of type {:?} are not supported", ty));
+    }
+    let mut inputs = examples.iter().filter(|e| e.inputs.len() == types.len()).flat_map(|e| rows(e)).collect_vec();
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _ in 0..samples {
+        let row = types.iter().enumerate().map(|(i, ty)| {
+            let seen = inputs.iter().map(|r| r[i]).collect_vec();
+            random_const(&mut rng, *ty, &seen)
+        }).collect::<Option<Vec<_>>>();
+        match row {
+            Some(row) => inputs.push(row),
+            None => break,
+        }
+    }
+    let columns = types.iter().enumerate().map(|(i, ty)| Value::from_const(*ty, inputs.iter().map(|r| r[i]).collect_vec().into_iter())).collect_vec();
+    let ctx = Context::new(inputs.len(), columns.clone(), vec![], Value::Null);
+    let (a, b) = (left.expr.eval(&ctx), right.expr.eval(&ctx));
+    let disagreement = (0..inputs.len()).find(|&i| a.to_display_string(i) != b.to_display_string(i)).map(|i| Disagreement {
+        inputs: columns.iter().map(|c| c.to_display_string(i)).collect(),
+        left: a.to_display_string(i),
+        right: b.to_display_string(i),
+    });
+    Ok(Report { tested: inputs.len(), disagreement })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::check::CheckProblem;
+
+    use super::check;
+
+    fn parse(body: &str) -> CheckProblem {
+        CheckProblem::parse(&format!("(set-logic SLIA)\n(define-fun f ((x String)) String {body})\n(assert (= (f \"ab\") \"ab.\"))\n(check-sat)")).unwrap()
+    }
+
+    #[test]
+    fn test_equiv() {
+        let a = parse(r#"(str.++ x ".")"#);
+        let b = parse(r#"(str.++ (str.++ x "") ".")"#);
+        let report = check(&a.definefun, &b.definefun, &[&a.examples], 200, 0).unwrap();
+        assert_eq!(report.tested, 201);
+        assert!(report.disagreement.is_none());
+        assert_eq!(report.to_string(), "equivalent on 201 tested inputs");
+
+        let c = parse(r#"(str.++ (str.substr x 0 2) ".")"#);
+        let report = check(&a.definefun, &c.definefun, &[&a.examples], 200, 0).unwrap();
+        let d = report.disagreement.unwrap();
+        assert_ne!(d.left, d.right);
+        assert!(d.inputs[0].len() > 4);
+    }
+}
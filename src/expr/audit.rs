@@ -0,0 +1,98 @@
+use super::{context::Context, ops::{Op1, Op2, Op3, Op3Enum}, Expr};
+use crate::value::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A subexpression of a solution which is inapplicable to the arguments of an example: its operator takes its failure path (`try_eval` gives `false`, with a default value or `Value::Null`) although its operands were evaluated.
+pub struct Failure {
+    pub expr: &'static Expr,
+    /// Index of the example
+    pub example: usize,
+    /// Whether the subexpression contributes to the output on the example, i.e. is not in a branch of an `ite` left out by its condition
+    pub live: bool,
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let place = if self.live { "on the path to the output" } else { "in a branch not taken" };
+        write!(f, "{:?} fails on example {} ({})", self.expr, self.example, place)
+    }
+}
+
+/// Re-evaluates `e` on each example of `ctx` separately, and returns the subexpressions that fail on some example.
+///
+/// Only the innermost failures are reported: an operation whose operand already failed is not. A solution with failures in branches not taken is correct on the examples only because the condition avoids them, and may fail on other inputs.
+pub fn audit(e: &'static Expr, ctx: &Context) -> Vec<Failure> {
+    let mut failures = Vec::new();
+    for i in 0..ctx.len {
+        let row = ctx.with_examples(&[i]);
+        visit(e, &row, i, true, &mut failures);
+    }
+    failures
+}
+
+/// Evaluates `e` on the single example of `ctx`, recording the failures; returns the value, and whether the evaluation failed.
+fn visit(e: &'static Expr, ctx: &Context, example: usize, live: bool, failures: &mut Vec<Failure>) -> (Value, bool) {
+    let fail = |ok: bool, failed: bool, v: Value, failures: &mut Vec<Failure>| {
+        if !ok && !failed { failures.push(Failure { expr: e, example, live }); }
+        (v, failed || !ok)
+    };
+    match e {
+        Expr::Const(_) | Expr::Var(_) => (e.eval(ctx), false),
+        Expr::Op1(op, a1) => {
+            let (v1, f1) = visit(a1, ctx, example, live, failures);
+            let (ok, v) = op.try_eval(v1);
+            fail(ok, f1, v, failures)
+        }
+        Expr::Op2(op, a1, a2) => {
+            let (v1, f1) = visit(a1, ctx, example, live, failures);
+            let (v2, f2) = visit(a2, ctx, example, live, failures);
+            let (ok, v) = op.try_eval(v1, v2);
+            fail(ok, f1 || f2, v, failures)
+        }
+        Expr::Op3(op @ Op3Enum::Ite(_), a1, a2, a3) => {
+            let (v1, f1) = visit(a1, ctx, example, live, failures);
+            let cond = matches!(v1, Value::Bool(b) if b[0]);
+            let (v2, f2) = visit(a2, ctx, example, live && !f1 && cond, failures);
+            let (v3, f3) = visit(a3, ctx, example, live && !f1 && !cond, failures);
+            let (ok, v) = op.try_eval(v1, v2, v3);
+            fail(ok, f1 || if cond { f2 } else { f3 }, v, failures)
+        }
+        Expr::Op3(op, a1, a2, a3) => {
+            let (v1, f1) = visit(a1, ctx, example, live, failures);
+            let (v2, f2) = visit(a2, ctx, example, live, failures);
+            let (v3, f3) = visit(a3, ctx, example, live, failures);
+            let (ok, v) = op.try_eval(v1, v2, v3);
+            fail(ok, f1 || f2 || f3, v, failures)
+        }
+        Expr::Let(v, a1, body) => {
+            let k = !*v as usize;
+            let (bound, f1) = visit(a1, ctx, example, live, failures);
+            let mut inner = ctx.clone();
+            if inner.n.len() <= k { inner.n.resize(k + 1, Value::Null); }
+            inner.n[k] = bound;
+            let (value, f2) = visit(body, &inner, example, live, failures);
+            (value, f1 || f2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr, expr::context::Context, galloc::{AllocForAny, AllocForExactSizeIter}};
+
+    use super::audit;
+
+    #[test]
+    fn test_audit() {
+        let inputs = ["ann@example.com", "bob"].into_iter().galloc_scollect();
+        let outputs = ["ann", "bob"].into_iter().galloc_scollect();
+        let ctx = Context::new(2, vec![inputs.into()], vec![], outputs.into());
+        assert!(audit(expr!(Concat [0] "").galloc(), &ctx).is_empty());
+        let e = expr!(Ite (Contains [0] "@") (EmailUser [0]) [0]).galloc();
+        assert_eq!(e.eval(&ctx), ctx.output);
+        let failures = audit(e, &ctx);
+        assert_eq!(failures.len(), 1);
+        assert!(!failures[0].live && failures[0].example == 1);
+        assert!(audit(expr!(EmailUser [0]).galloc(), &ctx)[0].live);
+    }
+}
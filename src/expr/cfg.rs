@@ -1,12 +1,13 @@
 use std::{collections::HashMap, cmp::min};
 
 use crate::{
-    expr::ops::{Op1Enum, Op2Enum, Op3Enum}, galloc::AllocForAny, parser::{
+    expr::ops::{Op1Enum, Op2Enum, Op3Enum}, forward::{cost_policy::CostPolicy, data::all_eq::Dedup}, galloc::AllocForAny, parser::{
         self,
         problem::{self, Error, PBEProblem, SynthFun},
         prod, config::Config,
     }, value::{ConstValue, Type}
 };
+use super::context::Context;
 use derive_more::{DebugCustom, Deref, DerefMut, From, Into, Index, IndexMut};
 use itertools::Itertools;
 use joinery::prelude::*;
@@ -73,6 +74,24 @@ impl ProdRule {
     
 }
 
+/// Turns the probability weights (`#weight:p`) of the operator rules of a non-terminal into enumeration costs. 
+/// 
+/// Weights are taken relative to the most likely rule of the non-terminal: every halving of the probability adds one to the `cost` of the rule (which defaults to `1`), so that unlikely operators are only tried at larger sizes.
+/// Rules without a weight, as well as constants and variables (which are always of size 1), are left unchanged.
+pub fn apply_weights(rules: &[prod::ProdRule]) -> Vec<prod::ProdRule> {
+    let weights = rules.iter().map(|r| r.config().get_f64("weight")).collect_vec();
+    let Some(max) = weights.iter().flatten().copied().reduce(f64::max) else { return rules.to_vec() };
+    rules.iter().zip(weights).map(|(r, w)| {
+        let mut r = r.clone();
+        if let Some(w) = w.filter(|_| matches!(r, prod::ProdRule::Op1(..) | prod::ProdRule::Op2(..) | prod::ProdRule::Op3(..))) {
+            assert!(w > 0.0, "Rule weights should be positive: {:?}", r);
+            let cost = r.config().get_usize("cost").unwrap_or(1) + (max / w).log2().round() as usize;
+            r.config_mut().set("cost", ConstValue::Int(cost as i64));
+        }
+        r
+    }).collect()
+}
+
 #[derive(DebugCustom, Clone)]
 #[debug(fmt = "({}: {:?}) -> {:?}", name, ty, rules)]
 /// A struct representing a grammar non-terminal. 
@@ -91,6 +110,12 @@ pub struct NonTerminal {
 }
 
 impl NonTerminal {
+    /// Returns the largest size of the terms enumerated for this non-terminal, given by `#max_size:N` in its configuration (unbounded by default).
+    /// 
+    /// Larger terms of this non-terminal can still be found by deduction.
+    pub fn max_size(&self) -> usize {
+        self.config.get_usize("max_size").unwrap_or(usize::MAX)
+    }
     /// Retrieves a unary operation production rule by name. 
     /// 
     /// This function iterates through the list of production rules associated with a non-terminal to find a unary operation rule (`Op1`) matching the specified operation name. 
@@ -191,15 +216,38 @@ impl NonTerminal {
 /// 
 pub struct CfgConfig {
     pub size_limit: usize,
+    /// Maximum size of the solution, enforced on deduced expressions and decision trees as well as on enumerated terms. Unlike `size_limit`, it is not lowered for the executors of sub-searches.
+    pub max_size: usize,
     pub time_limit: usize,
     pub substr_limit: usize,
     pub listsubseq_samples: usize,
     pub increase_cost_limit: usize,
+    /// How the budget of waiting deduction tasks is computed from `increase_cost_limit`.
+    pub cost_policy: CostPolicy,
     pub cond_search: bool,
     pub no_deduction: bool,
+    /// Disables the splitting of strings around a substring (the witness of `str.++` around a delimiter).
+    pub no_split: bool,
+    /// Disables the deduction of `str.join` over a list (the witness of `str.join` around a delimiter).
+    pub no_join: bool,
+    /// Disables the formatters deduced from the outputs (`#fmt` rules).
+    pub no_format_deduce: bool,
+    /// Disables the conditional concatenation of a prefix (`StrDeducer::ite_concat`).
+    pub no_ite_concat: bool,
     pub ite_limit_rate: usize,
     pub ite_limit_giveup: usize,
     pub tree_hole: bool,
+    pub no_text_objects: bool,
+    /// Maximum number of scanners whose readings of the same strings are kept, by decreasing confidence (see `TextObjData::update`).
+    pub text_objects_top: usize,
+    /// Discard enumerated strings dominated according to `forward::data::prune::SubstrDominance`.
+    pub dominance_pruning: bool,
+    /// Let the substring and prefix dispatchers match string terms up to casing, through their uppercase or lowercase conversion (`data.case_fold`).
+    pub case_fold: bool,
+    /// How the term bank identifies equal values.
+    pub dedup: Dedup,
+    /// Weight λ of the costs of expressions in the objective of tree learning (0 for entropy only).
+    pub tree_cost_weight: f32,
 }
 
 impl From<Config> for CfgConfig {
@@ -207,23 +255,36 @@ impl From<Config> for CfgConfig {
     /// 
     /// This method initializes each field of the struct with corresponding values fetched from the `Config` object, using specified keys. 
     /// If a key does not exist in the `Config`, a default value is assigned. 
-    /// For `size_limit` and `time_limit`, the size defaults to `usize::MAX`. 
+    /// For `size_limit` and `time_limit`, the size defaults to `usize::MAX`; `max_size` is also initialized from `size_limit`. 
     /// The `substr_limit` defaults to `4`, `listsubseq_samples` to `0`, `increase_cost_limit` to `2000`, `ite_limit_rate` to `1000`, and `ite_limit_giveup` to `40`. 
-    /// The boolean fields `cond_search`, `no_deduction`, and `tree_hole` are initialized as `false`. 
+    /// The cost policy is read from `cost_policy` (`fixed` by default), the deduplication mode of the term bank from `data.all_eq.dedup` (full values by default), the case-folded matching of the dispatchers from `data.case_fold` (off), and the cost weight of tree learning from `tree_learning.cost_weight` (`0`), and the number of readings of a string by different scanners kept from `text_objects.top` (`3`).
+    /// The boolean fields `cond_search`, `no_deduction`, the switches of single deduction rules, `tree_hole`, `no_text_objects`, and `dominance_pruning` are initialized as `false`. 
     /// This method is essential for transforming configuration data into a structured format used for synthesis constraints.
     /// 
     fn from(value: Config) -> Self {
         Self {
             size_limit: value.get_usize("size_limit").unwrap_or(usize::MAX),
+            max_size: value.get_usize("size_limit").unwrap_or(usize::MAX),
             time_limit: value.get_usize("time_limit").unwrap_or(usize::MAX),
             substr_limit: value.get_i64("data.substr.limit").unwrap_or(4) as usize,
             listsubseq_samples: value.get_i64("data.listsubseq.sample").unwrap_or(0) as usize,
             increase_cost_limit: value.get_i64("increase_cost_limit").unwrap_or(2000) as usize,
+            cost_policy: value.get_str("cost_policy").and_then(CostPolicy::from_name).unwrap_or_default(),
             cond_search: false,
             no_deduction: false,
+            no_split: false,
+            no_join: false,
+            no_format_deduce: false,
+            no_ite_concat: false,
             ite_limit_rate: value.get_i64("ite_limit_rate").unwrap_or(1000) as usize,
             ite_limit_giveup: value.get_i64("ite_limit_giveup").unwrap_or(40) as usize,
             tree_hole: false,
+            no_text_objects: false,
+            text_objects_top: value.get_usize("text_objects.top").unwrap_or(3),
+            dominance_pruning: false,
+            case_fold: value.get_bool("data.case_fold").unwrap_or(false),
+            dedup: value.get_str("data.all_eq.dedup").and_then(Dedup::from_name).unwrap_or_default(),
+            tree_cost_weight: value.get_f64("tree_learning.cost_weight").unwrap_or(0.0) as f32,
         }
     }
 }
@@ -262,7 +323,7 @@ impl Cfg {
     /// Constructs an instance from a provided `SynthFun` problem. 
     /// 
     /// This function extracts the configuration for constructing a context-free grammar (CFG) as represented by the `SynthFun` and populates the `Cfg` structure. 
-    /// It iterates over the non-terminal definitions within the given problem, mapping them to `NonTerminal` structures with relevant details such as name, type, production rules, and configuration. 
+    /// It iterates over the non-terminal definitions within the given problem, mapping them to `NonTerminal` structures with relevant details such as name, type, production rules (with their weights turned into costs by `apply_weights`), and configuration. 
     /// The first non-terminal is designated as the starting point. 
     /// The overall configuration for the CFG is cloned and assigned, ensuring the new `Cfg` instance accurately embodies the grammar and constraints defined in the `SynthFun` problem.
     /// 
@@ -271,12 +332,18 @@ impl Cfg {
             inner: problem.cfg.inner.iter().enumerate().map(|(i, nt)| NonTerminal {
                 name: nt.0.clone(),
                 ty: nt.1,
-                rules: nt.2.iter().map(|p| ProdRule::new(p, problem)).collect(), 
+                rules: apply_weights(&nt.2).iter().map(|p| ProdRule::new(p, problem)).collect(), 
                 config: nt.3.clone(),
             }).collect_vec(),
             config: problem.cfg.config.clone().into(),
         }
     }
+    /// Constructs a grammar made of a single non-terminal (index `0`) under the given configuration. 
+    /// 
+    /// Production rules of `nt` may only refer to non-terminal `0`; this is mainly used to exercise deducers without parsing a whole problem.
+    pub fn from_single_nt(nt: NonTerminal, config: CfgConfig) -> Self {
+        Self { inner: vec![nt], config }
+    }
     /// Find and return the index of the first `NonTerminal` in the collection with a specified type. 
     /// 
     /// The method iterates over the internal `Vec<NonTerminal>`, checking each element's type against the given `ty`. 
@@ -294,15 +361,58 @@ impl Cfg {
         new.swap(0, nstart);
         new
     }
+    /// Builds the grammar of a function nested in an operator (e.g. the `#f` of `list.foldl`), whose body is a term of `start`.
+    ///
+    /// The variables of the enclosing problem are removed; instead, the `i`-th parameter becomes variable `i` of the first non-terminal of type `params[i]`, preferring `start` itself.
+    pub fn nested(&self, start: usize, params: &[Type]) -> Self {
+        let mut new = self.change_start(start);
+        for nt in new.iter_mut() {
+            nt.rules.retain(|x| !matches!(x, ProdRule::Var(_)));
+        }
+        for (i, ty) in params.iter().enumerate() {
+            if let Some(nt) = new.find_by_type(*ty) {
+                new[nt].rules.push(ProdRule::Var(i as i64));
+            }
+        }
+        new
+    }
+    /// Adds threshold constants, mined from the numeric arguments of the examples, to the non-terminals compared by `int.lt`, `int.le`, `int.gt`, `int.ge`, their `float` versions or `int.between`.
+    ///
+    /// Splitting the examples on `x <= t` only needs `t` among the values of `x`, so the distinct arguments of the type of the non-terminal are used; at most [`MAX_THRESHOLDS`] of them are kept, evenly spread over their sorted values.
+    pub fn add_thresholds(&mut self, ctx: &Context) {
+        let compared = self.iter().flat_map(|nt| nt.rules.iter().flat_map(|r| match r {
+            ProdRule::Op2(op, n1, n2) if op.is_comparison() => vec![*n1, *n2],
+            ProdRule::Op3(Op3Enum::Between(_), n1, n2, n3) => vec![*n1, *n2, *n3],
+            _ => vec![],
+        })).unique().collect_vec();
+        for nt in compared {
+            let ty = self[nt].ty;
+            let key = |c: &ConstValue| c.as_i64().map(|i| i as f64).or(c.as_f64()).unwrap_or(f64::NAN);
+            let values = ctx.p.iter().filter(|v| v.ty() == ty && matches!(ty, Type::Int | Type::Float))
+                .flat_map(|v| (0..ctx.len).map(move |i| v.get_const(i)))
+                .sorted_by(|a, b| key(a).total_cmp(&key(b))).dedup().collect_vec();
+            let picked = if values.len() <= MAX_THRESHOLDS { values } else {
+                (0..MAX_THRESHOLDS).map(|k| values[k * (values.len() - 1) / (MAX_THRESHOLDS - 1)]).collect_vec()
+            };
+            for c in picked {
+                if !self[nt].rules.iter().any(|r| matches!(r, ProdRule::Const(d) if *d == c)) {
+                    self[nt].rules.push(ProdRule::Const(c));
+                }
+            }
+        }
+    }
 }
 
+/// Largest number of threshold constants added to a non-terminal by [`Cfg::add_thresholds`].
+pub const MAX_THRESHOLDS: usize = 16;
+
 #[cfg(test)]
 mod tests {
     use std::fs;
 
-    use crate::{parser::problem::PBEProblem, log};
+    use crate::{expr::{context::Context, ops::{Op2, Op3}}, galloc::AllocForExactSizeIter, parser::problem::PBEProblem, log, value::{ConstValue, Value}};
 
-    use super::Cfg;
+    use super::{Cfg, ProdRule};
 
     #[test]
     fn test_cfg() {
@@ -312,4 +422,59 @@ mod tests {
         let cfg = Cfg::from_synthfun(problem.synthfun());
         println!("{:?}", cfg);
     }
+
+    #[test]
+    fn test_weights() {
+        let s = r#"(set-logic SLIA)
+(synth-fun f ((name String)) String ((Start String (name
+    (str.++ Start Start #weight:0.5)
+    (str.at Start Int #weight:0.25)
+    (str.substr Start Int Int #weight:0.05 #cost:2)
+    (str.replace Start Start Start)))
+  (Int Int (0 1))))
+(check-synth)"#;
+        let problem = PBEProblem::parse(s).unwrap();
+        let cfg = Cfg::from_synthfun(problem.synthfun());
+        let costs = cfg[0].rules.iter().filter_map(|r| match r {
+            super::ProdRule::Op2(op, _, _) => Some(op.cost()),
+            super::ProdRule::Op3(op, _, _, _) => Some(op.cost()),
+            _ => None,
+        }).collect::<Vec<_>>();
+        assert_eq!(costs, vec![1, 2, 5, 1]);
+
+        for weight in ["0", "0.0", "high"] {
+            let s = format!("(set-logic SLIA)\n(synth-fun f ((name String)) String ((Start String (name (str.++ Start Start #weight:{weight})))))\n(check-synth)");
+            let err = PBEProblem::parse(&s).expect_err("a weight should be positive");
+            assert!(err.to_string().contains("Rule weights should be positive numbers"), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_max_size() {
+        let s = r#"(set-logic SLIA)
+(synth-fun f ((name String)) String ((Start String (name (str.++ Start Start) (str.at Start I)))
+  (I Int (0 1 (+ I I)) #max_size:5)))
+(check-synth)"#;
+        let problem = PBEProblem::parse(s).unwrap();
+        let cfg = Cfg::from_synthfun(problem.synthfun());
+        assert_eq!(cfg[0].max_size(), usize::MAX);
+        assert_eq!(cfg[1].max_size(), 5);
+    }
+
+    #[test]
+    fn test_thresholds() {
+        let s = r#"(set-logic SLIA)
+(synth-fun f ((x Int)) String ((Start String ("small" "big" (ite B Start Start)))
+  (B Bool ((int.le I I)))
+  (I Int (x 0))))
+(check-synth)"#;
+        let problem = PBEProblem::parse(s).unwrap();
+        let mut cfg = Cfg::from_synthfun(problem.synthfun());
+        let x: Value = [3i64, 10, 7, 3].into_iter().galloc_scollect().into();
+        let ctx = Context::new(4, vec![x], vec![], Value::Null);
+        cfg.add_thresholds(&ctx);
+        let consts = cfg[2].rules.iter().filter_map(|r| if let ProdRule::Const(c) = r { c.as_i64() } else { None }).collect::<Vec<_>>();
+        assert_eq!(consts, vec![0, 3, 7, 10]);
+        assert!(cfg[0].rules.iter().all(|r| !matches!(r, ProdRule::Const(ConstValue::Int(_)))));
+    }
 }
\ No newline at end of file
@@ -1,11 +1,12 @@
 
-use derive_more::{DebugCustom, Constructor};
+use derive_more::DebugCustom;
 use itertools::Itertools;
-use crate::{parser::{ioexamples::IOExamples, problem::PBEProblem}, tree_learning::Bits, value::Value};
+use rand::{rngs::StdRng, SeedableRng};
+use crate::{parser::{ioexamples::IOExamples, problem::PBEProblem}, tree_learning::{bits::BoxSliceExt, Bits}, value::Value};
 
 use super::Expr;
 
-#[derive(DebugCustom, Constructor, Clone)]
+#[derive(DebugCustom, Clone)]
 #[debug(fmt = "(n: {:?}, p: {:?})", n, p)]
 /// A struct that encapsulates the contextual information used during a string synthesis evaluation. 
 pub struct Context{
@@ -15,9 +16,21 @@ pub struct Context{
     /// No longer used
     pub n: Vec<Value>,
     pub output: Value,
+    /// Inputs of the negative examples
+    pub neg_inputs: Vec<Value>,
+    /// Outputs forbidden on the corresponding `neg_inputs`
+    pub neg_output: Value,
 }
 
 impl Context {
+    /// Creates a context without negative examples.
+    pub fn new(len: usize, p: Vec<Value>, n: Vec<Value>, output: Value) -> Self {
+        Self { len, p, n, output, neg_inputs: Vec::new(), neg_output: Value::Null }
+    }
+    /// Attaches negative examples to the context: `neg_output[i]` must not be produced on the `i`-th row of `neg_inputs`.
+    pub fn with_negatives(self, neg_inputs: Vec<Value>, neg_output: Value) -> Self {
+        Self { neg_inputs, neg_output, ..self }
+    }
     /// Returns the length of the context of the values.
     pub fn len(&self) -> usize { self.len }
     
@@ -43,6 +56,38 @@ impl Context {
         let v = e.eval(self);
         self.output.eq_bits(&v)
     }
+    /// Returns whether the expression produces a forbidden output on at least one negative example. 
+    /// 
+    /// The expression is evaluated on the inputs of the negative examples only, so this is cheap when there are few of them and free when there are none.
+    pub fn matches_negative(&self, e: &Expr) -> bool {
+        let len = self.neg_output.len();
+        if len == 0 { return false; }
+        let neg = Context::new(len, self.neg_inputs.clone(), Vec::new(), self.neg_output);
+        let v = e.eval(&neg);
+        neg.output.eq_bits(&v).is_some_and(|b| b.count_ones() > 0)
+    }
+    /// Formats the examples as a column-aligned table with one column per input followed by the output. 
+    /// 
+    /// The first row holds the given `header` names (one per input, then one for the output), and every following row shows one example rendered with `Value::to_display_string`.
+    pub fn format_table(&self, header: &[String]) -> String {
+        let columns = self.inputs().chain(self.outputs()).collect_vec();
+        assert_eq!(header.len(), columns.len(), "Expecting one header per input and one for the output");
+        let rows = std::iter::once(header.to_vec())
+            .chain((0..self.len).map(|i| columns.iter().map(|v| v.to_display_string(i)).collect_vec()))
+            .collect_vec();
+        let widths = (0..columns.len()).map(|j| rows.iter().map(|r| r[j].chars().count()).max().unwrap_or(0)).collect_vec();
+        let mut result = String::new();
+        for (k, row) in rows.iter().enumerate() {
+            let line = row.iter().zip(widths.iter()).map(|(cell, w)| format!("{:<w$}", cell, w = *w)).join(" | ");
+            result.push_str(line.trim_end());
+            result.push('\n');
+            if k == 0 {
+                result.push_str(&widths.iter().map(|w| "-".repeat(*w)).join("-+-"));
+                result.push('\n');
+            }
+        }
+        result
+    }
     /// Creates a new instance by filtering the existing values with provided indices. 
     pub fn with_examples(&self, exs: &[usize]) -> Context {
         Context {
@@ -50,8 +95,26 @@ impl Context {
             p: self.p.iter().map(|x| x.with_examples(exs)).collect_vec(),
             n: self.n.iter().map(|x| x.with_examples(exs)).collect_vec(),
             output: self.output.with_examples(exs),
+            neg_inputs: self.neg_inputs.clone(),
+            neg_output: self.neg_output,
         }
     }
+    /// Splits the examples into a training context and a context of `k` held-out examples, chosen at random with `seed`.
+    /// 
+    /// Both contexts keep the examples in their original order, and the negative examples go to both.
+    pub fn split_holdout(&self, k: usize, seed: u64) -> (Context, Context) {
+        let held = self.holdout_examples(k, seed);
+        (self.without_examples(&held), self.with_examples(&held))
+    }
+    /// The indices of the examples held out by [`Context::split_holdout`], in order.
+    pub fn holdout_examples(&self, k: usize, seed: u64) -> Vec<usize> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        rand::seq::index::sample(&mut rng, self.len, k.min(self.len)).into_iter().sorted().collect_vec()
+    }
+    /// Creates a new instance without the examples of the sorted indices `exs`, e.g. the training examples once examples were added after the held-out ones were chosen.
+    pub fn without_examples(&self, exs: &[usize]) -> Context {
+        self.with_examples(&(0..self.len).filter(|i| exs.binary_search(i).is_err()).collect_vec())
+    }
 }
 
 impl std::ops::Index<i64> for Context {
@@ -71,8 +134,50 @@ impl Context {
             len: examples.output.len(),
             p: examples.inputs.clone(),
             n: Vec::new(),
-            output: examples.output
+            output: examples.output,
+            neg_inputs: examples.neg_inputs.clone(),
+            neg_output: examples.neg_output,
         }
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use crate::galloc::AllocForExactSizeIter;
+    use crate::value::Value;
+
+    use super::Context;
+
+    #[test]
+    fn test_format_table() {
+        let name: Value = ["John Smith", "Al", "Bo"].into_iter().galloc_scollect().into();
+        let age: Value = [42i64, 7, 100].into_iter().galloc_scollect().into();
+        let output: Value = ["J. Smith", "A.", "B."].into_iter().galloc_scollect().into();
+        let ctx = Context::new(3, vec![name, age], vec![], output);
+        let header = ["name", "age", "f"].map(String::from);
+        assert_eq!(ctx.format_table(&header), concat!(
+            "name         | age | f\n",
+            "-------------+-----+-----------\n",
+            "\"John Smith\" | 42  | \"J. Smith\"\n",
+            "\"Al\"         | 7   | \"A.\"\n",
+            "\"Bo\"         | 100 | \"B.\"\n",
+        ));
+    }
+
+    #[test]
+    fn test_split_holdout() {
+        let input: Value = ["a", "b", "c", "d", "e"].into_iter().galloc_scollect().into();
+        let output: Value = ["A", "B", "C", "D", "E"].into_iter().galloc_scollect().into();
+        let ctx = Context::new(5, vec![input], vec![], output);
+        let (training, holdout) = ctx.split_holdout(2, 7);
+        assert_eq!((training.len, holdout.len), (3, 2));
+        let mut all = training.p[0].to_str().iter().chain(holdout.p[0].to_str().iter()).cloned().collect::<Vec<_>>();
+        all.sort();
+        assert_eq!(all, ["a", "b", "c", "d", "e"]);
+        assert_eq!(training.p[0].to_str().iter().map(|s| s.to_uppercase()).collect::<Vec<_>>(), training.output.to_str().to_vec());
+        assert_eq!(ctx.split_holdout(2, 7).1.output, holdout.output);
+        let held = ctx.holdout_examples(2, 7);
+        assert_eq!(ctx.without_examples(&held).output, training.output);
+    }
+}
@@ -1,6 +1,9 @@
+use std::collections::HashMap;
+
 use enum_dispatch::enum_dispatch;
+use serde::{Deserialize, Serialize};
 
-use crate::{debg2, galloc::AllocForAny, parser::problem::FunSig, value::{ConstValue, Value}};
+use crate::{debg2, galloc::{AllocForAny, Category}, parser::{config::Config, problem::FunSig}, value::{ConstValue, Value}};
 
 
 /// Program running context
@@ -12,9 +15,21 @@ pub mod cfg;
 /// Operators
 pub mod ops;
 
+/// Algebraic simplification of solutions (`--minimize`)
+pub mod simplify;
+
+/// Binding of repeated subexpressions with `let` (`--share`)
+pub mod share;
+
+/// Example-by-example check of solutions for operations failing on some examples (`--strict`)
+pub mod audit;
+
+/// Human-readable layout of expressions, in s-expressions or infix notation (`--pretty`)
+pub mod pretty;
+
 use derive_more::DebugCustom;
 
-use self::{context::Context, ops::{Op1, Op1Enum, Op2, Op2Enum, Op3, Op3Enum}};
+use self::{cfg::{Cfg, ProdRule}, context::Context, ops::{Op1, Op1Enum, Op2, Op2Enum, Op3, Op3Enum}};
 #[derive(DebugCustom, PartialEq, Eq, Clone, Hash)]
 /// Expressions, statically referenced.
 /// 
@@ -22,7 +37,7 @@ use self::{context::Context, ops::{Op1, Op1Enum, Op2, Op2Enum, Op3, Op3Enum}};
 /// Constants are represented by the `Const` variant, holding a `ConstValue`. 
 /// Variables are stored as a 64-bit integer with the `Var` variant. 
 /// Operations are categorized into unary, binary, and ternary, represented by `Op1`, `Op2`, and `Op3` respectively, each associated with an operation enumeration and the relevant sub-expressions. 
-/// 
+/// `Let(v, e, body)` evaluates `body` with the variable `v`, a negative index printed as `_t0`, `_t1`, ..., bound to the value of `e`.
 /// 
 /// The enum variants include formatting annotations used in debugging to display the expressions in a readable format, enhancing the ability to trace and verify expression states during execution. 
 /// These representations ensure the flexibility and power required for manipulating and evaluating expressions within the synthesis tasks.
@@ -37,9 +52,16 @@ pub enum Expr {
     Op2(&'static Op2Enum, &'static Expr, &'static Expr),
     #[debug(fmt = "({} {:?} {:?} {:?})", _0, _1, _2, _3)]
     Op3(&'static Op3Enum, &'static Expr, &'static Expr, &'static Expr),
+    #[debug(fmt = "(let <{:?}> {:?} {:?})", _0, _1, _2)]
+    Let(i64, &'static Expr, &'static Expr),
 }
 
 impl Expr {
+    #[inline(always)]
+    /// Allocates the expression in the arena, counted as [`Category::Expr`] by `--galloc-stats`; shadows [`AllocForAny::galloc`] for expressions.
+    pub fn galloc(self) -> &'static Expr {
+        self.galloc_as(Category::Expr)
+    }
     /// Evaluates the expression within a given context to produce a `Value`. 
     pub fn eval(&self, ctx: &Context) -> Value {
         
@@ -49,6 +71,13 @@ impl Expr {
             Expr::Op1(op1, a1) => op1.eval(a1.eval(ctx)),
             Expr::Op2(op2, a1, a2) => op2.eval(a1.eval(ctx), a2.eval(ctx)),
             Expr::Op3(op3, a1, a2, a3) => op3.eval(a1.eval(ctx), a2.eval(ctx), a3.eval(ctx)),
+            Expr::Let(v, e, body) => {
+                let k = !*v as usize;
+                let mut inner = ctx.clone();
+                if inner.n.len() <= k { inner.n.resize(k + 1, Value::Null); }
+                inner.n[k] = e.eval(ctx);
+                body.eval(&inner)
+            }
         }
     }
     /// Calculates the cost of an expression. 
@@ -59,9 +88,31 @@ impl Expr {
             Expr::Op1(op1, a1) => op1.cost() + a1.cost(),
             Expr::Op2(op2, a1, a2) => op2.cost() + a1.cost() + a2.cost(),
             Expr::Op3(op3, a1, a2, a3) => op3.cost() + a1.cost() + a2.cost() + a3.cost(),
+            Expr::Let(_, e, body) => e.cost() + body.cost(),
+        }
+    }
+    /// Returns the number of nodes of the expression, independently of the costs of operators.
+    pub fn size(&self) -> usize {
+        match self {
+            Expr::Const(_) | Expr::Var(_) => 1,
+            Expr::Op1(_, a1) => 1 + a1.size(),
+            Expr::Op2(_, a1, a2) => 1 + a1.size() + a2.size(),
+            Expr::Op3(_, a1, a2, a3) => 1 + a1.size() + a2.size() + a3.size(),
+            Expr::Let(_, e, body) => 1 + e.size() + body.size(),
         }
     }
-    /// Determines whether an expression contains another expression. 
+    /// Returns whether the expression refers to an input variable.
+    pub fn has_var(&self) -> bool {
+        match self {
+            Expr::Const(_) => false,
+            Expr::Var(_) => true,
+            Expr::Op1(_, a1) => a1.has_var(),
+            Expr::Op2(_, a1, a2) => a1.has_var() || a2.has_var(),
+            Expr::Op3(_, a1, a2, a3) => a1.has_var() || a2.has_var() || a3.has_var(),
+            Expr::Let(_, e, body) => e.has_var() || body.has_var(),
+        }
+    }
+    /// Determines whether an expression contains another expression.
     pub fn contains(&self, other: &Expr) -> bool {
         if self == other { true } 
         else {
@@ -71,6 +122,7 @@ impl Expr {
                 Expr::Op1(_, e1) => e1.contains(other),
                 Expr::Op2(_, e1, e2) => e1.contains(other) || e2.contains(other),
                 Expr::Op3(_, e1, e2, e3) => e1.contains(other) || e2.contains(other) || e3.contains(other),
+                Expr::Let(_, e1, e2) => e1.contains(other) || e2.contains(other),
             }
         }
     }
@@ -78,12 +130,18 @@ impl Expr {
     pub fn format(&self, sig: &FunSig) -> String {
         match self {
             Expr::Const(c) => format!("{:?}", c),
+            Expr::Var(index) if *index < 0 => Expr::let_name(*index),
             Expr::Var(index) => sig.args[*index as usize].0.clone(),
             Expr::Op1(op1, a1) => format!("({} {})", op1, a1.format(sig)),
             Expr::Op2(op2, a1, a2) => format!("({} {} {})", op2, a1.format(sig), a2.format(sig)),
             Expr::Op3(op3, a1, a2, a3) => format!("({} {} {} {})", op3, a1.format(sig), a2.format(sig), a3.format(sig)),
+            Expr::Let(v, e, body) => format!("(let (({} {})) {})", Expr::let_name(*v), e.format(sig), body.format(sig)),
         }
     }
+    /// Returns the name under which the variable `v` bound by a `let` is printed.
+    pub fn let_name(v: i64) -> String {
+        format!("_t{}", !v)
+    }
     /// Construct a ternary expression that represents an if-then-else operation within the context of the `Expr` enum. 
     pub fn ite(&'static self, t: &'static Expr, f: &'static Expr) -> &'static Expr {
         crate::expr!(Ite {self} {t} {f}).galloc()
@@ -96,6 +154,7 @@ impl Expr {
             Expr::Op1(op, a1) => Expression::Op1((*op).clone(), a1.to_expression().into()),
             Expr::Op2(op, a1, a2) => Expression::Op2((*op).clone(), a1.to_expression().into(), a2.to_expression().into()),
             Expr::Op3(op, a1, a2, a3) => Expression::Op3((*op).clone(), a1.to_expression().into(), a2.to_expression().into(), a3.to_expression().into()),
+            Expr::Let(v, e, body) => Expression::Let(*v, e.to_expression().into(), body.to_expression().into()),
         }
     }
 }
@@ -113,6 +172,8 @@ pub enum Expression {
     Op2(Op2Enum, Box<Expression>, Box<Expression>),
     #[debug(fmt = "({} {:?} {:?} {:?})", _0, _1, _2, _3)]
     Op3(Op3Enum, Box<Expression>, Box<Expression>, Box<Expression>),
+    #[debug(fmt = "(let <{:?}> {:?} {:?})", _0, _1, _2)]
+    Let(i64, Box<Expression>, Box<Expression>),
 }
 
 impl Expression {
@@ -124,7 +185,109 @@ impl Expression {
             Expression::Op1(op1, a1) => Expr::Op1(op1.galloc(), a1.alloc_local()).galloc(),
             Expression::Op2(op1, a1, a2) => Expr::Op2(op1.galloc(), a1.alloc_local(), a2.alloc_local()).galloc(),
             Expression::Op3(op1, a1, a2, a3) => Expr::Op3(op1.galloc(), a1.alloc_local(), a2.alloc_local(), a3.alloc_local()).galloc(),
+            Expression::Let(v, e, body) => Expr::Let(v, e.alloc_local(), body.alloc_local()).galloc(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename = "Expr")]
+/// Borrowed serialized form of an `Expr`, with operators written by name.
+enum ExprRef<'a> {
+    Const(&'a ConstValue),
+    Var(i64),
+    Op1(&'static str, &'a Expr),
+    Op2(&'static str, &'a Expr, &'a Expr),
+    Op3(&'static str, &'a Expr, &'a Expr, &'a Expr),
+    Let(i64, &'a Expr, &'a Expr),
+}
+
+impl Serialize for Expr {
+    /// Serializes the expression with its operators referred to by name; it is read back as an `ExprData`.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Expr::Const(c) => ExprRef::Const(c),
+            Expr::Var(v) => ExprRef::Var(*v),
+            Expr::Op1(op, a1) => ExprRef::Op1(op.name(), a1),
+            Expr::Op2(op, a1, a2) => ExprRef::Op2(op.name(), a1, a2),
+            Expr::Op3(op, a1, a2, a3) => ExprRef::Op3(op.name(), a1, a2, a3),
+            Expr::Let(v, e, body) => ExprRef::Let(*v, e, body),
+        }.serialize(serializer)
+    }
+}
+
+#[derive(DebugCustom, PartialEq, Clone, Deserialize)]
+#[serde(rename = "Expr")]
+/// Deserialized expressions, whose operators are only known by name until they are allocated with `ExprData::alloc`.
+pub enum ExprData {
+    #[debug(fmt = "{:?}", _0)]
+    Const(ConstValue),
+    #[debug(fmt = "<{:?}>", _0)]
+    Var(i64),
+    #[debug(fmt = "({} {:?})", _0, _1)]
+    Op1(String, Box<ExprData>),
+    #[debug(fmt = "({} {:?} {:?})", _0, _1, _2)]
+    Op2(String, Box<ExprData>, Box<ExprData>),
+    #[debug(fmt = "({} {:?} {:?} {:?})", _0, _1, _2, _3)]
+    Op3(String, Box<ExprData>, Box<ExprData>, Box<ExprData>),
+    #[debug(fmt = "(let <{:?}> {:?} {:?})", _0, _1, _2)]
+    Let(i64, Box<ExprData>, Box<ExprData>),
+}
+
+#[derive(Default)]
+/// Operators of a grammar indexed by name, so that deserialized expressions get the configuration (e.g. the cost) operators have in the grammar.
+pub struct OpTable {
+    op1: HashMap<&'static str, &'static Op1Enum>,
+    op2: HashMap<&'static str, &'static Op2Enum>,
+    op3: HashMap<&'static str, &'static Op3Enum>,
+}
+
+impl OpTable {
+    /// Collects the operators used by the rules of `cfg`.
+    pub fn new(cfg: &Cfg) -> Self {
+        let mut table = Self::default();
+        for rule in cfg.iter().flat_map(|nt| nt.rules.iter()) {
+            match rule {
+                ProdRule::Op1(op, _) => { table.op1.entry(op.name()).or_insert(op); }
+                ProdRule::Op2(op, _, _) => { table.op2.entry(op.name()).or_insert(op); }
+                ProdRule::Op3(op, _, _, _) => { table.op3.entry(op.name()).or_insert(op); }
+                _ => (),
+            }
         }
+        table
+    }
+}
+
+impl ExprData {
+    /// Allocates the expression. Operators missing from `ops` (e.g. those introduced by deduction) are created with their default configuration.
+    pub fn alloc(&self, ops: &OpTable) -> Result<&'static Expr, String> {
+        let unknown = |name: &str| format!("Unknown Operator {}", name);
+        Ok(match self {
+            ExprData::Const(c) => Expr::Const(*c).galloc(),
+            ExprData::Var(v) => Expr::Var(*v).galloc(),
+            ExprData::Op1(name, a1) => {
+                let op = match ops.op1.get(name.as_str()) {
+                    Some(op) => *op,
+                    None => Op1Enum::try_from_name(name, &Config::default()).ok_or_else(|| unknown(name))?.galloc(),
+                };
+                Expr::Op1(op, a1.alloc(ops)?).galloc()
+            }
+            ExprData::Op2(name, a1, a2) => {
+                let op = match ops.op2.get(name.as_str()) {
+                    Some(op) => *op,
+                    None => Op2Enum::try_from_name(name, &Config::default()).ok_or_else(|| unknown(name))?.galloc(),
+                };
+                Expr::Op2(op, a1.alloc(ops)?, a2.alloc(ops)?).galloc()
+            }
+            ExprData::Op3(name, a1, a2, a3) => {
+                let op = match ops.op3.get(name.as_str()) {
+                    Some(op) => *op,
+                    None => Op3Enum::try_from_name(name, &Config::default()).ok_or_else(|| unknown(name))?.galloc(),
+                };
+                Expr::Op3(op, a1.alloc(ops)?, a2.alloc(ops)?, a3.alloc(ops)?).galloc()
+            }
+            ExprData::Let(v, e, body) => Expr::Let(*v, e.alloc(ops)?, body.alloc(ops)?).galloc(),
+        })
     }
 }
 
@@ -170,6 +333,17 @@ mod tests {
         let e = expr!{ (Replace (Replace [0] "-" ".") "-" ".") };
         assert_eq!(e.eval(&ctx), output);
     }
+
+    #[test]
+    fn test_serialize() {
+        let e = expr!{ (Replace (Replace [0] "-" ".") "-" ".") };
+        let data: super::ExprData = serde_json::from_str(&serde_json::to_string(&e).unwrap()).unwrap();
+        assert_eq!(data.alloc(&Default::default()).unwrap(), &e);
+
+        let input = const_value!("938-242-504").value(1);
+        let value: Value = serde_json::from_str(&serde_json::to_string(&input).unwrap()).unwrap();
+        assert_eq!(value, input);
+    }
 }
 
 
@@ -1,10 +1,11 @@
 use crate::{
     expr::ops, forward::enumeration, galloc::AllocForExactSizeIter, impl_basic, new_op1,
-    value::Value,
+    parser::config::Config, value::Value,
 };
 
-fn mask(i: usize) -> u64 { 
-    (1u64 << i) - 1
+/// Returns the mask of the `i` lowest bits.
+pub fn mask(i: usize) -> u64 {
+    if i >= 64 { u64::MAX } else { (1u64 << i) - 1 }
 }
 fn to_signed(i: usize, a: u64) -> i64 {
     if a & (1u64 << (i - 1)) != 0 {
@@ -109,7 +110,7 @@ impl_bvop2!(BvAShr, "bvashr", |i1, a1: &'static [u64], i2, a2: &'static [u64]| {
 });
 
 impl_bvop2!(BvUDiv, "bvudiv", |i1, a1: &'static [u64], i2, a2: &'static [u64]| {
-    if a2.iter().any(|&x| x == 0) {
+    if a2.contains(&0) {
         return (false, Value::Null);
     }
     let i = std::cmp::max(i1, i2);
@@ -118,7 +119,7 @@ impl_bvop2!(BvUDiv, "bvudiv", |i1, a1: &'static [u64], i2, a2: &'static [u64]| {
 });
 
 impl_bvop2!(BvSDiv, "bvsdiv", |i1, a1: &'static [u64], i2, a2: &'static [u64]| {
-    if a2.iter().any(|&x| x == 0) {
+    if a2.contains(&0) {
         return (false, Value::Null);
     }
     let i = std::cmp::max(i1, i2);
@@ -127,7 +128,7 @@ impl_bvop2!(BvSDiv, "bvsdiv", |i1, a1: &'static [u64], i2, a2: &'static [u64]| {
 });
 
 impl_bvop2!(BvURem, "bvurem", |i1, a1: &'static [u64], i2, a2: &'static [u64]| {
-    if a2.iter().any(|&x| x == 0) {
+    if a2.contains(&0) {
         return (false, Value::Null);
     }
     let i = std::cmp::max(i1, i2);
@@ -136,7 +137,7 @@ impl_bvop2!(BvURem, "bvurem", |i1, a1: &'static [u64], i2, a2: &'static [u64]| {
 });
 
 impl_bvop2!(BvSRem, "bvsrem", |i1, a1: &'static [u64], i2, a2: &'static [u64]| {
-    if a2.iter().any(|&x| x == 0) {
+    if a2.contains(&0) {
         return (false, Value::Null);
     }
     let i = std::cmp::max(i1, i2);
@@ -155,4 +156,152 @@ impl_bvop2!(BvUlt, "bvult", |i1, a1: &'static [u64], i2, a2: &'static [u64]| {
     let result = a1.iter().zip(a2.iter()).map(|(x, y)| x < y).galloc_scollect();
     (true, Value::Bool(result))
 });
+impl_bvop2!(BvUle, "bvule", |_, a1: &'static [u64], _, a2: &'static [u64]| {
+    let result = a1.iter().zip(a2.iter()).map(|(x, y)| x <= y).galloc_scollect();
+    (true, Value::Bool(result))
+});
+impl_bvop2!(BvUgt, "bvugt", |_, a1: &'static [u64], _, a2: &'static [u64]| {
+    let result = a1.iter().zip(a2.iter()).map(|(x, y)| x > y).galloc_scollect();
+    (true, Value::Bool(result))
+});
+impl_bvop2!(BvUge, "bvuge", |_, a1: &'static [u64], _, a2: &'static [u64]| {
+    let result = a1.iter().zip(a2.iter()).map(|(x, y)| x >= y).galloc_scollect();
+    (true, Value::Bool(result))
+});
+impl_bvop2!(BvSle, "bvsle", |i1, a1: &'static [u64], i2, a2: &'static [u64]| {
+    let i = std::cmp::max(i1, i2);
+    let result = a1.iter().zip(a2.iter()).map(|(x, y)| to_signed(i, *x) <= to_signed(i, *y)).galloc_scollect();
+    (true, Value::Bool(result))
+});
+impl_bvop2!(BvSgt, "bvsgt", |i1, a1: &'static [u64], i2, a2: &'static [u64]| {
+    let i = std::cmp::max(i1, i2);
+    let result = a1.iter().zip(a2.iter()).map(|(x, y)| to_signed(i, *x) > to_signed(i, *y)).galloc_scollect();
+    (true, Value::Bool(result))
+});
+impl_bvop2!(BvSge, "bvsge", |i1, a1: &'static [u64], i2, a2: &'static [u64]| {
+    let i = std::cmp::max(i1, i2);
+    let result = a1.iter().zip(a2.iter()).map(|(x, y)| to_signed(i, *x) >= to_signed(i, *y)).galloc_scollect();
+    (true, Value::Bool(result))
+});
 
+// `(concat x y)` places `x` above `y`: the result is as wide as both, and must fit in 64 bits.
+impl_bvop2!(BvConcat, "concat", |i1: usize, a1: &'static [u64], i2: usize, a2: &'static [u64]| {
+    if i1 + i2 > 64 { return (false, Value::Null); }
+    let result = a1.iter().zip(a2.iter()).map(|(x, y)| if i2 >= 64 { *y } else { (x << i2) | y }).galloc_scollect();
+    (true, Value::BitVector(i1 + i2, result))
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The indexed operator `(_ extract hi lo)`, keeping bits `hi` down to `lo` of its argument.
+///
+/// The fields are the cost, `hi` and `lo`, read from the `#hi` and `#lo` entries of the configuration; a grammar written `((_ extract 7 4) x)` fills them in.
+pub struct BvExtract(pub usize, pub usize, pub usize);
+
+impl BvExtract {
+    pub fn from_config(config: &Config) -> Self {
+        Self(config.get_usize("cost").unwrap_or(1), config.get_usize("hi").unwrap_or(0), config.get_usize("lo").unwrap_or(0))
+    }
+    pub fn name() -> &'static str { "extract" }
+}
+impl std::fmt::Display for BvExtract {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(_ {} {} {})", Self::name(), self.1, self.2)
+    }
+}
+impl Default for BvExtract {
+    fn default() -> Self { Self::from_config(&Default::default()) }
+}
+impl enumeration::Enumerator1 for BvExtract {}
+impl ops::Op1 for BvExtract {
+    fn cost(&self) -> usize { self.0 }
+
+    fn try_eval(&self, a1: Value) -> (bool, Value) {
+        let (hi, lo) = (self.1, self.2);
+        match a1 {
+            Value::BitVector(i, a1) if lo <= hi && hi < i => {
+                let w = hi - lo + 1;
+                (true, Value::BitVector(w, a1.iter().map(|x| (x >> lo) & mask(w)).galloc_scollect()))
+            }
+            _ => (false, Value::Null),
+        }
+    }
+}
+
+// Defines an operator indexed by a single number, such as `(_ zero_extend 8)`: the second field, read from the `#n` entry of the configuration.
+// `$f` computes the result from the index, the width and the values of the argument.
+macro_rules! impl_bvindexed {
+    ($op:ident, $name:literal, $f:expr) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct $op(pub usize, pub usize);
+        impl $op {
+            pub fn from_config(config: &Config) -> Self {
+                Self(config.get_usize("cost").unwrap_or(1), config.get_usize("n").unwrap_or(0))
+            }
+            pub fn name() -> &'static str { $name }
+        }
+        impl std::fmt::Display for $op {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "(_ {} {})", Self::name(), self.1)
+            }
+        }
+        impl Default for $op {
+            fn default() -> Self { Self::from_config(&Default::default()) }
+        }
+        impl enumeration::Enumerator1 for $op {}
+        impl ops::Op1 for $op {
+            fn cost(&self) -> usize { self.0 }
+
+            fn try_eval(&self, a1: Value) -> (bool, Value) {
+                if let Value::BitVector(i, a1) = a1 {
+                    $f(self.1, i, a1)
+                } else {
+                    (false, Value::Null)
+                }
+            }
+        }
+    };
+}
+
+impl_bvindexed!(BvZeroExtend, "zero_extend", |n: usize, i: usize, a1: &'static [u64]| {
+    if i + n > 64 { return (false, Value::Null); }
+    (true, Value::BitVector(i + n, a1))
+});
+impl_bvindexed!(BvSignExtend, "sign_extend", |n: usize, i: usize, a1: &'static [u64]| {
+    if i + n > 64 { return (false, Value::Null); }
+    (true, Value::BitVector(i + n, a1.iter().map(|x| to_signed(i, *x) as u64 & mask(i + n)).galloc_scollect()))
+});
+impl_bvindexed!(BvRotateLeft, "rotate_left", |n: usize, i: usize, a1: &'static [u64]| {
+    let n = n % i;
+    (true, Value::BitVector(i, a1.iter().map(|x| if n == 0 { *x } else { ((x << n) | (x >> (i - n))) & mask(i) }).galloc_scollect()))
+});
+impl_bvindexed!(BvRotateRight, "rotate_right", |n: usize, i: usize, a1: &'static [u64]| {
+    let n = n % i;
+    (true, Value::BitVector(i, a1.iter().map(|x| if n == 0 { *x } else { ((x >> n) | (x << (i - n))) & mask(i) }).galloc_scollect()))
+});
+
+#[cfg(test)]
+mod tests {
+    use crate::expr::ops::{Op1, Op2};
+    use crate::parser::config::Config;
+    use crate::value::{ConstValue, Value};
+
+    use super::{BvConcat, BvExtract, BvRotateLeft, BvSignExtend, BvSle, BvZeroExtend};
+
+    #[test]
+    fn test_bv_width() {
+        let x = Value::BitVector(8, &[0xa5, 0x0f]);
+        let mut config = Config::new();
+        config.set("hi", ConstValue::Int(7));
+        config.set("lo", ConstValue::Int(4));
+        let extract = BvExtract::from_config(&config);
+        assert_eq!(extract.to_string(), "(_ extract 7 4)");
+        assert_eq!(extract.try_eval(x), (true, Value::BitVector(4, &[0xa, 0x0])));
+        assert!(!BvExtract(1, 8, 0).try_eval(x).0);
+
+        assert_eq!(BvZeroExtend(1, 8).try_eval(x), (true, Value::BitVector(16, &[0xa5, 0x0f])));
+        assert_eq!(BvSignExtend(1, 8).try_eval(x), (true, Value::BitVector(16, &[0xffa5, 0x0f])));
+        assert_eq!(BvRotateLeft(1, 4).try_eval(x), (true, Value::BitVector(8, &[0x5a, 0xf0])));
+        assert_eq!(BvConcat::default().try_eval(Value::BitVector(4, &[0xa, 0x1]), x), (true, Value::BitVector(12, &[0xaa5, 0x10f])));
+        assert_eq!(BvSle::default().try_eval(x, Value::BitVector(8, &[0x00, 0x00])), (true, Value::Bool(&[true, false])));
+    }
+}
@@ -0,0 +1,73 @@
+use crate::galloc::{AllocForExactSizeIter, AllocForStr};
+use crate::{new_op1, new_op2_opt};
+
+use super::{Op1, Op2};
+
+new_op2_opt!(CharAt, "char.at",
+    (Str, Int) -> Char { |(s1, s2)| {
+        if *s2 < 0 { return None; }
+        s1.chars().nth(*s2 as usize)
+    }}
+);
+
+new_op1!(CharIsDigit, "char.is_digit",
+    Char -> Bool { |c| c.is_ascii_digit() }
+);
+
+new_op1!(CharIsLetter, "char.is_letter",
+    Char -> Bool { |c| c.is_alphabetic() }
+);
+
+new_op1!(CharIsUpper, "char.is_upper",
+    Char -> Bool { |c| c.is_uppercase() }
+);
+
+/// Case conversions that would expand into several characters (e.g. `ß`) keep the character unchanged.
+fn single(mut it: impl Iterator<Item = char>, c: char) -> char {
+    match (it.next(), it.next()) {
+        (Some(a), None) => a,
+        _ => c,
+    }
+}
+
+new_op1!(CharToUpper, "char.to_upper",
+    Char -> Char { |c| single(c.to_uppercase(), *c) }
+);
+
+new_op1!(CharToLower, "char.to_lower",
+    Char -> Char { |c| single(c.to_lowercase(), *c) }
+);
+
+new_op1!(CharCode, "char.code",
+    Char -> Int { |c| *c as i64 }
+);
+
+new_op1!(CharToStr, "char.to_str",
+    Char -> Str { |c| c.encode_utf8(&mut [0; 4]).galloc_str() }
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::expr::ops::{Op1, Op2};
+    use crate::galloc::AllocForExactSizeIter;
+    use crate::value::Value;
+
+    use super::{CharAt, CharCode, CharIsDigit, CharToStr, CharToUpper};
+
+    #[test]
+    fn test_char_ops() {
+        let s: Value = ["a1", "ßb", "x"].into_iter().galloc_scollect().into();
+        let i: Value = [0i64, 0, 0].into_iter().galloc_scollect().into();
+        let (ok, c) = CharAt::default().try_eval(s, i);
+        assert!(ok);
+        assert_eq!(c.to_char(), &['a', 'ß', 'x']);
+        assert_eq!(CharToUpper::default().try_eval(c).1.to_char(), &['A', 'ß', 'X']);
+        assert_eq!(CharCode::default().try_eval(c).1.to_int(), &[97, 223, 120]);
+        assert_eq!(CharToStr::default().try_eval(c).1.to_str(), &["a", "ß", "x"]);
+
+        let i: Value = [1i64, 2, -1].into_iter().galloc_scollect().into();
+        let (ok, c) = CharAt::default().try_eval(s, i);
+        assert!(!ok);
+        assert_eq!(CharIsDigit::default().try_eval(c).1.to_bool(), &[true, false, false]);
+    }
+}
@@ -1,4 +1,4 @@
-use chrono::NaiveDate;
+use chrono::{Days, Months, NaiveDate};
 use chrono::Datelike;
 
 use std::cmp::min;
@@ -12,6 +12,8 @@ use crate::{new_op1, new_op2, impl_op3, impl_op3_opt, impl_op2_opt, new_op1_opt}
 use itertools::izip;
 
 
+use crate::text::zone;
+
 use super::list::to_index;
 use super::{Op1, Op3, Op2};
 
@@ -51,6 +53,26 @@ new_op1_opt!(AsWeekDay, "date.weekday",
     }}
 );
 
+new_op1_opt!(AsQuarter, "date.quarter",
+    Int -> Int { |s1| {
+        to_date(*s1).map(|date| date.month0() as i64 / 3 + 1)
+    }}
+);
+
+// ISO 8601 week number, from 1 to 53: weeks start on Monday and week 1 holds the first Thursday of the year.
+new_op1_opt!(AsWeekOfYear, "date.week",
+    Int -> Int { |s1| {
+        to_date(*s1).map(|date| date.iso_week().week() as i64)
+    }}
+);
+
+// Writes the quarter of a date with its year, e.g. `Q3 2021`, as read by `quarter.parse`.
+new_op1_opt!(FormatQuarter, "quarter.fmt",
+    Int -> Str { |s1| {
+        to_date(*s1).map(|date| format!("Q{} {}", date.month0() / 3 + 1, date.year()).galloc_str())
+    }}
+);
+
 new_op2_opt!(TimeFloor, "time.floor",
     (Int, Int) -> Int { |(s1, s2)| {
         if *s2 != 0 {
@@ -69,4 +91,109 @@ new_op2!(TimeMul, "time.*",
     (Int, Int) -> Int { |(s1, s2)| {
         (s1 * s2) % (60 * 60 * 60)
     }}
-);
\ No newline at end of file
+);
+
+/// Seconds of a time since midnight UTC, or since midnight for a time without a zone.
+fn utc_seconds(t: i64) -> i64 {
+    match zone::split(t) {
+        (secs, Some(offset)) => (secs - offset * 60).rem_euclid(86400),
+        (secs, None) => secs,
+    }
+}
+
+// Durations are integers of seconds, like times: the time from `s1` to `s2`, past midnight if `s2` is earlier (`23:30` to `00:15` is 45 minutes).
+new_op2!(TimeDiff, "time.diff",
+    (Int, Int) -> Int { |(s1, s2)| {
+        (utc_seconds(*s2) - utc_seconds(*s1)).rem_euclid(86400)
+    }}
+);
+
+// Writes a duration in seconds as `01:23:45`, with hours beyond 24 if needed (`26:00:00`).
+new_op1!(DurationToHms, "duration.to_hms",
+    Int -> Str { |s1| {
+        let (sign, secs) = (if *s1 < 0 { "-" } else { "" }, s1.unsigned_abs());
+        format!("{sign}{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60).galloc_str()
+    }}
+);
+
+/// Converts a date, represented as a number of days since the common era (January 1, 1 is day 1), to a calendar date.
+fn to_date(d: i64) -> Option<NaiveDate> {
+    NaiveDate::from_num_days_from_ce_opt(i32::try_from(d).ok()?)
+}
+
+fn from_date(d: NaiveDate) -> i64 {
+    d.num_days_from_ce() as i64
+}
+
+new_op2_opt!(DiffDays, "date.diff_days",
+    (Int, Int) -> Int { |(s1, s2)| {
+        to_date(*s1)?;
+        to_date(*s2)?;
+        Some(s2 - s1)
+    }}
+);
+
+// Adding a month to January 31 gives the last day of February, as in Excel's `EDATE`.
+new_op2_opt!(AddMonths, "date.add_months",
+    (Int, Int) -> Int { |(s1, s2)| {
+        let d = to_date(*s1)?;
+        let n = Months::new(u32::try_from(s2.unsigned_abs()).ok()?);
+        if *s2 >= 0 { d.checked_add_months(n) } else { d.checked_sub_months(n) }.map(from_date)
+    }}
+);
+
+// Truncates a date to the first day of its `"year"`, `"month"` or `"week"` (weeks start on Sunday, as for `date.weekday`).
+new_op2_opt!(DateTruncate, "date.truncate",
+    (Int, Str) -> Int { |(s1, s2)| {
+        let d = to_date(*s1)?;
+        match *s2 {
+            "year" => d.with_ordinal(1),
+            "month" => d.with_day(1),
+            "week" => d.checked_sub_days(Days::new(d.weekday().num_days_from_sunday() as u64)),
+            _ => None,
+        }.map(from_date)
+    }}
+);
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::{expr::ops::{Op1, Op2}, galloc::AllocForExactSizeIter, text::zone::zoned, value::Value};
+
+    use super::{from_date, AddMonths, AsQuarter, AsWeekOfYear, DateTruncate, DiffDays, DurationToHms, FormatQuarter, TimeDiff};
+
+    fn days(dates: [(i32, u32, u32); 3]) -> Value {
+        Value::Int(dates.into_iter().map(|(y, m, d)| from_date(NaiveDate::from_ymd_opt(y, m, d).unwrap())).galloc_scollect())
+    }
+
+    #[test]
+    fn test_date_arithmetic() {
+        let dates = days([(2024, 1, 31), (2023, 1, 31), (2024, 3, 31)]);
+        let result = AddMonths::default().try_eval(dates, Value::Int(&[1, 1, -1]));
+        assert_eq!(result, (true, days([(2024, 2, 29), (2023, 2, 28), (2024, 2, 29)])));
+
+        let later = days([(2024, 3, 1), (2024, 1, 31), (2024, 3, 31)]);
+        assert_eq!(DiffDays::default().try_eval(dates, later), (true, Value::Int(&[30, 365, 0])));
+
+        let result = DateTruncate::default().try_eval(dates, Value::Str(&["year", "month", "week"]));
+        assert_eq!(result, (true, days([(2024, 1, 1), (2023, 1, 1), (2024, 3, 31)])));
+        assert!(!DateTruncate::default().try_eval(dates, Value::Str(&["day", "day", "day"])).0);
+    }
+
+    #[test]
+    fn test_quarter_and_week() {
+        let dates = days([(2021, 8, 15), (2021, 1, 1), (2020, 12, 31)]);
+        assert_eq!(AsQuarter::default().try_eval(dates), (true, Value::Int(&[3, 1, 4])));
+        assert_eq!(AsWeekOfYear::default().try_eval(dates), (true, Value::Int(&[32, 53, 53])));
+        assert_eq!(FormatQuarter::default().try_eval(dates), (true, Value::Str(&["Q3 2021", "Q1 2021", "Q4 2020"])));
+    }
+
+    #[test]
+    fn test_durations() {
+        let start = Value::Int([3600, 84600, zoned(3600, 60)].into_iter().galloc_scollect());
+        let end = Value::Int([8625, 900, zoned(3600, 0)].into_iter().galloc_scollect());
+        assert_eq!(TimeDiff::default().try_eval(start, end), (true, Value::Int(&[5025, 2700, 3600])));
+        assert_eq!(DurationToHms::default().try_eval(Value::Int(&[5025, 93600, -60])), (true, Value::Str(&["01:23:45", "26:00:00", "-00:01:00"])));
+    }
+}
@@ -24,6 +24,19 @@ new_op2!(FSub, "float.-",
 );
 
 
+new_op2!(FLt, "float.lt",
+    (Float, Float) -> Bool { |(s1, s2)| { **s1 < **s2 }}
+);
+new_op2!(FLe, "float.le",
+    (Float, Float) -> Bool { |(s1, s2)| { **s1 <= **s2 }}
+);
+new_op2!(FGt, "float.gt",
+    (Float, Float) -> Bool { |(s1, s2)| { **s1 > **s2 }}
+);
+new_op2!(FGe, "float.ge",
+    (Float, Float) -> Bool { |(s1, s2)| { **s1 >= **s2 }}
+);
+
 new_op1!(FNeg, "float.neg",
     Float -> Float { |&s1| {
         F64::new(-*s1)
@@ -83,6 +96,17 @@ new_op2_opt!(FCeil, "float.ceil",
     }}
 );
 
+/// Most decimal places `float.round_to` rounds to, beyond which rounding is no longer exact.
+const ROUND_TO_LIMIT: i64 = 15;
+
+new_op2_opt!(FRoundTo, "float.round_to",
+    (Float, Int) -> Float { |(&s1, &s2)| {
+        if !(0..=ROUND_TO_LIMIT).contains(&s2) { return None }
+        let scale = 10.0f64.powi(s2 as i32);
+        Some(F64::new((*s1 * scale).round() / scale))
+    }}
+);
+
 new_op1!(IntToFloat, "int.to.float",
     Int -> Float { |&s1| {
         F64::new(s1 as f64)
@@ -104,7 +128,7 @@ new_op1_opt!(StrToFloat, "str.to.float",
 
 #[cfg(test)]
 mod tests {
-    use crate::{expr::{ context::Context}, value::ConstValue};
+    use crate::{expr::{ context::Context, ops::Op2}, value::ConstValue};
     use crate::expr;
 
     #[test]
@@ -113,4 +137,12 @@ mod tests {
         let result = expr!(FFloor (FNeg (FExp10 1)) (FNeg (IntToFloat 0))).eval(ctx);
         println!("{result:?}");
     }
+
+    #[test]
+    fn test_round_to() {
+        let ctx = &Context::new(1, Vec::new(), Vec::new(), ConstValue::Int(0).value(1));
+        assert_eq!(expr!(FRoundTo (StrToFloat "0.3456") 3).eval(ctx), expr!(StrToFloat "0.346").eval(ctx));
+        assert_eq!(expr!(FRoundTo (StrToFloat "-2.5") 0).eval(ctx), expr!(StrToFloat "-3").eval(ctx));
+        assert!(!super::FRoundTo::default().try_eval(ConstValue::Float(2.5.into()).value(1), ConstValue::Int(-1).value(1)).0);
+    }
 }
\ No newline at end of file
@@ -4,7 +4,7 @@ use std::ops::Not;
 
 use derive_more::DebugCustom;
 use crate::galloc::{AllocForStr, AllocForExactSizeIter, TryAllocForExactSizeIter, AllocForIter};
-use crate::{new_op1, new_op2, new_op2_opt, new_op3};
+use crate::{new_op1, new_op1_opt, new_op2, new_op2_opt, new_op3};
 use itertools::izip;
 
 
@@ -72,10 +72,54 @@ new_op2_opt!(Ceil, "int.ceil",
     }}
 );
 
+new_op2_opt!(Mul, "int.*",
+    (Int, Int) -> Int { |(s1, s2)| {
+        s1.checked_mul(*s2)
+    }}
+);
+// Euclidean division, as `div` and `mod` in SMT-LIB: the remainder is never negative
+new_op2_opt!(Div, "int.div",
+    (Int, Int) -> Int { |(s1, s2)| {
+        s1.checked_div_euclid(*s2)
+    }}
+);
+new_op2_opt!(Mod, "int.mod",
+    (Int, Int) -> Int { |(s1, s2)| {
+        s1.checked_rem_euclid(*s2)
+    }}
+);
+new_op1_opt!(Abs, "int.abs",
+    Int -> Int { |s1| {
+        s1.checked_abs()
+    }}
+);
+new_op2!(Min, "int.min",
+    (Int, Int) -> Int { |(s1, s2)| { *s1.min(s2) }}
+);
+new_op2!(Max, "int.max",
+    (Int, Int) -> Int { |(s1, s2)| { *s1.max(s2) }}
+);
+
+new_op2!(Lt, "int.lt",
+    (Int, Int) -> Bool { |(s1, s2)| { s1 < s2 }}
+);
+new_op2!(Le, "int.le",
+    (Int, Int) -> Bool { |(s1, s2)| { s1 <= s2 }}
+);
+new_op2!(Gt, "int.gt",
+    (Int, Int) -> Bool { |(s1, s2)| { s1 > s2 }}
+);
+new_op2!(Ge, "int.ge",
+    (Int, Int) -> Bool { |(s1, s2)| { s1 >= s2 }}
+);
+
+new_op3!(Between, "int.between",
+    (Int, Int, Int) -> Bool { |(s1, s2, s3)| { s2 <= s1 && s1 <= s3 }}
+);
 
 #[cfg(test)]
 mod tests {
-    use crate::{expr::{ context::Context}, value::ConstValue};
+    use crate::{expr::{ context::Context, ops::Op2}, value::ConstValue};
     use crate::expr;
 
     #[test]
@@ -84,4 +128,30 @@ mod tests {
         let result = expr!(Add (Ceil 90 10) (Neg 1)).eval(ctx);
         println!("{result:?}");
     }
+
+    #[test]
+    fn test_arithmetic() {
+        let ctx = &Context::new(1, Vec::new(), Vec::new(), ConstValue::Int(0).value(1));
+        let int = |i: i64| ConstValue::Int(i).value(1);
+        assert_eq!(expr!(Mul 6 (Neg 7)).eval(ctx), int(-42));
+        assert_eq!(expr!(Div (Neg 7) 2).eval(ctx), int(-4));
+        assert_eq!(expr!(Mod (Neg 7) 2).eval(ctx), int(1));
+        assert_eq!(expr!(Abs (Neg 7)).eval(ctx), int(7));
+        assert_eq!(expr!(Min 3 (Max 5 2)).eval(ctx), int(3));
+        assert!(!super::Div::default().try_eval(int(7), int(0)).0);
+        assert!(!super::Mod::default().try_eval(int(7), int(0)).0);
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let ctx = &Context::new(1, Vec::new(), Vec::new(), ConstValue::Int(0).value(1));
+        let t = ConstValue::Bool(true).value(1);
+        let f = ConstValue::Bool(false).value(1);
+        assert_eq!(expr!(Lt 3 3).eval(ctx), f);
+        assert_eq!(expr!(Le 3 3).eval(ctx), t);
+        assert_eq!(expr!(Gt 4 (Neg 5)).eval(ctx), t);
+        assert_eq!(expr!(Ge 2 3).eval(ctx), f);
+        assert_eq!(expr!(Between 5 1 5).eval(ctx), t);
+        assert_eq!(expr!(Between 6 1 5).eval(ctx), f);
+    }
 }
\ No newline at end of file
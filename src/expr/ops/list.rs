@@ -4,11 +4,12 @@ use std::ops::Not;
 use derive_more::DebugCustom;
 use crate::galloc::{AllocForStr, AllocForExactSizeIter, TryAllocForExactSizeIter};
 use crate::utils::F64;
-use crate::{new_op1, new_op2, new_op3, new_op2_opt};
-use itertools::izip;
+use crate::{new_op1, new_op1_opt, new_op2, new_op3, new_op2_opt};
+use itertools::{izip, Itertools};
 
 
 
+use super::str::StrIndexing;
 use super::{Op1, Op3, Op2};
 
 /// Converts an integer to a valid index within a bounded length. 
@@ -28,8 +29,9 @@ pub fn to_index(len: usize, i: i64) -> usize {
 new_op2_opt!(At, "list.at",
     (Str, Int) -> Str { |(s1, s2)| {
         if !s1.is_empty() {
-            let i = to_index(s1.len(), *s2);
-            Some(s1[i..=i].galloc_str())
+            let ix = StrIndexing::current();
+            let i = to_index(ix.len(s1), *s2);
+            Some(ix.slice(s1, i, i + 1).galloc_str())
         } else { None }
     }},
     (ListInt, Int) -> Int { |(s1, s2)| {
@@ -46,8 +48,9 @@ new_op2_opt!(At, "list.at",
     }},
     (Str, Float) -> Str { |(s1, s2)| {
         if !s1.is_empty() {
-            let i = to_index(s1.len(), **s2 as i64);
-            Some(s1[i..=i].galloc_str())
+            let ix = StrIndexing::current();
+            let i = to_index(ix.len(s1), **s2 as i64);
+            Some(ix.slice(s1, i, i + 1).galloc_str())
         } else { None }
     }},
     (ListInt, Float) -> Int { |(s1, s2)| {
@@ -66,29 +69,115 @@ new_op2_opt!(At, "list.at",
 
 new_op2!(StrAt, "str.at",
     (Str, Int) -> Str { |(s1, s2)| {
-        if !s1.is_empty() {
-            if *s2 >= 0 && (*s2 as usize) < s1.len() {
-                let i = *s2 as usize;
-                s1[i..=i].galloc_str()
-            } else { "" }
-        } else { "" }
+        StrIndexing::current().at(s1, *s2).galloc_str()
     }}
 );
 
 new_op1!(Len, "list.len", 
-    Str -> Int { |s| s.len() as i64 },
+    Str -> Int { |s| StrIndexing::current().len(s) as i64 },
     ListInt -> Int { |s| s.len() as i64 },
     ListStr -> Int { |s| s.len() as i64 }
 );
 
 new_op1!(FLen, "list.flen", 
-    Str -> Float { |s| F64::from_usize(s.len()) },
+    Str -> Float { |s| F64::from_usize(StrIndexing::current().len(s)) },
     ListInt -> Float { |s| F64::from_usize(s.len()) },
     ListStr -> Float { |s| F64::from_usize(s.len()) }
 );
 
+new_op1!(ListSum, "list.sum",
+    ListInt -> Int { |s| s.iter().fold(0i64, |a, b| a.wrapping_add(*b)) }
+);
+
+new_op1_opt!(ListMax, "list.max",
+    ListInt -> Int { |s| s.iter().max().copied() }
+);
+
+new_op1_opt!(ListMin, "list.min",
+    ListInt -> Int { |s| s.iter().min().copied() }
+);
+
+new_op1!(ListSort, "list.sort",
+    ListInt -> ListInt { |s| s.iter().copied().sorted().collect_vec().into_iter().galloc_scollect() },
+    ListStr -> ListStr { |s| s.iter().copied().sorted().collect_vec().into_iter().galloc_scollect() }
+);
+
+new_op1!(ListRev, "list.rev",
+    ListInt -> ListInt { |s| s.iter().rev().copied().galloc_scollect() },
+    ListStr -> ListStr { |s| s.iter().rev().copied().galloc_scollect() }
+);
+
+/// Clamps an index of a list operation (`list.take`, `list.drop`, `list.slice`) to `0..=len`.
+fn clamp_index(len: usize, i: i64) -> usize {
+    min(i.max(0) as usize, len)
+}
+
+new_op2!(ListTake, "list.take",
+    (ListStr, Int) -> ListStr { |(s1, s2)| s1[..clamp_index(s1.len(), *s2)].iter().copied().galloc_scollect() },
+    (ListInt, Int) -> ListInt { |(s1, s2)| s1[..clamp_index(s1.len(), *s2)].iter().copied().galloc_scollect() }
+);
+
+new_op2!(ListDrop, "list.drop",
+    (ListStr, Int) -> ListStr { |(s1, s2)| s1[clamp_index(s1.len(), *s2)..].iter().copied().galloc_scollect() },
+    (ListInt, Int) -> ListInt { |(s1, s2)| s1[clamp_index(s1.len(), *s2)..].iter().copied().galloc_scollect() }
+);
+
+new_op3!(ListSlice, "list.slice",
+    (ListStr, Int, Int) -> ListStr { |(s1, s2, s3)| {
+        let (i, j) = (clamp_index(s1.len(), *s2), clamp_index(s1.len(), *s3));
+        s1[i..j.max(i)].iter().copied().galloc_scollect()
+    }},
+    (ListInt, Int, Int) -> ListInt { |(s1, s2, s3)| {
+        let (i, j) = (clamp_index(s1.len(), *s2), clamp_index(s1.len(), *s3));
+        s1[i..j.max(i)].iter().copied().galloc_scollect()
+    }}
+);
+
 pub mod map;
 pub use map::Map;
 
 pub mod filter;
-pub use filter::Filter;
\ No newline at end of file
+pub use filter::Filter;
+
+pub mod fold;
+pub use fold::FoldL;
+
+pub mod zip;
+pub use zip::ZipWith;
+
+#[cfg(test)]
+mod tests {
+    use crate::expr::ops::Op1;
+    use crate::galloc::AllocForExactSizeIter;
+    use crate::value::Value;
+
+    use super::{ListDrop, ListMax, ListMin, ListRev, ListSlice, ListSort, ListSum, ListTake};
+
+    #[test]
+    fn test_list_int_ops() {
+        let l: Value = [&[3i64, -1, 2][..], &[], &[5]].into_iter().galloc_scollect().into();
+        assert_eq!(ListSum::default().try_eval(l).1.to_int(), &[4, 0, 5]);
+        let (ok, max) = ListMax::default().try_eval(l);
+        assert!(!ok);
+        assert_eq!(max.to_int(), &[3, 0, 5]);
+        assert_eq!(ListMin::default().try_eval(l).1.to_int(), &[-1, 0, 5]);
+
+        let sorted: &[&[i64]] = ListSort::default().try_eval(l).1.try_into().unwrap();
+        assert_eq!(sorted, &[&[-1i64, 2, 3][..], &[], &[5]]);
+        let reversed: &[&[i64]] = ListRev::default().try_eval(l).1.try_into().unwrap();
+        assert_eq!(reversed[0], &[2, -1, 3]);
+    }
+
+    #[test]
+    fn test_list_slices() {
+        use crate::expr::ops::{Op2, Op3};
+        let l: Value = [&["a", "b", "c"][..], &["d"]].into_iter().galloc_scollect().into();
+        let n: Value = [2i64, 2].into_iter().galloc_scollect().into();
+        assert_eq!(ListTake::default().try_eval(l, n).1.to_liststr(), &[&["a", "b"][..], &["d"]]);
+        assert_eq!(ListDrop::default().try_eval(l, n).1.to_liststr(), &[&["c"][..], &[]]);
+        let i: Value = [1i64, -1].into_iter().galloc_scollect().into();
+        let j: Value = [-1i64, 5].into_iter().galloc_scollect().into();
+        assert_eq!(ListSlice::default().try_eval(l, i, j).1.to_liststr(), &[&[][..], &["d"]]);
+        assert_eq!(ListSlice::default().try_eval(l, i, n).1.to_liststr(), &[&["b"][..], &["d"]]);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::expr::context::Context;
+use crate::expr::Expr;
+use crate::galloc::AllocForExactSizeIter;
+use crate::parser::config::Config;
+use crate::value::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Left fold over a list: `(list.foldl l init)` starts from `init` and replaces the accumulator by `f(acc, x)` for each element `x` of `l`.
+///
+/// Like `list.map`, the function `f` is a nested expression held by the operator (`#f`), in which variable `0` is the accumulator and variable `1` the element.
+/// It is never enumerated: it is only built by the fold deduction of `StrDeducer` (see `FoldDeducer`), or given explicitly in the grammar.
+pub struct FoldL(pub Option<&'static Expr>);
+
+impl std::hash::Hash for FoldL {
+    /// Hashes the nested function by address, as `Map` does.
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.map(|x| x as *const Expr).hash(state);
+    }
+}
+
+impl FoldL {
+    /// Creates a `FoldL` whose function is the expression `#f` of the configuration, if any.
+    pub fn from_config(config: &Config) -> Self {
+        Self(config.get_expr("f"))
+    }
+    /// Returns the name of the operator, `list.foldl`.
+    pub fn name() -> &'static str {
+        "list.foldl"
+    }
+}
+
+impl std::fmt::Display for FoldL {
+    /// Formats the operator together with its nested function, if any.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(e) = self.0 {
+            write!(f, "list.foldl #f:{:?}", e)
+        } else {
+            write!(f, "list.foldl")
+        }
+    }
+}
+
+impl Default for FoldL {
+    /// Creates a `FoldL` without nested function.
+    fn default() -> Self {
+        Self::from_config(&Default::default())
+    }
+}
+
+impl crate::forward::enumeration::Enumerator2 for FoldL {
+    /// Folds are only produced by deduction, so nothing is enumerated.
+    fn enumerate(&self, this: &'static crate::expr::ops::Op2Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 2]) -> Result<(), ()> { Ok(()) }
+}
+
+/// Folds every list of `lists` from the corresponding value of `init`.
+///
+/// All examples advance by one element at each step, so `f` is evaluated once per step on the examples whose list is not exhausted yet.
+/// Returns `None` if `f` does not produce a value of the accumulator's type.
+fn foldl<T: Copy + 'static>(f: &'static Expr, lists: &'static [&'static [T]], init: &'static [T]) -> Option<&'static [T]>
+where Value: From<&'static [T]>, &'static [T]: TryFrom<Value> {
+    let mut acc = init.to_vec();
+    let steps = lists.iter().map(|l| l.len()).max().unwrap_or(0);
+    for step in 0..steps {
+        let active: Vec<usize> = (0..lists.len()).filter(|&i| lists[i].len() > step).collect();
+        let accs = active.iter().map(|&i| acc[i]).galloc_scollect();
+        let elems = active.iter().map(|&i| lists[i][step]).galloc_scollect();
+        let ctx = Context::new(active.len(), vec![accs.into(), elems.into()], vec![], Value::Null);
+        let result: &'static [T] = f.eval(&ctx).try_into().ok()?;
+        for (&i, &r) in active.iter().zip(result.iter()) {
+            acc[i] = r;
+        }
+    }
+    Some(acc.into_iter().galloc_scollect())
+}
+
+impl crate::expr::ops::Op2 for FoldL {
+    fn cost(&self) -> usize { 1 }
+    /// Folds lists of strings from a string, or lists of integers from an integer.
+    fn try_eval(&self, a1: Value, a2: Value) -> (bool, Value) {
+        let Some(f) = self.0 else { return (false, Value::Null) };
+        let result = match (a1, a2) {
+            (Value::ListStr(l), Value::Str(init)) => foldl(f, l, init).map(Value::from),
+            (Value::ListInt(l), Value::Int(init)) => foldl(f, l, init).map(Value::from),
+            _ => None,
+        };
+        match result {
+            Some(v) => (true, v),
+            None => (false, Value::Null),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expr;
+    use crate::expr::ops::Op2;
+    use crate::galloc::{AllocForAny, AllocForExactSizeIter};
+    use crate::value::Value;
+
+    use super::FoldL;
+
+    #[test]
+    fn test_foldl() {
+        let f = FoldL(Some(expr!(Concat (Concat [0] "<") [1]).galloc()));
+        let l: Value = [&["a", "b"][..], &[], &["c"]].into_iter().galloc_scollect().into();
+        let init: Value = ["", "x", "y"].into_iter().galloc_scollect().into();
+        let (ok, v) = f.try_eval(l, init);
+        assert!(ok);
+        assert_eq!(v.to_str(), &["<a<b", "x", "y<c"]);
+        assert!(!FoldL::default().try_eval(l, init).0);
+    }
+}
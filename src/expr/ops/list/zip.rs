@@ -0,0 +1,93 @@
+use crate::expr::context::Context;
+use crate::expr::Expr;
+use crate::galloc::{AllocForExactSizeIter, AllocForIter};
+use crate::parser::config::Config;
+use crate::value::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Element-wise combination of two lists of strings: `(list.zip_with l1 l2)` gives `f(x, y)` for each pair of elements of `l1` and `l2` at the same position, stopping at the end of the shorter list.
+///
+/// As for `list.map`, the function `f` is a nested expression held by the operator (`#f`), in which variable `0` is the element of `l1` and variable `1` the element of `l2`.
+/// It is never enumerated: it is only built by the zip deduction of `ListDeducer`, or given explicitly in the grammar.
+pub struct ZipWith(pub Option<&'static Expr>);
+
+impl std::hash::Hash for ZipWith {
+    /// Hashes the nested function by address, as `Map` does.
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.map(|x| x as *const Expr).hash(state);
+    }
+}
+
+impl ZipWith {
+    /// Creates a `ZipWith` whose function is the expression `#f` of the configuration, if any.
+    pub fn from_config(config: &Config) -> Self {
+        Self(config.get_expr("f"))
+    }
+    /// Returns the name of the operator, `list.zip_with`.
+    pub fn name() -> &'static str {
+        "list.zip_with"
+    }
+}
+
+impl std::fmt::Display for ZipWith {
+    /// Formats the operator together with its nested function, if any.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(e) = self.0 {
+            write!(f, "list.zip_with #f:{:?}", e)
+        } else {
+            write!(f, "list.zip_with")
+        }
+    }
+}
+
+impl Default for ZipWith {
+    /// Creates a `ZipWith` without nested function.
+    fn default() -> Self {
+        Self::from_config(&Default::default())
+    }
+}
+
+impl crate::forward::enumeration::Enumerator2 for ZipWith {
+    /// Zips are only produced by deduction, so nothing is enumerated.
+    fn enumerate(&self, this: &'static crate::expr::ops::Op2Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 2]) -> Result<(), ()> { Ok(()) }
+}
+
+impl crate::expr::ops::Op2 for ZipWith {
+    fn cost(&self) -> usize { 1 }
+    /// Evaluates `f` once on the pairs of elements of all examples, then cuts the results back into one list per example.
+    fn try_eval(&self, a1: Value, a2: Value) -> (bool, Value) {
+        let (Some(f), Value::ListStr(l1), Value::ListStr(l2)) = (self.0, a1, a2) else { return (false, Value::Null) };
+        let lens = l1.iter().zip(l2.iter()).map(|(a, b)| a.len().min(b.len())).collect::<Vec<_>>();
+        let left = l1.iter().zip(lens.iter()).flat_map(|(a, n)| a[..*n].iter().copied()).galloc_collect();
+        let right = l2.iter().zip(lens.iter()).flat_map(|(b, n)| b[..*n].iter().copied()).galloc_collect();
+        let ctx = Context::new(left.len(), vec![left.into(), right.into()], vec![], Value::Null);
+        let Ok(result) = <&'static [&'static str]>::try_from(f.eval(&ctx)) else { return (false, Value::Null) };
+        let mut start = 0;
+        let lists = lens.into_iter().map(|n| {
+            start += n;
+            &result[start - n..start]
+        }).galloc_scollect();
+        (true, Value::ListStr(lists))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expr;
+    use crate::expr::ops::Op2;
+    use crate::galloc::{AllocForAny, AllocForExactSizeIter};
+    use crate::value::Value;
+
+    use super::ZipWith;
+
+    #[test]
+    fn test_zip_with() {
+        let f = ZipWith(Some(expr!(Concat (Concat [0] "=") [1]).galloc()));
+        let keys: Value = [&["a", "b"][..], &["c", "d", "e"], &[]].into_iter().galloc_scollect().into();
+        let values: Value = [&["1", "2"][..], &["3"], &["4"]].into_iter().galloc_scollect().into();
+        let (ok, v) = f.try_eval(keys, values);
+        assert!(ok);
+        assert_eq!(v.to_liststr(), &[&["a=1", "b=2"][..], &["c=3"], &[]]);
+        assert!(!ZipWith::default().try_eval(keys, values).0);
+    }
+}
@@ -11,7 +11,7 @@ use super::*;
 /// 
 macro_rules! for_all_op1 {
     () => {
-        _do!(Len ToInt ToStr Neg IsPos IsZero IsNatural RetainLl RetainLc RetainN RetainL RetainLN Uppercase Lowercase ParseDate AsMonth AsDay AsYear AsWeekDay ParseTime FormatFloat
+        _do!(Len ToInt ToStr Neg Abs IsPos IsZero IsNatural RetainLl RetainLc RetainN RetainL RetainLN Uppercase Lowercase TitleCase Capitalize Trim TrimStart TrimEnd SqueezeWs ParseDate AsMonth AsDay AsYear AsWeekDay AsQuarter AsWeekOfYear FormatQuarter DurationToHms ToUtc ParseTime FormatFloat
             ParseInt 
             FormatInt
             ParseMonth
@@ -21,6 +21,15 @@ macro_rules! for_all_op1 {
             FormatWeekday
             FormatFloat
             ParseFloat
+            ParseCurrency FormatCurrency FormatPercent
+            ParseSize FormatSize
+            ParseRoman FormatRoman
+            ParseOrdinal FormatOrdinal
+            ParseQuarter
+            ParseDuration FormatDuration
+            ParseIso8601 FormatIso8601
+            ParseDigits FormatDigits
+            ParseCustom
             FNeg
             FAbs
             FIsPos
@@ -33,7 +42,11 @@ macro_rules! for_all_op1 {
             FLen
             Map
             Filter
-            BvNot BvNeg);
+            UrlEncode UrlDecode IsEmpty EmailUser EmailDomain UrlHost UrlPath
+            BvNot BvNeg BvExtract BvZeroExtend BvSignExtend BvRotateLeft BvRotateRight
+            ListSum ListMax ListMin ListSort ListRev
+            CharIsDigit CharIsLetter CharIsUpper CharToUpper CharToLower CharCode CharToStr
+            TupleFst TupleSnd);
     };
 }
 #[macro_export]
@@ -46,9 +59,10 @@ macro_rules! for_all_op1 {
 /// 
 macro_rules! for_all_op2 {
     () => { 
-        _do!(Concat Eq At PrefixOf SuffixOf Contains Split Join Count Add Sub Head Tail TimeFloor TimeAdd Floor Round Ceil FAdd FSub FFloor FRound FCeil FCount FShl10
-            TimeMul StrAt
-            BvAdd BvSub BvMul BvUDiv BvURem BvSDiv BvSRem BvOr BvAnd BvXor BvShl BvAShr BvLShr)
+        _do!(Concat Eq At PrefixOf SuffixOf Contains Split Join Count Add Sub Head Tail TimeFloor TimeAdd Floor Round Ceil FAdd FSub FFloor FRound FCeil FRoundTo FCount FShl10
+            TimeMul TimeDiff StrAt DiffDays AddMonths DateTruncate Match ExtractRe CharAt EqIgnoreCase NumericCmp ContainsCi PrefixOfCi Initials MapLookup MapContainsKey TupleMake FoldL ZipWith ListTake ListDrop Mul Div Mod Min Max Lt Le Gt Ge FLt FLe FGt FGe
+            BvAdd BvSub BvMul BvUDiv BvURem BvSDiv BvSRem BvOr BvAnd BvXor BvShl BvAShr BvLShr
+            BvConcat BvUlt BvUle BvUgt BvUge BvSlt BvSle BvSgt BvSge)
     };
 }
 #[macro_export]
@@ -59,6 +73,6 @@ macro_rules! for_all_op2 {
 /// 
 macro_rules! for_all_op3 {
     () => {
-        _do!(Replace Ite SubStr IndexOf)
+        _do!(Replace Ite SubStr IndexOf ReplaceRe ReplaceCi PadLeft PadRight ListSlice IndexOfNth ReplaceNth ReplaceAll Between)
     };
 }
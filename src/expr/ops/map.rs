@@ -0,0 +1,37 @@
+use crate::galloc::AllocForExactSizeIter;
+use crate::{new_op2, new_op2_opt};
+
+use super::Op2;
+
+new_op2_opt!(MapLookup, "map.lookup",
+    (MapStr, Str) -> Str { |(m, k)| {
+        m.iter().find(|e| e.0 == *k).map(|e| e.1)
+    }}
+);
+
+new_op2!(MapContainsKey, "map.contains_key",
+    (MapStr, Str) -> Bool { |(m, k)| {
+        m.iter().any(|e| e.0 == *k)
+    }}
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::expr::ops::Op2;
+    use crate::galloc::AllocForExactSizeIter;
+    use crate::value::Value;
+
+    use super::{MapContainsKey, MapLookup};
+
+    #[test]
+    fn test_map_ops() {
+        let states: &'static [(&'static str, &'static str)] = [("CA", "California"), ("NY", "New York")].into_iter().galloc_scollect();
+        let m = Value::MapStr([states, states, states].into_iter().galloc_scollect());
+        let k: Value = ["NY", "CA", "TX"].into_iter().galloc_scollect().into();
+
+        let (ok, v) = MapLookup::default().try_eval(m, k);
+        assert!(!ok);
+        assert_eq!(v.to_str(), &["New York", "California", ""]);
+        assert_eq!(MapContainsKey::default().try_eval(m, k).1.to_bool(), &[true, true, false]);
+    }
+}
@@ -12,6 +12,7 @@ pub use self::str::*;
 
 use crate::text::parsing::*;
 use crate::text::formatting::*;
+use crate::text::zone::ToUtc;
 pub mod base;
 pub use self::base::*;
 
@@ -28,6 +29,12 @@ pub mod date;
 pub use date::*;
 pub mod bv;
 pub use bv::*;
+pub mod chars;
+pub use chars::*;
+pub mod map;
+pub use map::*;
+pub mod tuple;
+pub use tuple::*;
 pub mod macros;
 
 #[enum_dispatch]
@@ -108,14 +115,15 @@ impl Op3Enum {
 /// The operations cover a diverse set of functionalities such as conversions between data types (e.g., `ToInt`, `ToStr`, `IntToFloat`, `FloatToInt`, `StrToFloat`), string manipulations like changing case (`Uppercase`, `Lowercase`) and retaining specific character types (`RetainLl`, `RetainLc`, `RetainN`, `RetainL`, `RetainLN`). 
 /// 
 /// 
-/// Additionally, the enum supports various mathematical and logical checks (`IsPos`, `IsZero`, `IsNatural`, `FIsPos`, `FIsZero`, `FNotNeg`), numerical operations (`Neg`, `FNeg`, `FAbs`, `FExp10`), formatting (`FormatInt`, `FormatFloat`, `FormatTime`, `FormatMonth`, `FormatWeekday`), and parsing (`ParseTime`, `ParseDate`, `ParseInt`, `ParseMonth`, `ParseWeekday`, `ParseFloat`). 
-/// It also includes utilities like `Len` for measuring length and several date-related transformations (`AsMonth`, `AsDay`, `AsYear`, `AsWeekDay`). 
+/// Additionally, the enum supports various mathematical and logical checks (`IsPos`, `IsZero`, `IsNatural`, `FIsPos`, `FIsZero`, `FNotNeg`), numerical operations (`Neg`, `FNeg`, `FAbs`, `FExp10`), formatting (`FormatInt`, `FormatFloat`, `FormatTime`, `FormatMonth`, `FormatWeekday`, `FormatCurrency`, `FormatSize`, `FormatRoman`, `FormatOrdinal`, `FormatDuration`, `FormatIso8601`, `FormatDigits`), and parsing (`ParseTime`, `ParseDate`, `ParseInt`, `ParseMonth`, `ParseWeekday`, `ParseFloat`, `ParseCurrency`, `ParseSize`, `ParseRoman`, `ParseOrdinal`, `ParseQuarter`, `ParseDuration`, `ParseIso8601`, `ParseDigits`, and `ParseCustom` for the scanners declared by the problem). 
+/// It also includes utilities like `Len` for measuring length and several date-related transformations (`AsMonth`, `AsDay`, `AsYear`, `AsWeekDay`, `AsQuarter`, `AsWeekOfYear`, `FormatQuarter`). 
 /// This diverse suite of operations enables flexible and efficient manipulation of data types required for string synthesis challenges.
 pub enum Op1Enum {
     Len,
     ToInt,
     ToStr,
     Neg,
+    Abs,
     IsPos,
     IsZero,
     IsNatural,
@@ -128,21 +136,49 @@ pub enum Op1Enum {
     Filter,
     Uppercase,
     Lowercase,
+    TitleCase,
+    Capitalize,
+    Trim,
+    TrimStart,
+    TrimEnd,
+    SqueezeWs,
     AsMonth,
     AsDay,
     AsYear,
     AsWeekDay,
+    AsQuarter,
+    AsWeekOfYear,
+    FormatQuarter,
+    DurationToHms,
+    ToUtc,
     ParseTime,
     ParseDate,
     ParseInt,
     ParseMonth,
     ParseWeekday,
     ParseFloat,
+    ParseCurrency,
+    ParseSize,
+    ParseRoman,
+    ParseOrdinal,
+    ParseQuarter,
+    ParseDuration,
+    ParseIso8601,
+    ParseDigits,
+    ParseCustom,
     FormatInt,
     FormatFloat,
     FormatTime,
     FormatMonth,
     FormatWeekday,
+    FormatCurrency,
+    FormatPercent,
+    FormatSize,
+    FormatRoman,
+    FormatOrdinal,
+    FormatDuration,
+    FormatIso8601,
+    FormatDigits,
     FNeg,
     FAbs,
     FIsPos,
@@ -153,8 +189,34 @@ pub enum Op1Enum {
     FIsZero,
     FNotNeg,
     FLen,
+    UrlEncode,
+    UrlDecode,
+    EmailUser,
+    EmailDomain,
+    UrlHost,
+    UrlPath,
+    IsEmpty,
     BvNot,
-    BvNeg
+    BvNeg,
+    BvExtract,
+    BvZeroExtend,
+    BvSignExtend,
+    BvRotateLeft,
+    BvRotateRight,
+    ListSum,
+    ListMax,
+    ListMin,
+    ListSort,
+    ListRev,
+    CharIsDigit,
+    CharIsLetter,
+    CharIsUpper,
+    CharToUpper,
+    CharToLower,
+    CharCode,
+    CharToStr,
+    TupleFst,
+    TupleSnd,
 }
 impl std::fmt::Display for Op1Enum {
     /// Formats the operation represented by `Op1Enum` for printing. 
@@ -186,21 +248,30 @@ impl Op1Enum {
     /// If a match is found, it returns the operation configured with the supplied configuration. 
     /// For specific operations like "str.len", "str.from_int", and "str.to_int", direct matches that do not utilize the macro are provided for convenience. 
     /// If no operation matches the given string name, the method panics with an "Unknown Operator" error message.
+    /// When a cost table is loaded (see `tuning::COST_TABLE`), the cost of the operator is taken from it.
     /// 
     pub fn from_name(name: &str, config: &Config) -> Self {
+        Self::try_from_name(name, config).unwrap_or_else(|| panic!("Unknown Operator {}", name))
+    }
+    /// Same as `from_name`, but returns `None` for an unknown operator instead of panicking.
+    /// The names of the scanners declared by the problem (see `text::parsing::define_scanner`) are operators as well.
+    pub fn try_from_name(name: &str, config: &Config) -> Option<Self> {
+        let tuned = crate::tuning::tuned_config(name, config);
+        let config = tuned.as_ref();
         macro_rules! _do { ($($op:ident)*) => {
             $(
                 if $op::name() == name {
-                    return $op::from_config(config).into();
+                    return Some($op::from_config(config).into());
                 }
             )*
         }}
         crate::for_all_op1!();
         match name {
-            "str.len" => Len::from_config(config).into(),
-            "str.from_int" => ToStr::from_config(config).into(),
-            "str.to_int" => ToInt::from_config(config).into(),
-            _ => panic!("Unknown Operator {}", name),
+            "str.len" => Some(Len::from_config(config).into()),
+            "str.from_int" => Some(ToStr::from_config(config).into()),
+            "str.to_int" => Some(ToInt::from_config(config).into()),
+            "abs" => Some(Abs::from_config(config).into()),
+            _ => lookup_scanner(name).map(|i| ParseCustom::new(i, config).into()),
         }
     }
     /// Provides a method to retrieve the name of a unary operation as a static string. 
@@ -232,7 +303,7 @@ impl Op1Enum {
 /// There are also numerical operations like `Add`, `Sub`, alongside floating-point specific operations like `FAdd`, `FSub`, and rounding techniques such as `Floor`, `Round`, and `Ceil`.
 /// 
 /// Moreover, the enum encapsulates list operations such as `Head`, `Tail`, and `Filter`, indicating capabilities to manipulate and traverse lists. 
-/// Time-based operations like `TimeFloor`, `TimeAdd`, and `TimeMul` are included, reflecting tasks related to temporal data. 
+/// Time-based operations like `TimeFloor`, `TimeAdd`, `TimeMul` and `TimeDiff` are included, reflecting tasks related to temporal data, together with calendar arithmetic on dates (`DiffDays`, `AddMonths`, `DateTruncate`). 
 /// `Split` and `Join` manage compound string or list structures, and `StrAt` and `At` facilitate index-based access in strings or lists. 
 /// The enumeration is designed to accommodate various contexts and operations necessary for a comprehensive synthesis framework, supporting diverse data types and manipulation techniques.
 pub enum Op2Enum {
@@ -252,8 +323,19 @@ pub enum Op2Enum {
     TimeFloor,
     TimeAdd,
     Floor, Round, Ceil,
-    FAdd, FSub, FFloor, FRound, FCeil, FCount, FShl10, TimeMul, StrAt,
-    BvAdd, BvSub, BvMul, BvUDiv, BvURem, BvSDiv, BvSRem, BvOr, BvAnd, BvXor, BvShl, BvAShr, BvLShr
+    FAdd, FSub, FFloor, FRound, FCeil, FRoundTo, FCount, FShl10, TimeMul, StrAt,
+    DiffDays, AddMonths, DateTruncate,
+    TimeDiff,
+    Match, ExtractRe, CharAt,
+    EqIgnoreCase, NumericCmp, ContainsCi, PrefixOfCi, Initials,
+    MapLookup, MapContainsKey,
+    TupleMake,
+    FoldL, ZipWith,
+    ListTake, ListDrop,
+    Mul, Div, Mod, Min, Max,
+    Lt, Le, Gt, Ge, FLt, FLe, FGt, FGe,
+    BvAdd, BvSub, BvMul, BvUDiv, BvURem, BvSDiv, BvSRem, BvOr, BvAnd, BvXor, BvShl, BvAShr, BvLShr,
+    BvConcat, BvUlt, BvUle, BvUgt, BvUge, BvSlt, BvSle, BvSgt, BvSge
 }
 
 impl std::fmt::Display for Op2Enum {
@@ -277,26 +359,44 @@ impl std::fmt::Display for Op2Enum {
 }
 
 impl Op2Enum {
+    /// Whether the operator is one of the numeric comparisons (`int.lt`, ..., `float.ge`), whose compared non-terminals receive threshold constants in condition search.
+    pub fn is_comparison(&self) -> bool {
+        matches!(self, Self::Lt(_) | Self::Le(_) | Self::Gt(_) | Self::Ge(_) | Self::FLt(_) | Self::FLe(_) | Self::FGt(_) | Self::FGe(_))
+    }
     /// Converts a string name into an `Op2Enum` variant by matching the provided name with known operation names and configurations. 
     /// 
     /// The function utilizes a macro to iterate through all defined binary operations (`Op2`) and checks if the operation's name matches the input string. 
     /// If a match is found, it retrieves the operation with the given configuration and converts it into the `Op2Enum` type. 
     /// For specific operators like `"+"` and `"-"`, the function directly constructs their corresponding `Add` or `Sub` variants, respectively. 
     /// If no matching operation is found, it raises a panic with an error message indicating the unknown operator name.
+    /// As for unary operations, a loaded cost table overrides the configured cost.
     /// 
     pub fn from_name(name: &str, config: &Config) -> Self {
+        Self::try_from_name(name, config).unwrap_or_else(|| panic!("Unknown Operator: {}", name))
+    }
+    /// Same as `from_name`, but returns `None` for an unknown operator instead of panicking.
+    pub fn try_from_name(name: &str, config: &Config) -> Option<Self> {
+        let tuned = crate::tuning::tuned_config(name, config);
+        let config = tuned.as_ref();
         macro_rules! _do { ($($op:ident)*) => {
             $(
                 if $op::name() == name {
-                    return $op::from_config(config).into();
+                    return Some($op::from_config(config).into());
                 }
             )*
         }}
         crate::for_all_op2!();
         match name {
-            "+" => Add::from_config(config).into(),
-            "-" => Sub::from_config(config).into(),
-            _ => panic!("Unknown Operator: {}", name),
+            "+" => Some(Add::from_config(config).into()),
+            "-" => Some(Sub::from_config(config).into()),
+            "*" => Some(Mul::from_config(config).into()),
+            "div" => Some(Div::from_config(config).into()),
+            "mod" => Some(Mod::from_config(config).into()),
+            "<" => Some(Lt::from_config(config).into()),
+            "<=" => Some(Le::from_config(config).into()),
+            ">" => Some(Gt::from_config(config).into()),
+            ">=" => Some(Ge::from_config(config).into()),
+            _ => None,
         }
     }
     /// Returns the name of the operation represented by the given instance of the enumeration. 
@@ -332,6 +432,15 @@ pub enum Op3Enum {
     Ite,
     SubStr,
     IndexOf,
+    ReplaceRe,
+    ReplaceCi,
+    PadLeft,
+    PadRight,
+    ListSlice,
+    IndexOfNth,
+    ReplaceNth,
+    ReplaceAll,
+    Between,
 }
 
 impl std::fmt::Display for Op3Enum {
@@ -363,17 +472,27 @@ impl Op3Enum {
     /// If a match is found, it returns the corresponding operator configured via the `from_config` method. 
     /// In case no matching operation name is found, the function will terminate execution and issue a panic with an error message indicating the unknown operator. 
     /// This method ensures that each operation can be instantiated from a configuration while providing runtime safety against undefined operations.
+    /// Costs from a loaded cost table take precedence over the configuration.
     /// 
     pub fn from_name(name: &str, config: &Config) -> Self {
+        Self::try_from_name(name, config).unwrap_or_else(|| panic!("Unknown Operator: {}", name))
+    }
+    /// Same as `from_name`, but returns `None` for an unknown operator instead of panicking.
+    pub fn try_from_name(name: &str, config: &Config) -> Option<Self> {
+        let tuned = crate::tuning::tuned_config(name, config);
+        let config = tuned.as_ref();
         macro_rules! _do { ($($op:ident)*) => {
             $(
                 if $op::name() == name {
-                    return $op::from_config(config).into();
+                    return Some($op::from_config(config).into());
                 }
             )*
         }}
         crate::for_all_op3!();
-        panic!("Unknown Operator: {}", name);
+        match name {
+            "str.replaceall" => Some(ReplaceAll::from_config(config).into()),
+            _ => None,
+        }
     }
     /// Provides an implementation to retrieve the name of an operation represented by this item. 
     /// 
@@ -42,6 +42,7 @@ macro_rules! default_value {
     (Int) => { 0i64 }; 
     (Bool) => { false }; 
     (Float) => { $crate::utils::F64(0.0) }; 
+    (Char) => { '\0' }; 
     (ListStr) => { &[] as &[&str] }; 
     (ListInt) => { [] }; 
 }
@@ -334,7 +335,7 @@ macro_rules! impl_op3_opt {
                 match (a1, a2, a3) {
                     $(
                         (crate::value::Value::$t1(s1), crate::value::Value::$t2(s2), crate::value::Value::$t3(s3)) => {
-                            let mut flag = true
+                            let mut flag = true;
                             let a = itertools::izip!(s1.iter(), s2.iter(), s3.iter()).map($f).map(|f| { flag &= f.is_some(); f.unwrap_or($crate::default_value![$rt]) }).galloc_scollect();
                             (flag, crate::value::Value::$rt(a))
                         }
@@ -1,11 +1,12 @@
 use std::cmp::min;
 use std::ops::Not;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use bumpalo::collections::CollectIn;
 use derive_more::DebugCustom;
 use crate::galloc::{AllocForStr, AllocForExactSizeIter, TryAllocForExactSizeIter, AllocForIter, AllocForCharIter};
 use crate::utils::F64;
-use crate::{new_op1, new_op2, new_op3, new_op3_opt, new_op2_opt};
+use crate::{new_op1, new_op1_opt, new_op2, new_op3, new_op3_opt, new_op2_opt};
 use itertools::izip;
 
 
@@ -13,6 +14,87 @@ use itertools::izip;
 use super::list::to_index;
 use super::{Op1, Op3, Op2};
 
+static CHARS: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How `list.len`, `str.substr`, `list.at`, `str.at` and `str.indexof` index strings.
+pub enum StrIndexing {
+    /// Positions are bytes of the UTF-8 encoding, as in the SyGuS benchmarks, which are ASCII (default)
+    Bytes,
+    /// Positions are characters (`--unicode`): an accented letter or an emoji is one position, and no string is cut inside a character
+    Chars,
+}
+
+impl StrIndexing {
+    /// Returns the indexing of the current run.
+    #[inline(always)]
+    pub fn current() -> Self {
+        if CHARS.load(Ordering::Relaxed) { Self::Chars } else { Self::Bytes }
+    }
+
+    /// Sets the indexing of the string operations for the whole run.
+    pub fn set(self) {
+        CHARS.store(self == Self::Chars, Ordering::Relaxed);
+    }
+
+    /// Number of positions of `s`.
+    #[inline]
+    pub fn len(self, s: &str) -> usize {
+        match self {
+            Self::Chars if !s.is_ascii() => s.chars().count(),
+            _ => s.len(),
+        }
+    }
+
+    /// Byte offset of the position `i` of `s`, which may be its length; `None` past the end.
+    #[inline]
+    pub fn offset(self, s: &str, i: usize) -> Option<usize> {
+        match self {
+            Self::Chars if !s.is_ascii() => s.char_indices().map(|(b, _)| b).chain(std::iter::once(s.len())).nth(i),
+            _ => (i <= s.len()).then_some(i),
+        }
+    }
+
+    /// Position of the byte offset `b` of `s`, which must be on a character boundary.
+    #[inline]
+    pub fn position(self, s: &str, b: usize) -> usize {
+        match self {
+            Self::Chars => s[..b].chars().count(),
+            Self::Bytes => b,
+        }
+    }
+
+    /// Substring of `s` from position `i` to position `j`, both at most the length of `s`.
+    #[inline]
+    pub fn slice(self, s: &str, i: usize, j: usize) -> &str {
+        match self {
+            Self::Chars if !s.is_ascii() => &s[self.offset(s, i).unwrap()..self.offset(s, j).unwrap()],
+            _ => &s[i..j],
+        }
+    }
+
+    /// `str.substr`: the substring of `s` of at most `n` positions from position `i`, empty if `i` is out of `s` or `n` is negative.
+    pub fn substr(self, s: &str, i: i64, n: i64) -> &str {
+        let len = self.len(s);
+        if i >= 0 && (i as usize) < len && n >= 0 {
+            let i = i as usize;
+            self.slice(s, i, std::cmp::min(i + n as usize, len))
+        } else { "" }
+    }
+
+    /// `str.at`: the character at position `i` of `s`, empty if `i` is out of `s`.
+    pub fn at(self, s: &str, i: i64) -> &str {
+        self.substr(s, i, 1)
+    }
+
+    /// `str.indexof`: the position of the first occurrence of `t` in `s` from position `i`, `-1` if none or if `i` is past the end of `s`.
+    pub fn index_of(self, s: &str, t: &str, i: i64) -> i64 {
+        if i < 0 || i as usize > self.len(s) { return -1; }
+        let start = self.offset(s, i as usize).unwrap();
+        s[start..].find(t).map_or(-1, |r| self.position(s, start + r) as i64)
+    }
+}
+
 
 new_op2!(Concat, "str.++",
     (Str, Str) -> Str { |(s1, s2)| {
@@ -23,15 +105,13 @@ new_op2!(Concat, "str.++",
 mod replace;
 pub use replace::*;
 
+mod re;
+pub use re::*;
+
 
 new_op3!(SubStr, "str.substr",
     (Str, Int, Int) -> Str { |(s1, s2, s3)| {
-        if s1.is_empty() { return ""; }
-        if *s2 >= 0 && (*s2 as usize) < s1.len() && *s3 >= 0 {
-            let i = *s2 as usize;
-            let j = std::cmp::min(i + *s3 as usize, s1.len());
-            s1[i..j].galloc_str()
-        } else { "" }
+        StrIndexing::current().substr(s1, *s2, *s3).galloc_str()
     }}
 );
 
@@ -109,10 +189,7 @@ pub fn str_index_of_b(s1: &str, s2: &str, s3: usize) -> i64 {
 
 new_op3!(IndexOf, "str.indexof",
     (Str, Str, Int) -> Int { |(s1, s2, s3)| {
-        if *s3 < 0 || *s3 as usize > s1.len() { return -1i64; }
-        if let Some(r) = s1[*s3 as usize..].find(s2) {
-            *s3 + r as i64
-        } else { -1i64 }
+        StrIndexing::current().index_of(s1, s2, *s3)
     }}
 );
 
@@ -132,6 +209,87 @@ new_op2!(Contains, "str.contains",
     }}
 );
 
+new_op2!(EqIgnoreCase, "str.eq_ignorecase",
+    (Str, Str) -> Bool { |(s1, s2)| {
+        s1.chars().flat_map(char::to_lowercase).eq(s2.chars().flat_map(char::to_lowercase))
+    }}
+);
+
+/// Returns whether two characters are equal up to casing.
+fn eq_ignore_case(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Returns the byte range of the first occurrence of `pat` in `s`, comparing characters up to casing.
+pub fn find_ignore_case(s: &str, pat: &str) -> Option<(usize, usize)> {
+    s.char_indices().map(|(i, _)| i).chain(std::iter::once(s.len())).find_map(|i| {
+        let mut rest = s[i..].char_indices();
+        let mut end = i;
+        for p in pat.chars() {
+            let (j, c) = rest.next()?;
+            if !eq_ignore_case(c, p) { return None; }
+            end = i + j + c.len_utf8();
+        }
+        Some((i, end))
+    })
+}
+
+new_op2!(ContainsCi, "str.contains_ci",
+    (Str, Str) -> Bool { |(s1, s2)| {
+        find_ignore_case(s1, s2).is_some()
+    }}
+);
+
+new_op2!(PrefixOfCi, "str.prefixof_ci",
+    (Str, Str) -> Bool { |(s1, s2)| {
+        let mut chars = s2.chars();
+        s1.chars().all(|c| chars.next().is_some_and(|d| eq_ignore_case(c, d)))
+    }}
+);
+
+new_op3!(ReplaceCi, "str.replace_ci",
+    (Str, Str, Str) -> Str { |(s1, s2, s3)| {
+        match find_ignore_case(s1, s2) {
+            Some((i, j)) => [&s1[..i], *s3, &s1[j..]].concat().galloc_str(),
+            None => *s1,
+        }
+    }}
+);
+
+/// Returns the byte offset of the `n`-th non-overlapping occurrence of `pat` in `s`, counting from 1, or from the end if `n` is negative.
+///
+/// The occurrences are found from the left in both cases, so that `-1` is the last of the matches counted by `1`, `2`, ...
+pub fn nth_match(s: &str, pat: &str, n: i64) -> Option<usize> {
+    if pat.is_empty() || n == 0 { return None; }
+    if n > 0 { return s.match_indices(pat).nth(n as usize - 1).map(|(i, _)| i); }
+    let found = s.match_indices(pat).map(|(i, _)| i).collect::<Vec<_>>();
+    found.len().checked_sub(n.unsigned_abs() as usize).map(|k| found[k])
+}
+
+new_op3!(IndexOfNth, "str.indexof_nth",
+    (Str, Str, Int) -> Int { |(s1, s2, i)| {
+        nth_match(s1, s2, *i).map_or(-1, |k| StrIndexing::current().position(s1, k) as i64)
+    }}
+);
+
+new_op1!(IsEmpty, "str.is_empty",
+    Str -> Bool { |s1| {
+        s1.is_empty()
+    }}
+);
+
+/// Parses a string as a finite number, ignoring surrounding whitespace.
+pub fn parse_number(s: &str) -> Option<f64> {
+    s.trim().parse::<f64>().ok().filter(|x| x.is_finite())
+}
+
+new_op2!(NumericCmp, "str.numeric_cmp",
+    (Str, Str) -> Bool { |(s1, s2)| {
+        // Strings that are not numbers compare as false, so that the predicate is defined on every example.
+        matches!((parse_number(s1), parse_number(s2)), (Some(a), Some(b)) if a < b)
+    }}
+);
+
 
 new_op2!(Split, "str.split",
     (Str, Str) -> ListStr { |(s1, s2)| {
@@ -199,9 +357,216 @@ new_op1!(Lowercase, "str.lowercase",
     }}
 );
 
+/// Converts `s` to title case as Python's `str.title`: letters following a letter are lowercased, other letters uppercased, e.g. `"Jean-luc O'NEIL"` becomes `"Jean-Luc O'Neil"`.
+pub fn title_case(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut letter = false;
+    for c in s.chars() {
+        if letter { result.extend(c.to_lowercase()) } else { result.extend(c.to_uppercase()) }
+        letter = c.is_alphabetic();
+    }
+    result
+}
+
+new_op1!(TitleCase, "str.titlecase",
+    Str -> Str { |s1| {
+        title_case(s1).galloc_str()
+    }}
+);
+
+new_op1!(Capitalize, "str.capitalize",
+    Str -> Str { |s1| {
+        let mut chars = s1.chars();
+        match chars.next() {
+            Some(c) => c.to_uppercase().chain(chars.flat_map(char::to_lowercase)).galloc_collect_str(),
+            None => "",
+        }
+    }}
+);
+
+/// Returns the first character of each whitespace-separated word of `s`, each followed by `sep`, e.g. `"J.R.T."` for `"John Ronald Tolkien"` and `"."`.
+pub fn initials(s: &str, sep: &str) -> String {
+    let mut result = String::new();
+    for c in s.split_whitespace().filter_map(|w| w.chars().next()) {
+        result.push(c);
+        result.push_str(sep);
+    }
+    result
+}
+
+new_op2!(Initials, "str.initials",
+    (Str, Str) -> Str { |(s1, s2)| {
+        initials(s1, s2).galloc_str()
+    }}
+);
+
+new_op1!(Trim, "str.trim",
+    Str -> Str { |s1| {
+        (*s1).trim()
+    }}
+);
+
+new_op1!(TrimStart, "str.trim_start",
+    Str -> Str { |s1| {
+        (*s1).trim_start()
+    }}
+);
+
+new_op1!(TrimEnd, "str.trim_end",
+    Str -> Str { |s1| {
+        (*s1).trim_end()
+    }}
+);
+
+/// Replaces every run of whitespace characters of `s` by a single space.
+pub fn squeeze_whitespace(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut space = false;
+    for c in s.chars() {
+        if !c.is_whitespace() { result.push(c); }
+        else if !space { result.push(' '); }
+        space = c.is_whitespace();
+    }
+    result
+}
+
+new_op1!(SqueezeWs, "str.squeeze_ws",
+    Str -> Str { |s1| {
+        if s1.contains(|c: char| c.is_whitespace() && c != ' ') || s1.contains("  ") {
+            squeeze_whitespace(s1).galloc_str()
+        } else { *s1 }
+    }}
+);
+
+/// Percent-encodes a string as in RFC 3986: unreserved characters (`A-Z`, `a-z`, `0-9`, `-`, `_`, `.`, `~`) are kept, every other UTF-8 byte becomes `%XX`.
+pub fn url_encode(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) {
+            result.push(b as char);
+        } else {
+            result.push_str(&format!("%{:02X}", b));
+        }
+    }
+    result
+}
+
+/// Decodes a percent-encoded string. 
+/// 
+/// Returns `None` if a `%` is not followed by two hexadecimal digits or if the decoded bytes are not valid UTF-8.
+pub fn url_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i+1..i+3)?).ok()?;
+            if !hex.bytes().all(|c| c.is_ascii_hexdigit()) { return None; }
+            result.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            result.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(result).ok()
+}
+
+new_op1!(UrlEncode, "str.url_encode",
+    Str -> Str { |s1| {
+        url_encode(s1).galloc_str()
+    }}
+);
+
+new_op1_opt!(UrlDecode, "str.url_decode",
+    Str -> Str { |s1| {
+        url_decode(s1).map(|s| s.galloc_str())
+    }}
+);
+
+/// Returns the user and domain of the first e-mail address of `s`, e.g. `("john.doe", "example.com")` in `Mail: john.doe@example.com.`.
+pub fn email_parts(s: &str) -> Option<(&str, &str)> {
+    let is_user = |c: char| c.is_alphanumeric() || "._%+-".contains(c);
+    let is_domain = |c: char| c.is_alphanumeric() || ".-".contains(c);
+    s.match_indices('@').find_map(|(i, _)| {
+        let user = &s[s[..i].rfind(|c| !is_user(c)).map_or(0, |j| j + s[j..].chars().next().unwrap().len_utf8())..i];
+        let domain = &s[i + 1..];
+        let domain = domain[..domain.find(|c| !is_domain(c)).unwrap_or(domain.len())].trim_end_matches(['.', '-']);
+        (!user.is_empty() && domain.contains('.')).then_some((user, domain))
+    })
+}
+
+/// Returns the host and path of the first URL of `s`, e.g. `("www.example.com", "/a/b")` in `see https://user@www.example.com:8080/a/b?q=1`.
+/// The path is empty if the URL has none.
+pub fn url_parts(s: &str) -> Option<(&str, &str)> {
+    let start = s.find("://")? + 3;
+    let rest = &s[start..];
+    let rest = &rest[..rest.find(|c: char| c.is_whitespace() || "?#\"<>".contains(c)).unwrap_or(rest.len())];
+    let (authority, path) = rest.find('/').map_or((rest, ""), |i| rest.split_at(i));
+    let host = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    let host = host.split_once(':').map_or(host, |(h, _)| h);
+    (!host.is_empty()).then_some((host, path))
+}
+
+new_op1_opt!(EmailUser, "str.email_user",
+    Str -> Str { |s1| {
+        email_parts(s1).map(|(user, _)| user)
+    }}
+);
+
+new_op1_opt!(EmailDomain, "str.email_domain",
+    Str -> Str { |s1| {
+        email_parts(s1).map(|(_, domain)| domain)
+    }}
+);
+
+new_op1_opt!(UrlHost, "str.url_host",
+    Str -> Str { |s1| {
+        url_parts(s1).map(|(host, _)| host)
+    }}
+);
+
+new_op1_opt!(UrlPath, "str.url_path",
+    Str -> Str { |s1| {
+        url_parts(s1).map(|(_, path)| path)
+    }}
+);
+
+/// Widest padding produced by `str.pad_left` and `str.pad_right`, to keep enumeration from allocating huge strings.
+const PAD_WIDTH_LIMIT: i64 = 256;
+
+/// Returns the padding character and the number of times it must be repeated to bring `s` to `width` characters.
+///
+/// The padding string must be a single character; `None` is returned otherwise, or if `width` is out of range.
+fn padding(s: &str, width: i64, pad: &str) -> Option<(char, usize)> {
+    let mut chars = pad.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else { return None };
+    if !(0..=PAD_WIDTH_LIMIT).contains(&width) { return None; }
+    Some((c, (width as usize).saturating_sub(s.chars().count())))
+}
+
+new_op3_opt!(PadLeft, "str.pad_left",
+    (Str, Int, Str) -> Str { |(s1, s2, s3)| {
+        let (c, n) = padding(s1, *s2, s3)?;
+        if n == 0 { return Some(*s1); }
+        Some(std::iter::repeat_n(c, n).chain(s1.chars()).galloc_collect_str())
+    }}
+);
+
+new_op3_opt!(PadRight, "str.pad_right",
+    (Str, Int, Str) -> Str { |(s1, s2, s3)| {
+        let (c, n) = padding(s1, *s2, s3)?;
+        if n == 0 { return Some(*s1); }
+        Some(s1.chars().chain(std::iter::repeat_n(c, n)).galloc_collect_str())
+    }}
+);
+
 #[cfg(test)]
 mod tests {
-    use crate::expr::ops::str::{str_index_of_f, str_index_of_b};
+    use crate::expr::ops::str::{str_index_of_f, str_index_of_b, url_encode, url_decode, email_parts, url_parts, UrlEncode, UrlDecode, PadLeft, PadRight, EqIgnoreCase, IsEmpty, NumericCmp, StrIndexing, ContainsCi, PrefixOfCi, ReplaceCi, find_ignore_case, Trim, TrimStart, TrimEnd, SqueezeWs, TitleCase, Capitalize, Initials, IndexOfNth, ReplaceNth, nth_match, ReplaceAll, Replace};
+    use crate::expr::ops::{Op1, Op2, Op3};
+    use crate::galloc::AllocForExactSizeIter;
+    use crate::value::Value;
 
     #[test]
     fn test_str_index_of_inner() {
@@ -235,5 +600,142 @@ mod tests {
         assert!(str_index_of_b("a s1s1s1 s", "s", 1) == 9);
         assert!(str_index_of_b("a s1s1s1 s", "s", 5) == -1);
     }
-}
 
+    #[test]
+    fn test_url_encode_decode() {
+        assert_eq!(url_encode("a b&c"), "a%20b%26c");
+        assert_eq!(url_decode("a%20b%26c").as_deref(), Some("a b&c"));
+        assert_eq!(url_decode(&url_encode("caf\u{e9}/?x=1")).as_deref(), Some("caf\u{e9}/?x=1"));
+
+        assert_eq!(url_decode("100%"), None);
+        assert_eq!(url_decode("%2"), None);
+        assert_eq!(url_decode("%zz"), None);
+        assert_eq!(url_decode("%FF"), None);
+
+        let input: Value = ["a b&c"].into_iter().galloc_scollect().into();
+        let (ok, encoded) = UrlEncode::default().try_eval(input);
+        assert!(ok);
+        let (ok, decoded) = UrlDecode::default().try_eval(encoded);
+        assert!(ok && decoded == input);
+
+        let malformed: Value = ["a%2", "a%20b"].into_iter().galloc_scollect().into();
+        let (ok, _) = UrlDecode::default().try_eval(malformed);
+        assert!(!ok);
+    }
+
+    #[test]
+    fn test_email_url() {
+        assert_eq!(email_parts("Mail: john.doe+x@mail.example.com."), Some(("john.doe+x", "mail.example.com")));
+        assert_eq!(email_parts("@home or a@b"), None);
+        assert_eq!(url_parts("see https://user@www.example.com:8080/a/b?q=1 now"), Some(("www.example.com", "/a/b")));
+        assert_eq!(url_parts("ftp://host"), Some(("host", "")));
+        assert_eq!(url_parts("example.com/a"), None);
+    }
+
+    #[test]
+    fn test_pad() {
+        let s: Value = ["42", "12345", "7"].into_iter().galloc_scollect().into();
+        let width: Value = [5i64, 3, 3].into_iter().galloc_scollect().into();
+        let zero: Value = ["0", "0", "0"].into_iter().galloc_scollect().into();
+        let (ok, v) = PadLeft::default().try_eval(s, width, zero);
+        assert!(ok);
+        assert_eq!(v.to_str(), &["00042", "12345", "007"]);
+        let dot: Value = [".", ".", "."].into_iter().galloc_scollect().into();
+        assert_eq!(PadRight::default().try_eval(s, width, dot).1.to_str(), &["42...", "12345", "7.."]);
+        let bad: Value = ["ab", "", "0"].into_iter().galloc_scollect().into();
+        assert!(!PadLeft::default().try_eval(s, width, bad).0);
+    }
+
+    #[test]
+    fn test_conditions() {
+        let a: Value = ["10", "Abc", "9.5", "x", ""].into_iter().galloc_scollect().into();
+        let b: Value = ["9", "aBC", " 12 ", "1", ""].into_iter().galloc_scollect().into();
+        assert_eq!(NumericCmp::default().try_eval(a, b).1.to_bool(), &[false, false, true, false, false]);
+        assert_eq!(EqIgnoreCase::default().try_eval(a, b).1.to_bool(), &[false, true, false, false, true]);
+        assert_eq!(IsEmpty::default().try_eval(a).1.to_bool(), &[false, false, false, false, true]);
+    }
+
+    #[test]
+    fn test_case_conversion() {
+        let s: Value = ["jean-luc O'NEIL", "mARY ann", "", "1st ÉTÉ"].into_iter().galloc_scollect().into();
+        assert_eq!(TitleCase::default().try_eval(s).1.to_str(), &["Jean-Luc O'Neil", "Mary Ann", "", "1St Été"]);
+        assert_eq!(Capitalize::default().try_eval(s).1.to_str(), &["Jean-luc o'neil", "Mary ann", "", "1st été"]);
+
+        let sep: Value = [".", "", ". ", "-"].into_iter().galloc_scollect().into();
+        assert_eq!(Initials::default().try_eval(s, sep).1.to_str(), &["j.O.", "ma", "", "1-É-"]);
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let s: Value = ["  a  b ", "\tx\n", "c"].into_iter().galloc_scollect().into();
+        assert_eq!(Trim::default().try_eval(s).1.to_str(), &["a  b", "x", "c"]);
+        assert_eq!(TrimStart::default().try_eval(s).1.to_str(), &["a  b ", "x\n", "c"]);
+        assert_eq!(TrimEnd::default().try_eval(s).1.to_str(), &["  a  b", "\tx", "c"]);
+        assert_eq!(SqueezeWs::default().try_eval(s).1.to_str(), &[" a b ", " x ", "c"]);
+    }
+
+    #[test]
+    fn test_ignore_case() {
+        assert_eq!(find_ignore_case("Total: 3", "TOTAL"), Some((0, 5)));
+        assert_eq!(find_ignore_case("rue de l'ÉTÉ", "été"), Some((9, 14)));
+        assert_eq!(find_ignore_case("abc", ""), Some((0, 0)));
+        assert_eq!(find_ignore_case("abc", "abcd"), None);
+
+        let a: Value = ["Hello World", "ACME Inc.", "x"].into_iter().galloc_scollect().into();
+        let b: Value = ["world", "acme", "X"].into_iter().galloc_scollect().into();
+        let by: Value = ["Moon", "Acme", "y"].into_iter().galloc_scollect().into();
+        assert_eq!(ContainsCi::default().try_eval(a, b).1.to_bool(), &[true, true, true]);
+        assert_eq!(PrefixOfCi::default().try_eval(b, a).1.to_bool(), &[false, true, true]);
+        assert_eq!(ReplaceCi::default().try_eval(a, b, by).1.to_str(), &["Hello Moon", "Acme Inc.", "y"]);
+    }
+
+    #[test]
+    fn test_str_indexing() {
+        for ix in [StrIndexing::Bytes, StrIndexing::Chars] {
+            assert_eq!(ix.len("abc"), 3);
+            assert_eq!(ix.slice("abcd", 1, 3), "bc");
+            assert_eq!(ix.offset("abc", 3), Some(3));
+            assert_eq!(ix.offset("abc", 4), None);
+        }
+        let ix = StrIndexing::Chars;
+        assert_eq!(ix.len("café 👍🏽"), 7);
+        assert_eq!(ix.slice("café 👍🏽!", 3, 6), "é 👍");
+        assert_eq!(ix.position("naïve", "naïve".find('v').unwrap()), 3);
+        assert_eq!(StrIndexing::Bytes.len("café"), 5);
+
+        // The operations take the indexing of the run; their semantics are tested here on characters without setting it
+        assert_eq!([ix.substr("café", 3, 1), ix.substr("😀 smile", 0, 1), ix.substr("abc", 1, 5)], ["é", "😀", "bc"]);
+        assert_eq!([ix.substr("abc", 3, 1), ix.substr("abc", -1, 1), ix.substr("abc", 0, -1)], ["", "", ""]);
+        assert_eq!([ix.at("café", 3), ix.at("😀 smile", 0), ix.at("abc", 1), ix.at("abc", 3)], ["é", "😀", "b", ""]);
+        assert_eq!([ix.len("café"), ix.len("😀 smile"), ix.len("abc")], [4, 7, 3]);
+        assert_eq!([ix.index_of("café", "é", 0), ix.index_of("😀 smile", " ", 0), ix.index_of("abc", "c", 0)], [3, 1, 2]);
+        assert_eq!([ix.index_of("café", "é", 4), ix.index_of("café", "", 4), ix.index_of("café", "", 5)], [-1, 4, -1]);
+        assert_eq!(StrIndexing::Bytes.index_of("café", "é", 0), 3);
+    }
+
+    #[test]
+    fn test_replace_all() {
+        let s: Value = ["555-123-4567", "a-b", "x"].into_iter().galloc_scollect().into();
+        let dash: Value = ["-", "-", ""].into_iter().galloc_scollect().into();
+        let dot: Value = [".", ".", "."].into_iter().galloc_scollect().into();
+        assert_eq!(ReplaceAll::default().try_eval(s, dash, dot).1.to_str(), &["555.123.4567", "a.b", "x"]);
+        assert_eq!(Replace::default().try_eval(s, dash, dot).1.to_str(), &["555.123-4567", "a.b", ".x"]);
+    }
+
+    #[test]
+    fn test_nth_match() {
+        assert_eq!(nth_match("a-b-c-d", "-", 2), Some(3));
+        assert_eq!(nth_match("a-b-c-d", "-", -1), Some(5));
+        assert_eq!(nth_match("a-b-c-d", "-", 4), None);
+        assert_eq!(nth_match("aaaa", "aa", -1), Some(2));
+        assert_eq!(nth_match("abc", "", 1), None);
+
+        let s: Value = ["555-123-4567", "a-b", "x"].into_iter().galloc_scollect().into();
+        let dash: Value = ["-", "-", "-"].into_iter().galloc_scollect().into();
+        let k: Value = [2i64, -1, 1].into_iter().galloc_scollect().into();
+        assert_eq!(IndexOfNth::default().try_eval(s, dash, k).1.to_int(), &[7, 1, -1]);
+        let by: Value = [" ", " ", " "].into_iter().galloc_scollect().into();
+        assert_eq!(ReplaceNth::default().try_eval(s, dash, by).1.to_str(), &["555-123 4567", "a-b", "x"]);
+        assert_eq!(ReplaceNth(1, -1).try_eval(s, dash, by).1.to_str(), &["555-123 4567", "a b", "x"]);
+    }
+}
@@ -0,0 +1,81 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use regex::Regex;
+
+use crate::{galloc::{AllocForExactSizeIter, AllocForStr}, new_op2_opt, new_op3_opt};
+
+/// Maximum number of patterns kept in the per-thread compilation cache before it is flushed.
+const REGEX_CACHE_LIMIT: usize = 1024;
+
+thread_local! {
+    static REGEX_CACHE: RefCell<HashMap<String, Option<Regex>>> = RefCell::new(HashMap::new());
+}
+
+/// Compiles a regular expression, reusing previous compilations on the same thread.
+///
+/// Enumeration evaluates the same few patterns (usually grammar constants) over and over, so compiling them each time would dominate the cost of regex operators.
+/// Invalid patterns are cached as `None` as well.
+pub fn compile_regex(pattern: &str) -> Option<Regex> {
+    REGEX_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if let Some(r) = cache.get(pattern) {
+            return r.clone();
+        }
+        if cache.len() >= REGEX_CACHE_LIMIT { cache.clear(); }
+        let r = Regex::new(pattern).ok();
+        cache.insert(pattern.to_string(), r.clone());
+        r
+    })
+}
+
+new_op3_opt!(ReplaceRe, "str.replace_re",
+    (Str, Str, Str) -> Str { |(s1, s2, s3)| {
+        let re = compile_regex(s2)?;
+        Some(re.replace(s1, regex::NoExpand(s3)).galloc_str())
+    }}
+);
+
+new_op2_opt!(Match, "str.match",
+    (Str, Str) -> Bool { |(s1, s2)| {
+        Some(compile_regex(s2)?.is_match(s1))
+    }}
+);
+
+new_op2_opt!(ExtractRe, "str.extract_re",
+    (Str, Str) -> Str { |(s1, s2)| {
+        let re = compile_regex(s2)?;
+        Some(re.find(s1).map(|m| m.as_str()).unwrap_or("").galloc_str())
+    }}
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::expr::ops::{Op2, Op3};
+    use crate::galloc::AllocForExactSizeIter;
+    use crate::value::Value;
+
+    use super::{compile_regex, ExtractRe, Match, ReplaceRe};
+
+    #[test]
+    fn test_regex_ops() {
+        let input: Value = ["Order 42 shipped", "no number", "12-34"].into_iter().galloc_scollect().into();
+        let pattern: Value = ["[0-9]+", "[0-9]+", "[0-9]+"].into_iter().galloc_scollect().into();
+
+        let (ok, extracted) = ExtractRe::default().try_eval(input, pattern);
+        assert!(ok);
+        assert_eq!(extracted.to_str(), &["42", "", "12"]);
+
+        let (ok, matched) = Match::default().try_eval(input, pattern);
+        assert!(ok);
+        assert_eq!(matched.to_bool(), &[true, false, true]);
+
+        let by: Value = ["#", "#", "$0"].into_iter().galloc_scollect().into();
+        let (ok, replaced) = ReplaceRe::default().try_eval(input, pattern, by);
+        assert!(ok);
+        assert_eq!(replaced.to_str(), &["Order # shipped", "no number", "$0-34"]);
+
+        let invalid: Value = ["(", "(", "("].into_iter().galloc_scollect().into();
+        assert!(!ExtractRe::default().try_eval(input, invalid).0);
+        assert!(compile_regex("(").is_none());
+    }
+}
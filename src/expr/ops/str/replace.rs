@@ -64,20 +64,25 @@ impl Enumerator3 for Replace {
     /// The process ensures that only valid expressions with feasible evaluations are considered, thus optimizing the string synthesis tasks.
     /// 
     fn enumerate(&self, this: &'static crate::expr::ops::Op3Enum, exec: &'static crate::forward::executor::Executor, nt: [usize; 3]) -> Result<(), ()> {
-        if exec.size() < self.cost() { return Ok(()); }
-        let total = exec.size() - self.cost();
-        for (i, (e2, v2)) in exec.data[nt[0]].size.get_all_under(min(total, self.1)) {
-            for (j, (e3, v3)) in exec.data[nt[1]].size.get_all_under(min(total - i, self.1)) {
-                for (e1, v1) in exec.data[nt[2]].size.get_all(total - i - j) {
-                    let expr = Expr::Op3(this, e1, e2, e3);
-                    if let (true, value) = self.try_eval(*v1, *v2, *v3) {
-                        exec.enum_expr(expr, value)?;
-                    }
+        enumerate_replace(self, self.1, this, exec, nt)
+    }
+}
+
+/// Enumerates a replacement whose pattern and replacement strings are bounded in size by `limit`, shared by `str.replace` and `str.replace_all`.
+fn enumerate_replace(op: &impl Op3, limit: usize, this: &'static crate::expr::ops::Op3Enum, exec: &'static crate::forward::executor::Executor, nt: [usize; 3]) -> Result<(), ()> {
+    if exec.size() < op.cost() { return Ok(()); }
+    let total = exec.size() - op.cost();
+    for (i, (e2, v2)) in exec.data[nt[0]].size.get_all_under(min(total, limit)) {
+        for (j, (e3, v3)) in exec.data[nt[1]].size.get_all_under(min(total - i, limit)) {
+            for (e1, v1) in exec.data[nt[2]].size.get_all(total - i - j) {
+                let expr = Expr::Op3(this, e1, e2, e3);
+                if let (true, value) = op.try_eval(*v1, *v2, *v3) {
+                    exec.enum_expr(expr, value)?;
                 }
-            } 
-        }
-        Ok(())
+            }
+        } 
     }
+    Ok(())
 }
 
 impl Op3 for Replace {
@@ -108,3 +113,102 @@ impl Op3 for Replace {
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// `str.replace_all` of SMT-LIB: replaces every occurrence of a substring, where `str.replace` only replaces the first.
+///
+/// Configured as `Replace`, with its own `cost` and `enum_replace_cost` keys.
+pub struct ReplaceAll(pub usize, pub usize);
+
+impl ReplaceAll {
+    pub fn from_config(config: &Config) -> Self {
+        Self(config.get_usize("cost").unwrap_or(1), config.get_usize("enum_replace_cost").unwrap_or(3))
+    }
+    pub fn name() -> &'static str {
+        "str.replace_all"
+    }
+}
+
+impl std::fmt::Display for ReplaceAll {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Self::name().fmt(f)
+    }
+}
+
+impl Default for ReplaceAll {
+    fn default() -> Self {
+        Self::from_config(&Default::default())
+    }
+}
+
+impl Enumerator3 for ReplaceAll {
+    fn enumerate(&self, this: &'static crate::expr::ops::Op3Enum, exec: &'static crate::forward::executor::Executor, nt: [usize; 3]) -> Result<(), ()> {
+        enumerate_replace(self, self.1, this, exec, nt)
+    }
+}
+
+impl Op3 for ReplaceAll {
+    fn cost(&self) -> usize {
+        self.0
+    }
+    /// An empty pattern leaves the string unchanged, as in SMT-LIB.
+    fn try_eval(&self, a1: Value, a2: Value, a3: Value) -> (bool, Value) {
+        match (a1, a2, a3) {
+            (Value::Str(s1), Value::Str(s2), Value::Str(s3)) => (true, Value::Str(
+                itertools::izip!(s1.iter(), s2.iter(), s3.iter())
+                    .map(|(s1, s2, s3)| if s2.is_empty() || !s1.contains(*s2) { *s1 } else { s1.replace(*s2, s3).galloc_str() })
+                    .galloc_scollect(),
+            )),
+            _ => (false, Value::Null),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// `str.replace_nth`: replaces only the `n`-th occurrence of a substring, as in "replace the second dash".
+///
+/// The cost is followed by `n`, read from the `n` key of the operator configuration (2 by default); a negative `n` counts the occurrences from the end.
+pub struct ReplaceNth(pub usize, pub i64);
+
+impl ReplaceNth {
+    pub fn from_config(config: &Config) -> Self {
+        Self(config.get_usize("cost").unwrap_or(1), config.get_i64("n").unwrap_or(2))
+    }
+    pub fn name() -> &'static str {
+        "str.replace_nth"
+    }
+}
+
+impl std::fmt::Display for ReplaceNth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} #n:{}", Self::name(), self.1)
+    }
+}
+
+impl Default for ReplaceNth {
+    fn default() -> Self {
+        Self::from_config(&Default::default())
+    }
+}
+
+impl Enumerator3 for ReplaceNth {}
+
+impl Op3 for ReplaceNth {
+    fn cost(&self) -> usize {
+        self.0
+    }
+    /// Leaves the string unchanged when it has fewer than `n` occurrences of the substring.
+    fn try_eval(&self, a1: Value, a2: Value, a3: Value) -> (bool, Value) {
+        match (a1, a2, a3) {
+            (Value::Str(s1), Value::Str(s2), Value::Str(s3)) => (true, Value::Str(
+                itertools::izip!(s1.iter(), s2.iter(), s3.iter())
+                    .map(|(s1, s2, s3)| match super::nth_match(s1, s2, self.1) {
+                        Some(i) => [&s1[..i], *s3, &s1[i + s2.len()..]].concat().galloc_str(),
+                        None => *s1,
+                    })
+                    .galloc_scollect(),
+            )),
+            _ => (false, Value::Null),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use crate::galloc::AllocForExactSizeIter;
+use crate::{new_op1, new_op2};
+
+use super::{Op1, Op2};
+
+new_op2!(TupleMake, "tuple.make",
+    (Str, Str) -> Tuple { |(a, b)| (*a, *b) }
+);
+
+new_op1!(TupleFst, "tuple.fst",
+    Tuple -> Str { |t| t.0 }
+);
+
+new_op1!(TupleSnd, "tuple.snd",
+    Tuple -> Str { |t| t.1 }
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::expr::ops::{Op1, Op2};
+    use crate::galloc::AllocForExactSizeIter;
+    use crate::value::Value;
+
+    use super::{TupleFst, TupleMake, TupleSnd};
+
+    #[test]
+    fn test_tuple_ops() {
+        let first: Value = ["John", "Jane"].into_iter().galloc_scollect().into();
+        let last: Value = ["Smith", "Doe"].into_iter().galloc_scollect().into();
+        let (ok, t) = TupleMake::default().try_eval(first, last);
+        assert!(ok);
+        assert_eq!(t, Value::Tuple(&[("John", "Smith"), ("Jane", "Doe")]));
+        assert_eq!(TupleFst::default().try_eval(t).1, first);
+        assert_eq!(TupleSnd::default().try_eval(t).1, last);
+        assert!(!TupleFst::default().try_eval(first).0);
+    }
+}
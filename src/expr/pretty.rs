@@ -0,0 +1,229 @@
+use std::sync::OnceLock;
+
+use itertools::Itertools;
+
+use crate::parser::problem::FunSig;
+
+use super::{ops::Op3Enum, Expr};
+
+/// Width of one level of indentation.
+const INDENT: usize = 4;
+
+/// Printed in place of the subexpressions below the maximum depth.
+const ELIDED: &str = "…";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+/// Notation of [`Expr::pretty`].
+pub enum Notation {
+    /// S-expressions, as in SyGuS
+    #[default]
+    Sexpr,
+    /// Infix arithmetic and comparisons, calls `op(a, b)`, and `if`/`elif`/`else` chains, in the manner of Python
+    Infix,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// How [`Expr::pretty`] lays out an expression.
+pub struct Style {
+    pub notation: Notation,
+    /// Operations nested deeper than this are elided as `…`; the branches of an `ite` chain are at the depth of the chain.
+    pub max_depth: Option<usize>,
+}
+
+impl Style {
+    pub fn new(notation: Notation, max_depth: Option<usize>) -> Self {
+        Self { notation, max_depth }
+    }
+}
+
+/// The infix symbol of a binary operator, if it has one.
+fn infix(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "str.++" | "int.+" | "float.+" | "time.+" => "+",
+        "int.-" | "float.-" => "-",
+        "int.*" | "time.*" => "*",
+        "int.div" => "//",
+        "int.mod" => "%",
+        "=" => "==",
+        "int.lt" | "float.lt" => "<",
+        "int.le" | "float.le" => "<=",
+        "int.gt" | "float.gt" => ">",
+        "int.ge" | "float.ge" => ">=",
+        _ => return None,
+    })
+}
+
+/// Indents all lines of `s` but the first by `n` spaces.
+fn indent(s: &str, n: usize) -> String {
+    s.lines().join(&format!("\n{}", " ".repeat(n)))
+}
+
+/// Whether `e` is an `ite` with another `ite` in one of its branches, laid out over several lines.
+fn is_chain(e: &Expr) -> bool {
+    match e {
+        Expr::Op3(Op3Enum::Ite(_), _, t, f) => is_ite(t) || is_ite(f),
+        _ => false,
+    }
+}
+
+fn is_ite(e: &Expr) -> bool {
+    matches!(e, Expr::Op3(Op3Enum::Ite(_), _, _, _))
+}
+
+struct Printer<'a> {
+    sig: &'a FunSig,
+    style: Style,
+}
+
+impl Printer<'_> {
+    fn elided(&self, depth: usize) -> bool {
+        self.style.max_depth.is_some_and(|d| depth > d)
+    }
+    fn leaf(&self, e: &Expr) -> Option<String> {
+        match e {
+            Expr::Const(c) => Some(format!("{:?}", c)),
+            Expr::Var(v) if *v < 0 => Some(Expr::let_name(*v)),
+            Expr::Var(v) => Some(self.sig.args.get(*v as usize).map_or(format!("<{v}>"), |a| a.0.clone())),
+            _ => None,
+        }
+    }
+    /// The operator of an operation and its arguments, with the configuration of the operator (`#key:value`) apart from its name.
+    fn parts(e: &Expr) -> (String, String, Vec<&Expr>) {
+        let (name, display, args) = match e {
+            Expr::Op1(op, a1) => (op.name(), op.to_string(), vec![*a1]),
+            Expr::Op2(op, a1, a2) => (op.name(), op.to_string(), vec![*a1, *a2]),
+            Expr::Op3(op, a1, a2, a3) => (op.name(), op.to_string(), vec![*a1, *a2, *a3]),
+            _ => unreachable!(),
+        };
+        match display.strip_prefix(name) {
+            Some(config) => (name.to_string(), config.trim().to_string(), args),
+            // Operators named after a declaration, such as scanners
+            None => (display, String::new(), args),
+        }
+    }
+
+    fn sexpr(&self, e: &Expr, depth: usize) -> String {
+        if let Some(s) = self.leaf(e) { return s; }
+        if self.elided(depth) { return ELIDED.to_string(); }
+        match e {
+            Expr::Let(v, a, body) => format!("(let (({} {})) {})", Expr::let_name(*v), self.sexpr(a, depth + 1), self.sexpr(body, depth + 1)),
+            Expr::Op3(op, c, t, f) if is_chain(e) => {
+                let pad = " ".repeat(INDENT);
+                let branch = |b: &Expr| indent(&self.sexpr(b, depth), INDENT);
+                format!("({} {}\n{pad}{}\n{pad}{})", op, self.sexpr(c, depth + 1), branch(t), branch(f))
+            }
+            _ => {
+                let (name, config, args) = Self::parts(e);
+                let op = if config.is_empty() { name.to_string() } else { format!("{name} {config}") };
+                let args = args.iter().map(|a| self.sexpr(a, depth + 1)).collect_vec();
+                if args.iter().any(|a| a.contains('\n')) {
+                    format!("({}\n{}{})", op, " ".repeat(INDENT), args.iter().map(|a| indent(a, INDENT)).join(&format!("\n{}", " ".repeat(INDENT))))
+                } else {
+                    format!("({} {})", op, args.join(" "))
+                }
+            }
+        }
+    }
+
+    /// Prints an operand of an infix operator, parenthesized unless it is a leaf or a call.
+    fn operand(&self, e: &Expr, depth: usize) -> String {
+        let s = self.infix(e, depth);
+        let bare = self.leaf(e).is_some() || s == ELIDED || match e {
+            Expr::Op2(op, _, _) => infix(op.name()).is_none(),
+            Expr::Op1(op, _) => !matches!(op.name(), "int.neg" | "float.neg"),
+            Expr::Op3(..) => !is_ite(e),
+            _ => false,
+        };
+        if bare { s } else if s.contains('\n') { format!("(\n{}{}\n)", " ".repeat(INDENT), indent(&s, INDENT)) } else { format!("({s})") }
+    }
+
+    fn infix(&self, e: &Expr, depth: usize) -> String {
+        if let Some(s) = self.leaf(e) { return s; }
+        if self.elided(depth) { return ELIDED.to_string(); }
+        match e {
+            Expr::Let(v, a, body) => format!("{} = {}\n{}", Expr::let_name(*v), self.infix(a, depth + 1), self.infix(body, depth)),
+            Expr::Op3(Op3Enum::Ite(_), ..) if is_chain(e) => {
+                let mut lines = Vec::new();
+                let mut cur = e;
+                while let Expr::Op3(Op3Enum::Ite(_), c, t, f) = cur {
+                    let kw = if lines.is_empty() { "if" } else { "elif" };
+                    lines.push(format!("{kw} {}: {}", self.infix(c, depth + 1), indent(&self.infix(t, depth), INDENT)));
+                    cur = f;
+                }
+                lines.push(format!("else: {}", indent(&self.infix(cur, depth), INDENT)));
+                lines.join("\n")
+            }
+            Expr::Op3(Op3Enum::Ite(_), c, t, f) => format!("{} if {} else {}", self.operand(t, depth + 1), self.operand(c, depth + 1), self.operand(f, depth + 1)),
+            Expr::Op2(op, a1, a2) if infix(op.name()).is_some() => {
+                format!("{} {} {}", self.operand(a1, depth + 1), infix(op.name()).unwrap(), self.operand(a2, depth + 1))
+            }
+            Expr::Op1(op, a1) if matches!(op.name(), "int.neg" | "float.neg") => format!("-{}", self.operand(a1, depth + 1)),
+            _ => {
+                let (name, config, args) = Self::parts(e);
+                let mut args = args.iter().map(|a| self.infix(a, depth + 1)).collect_vec();
+                if !config.is_empty() { args.push(config); }
+                if args.iter().any(|a| a.contains('\n')) {
+                    let pad = " ".repeat(INDENT);
+                    format!("{name}(\n{pad}{}\n)", args.iter().map(|a| indent(a, INDENT)).join(&format!(",\n{pad}")))
+                } else {
+                    format!("{name}({})", args.join(", "))
+                }
+            }
+        }
+    }
+}
+
+/// Signature and style of the expressions written in logs, once set by [`enable`].
+static LOG_STYLE: OnceLock<(FunSig, Style)> = OnceLock::new();
+
+/// Writes the solutions in logs with [`Expr::pretty`] instead of debug s-expressions (`--pretty`).
+pub fn enable(sig: &FunSig, style: Style) {
+    let _ = LOG_STYLE.set((sig.clone(), style));
+}
+
+/// Formats `e` for logs: pretty on a new line if [`enable`]d, as a debug s-expression otherwise.
+pub fn log(e: &Expr) -> String {
+    match LOG_STYLE.get() {
+        Some((sig, style)) => format!("\n{}", e.pretty(sig, *style)),
+        None => format!("{:?}", e),
+    }
+}
+
+impl Expr {
+    /// Formats the expression for humans, with the arguments named after `sig`: nested `ite` chains are broken over several lines, and subexpressions deeper than the maximum depth of `style` are elided.
+    pub fn pretty(&self, sig: &FunSig, style: Style) -> String {
+        let printer = Printer { sig, style };
+        match style.notation {
+            Notation::Sexpr => printer.sexpr(self, 0),
+            Notation::Infix => printer.infix(self, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr, galloc::AllocForAny, parser::problem::FunSig, value::Type};
+
+    use super::{Notation, Style};
+
+    #[test]
+    fn test_pretty() {
+        let sig = FunSig { name: "f".into(), args: vec![("name".into(), Type::Str), ("age".into(), Type::Int)], rettype: Type::Str };
+        let e = expr!(Ite (IsEmpty [0]) "none" (Ite (PrefixOf "Dr" [0]) (Concat "Dr. " (SubStr [0] 3 [1])) [0])).galloc();
+        assert_eq!(e.pretty(&sig, Style::new(Notation::Infix, None)), concat!(
+            "if str.is_empty(name): \"none\"\n",
+            "elif str.prefixof(\"Dr\", name): \"Dr. \" + str.substr(name, 3, age)\n",
+            "else: name",
+        ));
+        assert_eq!(e.pretty(&sig, Style::new(Notation::Sexpr, None)), concat!(
+            "(ite (str.is_empty name)\n",
+            "    \"none\"\n",
+            "    (ite (str.prefixof \"Dr\" name) (str.++ \"Dr. \" (str.substr name 3 age)) name))",
+        ));
+        assert_eq!(e.pretty(&sig, Style::new(Notation::Infix, Some(0))), concat!(
+            "if …: \"none\"\n",
+            "elif …: \"Dr. \" + …\n",
+            "else: name",
+        ));
+    }
+}
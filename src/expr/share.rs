@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use crate::{debg, galloc::AllocForAny};
+
+use super::Expr;
+
+/// Subexpressions with fewer nodes (i.e. variables and constants) are left in place, as binding them would not shorten the output.
+const MIN_SIZE: usize = 2;
+
+/// Counts the occurrences of the subexpressions of `e`, recording them in `order` when first seen.
+///
+/// The children of a subexpression are only counted at its first occurrence: once the repeated subexpression is bound, the other copies disappear with their children.
+fn count(e: &'static Expr, counts: &mut HashMap<&'static Expr, usize>, order: &mut Vec<&'static Expr>) {
+    let n = counts.entry(e).or_insert(0);
+    *n += 1;
+    if *n > 1 { return; }
+    order.push(e);
+    match e {
+        Expr::Const(_) | Expr::Var(_) => (),
+        Expr::Op1(_, a1) => count(a1, counts, order),
+        Expr::Op2(_, a1, a2) | Expr::Let(_, a1, a2) => {
+            count(a1, counts, order);
+            count(a2, counts, order);
+        }
+        Expr::Op3(_, a1, a2, a3) => {
+            count(a1, counts, order);
+            count(a2, counts, order);
+            count(a3, counts, order);
+        }
+    }
+}
+
+/// Returns whether `e` uses a variable bound by a `let`.
+fn uses_bound(e: &Expr) -> bool {
+    match e {
+        Expr::Const(_) => false,
+        Expr::Var(v) => *v < 0,
+        Expr::Op1(_, a1) => uses_bound(a1),
+        Expr::Op2(_, a1, a2) | Expr::Let(_, a1, a2) => uses_bound(a1) || uses_bound(a2),
+        Expr::Op3(_, a1, a2, a3) => uses_bound(a1) || uses_bound(a2) || uses_bound(a3),
+    }
+}
+
+/// Replaces every occurrence of `target` in `e` by `by`.
+fn replace(e: &'static Expr, target: &'static Expr, by: &'static Expr) -> &'static Expr {
+    if e == target { return by; }
+    match *e {
+        Expr::Const(_) | Expr::Var(_) => e,
+        Expr::Op1(op, a1) => Expr::Op1(op, replace(a1, target, by)).galloc(),
+        Expr::Op2(op, a1, a2) => Expr::Op2(op, replace(a1, target, by), replace(a2, target, by)).galloc(),
+        Expr::Op3(op, a1, a2, a3) => Expr::Op3(op, replace(a1, target, by), replace(a2, target, by), replace(a3, target, by)).galloc(),
+        Expr::Let(v, a1, a2) => Expr::Let(v, replace(a1, target, by), replace(a2, target, by)).galloc(),
+    }
+}
+
+/// Renumbers the variables bound by `n` `let`s in binding order, the first bound being `Var(!0)`, so that the outermost `let` binds `Var(!0)`.
+fn renumber(e: &'static Expr, n: i64) -> &'static Expr {
+    match *e {
+        Expr::Var(v) if v < 0 => Expr::Var(!(n - 1 - !v)).galloc(),
+        Expr::Const(_) | Expr::Var(_) => e,
+        Expr::Op1(op, a1) => Expr::Op1(op, renumber(a1, n)).galloc(),
+        Expr::Op2(op, a1, a2) => Expr::Op2(op, renumber(a1, n), renumber(a2, n)).galloc(),
+        Expr::Op3(op, a1, a2, a3) => Expr::Op3(op, renumber(a1, n), renumber(a2, n), renumber(a3, n)).galloc(),
+        Expr::Let(v, a1, a2) => Expr::Let(!(n - 1 - !v), renumber(a1, n), renumber(a2, n)).galloc(),
+    }
+}
+
+/// Binds the subexpressions occurring several times in `e` with `let`, so that each is written once.
+///
+/// The largest repeated subexpression is bound first, then the search goes on in the remaining expression and the bound ones.
+/// Subexpressions using a bound variable are not bound themselves, so that every `let` can enclose the ones bound before it.
+/// Bound variables are numbered from `_t0` by nesting, the outermost `let` binding `_t0` as when the result is parsed back; `e` should not already use negative variables.
+pub fn share(e: &'static Expr) -> &'static Expr {
+    let mut bindings: Vec<(i64, &'static Expr)> = Vec::new();
+    let mut body = e;
+    loop {
+        let (mut counts, mut order) = (HashMap::new(), Vec::new());
+        count(body, &mut counts, &mut order);
+        for (_, b) in bindings.iter() {
+            count(b, &mut counts, &mut order);
+        }
+        let Some(target) = order.into_iter()
+            .filter(|t| counts[t] > 1 && t.size() >= MIN_SIZE && !uses_bound(t))
+            .max_by_key(|t| t.size()) else { break };
+        let v = !(bindings.len() as i64);
+        let var = Expr::Var(v).galloc();
+        debg!("Share {:?} as {}", target, Expr::let_name(v));
+        body = replace(body, target, var);
+        for b in bindings.iter_mut() {
+            b.1 = replace(b.1, target, var);
+        }
+        bindings.push((v, target));
+    }
+    // The last bound is the outermost `let`.
+    let n = bindings.len() as i64;
+    bindings.into_iter().fold(renumber(body, n), |body, (v, b)| Expr::Let(!(n - 1 - !v), renumber(b, n), body).galloc())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr, expr::{context::Context, Expr}, galloc::{AllocForAny, AllocForExactSizeIter}, parser::{check::DefineFun, problem::FunSig}, tuning::parse_solution, value::{Type, Value}};
+
+    use super::share;
+
+    #[test]
+    fn test_share() {
+        let input: Value = ["Hello World", "ab"].into_iter().galloc_scollect().into();
+        let ctx = Context::new(2, vec![input], Vec::new(), Value::Null);
+        let sig = FunSig { name: "f".into(), args: vec![("name".into(), Type::Str)], rettype: Type::Str };
+
+        let e = expr!(Ite (PrefixOf "H" (Uppercase [0])) (Concat (Uppercase [0]) "!") (Uppercase [0])).galloc();
+        let s = share(e);
+        assert_eq!(s.eval(&ctx), e.eval(&ctx));
+        assert_eq!(s.format(&sig), "(let ((_t0 (str.uppercase name))) (ite (str.prefixof \"H\" _t0) (str.++ _t0 \"!\") _t0))");
+
+        let e = expr!(Concat (Concat (SubStr [0] 0 1) (SubStr [0] 0 1)) (Concat (SubStr [0] 0 1) (SubStr [0] 0 1))).galloc();
+        let s = share(e);
+        assert_eq!(s.eval(&ctx), e.eval(&ctx));
+        assert!(matches!(s, Expr::Let(-1, _, Expr::Let(-2, _, _))));
+        let parsed = parse_solution(&DefineFun { sig: sig.clone(), expr: s }.to_string()).unwrap();
+        assert_eq!(parsed.expr, s);
+
+        let e = expr!(Concat [0] [0]).galloc();
+        assert_eq!(share(e), e);
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{debg, galloc::{AllocForAny, AllocForStr}, value::{ConstValue, Value}};
+
+use super::{context::Context, ops::{Op1Enum, Op2Enum, Op3Enum}, Expr};
+
+/// A rewrite rule: returns a smaller expression equivalent to `e`, whose children are already simplified, or `None` if the rule does not apply.
+pub type Rule = fn(&'static Expr) -> Option<&'static Expr>;
+
+/// Rewrite rules, tried in order at each node.
+pub const RULES: &[(&str, Rule)] = &[
+    ("fold", fold_constants),
+    ("concat-empty", concat_empty),
+    ("concat-consts", concat_consts),
+    ("substr-fusion", substr_fusion),
+    ("ite-same", ite_same),
+    ("int-identity", int_identity),
+    ("neg-neg", neg_neg),
+];
+
+/// Replaces an operation without variables by its value.
+fn fold_constants(e: &'static Expr) -> Option<&'static Expr> {
+    if matches!(e, Expr::Const(_)) || e.has_var() { return None; }
+    let v = e.eval(&Context::new(1, Vec::new(), Vec::new(), Value::Null));
+    if matches!(v, Value::Null | Value::ListInt(_) | Value::ListStr(_)) || v.len() != 1 { return None; }
+    Some(Expr::Const(v.get_const(0)).galloc())
+}
+
+fn as_str(e: &Expr) -> Option<&'static str> {
+    if let Expr::Const(ConstValue::Str(s)) = e { Some(s) } else { None }
+}
+
+fn as_int(e: &Expr) -> Option<i64> {
+    if let Expr::Const(ConstValue::Int(i)) = e { Some(*i) } else { None }
+}
+
+/// `(str.++ x "")` and `(str.++ "" x)` are `x`.
+fn concat_empty(e: &'static Expr) -> Option<&'static Expr> {
+    let Expr::Op2(Op2Enum::Concat(_), a1, a2) = e else { return None };
+    if as_str(a2) == Some("") { Some(*a1) }
+    else if as_str(a1) == Some("") { Some(*a2) }
+    else { None }
+}
+
+/// Merges adjacent constants of nested concatenations: `(str.++ (str.++ x "a") "b")` is `(str.++ x "ab")`, and symmetrically on the left.
+fn concat_consts(e: &'static Expr) -> Option<&'static Expr> {
+    let Expr::Op2(op @ Op2Enum::Concat(_), a1, a2) = *e else { return None };
+    let merged = |s1: &str, s2: &str| Expr::Const(ConstValue::Str(format!("{s1}{s2}").galloc_str())).galloc();
+    match (a1, a2) {
+        (Expr::Op2(Op2Enum::Concat(_), x, c1), c2) if as_str(c1).is_some() && as_str(c2).is_some() => {
+            Some(Expr::Op2(op, x, merged(as_str(c1)?, as_str(c2)?)).galloc())
+        }
+        (c1, Expr::Op2(Op2Enum::Concat(_), c2, x)) if as_str(c1).is_some() && as_str(c2).is_some() => {
+            Some(Expr::Op2(op, merged(as_str(c1)?, as_str(c2)?), x).galloc())
+        }
+        _ => None,
+    }
+}
+
+/// `(str.substr (str.substr x i n) j m)` is `(str.substr x (+ i j) (min m (- n j)))` for non-negative constants with `j < n`.
+fn substr_fusion(e: &'static Expr) -> Option<&'static Expr> {
+    let Expr::Op3(op @ Op3Enum::SubStr(_), inner, j, m) = *e else { return None };
+    let Expr::Op3(Op3Enum::SubStr(_), x, i, n) = inner else { return None };
+    let (i, n, j, m) = (as_int(i)?, as_int(n)?, as_int(j)?, as_int(m)?);
+    if i < 0 || n < 0 || j < 0 || m < 0 || j >= n { return None; }
+    let int = |v: i64| Expr::Const(ConstValue::Int(v)).galloc();
+    Some(Expr::Op3(op, x, int(i + j), int(m.min(n - j))).galloc())
+}
+
+/// `(ite c x x)` is `x`.
+fn ite_same(e: &'static Expr) -> Option<&'static Expr> {
+    let Expr::Op3(Op3Enum::Ite(_), _, a1, a2) = e else { return None };
+    (a1 == a2).then_some(*a1)
+}
+
+/// `(int.+ x 0)`, `(int.+ 0 x)` and `(int.- x 0)` are `x`.
+fn int_identity(e: &'static Expr) -> Option<&'static Expr> {
+    match e {
+        Expr::Op2(Op2Enum::Add(_) | Op2Enum::Sub(_), a1, a2) if as_int(a2) == Some(0) => Some(*a1),
+        Expr::Op2(Op2Enum::Add(_), a1, a2) if as_int(a1) == Some(0) => Some(*a2),
+        _ => None,
+    }
+}
+
+/// `(int.neg (int.neg x))` is `x`.
+fn neg_neg(e: &'static Expr) -> Option<&'static Expr> {
+    let Expr::Op1(Op1Enum::Neg(_), a1) = e else { return None };
+    let Expr::Op1(Op1Enum::Neg(_), x) = a1 else { return None };
+    Some(*x)
+}
+
+/// Rebuilds `e` with simplified children.
+fn simplify_children(e: &'static Expr, ctx: &Context) -> &'static Expr {
+    match *e {
+        Expr::Const(_) | Expr::Var(_) => e,
+        Expr::Op1(op, a1) => {
+            let b1 = simplify(a1, ctx);
+            if b1 == a1 { e } else { Expr::Op1(op, b1).galloc() }
+        }
+        Expr::Op2(op, a1, a2) => {
+            let (b1, b2) = (simplify(a1, ctx), simplify(a2, ctx));
+            if b1 == a1 && b2 == a2 { e } else { Expr::Op2(op, b1, b2).galloc() }
+        }
+        Expr::Op3(op, a1, a2, a3) => {
+            let (b1, b2, b3) = (simplify(a1, ctx), simplify(a2, ctx), simplify(a3, ctx));
+            if b1 == a1 && b2 == a2 && b3 == a3 { e } else { Expr::Op3(op, b1, b2, b3).galloc() }
+        }
+        Expr::Let(v, a1, a2) => {
+            let (b1, b2) = (simplify(a1, ctx), simplify(a2, ctx));
+            if b1 == a1 && b2 == a2 { e } else { Expr::Let(v, b1, b2).galloc() }
+        }
+    }
+}
+
+/// Simplifies an expression bottom-up with [`RULES`], until no rule applies.
+///
+/// A rewrite is only kept if it makes the expression smaller and evaluates to the same value on the examples of `ctx`.
+/// The rules are meant to be equivalences, so this check only guards against corner cases of operator semantics (e.g. out-of-range indices), and ensures termination.
+pub fn simplify(e: &'static Expr, ctx: &Context) -> &'static Expr {
+    let mut e = simplify_children(e, ctx);
+    'outer: loop {
+        for (name, rule) in RULES {
+            if let Some(r) = rule(e).filter(|r| r.size() < e.size() && r.eval(ctx) == e.eval(ctx)) {
+                debg!("Simplify ({}): {:?} -> {:?}", name, e, r);
+                e = simplify_children(r, ctx);
+                continue 'outer;
+            }
+        }
+        return e;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr, expr::{context::Context, Expr}, galloc::{AllocForAny, AllocForExactSizeIter}, value::Value};
+
+    use super::simplify;
+
+    fn ctx() -> Context {
+        let input: Value = ["Hello World", "", "ab"].into_iter().galloc_scollect().into();
+        Context::new(3, vec![input], Vec::new(), Value::Null)
+    }
+
+    fn check(e: &'static Expr, expected: &'static Expr) {
+        let ctx = ctx();
+        let s = simplify(e, &ctx);
+        assert_eq!(s, expected);
+        assert_eq!(s.eval(&ctx), e.eval(&ctx));
+    }
+
+    #[test]
+    fn test_simplify() {
+        check(expr!(Concat [0] "").galloc(), expr!([0]).galloc());
+        check(expr!(Concat (Concat [0] (Concat "a" "b")) "c").galloc(), expr!(Concat [0] "abc").galloc());
+        check(expr!(SubStr (SubStr [0] 2 6) 1 10).galloc(), expr!(SubStr [0] 3 5).galloc());
+        check(expr!(Ite (PrefixOf "a" [0]) (Concat "" [0]) [0]).galloc(), expr!([0]).galloc());
+        check(expr!(SubStr (SubStr [0] 2 1) 1 10).galloc(), expr!(SubStr (SubStr [0] 2 1) 1 10).galloc());
+    }
+}
@@ -1,17 +1,20 @@
 
-use std::{cell::UnsafeCell, task::{Poll, Waker}};
+use std::{cell::UnsafeCell, sync::{atomic::Ordering, OnceLock}, task::{Poll, Waker}};
 
 use futures::FutureExt;
 use itertools::Itertools;
+use rayon::{prelude::ParallelIterator, slice::ParallelSlice, ThreadPool};
 use simple_rc_async::sync::oneshot;
 use tokio::task::JoinHandle;
 
-use crate::{expr::{Expr, Expression}, info, utils::UnsafeCellExt};
+use crate::{expr::{Expr, Expression}, galloc, info, utils::UnsafeCellExt, value::Value};
+
+use super::executor::DETERMINISTIC;
 
 
 
 /// a bridge for interthread communication.
-pub struct Bridge(UnsafeCell<Vec<(JoinHandle<Expression>, oneshot::Sender<Expression>)>>);
+pub struct Bridge(UnsafeCell<Vec<(JoinHandle<Option<Expression>>, oneshot::Sender<Expression>)>>);
 
 impl Default for Bridge {
     /// A default constructor for the type. 
@@ -26,7 +29,7 @@ impl Bridge {
         Self(Vec::new().into())
     }
     /// Provides a mutable reference to the inner vector of tuples, each containing a `JoinHandle` and a `oneshot::Sender`, which are used for asynchronous computation and message passing, respectively. 
-    fn inner(&self) -> &mut Vec<(JoinHandle<Expression>, oneshot::Sender<Expression>)> {
+    fn inner(&self) -> &mut Vec<(JoinHandle<Option<Expression>>, oneshot::Sender<Expression>)> {
         unsafe { self.0.as_mut() }
     }
     /// Waits for the completion of a synthesis task and returns a receiver for results. 
@@ -35,8 +38,9 @@ impl Bridge {
     /// It creates a oneshot channel, which is used for sending an expression once the task completes. 
     /// The sender part of the channel is paired with the `JoinHandle` and added to the vector inside the `Bridge`. 
     /// The method returns the receiver part of the channel, allowing the caller to wait for and retrieve the result of the task once it's completed.
+    /// The receiver never completes if the task ends without a result.
     /// 
-    pub fn wait(&self, handle: JoinHandle<Expression>) -> oneshot::Reciever<Expression> {
+    pub fn wait(&self, handle: JoinHandle<Option<Expression>>) -> oneshot::Reciever<Expression> {
         let rv = oneshot::channel();
         self.inner().push((handle, rv.sender()));
         rv
@@ -49,13 +53,17 @@ impl Bridge {
         *self.inner() = Vec::new();
     }
     /// Checks and handles the status of ongoing tasks and their results. 
+    /// 
+    /// In deterministic mode, every task is waited for, so that results are delivered at the same point of the enumeration in every run.
     pub fn check(&self) {
         let vec = std::mem::take(self.inner());
+        let deterministic = DETERMINISTIC.load(Ordering::Relaxed);
         let mut v = vec.into_iter().flat_map(|(mut h, s)| {
             let mut cx = std::task::Context::from_waker(Waker::noop());
-            if let Poll::Ready(r) = h.poll_unpin(&mut cx) { 
+            let poll = if deterministic { Poll::Ready(futures::executor::block_on(&mut h)) } else { h.poll_unpin(&mut cx) };
+            if let Poll::Ready(r) = poll { 
                 info!("Thread {} ended", h.id());
-                if let Ok(r) = r { let _ = s.send(r); }
+                if let Ok(Some(r)) = r { let _ = s.send(r); }
                 None
             } else { Some((h, s)) }
         }).collect_vec();
@@ -63,3 +71,128 @@ impl Bridge {
     }
 }
 
+/// Small terms enumerated once on all the examples, shared read-only by the executors of every example set (`--shared-bank`).
+pub struct TermBank {
+    /// Number of examples the values of the bank are computed on
+    pub examples: usize,
+    /// `bank[size - 1][nt]` lists the terms of size `size` of the non-terminal `nt`, as in a checkpoint
+    pub bank: Vec<Vec<Vec<(&'static Expr, Value)>>>,
+}
+
+static SHARED_BANK: spin::Mutex<Option<&'static TermBank>> = spin::Mutex::new(None);
+
+/// Publishes `bank` to the executors created from now on, replacing the previous one.
+pub fn share_bank(bank: TermBank) {
+    info!("Sharing a term bank of {} sizes, {} terms", bank.bank.len(), bank.bank.iter().flatten().map(|l| l.len()).sum::<usize>());
+    *SHARED_BANK.lock() = Some(Box::leak(Box::new(bank)));
+}
+
+/// Returns the shared term bank, if any, when its values hold for the examples `exs` of the `examples` current ones.
+/// 
+/// Examples are only ever appended (by CEGIS or `--serve`), so a bank computed on the first examples still holds for the example sets among them.
+pub fn shared_bank(exs: &[usize], examples: usize) -> Option<&'static TermBank> {
+    (*SHARED_BANK.lock()).filter(|b| b.examples <= examples && exs.iter().all(|i| *i < b.examples))
+}
+
+/// Withdraws the shared term bank from the executors created from now on.
+pub fn unshare_bank() {
+    *SHARED_BANK.lock() = None;
+}
+
+/// Pool on which large production rules are evaluated (`--enum-threads`).
+static POOL: OnceLock<ThreadPool> = OnceLock::new();
+
+/// Minimum number of evaluations for a production rule to be sharded across the pool; smaller rules are not worth the synchronization.
+pub const SHARD_THRESHOLD: usize = 1 << 14;
+
+/// Number of items of the outer loop of a rule given to a pool thread at once.
+const SHARD_CHUNK: usize = 64;
+
+/// Creates the pool of `n` threads used to evaluate the terms of large production rules. Does nothing if `n <= 1`.
+/// 
+/// The pool is never dropped: values evaluated on its threads are allocated in their arenas, which live as long as the threads.
+pub fn init_pool(n: usize) {
+    if n > 1 {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(n).thread_name(|i| format!("enum-{i}")).build().expect("failed to create the enumeration pool");
+        let _ = POOL.set(pool);
+    }
+}
+
+/// Returns whether the pool was created.
+pub fn has_pool() -> bool {
+    POOL.get().is_some()
+}
+
+/// Returns whether a production rule with `work` evaluations should be sharded across the pool.
+pub fn sharding(work: usize) -> bool {
+    has_pool() && work >= SHARD_THRESHOLD
+}
+
+/// Evaluates `eval` on every item of `items` on the pool, and hands the results to `merge` on the calling thread, in the order of `items`.
+/// 
+/// Items are processed in rounds of a few chunks per thread, so that the results waiting to be merged stay bounded and an error of `merge` (e.g. a time limit) stops the evaluation after the current round.
+/// The results are the same, in the same order, as a sequential evaluation, so sharding does not affect deterministic runs.
+/// Pool threads report the usage of their arenas after each chunk, for the memory pressure seen by the executors.
+pub fn shard<T: Sync, R: Send>(items: &[T], eval: impl Fn(&T, &mut Vec<R>) + Sync, mut merge: impl FnMut(R) -> Result<(), ()>) -> Result<(), ()> {
+    let Some(pool) = POOL.get() else {
+        let mut out = Vec::new();
+        for item in items { eval(item, &mut out); }
+        return out.into_iter().try_for_each(merge);
+    };
+    for round in items.chunks(SHARD_CHUNK * pool.current_num_threads() * 2) {
+        let results: Vec<Vec<R>> = pool.install(|| round.par_chunks(SHARD_CHUNK).map(|chunk| {
+            let mut out = Vec::new();
+            for item in chunk { eval(item, &mut out); }
+            // The values are allocated in the arena of the pool thread, which counts towards `--mem-limit` only once reported
+            galloc::report_usage();
+            out
+        }).collect());
+        results.into_iter().flatten().try_for_each(&mut merge)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use crate::{expr::{cfg::Cfg, context::Context}, forward::executor::Executor, parser::problem::PBEProblem};
+
+    use super::{init_pool, shard, share_bank};
+
+    #[test]
+    fn test_shard_order() {
+        init_pool(3);
+        let items = (0..10000).collect_vec();
+        let mut merged = Vec::new();
+        shard(&items, |x, out| if x % 3 != 0 { out.push(x * 2) }, |r| { merged.push(r); Ok(()) }).unwrap();
+        assert_eq!(merged, items.iter().filter(|x| *x % 3 != 0).map(|x| x * 2).collect_vec());
+
+        let mut count = 0;
+        assert!(shard(&items, |x, out| out.push(*x), |_| { count += 1; if count == 10 { Err(()) } else { Ok(()) } }).is_err());
+        assert_eq!(count, 10);
+    }
+
+    #[test]
+    fn test_shared_bank() {
+        let s = r#"
+            (set-logic SLIA)
+            (synth-fun f ((name String)) String ((Start String (ntString)) (ntString String (name " " "," (str.++ ntString ntString)))))
+            (constraint (= (f "ab") "ab,"))
+            (constraint (= (f "c") "c,"))
+            (constraint (= (f "de") "de,"))
+            (check-synth)
+        "#;
+        let problem = PBEProblem::parse(s).unwrap();
+        let cfg = Cfg::from_synthfun(problem.synthfun());
+        let ctx = Context::from_examples(&problem.examples);
+        let bank = Executor::enumerate_bank(ctx.clone(), cfg.clone(), 3);
+        assert_eq!(bank.bank.len(), 3);
+        assert!(bank.bank.iter().flatten().flatten().all(|(e, v)| e.eval(&ctx) == *v));
+        share_bank(bank);
+        let exs = [0, 2];
+        let sub = ctx.with_examples(&exs);
+        let result = Executor::new(sub.clone(), cfg).with_shared_bank(&exs, ctx.len).solve_top_with_limit().unwrap();
+        assert_eq!(result.eval(&sub), sub.output);
+    }
+}
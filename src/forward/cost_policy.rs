@@ -0,0 +1,89 @@
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+/// When the deduction tasks waiting for cost are resumed (`--cost-policy`, or `#cost_policy` in the configuration).
+pub enum CostPolicy {
+    /// A fixed budget of `increase_cost_limit` at every release
+    #[default]
+    Fixed,
+    /// A budget adjusted to the progress of the enumeration, the number of waiting tasks and the remaining time
+    Adaptive,
+}
+
+impl CostPolicy {
+    /// Parses the name of a policy, as written in the configuration.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "fixed" => Some(Self::Fixed),
+            "adaptive" => Some(Self::Adaptive),
+            _ => None,
+        }
+    }
+}
+
+/// Largest factor by which the adaptive policy scales the base budget, in either direction.
+const MAX_SCALE: f64 = 8.0;
+
+/// Weight of the latest measure in the moving average of the enumeration rate.
+const SMOOTHING: f64 = 0.2;
+
+/// Computes the budget released to the waiting deduction tasks at each release of an [`Executor`](super::executor::Executor).
+///
+/// With [`CostPolicy::Adaptive`], the budget grows when the enumeration slows down (terms per second fall below their moving average, as at large sizes), when many tasks are waiting, and as the deadline approaches, so that deductions are explored before the search ends; it shrinks when the enumeration speeds up and few tasks wait.
+pub struct CostController {
+    policy: CostPolicy,
+    base: usize,
+    last: Option<(Instant, usize)>,
+    /// Moving average of the enumeration rate, in terms per second
+    average: Option<f64>,
+}
+
+impl CostController {
+    pub fn new(policy: CostPolicy, base: usize) -> Self {
+        Self { policy, base, last: None, average: None }
+    }
+
+    /// Returns the budget to release at `now`, after `terms` enumerated terms, with `waiting` tasks waiting and the fraction `elapsed` of the time limit spent.
+    pub fn budget(&mut self, now: Instant, terms: usize, waiting: usize, elapsed: f64) -> usize {
+        if self.policy == CostPolicy::Fixed { return self.base; }
+        let mut scale = 1.0;
+        if let Some((t, n)) = self.last {
+            let secs = (now - t).as_secs_f64();
+            if secs > 0.0 && terms > n {
+                let rate = (terms - n) as f64 / secs;
+                let average = self.average.map_or(rate, |a| a * (1.0 - SMOOTHING) + rate * SMOOTHING);
+                scale *= average / rate;
+                self.average = Some(average);
+            }
+        }
+        self.last = Some((now, terms));
+        scale *= 0.5 + waiting as f64 / self.base.max(1) as f64;
+        scale *= 1.0 + 3.0 * elapsed.clamp(0.0, 1.0);
+        let scale = scale.clamp(1.0 / MAX_SCALE, MAX_SCALE);
+        ((self.base as f64 * scale).round() as usize).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{CostController, CostPolicy};
+
+    #[test]
+    fn test_cost_policy() {
+        let now = Instant::now();
+        let mut fixed = CostController::new(CostPolicy::Fixed, 2000);
+        assert_eq!(fixed.budget(now, 10000, 5000, 0.9), 2000);
+
+        let mut adaptive = CostController::new(CostPolicy::Adaptive, 2000);
+        let idle = adaptive.budget(now, 10000, 0, 0.0);
+        assert!(idle < 2000);
+        // The enumeration slows down ten times, with many tasks waiting
+        let _ = adaptive.budget(now + Duration::from_millis(100), 20000, 0, 0.0);
+        let slow = adaptive.budget(now + Duration::from_millis(1100), 30000, 4000, 0.0);
+        assert!(slow > 4 * 2000, "{slow}");
+        let late = CostController::new(CostPolicy::Adaptive, 2000).budget(now, 10000, 2000, 1.0);
+        assert!(late > CostController::new(CostPolicy::Adaptive, 2000).budget(now, 10000, 2000, 0.0));
+    }
+}
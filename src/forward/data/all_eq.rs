@@ -1,6 +1,7 @@
 use std::{
     cell::UnsafeCell,
     collections::hash_map,
+    hash::{Hash, Hasher},
     task::Poll, ops::Index,
 };
 
@@ -16,9 +17,78 @@ use crate::{
 };
 use ahash::AHashMap as HashMap;
 
-#[derive(From, Deref)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// How the term dispatcher for equality identifies values (`#data.all_eq.dedup`).
+pub enum Dedup {
+    /// Values are keys of the bank, and are hashed and compared in full (`value`).
+    #[default]
+    Value,
+    /// Values are keyed by a 64-bit fingerprint computed once, and compared in full only when fingerprints are equal (`fingerprint`).
+    Fingerprint,
+    /// Only fingerprints are stored; values with the same fingerprint are considered equal (`fingerprint-unverified`).
+    /// This saves the memory of the values, at the risk of a collision discarding a term.
+    Unverified,
+}
+
+impl Dedup {
+    /// Parses the name of a mode, as written in the configuration.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "value" => Some(Self::Value),
+            "fingerprint" => Some(Self::Fingerprint),
+            "fingerprint-unverified" => Some(Self::Unverified),
+            _ => None,
+        }
+    }
+}
+
+/// Returns a 64-bit fingerprint of the value, with fixed keys so that it does not depend on the run.
+fn fingerprint(v: Value) -> u64 {
+    let mut hasher = ahash::AHasher::default();
+    v.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[derive(Clone, Copy)]
+/// A value together with its fingerprint, which is all that is hashed.
+struct Fingerprinted(u64, Value);
+
+impl Hash for Fingerprinted {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.0);
+    }
+}
+
+impl PartialEq for Fingerprinted {
+    /// Values are only compared when their fingerprints collide.
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0 && self.1 == other.1
+    }
+}
+
+impl Eq for Fingerprinted {}
+
+/// Storage of the term dispatcher for equality, depending on the `Dedup` mode.
+enum Bank {
+    Value(HashMap<Value, MaybeReady<&'static Expr>>),
+    Fingerprint(HashMap<Fingerprinted, MaybeReady<&'static Expr>>),
+    Unverified(HashMap<u64, MaybeReady<&'static Expr>>),
+}
+
+/// Runs `$body` with `$map` bound to the map of the bank and `$key` to the key of `$v` in it.
+macro_rules! with_bank {
+    ($self:ident, $v:expr, |$map:ident, $key:ident| $body:expr) => {
+        match unsafe { $self.0.as_mut() } {
+            Bank::Value($map) => { let $key = $v; $body }
+            Bank::Fingerprint($map) => { let $key = Fingerprinted(fingerprint($v), $v); $body }
+            Bank::Unverified($map) => { let $key = fingerprint($v); $body }
+        }
+    };
+}
+
+#[derive(From)]
 /// A Term Dispatcher for Equal
-pub struct Data(UnsafeCell<HashMap<Value, MaybeReady<&'static Expr>>>);
+pub struct Data(UnsafeCell<Bank>);
 
 impl Default for Data {
     /// Creates a new instance using the default initialization, which internally delegates to the new method.
@@ -29,16 +99,32 @@ impl Default for Data {
 
 impl Data {
     /// Creates and returns a new instance of the data structure by initializing it with an empty hash map. 
-    pub fn new() -> Self { Self(HashMap::new().into()) }
+    pub fn new() -> Self { Self::with_dedup(Dedup::Value) }
+
+    /// Creates an empty term dispatcher identifying values as specified by `dedup`.
+    pub fn with_dedup(dedup: Dedup) -> Self {
+        let bank = match dedup {
+            Dedup::Value => Bank::Value(HashMap::new()),
+            Dedup::Fingerprint => Bank::Fingerprint(HashMap::new()),
+            Dedup::Unverified => Bank::Unverified(HashMap::new()),
+        };
+        Self(bank.into())
+    }
 
     #[inline(always)]
     /// Return the number of entries stored in the underlying container. 
-    pub fn count(&self) -> usize { unsafe{self.as_mut().len()} }
+    pub fn count(&self) -> usize {
+        match unsafe { self.0.as_mut() } {
+            Bank::Value(map) => map.len(),
+            Bank::Fingerprint(map) => map.len(),
+            Bank::Unverified(map) => map.len(),
+        }
+    }
 
     #[inline(always)]
     /// Updates the mapping by setting an associated expression for a given value and returns the updated expression when modifications are made.
     pub fn set(&self, v: Value, e: Expr) -> Option<&'static Expr> {
-        match unsafe{ self.as_mut().entry(v) } {
+        with_bank!(self, v, |map, key| match map.entry(key) {
             hash_map::Entry::Occupied(mut p) => {
                 if p.get().is_ready() {
                     None
@@ -53,50 +139,41 @@ impl Data {
                 v.insert(MaybeReady::Ready(e));
                 Some(e)
             }
-        }
+        })
     }
 
     #[inline(always)]
     /// Sets the internal mapping for a given key with a new static expression value, and if necessary, triggers a notification to propagate the update. 
     pub fn set_ref(&self, v: Value, e: &'static Expr) {
         let mut sd = None;
-        match unsafe{ self.as_mut().entry(v) } {
+        with_bank!(self, v, |map, key| match map.entry(key) {
             hash_map::Entry::Occupied(mut p) => {
                 if !p.get().is_ready() { sd = p.get_mut().sender(e); }
             }
             hash_map::Entry::Vacant(v) => {
                 v.insert(MaybeReady::Ready(e));
             }
-        }
+        });
         if let Some(x) = sd { x.send(e) }
     }
 
     #[inline(always)]
     /// Acquires asynchronously an expression for a given value, waiting for its readiness if necessary.
     pub async fn acquire(&self, v: Value) -> &'static Expr {
-        match unsafe{ self.as_mut().entry(v) } {
-            hash_map::Entry::Occupied(o) => o.get().get().await,
-            hash_map::Entry::Vacant(v) => v.insert(MaybeReady::pending()).get().await,
-        }
+        let slot = with_bank!(self, v, |map, key| map.entry(key).or_insert_with(MaybeReady::pending).get());
+        slot.await
     }
 
     #[inline(always)]
     /// Checks whether the synthesis term associated with the provided value is still pending. 
     pub fn is_pending(&self, v: Value) -> bool {
-        if let Some(a) = unsafe{ self.as_mut().get(&v) } {
-            !a.is_ready()
-        } else {
-            false
-        }
+        with_bank!(self, v, |map, key| map.get(&key).is_some_and(|a| !a.is_ready()))
     }
 
     #[inline(always)]
     /// Checks whether the underlying data structure contains the specified key. 
     pub fn contains(&self, v: Value) -> bool {
-        match unsafe{ self.as_mut().entry(v) } {
-            hash_map::Entry::Occupied(o) => true,
-            hash_map::Entry::Vacant(v) => false,
-        }
+        with_bank!(self, v, |map, key| map.contains_key(&key))
     }
     // #[inline(always)]
     // pub fn try_acquire(&self, v: Value) -> Option<EventBusRc<&'static Expr>> {
@@ -116,9 +193,7 @@ impl Data {
     // }
     /// Retrieves an expression reference from the underlying data structure based on a given index. 
     pub fn at(&self, index: Value) -> Option<&'static Expr> {
-        unsafe{ &self.as_mut().get(&index) }.and_then(|x| {
-            x.poll_opt()
-        })
+        with_bank!(self, index, |map, key| map.get(&key).and_then(|x| x.poll_opt()))
     }
     /// Retrieves a static expression reference corresponding to the provided value by performing an internal lookup. 
     pub fn get(&self, index: Value) -> &'static Expr {
@@ -158,3 +233,28 @@ impl Data {
 //         }
 //     })
 // }
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr, galloc::AllocForExactSizeIter, value::Value};
+
+    use super::{Data, Dedup};
+
+    #[test]
+    fn test_dedup_modes() {
+        for dedup in [Dedup::Value, Dedup::Fingerprint, Dedup::Unverified] {
+            let data = Data::with_dedup(dedup);
+            let a: Value = ["a", "b"].into_iter().galloc_scollect().into();
+            let a2: Value = ["a", "b"].into_iter().galloc_scollect().into();
+            let b: Value = ["a", "c"].into_iter().galloc_scollect().into();
+            assert!(data.set(a, expr!("x")).is_some());
+            assert!(data.set(a2, expr!("y")).is_none(), "{:?}", dedup);
+            assert!(data.set(b, expr!("z")).is_some());
+            assert_eq!(data.count(), 2);
+            assert!(data.contains(a2) && !data.is_pending(a2));
+            assert_eq!(data.at(a2), Some(&expr!("x")));
+        }
+        assert_eq!(Dedup::from_name("fingerprint"), Some(Dedup::Fingerprint));
+        assert_eq!(Dedup::from_name("hash"), None);
+    }
+}
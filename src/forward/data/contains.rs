@@ -23,7 +23,7 @@ pub fn listsubseq(that: ListStr, this: ListStr) -> bool {
             None => return true,
         }
     }
-    return iter.peek() == None;
+    iter.peek().is_none()
 }
 
 pub type ListData = HashMap<String, Vec<broadcast::Sender<Value>>>;
@@ -41,7 +41,8 @@ impl Data {
     fn get(&self) -> &mut Vec<ListData> {
         unsafe { self.0.as_mut() }
     }
-    pub fn update(&self, value: Value) -> () {
+    #[allow(clippy::mutable_key_type)]
+    pub fn update(&self, value: Value) {
         if let Value::ListStr(ls) = value {
             let mut iter = ls.iter().zip(self.get().iter());
             let mut senders = HashMap::<broadcast::Sender<Value>, usize>::new();
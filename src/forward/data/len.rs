@@ -44,6 +44,10 @@ impl Data {
             hash_map::Entry::Vacant(v) => { v.insert(vec![value]); }
         }
     }
+    /// Returns the lists found so far whose elements have the given lengths.
+    pub fn found(&self, lengths: &[usize]) -> &[Value] {
+        self.found.get(lengths).map(|v| v.as_slice()).unwrap_or(&[])
+    }
     pub fn listen_at(&mut self, v: Vec<usize>) -> broadcast::Reciever<Value> {
         match self.event.entry(v) {
             hash_map::Entry::Occupied(o) => o.get().reciever(),
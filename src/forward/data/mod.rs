@@ -4,7 +4,7 @@ use std::cell::{RefCell, UnsafeCell};
 
 use itertools::Itertools;
 
-use crate::{expr::{cfg::Cfg, context::Context, Expr}, text::parsing::TextObjData, utils::UnsafeCellExt, value::{Type, Value}};
+use crate::{expr::{cfg::Cfg, context::Context, ops::{Lowercase, Op1, Op1Enum, Uppercase}, Expr}, galloc::AllocForAny, text::parsing::TextObjData, utils::UnsafeCellExt, value::{Type, Value}};
 
 use self::size::{VecEv, EV};
 
@@ -27,6 +27,9 @@ pub mod contains;
 /// Term Dispatcher for Len
 pub mod len;
 
+/// Pruning of dominated values
+pub mod prune;
+
 /// All Term Dispatchers
 pub struct Data {
     pub size: size::Data,
@@ -35,8 +38,11 @@ pub struct Data {
     pub prefix: Option<UnsafeCell<prefix::Data>>,
     pub contains: Option<contains::Data>,
     pub len: Option<UnsafeCell<len::Data>>,
-    pub to: TextObjData,
+    /// Text-object scanners, `None` when disabled by `CfgConfig::no_text_objects`
+    pub to: Option<TextObjData>,
     pub new_ev: RefCell<Vec<(&'static Expr, Value)>>,
+    /// Policy discarding dominated values, if enabled by `CfgConfig::dominance_pruning`
+    pub pruning: Option<Box<dyn prune::PruningPolicy>>,
 } 
 
 impl Data {
@@ -45,13 +51,14 @@ impl Data {
         cfg.iter().enumerate().map(|(i, nt)| {
             Self {
                 size: size::Data::new(cfg),
-                all_eq: all_eq::Data::new(),
+                all_eq: all_eq::Data::with_dedup(cfg.config.dedup),
                 substr: substr::Data::new(ctx.output, cfg.config.substr_limit),
                 prefix: prefix::Data::new(ctx.output, usize::MAX),
                 contains: contains::Data::new(ctx.output.len(), nt.ty),
                 len: if nt.ty != Type::ListStr && cfg[i].get_op1("list.map").is_some() { None } else { Some(len::Data::new().into()) },
-                to: TextObjData::new(),
-                new_ev: Vec::<(&'static Expr, Value)>::new().into()
+                to: (!cfg.config.no_text_objects).then(TextObjData::new),
+                new_ev: Vec::<(&'static Expr, Value)>::new().into(),
+                pruning: cfg.config.dominance_pruning.then(|| Box::new(prune::SubstrDominance::new(ctx)) as Box<dyn prune::PruningPolicy>),
             }
         }).collect_vec()
     }
@@ -81,13 +88,33 @@ impl Data {
             if let Some(s) = self.prefix() { s.update(v, exec); }
             if let Some(l) = self.len() { l.update(v, exec); };
             if let Some(c) = self.contains.as_ref() { c.update(v); }
+            if exec.cfg.config.case_fold { self.case_fold(exec, e, v); }
             // self.listsubseq.update(v)?;
-            self.to.update(exec, e, v);
+            if let Some(to) = self.to.as_ref() { to.update(exec, e, v); }
             Ok(Some(e))
         } else {
             Ok(None)
         }
     }
+    /// Registers the uppercase and lowercase conversions of a string term which is not a substring of the outputs, when they are (`data.case_fold`).
+    /// 
+    /// The substring and prefix dispatchers then match terms that differ from the outputs only in casing, e.g. a constant `"total"` for outputs containing `"TOTAL"`, and the deducers build on the converted term.
+    fn case_fold(&self, exec: &'static Executor, e: &'static Expr, v: Value) {
+        let Some(substr) = self.substr() else { return };
+        if !matches!(v, Value::Str(_)) || substr.expected_contains(v) { return; }
+        let folds = [Op1Enum::from(Uppercase::default()), Op1Enum::from(Lowercase::default())];
+        for op in folds {
+            let (_, folded) = op.try_eval(v);
+            if folded == v || !substr.expected_contains(folded) || self.all_eq.contains(folded) { continue; }
+            self.all_eq.set_ref(folded, Expr::Op1(op.galloc(), e).galloc());
+            substr.update(folded, exec);
+            if let Some(p) = self.prefix() { p.update(folded, exec); }
+        }
+    }
+    /// Returns whether the pruning policy, if any, discards the value.
+    pub fn pruned(&self, v: Value) -> bool {
+        self.pruning.as_ref().is_some_and(|p| p.prune(self, v))
+    }
     pub fn add_ev(&self, e: &'static Expr, v: Value) {
         self.new_ev.borrow_mut().push((e, v));
     }
@@ -0,0 +1,58 @@
+use crate::{expr::context::Context, value::Value};
+
+use super::Data;
+
+/// A policy discarding enumerated values that cannot contribute to a solution, before they reach the term dispatchers.
+pub trait PruningPolicy {
+    /// Returns whether `v`, enumerated for the non-terminal whose term dispatchers are `data`, is dominated and can be discarded.
+    fn prune(&self, data: &Data, v: Value) -> bool;
+}
+
+/// Prunes strings occurring neither in the output nor in the string inputs (`--prune-dominated`).
+///
+/// A string is kept if, on some example, it is a substring of the output (as recorded by the substring dispatcher) or of an input: it may then be a part of the output, or an intermediate value such as the delimiter of a `str.indexof`.
+/// This is a heuristic, since operators like `str.replace` may build the output from strings occurring nowhere; it is therefore disabled by default.
+pub struct SubstrDominance {
+    inputs: Vec<&'static [&'static str]>,
+}
+
+impl SubstrDominance {
+    /// Creates the policy for the string inputs of `ctx`.
+    pub fn new(ctx: &Context) -> Self {
+        Self { inputs: ctx.p.iter().filter_map(|v| if let Value::Str(s) = v { Some(*s) } else { None }).collect() }
+    }
+}
+
+impl PruningPolicy for SubstrDominance {
+    /// Values other than strings, and all values when the output is not a string, are kept.
+    fn prune(&self, data: &Data, v: Value) -> bool {
+        let (Value::Str(strs), Some(substr)) = (v, data.substr()) else { return false };
+        !strs.iter().enumerate().any(|(i, s)| substr.expected_contains_at(i, s) || self.inputs.iter().any(|input| input[i].contains(s)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr::{cfg::{Cfg, CfgConfig, NonTerminal, ProdRule}, context::Context}, galloc::AllocForExactSizeIter, parser::config::Config, value::{Type, Value}};
+
+    use super::super::Data;
+
+    #[test]
+    fn test_substr_dominance() {
+        let input: Value = ["John Smith", "Jane Doe"].into_iter().galloc_scollect().into();
+        let output: Value = ["J. Smith", "J. Doe"].into_iter().galloc_scollect().into();
+        let ctx = Context::new(2, vec![input], vec![], output);
+        let nt = NonTerminal { name: "S".into(), ty: Type::Str, rules: vec![ProdRule::Var(0)], config: Config::new() };
+        let mut config = CfgConfig::from(Config::new());
+        config.dominance_pruning = true;
+        let data = Data::new(&Cfg::from_single_nt(nt, config), &ctx);
+        let value = |v: [&'static str; 2]| -> Value { v.into_iter().galloc_scollect().into() };
+        assert!(!data[0].pruned(value(["J.", "Doe"])));
+        assert!(!data[0].pruned(value([" ", " "])));
+        assert!(data[0].pruned(value(["JOHN", "JANE"])));
+        assert!(!data[0].pruned(Value::Int(&[1, 2])));
+
+        let data = Data::new(&Cfg::from_single_nt(NonTerminal { name: "S".into(), ty: Type::Str, rules: vec![], config: Config::new() }, CfgConfig::from(Config::new())), &ctx);
+        assert!(!data[0].pruned(value(["JOHN", "JANE"])));
+    }
+}
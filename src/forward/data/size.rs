@@ -25,6 +25,8 @@ impl Data {
     fn unsafe_inner(&self) -> &mut SizeVec { unsafe { self.0.as_mut() } }
     pub fn new(cfg: &Cfg) -> Self { Self(vec![vec![]].into()) }
     pub fn len(&self) -> usize { self.unsafe_inner().len() }
+    /// Total number of stored terms, over all sizes.
+    pub fn count(&self) -> usize { self.unsafe_inner().iter().map(|x| x.len()).sum() }
     pub fn get_all(&self, size: usize) -> &[EV] { self.unsafe_inner()[size].as_slice() }
     #[inline(always)]
     pub fn get_all_under(&self, size: usize) -> impl Iterator<Item = (usize, &EV)> + '_ {
@@ -20,7 +20,6 @@ pub struct Data {
     event: IntervalTreeN,
     senders: HashMap<Value, broadcast::Sender<Value>>,
     size_limit: usize,
-    exceeded_size_limit: bool,
 }
 
 impl Data {
@@ -32,10 +31,13 @@ impl Data {
                 event: IntervalTreeN::new(e),
                 senders: HashMap::new(),
                 size_limit,
-                exceeded_size_limit: false,
             }.into())
         } else { None }
     }
+    /// Returns whether `s` is a substring of the output of the `i`-th example.
+    pub fn expected_contains_at(&self, i: usize, s: &str) -> bool {
+        self.expected[i].contains(s)
+    }
     pub fn expected_contains(&self, value: Value) -> bool {
         if let Ok(v) = TryInto::<&[&str]>::try_into(value) {
             v.iter().cloned().zip(self.expected.iter().cloned()).all(|(a, b)| b.contains(a) && !a.is_empty())
@@ -6,25 +6,48 @@ use itertools::Itertools;
 // pub mod simple;
 // use simple::*;
 
-use crate::{expr::{cfg::ProdRule, ops::{Op1, Op1Enum, Op2, Op2Enum, Op3, Op3Enum}, Expr}, galloc::AllocForAny};
+/// Per-operator statistics (`--stats`)
+pub mod stats;
+
+use crate::{expr::{cfg::{NonTerminal, ProdRule}, ops::{Op1, Op1Enum, Op2, Op2Enum, Op3, Op3Enum}, Expr}, galloc::AllocForAny, value::Value};
 use ext_trait::extension;
-use super::executor::Executor;
+use super::{bridge, executor::Executor};
 
 
 /// An enumerator for a specific production rule.
-pub trait Enumerator1 : Op1 {
+pub trait Enumerator1 : Op1 + Sync {
     #[inline(always)]
     fn enumerate(&self, this: &'static Op1Enum, exec: &'static Executor, opnt: [usize; 1]) -> Result<(), ()> {
         enumerate1(self, this, exec, opnt)
     }
 }
-#[inline(always)]
 
-pub fn enumerate1(s: &impl Op1, this: &'static Op1Enum, exec: &'static Executor, opnt: [usize; 1]) -> Result<(), ()> {
+/// Evaluates the terms of a production rule on the enumeration pool (`bridge::shard`), from the items `outer` of its outermost loop, and sends them to `exec`.
+/// 
+/// `work` is the number of evaluations, counted at once since the pool threads do not report to `count`.
+fn enumerate_sharded<T: Sync>(exec: &'static Executor, outer: &[T], work: usize, count: &mut stats::EvalCount, eval: impl Fn(&T, &mut Vec<(Expr, Value)>) + Sync) -> Result<(), ()> {
+    count.add(work as u64, 0);
+    bridge::shard(outer, eval, |(expr, value)| {
+        count.add(0, 1);
+        exec.enum_expr(expr, value)
+    })
+}
+
+#[inline(always)]
+pub fn enumerate1(s: &(impl Op1 + Sync), this: &'static Op1Enum, exec: &'static Executor, opnt: [usize; 1]) -> Result<(), ()> {
     if exec.size() <= s.cost() { return Ok(()); }
-    for (e, v) in exec.data[opnt[0]].size.get_all(exec.size() - s.cost()) {
+    let mut count = stats::EvalCount::default();
+    let terms = exec.data[opnt[0]].size.get_all(exec.size() - s.cost());
+    if bridge::sharding(terms.len()) {
+        return enumerate_sharded(exec, terms, terms.len(), &mut count, |&(e, v), out| {
+            if let (true, value) = s.try_eval(v) { out.push((Expr::Op1(this, e), value)); }
+        });
+    }
+    for (e, v) in terms {
         let expr = Expr::Op1(this, e);
-        if let (true, value) = s.try_eval(*v) {
+        let (ok, value) = s.try_eval(*v);
+        count.eval(ok);
+        if ok {
             exec.enum_expr(expr, value)?;
         }
     }
@@ -32,20 +55,35 @@ pub fn enumerate1(s: &impl Op1, this: &'static Op1Enum, exec: &'static Executor,
 }
 
 /// An enumerator for a specific production rule.
-pub trait Enumerator2 : Op2 {
+pub trait Enumerator2 : Op2 + Sync {
     #[inline(always)]
     fn enumerate(&self, this: &'static Op2Enum, exec: &'static Executor, nt: [usize; 2]) -> Result<(), ()> {
         enumerate2(self, this, exec, nt)
     }
 }
 #[inline(always)]
-pub fn enumerate2(s: &impl Op2, this: &'static Op2Enum, exec: &'static Executor, nt: [usize; 2]) -> Result<(), ()> {
+pub fn enumerate2(s: &(impl Op2 + Sync), this: &'static Op2Enum, exec: &'static Executor, nt: [usize; 2]) -> Result<(), ()> {
     if exec.size() <= s.cost() { return Ok(()); }
     let total = exec.size() - s.cost();
+    let mut count = stats::EvalCount::default();
+    if bridge::has_pool() {
+        let outer = exec.data[nt[0]].size.get_all_under(total).collect_vec();
+        let inner = (0..total).map(|k| exec.data[nt[1]].size.get_all(k)).collect_vec();
+        let work = outer.iter().map(|(i, _)| inner[total - i].len()).sum();
+        if bridge::sharding(work) {
+            return enumerate_sharded(exec, &outer, work, &mut count, |&(i, &(e1, v1)), out| {
+                for &(e2, v2) in inner[total - i] {
+                    if let (true, value) = s.try_eval(v1, v2) { out.push((Expr::Op2(this, e1, e2), value)); }
+                }
+            });
+        }
+    }
     for (i, (e1, v1)) in exec.data[nt[0]].size.get_all_under(total) {
         for (e2, v2) in exec.data[nt[1]].size.get_all(total - i) {
             let expr = Expr::Op2(this, e1, e2);
-            if let (true, value) = s.try_eval(*v1, *v2) {
+            let (ok, value) = s.try_eval(*v1, *v2);
+            count.eval(ok);
+            if ok {
                 exec.enum_expr(expr, value)?;
             }
         }
@@ -54,21 +92,38 @@ pub fn enumerate2(s: &impl Op2, this: &'static Op2Enum, exec: &'static Executor,
 }
 
 /// An enumerator for a specific production rule.
-pub trait Enumerator3 : Op3 {
+pub trait Enumerator3 : Op3 + Sync {
     #[inline(always)]
     fn enumerate(&self, this: &'static Op3Enum, exec: &'static Executor, nt: [usize; 3]) -> Result<(), ()> {
         enumerate3(self, this, exec, nt)
     }
 }
 #[inline(always)]
-pub fn enumerate3(s: &impl Op3, this: &'static Op3Enum, exec: &'static Executor, nt: [usize; 3]) -> Result<(), ()> {
+pub fn enumerate3(s: &(impl Op3 + Sync), this: &'static Op3Enum, exec: &'static Executor, nt: [usize; 3]) -> Result<(), ()> {
     if exec.size() < s.cost() { return Ok(()); }
     let total = exec.size() - s.cost();
+    let mut count = stats::EvalCount::default();
+    if bridge::has_pool() {
+        let outer = exec.data[nt[0]].size.get_all_under(total).flat_map(|(i, ev1)| {
+            exec.data[nt[1]].size.get_all_under(total - i).map(move |(j, ev2)| (i + j, ev1, ev2))
+        }).collect_vec();
+        let inner = (0..total).map(|k| exec.data[nt[2]].size.get_all(k)).collect_vec();
+        let work = outer.iter().map(|(ij, _, _)| inner[total - ij].len()).sum();
+        if bridge::sharding(work) {
+            return enumerate_sharded(exec, &outer, work, &mut count, |&(ij, &(e1, v1), &(e2, v2)), out| {
+                for &(e3, v3) in inner[total - ij] {
+                    if let (true, value) = s.try_eval(v1, v2, v3) { out.push((Expr::Op3(this, e1, e2, e3), value)); }
+                }
+            });
+        }
+    }
     for (i, (e1, v1)) in exec.data[nt[0]].size.get_all_under(total) {
         for (j, (e2, v2)) in exec.data[nt[1]].size.get_all_under(total - i) {
             for (e3, v3) in exec.data[nt[2]].size.get_all(total - i - j) {
                 let expr = Expr::Op3(this, e1, e2, e3);
-                if let (true, value) = s.try_eval(*v1, *v2, *v3) {
+                let (ok, value) = s.try_eval(*v1, *v2, *v3);
+                count.eval(ok);
+                if ok {
                     exec.enum_expr(expr, value)?;
                 }
             }
@@ -82,7 +137,11 @@ impl Enumerator1 for Op1Enum {
     fn enumerate(&self, this: &'static Op1Enum, exec: &'static Executor, opnt: [usize; 1]) -> Result<(), ()> {
         macro_rules! _do {($($op:ident)*) => {$(
             if let Self::$op(a) = self {
-                return a.enumerate(this, exec, opnt);
+                if !stats::enabled() { return a.enumerate(this, exec, opnt); }
+                let counter = exec.counter.get();
+                let result = a.enumerate(this, exec, opnt);
+                stats::record(this.name(), (exec.counter.get() - counter) as u64);
+                return result;
             }
         )*};}
         crate::for_all_op1!();
@@ -95,7 +154,11 @@ impl Enumerator2 for Op2Enum {
     fn enumerate(&self, this: &'static Op2Enum, exec: &'static Executor, opnt: [usize; 2]) -> Result<(), ()> {
         macro_rules! _do {($($op:ident)*) => {$(
             if let Self::$op(a) = self {
-                return a.enumerate(this, exec, opnt);
+                if !stats::enabled() { return a.enumerate(this, exec, opnt); }
+                let counter = exec.counter.get();
+                let result = a.enumerate(this, exec, opnt);
+                stats::record(this.name(), (exec.counter.get() - counter) as u64);
+                return result;
             }
         )*};}
         crate::for_all_op2!();
@@ -108,7 +171,11 @@ impl Enumerator3 for Op3Enum {
     fn enumerate(&self, this: &'static Op3Enum, exec: &'static Executor, opnt: [usize; 3]) -> Result<(), ()> {
         macro_rules! _do {($($op:ident)*) => {$(
             if let Self::$op(a) = self {
-                return a.enumerate(this, exec, opnt);
+                if !stats::enabled() { return a.enumerate(this, exec, opnt); }
+                let counter = exec.counter.get();
+                let result = a.enumerate(this, exec, opnt);
+                stats::record(this.name(), (exec.counter.get() - counter) as u64);
+                return result;
             }
         )*};}
         crate::for_all_op3!();
@@ -147,6 +214,20 @@ impl ProdRule {
     }
 }
 
+#[extension(pub trait NonTerminalEnumerateExt)]
+impl NonTerminal {
+    /// Enumerates the terms of the current size of the non-terminal, i.e. its text objects and the terms of its rules.
+    /// 
+    /// Nothing is enumerated above the `#max_size` of the non-terminal.
+    fn enumerate(&self, exec: &'static Executor) -> Result<(), ()> {
+        if exec.size() > self.max_size() { return Ok(()); }
+        if let Some(to) = exec.cur_data().to.as_ref() { to.enumerate(exec)?; }
+        for rule in &self.rules {
+            rule.enumerate(exec)?;
+        }
+        Ok(())
+    }
+}
 
 // #[derive(From, Into, Deref)]
 // pub struct EnumerationCfg(Vec<Vec<EnumeratorEnum>>);
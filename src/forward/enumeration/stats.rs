@@ -0,0 +1,117 @@
+use std::{cell::Cell, collections::BTreeMap, sync::atomic::{AtomicBool, Ordering}};
+
+use itertools::Itertools;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static STATS: spin::Mutex<BTreeMap<&'static str, OpStats>> = spin::Mutex::new(BTreeMap::new());
+
+thread_local! {
+    /// Evaluations counted by `EvalCount` since the last call to [`record`] on this thread.
+    static PENDING: Cell<(u64, u64)> = const { Cell::new((0, 0)) };
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// Statistics of an operator, summed over all threads (`--stats`).
+pub struct OpStats {
+    /// Number of evaluations of the operator during enumeration
+    pub evals: u64,
+    /// Number of evaluations that produced a value
+    pub successes: u64,
+    /// Number of expressions rooted at the operator sent to the term bank (before deduplication)
+    pub enumerated: u64,
+}
+
+/// Enables the collection of operator statistics.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Returns whether operator statistics are collected.
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+#[derive(Default)]
+/// Counts the evaluations of one call of the generic enumerators, handed over to [`record`] when dropped.
+///
+/// Counting into a local keeps the enumeration loops free of synchronization; operators with their own enumeration (e.g. `ite`) only report enumerated expressions.
+pub struct EvalCount {
+    evals: u64,
+    successes: u64,
+}
+
+impl EvalCount {
+    #[inline(always)]
+    pub fn eval(&mut self, success: bool) {
+        self.evals += 1;
+        self.successes += success as u64;
+    }
+    /// Counts evaluations performed on other threads, which are not seen by their own `EvalCount`.
+    pub fn add(&mut self, evals: u64, successes: u64) {
+        self.evals += evals;
+        self.successes += successes;
+    }
+}
+
+impl Drop for EvalCount {
+    fn drop(&mut self) {
+        if self.evals > 0 {
+            PENDING.with(|p| {
+                let (e, s) = p.get();
+                p.set((e + self.evals, s + self.successes));
+            });
+        }
+    }
+}
+
+/// Adds the evaluations counted on this thread since the last call, and `enumerated` expressions, to the statistics of the operator `name`.
+pub fn record(name: &'static str, enumerated: u64) {
+    let (evals, successes) = PENDING.with(|p| p.replace((0, 0)));
+    let mut stats = STATS.lock();
+    let s = stats.entry(name).or_default();
+    s.evals += evals;
+    s.successes += successes;
+    s.enumerated += enumerated;
+}
+
+/// Returns the statistics of all operators, most evaluated first.
+pub fn snapshot() -> Vec<(&'static str, OpStats)> {
+    STATS.lock().iter().map(|(k, v)| (*k, *v)).sorted_by_key(|(_, s)| std::cmp::Reverse((s.evals, s.enumerated))).collect()
+}
+
+/// Formats statistics as a column-aligned table, with the share of each operator in the total number of evaluations.
+pub fn format_table(stats: &[(&'static str, OpStats)]) -> String {
+    let total = stats.iter().map(|(_, s)| s.evals).sum::<u64>().max(1);
+    let width = stats.iter().map(|(n, _)| n.len()).chain(std::iter::once(8)).max().unwrap();
+    let mut result = format!("{:<width$} | {:>12} | {:>6} | {:>12} | {:>12}\n", "operator", "evals", "share", "successes", "enumerated");
+    result += &format!("{}-+-{}-+-{}-+-{}-+-{}\n", "-".repeat(width), "-".repeat(12), "-".repeat(6), "-".repeat(12), "-".repeat(12));
+    for (name, s) in stats {
+        let share = format!("{:.1}%", s.evals as f64 * 100.0 / total as f64);
+        result += &format!("{:<width$} | {:>12} | {:>6} | {:>12} | {:>12}\n", name, s.evals, share, s.successes, s.enumerated);
+    }
+    result
+}
+
+/// Prints the statistics to stderr if they are enabled.
+pub fn report() {
+    if enabled() {
+        eprint!("{}", format_table(&snapshot()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_table, OpStats};
+
+    #[test]
+    fn test_format_table() {
+        let stats = [("str.++", OpStats { evals: 300, successes: 300, enumerated: 300 }), ("int.+", OpStats { evals: 100, successes: 90, enumerated: 90 })];
+        let table = format_table(&stats);
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[2].starts_with("str.++   |"));
+        assert!(lines[2].contains("75.0%"));
+        assert!(lines[3].contains("25.0%"));
+    }
+}
@@ -1,19 +1,20 @@
 use std::{
-    cell::{Cell, RefCell, UnsafeCell}, collections::{hash_map::Entry, HashMap, HashSet}, default, f64::consts::E, fs, future::Future, pin::pin, sync::atomic::AtomicBool, task::Poll, time::{self, Duration, Instant}
+    cell::{Cell, RefCell, UnsafeCell}, collections::{hash_map::Entry, HashMap, HashSet}, default, f64::consts::E, fs, future::Future, pin::pin, sync::atomic::{AtomicBool, AtomicUsize, Ordering}, task::Poll, time::{self, Duration, Instant}
 };
 
 use derive_more::{Constructor, Deref, From, Into};
 use futures::StreamExt;
 use itertools::Itertools;
-use simple_rc_async::{sync::{broadcast, broadcastque}, task::{self, JoinHandle}};
+use simple_rc_async::{sync::broadcast, task::{self, JoinHandle}};
 
 use crate::{
-    backward::{ Deducer, DeducerEnum, Problem}, debg, debg2, expr::{
-         cfg::{Cfg, ProdRule}, context::Context, Expr
-    }, forward::{data::{size, substr}, enumeration::ProdRuleEnumerateExt, executor}, galloc::AllocForAny, info, log, parser::problem::PBEProblem, solutions::CONDITIONS, text::parsing::{ParseInt, TextObjData}, utils::UnsafeCellExt, value::{ConstValue, Type, Value}, warn
+    backward::{ Deducer, DeducerEnum, Problem}, checkpoint, debg, debg2, expr::{
+         cfg::{Cfg, CfgConfig, NonTerminal, ProdRule}, context::Context, Expr
+    }, forward::{data::{size, substr}, enumeration::NonTerminalEnumerateExt, executor}, galloc::{self, AllocForAny, MemPressure}, info, log, observer, parser::problem::PBEProblem, solutions::CONDITIONS, text::parsing::{ParseInt, TextObjData}, utils::UnsafeCellExt, value::{ConstValue, Type, Value}, warn
 };
 use crate::expr;
-use super::{bridge::Bridge, data::{self, all_eq, size::EV, Data}};
+use crate::provenance::{self, Provenance};
+use super::{bridge::{Bridge, TermBank}, data::{self, all_eq, size::EV, Data}, scheduler::{self, Scheduler}, cost_policy::{CostController, CostPolicy}};
 
 pub trait EnumFn = FnMut(Expr, Value) -> Result<(), ()>;
 
@@ -25,10 +26,21 @@ pub trait EnumFn = FnMut(Expr, Value) -> Result<(), ()>;
 /// 
 pub static STOP_SIGNAL: AtomicBool = AtomicBool::new(false);
 
-/// Holds all tasks waiting for a cost limit to be released.
+/// Generation of the examples being solved. 
+/// 
+/// Enumeration runs synchronously inside worker threads, so aborting their tasks does not stop them; instead, every executor records the epoch at its creation and gives up once it changes. 
+/// It is bumped whenever the examples are extended (e.g. by a CEGIS counterexample), which makes all running executors obsolete.
+pub static EPOCH: AtomicUsize = AtomicUsize::new(0);
+
+/// Deterministic mode (`--deterministic`): time limits of executors are replaced by budgets of enumerated terms (`TERMS_PER_MS` per millisecond), and nested threads are waited for at fixed points of the enumeration, so that runs do not depend on timing.
+pub static DETERMINISTIC: AtomicBool = AtomicBool::new(false);
+
+/// Number of terms standing for one millisecond of time limit in deterministic mode.
+pub const TERMS_PER_MS: usize = 100;
+
+/// Holds all tasks waiting for a cost limit to be released, resumed by priority (see [`Scheduler`]).
 pub struct TaskWaitingCost {
-    sender: broadcastque::Sender<()>,
-    cur_cost: usize,
+    scheduler: Scheduler,
 }
 
 impl Default for TaskWaitingCost {
@@ -41,28 +53,27 @@ impl Default for TaskWaitingCost {
 impl TaskWaitingCost {
     /// Creates a new instance of TaskWaitingCost. 
     pub fn new() -> Self {
-        TaskWaitingCost { sender: broadcastque::channel(), cur_cost: 0  }
+        TaskWaitingCost { scheduler: Scheduler::new() }
     }
     
     /// Increments the cost associated with a task and updates the problem's used cost. 
     /// 
-    /// It will wait for the specified amount of cost to be released before proceeding.
+    /// The task then waits until the scheduler resumes it, which happens sooner for cheaper subproblems.
     pub async fn inc_cost(&mut self, problem: &mut Problem, amount: usize) {
-        let mut rv: broadcastque::Reciever<()> = self.sender.reciever();
         problem.used_cost += amount;
-        let amount = problem.used_cost as isize - self.cur_cost as isize;
-        if amount > 0 {
-            for _ in 0..amount {
-                let _ = rv.next().await;
-            }
-        }
+        let rv = self.scheduler.wait(problem.used_cost, scheduler::value_size(&problem.value));
+        rv.await;
     }
     
-    /// Releases a specified number of waiting slots on a task queue. 
-    /// 
-    /// This function sends a signal to the task queue, allowing the specified number of tasks to proceed.
+    /// Number of waiting tasks.
+    pub fn len(&self) -> usize {
+        self.scheduler.len()
+    }
+
+    /// Grants a budget of `count` units of cost to the waiting tasks, resuming them by priority. 
     pub fn release_cost_limit(&mut self, count: usize) {
-        self.sender.send((), count);
+        let resumed = self.scheduler.release(count);
+        debg2!("Resumed {} tasks, {} waiting", resumed, self.scheduler.len());
     }
 }
 
@@ -106,6 +117,14 @@ pub struct Executor {
     pub bridge: Bridge,
     /// Timestamp when the executor started.
     pub start_time: time::Instant,
+    /// Value of `EPOCH` when the executor was created.
+    epoch: usize,
+    /// Memory pressure at the last report of this executor (`--mem-limit`).
+    pressure: Cell<MemPressure>,
+    /// Shared term bank replayed instead of enumerating the sizes it covers, with the indices of the examples of `ctx` among those of the bank.
+    shared: Option<(&'static TermBank, Vec<usize>)>,
+    /// Budget released to the waiting tasks (`--cost-policy`).
+    cost: RefCell<CostController>,
 }
 
 impl Executor {
@@ -114,16 +133,49 @@ impl Executor {
         self.subproblem_count.get()
     }
     /// Creates a new instance. 
-    pub fn new(ctx: Context, cfg: Cfg) -> Self {
+    pub fn new(ctx: Context, mut cfg: Cfg) -> Self {
+        if cfg.config.cond_search {
+            cfg.add_thresholds(&ctx);
+        }
         let data = Data::new(&cfg, &ctx);
+        // Adapting to the timing would make deterministic runs depend on it
+        let policy = if DETERMINISTIC.load(Ordering::Relaxed) { CostPolicy::Fixed } else { cfg.config.cost_policy };
+        let cost = CostController::new(policy, cfg.config.increase_cost_limit).into();
         let deducers = (0..cfg.len()).map(|i, | DeducerEnum::from_nt(&cfg, &ctx, i)).collect_vec();
         let exec = Self { counter: 0.into(), subproblem_count: 0.into(), ctx, cfg, data, deducers, expr_collector: Vec::new().into(),
             cur_size: 0.into(), cur_nt: 0.into(), waiting_tasks: TaskWaitingCost::new().into(),
             top_task: task::spawn(futures::future::pending()).into(), bridge: Bridge::new(),
-            start_time: Instant::now() };
+            start_time: Instant::now(), epoch: EPOCH.load(Ordering::Relaxed), pressure: MemPressure::Normal.into(), shared: None, cost };
         TextObjData::build_trie(&exec);
         exec
     }
+    /// Replays the shared term bank (see [`bridge::share_bank`](super::bridge::share_bank)), if there is one holding for the examples `exs` of the `examples` current ones that form `ctx`. 
+    /// 
+    /// The values of the bank are projected down to `exs` with [`Value::with_examples`] instead of evaluating the terms again.
+    pub fn with_shared_bank(mut self, exs: &[usize], examples: usize) -> Self {
+        self.shared = super::bridge::shared_bank(exs, examples).map(|b| (b, exs.to_vec()));
+        self
+    }
+    /// Enumerates the terms of `cfg` up to size `k` on the examples of `ctx`, without solving anything, and returns them as a bank to be shared with [`bridge::share_bank`](super::bridge::share_bank). 
+    /// 
+    /// The bank stops at the last size enumerated completely within the time limit of `cfg`.
+    pub fn enumerate_bank(ctx: Context, mut cfg: Cfg, k: usize) -> TermBank {
+        cfg.config.size_limit = k + 1;
+        let exec = Self::new(ctx, cfg);
+        let this = unsafe { (&exec as *const Executor).as_ref::<'static>().unwrap() };
+        let complete = match this.run() {
+            Ok(()) => k,
+            Err(()) => this.cur_size.get().saturating_sub(1),
+        };
+        let bank = (1..=complete).map(|size| this.data.iter().map(|d| d.size.get_all(size).to_vec()).collect()).collect();
+        TermBank { examples: this.ctx.len, bank }
+    }
+    /// Creates an executor whose grammar consists of the single non-terminal `nt`. 
+    /// 
+    /// Together with [`Executor::deduce_with`], this lets a test drive one deducer on a crafted [`Problem`] without a full synthesis problem.
+    pub fn for_single_nt(ctx: Context, nt: NonTerminal, config: CfgConfig) -> Self {
+        Self::new(ctx, Cfg::from_single_nt(nt, config))
+    }
     pub fn top_task(&self) -> &mut JoinHandle<&'static Expr> {
         unsafe { self.top_task.as_mut() }
     }
@@ -161,9 +213,9 @@ impl Executor {
         let cond = futures::future::select(left, right).await;
         match cond {
             futures::future::Either::Left((c, _)) => 
-                expr!(Ite {c} {result} "").galloc(),
+                provenance::record(expr!(Ite {c} {result} "").galloc(), Provenance::Deduced("Executor::generate_condition")),
             futures::future::Either::Right((c, _)) => 
-                expr!(Ite {c} "" {result}).galloc(),
+                provenance::record(expr!(Ite {c} "" {result}).galloc(), Provenance::Deduced("Executor::generate_condition")),
         }
     }
     /// Attempts to solve the top-level problem and manage its execution. 
@@ -188,6 +240,8 @@ impl Executor {
     }
 
     /// Attempts to solve the top problem with a limit within the `Executor`. 
+    /// 
+    /// The result is discarded if it is larger than the maximum size, or if it produces a forbidden output on a negative example.
     pub fn solve_top_with_limit(self) -> Option<&'static Expr> {
         let problem = Problem::root(0, self.ctx.output);
         let this = unsafe { (&self as *const Executor).as_ref::<'static>().unwrap() };
@@ -195,6 +249,29 @@ impl Executor {
         *this.top_task() = task::spawn(this.deducers[problem.nt].deduce(this, problem));
         let _ = this.run();
         self.bridge.abort_all();
+        if let Poll::Ready(r) = this.top_task().poll_rc_nocx() {
+            if r.size() > this.cfg.config.max_size {
+                warn!("Discarding {:?}: larger than the maximum size {}", r, this.cfg.config.max_size);
+                return None;
+            }
+            // Deduced expressions are not checked against the negative examples on the way
+            if this.ctx.matches_negative(r) {
+                warn!("Discarding {:?}: matches a negative example", r);
+                return None;
+            }
+            Some(r)
+        } else { None }
+    }
+
+    /// Runs the given deducer on `problem` as the top task while enumerating terms. 
+    /// 
+    /// Returns the deduced expression, or `None` if the enumeration stops (size or time limit) before the deducer completes.
+    pub fn deduce_with<D: Deducer + 'static>(self, deducer: &'static D, problem: Problem) -> Option<&'static Expr> {
+        let this = unsafe { (&self as *const Executor).as_ref::<'static>().unwrap() };
+        this.subproblem_count.update(|x| x+1);
+        *this.top_task() = task::spawn(deducer.deduce(this, problem));
+        let _ = this.run();
+        self.bridge.abort_all();
         if let Poll::Ready(r) = this.top_task().poll_rc_nocx() {
             Some(r)
         } else { None }
@@ -211,45 +288,94 @@ impl Executor {
     
     #[inline]
     /// Handle when a new express is enumerated.
+    /// 
+    /// Under memory pressure (`--mem-limit`), new terms are still matched against the problems of the deducers, but no longer stored for larger sizes; past the hard threshold, the enumeration stops.
     pub fn enum_expr(&'static self, e: Expr, v: Value) -> Result<(), ()> {
         if self.counter.get() % 10000 == 1 {
             if self.counter.get() % 300000 == 1 {
                 info!("Searching size={} [{}] - {:?} {:?} {}", self.cur_size.get(), self.counter.get(), e, v, self.subproblem_count.get());
             }
-            self.waiting_tasks().release_cost_limit(self.cfg.config.increase_cost_limit);
+            let budget = self.cost.borrow_mut().budget(Instant::now(), self.counter.get(), self.waiting_tasks().len(), self.elapsed());
+            self.waiting_tasks().release_cost_limit(budget);
             self.bridge.check();
+            if EPOCH.load(Ordering::Relaxed) != self.epoch {
+                return Err(());
+            }
+            self.pressure.set(galloc::report_usage());
+            if self.pressure.get() >= MemPressure::Hard {
+                return Err(());
+            }
         }
         self.counter.update(|x| x + 1);
+        if v == self.ctx.output && self.ctx.matches_negative(&e) {
+            debg!("Rejecting {:?}: matches a negative example", e);
+            return Ok(());
+        }
         if self.ctx.output.ty() != Type::Bool && v.ty() == Type::Bool {
-            self.collect_condition(&e);
+            self.collect_condition(&e, v);
+        } else if self.cur_data().pruned(v) {
+            debg2!("Pruning {:?}: dominated", e);
         } else if let Some(e) = self.cur_data().update(self, e, v)? {
-            self.collect_expr(e,v);
+            provenance::record(e, Provenance::Enumerated(self.cur_size.get()));
+            if self.pressure.get() < MemPressure::Soft {
+                self.collect_expr(e,v);
+            }
         }
-        if self.top_task().is_ready() || (Instant::now() - self.start_time).as_millis() >= self.cfg.config.time_limit as u128 {
+        if self.top_task().is_ready() || self.out_of_time() {
             return Err(());
         }
-        while STOP_SIGNAL.load(std::sync::atomic::Ordering::Relaxed) { std::hint::spin_loop() }
+        while STOP_SIGNAL.load(Ordering::Relaxed) { std::hint::spin_loop() }
         Ok(())
     }
-    /// Collects and inserts an expression into a shared collection of conditions `CONDITIONS` 
-    fn collect_condition(&'static self, e: &Expr) {
-        if let Some(x) = CONDITIONS.lock().as_mut() { x.insert(e) }
+    /// Returns the fraction of the time limit spent, 0 without time limit.
+    fn elapsed(&self) -> f64 {
+        if self.cfg.config.time_limit == usize::MAX { return 0.0; }
+        (Instant::now() - self.start_time).as_millis() as f64 / self.cfg.config.time_limit.max(1) as f64
+    }
+    /// Returns whether the time limit is exceeded, or in deterministic mode, the corresponding budget of enumerated terms.
+    fn out_of_time(&self) -> bool {
+        if DETERMINISTIC.load(Ordering::Relaxed) {
+            self.counter.get() >= self.cfg.config.time_limit.saturating_mul(TERMS_PER_MS)
+        } else {
+            (Instant::now() - self.start_time).as_millis() >= self.cfg.config.time_limit as u128
+        }
+    }
+    /// Collects and inserts an expression into a shared collection of conditions `CONDITIONS`, reusing its value `v` when it is enumerated on all the examples (see `ConditionTracker::insert_evaluated`).
+    fn collect_condition(&'static self, e: &Expr, v: Value) {
+        if let Some(x) = CONDITIONS.lock().as_mut() { x.insert_evaluated(e, v) }
     }
     /// Start Enumeration
+    /// 
+    /// Sizes covered by a term bank restored from a checkpoint, or by the shared term bank, are replayed from it instead of being enumerated.
     fn run(&'static self) -> Result<(), ()> {
         let _ = self.extract_expr_collector();
+        let restored = checkpoint::restored();
         for size in 1 ..self.cfg.config.size_limit {
+            let level = restored.and_then(|r| r.bank.get(size - 1).map(|level| (r.examples == self.ctx.len, level)));
+            let shared = self.shared.as_ref().filter(|_| level.is_none()).and_then(|(b, exs)| b.bank.get(size - 1).map(|level| (exs, level)));
+            observer::notify(|o| o.on_size_increase(self.ctx.len, size));
             for (nt, ntdata) in self.cfg.iter().enumerate() {
                 self.cur_size.set(size);
                 self.cur_nt.set(nt);
-                info!("Enumerating size={} nt={} with - {}", size, ntdata.name, self.counter.get());
-                self.cur_data().to.enumerate(self)?;
-                for rule in &ntdata.rules {
-                    rule.enumerate(self)?;
+                if let Some((same_examples, level)) = level {
+                    info!("Restoring size={} nt={} with - {}", size, ntdata.name, self.counter.get());
+                    for (e, v) in level[nt].iter() {
+                        self.enum_expr((*e).clone(), if same_examples { *v } else { e.eval(&self.ctx) })?;
+                    }
+                } else if let Some((exs, level)) = shared {
+                    info!("Projecting shared size={} nt={} with - {}", size, ntdata.name, self.counter.get());
+                    for (e, v) in level[nt].iter() {
+                        self.enum_expr((*e).clone(), v.with_examples(exs))?;
+                    }
+                } else {
+                    info!("Enumerating size={} nt={} with - {}", size, ntdata.name, self.counter.get());
+                    log::progress::emit(log::progress::Event::Enumerate { examples: self.ctx.len, size, nt: &ntdata.name, terms: self.cur_data().size.count(), enumerated: self.counter.get() });
+                    ntdata.enumerate(self)?;
                 }
                 
                 self.cur_data().size.add(size, self.extract_expr_collector());
             }
+            checkpoint::record_level(self, size);
         }
         Ok(())
     }
@@ -11,4 +11,10 @@ pub mod executor;
 pub mod enumeration;
 
 /// Bridge for interthread communication
-pub mod bridge;
\ No newline at end of file
+pub mod bridge;
+
+/// Priority scheduling of the deduction tasks waiting for cost
+pub mod scheduler;
+
+/// Budget of the deduction tasks waiting for cost (`--cost-policy`)
+pub mod cost_policy;
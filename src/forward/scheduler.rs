@@ -0,0 +1,111 @@
+use simple_rc_async::sync::oneshot;
+
+use crate::value::Value;
+
+/// Number of releases after which a waiting task gains one unit of priority, so that expensive tasks are delayed but never starved.
+pub const AGING: usize = 4;
+
+/// A deduction task waiting for its turn.
+struct Waiting {
+    /// Cost used by the subproblem of the task so far
+    cost: usize,
+    /// Size of the value of the subproblem, see [`value_size`]
+    size: usize,
+    /// Number of releases before the task started waiting
+    since: usize,
+    seq: usize,
+    sender: oneshot::Sender<()>,
+}
+
+/// Cooperative scheduler of deduction tasks.
+///
+/// Tasks wait with [`Scheduler::wait`] before exploring a costly deduction (e.g. a witness around a delimiter). At each release, the enumeration grants a budget, spent on resuming waiting tasks by priority: the cheapest subproblems first (least `used_cost`, then smallest value), each consuming its cost, as long as it fits in the budget.
+/// Expensive explorations are thereby preempted in favour of cheap ones such as formatter deductions, and the priority of a task grows with the releases it waited for.
+#[derive(Default)]
+pub struct Scheduler {
+    waiting: Vec<Waiting>,
+    releases: usize,
+    seq: usize,
+}
+
+/// Size of the value of a subproblem: the total length of its strings or lists, or else its number of examples.
+pub fn value_size(v: &Value) -> usize {
+    v.length_inside().map_or(v.len(), |l| l.iter().sum())
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Number of waiting tasks.
+    pub fn len(&self) -> usize {
+        self.waiting.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.waiting.is_empty()
+    }
+    /// Enqueues a task whose subproblem has used `cost` and has a value of size `size`; the returned receiver completes when the task is resumed.
+    pub fn wait(&mut self, cost: usize, size: usize) -> oneshot::Reciever<()> {
+        let rv = oneshot::channel();
+        self.seq += 1;
+        self.waiting.push(Waiting { cost, size, since: self.releases, seq: self.seq, sender: rv.sender() });
+        rv
+    }
+    /// Resumes waiting tasks by priority while their costs fit in `budget`, returning the number of tasks resumed.
+    ///
+    /// Tasks costing more than what is left of the budget keep waiting. Aging lowers the cost counted for a task by one unit every [`AGING`] releases, so that it fits in the budget after waiting long enough.
+    pub fn release(&mut self, budget: usize) -> usize {
+        self.releases += 1;
+        let releases = self.releases;
+        let aged_cost = |w: &Waiting| w.cost.saturating_sub((releases - w.since) / AGING).max(1);
+        self.waiting.sort_by_key(|w| (aged_cost(w), w.size, w.seq));
+        let mut spent = 0;
+        let count = self.waiting.iter().take_while(|w| {
+            let take = spent + aged_cost(w) <= budget;
+            if take { spent += aged_cost(w); }
+            take
+        }).count();
+        // Resumed tasks may wait again, so they are removed from the queue before
+        let ready = self.waiting.drain(..count).collect::<Vec<_>>();
+        for w in ready {
+            let _ = w.sender.send(());
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use itertools::Itertools;
+    use simple_rc_async::task;
+
+    use super::Scheduler;
+
+    #[test]
+    fn test_scheduler() {
+        let mut s = Scheduler::new();
+        let resumed = Rc::new(RefCell::new(Vec::new()));
+        let _tasks = [(10, 5), (1, 50), (1, 3), (3, 1)].into_iter().enumerate().map(|(i, (cost, size))| {
+            let (rv, resumed) = (s.wait(cost, size), resumed.clone());
+            task::spawn(async move { rv.await; resumed.borrow_mut().push(i); })
+        }).collect_vec();
+        assert_eq!(s.len(), 4);
+        // The cheapest first, then the smallest
+        assert_eq!(s.release(2), 2);
+        assert_eq!(*resumed.borrow(), vec![2, 1]);
+        // Over the budget until aging lowers its cost to 2
+        assert_eq!(s.release(2), 0);
+        assert_eq!(s.release(2), 0);
+        assert_eq!(s.release(2), 1);
+        assert_eq!(*resumed.borrow(), vec![2, 1, 3]);
+        for _ in 5..32 {
+            assert_eq!(s.release(2), 0);
+        }
+        assert_eq!(s.len(), 1);
+        assert_eq!(s.release(2), 1);
+        assert_eq!(*resumed.borrow(), vec![2, 1, 3, 0]);
+        assert!(s.is_empty());
+    }
+}
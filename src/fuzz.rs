@@ -0,0 +1,213 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{expr::{context::Context, Expr}, galloc::AllocForStr, text::parsing::date, utils::F64, value::{ConstValue, Type, Value}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Class of a character of an example input, kept by the generated inputs.
+enum CharClass {
+    Digit,
+    Upper,
+    Lower,
+    /// Any other character is kept as it is
+    Literal(char),
+}
+
+impl CharClass {
+    fn of(c: char) -> Self {
+        if c.is_ascii_digit() { Self::Digit }
+        else if c.is_uppercase() { Self::Upper }
+        else if c.is_lowercase() { Self::Lower }
+        else { Self::Literal(c) }
+    }
+    fn sample(self, rng: &mut StdRng) -> char {
+        match self {
+            Self::Digit => rng.gen_range('0'..='9'),
+            Self::Upper => rng.gen_range('A'..='Z'),
+            Self::Lower => rng.gen_range('a'..='z'),
+            Self::Literal(c) => c,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Shape of one argument, mined from its values on the examples.
+struct Column {
+    ty: Type,
+    seen: Vec<ConstValue>,
+    /// Shortest and longest string argument, in characters
+    lengths: (usize, usize),
+    /// Whether the string arguments look like dates, in which case runs of digits keep their observed range
+    date: bool,
+    /// Range of the values of the `k`-th run of digits in the string arguments
+    runs: Vec<(u64, u64)>,
+}
+
+fn digit_runs(s: &str) -> impl Iterator<Item=&str> {
+    s.split(|c: char| !c.is_ascii_digit()).filter(|r| !r.is_empty())
+}
+
+impl Column {
+    fn new(value: &Value, len: usize) -> Self {
+        let seen = (0..len).map(|i| value.get_const(i)).collect_vec();
+        let strs = seen.iter().filter_map(|c| c.as_str()).collect_vec();
+        let lengths = strs.iter().map(|s| s.chars().count()).minmax().into_option().unwrap_or((0, 0));
+        let date = !strs.is_empty() && strs.iter().all(|s| date::detector(s));
+        let mut runs: Vec<(u64, u64)> = Vec::new();
+        for s in strs.iter() {
+            for (k, run) in digit_runs(s).enumerate() {
+                let Ok(n) = run.parse::<u64>() else { continue };
+                match runs.get_mut(k) {
+                    Some(r) => *r = (r.0.min(n), r.1.max(n)),
+                    None => runs.push((n, n)),
+                }
+            }
+        }
+        Self { ty: value.ty(), seen, lengths, date, runs }
+    }
+
+    /// Draws a string with the character classes of a seen one.
+    ///
+    /// In a date, the other characters (separators, month names) are kept and each run of digits is replaced by a number in the range of the run at the same position, padded to the same width.
+    /// Otherwise, the length of the string may change within the observed lengths.
+    fn string(&self, rng: &mut StdRng) -> Option<&'static str> {
+        let template = self.seen.choose(rng)?.as_str()?;
+        let mut result = String::new();
+        if self.date {
+            let mut rest = template;
+            let mut k = 0;
+            while let Some(start) = rest.find(|c: char| c.is_ascii_digit()) {
+                result += &rest[..start];
+                let end = rest[start..].find(|c: char| !c.is_ascii_digit()).map_or(rest.len(), |e| start + e);
+                let (lo, hi) = self.runs.get(k).copied().unwrap_or((0, 9));
+                result += &format!("{:0width$}", rng.gen_range(lo..=hi), width = end - start);
+                rest = &rest[end..];
+                k += 1;
+            }
+            result += rest;
+        } else {
+            let classes = template.chars().map(CharClass::of).collect_vec();
+            let len = if rng.gen_bool(0.25) { rng.gen_range(self.lengths.0..=self.lengths.1) } else { classes.len() };
+            for i in 0..len {
+                // Past the length of the template, the classes are picked at random from it.
+                let class = match classes.get(i) {
+                    Some(c) => Some(c),
+                    None => classes.choose(rng),
+                };
+                if let Some(c) = class { result.push(c.sample(rng)); }
+            }
+        }
+        Some(result.galloc_str())
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> Option<ConstValue> {
+        let pick = |rng: &mut StdRng| self.seen.choose(rng).copied();
+        Some(match self.ty {
+            Type::Str => ConstValue::Str(self.string(rng)?),
+            Type::Int => {
+                let (lo, hi) = self.seen.iter().filter_map(|c| c.as_i64()).minmax().into_option()?;
+                let margin = (hi - lo).max(10);
+                ConstValue::Int(rng.gen_range(lo - margin..=hi + margin))
+            }
+            Type::Float => {
+                let (lo, hi) = self.seen.iter().filter_map(|c| c.as_f64()).minmax_by(|a, b| a.total_cmp(b)).into_option()?;
+                let margin = (hi - lo).max(10.0);
+                ConstValue::Float(F64::new((rng.gen_range(lo - margin..=hi + margin) * 100.0).round() / 100.0))
+            }
+            Type::Bool => ConstValue::Bool(rng.gen_bool(0.5)),
+            Type::Char => ConstValue::Char(CharClass::of(pick(rng)?.as_char()?).sample(rng)),
+            _ => pick(rng)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Generator of random inputs resembling the inputs of a [`Context`]: strings keep the character classes, lengths and date layout of the examples, numbers stay around the observed ones.
+pub struct InputGenerator {
+    columns: Vec<Column>,
+    rng: StdRng,
+}
+
+impl InputGenerator {
+    /// Mines the shapes of the arguments from the examples of `ctx`.
+    pub fn new(ctx: &Context, seed: u64) -> Self {
+        Self { columns: ctx.p.iter().map(|v| Column::new(v, ctx.len)).collect(), rng: StdRng::seed_from_u64(seed) }
+    }
+
+    /// Draws one row of arguments, or `None` if an argument has no example to draw it from.
+    pub fn sample(&mut self) -> Option<Vec<ConstValue>> {
+        let rng = &mut self.rng;
+        self.columns.iter().map(|c| c.sample(rng)).collect()
+    }
+
+    /// Draws `n` rows of arguments, as a context without outputs.
+    pub fn context(&mut self, n: usize) -> Option<Context> {
+        let rows = (0..n).map(|_| self.sample()).collect::<Option<Vec<_>>>()?;
+        let inputs = self.columns.iter().enumerate().map(|(i, c)| Value::from_const(c.ty, rows.iter().map(|r| r[i]).collect_vec().into_iter())).collect();
+        Some(Context::new(n, inputs, vec![], Value::Null))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Output of a solution on one generated input.
+pub enum Outcome {
+    Output(String),
+    /// The solution is undefined on the input
+    Null,
+    /// The evaluation panicked, with the message of the panic
+    Panic(String),
+}
+
+/// Evaluates `e` on each row of `ctx` separately, so that a panic is attributed to the input which caused it.
+pub fn run(e: &'static Expr, ctx: &Context) -> Vec<(Vec<String>, Outcome)> {
+    (0..ctx.len).map(|i| {
+        let row = Context::new(1, ctx.p.iter().map(|v| Value::from_const(v.ty(), std::iter::once(v.get_const(i)))).collect(), vec![], Value::Null);
+        let inputs = row.p.iter().map(|v| v.to_display_string(0)).collect();
+        let outcome = match catch_unwind(AssertUnwindSafe(|| e.eval(&row))) {
+            Ok(Value::Null) => Outcome::Null,
+            Ok(v) => Outcome::Output(v.to_display_string(0)),
+            Err(p) => Outcome::Panic(p.downcast_ref::<&str>().map(|s| s.to_string()).or_else(|| p.downcast_ref::<String>().cloned()).unwrap_or_default()),
+        };
+        (inputs, outcome)
+    }).collect()
+}
+
+/// Formats the outputs of a solution on the generated inputs as `;` comment lines, one per input, with a summary of the flagged ones.
+pub fn report(results: &[(Vec<String>, Outcome)]) -> String {
+    let mut lines = results.iter().map(|(inputs, outcome)| match outcome {
+        Outcome::Output(o) => format!("; ({}) -> {}", inputs.join(" "), o),
+        Outcome::Null => format!("; ({}) -> null  [FLAGGED]", inputs.join(" ")),
+        Outcome::Panic(m) => format!("; ({}) -> panic: {}  [FLAGGED]", inputs.join(" "), m),
+    }).collect_vec();
+    let flagged = results.iter().filter(|(_, o)| !matches!(o, Outcome::Output(_))).count();
+    lines.push(format!("; fuzzing: {} of {} inputs flagged", flagged, results.len()));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr, expr::context::Context, galloc::{AllocForAny, AllocForExactSizeIter}};
+
+    use super::{report, run, InputGenerator, Outcome};
+
+    #[test]
+    fn test_fuzz() {
+        let dates = ["2021-03-14", "1999-12-01", "2010-07-30"].into_iter().galloc_scollect();
+        let ctx = Context::new(3, vec![dates.into()], vec![], dates.into());
+        let generated = InputGenerator::new(&ctx, 0).context(50).unwrap();
+        for s in generated.p[0].to_str() {
+            let parts: Vec<&str> = s.split('-').collect();
+            assert_eq!(parts.iter().map(|p| p.len()).collect::<Vec<_>>(), vec![4, 2, 2]);
+            assert!((1999..=2021).contains(&parts[0].parse::<u64>().unwrap()));
+            assert!((1..=12).contains(&parts[1].parse::<u64>().unwrap()));
+        }
+        let e = expr!(SubStr [0] 5 2).galloc();
+        let results = run(e, &generated);
+        assert_eq!(results.len(), 50);
+        // The month, quoted, e.g. `"03"`
+        assert!(results.iter().all(|(_, o)| matches!(o, Outcome::Output(s) if s.len() == 4)));
+        assert!(report(&results).ends_with("; fuzzing: 0 of 50 inputs flagged"));
+    }
+}
@@ -1,4 +1,6 @@
+use std::cell::Cell;
 use std::str::{from_utf8, from_utf8_unchecked};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
 use bumpalo::collections::{String as BString, CollectIn};
 use bumpalo::Bump;
 use ext_trait::extension;
@@ -17,8 +19,14 @@ impl<T> T {
     /// This implementation of `galloc` takes ownership of the `T` instance and uses the `alloc` function to place it in a location with a static lifetime, presumably managing it in a way that ensures its persistence for the duration of the program. 
     /// This can be particularly useful for scenarios where a static lifetime is required, such as when interfacing with systems or patterns that necessitate global state or long-lived data.
     /// 
+    /// Counted as [`Category::Other`] by `--galloc-stats`; see [`galloc_as`](Self::galloc_as) (and `Expr::galloc`) for the others.
     fn galloc(self) -> &'static T {
-        alloc(self)
+        alloc(self, Category::Other)
+    }
+    #[inline(always)]
+    /// Allocates the instance like [`galloc`](Self::galloc), counted as `category` by `--galloc-stats`.
+    fn galloc_as(self, category: Category) -> &'static T {
+        alloc(self, category)
     }
     #[inline(always)]
     /// Provides a method that moves the instance and returns a reference to it allocated with a static lifetime. 
@@ -27,7 +35,7 @@ impl<T> T {
     /// These semantics allow the user to safely assume that the reference will not expire during the program's execution, making it suitable for long-lived data structures or operations that require such guarantees.
     /// 
     fn galloc_mut(self) -> &'static T {
-        alloc_mut(self)
+        alloc_mut(self, Category::Other)
     }
 }
 
@@ -39,8 +47,14 @@ impl<T: ExactSizeIterator> T {
     /// This method consumes the instance of the type, calling `alloc_iter` which is expected to allocate or collect items into a static slice. 
     /// This process typically involves aggregating items from the iterator and providing a reference that persists for the 'static lifetime, enabling access to the collected items beyond the scope of the function execution.
     /// 
+    /// Counted as [`Category::Value`] by `--galloc-stats`.
     fn galloc_scollect(self) -> &'static [T::Item] {
-        alloc_iter(self)
+        alloc_iter(self, Category::Value)
+    }
+    #[inline(always)]
+    /// Collects the items like [`galloc_scollect`](Self::galloc_scollect), counted as `category` by `--galloc-stats`.
+    fn galloc_scollect_as(self, category: Category) -> &'static [T::Item] {
+        alloc_iter(self, category)
     }
 }
 
@@ -66,8 +80,14 @@ impl<T: Iterator> T {
     /// This method consumes the iterator from which it is called and collects all of its items using an allocation function. 
     /// The items are stored in a statically allocated slice, which means that the lifetime of the collected items is tied to the entire duration of the program. 
     /// This could be particularly useful in contexts where the collected items need to be immutable and available for the entire runtime, though caution should be taken to ensure memory usage is within acceptable bounds considering the static lifetime.
+    /// Counted as [`Category::Value`] by `--galloc-stats`.
     fn galloc_collect(self) -> &'static [T::Item] {
-        alloc_iter2(self)
+        alloc_iter2(self, Category::Value)
+    }
+    #[inline(always)]
+    /// Collects the items like [`galloc_collect`](Self::galloc_collect), counted as `category` by `--galloc-stats`.
+    fn galloc_collect_as(self, category: Category) -> &'static [T::Item] {
+        alloc_iter2(self, category)
     }
 }
 
@@ -104,7 +124,8 @@ impl str {
 /// It leverages the `THR_ARENA` thread-local variable, using its `alloc` method to store the value. 
 /// The pointer to the allocated memory is then cast to a mutable pointer, and an unsafe operation is performed to convert it into a static reference. 
 /// As Rust's safety guarantees are bypassed here, this code snippet assumes that the reference's lifetime requirements will be properly managed to prevent undefined behavior.
-fn alloc<T>(t: T) -> &'static T {
+fn alloc<T>(t: T, category: Category) -> &'static T {
+    count(category, std::mem::size_of::<T>());
     THR_ARENA.with(|arena| {
         let p = arena.alloc(t) as *mut T;
         unsafe { p.as_ref::<'static>().unwrap() }
@@ -120,7 +141,8 @@ fn alloc<T>(t: T) -> &'static T {
 /// The allocation is done by casting the allocated value to a mutable raw pointer, and then converting it to a mutable reference with a static lifetime using unsafe operations. 
 /// The use of `unsafe` indicates that it is the programmer's responsibility to uphold the memory safety guarantees manually. 
 /// This approach is typically employed when fine-grained control over memory allocation and lifetimes is necessary for performance-critical code.
-fn alloc_mut<T>(t: T) -> &'static mut T {
+fn alloc_mut<T>(t: T, category: Category) -> &'static mut T {
+    count(category, std::mem::size_of::<T>());
     THR_ARENA.with(|arena| {
         let p = arena.alloc(t) as *mut T;
         unsafe { p.as_mut::<'static>().unwrap() }
@@ -136,7 +158,8 @@ fn alloc_mut<T>(t: T) -> &'static mut T {
 /// Inside the thread-local arena, it calls `alloc_slice_fill_iter` to allocate and fill the slice based on the provided iterator. 
 /// The memory address of this allocated slice is then manipulated as a mutable pointer, which is unsafely coerced into a reference with a static lifetime and returned. 
 /// This requires caution, as improper lifetime management may lead to undefined behavior.
-fn alloc_iter<T>(iter: impl ExactSizeIterator<Item= T>) -> &'static [T] {
+fn alloc_iter<T>(iter: impl ExactSizeIterator<Item= T>, category: Category) -> &'static [T] {
+    count(category, iter.len() * std::mem::size_of::<T>());
     THR_ARENA.with(|arena| {
         let p = arena.alloc_slice_fill_iter(iter) as *mut [T];
         unsafe { p.as_ref::<'static>().unwrap() }
@@ -155,7 +178,7 @@ fn try_alloc_iter<T>(iter: impl ExactSizeIterator<Item= Option<T>>) -> Option<&'
     THR_ARENA.with(|arena| {
         let p = arena as *const Bump;
         let vec: Option<BVec<_>> = unsafe { iter.collect_in(p.as_ref::<'static>().unwrap()) };
-        vec.map(|x| x.into_bump_slice())
+        vec.map(|x| { count(Category::Value, x.len() * std::mem::size_of::<T>()); x.into_bump_slice() })
     })
 }
 
@@ -167,10 +190,11 @@ fn try_alloc_iter<T>(iter: impl ExactSizeIterator<Item= Option<T>>) -> Option<&'
 /// It uses `collect_in` to gather the iterator's elements within the context of the arena's bump allocation, ensuring the allocation remains valid for the program's lifetime. 
 /// A reference to the bump allocator is obtained through unsafe pointer dereferencing, and the iterator's elements are collected into a bump vector (`BVec`). 
 /// This vector is then converted into a static reference to a slice stored in the arena memory, offering a performance advantage by reducing heap allocation overhead.
-fn alloc_iter2<T>(iter: impl Iterator<Item= T>) -> &'static [T] {
+fn alloc_iter2<T>(iter: impl Iterator<Item= T>, category: Category) -> &'static [T] {
     THR_ARENA.with(|arena| {
         let p = arena as *const Bump;
         let vec: BVec<_> = unsafe { iter.collect_in(p.as_ref::<'static>().unwrap()) };
+        count(category, vec.len() * std::mem::size_of::<T>());
         vec.into_bump_slice()
     })
 }
@@ -196,6 +220,7 @@ pub fn new_bvec<T>(cap: usize) -> BVec<'static, T> {
 /// Unsafe operations are required to perform this operation due to manual memory management and lifetime extension, ensuring that the memory is valid throughout the entire program execution within that thread, without being subject to Rust's usual borrowing constraints.
 /// 
 fn alloc_str(s: &str) -> &'static str {
+    count(Category::Str, s.len());
     THR_ARENA.with(|arena| {
         let p = arena.alloc_str(s) as *mut str;
         unsafe { p.as_ref::<'static>().unwrap() }
@@ -211,6 +236,7 @@ fn alloc_str(s: &str) -> &'static str {
 /// This approach improves performance by reducing heap allocations for short-lived objects in multi-threaded scenarios.
 /// 
 fn as_owned(s: &str) -> BString<'static> {
+    count(Category::Str, s.len());
     THR_ARENA.with(|arena| {
         let p = arena as *const Bump;
         unsafe { BString::from_str_in(s, p.as_ref::<'static>().unwrap()) }
@@ -241,6 +267,7 @@ fn collect_str_in_arena0(iter: impl Iterator<Item= char>) -> &'static str {
     THR_ARENA.with(|arena| {
         let p = arena as *const Bump;
         let vec: BString<'static> = unsafe { iter.collect_in::<BString>(p.as_ref::<'static>().unwrap()) };
+        count(Category::Str, vec.len());
         vec.into_bump_str()
     })
 }
@@ -258,13 +285,209 @@ impl<T: Iterator<Item=char>> T {
     }
 }
 
+/// Bytes allocated by the arenas of all live threads, as last reported by [`report_usage`].
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+/// Memory budget of the arenas in bytes (`--mem-limit`).
+static MEM_LIMIT: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Highest [`MemPressure`] reached so far, to warn once per level.
+static PRESSURE: AtomicU8 = AtomicU8::new(0);
+
+/// Bytes of the arena of the current thread already added to `ALLOCATED`, which are removed when the thread (and its arena) goes away.
+struct Reported(Cell<usize>);
+
+impl Drop for Reported {
+    fn drop(&mut self) {
+        ALLOCATED.fetch_sub(self.0.get(), Ordering::Relaxed);
+    }
+}
+
+thread_local! {
+    static REPORTED: Reported = const { Reported(Cell::new(0)) };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// How close the arenas are to the memory budget.
+pub enum MemPressure {
+    /// Below 70% of the budget, or no budget
+    Normal,
+    /// Above 70%: executors stop adding terms to the term bank, so that larger sizes are built from the current terms only
+    Soft,
+    /// Above 90%: executors stop enumerating, and the best solution is assembled from the solutions found so far
+    Hard,
+}
+
+/// Sets the memory budget of the arenas, in bytes.
+pub fn set_mem_limit(bytes: usize) {
+    MEM_LIMIT.store(bytes, Ordering::Relaxed);
+}
+
+/// Returns the bytes allocated by the arenas of all threads, as last reported.
+pub fn allocated() -> usize {
+    ALLOCATED.load(Ordering::Relaxed)
+}
+
+/// Returns the current memory pressure, from the last reported usage.
+pub fn pressure() -> MemPressure {
+    pressure_of(allocated(), MEM_LIMIT.load(Ordering::Relaxed))
+}
+
+fn pressure_of(used: usize, limit: usize) -> MemPressure {
+    if limit == usize::MAX { return MemPressure::Normal; }
+    let used = used as u128 * 10;
+    if used >= limit as u128 * 9 { MemPressure::Hard }
+    else if used >= limit as u128 * 7 { MemPressure::Soft }
+    else { MemPressure::Normal }
+}
+
+/// Adds the growth of the arena of the current thread since its last report to the global count, and returns the resulting memory pressure.
+/// 
+/// Arenas are thread-local, so each thread reports its own; this is cheap but not free, and is meant to be called periodically (e.g. every few thousand enumerated terms) rather than on each allocation.
+pub fn report_usage() -> MemPressure {
+    let now = THR_ARENA.with(|arena| arena.allocated_bytes());
+    let before = REPORTED.with(|r| r.0.replace(now));
+    ALLOCATED.fetch_add(now.saturating_sub(before), Ordering::Relaxed);
+    let p = pressure();
+    if PRESSURE.fetch_max(p as u8, Ordering::Relaxed) < p as u8 {
+        crate::warn!("Memory pressure {:?}: {} MB allocated of {} MB.", p, allocated() >> 20, MEM_LIMIT.load(Ordering::Relaxed) >> 20);
+    }
+    p
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Kind of the objects allocated in the arenas, as reported by `--galloc-stats`.
+/// 
+/// The category is given by the allocating call: `Expr::galloc` counts expressions, the slice collectors count values unless told otherwise with their `_as` variants, and everything else is `Other`.
+pub enum Category {
+    /// Expressions
+    Expr,
+    /// Slices of values, mostly the values of terms on the examples
+    Value,
+    /// String contents
+    Str,
+    Other,
+}
+
+impl Category {
+    const ALL: [Category; 4] = [Category::Expr, Category::Value, Category::Str, Category::Other];
+}
+
+static STATS_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Bytes allocated in the arenas per [`Category`], when `--galloc-stats` is enabled.
+static BYTES: [AtomicUsize; 4] = [AtomicUsize::new(0), AtomicUsize::new(0), AtomicUsize::new(0), AtomicUsize::new(0)];
+
+/// Number of regions popped and bytes they reclaimed.
+static REGIONS: AtomicUsize = AtomicUsize::new(0);
+static RECLAIMED: AtomicUsize = AtomicUsize::new(0);
+
+#[inline(always)]
+fn count(category: Category, bytes: usize) {
+    if STATS_ENABLED.load(Ordering::Relaxed) {
+        BYTES[category as usize].fetch_add(bytes, Ordering::Relaxed);
+    }
+}
+
+/// Enables the counting of allocated bytes per category (`--galloc-stats`).
+pub fn enable_stats() {
+    STATS_ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Returns the bytes allocated so far in each category.
+pub fn stats() -> Vec<(Category, usize)> {
+    Category::ALL.iter().map(|c| (*c, BYTES[*c as usize].load(Ordering::Relaxed))).collect()
+}
+
+fn format_stats(stats: &[(Category, usize)], regions: usize, reclaimed: usize) -> String {
+    let total: usize = stats.iter().map(|(_, b)| b).sum();
+    let mut res = String::from("category | bytes | share\n");
+    for (c, b) in stats {
+        res += &format!("{:<8} | {} | {:.1}%\n", format!("{:?}", c).to_lowercase(), b, *b as f64 * 100.0 / total.max(1) as f64);
+    }
+    res += &format!("total    | {}\nregions  | {} popped, {} bytes reclaimed\n", total, regions, reclaimed);
+    res
+}
+
+/// Prints the bytes allocated per category on stderr, if enabled.
+pub fn report_stats() {
+    if STATS_ENABLED.load(Ordering::Relaxed) {
+        eprint!("{}", format_stats(&stats(), REGIONS.load(Ordering::Relaxed), RECLAIMED.load(Ordering::Relaxed)));
+    }
+}
+
+/// A scoped arena for temporary slices, pushed with [`Region::push`] and popped (freeing all its allocations at once) when dropped.
+/// 
+/// Allocations in the thread arena live until the end of the program. Slices needed only while a subproblem is solved, such as the flattened lists of `Value::flatten_in`, can be allocated in a region owned by the subproblem instead.
+/// The returned references are `'static` like the other arena allocations, so that they fit in a [`Value`](crate::value::Value); the owner of the region must ensure they are no longer used once it is popped.
+pub struct Region {
+    arena: Bump,
+}
+
+impl Region {
+    /// Opens a new, empty region.
+    pub fn push() -> Self {
+        Region { arena: Bump::new() }
+    }
+
+    /// Collects an iterator into a slice allocated in the region.
+    pub fn collect<T>(&self, iter: impl Iterator<Item=T>) -> &'static [T] {
+        let vec: BVec<_> = iter.collect_in(&self.arena);
+        count(Category::Value, vec.len() * std::mem::size_of::<T>());
+        let p = vec.into_bump_slice() as *const [T];
+        unsafe { p.as_ref::<'static>().unwrap() }
+    }
+
+    /// Returns the bytes allocated in the region.
+    pub fn allocated(&self) -> usize {
+        self.arena.allocated_bytes()
+    }
+}
+
+impl Drop for Region {
+    fn drop(&mut self) {
+        REGIONS.fetch_add(1, Ordering::Relaxed);
+        RECLAIMED.fetch_add(self.allocated(), Ordering::Relaxed);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     #[test]
     fn test_alloc() {
-        let i = alloc(1isize);
+        let i = alloc(1isize, Category::Other);
         assert!(*i == 1)
     }
+
+    #[test]
+    fn test_pressure() {
+        assert_eq!(pressure_of(1 << 30, usize::MAX), MemPressure::Normal);
+        assert_eq!(pressure_of(69, 100), MemPressure::Normal);
+        assert_eq!(pressure_of(70, 100), MemPressure::Soft);
+        assert_eq!(pressure_of(95, 100), MemPressure::Hard);
+    }
+
+    #[test]
+    fn test_region() {
+        let region = Region::push();
+        let slice = region.collect((0..100u64).map(|i| i * 2));
+        assert_eq!(slice[99], 198);
+        assert!(region.allocated() >= 800);
+        let table = format_stats(&[(Category::Expr, 300), (Category::Value, 100), (Category::Str, 0), (Category::Other, 0)], 2, 4096);
+        assert!(table.contains("expr     | 300 | 75.0%"));
+        assert!(table.ends_with("regions  | 2 popped, 4096 bytes reclaimed\n"));
+    }
+
+    #[test]
+    fn test_category() {
+        enable_stats();
+        let bytes = |c: Category| BYTES[c as usize].load(Ordering::Relaxed);
+        let (expr, other) = (bytes(Category::Expr), bytes(Category::Other));
+        crate::expr::Expr::Var(0).galloc();
+        assert!(bytes(Category::Expr) >= expr + std::mem::size_of::<crate::expr::Expr>());
+        [1u64; 4].into_iter().galloc_scollect_as(Category::Other);
+        assert!(bytes(Category::Other) >= other + 32);
+    }
 }
 
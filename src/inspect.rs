@@ -0,0 +1,134 @@
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{expr::{cfg::{Cfg, ProdRule}, context::Context}, parser::problem::PBEProblem, text::parsing::ParsingOp, value::Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+/// Format of the report of `synthphonia inspect`.
+pub enum Format {
+    /// Human-readable text
+    Text,
+    /// A JSON object
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// A non-terminal of the grammar, with its rules.
+pub struct NonTerminalReport {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub rules: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+/// A text object found in the examples by a scanner of the grammar.
+pub struct TextObject {
+    /// Name of the scanner, e.g. `date.parse`
+    pub scanner: &'static str,
+    /// The text read by the scanner
+    pub text: &'static str,
+    /// The value read, as an SMT-LIB literal
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// What Synthphonia makes of a problem before solving it: its signature, the grammar it searches, and what it finds in the examples.
+pub struct Report {
+    pub signature: String,
+    pub examples: usize,
+    pub grammar: Vec<NonTerminalReport>,
+    pub text_objects: Vec<TextObject>,
+    pub constants: Vec<&'static str>,
+}
+
+impl Report {
+    /// Inspects `problem`, solved with the grammar `cfg` (as enriched for the examples), keeping at most `max_constants` extracted constants.
+    pub fn new(problem: &PBEProblem, cfg: &Cfg, max_constants: usize) -> Self {
+        let ctx = Context::from_examples(&problem.examples);
+        let grammar = cfg.iter().map(|nt| NonTerminalReport {
+            name: nt.name.clone(),
+            ty: format!("{:?}", nt.ty),
+            rules: nt.rules.iter().map(|r| format!("{:?}", r)).collect(),
+        }).collect();
+        Self {
+            signature: problem.synthfun().sig.to_string(),
+            examples: problem.examples.len(),
+            grammar,
+            text_objects: text_objects(cfg, &ctx),
+            constants: problem.examples.extract_constants(max_constants),
+        }
+    }
+
+    /// Renders the report in the given format.
+    pub fn format(&self, format: Format) -> Result<String, serde_json::Error> {
+        match format {
+            Format::Json => serde_json::to_string_pretty(self),
+            Format::Text => Ok(self.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Signature: {}", self.signature)?;
+        writeln!(f, "Examples: {}", self.examples)?;
+        writeln!(f, "Grammar: {} non-terminal(s), {} rule(s)", self.grammar.len(), self.grammar.iter().map(|nt| nt.rules.len()).sum::<usize>())?;
+        for (i, nt) in self.grammar.iter().enumerate() {
+            writeln!(f, "  nt{} {} {}: {} rule(s)", i, nt.name, nt.ty, nt.rules.len())?;
+            writeln!(f, "    {}", nt.rules.join(" "))?;
+        }
+        writeln!(f, "Text objects: {}", self.text_objects.len())?;
+        for o in self.text_objects.iter() {
+            writeln!(f, "  {} {:?} -> {}", o.scanner, o.text, o.value)?;
+        }
+        writeln!(f, "Constants: {}", self.constants.iter().map(|c| format!("{:?}", c)).join(" "))
+    }
+}
+
+/// Runs the scanners of the grammar (e.g. `date.parse`, `time.parse`) over the inputs and outputs of the examples, listing what each recognizes once.
+pub fn text_objects(cfg: &Cfg, ctx: &Context) -> Vec<TextObject> {
+    let scanners = cfg.iter().flat_map(|nt| nt.rules.iter()).filter_map(|r| match r {
+        ProdRule::Op1(op, _) => Some(*op),
+        _ => None,
+    }).unique_by(|op| op.name()).collect_vec();
+    let mut result = Vec::new();
+    for v in ctx.iter().chain(ctx.outputs()) {
+        let Value::Str(a) = v else { continue };
+        for input in a.iter() {
+            for op in scanners.iter() {
+                for (text, value) in op.parse_into(input) {
+                    result.push(TextObject { scanner: op.name(), text, value: format!("{:?}", value) });
+                }
+            }
+        }
+    }
+    result.into_iter().unique().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr::cfg::Cfg, parser::problem::PBEProblem};
+
+    use super::{Format, Report};
+
+    #[test]
+    fn test_report() {
+        let s = r#"
+            (set-logic SLIA)
+            (synth-fun f ((d String)) String ((Start String (ntString)) (ntString String (d " " (str.++ ntString ntString) (int.fmt ntDate))) (ntDate Int ((date.parse ntString)))))
+            (constraint (= (f "Due: Jan 5 2023") "Due 2023-01-05"))
+            (constraint (= (f "Due: Mar 9 2024") "Due 2024-03-09"))
+            (check-synth)
+        "#;
+        let problem = PBEProblem::parse(s).unwrap();
+        let cfg = Cfg::from_synthfun(problem.synthfun());
+        let report = Report::new(&problem, &cfg, 10);
+        assert_eq!(report.examples, 2);
+        assert_eq!(report.grammar.len(), cfg.len());
+        assert!(report.text_objects.iter().any(|o| o.scanner == "date.parse" && o.text.starts_with("Jan 5")));
+        assert!(report.constants.len() <= 10);
+        assert!(report.format(Format::Json).unwrap().contains("\"text_objects\""));
+        assert!(report.format(Format::Text).unwrap().starts_with("Signature: f ((d String)) String"));
+    }
+}
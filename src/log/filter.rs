@@ -0,0 +1,84 @@
+use std::{fs::File, io, sync::{Mutex, OnceLock}};
+
+/// Environment variable selecting log levels per module, e.g. `SYNTHPHONIA_LOG=backward=debug,forward=info`.
+pub const ENV_VAR: &str = "SYNTHPHONIA_LOG";
+
+pub(super) static FILTERS: OnceLock<Filters> = OnceLock::new();
+pub(super) static LOG_FILE: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Parses a log level, by name (`crit`, `warn`, `info`, `debug`, `trace`) or as a number (0 to 5, 0 being silent).
+pub fn parse_level(s: &str) -> Option<u8> {
+    match s {
+        "off" => Some(0),
+        "crit" | "error" => Some(1),
+        "warn" => Some(2),
+        "info" => Some(3),
+        "debug" => Some(4),
+        "trace" => Some(5),
+        s => s.parse::<u8>().ok().filter(|l| *l <= 5),
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Log levels of modules, overriding the global level for the messages they log.
+pub struct Filters {
+    /// Level of the modules without a more specific filter, given as a bare level (`SYNTHPHONIA_LOG=info`).
+    pub default: Option<u8>,
+    /// Module paths relative to the crate (e.g. `backward` or `forward::executor`) with their level.
+    pub modules: Vec<(String, u8)>,
+}
+
+impl Filters {
+    /// Parses comma-separated filters `module=level`, or a bare `level` for all the other modules.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut result = Self::default();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.split_once('=') {
+                Some((module, level)) => {
+                    let level = parse_level(level.trim()).ok_or_else(|| format!("unknown log level {level:?} in {ENV_VAR}"))?;
+                    result.modules.push((module.trim().to_string(), level));
+                }
+                None => result.default = Some(parse_level(entry).ok_or_else(|| format!("unknown log level {entry:?} in {ENV_VAR}"))?),
+            }
+        }
+        Ok(result)
+    }
+    /// Returns the level of the module at `module_path` (as given by `module_path!()`), from its most specific filter.
+    pub fn level(&self, module_path: &str) -> Option<u8> {
+        let module = module_path.split_once("::").map_or("", |(_, m)| m);
+        self.modules.iter()
+            .filter(|(m, _)| module == m || module.strip_prefix(m.as_str()).is_some_and(|rest| rest.starts_with("::")))
+            .max_by_key(|(m, _)| m.len())
+            .map(|(_, l)| *l)
+            .or(self.default)
+    }
+}
+
+/// Sets up logging from the environment variable [`ENV_VAR`], and redirects the logs to `log_file` if given.
+/// Without filters or a log file, logging stays as set by `set_log_level`.
+pub fn init(log_file: Option<&str>) -> Result<(), String> {
+    if let Ok(spec) = std::env::var(ENV_VAR) {
+        let _ = FILTERS.set(Filters::parse(&spec)?);
+    }
+    if let Some(path) = log_file {
+        let file = File::create(path).map_err(|e: io::Error| format!("{path}: {e}"))?;
+        let _ = LOG_FILE.set(Mutex::new(file));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Filters;
+
+    #[test]
+    fn test_filters() {
+        let filters = Filters::parse("backward=debug, forward::executor=trace,forward=warn").unwrap();
+        assert_eq!(filters.level("synthphonia::backward::str"), Some(4));
+        assert_eq!(filters.level("synthphonia::forward::executor"), Some(5));
+        assert_eq!(filters.level("synthphonia::forward::future::task"), Some(2));
+        assert_eq!(filters.level("synthphonia::backwardx"), None);
+        assert_eq!(Filters::parse("info,text=0").unwrap().level("synthphonia::solutions"), Some(3));
+        assert!(Filters::parse("backward=loud").is_err());
+    }
+}
@@ -1,5 +1,11 @@
 use std::cell::RefCell;
 use std::cell::Cell;
+use std::io::Write;
+
+/// Machine-readable progress reports (`--progress`)
+pub mod progress;
+/// Per-module log levels (`SYNTHPHONIA_LOG`) and log files (`--log-file`)
+pub mod filter;
 
 
 thread_local! {
@@ -26,6 +32,31 @@ pub fn log_level() -> u8 {
     LOGLEVEL.get()
 }
 
+/// Returns whether a message of `level` logged from the module at `module_path` is written.
+///
+/// The level of the module is taken from the filters of [`filter::ENV_VAR`] if one applies, and is the level set by [`set_log_level`] otherwise.
+/// A thread whose level is set to 0 (e.g. while searching conditions) logs nothing, whatever the filters.
+pub fn enabled(level: u8, module_path: &str) -> bool {
+    let current = LOGLEVEL.get();
+    if current == 0 { return false; }
+    match filter::FILTERS.get().and_then(|f| f.level(module_path)) {
+        Some(l) => level <= l,
+        None => level <= current,
+    }
+}
+
+/// Writes an indented log message to the log file if one is set, and to stderr otherwise.
+/// `tag` is the colored label of the message on stderr, and `plain` the label written to the log file.
+pub fn emit(tag: &str, plain: &str, message: std::fmt::Arguments, file: &str, line: u32) {
+    INDENT.with_borrow(|s| {
+        if let Some(f) = filter::LOG_FILE.get() {
+            let _ = writeln!(f.lock().unwrap(), "{}{} {:?} {}:{}", s, plain, message, file, line);
+        } else {
+            eprintln!("{}{} \u{001b}[36m{:?}\u{001b}[0m {}:{}", s, tag, message, file, line);
+        }
+    })
+}
+
 /// Increases the current indentation level by adding two spaces. 
 /// This function accesses a thread-local string variable to modify its content by appending two spaces, effectively augmenting the indentation for subsequent operations that rely on this thread-local state.
 pub fn indent() {
@@ -53,10 +84,8 @@ pub fn dedent() {
 /// 
 macro_rules! info {
     ($($fmt:expr),+) => {
-        if $crate::log::LOGLEVEL.get() >= 3 {
-            $crate::log::INDENT.with_borrow(|s| {
-                eprintln!("{}\u{001b}[34;1m\u{001b}[1mINFO\u{001b}[0m \u{001b}[36m{:?}\u{001b}[0m {}:{}", s, format_args!($($fmt),+), file!(), line!())
-            })
+        if $crate::log::enabled(3, module_path!()) {
+            $crate::log::emit("\u{001b}[34;1m\u{001b}[1mINFO\u{001b}[0m", "INFO", format_args!($($fmt),+), file!(), line!())
         }
     };
 }
@@ -71,10 +100,8 @@ macro_rules! info {
 /// 
 macro_rules! debg {
     ($($fmt:expr),+) => {
-        if $crate::log::LOGLEVEL.get() >= 4 {
-            $crate::log::INDENT.with_borrow(|s| {
-                eprintln!("{}\u{001b}[32mDEBG\u{001b}[0m \u{001b}[36m{:?}\u{001b}[0m {}:{}", s, format_args!($($fmt),+), file!(), line!())
-            })
+        if $crate::log::enabled(4, module_path!()) {
+            $crate::log::emit("\u{001b}[32mDEBG\u{001b}[0m", "DEBG", format_args!($($fmt),+), file!(), line!())
         }
     };
 }
@@ -89,10 +116,8 @@ macro_rules! debg {
 /// The macro relies on a thread-local storage for consistent formatting in multi-threaded scenarios.
 macro_rules! debg2 {
     ($($fmt:expr),+) => {
-        if $crate::log::LOGLEVEL.get() >= 5 {
-            $crate::log::INDENT.with_borrow(|s| {
-                eprintln!("{}\u{001b}[32mDEBG\u{001b}[0m \u{001b}[36m{:?}\u{001b}[0m {}:{}", s, format_args!($($fmt),+), file!(), line!())
-            })
+        if $crate::log::enabled(5, module_path!()) {
+            $crate::log::emit("\u{001b}[32mDEBG\u{001b}[0m", "DEBG", format_args!($($fmt),+), file!(), line!())
         }
     };
 }
@@ -107,10 +132,8 @@ macro_rules! debg2 {
 /// 
 macro_rules! crit {
     ($($fmt:expr),+) => {
-        if $crate::log::LOGLEVEL.get() >= 1 {
-        $crate::log::INDENT.with_borrow(|s| {
-            eprintln!("{}\u{001b}[31;1m\u{001b}[1mCRIT\u{001b}[0m \u{001b}[36m{:?}\u{001b}[0m {}:{}", s, format_args!($($fmt),+), file!(), line!())
-        })
+        if $crate::log::enabled(1, module_path!()) {
+        $crate::log::emit("\u{001b}[31;1m\u{001b}[1mCRIT\u{001b}[0m", "CRIT", format_args!($($fmt),+), file!(), line!())
     }
     };
 }
@@ -124,10 +147,8 @@ macro_rules! crit {
 /// 
 macro_rules! warn {
     ($($fmt:expr),+) => {
-        if $crate::log::LOGLEVEL.get() >= 2 {
-            $crate::log::INDENT.with_borrow(|s| {
-                eprintln!("{}\u{001b}[33;1m\u{001b}[1mWARN\u{001b}[0m \u{001b}[36m{:?}\u{001b}[0m {}:{}", s, format_args!($($fmt),+), file!(), line!())
-            })
+        if $crate::log::enabled(2, module_path!()) {
+            $crate::log::emit("\u{001b}[33;1m\u{001b}[1mWARN\u{001b}[0m", "WARN", format_args!($($fmt),+), file!(), line!())
         }
     };
 }
@@ -143,10 +164,8 @@ macro_rules! warn {
 /// 
 macro_rules! infob {
     ($fmt:literal, $e:expr) => {
-        if $crate::log::LOGLEVEL.get() >= 3 {
-            $crate::log::INDENT.with_borrow(|s| {
-                eprintln!("{}\u{001b}[36m\u{001b}[1mINFO\u{001b}[0m \u{001b}[36m{:?}\u{001b}[0m {}:{}", s, format_args!($fmt), file!(), line!())
-            });
+        if $crate::log::enabled(3, module_path!()) {
+            $crate::log::emit("\u{001b}[36m\u{001b}[1mINFO\u{001b}[0m", "INFO", format_args!($fmt), file!(), line!());
             $crate::log::indent();
             let _result_ = $e;
             $crate::log::dedent();
@@ -168,10 +187,8 @@ macro_rules! infob {
 /// 
 macro_rules! debgb {
     ($fmt:literal, $e:expr) => {
-        if $crate::log::LOGLEVEL.get() >= 4 {
-            $crate::log::INDENT.with_borrow(|s| {
-                eprintln!("{}\u{001b}[32mDEBG\u{001b}[0m \u{001b}[36m{:?}\u{001b}[0m {}:{}", s, format_args!($fmt), file!(), line!())
-            });
+        if $crate::log::enabled(4, module_path!()) {
+            $crate::log::emit("\u{001b}[32mDEBG\u{001b}[0m", "DEBG", format_args!($fmt), file!(), line!());
             $crate::log::indent();
             let _result_ = $e;
             $crate::log::dedent();
@@ -192,10 +209,8 @@ macro_rules! debgb {
 /// 
 macro_rules! debgb2 {
     ($fmt:literal, $e:expr) => {
-        if $crate::log::LOGLEVEL.get() >= 5 {
-            $crate::log::INDENT.with_borrow(|s| {
-                eprintln!("{}\u{001b}[32mDEBG\u{001b}[0m \u{001b}[36m{:?}\u{001b}[0m {}:{}", s, format_args!($fmt), file!(), line!())
-            });
+        if $crate::log::enabled(5, module_path!()) {
+            $crate::log::emit("\u{001b}[32mDEBG\u{001b}[0m", "DEBG", format_args!($fmt), file!(), line!());
             $crate::log::indent();
             let _result_ = $e;
             $crate::log::dedent();
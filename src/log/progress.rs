@@ -0,0 +1,64 @@
+use std::{io::Write, sync::{atomic::{AtomicBool, Ordering}, OnceLock}, time::Instant};
+
+use serde::Serialize;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static START: OnceLock<Instant> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+/// Format of the progress reports written to stderr.
+pub enum Format {
+    /// One JSON object per line
+    Jsonl,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+/// A machine-readable progress event.
+pub enum Event<'a> {
+    /// An executor working on `examples` examples starts enumerating terms of `size` for the non-terminal `nt`; `terms` counts the terms it stored so far for `nt`.
+    Enumerate { examples: usize, size: usize, nt: &'a str, terms: usize, enumerated: usize },
+    /// A thread found a solution; `solutions` partial solutions are now kept, which together solve `solved` of the `examples` examples.
+    Solution { solutions: usize, solved: usize, examples: usize },
+    /// Periodic state of the solving loop.
+    Status { solutions: usize, solved: usize, examples: usize, ite_limit: usize, threads: usize },
+}
+
+/// Enables progress reports in the given format.
+pub fn enable(format: Format) {
+    match format {
+        Format::Jsonl => ENABLED.store(true, Ordering::Relaxed),
+    }
+    START.get_or_init(Instant::now);
+}
+
+/// Returns whether progress reports are enabled.
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Writes `event` to stderr as a JSON line, together with the milliseconds elapsed since progress reports were enabled.
+///
+/// Does nothing unless reports are enabled, so callers only need to check [`enabled`] when building the event is costly.
+pub fn emit(event: Event) {
+    if !enabled() { return; }
+    let elapsed = START.get_or_init(Instant::now).elapsed().as_millis() as u64;
+    let mut json = serde_json::to_value(&event).unwrap();
+    json["elapsed_ms"] = elapsed.into();
+    let _ = writeln!(std::io::stderr().lock(), "{}", json);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Event;
+
+    #[test]
+    fn test_event_format() {
+        let e = serde_json::to_value(Event::Enumerate { examples: 3, size: 2, nt: "S", terms: 10, enumerated: 42 }).unwrap();
+        assert_eq!(e["event"], "enumerate");
+        assert_eq!(e["nt"], "S");
+        let e = serde_json::to_value(Event::Status { solutions: 1, solved: 2, examples: 3, ite_limit: 1, threads: 4 }).unwrap();
+        assert_eq!(e["event"], "status");
+        assert_eq!(e["ite_limit"], 1);
+    }
+}
@@ -1,11 +1,18 @@
 #![allow(unused_imports)] 
 #![allow(unused_mut)] 
+#![allow(unused_variables)]
+// Operators implement the enumeration hooks they do not need with unused arguments, return `Result<_, ()>` to stop the enumeration, and are written by macros local to the crate
+#![allow(clippy::result_unit_err, clippy::crate_in_macro_def)]
+// Arena allocation (see `galloc`) hands out mutable references from shared ones, and parse errors are those of `pest`
+#![allow(clippy::mut_from_ref, clippy::result_large_err)]
+#![allow(clippy::len_without_is_empty, clippy::type_complexity, async_fn_in_trait)]
 #![feature(int_roundings)]
 #![feature(thread_local)]
 #![feature(map_try_insert)]
 #![feature(hash_raw_entry)]
 #![feature(cell_update)]
 #![feature(trait_alias)]
+#![cfg_attr(test, feature(test))]
 
 /// Global allocation 
 pub mod galloc;
@@ -43,9 +50,57 @@ pub mod solutions;
 
 /// Handle special text objects.
 pub mod text;
-use std::{borrow::BorrowMut, cell::Cell, cmp::min, fs, os, process::exit};
 
-use clap::Parser;
+/// SMT solver interface, used as a verification oracle in CEGIS mode.
+pub mod smt;
+
+/// Emitting solutions in other languages
+pub mod emit;
+
+/// Incremental solving server (`--serve`)
+pub mod serve;
+
+/// Learning operator costs from solved problems (`tune`)
+pub mod tuning;
+
+/// Saving and resuming interrupted synthesis (`--checkpoint`, `--resume`)
+pub mod checkpoint;
+
+/// Running a directory of benchmarks (`bench`)
+pub mod bench;
+
+/// Asking the user to label inputs on which candidate solutions disagree (`--disambiguate`)
+pub mod disambiguate;
+
+/// Callbacks on the progress of the solver, for programs embedding it
+pub mod observer;
+
+/// Default grammars enriching the grammar of problems (`--profile`)
+pub mod profiles;
+
+/// Describing a problem without solving it (`inspect`)
+pub mod inspect;
+
+/// Baseline synthesizers for comparison (`--baseline`)
+pub mod baseline;
+
+/// Recording which mechanism produced each subexpression of the solution (`--explain`)
+pub mod provenance;
+
+/// Filling the holes of a program skeleton given with the problem
+pub mod sketch;
+
+/// Comparing two programs on random inputs (`equiv`)
+pub mod equiv;
+
+/// Random inputs shaped like the examples, to exercise solutions (`--fuzz`)
+pub mod fuzz;
+
+/// Analyses of the examples before the search, such as the columns of tabular inputs
+pub mod analysis;
+use std::{borrow::BorrowMut, cell::Cell, cmp::{max, min}, fs, io::IsTerminal, os, process::exit, time::Duration};
+
+use clap::{Parser, Subcommand};
 use expr::{cfg::Cfg, context::Context, Expr};
 use forward::executor::{Executor, STOP_SIGNAL};
 use futures::{stream::FuturesUnordered, StreamExt};
@@ -57,9 +112,9 @@ use solutions::{new_thread, CONDITIONS};
 use tokio::task::JoinHandle;
 use value::ConstValue;
 
-use crate::{backward::Problem, expr::cfg::{NonTerminal, ProdRule}, parser::{check::DefineFun, problem::PBEProblem}, solutions::{cond_search_thread, Solutions}, value::Type};
+use crate::{backward::Problem, expr::cfg::{NonTerminal, ProdRule}, parser::{check::DefineFun, problem::{FunSig, PBEProblem}}, solutions::{cond_search_thread, Solutions}, value::Type};
 #[derive(Debug, Parser)]
-#[command(name = "synthphonia")]
+#[command(name = "synthphonia", args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 /// A command-line interface configuration providing options for controlling a string synthesis process. 
 /// 
 /// The struct fields represent various parameters that users can configure, such as logging verbosity, file paths for grammar configurations, and the thread count for execution. 
@@ -69,12 +124,19 @@ use crate::{backward::Problem, expr::cfg::{NonTerminal, ProdRule}, parser::{chec
 /// Additional debugging options are available, allowing for more verbose output, viewing examples, or simply printing the signature of a synthesis problem without solving it.
 /// 
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Log level
     #[arg(short, long, action = clap::ArgAction::Count)]
     verbose: u8,
     /// Path to the context-free grammar configuration (enriched sygus-if)
     #[arg(short, long)]
     cfg: Option<String>,
+
+    /// Default grammar merged into the grammar of the problem when `--cfg` is not given: `text`, `tabular`, `numeric`, or `custom:<path>` for an enriched sygus-if file. Chosen from the examples by default (`text` if they contain text objects, `tabular` otherwise).
+    #[arg(long, value_parser = profiles::ProfileSpec::parse)]
+    profile: Option<profiles::ProfileSpec>,
     
     /// Number of threads
     #[arg(short='j', long, default_value_t=4)]
@@ -87,11 +149,127 @@ struct Cli {
     /// Set the rate limit of ITE (in milliseconds), i.e., how much time (without new solutions) does it take for the `ite_limit` to increment by one.
     #[arg(long, default_value_t=4000)]
     ite_limit_rate: usize,
+
+    /// Weight of the costs of conditions and branches against entropy in tree learning (e.g. 0.05). Higher values give smaller trees with cheaper conditions.
+    #[arg(long)]
+    tree_cost_weight: Option<f32>,
     
     /// Disable deduction, i.e., Enumeration + ACS.
     #[arg(long, default_value_t=false)]
     no_deduction: bool,
     
+    /// Run a baseline synthesizer instead, for comparison: `enum` is a plain bottom-up enumeration with observational equivalence, without deduction, case-splitting or text objects, printing the first solution.
+    #[arg(long, value_enum)]
+    baseline: Option<baseline::Baseline>,
+
+    /// Disable splitting strings around a substring (witness of `str.++` around a delimiter).
+    #[arg(long)]
+    no_split: bool,
+
+    /// Disable deducing `str.join` over a list (witness of `str.join` around a delimiter).
+    #[arg(long)]
+    no_join: bool,
+
+    /// Disable the formatters deduced from the outputs (e.g. `int.fmt`, `date.fmt`).
+    #[arg(long)]
+    no_format_deduce: bool,
+
+    /// Disable the conditional concatenation of a common prefix (deduction rule `StrDeducer::ite_concat`).
+    #[arg(long)]
+    no_ite_concat: bool,
+
+    /// Disable text objects, i.e., the date/time/month scanners.
+    #[arg(long, alias = "no-textobj")]
+    no_text_objects: bool,
+
+    /// When the deduction tasks waiting for cost are resumed: `fixed` releases `increase_cost_limit` at regular intervals of the enumeration, `adaptive` adjusts it to the enumeration rate, the number of waiting tasks and the remaining time.
+    #[arg(long, value_enum)]
+    cost_policy: Option<forward::cost_policy::CostPolicy>,
+
+    /// Discard enumerated strings that occur in neither the output nor the inputs of any example. Faster, but may miss solutions building the output from such strings (e.g. with `str.replace`).
+    #[arg(long)]
+    prune_dominated: bool,
+
+    /// Global timeout (in seconds). When it expires, the best partial solution found so far is printed with a coverage report.
+    #[arg(long)]
+    timeout: Option<u64>,
+
+    /// Maximum size (number of nodes) of the solution, overriding `(:size_limit N)` of the grammar. Larger enumerated terms, deduced expressions and decision trees are discarded.
+    #[arg(long)]
+    max_size: Option<usize>,
+
+    /// Withhold this number of random examples (chosen with `--seed`) from the search, and only accept solutions that are also correct on them, to avoid overfitting. Ignored with `--no-ite`.
+    #[arg(long)]
+    holdout: Option<usize>,
+
+    /// Keep solving after the first solution until this number of structurally distinct solutions are found (or the timeout expires), and print them all, smallest first. Ignored with `--no-ite`.
+    #[arg(long, default_value_t=1)]
+    top_k: usize,
+
+    /// Interactive Mode: when several distinct solutions are found (see `--top-k`, at least 2 in this mode), print an input on which they disagree and read its expected output (an SMT-LIB literal) from stdin, then solve again with it. An empty line stops asking. Ignored with `--no-ite`.
+    #[arg(long)]
+    disambiguate: bool,
+
+    /// Best-Effort Mode, for noisy examples: if no solution is found within the timeout, print the decision tree combining the solutions found so far that satisfies the most examples, followed by the unsatisfied examples.
+    #[arg(long)]
+    best_effort: bool,
+
+    /// Memory budget (in MB) of the term arenas. Close to it, the term bank stops growing, then enumeration stops and the best (partial) solution found so far is printed, instead of running out of memory.
+    #[arg(long)]
+    mem_limit: Option<usize>,
+
+    /// CEGIS Mode: verify each solution against the `constraint`s of the problem with an SMT solver, and resume synthesis with the counterexamples.
+    #[arg(long)]
+    cegis: bool,
+
+    /// SMT solver used by CEGIS mode (`z3`, `cvc5`, or another command reading SMT-LIB from stdin).
+    #[arg(long, default_value="z3")]
+    smt_solver: String,
+
+    /// Server Mode: read newline-delimited JSON requests (`add_example`, `solve`, `reset`) from stdin and answer each with a JSON line, reusing solutions and the terms up to size `--shared-bank` (4 by default) across requests.
+    #[arg(long)]
+    serve: bool,
+
+    /// Report progress on stderr in a machine-readable format (`jsonl`: one JSON event per line).
+    #[arg(long, value_enum)]
+    progress: Option<log::progress::Format>,
+
+    /// Write the logs to this file instead of stderr. Log levels may be set per module with `SYNTHPHONIA_LOG`, e.g. `SYNTHPHONIA_LOG=backward=debug,forward=info`.
+    #[arg(long)]
+    log_file: Option<String>,
+
+    /// Encoding of the input files. By default, it is detected from the byte order mark, then UTF-8 is assumed if the file is valid UTF-8, otherwise Windows-1252 (as exported by Excel).
+    #[arg(long, value_enum, default_value_t)]
+    encoding: parser::input::Encoding,
+
+    /// Two-digit years below this pivot are read by `date.parse` in the 2000s, the others in the 1900s, unless the rule has its own `#pivot`. Dates with two-digit years are also scanned in both centuries, for the one consistent with the outputs.
+    #[arg(long, default_value_t = text::parsing::DEFAULT_PIVOT)]
+    year_pivot: i64,
+
+    /// Index strings by characters instead of bytes in `str.len`, `str.substr`, `str.at` and `str.indexof`, for examples with accented letters or emoji.
+    #[arg(long)]
+    unicode: bool,
+
+    /// Print per-operator statistics (evaluations, successful evaluations, enumerated expressions) on stderr at exit, to spot wasteful grammar rules.
+    #[arg(long)]
+    stats: bool,
+
+    /// Print the bytes allocated in the term arenas per category (expressions, values, strings) on stderr at exit, with the bytes reclaimed from temporary regions.
+    #[arg(long)]
+    galloc_stats: bool,
+
+    /// Number of threads sharing the evaluation of each large production rule, within every solving thread. Helps large grammars when few example sets are solved at once.
+    #[arg(long, default_value_t=1)]
+    enum_threads: usize,
+
+    /// Seed of the random choice of example sets for the solving threads.
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Deterministic Mode: solve example sets one after another on a single thread, with term budgets instead of time limits, so that runs with the same `--seed` (0 by default) give the same result.
+    #[arg(long)]
+    deterministic: bool,
+
     /// Enable all-example thread (Using one thread for all-example thread)
     #[arg(long)]
     with_all_example_thread: bool,
@@ -99,21 +277,170 @@ struct Cli {
     /// Enable constant extraction.
     #[arg(long)]
     extract_constants: bool,
+
+    /// Maximum number of string constants added by `--extract-constants`, keeping the best ranked ones.
+    #[arg(long, default_value_t=20)]
+    max_constants: usize,
+
+    /// Path to a cost table (JSON, as written by `synthphonia tune`) overriding the costs of operators.
+    #[arg(long)]
+    costs: Option<String>,
     
-    /// Path to the input file: enriched sygus-if (.sl) for synthesis or smt2 (.smt2) to check the result.
-    path: String,
+    /// Enumerate the terms up to this size once on all the examples, and share them with the threads of every example set, which project them down instead of enumerating them again.
+    #[arg(long)]
+    shared_bank: Option<usize>,
+
+    /// Periodically save the term bank and the solutions found so far to this file, to be resumed with `--resume`.
+    #[arg(long)]
+    checkpoint: Option<String>,
+
+    /// Resume the synthesis from a file written by `--checkpoint`, with the same problem and grammar.
+    #[arg(long)]
+    resume: Option<String>,
+    
+    /// Path to the input file: enriched sygus-if (.sl) or JSON (.json) for synthesis, or smt2 (.smt2) to check the result.
+    #[arg(required = true)]
+    path: Option<String>,
     
     /// Debug Mode (More assertions)
     #[arg(short, long)]
     debug: bool,
         
-    /// Show examples (debugging)
+    /// Simplify the solution with algebraic rewrite rules (e.g. removing concatenations of empty strings, folding constants) before printing it.
+    #[arg(long)]
+    minimize: bool,
+
+    /// Bind the subexpressions occurring several times in the solution with `let` (`LET` in Excel), so that each is printed once.
+    #[arg(long)]
+    share: bool,
+
+    /// Print the solution as a tree annotated with the mechanism which produced each subexpression (enumeration at some size, a deduction rule, a formatter or tree learning), as `;` comments after it.
+    #[arg(long)]
+    explain: bool,
+
+    /// Reject solutions in which an operation fails on some example (e.g. a parser on a text it cannot read), even in a branch of an `ite` not taken on it, instead of only warning about them.
+    #[arg(long)]
+    strict: bool,
+
+    /// After solving, evaluate the solution on this many random inputs shaped like the examples (lengths, character classes, dates) and print its outputs as `;` comments, flagging inputs on which it is undefined or panics.
+    #[arg(long)]
+    fuzz: Option<usize>,
+
+    /// Output language of the solution.
+    #[arg(long, value_enum, default_value_t=emit::Target::Sygus)]
+    emit: emit::Target,
+
+    /// Print the solution, and the solutions in logs, for humans: `infix` (the default) in the manner of Python, or `sexpr`, with nested `ite` chains broken over several lines.
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "infix")]
+    pretty: Option<expr::pretty::Notation>,
+
+    /// With `--pretty`, elide the subexpressions nested deeper than this as `…`.
+    #[arg(long)]
+    pretty_depth: Option<usize>,
+
+    /// Show examples (debugging): as a table, or with `align`, each output aligned with the substrings of the inputs it copies.
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "table")]
+    showex: Option<analysis::ShowEx>,
+
+    /// Do not split delimited (CSV, TSV, `;`, `|`) or fixed-width string inputs into columns added as variables.
+    #[arg(long)]
+    no_columns: bool,
+
+    /// Enumerate first the arguments sharing the longest substrings with the outputs, for functions of many arguments.
     #[arg(long)]
-    showex: bool,
+    arg_priority: bool,
+
+    /// Print the solution over the detected columns (`line_0`, `line_1`, ...) as extra arguments, instead of rewriting them as accessors of the raw strings.
+    #[arg(long)]
+    show_columns: bool,
 
     /// Show Signature (Just Print the signature without solving)
     #[arg(long)]
-    sig: bool
+    sig: bool,
+
+    /// Print the source line under parse errors, with the error underlined.
+    #[arg(long)]
+    snippet: bool,
+}
+
+#[derive(Debug, Subcommand)]
+/// Subcommands other than solving.
+enum Command {
+    /// Learn a cost table from a directory of solved problems (`name.sl` with its solution `name.sol`)
+    Tune {
+        /// Directory of solved problems
+        dir: String,
+        /// Path of the cost table to write
+        #[arg(short, long, default_value="costs.json")]
+        output: String,
+    },
+    /// Solve every `.sl` file of a directory, checking solutions against the paired `.smt2` files, and print a summary
+    Bench {
+        /// Directory of problems
+        dir: String,
+        /// Timeout of each problem (in seconds)
+        #[arg(short, long, default_value_t=60)]
+        timeout: u64,
+        /// Number of problems solved at the same time
+        #[arg(short, long, default_value_t=1)]
+        jobs: usize,
+        /// Format of the summary
+        #[arg(short, long, value_enum, default_value_t=bench::Format::Csv)]
+        format: bench::Format,
+        /// Path of the summary (stdout if not given)
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Arguments passed to each run (after `--`)
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// Print what the solver makes of a problem without solving it: its signature, the grammar with the rules of each non-terminal, the number of examples, the text objects found by the scanners, and the extracted constants
+    Inspect {
+        /// Path of the problem (.sl or .json)
+        path: String,
+        /// Path to the context-free grammar configuration (enriched sygus-if)
+        #[arg(short, long)]
+        cfg: Option<String>,
+        /// Default grammar merged into the grammar of the problem (see the `--profile` option of solving)
+        #[arg(long, value_parser = profiles::ProfileSpec::parse)]
+        profile: Option<profiles::ProfileSpec>,
+        /// Disable text objects, i.e., the date/time/month scanners
+        #[arg(long)]
+        no_text_objects: bool,
+        /// Maximum number of extracted constants
+        #[arg(long, default_value_t=20)]
+        max_constants: usize,
+        /// Format of the report
+        #[arg(short, long, value_enum, default_value_t=inspect::Format::Text)]
+        format: inspect::Format,
+        /// Print the source line under parse errors
+        #[arg(long)]
+        snippet: bool,
+    },
+    /// Compare two programs (`.smt2` files with a `define-fun`) on the examples and on random inputs around them, reporting the first input on which they disagree
+    Equiv {
+        /// First program
+        a: String,
+        /// Second program
+        b: String,
+        /// Problem whose examples are tested too (.sl or .json)
+        #[arg(long)]
+        examples: Option<String>,
+        /// Number of random inputs
+        #[arg(short = 'n', long, default_value_t=1000)]
+        samples: usize,
+        /// Seed of the random inputs
+        #[arg(long, default_value_t=0)]
+        seed: u64,
+    },
+    /// Check the grammar of a SyGuS-IF problem and report unknown operators, type mismatches, and unreachable or non-productive rules
+    Validate {
+        /// Path of the problem
+        path: String,
+        /// Print the source line under each diagnostic
+        #[arg(long)]
+        snippet: bool,
+    },
 }
 
 #[thread_local]
@@ -140,15 +467,89 @@ pub static COUNTER: spin::Mutex<[usize; 6]> = spin::Mutex::new([0usize; 6]);
 async fn main() -> Result<(), Box<dyn std::error::Error>>{
     let args = Cli::parse();
     log::set_log_level(args.verbose + 2);
+    if let Err(e) = log::filter::init(args.log_file.as_deref()) {
+        eprintln!("{e}");
+        exit(1);
+    }
+    parser::input::set_encoding(args.encoding);
+    text::parsing::set_default_pivot(args.year_pivot);
+    if args.unicode {
+        expr::ops::str::StrIndexing::Chars.set();
+    }
+    if let Some(format) = args.progress {
+        log::progress::enable(format);
+    }
     DEBUG.set(args.debug);
+    if args.stats {
+        forward::enumeration::stats::enable();
+    }
+    if args.galloc_stats {
+        galloc::enable_stats();
+    }
+    forward::bridge::init_pool(args.enum_threads);
+    if let Some(mb) = args.mem_limit {
+        galloc::set_mem_limit(mb.saturating_mul(1 << 20));
+    }
+    if let Some(Command::Tune { dir, output }) = args.command {
+        let table = tuning::tune(&dir)?;
+        table.save(&output)?;
+        println!("; Wrote costs of {} operators to {}", table.0.len(), output);
+        return Ok(());
+    }
+    if let Some(Command::Bench { dir, timeout, jobs, format, output, args: run_args }) = args.command {
+        let results = bench::run(&dir, timeout, jobs, &run_args)?;
+        let summary = bench::format(&results, format)?;
+        match output {
+            Some(path) => fs::write(path, summary)?,
+            None => print!("{}", summary),
+        }
+        eprintln!("Solved {}/{}", results.iter().filter(|r| r.solved).count(), results.len());
+        return Ok(());
+    }
+    if let Some(Command::Validate { path, snippet }) = args.command {
+        let s = parser::input::read_to_string(path.as_str())?;
+        let diagnostics = parser::validate::validate(s.as_str()).unwrap_or_else(|e| parse_failed(path.as_str(), &e, snippet));
+        for d in diagnostics.iter() {
+            println!("{}", d.format(path.as_str(), snippet));
+        }
+        let errors = diagnostics.iter().filter(|d| d.severity == parser::validate::Severity::Error).count();
+        eprintln!("{} error(s), {} warning(s)", errors, diagnostics.len() - errors);
+        exit(if errors > 0 { 1 } else { 0 });
+    }
+    if let Some(Command::Equiv { a, b, examples, samples, seed }) = args.command {
+        let read = |path: &str| -> Result<CheckProblem, Box<dyn std::error::Error>> {
+            let s = parser::input::read_to_string(path)?;
+            Ok(CheckProblem::parse(s.as_str()).unwrap_or_else(|e| parse_failed(path, &e, args.snippet)))
+        };
+        let (a, b) = (read(&a)?, read(&b)?);
+        let problem = examples.map(|path| -> Result<PBEProblem, Box<dyn std::error::Error>> {
+            let s = parser::input::read_to_string(path.as_str())?;
+            Ok(parse_pbe(path.as_str(), s.as_str(), args.snippet))
+        }).transpose()?;
+        let sets = [Some(&a.examples), Some(&b.examples), problem.as_ref().map(|p| &p.examples)].into_iter().flatten().collect_vec();
+        let report = equiv::check(&a.definefun, &b.definefun, &sets, samples, seed)?;
+        println!("{}", report);
+        exit(if report.disagreement.is_some() { 1 } else { 0 });
+    }
+    if let Some(costs) = args.costs.as_ref() {
+        *tuning::COST_TABLE.lock() = Some(tuning::CostTable::load(costs)?);
+    }
+    if let Some(Command::Inspect { path, cfg, profile, no_text_objects, max_constants, format, snippet }) = args.command {
+        let s = parser::input::read_to_string(path.as_str())?;
+        let problem = parse_pbe(path.as_str(), s.as_str(), snippet);
+        let cfg = build_cfg(&problem, cfg.as_deref(), profile.as_ref(), !no_text_objects, snippet);
+        print!("{}", inspect::Report::new(&problem, &cfg, max_constants).format(format)?);
+        return Ok(());
+    }
+    let path = args.path.clone().unwrap();
     if args.sig {
-        let s = fs::read_to_string(args.path).unwrap();
-        let problem = PBEProblem::parse(s.as_str()).unwrap();
+        let s = parser::input::read_to_string(path.as_str()).unwrap();
+        let problem = parse_pbe(path.as_str(), s.as_str(), args.snippet);
         
         println!("{}", problem.synthfun().sig)
-    } else if args.path.ends_with(".smt2") {
-        let s = fs::read_to_string(args.path).unwrap();
-        let problem = CheckProblem::parse(s.as_str()).unwrap();
+    } else if path.ends_with(".smt2") {
+        let s = parser::input::read_to_string(path.as_str()).unwrap();
+        let problem = CheckProblem::parse(s.as_str()).unwrap_or_else(|e| parse_failed(path.as_str(), &e, args.snippet));
         let ctx = Context::from_examples(&problem.examples);
         info!("Expression: {:?}", problem.definefun.expr);
         info!("Examples: {:?}", problem.examples);
@@ -156,25 +557,30 @@ async fn main() -> Result<(), Box<dyn std::error::Error>>{
         info!("Result: {:?}", result);
         println!("{}", result.eq_count(&problem.examples.output));
     } else {
-        let s = fs::read_to_string(args.path).unwrap();
-        let problem = PBEProblem::parse(s.as_str()).unwrap();
-        let mut cfg = Cfg::from_synthfun(problem.synthfun());
-        if let Some(s) = args.cfg {
-            let sygus_if = fs::read_to_string(s).unwrap();
-            cfg = enrich_configuration(sygus_if.as_str(), cfg);
+        let s = parser::input::read_to_string(path.as_str()).unwrap();
+        if !path.ends_with(".json") { check_grammar(path.as_str(), s.as_str(), args.snippet); }
+        let mut problem = parse_pbe(path.as_str(), s.as_str(), args.snippet);
+        let oracle = if args.cegis {
+            Some(smt::Oracle::new(smt::SmtSolver::new(&args.smt_solver), &problem)?)
         } else {
-            let ctx = Context::from_examples(&problem.examples);
-            if text::parsing::detector(&ctx) {
-                let sygus_if = include_str!("../test/test.sl");
-                cfg = enrich_configuration(sygus_if, cfg);
-            } else {
-                let sygus_if = include_str!("../test/test2map.sl");
-                cfg = enrich_configuration(sygus_if, cfg);
+            if !problem.spec.is_empty() {
+                warn!("{} constraint(s) are not enforced beyond the examples, use --cegis to verify them", problem.spec.len());
             }
+            None
+        };
+        if let Some(oracle) = oracle.as_ref().filter(|_| problem.examples.len() == 0) {
+            let (inputs, output) = oracle.example(None)?;
+            problem.examples.add_example(&inputs, output);
         }
+        let sig = problem.synthfun().sig.clone();
+        let pretty = args.pretty.map(|n| expr::pretty::Style::new(n, args.pretty_depth));
+        if let Some(style) = pretty {
+            expr::pretty::enable(&sig, style);
+        }
+        let mut cfg = build_cfg(&problem, args.cfg.as_deref(), args.profile.as_ref(), !args.no_text_objects && args.baseline.is_none(), args.snippet);
 
         if args.extract_constants {
-            let constants = problem.examples.extract_constants();
+            let constants = problem.examples.extract_constants(args.max_constants);
             for nt in cfg.iter_mut() {
                 if nt.ty == Type::Str {
                     for c in constants.iter() {
@@ -185,48 +591,231 @@ async fn main() -> Result<(), Box<dyn std::error::Error>>{
         }
 
         info!("CFG: {:?}", cfg);
-        let ctx = Context::from_examples(&problem.examples);
+        let mut ctx = Context::from_examples(&problem.examples);
         debg!("Examples: {:?}", ctx.output);
-        if args.showex {
-            for i in ctx.inputs() {
-                println!("{:?}", i);
+        if let Some(view) = args.showex {
+            let header = sig.args.iter().map(|(name, _)| name.clone()).chain(std::iter::once(sig.name.clone())).collect_vec();
+            let aligned = (view == analysis::ShowEx::Align).then(|| analysis::format_alignment(&ctx, &header, std::io::stdout().is_terminal())).flatten();
+            if view == analysis::ShowEx::Align && aligned.is_none() {
+                warn!("Cannot align the examples: the outputs are not strings.");
             }
-            println!("{:?}", ctx.output);
+            print!("{}", aligned.unwrap_or_else(|| ctx.format_table(&header)));
             return Ok(());
         }
         cfg.config.no_deduction = args.no_deduction;
+        cfg.config.no_split = args.no_split;
+        cfg.config.no_join = args.no_join;
+        cfg.config.no_format_deduce = args.no_format_deduce;
+        cfg.config.no_ite_concat = args.no_ite_concat;
         cfg.config.ite_limit_rate = args.ite_limit_rate;
+        if let Some(w) = args.tree_cost_weight {
+            cfg.config.tree_cost_weight = w;
+        }
+        cfg.config.no_text_objects = args.no_text_objects;
+        cfg.config.dominance_pruning = args.prune_dominated;
+        if let Some(policy) = args.cost_policy {
+            cfg.config.cost_policy = policy;
+        }
+        if let Some(n) = args.max_size {
+            cfg.config.max_size = n;
+            cfg.config.size_limit = n;
+        }
+        if args.explain {
+            provenance::enable();
+        }
+        if args.emit == emit::Target::Dot {
+            tree_learning::record_trees();
+        }
+        if args.deterministic {
+            forward::executor::DETERMINISTIC.store(true, std::sync::atomic::Ordering::Relaxed);
+        }
+        if let Some(sketch) = problem.sketch.as_ref() {
+            if let Some(t) = args.timeout {
+                cfg.config.time_limit = t as usize * 1000;
+            }
+            match sketch::solve(sketch, cfg, ctx.clone()) {
+                Some(result) => {
+                    audit(args.strict, result, &ctx);
+                    print_solution(args.emit, pretty, &sig, result)?;
+                    fuzz(args.fuzz, args.seed, result, &ctx);
+                }
+                None => println!("; No solution found within the timeout"),
+            }
+            exit(0);
+        }
+        if let Some(baseline) = args.baseline {
+            if let Some(t) = args.timeout {
+                cfg.config.time_limit = t as usize * 1000;
+            }
+            match baseline::solve(baseline, cfg, ctx.clone()) {
+                Some(result) => {
+                    audit(args.strict, result, &ctx);
+                    print_solution(args.emit, pretty, &sig, result)?;
+                    fuzz(args.fuzz, args.seed, result, &ctx);
+                }
+                None => println!("; No solution found within the timeout"),
+            }
+            exit(0);
+        }
+        if args.serve {
+            let mut server = serve::Server::new(sig, cfg, problem.examples, args.thread, args.emit, args.timeout, args.shared_bank.unwrap_or(serve::BANK_SIZE));
+            server.run(std::io::stdin().lock(), std::io::stdout()).await?;
+            exit(0);
+        }
+        // Counterexamples and answers rebuild the context from the examples, without the columns.
+        let columns = if args.no_columns || oracle.is_some() || args.disambiguate {
+            analysis::Columns::default()
+        } else {
+            analysis::Columns::detect(&ctx)
+        };
+        if !columns.is_empty() {
+            info!("Columns: {:?}", columns);
+            columns.extend(&mut ctx, &mut cfg);
+        }
+        if args.arg_priority {
+            analysis::prioritize_args(&mut cfg, &ctx);
+            info!("Argument priority: {:?}", analysis::arg_priority(&ctx));
+        }
+        let out_sig = if args.show_columns { columns.signature(&sig) } else { sig.clone() };
+        let view = |e: &'static Expr| if args.show_columns { e } else { columns.expand(e) };
+        if let Some(path) = args.checkpoint.as_ref() {
+            checkpoint::enable(path, &sig, &cfg, ctx.len);
+        }
+        let resumed = match args.resume.as_ref() {
+            Some(path) => checkpoint::resume(path, &sig, &cfg, ctx.len)?,
+            None => Vec::new(),
+        };
         if args.no_ite {
             if args.no_ite {
                 cfg.config.cond_search = true;
             }
-            let exec = Executor::new(ctx, cfg);
-            info!("Deduction Configuration: {:?}", exec.deducers);
-            let result = exec.solve_top_blocked();
-            let func = DefineFun { sig: problem.synthfun().sig.clone(), expr: result};
-            println!("{}", func);
+            if let Some(t) = args.timeout {
+                cfg.config.time_limit = t as usize * 1000;
+            }
+            let result = loop {
+                let exec = Executor::new(ctx.clone(), cfg.clone());
+                info!("Deduction Configuration: {:?}", exec.deducers);
+                let result = exec.solve_top_with_limit();
+                let (Some(e), Some(oracle)) = (result, oracle.as_ref()) else { break result };
+                if oracle.refine(e, &mut problem.examples)? { break result }
+                info!("Counterexample found for {:?}, now with {} examples", e, problem.examples.len());
+                ctx = Context::from_examples(&problem.examples);
+                checkpoint::set_examples(ctx.len);
+            };
+            checkpoint::save();
+            if let Some(result) = result {
+                audit(args.strict, result, &ctx);
+                print_solution(args.emit, pretty, &out_sig, view(share(args.share, minimize(args.minimize, result, &ctx))))?;
+                explain(args.explain, result);
+                fuzz(args.fuzz, args.seed, columns.expand(result), &ctx);
+            } else {
+                warn!("Timeout: no solution found.");
+                println!("; No solution found within the timeout");
+            }
+            forward::enumeration::stats::report();
+            galloc::report_stats();
         } else {
-            let mut solutions = Solutions::new(cfg.clone(), ctx.clone());
+            let held = match args.holdout {
+                Some(k) if k > 0 && k < ctx.len => Some(ctx.holdout_examples(k, args.seed.unwrap_or(0))),
+                Some(k) => { warn!("Ignoring --holdout {}: there are {} examples.", k, ctx.len); None }
+                None => None,
+            };
+            let mut solutions = match held.as_ref() {
+                Some(held) => {
+                    let holdout = ctx.with_examples(held);
+                    info!("Holding out {} of {} examples", holdout.len, ctx.len);
+                    let mut solutions = Solutions::new(cfg.clone(), ctx.without_examples(held));
+                    solutions.set_holdout(holdout);
+                    solutions
+                }
+                None => Solutions::new(cfg.clone(), ctx.clone()),
+            };
+            solutions.set_top_k(if args.disambiguate { max(args.top_k, 2) } else { args.top_k });
+            solutions.set_best_effort(args.best_effort);
+            let mut resumed = solutions.restore(resumed);
+            if let Some(seed) = args.seed.or(args.deterministic.then_some(0)) {
+                solutions.set_seed(seed);
+            }
 
+            if let Some(k) = args.shared_bank {
+                solutions.share_bank(k);
+            }
             // solutions.create_cond_search_thread();
-            let mut nthread = min(args.thread, ctx.len);
-            if nthread > 1  && args.with_all_example_thread {
-                solutions.create_all_search_thread();
-                nthread -= 1;
+            let nthread = min(args.thread, ctx.len);
+            solutions.set_requested_threads(nthread);
+            if let Some(t) = args.timeout {
+                solutions.set_timeout(Duration::from_secs(t));
             }
-            for _ in 0..nthread {
-                solutions.create_new_thread();
+            if !args.deterministic {
+                solutions.create_threads(nthread, args.with_all_example_thread);
             }
 
-            let result = solutions.solve_loop().await;
-            let func = DefineFun { sig: problem.synthfun().sig.clone(), expr: result};
+            let result = loop {
+                let result = match resumed.take() {
+                    Some(e) => Some(e),
+                    None if args.deterministic => solutions.solve_deterministic(),
+                    None => solutions.solve_loop().await,
+                };
+                let Some(e) = result else { break result };
+                if oracle.as_ref().map_or(Ok(true), |oracle| oracle.refine(e, &mut problem.examples))? {
+                    if !args.disambiguate { break result }
+                    let Some(query) = disambiguate::distinguishing_input(&solutions.top_solutions(), &ctx) else { break result };
+                    if !disambiguate::ask(&query, &sig, &mut problem.examples, std::io::stdin().lock())? { break result }
+                    info!("Example added on {:?}, now with {} examples", query.inputs, problem.examples.len());
+                } else {
+                    info!("Counterexample found for {:?}, now with {} examples", e, problem.examples.len());
+                }
+                ctx = Context::from_examples(&problem.examples);
+                checkpoint::set_examples(ctx.len);
+                // Examples are only ever appended, so the held-out ones keep their indices and stay out of the search
+                solutions.extend_examples(match held.as_ref() {
+                    Some(held) => ctx.without_examples(held),
+                    None => ctx.clone(),
+                });
+                let nthread = min(args.thread, ctx.len);
+                solutions.set_requested_threads(nthread);
+                if !args.deterministic {
+                    solutions.create_threads(nthread, args.with_all_example_thread);
+                }
+            };
             // let nsols = solutions.count();
             // let ncons = CONDITIONS.lock().as_ref().unwrap().len();
             // eprintln!("nsols: {nsols}, ncons: {ncons}");
             STOP_SIGNAL.store(true, std::sync::atomic::Ordering::Relaxed);
+            checkpoint::save();
             
-            println!("{}", func);
+            let top = solutions.top_solutions();
+            if result.is_some() && args.top_k > 1 && !top.is_empty() {
+                // Under `--strict`, no solution is printed if one of them is rejected
+                for &e in top.iter() {
+                    audit(args.strict, e, &ctx);
+                }
+                for e in top {
+                    print_solution(args.emit, pretty, &out_sig, view(share(args.share, minimize(args.minimize, e, &ctx))))?;
+                    explain(args.explain, e);
+                    fuzz(args.fuzz, args.seed, columns.expand(e), &ctx);
+                }
+            } else if let Some(result) = result {
+                audit(args.strict, result, &ctx);
+                print_solution(args.emit, pretty, &out_sig, view(share(args.share, minimize(args.minimize, result, &ctx))))?;
+                explain(args.explain, result);
+                fuzz(args.fuzz, args.seed, columns.expand(result), &ctx);
+            } else if let Some((result, bits)) = solutions.partial_result() {
+                audit(args.strict, result, &ctx);
+                print_solution(args.emit, pretty, &out_sig, view(share(args.share, minimize(args.minimize, result, &ctx))))?;
+                explain(args.explain, result);
+                fuzz(args.fuzz, args.seed, columns.expand(result), &ctx);
+                println!("{}", solutions.coverage_report(&bits));
+                let unsatisfied = solutions.unsatisfied_report(result, &bits);
+                if args.best_effort && !unsatisfied.is_empty() {
+                    println!("{}", unsatisfied);
+                }
+            } else {
+                println!("; No solution found within the timeout");
+            }
 
+            forward::enumeration::stats::report();
+            galloc::report_stats();
             if !solutions.threads.is_empty() {
                 std::thread::sleep(std::time::Duration::from_millis(50));
             }
@@ -236,9 +825,131 @@ async fn main() -> Result<(), Box<dyn std::error::Error>>{
     Ok(())
 }
 
-/// Enhances the given configuration by integrating it with a parsed problem derived from the provided SyGuS-IF string. 
-fn enrich_configuration(sygus_if: &str, mut cfg: Cfg) -> Cfg {
-    let problem = PBEProblem::parse(sygus_if).unwrap();
+/// Simplifies the solution if `--minimize` is given, keeping its outputs on the examples of `ctx`.
+fn minimize(enabled: bool, e: &'static Expr, ctx: &Context) -> &'static Expr {
+    if !enabled { return e; }
+    let result = expr::simplify::simplify(e, ctx);
+    info!("Minimized from size {} to {}", e.size(), result.size());
+    result
+}
+
+/// Binds the repeated subexpressions of the solution if `--share` is given.
+fn share(enabled: bool, e: &'static Expr) -> &'static Expr {
+    if enabled { expr::share::share(e) } else { e }
+}
+
+/// Prints the provenance of the solution if `--explain` is given, before simplification, as comments.
+fn explain(enabled: bool, e: &'static Expr) {
+    if !enabled { return; }
+    for line in provenance::annotate(e).lines() {
+        println!("; {line}");
+    }
+}
+
+/// Warns about the operations of the solution failing on some example, and exits without it under `--strict`.
+fn audit(strict: bool, e: &'static Expr, ctx: &Context) {
+    let failures = expr::audit::audit(e, ctx);
+    for f in failures.iter() {
+        warn!("{}", f);
+    }
+    if strict && !failures.is_empty() {
+        println!("; Solution {:?} rejected by --strict: {} failing subexpression(s) on the examples", e, failures.len());
+        exit(1);
+    }
+}
+
+/// Prints the outputs of the solution on `n` random inputs shaped like the examples of `ctx` if `--fuzz n` is given, as comments.
+fn fuzz(n: Option<usize>, seed: Option<u64>, e: &'static Expr, ctx: &Context) {
+    let Some(n) = n else { return };
+    let Some(inputs) = fuzz::InputGenerator::new(ctx, seed.unwrap_or(0)).context(n) else {
+        warn!("Cannot fuzz the solution: no example to draw inputs from.");
+        return;
+    };
+    println!("{}", fuzz::report(&fuzz::run(e, &inputs)));
+}
+
+/// Prints a solution of the synthesis problem in the requested output language, or laid out for humans with `--pretty` instead of a `define-fun`.
+fn print_solution(target: emit::Target, pretty: Option<expr::pretty::Style>, sig: &FunSig, expr: &'static Expr) -> Result<(), emit::EmitError> {
+    match pretty {
+        Some(style) if target == emit::Target::Sygus => {
+            let args = sig.args.iter().map(|(name, _)| name.as_str()).join(", ");
+            println!("{}({}) =\n    {}", sig.name, args, expr.pretty(sig, style).lines().join("\n    "));
+        }
+        _ => println!("{}", emit::render(target, expr, sig)?),
+    }
+    Ok(())
+}
+
+/// Parses a PBE problem, choosing the JSON front-end for `.json` files and SyGuS-IF otherwise, and exits with the location of the error if it is malformed.
+fn parse_pbe(path: &str, s: &str, snippet: bool) -> PBEProblem {
+    if path.ends_with(".json") {
+        PBEProblem::parse_json(s).unwrap_or_else(|e| {
+            eprintln!("{}", parser::diagnostics::format_json_error(&e, path));
+            exit(1)
+        })
+    } else {
+        let mut problem = PBEProblem::parse(s).unwrap_or_else(|e| parse_failed(path, &e, snippet));
+        include(&mut problem, path);
+        problem
+    }
+}
+
+/// Merges the grammars of the files included by `problem`, read from `path`, and exits if one of them cannot be read or merged.
+fn include(problem: &mut PBEProblem, path: &str) {
+    if let Err(e) = parser::include::resolve(problem, std::path::Path::new(path)) {
+        eprintln!("{e}");
+        exit(1)
+    }
+}
+
+/// Prints a parse error of the file `path` as `path:line:col: message` and exits.
+fn parse_failed(path: &str, e: &parser::problem::Error, snippet: bool) -> ! {
+    eprintln!("{}", parser::diagnostics::format_error(e, path, snippet));
+    exit(1)
+}
+
+/// Validates the grammar of a SyGuS-IF problem before solving it, logging warnings and exiting on errors, which would otherwise panic while building the grammar.
+fn check_grammar(path: &str, s: &str, snippet: bool) {
+    let diagnostics = parser::validate::validate(s).unwrap_or_else(|e| parse_failed(path, &e, snippet));
+    for d in diagnostics.iter().filter(|d| d.severity == parser::validate::Severity::Warning) {
+        warn!("{}", d.format(path, false));
+    }
+    let errors = diagnostics.iter().filter(|d| d.severity == parser::validate::Severity::Error).collect_vec();
+    if errors.is_empty() { return; }
+    for d in errors.iter() {
+        eprintln!("{}", d.format(path, snippet));
+    }
+    eprintln!("{} error(s) in the grammar", errors.len());
+    exit(1);
+}
+
+/// Builds the grammar of the problem, enriched with the configuration at `cfg_path` (or a `custom:<path>` profile) if given, or else with a built-in profile.
+/// Without `--profile`, the profile is selected from the examples by `text::parsing::default_profile`, or is `tabular` if `text_objects` is not set.
+fn build_cfg(problem: &PBEProblem, cfg_path: Option<&str>, profile: Option<&profiles::ProfileSpec>, text_objects: bool, snippet: bool) -> Cfg {
+    let cfg = Cfg::from_synthfun(problem.synthfun());
+    let custom = cfg_path.or(match profile {
+        Some(profiles::ProfileSpec::Custom(path)) => Some(path.as_str()),
+        _ => None,
+    });
+    if let Some(s) = custom {
+        let sygus_if = parser::input::read_to_string(s).unwrap();
+        let mut enrichment = PBEProblem::parse(sygus_if.as_str()).unwrap_or_else(|e| parse_failed(s, &e, snippet));
+        include(&mut enrichment, s);
+        return enrich_configuration(&enrichment, cfg);
+    }
+    let ctx = Context::from_examples(&problem.examples);
+    let profile = match profile {
+        Some(profiles::ProfileSpec::Builtin(p)) => p,
+        _ => profiles::get(if text_objects { text::parsing::default_profile(&ctx) } else { "tabular" }).unwrap(),
+    };
+    info!("Enrichment profile: {}", profile.name);
+    profile.apply(cfg, &ctx)
+}
+
+/// Enhances the given configuration by integrating it with the grammar of a problem parsed from a SyGuS-IF file. 
+///
+/// Constants and variables of each non-terminal of `cfg` move to the first non-terminal of the enrichment of the same type, so that arguments of mixed types each land in a non-terminal of their own type, whatever the order of the non-terminals in either grammar.
+fn enrich_configuration(problem: &PBEProblem, mut cfg: Cfg) -> Cfg {
     let mut synthfun = problem.synthfun().clone();
     synthfun.cfg.start = synthfun.cfg.get_nt_by_type(&cfg[0].ty);
     synthfun.cfg.reset_start();
@@ -246,10 +957,11 @@ fn enrich_configuration(sygus_if: &str, mut cfg: Cfg) -> Cfg {
     for nt in cfg1.iter_mut() {
         nt.rules.retain(|x| !matches!(x, ProdRule::Var(_)));
     }
-    for (nt1, nt) in cfg1.iter_mut().zip(cfg.iter()) {
+    for nt in cfg.iter() {
+        let Some(i) = cfg1.find_by_type(nt.ty) else { continue };
         for r in nt.rules.iter() {
             if let ProdRule::Const(_) | ProdRule::Var(_) = r {
-                nt1.rules.push(r.clone());
+                cfg1[i].rules.push(r.clone());
             }
         }
     }
@@ -0,0 +1,61 @@
+use std::sync::OnceLock;
+
+use crate::expr::Expr;
+
+/// Callbacks reporting the progress of the solver as it happens, e.g. to display live candidate programs in a GUI embedding the solver.
+///
+/// Methods are called from the solver threads, and must return quickly since the solver waits for them. All methods do nothing by default.
+pub trait SolveObserver: Send + Sync {
+    /// A partial solution is kept, which solves `solved` of the `examples` examples; together with the previous ones, it is a candidate for the decision tree.
+    fn on_partial_solution(&self, _expr: &'static Expr, _solved: usize, _examples: usize) {}
+    /// A condition distinguishing the examples in a new way is collected for the decision tree.
+    fn on_new_condition(&self, _cond: &'static Expr) {}
+    /// An executor working on `examples` examples starts enumerating terms of `size`.
+    fn on_size_increase(&self, _examples: usize, _size: usize) {}
+}
+
+static OBSERVER: OnceLock<Box<dyn SolveObserver>> = OnceLock::new();
+
+/// Registers the observer of the solver, before solving starts. Returns `false` if an observer was already registered, which is kept.
+pub fn set_observer(observer: impl SolveObserver + 'static) -> bool {
+    OBSERVER.set(Box::new(observer)).is_ok()
+}
+
+/// Calls `f` on the registered observer, if any.
+pub fn notify(f: impl FnOnce(&dyn SolveObserver)) {
+    if let Some(o) = OBSERVER.get() { f(o.as_ref()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use crate::{expr, expr::{context::Context, Expr}, galloc::AllocForExactSizeIter, solutions::ConditionTracker, value::Value};
+
+    use super::{set_observer, SolveObserver};
+
+    static CONDITIONS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    struct Recorder;
+
+    impl SolveObserver for Recorder {
+        fn on_new_condition(&self, cond: &'static Expr) {
+            CONDITIONS.lock().unwrap().push(format!("{:?}", cond));
+        }
+    }
+
+    #[test]
+    fn test_observer() {
+        assert!(set_observer(Recorder));
+        assert!(!set_observer(Recorder));
+        let input: Value = ["observer-a", "observer-b"].into_iter().galloc_scollect().into();
+        let mut tracker = ConditionTracker::new(Context::new(2, vec![input], vec![], Value::Null));
+        tracker.insert(&expr!(PrefixOf "observer-a" [0]));
+        tracker.insert(&expr!(SuffixOf "a" [0]));
+        tracker.insert(&expr!(PrefixOf "observer-b" [0]));
+        let conditions = CONDITIONS.lock().unwrap();
+        assert!(conditions.contains(&format!("{:?}", expr!(PrefixOf "observer-a" [0]))));
+        assert!(!conditions.contains(&format!("{:?}", expr!(SuffixOf "a" [0]))));
+        assert!(conditions.contains(&format!("{:?}", expr!(PrefixOf "observer-b" [0]))));
+    }
+}
@@ -7,12 +7,20 @@ use regex::Regex;
 use crate::{expr::{ops::{Op1Enum, Op2Enum, Op3Enum}, Expr}, galloc::AllocForAny, utils::TryRetain, value::{ConstValue, Type}};
 use derive_more::Display;
 
-use super::{config::Config, ioexamples::IOExamples, problem::{new_custom_error_span, Error, FunSig, ProblemParser, Rule}};
+use super::{config::Config, ioexamples::IOExamples, prod::operator_name, problem::{new_custom_error_span, Error, FunSig, ProblemParser, Rule}};
 
 
 impl Expr {
     /// Parses an expression from a parsed `Pair` using an optional function signature and returns a static lifetime reference to an `Expr`, or an error. 
     pub fn parse(pair: Pair<'_, Rule>, sig: Option<&FunSig>) -> Result<&'static Expr, Error> {
+        Self::parse_in(pair, sig, &[], None)
+    }
+    /// Parses an expression in the scope of the variables bound by the enclosing `let`s, the `k`-th being `Var(!k)`.
+    ///
+    /// With `holes`, a hole `(?? nt)` becomes the variable following the arguments of `sig` and the holes already parsed, and the name of its non-terminal is pushed to `holes`; otherwise holes are rejected.
+    fn parse_in(pair: Pair<'_, Rule>, sig: Option<&FunSig>, scope: &[String], mut holes: Option<&mut Vec<String>>) -> Result<&'static Expr, Error> {
+        let span = pair.as_span();
+        let unknown = |op: &Pair<'_, Rule>| new_custom_error_span(format!("Unknown operator {}", op.as_str()), op.as_span());
         let mut vec = pair.into_inner().collect_vec();
         let mut config = Config::new();
         vec.try_retain(|x| {
@@ -25,9 +33,24 @@ impl Expr {
             let [value]: [_; 1] = vec.try_into().unwrap();
             match value.as_rule() {
                 Rule::value => Ok(Self::Const(ConstValue::parse(value)?).galloc()),
+                Rule::letexpr => {
+                    let mut inner = value.into_inner().collect_vec();
+                    let body = inner.pop().unwrap();
+                    let mut bound = scope.to_vec();
+                    let mut bindings = Vec::new();
+                    for b in inner {
+                        let [name, e]: [_; 2] = b.into_inner().collect_vec().try_into().unwrap();
+                        bindings.push((!(bound.len() as i64), Expr::parse_in(e, sig, scope, holes.as_deref_mut())?));
+                        bound.push(name.as_str().to_string());
+                    }
+                    let body = Expr::parse_in(body, sig, &bound, holes)?;
+                    Ok(bindings.into_iter().rev().fold(body, |body, (v, e)| Expr::Let(v, e, body).galloc()))
+                }
                 Rule::symbol => {
                     let regex1 = Regex::new(r"^<[0-9]>$".to_string().as_str()).unwrap();
-                    if let Some(v) = sig.and_then(|x| x.index(value.as_str())) {
+                    if let Some(k) = scope.iter().rposition(|n| n == value.as_str()) {
+                        Ok(Self::Var(!(k as i64)).galloc())
+                    } else if let Some(v) = sig.and_then(|x| x.index(value.as_str())) {
                         Ok(Self::Var(v as _).galloc())
                     } else if regex1.is_match(value.as_str()) {
                         Ok(Self::Var(value.as_str()[1..2].parse::<_>().unwrap()).galloc())
@@ -35,30 +58,42 @@ impl Expr {
                         return Err(new_custom_error_span("Not an input variable".into(), value.as_span()));
                     }
                 }
-                _ => panic!("should not reach here"),
+                Rule::hole => {
+                    let Some(holes) = holes else {
+                        return Err(new_custom_error_span("Holes are only allowed in the sketch of a synthesis problem".into(), value.as_span()));
+                    };
+                    let nargs = sig.map(|s| s.args.len()).unwrap_or(0);
+                    holes.push(value.into_inner().next().unwrap().as_str().to_owned());
+                    Ok(Self::Var((nargs + holes.len() - 1) as i64).galloc())
+                }
+                _ => Err(new_custom_error_span("Not an expression".into(), value.as_span())),
             }
         } else {
+            let name = operator_name(&vec[0], &mut config)?;
             match vec.as_slice() {
                 [op, a1] => {
-                    let op = Op1Enum::from_name(op.as_str(), &config);
-                    Ok(Self::Op1(op.galloc(), Expr::parse(a1.clone(), sig)?).galloc())
+                    let op = Op1Enum::try_from_name(&name, &config).ok_or_else(|| unknown(op))?;
+                    Ok(Self::Op1(op.galloc(), Expr::parse_in(a1.clone(), sig, scope, holes)?).galloc())
                 }
                 [op, a1, a2] => {
-                    let op = Op2Enum::from_name(op.as_str(), &config);
-                    Ok(Self::Op2(op.galloc(), Expr::parse(a1.clone(), sig)?, Expr::parse(a2.clone(), sig)?).galloc())
+                    let op = Op2Enum::try_from_name(&name, &config).ok_or_else(|| unknown(op))?;
+                    let a1 = Expr::parse_in(a1.clone(), sig, scope, holes.as_deref_mut())?;
+                    Ok(Self::Op2(op.galloc(), a1, Expr::parse_in(a2.clone(), sig, scope, holes)?).galloc())
                 }
                 [op, a1, a2, a3] => {
-                    let op = Op3Enum::from_name(op.as_str(), &config);
-                    Ok(Self::Op3(op.galloc(), Expr::parse(a1.clone(), sig)?, Expr::parse(a2.clone(), sig)?, Expr::parse(a3.clone(), sig)?).galloc())
+                    let op = Op3Enum::try_from_name(&name, &config).ok_or_else(|| unknown(op))?;
+                    let a1 = Expr::parse_in(a1.clone(), sig, scope, holes.as_deref_mut())?;
+                    let a2 = Expr::parse_in(a2.clone(), sig, scope, holes.as_deref_mut())?;
+                    Ok(Self::Op3(op.galloc(), a1, a2, Expr::parse_in(a3.clone(), sig, scope, holes)?).galloc())
                 }
-                _ => panic!("should not reach here"),
+                _ => Err(new_custom_error_span(format!("Unsupported number of arguments: {}", vec.len().saturating_sub(1)), span)),
             }
         }
     }
 }
 
 #[derive(Debug, Display, Clone)]
-#[display(fmt = "(define-fun {} {})", "sig", "expr.format(&sig)")]
+#[display(fmt = "(define-fun {} {})", "sig", "expr.format(sig)")]
 /// A struct that encapsulates the definition of a function in the synthesis problem. 
 /// 
 /// It contains two fields: `sig`, which holds the function's signature defined by the `FunSig` type, describing the function's name, return type, and its parameters; and `expr`, a reference to a static expression represented by the `Expr` type, which defines the body or implementation of the function. 
@@ -71,7 +106,10 @@ pub struct DefineFun {
 
 impl DefineFun {
     /// Parses a `DefineFun` instance from a sequence of parsed pairs. 
-    pub fn parse<'i>(pairs: Pair<'_, Rule>) -> Result<DefineFun, Error> {
+    pub fn parse(pairs: Pair<'_, Rule>) -> Result<DefineFun, Error> {
+        Self::parse_with_holes(pairs, None)
+    }
+    fn parse_with_holes(pairs: Pair<'_, Rule>, holes: Option<&mut Vec<String>>) -> Result<DefineFun, Error> {
         let [name, arglist, typ, expr]: [_; 4] = pairs.into_inner().collect_vec().try_into().unwrap();
         let args: Vec<(String, Type)> = arglist
             .into_inner()
@@ -83,11 +121,30 @@ impl DefineFun {
         let rettype = Type::parse(typ)?;
         let sig = FunSig{name: name.as_str().into(), args, rettype};
         
-        let expr = Expr::parse(expr, Some(&sig))?;
+        let expr = Expr::parse_in(expr, Some(&sig), &[], holes)?;
         Ok(Self{sig, expr})
     }
 }
 
+#[derive(Debug, Clone)]
+/// A program skeleton given with the synthesis problem: a `define-fun` of the function whose body contains holes `(?? nt)`, to be filled with terms of the non-terminal `nt` of the grammar (see [`crate::sketch`]).
+///
+/// The `k`-th hole of the body is the variable following the arguments of the function, `Var(nargs + k)`.
+pub struct Sketch {
+    pub definefun: DefineFun,
+    /// Non-terminal of each hole, in order
+    pub holes: Vec<String>,
+}
+
+impl Sketch {
+    /// Parses a `define-fun` whose body may contain holes.
+    pub fn parse(pairs: Pair<'_, Rule>) -> Result<Sketch, Error> {
+        let mut holes = Vec::new();
+        let definefun = DefineFun::parse_with_holes(pairs, Some(&mut holes))?;
+        Ok(Self { definefun, holes })
+    }
+}
+
 #[derive(Debug, Clone)]
 /// A struct representing a problem to be checked for synthesis validity. 
 /// 
@@ -124,6 +181,8 @@ impl CheckProblem {
 #[cfg(test)]
 mod tests {
     use std::fs;
+    use crate::{expr::Expr, parser::problem::PBEProblem};
+
     use super::CheckProblem;
 
     #[test]
@@ -132,4 +191,19 @@ mod tests {
         let a = CheckProblem::parse(s.as_str()).unwrap();
         println!("{:?}", a);
     }
+
+    #[test]
+    fn test_sketch() {
+        let s = r#"
+            (set-logic SLIA)
+            (synth-fun f ((name String)) String ((Start String (ntString)) (ntString String (name " " (str.++ ntString ntString)))))
+            (define-fun f ((name String)) String (str.++ (?? ntString) (str.++ "-" (?? ntString))))
+            (constraint (= (f "a b") "a-b"))
+            (check-synth)
+        "#;
+        let problem = PBEProblem::parse(s).unwrap();
+        let sketch = problem.sketch.unwrap();
+        assert_eq!(sketch.holes, vec!["ntString".to_string(), "ntString".to_string()]);
+        assert!(matches!(sketch.definefun.expr, Expr::Op2(_, Expr::Var(1), Expr::Op2(_, _, Expr::Var(2)))));
+    }
 }
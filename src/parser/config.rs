@@ -23,12 +23,18 @@ impl Config {
         let span = pair.as_span();
         let hash: Result<BTreeMap<String, ConstValue>, Error> = pair.into_inner().map(|x| {
             let [sym, v] : [Pair<'_, Rule>; 2] = x.into_inner().collect_vec().try_into().map_err(|_| new_custom_error_span("Expecting [(key value),*]".into(), span))?;
-            match v.as_rule() {
-                Rule::value => Ok((sym.as_str().into(), ConstValue::parse(v)?)),
-                Rule::symbol => Ok((sym.as_str().into(), ConstValue::Str(v.as_str().chars().galloc_collect_str()))),
-                Rule::expr => Ok((sym.as_str().into(), ConstValue::Expr(Expr::parse(v, None).unwrap()))),
+            let value_span = v.as_span();
+            let value = match v.as_rule() {
+                Rule::value => ConstValue::parse(v)?,
+                Rule::symbol => ConstValue::Str(v.as_str().chars().galloc_collect_str()),
+                Rule::expr => ConstValue::Expr(Expr::parse(v, None)?),
                 _ => panic!(),
+            };
+            // Rule weights are probabilities, whose logarithm gives the cost of the rule (see `apply_weights`)
+            if sym.as_str() == "weight" && !value.as_f64().or(value.as_i64().map(|i| i as f64)).is_some_and(|w| w > 0.0) {
+                return Err(new_custom_error_span("Rule weights should be positive numbers".into(), value_span));
             }
+            Ok((sym.as_str().into(), value))
         }).collect();
         Ok(hash?.into())
     }
@@ -49,12 +55,19 @@ impl Config {
     pub fn get_bool(&self, name: &str) -> Option<bool> {
         self.0.get(name).and_then(|x| x.as_bool())
     }
+    /// Retrieves a numeric value as `f64`, accepting both float and integer entries.
+    pub fn get_f64(&self, name: &str) -> Option<f64> {
+        self.0.get(name).and_then(|x| x.as_f64().or(x.as_i64().map(|i| i as f64)))
+    }
+    /// Sets the value associated with a key, replacing any previous one.
+    pub fn set(&mut self, name: &str, value: ConstValue) {
+        self.0.insert(name.into(), value);
+    }
     /// Provides functionality to retrieve a static reference to an `Expr` associated with a given name.  
     pub fn get_expr(&self, name: &str) -> Option<&'static Expr> {
         self.0.get(name).and_then(|x| x.as_expr())
     }
     /// Merges another instance into the current one by extending its internal map with the entries from the provided instance. 
-
     pub fn merge(&mut self, other: Self) {
         self.0.extend(other.0);
     }
@@ -62,7 +75,6 @@ impl Config {
 
 impl std::fmt::Debug for Config {
     /// Formats the contents of the `Config` into a user-friendly string representation. 
-
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for (k, v) in self.0.iter() {
             write!(f, " #{}:{}", k, v)?
@@ -0,0 +1,90 @@
+use pest::error::{ErrorVariant, LineColLocation};
+
+use super::problem::{Error, Rule};
+
+/// Describes a grammar rule as it appears in messages, e.g. `expected production rule`.
+fn describe(rule: &Rule) -> String {
+    match rule {
+        Rule::symbol => "symbol",
+        Rule::value | Rule::numeral | Rule::hexnum | Rule::binnum | Rule::strlit | Rule::boollit | Rule::charlit | Rule::maplit | Rule::tuplelit | Rule::listlit => "literal",
+        Rule::config | Rule::config_entry => "configuration `#name:value`",
+        Rule::logic => "`(set-logic ...)`",
+        Rule::include | Rule::includes => "`(include ...)`",
+        Rule::typ => "type",
+        Rule::arg | Rule::arglist => "argument list",
+        Rule::prod => "production rule",
+        Rule::prods => "list of production rules",
+        Rule::nt => "non-terminal",
+        Rule::cfg => "grammar",
+        Rule::synthfun | Rule::synthsubproblem | Rule::synthproblem => "`(synth-fun ...)`",
+        Rule::example | Rule::negexample | Rule::spec | Rule::examples => "constraint",
+        Rule::declvar => "`(declare-var ...)`",
+        Rule::check_synth => "`(check-synth)`",
+        Rule::definefun => "`(define-fun ...)`",
+        Rule::assertion | Rule::assertions => "assertion",
+        Rule::check_sat => "`(check-sat)`",
+        Rule::expr | Rule::term | Rule::letexpr => "expression",
+        Rule::hole => "hole `(?? nt)`",
+        r => return format!("{:?}", r),
+    }.into()
+}
+
+/// Returns the message of a parse error, without its location.
+pub fn message(e: &Error) -> String {
+    match e.clone().renamed_rules(describe).variant {
+        ErrorVariant::CustomError { message } => message,
+        variant => variant.message().into_owned(),
+    }
+}
+
+/// Formats a parse error as `path:line:col: message`, followed by the source line with the error underlined if `snippet` is set.
+pub fn format_error(e: &Error, path: &str, snippet: bool) -> String {
+    format_message(e, path, &message(e), snippet)
+}
+
+/// Formats `message` at the location of the error `e`, in the same way as [`format_error`].
+pub fn format_message(e: &Error, path: &str, message: &str, snippet: bool) -> String {
+    let ((line, col), end) = match e.line_col {
+        LineColLocation::Pos(p) => (p, None),
+        LineColLocation::Span(p, q) => (p, Some(q)),
+    };
+    let mut result = format!("{path}:{line}:{col}: {message}");
+    if snippet {
+        let source = e.line().trim_end_matches(['\r', '\n']);
+        let width = match end {
+            Some((l, c)) if l == line => c.saturating_sub(col).max(1),
+            Some(_) => source.chars().count().saturating_sub(col - 1).max(1),
+            None => 1,
+        };
+        let margin = " ".repeat(line.to_string().len());
+        result += &format!("\n{margin} |\n{line} | {source}\n{margin} | {}{}", " ".repeat(col - 1), "^".repeat(width));
+    }
+    result
+}
+
+/// Formats an error of the JSON front-end as `path:line:col: message`.
+pub fn format_json_error(e: &serde_json::Error, path: &str) -> String {
+    let message = e.to_string();
+    // `serde_json` appends the location to its messages.
+    let message = message.rsplit_once(" at line ").map_or(message.as_str(), |(m, _)| m);
+    if e.line() == 0 { format!("{path}: {message}") } else { format!("{path}:{}:{}: {message}", e.line(), e.column()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::problem::PBEProblem;
+
+    use super::format_error;
+
+    #[test]
+    fn test_format_error() {
+        let s = "(set-logic SLIA)\n(synth-fun f ((name String)) String\n  ((Start String (name \"a\" ])))\n(constraint (= (f \"a\") \"b\"))\n(check-synth)";
+        let e = PBEProblem::parse(s).unwrap_err();
+        let formatted = format_error(&e, "f.sl", false);
+        assert!(formatted.starts_with("f.sl:3:28: expected"), "{formatted}");
+        assert!(!formatted.contains('\n'));
+        let formatted = format_error(&e, "f.sl", true);
+        assert!(formatted.contains("3 |   ((Start String"), "{formatted}");
+        assert!(formatted.ends_with('^'));
+    }
+}
@@ -0,0 +1,124 @@
+use std::{collections::{HashMap, HashSet}, path::{Path, PathBuf}};
+
+use derive_more::Display;
+
+use super::{diagnostics, input, problem::{Cfg, Error, PBEProblem}, prod::ProdRule};
+
+#[derive(Debug, Display)]
+/// Errors raised while resolving the `(include "...")` directives of a file.
+pub enum IncludeError {
+    #[display(fmt = "Failed to read included file {}: {}", _0, _1)]
+    Io(String, std::io::Error),
+    #[display(fmt = "{}", "diagnostics::format_error(_1, _0, false)")]
+    Parse(String, Box<Error>),
+    #[display(fmt = "Failed to merge the grammar of {}: {}", _0, _1)]
+    Merge(String, String),
+}
+
+impl std::error::Error for IncludeError {}
+
+/// Resolves the `(include "...")` directives of `problem`, read from the file `path`, merging the grammars of the included files into its `synth-fun` with [`merge`].
+///
+/// Paths are relative to the directory of the including file. Included files are enriched SyGuS-IF files, which may include other files in turn; a file is merged only once, even if included several times.
+pub fn resolve(problem: &mut PBEProblem, path: &Path) -> Result<(), IncludeError> {
+    let mut seen = HashSet::new();
+    seen.insert(canonical(path));
+    resolve_rec(problem, path, &mut seen)
+}
+
+fn resolve_rec(problem: &mut PBEProblem, path: &Path, seen: &mut HashSet<PathBuf>) -> Result<(), IncludeError> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    for include in std::mem::take(&mut problem.includes) {
+        let file = dir.join(include);
+        if !seen.insert(canonical(&file)) { continue; }
+        let name = file.display().to_string();
+        let s = input::read_to_string(&file).map_err(|e| IncludeError::Io(name.clone(), e))?;
+        let mut included = PBEProblem::parse(&s).map_err(|e| IncludeError::Parse(name.clone(), Box::new(e)))?;
+        resolve_rec(&mut included, &file, seen)?;
+        let index = problem.problem_index;
+        merge(&mut problem.synthfuns[index].cfg, &included.synthfun().cfg).map_err(|e| IncludeError::Merge(name, e))?;
+    }
+    Ok(())
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Merges the grammar `other` into `cfg`, keeping the start symbol of `cfg`.
+///
+/// A non-terminal of `other` is merged into the non-terminal of `cfg` with the same name, which must have the same type; failing that, into the first one of the same type, to which its references are renamed. The others are appended.
+/// Arguments of the function of `other` are dropped, rules already present in the non-terminal are skipped, and the configuration of a non-terminal of `cfg` takes precedence over that of `other`.
+pub fn merge(cfg: &mut Cfg, other: &Cfg) -> Result<(), String> {
+    let mut names = HashMap::new();
+    for nt in other.inner.iter() {
+        let target = match cfg.inner.iter().find(|x| x.0 == nt.0) {
+            Some(x) if x.1 != nt.1 => return Err(format!("non-terminal {} has type {:?}, but {:?} in the included grammar", nt.0, x.1, nt.1)),
+            Some(x) => x.0.clone(),
+            None => cfg.inner.iter().find(|x| x.1 == nt.1).map(|x| x.0.clone()).unwrap_or_else(|| nt.0.clone()),
+        };
+        names.insert(nt.0.clone(), target);
+    }
+    for nt in other.inner.iter() {
+        let target = &names[&nt.0];
+        // Arguments of the included function mean nothing in `cfg`
+        let rules = nt.2.iter().filter(|r| !matches!(r, ProdRule::Var(a, _) if !names.contains_key(a))).map(|r| rename(r, &names)).collect::<Vec<_>>();
+        match cfg.inner.iter_mut().find(|x| &x.0 == target) {
+            Some(x) => {
+                for r in rules {
+                    if !x.2.contains(&r) { x.2.push(r); }
+                }
+                let mut config = nt.3.clone();
+                config.merge(std::mem::take(&mut x.3));
+                x.3 = config;
+            }
+            None => {
+                let mut nt = nt.clone();
+                nt.2 = rules.into_iter().fold(Vec::new(), |mut v, r| { if !v.contains(&r) { v.push(r); } v });
+                cfg.inner.push(nt);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Renames the non-terminals referred to by `rule`; other symbols (e.g. arguments of the function) are left as they are.
+fn rename(rule: &ProdRule, names: &HashMap<String, String>) -> ProdRule {
+    let r = |s: &String| names.get(s).cloned().unwrap_or_else(|| s.clone());
+    match rule {
+        ProdRule::Var(a, c) => ProdRule::Var(r(a), c.clone()),
+        ProdRule::Const(..) => rule.clone(),
+        ProdRule::Op1(op, a, c) => ProdRule::Op1(op.clone(), r(a), c.clone()),
+        ProdRule::Op2(op, a, b, c) => ProdRule::Op2(op.clone(), r(a), r(b), c.clone()),
+        ProdRule::Op3(op, a, b, d, c) => ProdRule::Op3(op.clone(), r(a), r(b), r(d), c.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::parser::problem::PBEProblem;
+
+    use super::resolve;
+
+    #[test]
+    fn test_include() {
+        let s = r#"
+            (set-logic SLIA)
+            (include "common-ops.sl")
+            (synth-fun f ((name String)) String ((Start String (ntString)) (ntString String (name " " (str.++ ntString ntString)))))
+            (constraint (= (f "a") "a a"))
+            (check-synth)
+        "#;
+        let mut problem = PBEProblem::parse(s).unwrap();
+        assert_eq!(problem.includes, vec!["common-ops.sl".to_string()]);
+        resolve(&mut problem, Path::new("test/main.sl")).unwrap();
+        let cfg = &problem.synthfun().cfg;
+        let string = cfg.inner.iter().find(|nt| nt.0 == "ntString").unwrap();
+        assert_eq!(string.2.iter().filter(|r| format!("{:?}", r) == "(str.++ ntString ntString)").count(), 1);
+        assert!(string.2.iter().any(|r| format!("{:?}", r) == "(str.at ntString ntInt)"));
+        assert!(cfg.inner.iter().any(|nt| nt.0 == "ntInt"));
+        assert!(problem.includes.is_empty());
+    }
+}
@@ -0,0 +1,108 @@
+use std::{io, path::Path};
+
+use crate::info;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+/// Text encoding of the input files (`--encoding`).
+pub enum Encoding {
+    /// Detected from the byte order mark, then from the bytes: UTF-8 if they are valid UTF-8, otherwise UTF-16 if they look like it, otherwise Windows-1252
+    #[default]
+    Auto,
+    Utf8,
+    Utf16le,
+    Utf16be,
+    /// The encoding of Excel exports on western Windows systems, a superset of the printable characters of Latin-1
+    Windows1252,
+    Latin1,
+}
+
+static ENCODING: spin::Mutex<Encoding> = spin::Mutex::new(Encoding::Auto);
+
+/// Sets the encoding of the files read by [`read_to_string`].
+pub fn set_encoding(encoding: Encoding) {
+    *ENCODING.lock() = encoding;
+}
+
+/// Characters of the bytes 0x80 to 0x9F in Windows-1252, where it differs from Latin-1. The five unassigned bytes are kept as the control characters of Latin-1.
+const WINDOWS_1252: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}',
+];
+
+fn utf16(bytes: &[u8], little_endian: bool) -> String {
+    let units = bytes.chunks_exact(2).map(|c| if little_endian { u16::from_le_bytes([c[0], c[1]]) } else { u16::from_be_bytes([c[0], c[1]]) });
+    char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)).collect()
+}
+
+/// Guesses the encoding of bytes without a byte order mark.
+///
+/// Text in UTF-16 has a zero byte in most code units, since SyGuS files are mostly ASCII; the side of the zero bytes gives the byte order.
+fn detect(bytes: &[u8]) -> Encoding {
+    if std::str::from_utf8(bytes).is_ok() { return Encoding::Utf8; }
+    let zeros = |parity: usize| bytes.iter().skip(parity).step_by(2).filter(|b| **b == 0).count();
+    let half = bytes.len() / 4;
+    if zeros(1) > half { Encoding::Utf16le }
+    else if zeros(0) > half { Encoding::Utf16be }
+    else { Encoding::Windows1252 }
+}
+
+/// Decodes `bytes` in `encoding`, removing the byte order mark if any, and returns the text with the encoding actually used.
+pub fn decode(bytes: &[u8], encoding: Encoding) -> Result<(String, Encoding), String> {
+    let (bytes, encoding) = match encoding {
+        Encoding::Auto => match bytes {
+            [0xEF, 0xBB, 0xBF, rest @ ..] => (rest, Encoding::Utf8),
+            [0xFF, 0xFE, rest @ ..] => (rest, Encoding::Utf16le),
+            [0xFE, 0xFF, rest @ ..] => (rest, Encoding::Utf16be),
+            _ => (bytes, detect(bytes)),
+        },
+        Encoding::Utf8 => (bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes), encoding),
+        Encoding::Utf16le => (bytes.strip_prefix(&[0xFF, 0xFE]).unwrap_or(bytes), encoding),
+        Encoding::Utf16be => (bytes.strip_prefix(&[0xFE, 0xFF]).unwrap_or(bytes), encoding),
+        _ => (bytes, encoding),
+    };
+    let text = match encoding {
+        Encoding::Utf8 => std::str::from_utf8(bytes).map_err(|e| format!("invalid UTF-8 ({}), set the encoding with --encoding", e))?.to_string(),
+        Encoding::Utf16le => utf16(bytes, true),
+        Encoding::Utf16be => utf16(bytes, false),
+        Encoding::Windows1252 => bytes.iter().map(|&b| if (0x80..0xA0).contains(&b) { WINDOWS_1252[b as usize - 0x80] } else { b as char }).collect(),
+        Encoding::Latin1 | Encoding::Auto => bytes.iter().map(|&b| b as char).collect(),
+    };
+    Ok((text, encoding))
+}
+
+/// Reads an input file as text, transcoding it to UTF-8 from the encoding set by `--encoding` (detected by default).
+///
+/// Used in place of `fs::read_to_string`, which fails on files exported in Latin-1 or Windows-1252.
+pub fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path)?;
+    let (text, encoding) = decode(&bytes, *ENCODING.lock()).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))?;
+    if encoding != Encoding::Utf8 {
+        info!("Read {} as {:?}", path.display(), encoding);
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, Encoding};
+
+    #[test]
+    fn test_decode() {
+        let utf8 = "(constraint (= (f \"Café\") \"l’été\"))";
+        assert_eq!(decode(utf8.as_bytes(), Encoding::Auto).unwrap(), (utf8.to_string(), Encoding::Utf8));
+        let bom = [&[0xEF, 0xBB, 0xBF][..], utf8.as_bytes()].concat();
+        assert_eq!(decode(&bom, Encoding::Auto).unwrap().0, utf8);
+
+        let cp1252 = b"(f \"Caf\xE9\") \"l\x92\xE9t\xE9\"";
+        assert_eq!(decode(cp1252, Encoding::Auto).unwrap(), ("(f \"Café\") \"l’été\"".to_string(), Encoding::Windows1252));
+        assert_eq!(decode(cp1252, Encoding::Latin1).unwrap().0.chars().nth(13), Some('\u{92}'));
+        assert!(decode(cp1252, Encoding::Utf8).is_err());
+
+        let utf16: Vec<u8> = [0xFF, 0xFE].into_iter().chain("é’x".encode_utf16().flat_map(|u| u.to_le_bytes())).collect();
+        assert_eq!(decode(&utf16, Encoding::Auto).unwrap(), ("é’x".to_string(), Encoding::Utf16le));
+        assert_eq!(decode(&utf16[2..], Encoding::Auto).unwrap().1, Encoding::Utf16le);
+    }
+}
@@ -1,6 +1,6 @@
 // use crate::galloc::alloc_iter;
 
-use std::collections::HashSet;
+use std::{cmp::Reverse, collections::HashSet};
 
 use crate::galloc::{self, AllocForIter};
 
@@ -33,6 +33,10 @@ use derive_more::DebugCustom;
 pub struct IOExamples {
     pub(crate) inputs: Vec<Value>,
     pub(crate) output: Value,
+    /// Inputs of the negative examples, i.e. constraints `(not (= (f ...) bad))`.
+    pub(crate) neg_inputs: Vec<Value>,
+    /// Outputs that the solution must *not* produce on the corresponding `neg_inputs`.
+    pub(crate) neg_output: Value,
 }
 
 impl IOExamples {
@@ -42,6 +46,7 @@ impl IOExamples {
     /// The function processes the provided examples by iterating over them, ensuring each example contains a correct number of arguments and matching types. 
     /// If the 'dedup' parameter is set to true, duplicates are removed using a `HashSet`. 
     /// Finally, the function constructs the `inputs` and `output`, organizing each example's inputs by type before returning the assembled `IOExamples` structure.
+    /// Negative examples (`(constraint (not (= (f ...) v)))`) are checked the same way and collected separately into `neg_inputs` and `neg_output`.
     /// 
     pub(crate) fn parse(examples: Pair<'_, Rule>, sig: &FunSig, dedup: bool) -> Result<Self, Error> {
         let name = sig.name.as_str();
@@ -49,33 +54,65 @@ impl IOExamples {
         let rettype = sig.rettype;
         let mut types = args.iter().map(|x| x.1).collect_vec();
         types.push(rettype);
-        let mut v: Vec<_> = examples
-            .into_inner()
-            .map(|x| {
-                let span = x.as_span();
-                let v = x.into_inner().skip(1).collect_vec();
-                let v: Vec<_> = v.into_iter().map(|x| ConstValue::parse(x)).try_collect()?;
-                if v.len() != types.len() {
-                    return Err(new_custom_error_span(format!("wrong number of arguments for {}: expected", name), span));
-                }
-                for (value, typ) in v.iter().zip(types.iter()) {
-                    if value.ty() != *typ {
-                        return Err(new_custom_error_span(format!("wrong type for {}", name), span));
-                    }
+        let parse_row = |x: Pair<'_, Rule>| {
+            let span = x.as_span();
+            let v = x.into_inner().skip(1).collect_vec();
+            let v: Vec<_> = v.into_iter().map(|x| ConstValue::parse(x)).try_collect()?;
+            if v.len() != types.len() {
+                return Err(new_custom_error_span(format!("wrong number of arguments for {}: expected", name), span));
+            }
+            for (value, typ) in v.iter().zip(types.iter()) {
+                if value.ty() != *typ {
+                    return Err(new_custom_error_span(format!("wrong type for {}", name), span));
                 }
-                Ok(v)
-            }).try_collect()?;
+            }
+            Ok(v)
+        };
+        let (mut v, mut neg) = (Vec::new(), Vec::new());
+        for x in examples.into_inner() {
+            match x.as_rule() {
+                Rule::spec | Rule::declvar | Rule::definefun | Rule::definescanner | Rule::unsupported => (),
+                Rule::negexample => neg.push(parse_row(x)?),
+                _ => v.push(parse_row(x)?),
+            }
+        }
             
         if dedup {
             let set: HashSet<_> = v.iter().cloned().collect();
             v = set.into_iter().collect_vec();
+            neg = neg.into_iter().unique().collect_vec();
         }
 
-        let mut inputs = types.iter().enumerate().map(|(i, ty)| Value::from_const(*ty, v.iter().map(|input| &input[i]).cloned())).collect_vec();
-        let output = inputs.pop().unwrap();
-        Ok(Self { inputs, output })
+        let columns = |rows: &[Vec<ConstValue>]| {
+            let mut columns = types.iter().enumerate().map(|(i, ty)| Value::from_const(*ty, rows.iter().map(|input| &input[i]).cloned())).collect_vec();
+            let output = columns.pop().unwrap();
+            (columns, output)
+        };
+        let (inputs, output) = columns(&v);
+        let (neg_inputs, neg_output) = columns(&neg);
+        Ok(Self { inputs, output, neg_inputs, neg_output })
     }
     
+    /// Returns the number of examples.
+    pub fn len(&self) -> usize {
+        self.output.len()
+    }
+    /// Returns the number of negative examples.
+    pub fn neg_len(&self) -> usize {
+        self.neg_output.len()
+    }
+    /// Appends one example, given as the argument values followed by the expected output. 
+    /// 
+    /// The values must have the types of the corresponding columns; this is used to add counterexamples found during CEGIS.
+    pub fn add_example(&mut self, inputs: &[ConstValue], output: ConstValue) {
+        assert_eq!(inputs.len(), self.inputs.len(), "wrong number of arguments");
+        let len = self.len();
+        for (column, c) in self.inputs.iter_mut().chain(std::iter::once(&mut self.output)).zip(inputs.iter().chain(std::iter::once(&output))) {
+            let ty = if len == 0 { c.ty() } else { column.ty() };
+            let values = (0..len).map(|i| column.get_const(i)).chain(std::iter::once(*c)).collect_vec();
+            *column = Value::from_const(ty, values.into_iter());
+        }
+    }
     /// Extracts and returns a list of constant substrings identified in the input and output examples of string synthesis problems.
     /// 
     /// The method iterates over all input strings and the output string, treating them as a unified sequence. 
@@ -83,7 +120,8 @@ impl IOExamples {
     /// It then evaluates each distinct substring, checking for specific filtering conditions: the substring must appear with sufficient frequency, must either be a significant length or show certain frequency patterns, and should not be simple numeric or alphanumeric characters. 
     /// Substrings meeting these criteria that are not already surpassed in count by longer substrings are added to the list of constants. 
     /// This approach helps in identifying significant repeating string patterns, which can play a crucial role in constructing string transformation rules.
-    pub fn extract_constants(&self) -> Vec<&'static str> {
+    /// The constants mined from the outputs alone (see `output_constants`) are ranked first, followed by the repeating substrings from the most to the least frequent; at most `max` constants are returned.
+    pub fn extract_constants(&self, max: usize) -> Vec<&'static str> {
         let mut counter = Counter::<&str, usize>::new();
         let mut total_len = 0;
         for s1 in self.inputs.iter().chain(std::iter::once(&self.output)) {
@@ -110,11 +148,7 @@ impl IOExamples {
                 if k.len() == 1 {
                     flag = true;
                 }
-                if k.len() >= 6 {
-                    flag = true;
-                } else if k.len() >= 4 && *v >= std::cmp::max(5, total_len / 100) {
-                    flag = true;
-                } else if *v >= std::cmp::max(8, total_len / 30) {
+                if k.len() >= 6 || (k.len() >= 4 && *v >= std::cmp::max(5, total_len / 100)) || *v >= std::cmp::max(8, total_len / 30) {
                     flag = true;
                 }
 
@@ -124,8 +158,38 @@ impl IOExamples {
                 }
             }
         }
+        constants.sort_by_key(|c| (Reverse(counter[c]), Reverse(c.len()), *c));
 
-        constants
+        let mut result = self.output_constants().into_iter().map(|(c, _)| c).collect_vec();
+        for c in constants {
+            if !result.contains(&c) { result.push(c); }
+        }
+        result.truncate(max);
+        result
+    }
+    /// Mines the scaffolding of the outputs: substrings shared by several outputs that cannot be copied from the inputs of their examples, such as the separators between the varying parts of the outputs.
+    ///
+    /// A substring is kept if at least two outputs, and half of them, contain it, unless a longer kept substring contains it in as many outputs.
+    /// The substrings are returned with the number of outputs containing them, ranked by this number and then by length.
+    fn output_constants(&self) -> Vec<(&'static str, usize)> {
+        let Value::Str(outputs) = self.output else { return Vec::new() };
+        let mut support = Counter::<&'static str, usize>::new();
+        for (i, o) in outputs.iter().enumerate() {
+            let inputs = self.inputs.iter().filter_map(|v| if let Value::Str(a) = v { Some(a[i]) } else { None }).collect_vec();
+            let slices: HashSet<&'static str> = all_slices(o).filter(|s| !inputs.iter().any(|inp| inp.contains(s))).collect();
+            for s in slices { support[&s] += 1; }
+        }
+        let threshold = std::cmp::max(2, outputs.len().div_ceil(2));
+        let mut candidates = support.iter().filter(|(k, v)| {
+            **v >= threshold && !(k.chars().count() == 1 && k.chars().all(char::is_alphanumeric)) && !k.chars().all(char::is_numeric)
+        }).map(|(k, v)| (*k, *v)).collect_vec();
+        candidates.sort_by_key(|(k, v)| (Reverse(k.len()), Reverse(*v), *k));
+        let mut result: Vec<(&'static str, usize)> = Vec::new();
+        for (k, v) in candidates {
+            if !result.iter().any(|(c, n)| c.contains(k) && *n >= v) { result.push((k, v)); }
+        }
+        result.sort_by_key(|(k, v)| (Reverse(*v), Reverse(k.len()), *k));
+        result
     }
 }
 
@@ -139,3 +203,20 @@ impl IOExamples {
 fn all_slices(a: &str) -> impl Iterator<Item = &str> {
     (0..a.len()).flat_map(move |i| a.char_indices().skip(i).map(move |(j, _)| &a[i..j + 1]))
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{galloc::AllocForExactSizeIter, value::Value};
+
+    use super::IOExamples;
+
+    #[test]
+    fn test_output_constants() {
+        let input: Value = ["John Smith", "Ann Lee", "Bob Stone"].into_iter().galloc_scollect().into();
+        let output: Value = ["Name: Smith, John.", "Name: Lee, Ann.", "Name: Stone, Bob."].into_iter().galloc_scollect().into();
+        let examples = IOExamples { inputs: vec![input], output, neg_inputs: Vec::new(), neg_output: Value::Null };
+        let constants = examples.extract_constants(2);
+        assert_eq!(constants, vec!["Name: ", ", "]);
+        assert!(examples.extract_constants(20).contains(&"."));
+    }
+}
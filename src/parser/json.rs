@@ -0,0 +1,144 @@
+use itertools::Itertools;
+use serde::{de::Error as _, Deserialize};
+
+use crate::{galloc::AllocForStr, value::{ConstValue, Type, Value}};
+
+use super::{config::Config, ioexamples::IOExamples, problem::{Cfg, FunSig, NonTerminal, PBEProblem, SynthFun}, prod::ProdRule};
+
+pub type Error = serde_json::Error;
+
+#[derive(Deserialize)]
+/// A PBE problem in JSON format: `{"inputs": [...], "outputs": [...], "grammar": "string-default"}`.
+///
+/// Each entry of `inputs` is either a single value (for a one-argument function) or an array holding one value per argument.
+/// Entries of `outputs` are single values, matched with `inputs` by position.
+pub struct JsonProblem {
+    pub inputs: Vec<serde_json::Value>,
+    pub outputs: Vec<serde_json::Value>,
+    #[serde(default = "default_grammar")]
+    pub grammar: String,
+}
+
+fn default_grammar() -> String { "string-default".into() }
+
+/// Converts a JSON scalar into a constant. Strings, integers, floats and booleans are supported.
+pub(crate) fn to_const(v: &serde_json::Value) -> Result<ConstValue, Error> {
+    match v {
+        serde_json::Value::String(s) => Ok(ConstValue::Str(s.as_str().galloc_str())),
+        serde_json::Value::Bool(b) => Ok(ConstValue::Bool(*b)),
+        serde_json::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Ok(ConstValue::Int(i))
+            } else if let Some(f) = n.as_f64() {
+                Ok(ConstValue::Float(f.into()))
+            } else { Err(Error::custom(format!("Unsupported number {n}"))) }
+        }
+        _ => Err(Error::custom(format!("Unsupported value {v}"))),
+    }
+}
+
+/// Infers the type of a column of constants, failing if they do not agree.
+fn column_type(column: &[ConstValue], name: &str) -> Result<Type, Error> {
+    let ty = column.first().ok_or_else(|| Error::custom("No examples"))?.ty();
+    if column.iter().any(|c| c.ty() != ty) {
+        return Err(Error::custom(format!("Values of {name} have different types")));
+    }
+    Ok(ty)
+}
+
+impl JsonProblem {
+    /// Builds the examples as rows of constants (inputs followed by the output), deduplicated in order.
+    fn rows(&self) -> Result<Vec<Vec<ConstValue>>, Error> {
+        if self.inputs.len() != self.outputs.len() {
+            return Err(Error::custom(format!("{} inputs but {} outputs", self.inputs.len(), self.outputs.len())));
+        }
+        let rows: Vec<Vec<ConstValue>> = self.inputs.iter().zip(self.outputs.iter()).map(|(i, o)| {
+            let mut row: Vec<ConstValue> = match i {
+                serde_json::Value::Array(args) => args.iter().map(to_const).try_collect()?,
+                v => vec![to_const(v)?],
+            };
+            row.push(to_const(o)?);
+            Ok::<_, Error>(row)
+        }).try_collect()?;
+        if let Some(r) = rows.iter().find(|r| r.len() != rows[0].len()) {
+            return Err(Error::custom(format!("Expecting {} arguments, found {}", rows[0].len() - 1, r.len() - 1)));
+        }
+        Ok(rows.into_iter().unique().collect_vec())
+    }
+}
+
+impl PBEProblem {
+    /// Parses a PBE problem from its JSON representation (see [`JsonProblem`]).
+    ///
+    /// Arguments are named `x0`, `x1`, ... and the function is named `f`; argument and return types are inferred from the values.
+    /// The only supported grammar is `string-default`, with one non-terminal per type (the start symbol first) deriving the arguments of that type; the usual enrichment of the grammar then applies as for SyGuS-IF problems.
+    pub fn parse_json(input: &str) -> Result<PBEProblem, Error> {
+        let json: JsonProblem = serde_json::from_str(input)?;
+        if json.grammar != "string-default" {
+            return Err(Error::custom(format!("Unknown grammar {}", json.grammar)));
+        }
+        let rows = json.rows()?;
+        let ncol = rows.first().ok_or_else(|| Error::custom("No examples"))?.len();
+        let columns = (0..ncol).map(|i| rows.iter().map(|r| r[i]).collect_vec()).collect_vec();
+        let types: Vec<Type> = columns.iter().enumerate().map(|(i, c)| {
+            column_type(c, &if i + 1 == ncol { "outputs".to_string() } else { format!("argument {i}") })
+        }).try_collect()?;
+
+        let args = types[..ncol - 1].iter().enumerate().map(|(i, ty)| (format!("x{i}"), *ty)).collect_vec();
+        let rettype = types[ncol - 1];
+        // One non-terminal per type, deriving the arguments of that type, the start symbol first.
+        let nt_types = std::iter::once(rettype).chain(args.iter().map(|(_, ty)| *ty)).unique().collect_vec();
+        let inner = nt_types.iter().enumerate().map(|(i, ty)| {
+            let name = if i == 0 { "Start".to_string() } else { format!("Arg{i}") };
+            let vars = args.iter().filter(|(_, t)| t == ty).map(|(name, _)| ProdRule::Var(name.clone(), Config::new())).collect();
+            NonTerminal(name, *ty, vars, Config::new())
+        }).collect_vec();
+        let synthfun = SynthFun {
+            sig: FunSig { name: "f".into(), args, rettype },
+            cfg: Cfg { start: "Start".into(), inner, config: Config::new() },
+            subproblem: false,
+        };
+
+        let neg_inputs = types[..ncol - 1].iter().map(|ty| Value::from_const(*ty, std::iter::empty())).collect_vec();
+        let neg_output = Value::from_const(rettype, std::iter::empty());
+        let mut inputs = columns.into_iter().zip(types).map(|(c, ty)| Value::from_const(ty, c.into_iter())).collect_vec();
+        let output = inputs.pop().unwrap();
+        Ok(PBEProblem {
+            logic: "SLIA".into(),
+            synthfuns: vec![synthfun],
+            problem_index: 0,
+            examples: IOExamples { inputs, output, neg_inputs, neg_output },
+            vars: Vec::new(),
+            spec: Vec::new(),
+            includes: Vec::new(),
+            sketch: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr::context::Context, parser::problem::PBEProblem, value::Type};
+
+    #[test]
+    fn test_parse_json() {
+        let problem = PBEProblem::parse_json(r#"{
+            "inputs": [["John", 3], ["Jane \"J\"", 4], ["John", 3]],
+            "outputs": ["J3", "J4", "J3"],
+            "grammar": "string-default"
+        }"#).unwrap();
+        let sig = &problem.synthfun().sig;
+        assert_eq!(sig.args, vec![("x0".to_string(), Type::Str), ("x1".to_string(), Type::Int)]);
+        assert_eq!(sig.rettype, Type::Str);
+        let ctx = Context::from_examples(&problem.examples);
+        assert_eq!(ctx.len, 2);
+        assert_eq!(ctx.p[0].to_str(), &["John", "Jane \"J\""]);
+        assert_eq!(ctx.output.to_str(), &["J3", "J4"]);
+        let cfg = &problem.synthfun().cfg;
+        assert_eq!(cfg.inner.iter().map(|nt| (nt.1, nt.2.len())).collect::<Vec<_>>(), vec![(Type::Str, 1), (Type::Int, 1)]);
+
+        assert!(PBEProblem::parse_json(r#"{"inputs": ["a", 1], "outputs": ["a", "b"]}"#).is_err());
+        assert!(PBEProblem::parse_json(r#"{"inputs": ["a"], "outputs": ["a", "b"]}"#).is_err());
+        assert!(PBEProblem::parse_json(r#"{"inputs": ["a"], "outputs": ["a"], "grammar": "int"}"#).is_err());
+    }
+}
@@ -8,8 +8,25 @@ pub mod prod;
 /// Parser for examples
 pub mod ioexamples;
 
+/// Compilation of general constraints to examples
+pub mod spec;
+
+/// Parser for PBE problems in JSON format
+pub mod json;
+
 /// Parser for specific configuration in extended SyGuS-IF
 pub mod config;
 
+/// Reading of input files in other encodings than UTF-8 (`--encoding`)
+pub mod input;
+
+/// Resolution of `(include "...")` directives, merging grammar fragments
+pub mod include;
+
 /// Contains parser for `expression`, `def-fun`, and a structure used for checking correctness
-pub mod check;
\ No newline at end of file
+pub mod check;
+/// Formatting of parse errors as `path:line:col: message`
+pub mod diagnostics;
+
+/// Sanity checks of grammars, reported with source locations (`validate`)
+pub mod validate;
@@ -13,16 +13,14 @@ use crate::{
     value::Type,
 };
 
-use super::{ioexamples::IOExamples, prod::ProdRule, config::{Config, self}};
+use super::{check::Sketch, ioexamples::IOExamples, prod::ProdRule, config::{Config, self}, spec::{self, Definition}};
 use derive_more::DebugCustom;
 
 pub type Error = pest::error::Error<Rule>;
 
 /// Creates a new custom error instance associated with a specific span. 
-
 pub fn new_custom_error_span<'i>(msg: String, span: Span<'i>) -> Error { Error::new_from_span(pest::error::ErrorVariant::CustomError { message: msg }, span) }
 /// Constructs and returns an error with a custom error message and position. 
-
 pub fn new_costom_error_pos<'i>(msg: String, pos: Position<'i>) -> Error { Error::new_from_pos(pest::error::ErrorVariant::CustomError { message: msg }, pos) }
 
 #[derive(DebugCustom, PartialEq, Eq, Hash, Clone)]
@@ -38,7 +36,6 @@ pub struct NonTerminal(pub String, pub Type, pub Vec<ProdRule>, pub Config);
 
 impl NonTerminal {
     /// Parses a `Pair` into a `NonTerminal`. 
-
     pub fn parse(pair: Pair<'_, Rule>) -> Result<NonTerminal, Error> {
         let mut vec = pair.into_inner().collect_vec();
         let config = vec.last().unwrap().clone();
@@ -95,7 +92,6 @@ impl Cfg {
         Ok(cfg)
     }
     /// This function resets the position of the start non-terminal in the control flow graph. 
-
     pub fn reset_start(&mut self) {
         let start_index = self.inner.iter().position(|x| x.0 == self.start).unwrap();
         let start_nt = self.inner.remove(start_index);
@@ -103,7 +99,6 @@ impl Cfg {
     }
     /// Retrieves the name of the non-terminal by type. 
     /// 
-
     pub fn get_nt_by_type(&self, ty: &Type) -> String {
         self.inner.iter().find_map(|x| (x.1 == *ty).then_some(x.0.clone())).unwrap()
     }
@@ -146,7 +141,6 @@ pub struct FunSig {
 
 impl FunSig {
     /// Returns the index of a named argument within the function signature's argument list. 
-
     pub fn index(&self, argname: &str) -> Option<usize> {
         self.args.iter().position(|x| x.0 == argname)
     }
@@ -167,7 +161,6 @@ pub struct SynthFun {
 
 impl SynthFun {
     /// Parses a `synthfun` rule from a given input and constructs a `SynthFun` instance. 
-
     pub fn parse(synthfun: Pair<'_, Rule>) -> Result<Self, Error> {
         let subproblem = synthfun.as_rule() == Rule::synthsubproblem;
         let [name, arglist, typ, cfg]: [_; 4] = synthfun.into_inner().collect_vec().try_into().unwrap();
@@ -183,7 +176,6 @@ impl SynthFun {
         Ok(Self{sig: FunSig{name: name.as_str().into(), args, rettype}, cfg, subproblem})
     }
     /// Lookup for a non-terminal within the synthesis function's configuration. 
-
     pub fn lookup_nt(&self, nt: &str) -> Option<usize> {
         self.cfg.inner.iter().find_position(|x| x.0.as_str() == nt).map(|x| x.0)
     }
@@ -203,9 +195,22 @@ impl Type {
     /// It then matches this symbol against several predefined strings representing basic types, converting it into the corresponding `Type` variant. 
     /// These types include `Int`, `String`, `Bool`, and `Float`. 
     /// If the string representation of the pair contains "List", the function attempts to convert the basic type into a list type using `to_list()`, returning an error if this is unsupported. 
+    /// Maps are only supported from strings to strings, written `(Map String String)`, and tuples only as pairs of strings, written `(Tuple String String)`.
     /// The function returns the parsed `Type` or an error if an unknown type is encountered.
     /// 
     pub fn parse(pair: Pair<'_, Rule>) -> Result<Self, Error> {
+        if pair.as_str().contains("Map") {
+            return match pair.clone().into_inner().map(|s| s.as_str()).collect_tuple() {
+                Some(("String", "String")) => Ok(Self::MapStr),
+                _ => Err(new_custom_error_span("Unsupported map type".into(), pair.as_span())),
+            };
+        }
+        if pair.as_str().contains("Tuple") {
+            return match pair.clone().into_inner().map(|s| s.as_str()).collect_tuple() {
+                Some(("String", "String")) => Ok(Self::Tuple),
+                _ => Err(new_custom_error_span("Unsupported tuple type".into(), pair.as_span())),
+            };
+        }
         let [symbol]: [_; 1] = pair.clone().into_inner().collect_vec().try_into().unwrap();
         if pair.as_str().contains("BitVec") {
             let b = symbol.as_str().parse::<usize>().map_err(|_| new_custom_error_span("Can not parse BitVec".into(), pair.as_span()))?;
@@ -216,7 +221,8 @@ impl Type {
             "String" => Self::Str,
             "Bool" => Self::Bool,
             "Float" => Self::Float,
-            _ => panic!("Unknown Type {}", symbol.as_str()),
+            "Char" => Self::Char,
+            _ => return Err(new_custom_error_span(format!("Unknown type {}", symbol.as_str()), symbol.as_span())),
         };
         if pair.as_str().contains("List") {
             basic.to_list().ok_or(new_custom_error_span("Unsupported list type".into(), pair.as_span()))
@@ -233,6 +239,16 @@ pub struct PBEProblem {
     pub synthfuns: Vec<SynthFun>,
     pub problem_index: usize,
     pub examples: IOExamples,
+    /// Universally quantified variables declared with `declare-var`.
+    pub vars: Vec<(String, Type)>,
+    /// Bodies of the `constraint`s that are not plain input-output examples, as SMT-LIB terms.
+    ///
+    /// Constraints over literals only are compiled to examples and do not appear here; other definitions of the function are also kept here after being instantiated on the examples (see [`super::spec::compile`]).
+    pub spec: Vec<String>,
+    /// Paths of the files included with `(include "...")`, whose grammars are merged by [`super::include::resolve`].
+    pub includes: Vec<String>,
+    /// Program skeleton given by a `define-fun` of the function with holes, if any.
+    pub sketch: Option<Sketch>,
 }
 
 impl PBEProblem {
@@ -254,25 +270,98 @@ impl PBEProblem {
     /// The synthesis examples are parsed to ensure they match the signature of the main synthesis function. 
     /// It constructs and returns a `PBEProblem` comprising the logic, a vector of synthesis functions, the index of the main problem, and the parsed examples. 
     /// The method will fail if the input does not conform to expected structures or logic, returning an error.
+    /// General `constraint`s are kept in `spec`; those defining the function by a term over the declared variables are also compiled to examples by instantiating them on the example points.
+    /// Commands outside of this subset of SyGuS-IF (e.g. `define-fun`) are reported as errors.
+    /// A `define-fun` of the function is read as a sketch, whose holes `(?? nt)` must name non-terminals of its grammar.
+    /// `(include "...")` directives, right after `set-logic`, are only recorded: the included files are read by [`super::include::resolve`].
+    /// `(define-scanner name "regex" Type)` declares a scanner, which grammars use as the operator `name` (see [`define_scanner`]).
     /// 
     pub fn parse(input: &str) -> Result<PBEProblem, Error> {
         let [file]: [_; 1] = ProblemParser::parse(Rule::file, input)?.collect_vec().try_into().unwrap();
-        let [_, logic, synthproblem, examples, checksynth]: [_; 5] = file.into_inner().collect_vec().try_into().unwrap();
+        let [_, logic, includes, synthproblem, examples, checksynth]: [_; 6] = file.into_inner().collect_vec().try_into().unwrap();
         let [logic]: [_; 1] = logic.into_inner().collect_vec().try_into().unwrap();
-        let synthfuns: Vec<_> = synthproblem.into_inner().enumerate().map(|(i, pair)| SynthFun::parse(pair)).collect::<Result<Vec<_>, _>>()?;
+        let includes = includes.into_inner().map(|include| {
+            let path = include.into_inner().next().unwrap().as_str();
+            path[1..(path.len() - 1)].to_owned()
+        }).collect_vec();
+        let synthfuns: Vec<_> = synthproblem.clone().into_inner().enumerate().map(|(i, pair)| SynthFun::parse(pair)).collect::<Result<Vec<_>, _>>()?;
         let vec = synthfuns.iter().enumerate().filter(|x| !x.1.subproblem).map(|i|i.0).collect_vec();
-        let problem_index = if let [a] = vec.as_slice() {*a} else { panic!("There should be only one synth-fun."); };
-        let examples = IOExamples::parse(examples, &synthfuns[problem_index].sig, true)?;
+        let problem_index = if let [a] = vec.as_slice() {*a} else {
+            return Err(new_custom_error_span("There should be only one synth-fun.".into(), synthproblem.as_span()));
+        };
+        let sig = &synthfuns[problem_index].sig;
+        let mut vars = Vec::new();
+        let mut spec = Vec::new();
+        let mut definitions = Vec::new();
+        let mut sketch = None;
+        for pair in examples.clone().into_inner() {
+            match pair.as_rule() {
+                Rule::definefun => {
+                    let span = pair.as_span();
+                    let s = Sketch::parse(pair)?;
+                    if sketch.is_some() {
+                        return Err(new_custom_error_span("There should be at most one sketch.".into(), span));
+                    }
+                    if s.definefun.sig.to_string() != sig.to_string() {
+                        return Err(new_custom_error_span(format!("The sketch should define {}", sig), span));
+                    }
+                    if let Some(nt) = s.holes.iter().find(|nt| synthfuns[problem_index].lookup_nt(nt).is_none()) {
+                        return Err(new_custom_error_span(format!("Unknown non-terminal {} in a hole", nt), span));
+                    }
+                    sketch = Some(s);
+                }
+                Rule::declvar => {
+                    let [name, typ]: [_; 2] = pair.into_inner().collect_vec().try_into().unwrap();
+                    vars.push((name.as_str().to_owned(), Type::parse(typ)?));
+                }
+                Rule::definescanner => define_scanner(pair)?,
+                Rule::spec => {
+                    let term = pair.clone().into_inner().next().unwrap();
+                    match Definition::parse(term.clone(), sig, &vars) {
+                        Some(d) if d.ground().is_some() => definitions.push((d, pair.as_span())),
+                        Some(d) => {
+                            definitions.push((d, pair.as_span()));
+                            spec.push(term.as_str().to_owned());
+                        }
+                        None => spec.push(term.as_str().to_owned()),
+                    }
+                }
+                Rule::unsupported => {
+                    let command = pair.clone().into_inner().next().unwrap();
+                    return Err(new_custom_error_span(format!("Unsupported command {}", command.as_str()), pair.as_span()));
+                }
+                _ => (),
+            }
+        }
+        let mut examples = IOExamples::parse(examples, sig, true)?;
+        spec::compile(&mut examples, &definitions, &vars, sig.rettype)?;
 
         Ok(PBEProblem {
             logic: logic.as_str().to_owned(),
             synthfuns,
             problem_index,
             examples,
+            vars,
+            spec,
+            includes,
+            sketch,
         })
     }
 }
 
+/// Declares the scanner of a `(define-scanner name "regex" Type)` command, reading `String`s if the type is omitted (see [`crate::text::parsing::define_scanner`]).
+///
+/// Scanners are declared globally, since grammars refer to them by name when their operators are built.
+pub fn define_scanner(pair: Pair<'_, Rule>) -> Result<(), Error> {
+    let span = pair.as_span();
+    let mut inner = pair.into_inner();
+    let name = inner.next().unwrap().as_str();
+    let regex = inner.next().unwrap().as_str();
+    let ty = inner.next().map(Type::parse).transpose()?.unwrap_or(Type::Str);
+    crate::text::parsing::define_scanner(name, &regex[1..(regex.len() - 1)], ty).map_err(|e| new_custom_error_span(e, span))?;
+    Ok(())
+}
+
 #[derive(pest_derive::Parser)]
 #[grammar = "src/parser/problem.pest"]
 /// A unit struct that serves as a parser for synthesis problems. 
@@ -294,4 +383,97 @@ mod tests {
         let result = PBEProblem::parse(s.as_str());
         println!("{:?}", result.map(|x| x.synthfun().cfg.clone()));
     }
+
+    #[test]
+    fn parse_spec_test() {
+        let s = r#"(set-logic SLIA)
+(synth-fun f ((name String)) String ((Start String (name "a" (str.++ Start Start)))))
+(declare-var x String)
+(constraint (= (f "b") "ba"))
+(constraint (= (f x) (str.++ x "a")))
+(check-synth)"#;
+        let problem = PBEProblem::parse(s).unwrap();
+        assert_eq!(problem.examples.len(), 1);
+        assert_eq!(problem.vars, vec![("x".to_string(), crate::value::Type::Str)]);
+        assert_eq!(problem.spec, vec![r#"(= (f x) (str.++ x "a"))"#.to_string()]);
+    }
+
+    #[test]
+    fn parse_negative_examples_test() {
+        let s = r#"(set-logic SLIA)
+(synth-fun f ((name String)) String ((Start String (name "a" (str.++ Start Start)))))
+(constraint (= (f "b") "ba"))
+(constraint (not (= (f "c") "c")))
+(constraint (not (= (f "d") "d")))
+(check-synth)"#;
+        let problem = PBEProblem::parse(s).unwrap();
+        assert_eq!(problem.examples.len(), 1);
+        assert_eq!(problem.examples.neg_len(), 2);
+        assert!(problem.spec.is_empty());
+        let ctx = crate::expr::context::Context::from_examples(&problem.examples);
+        assert!(ctx.matches_negative(&crate::expr!([0])));
+        assert!(!ctx.matches_negative(&crate::expr!(Concat [0] "a")));
+    }
+
+    #[test]
+    fn parse_char_test() {
+        let s = r#"(set-logic SLIA)
+(synth-fun f ((name String)) Bool ((Start Bool ((char.is_upper C))) (C Char ((char "x") (char.at name I) (char.to_upper C))) (I Int (0 1))))
+(constraint (= (f "Ab") true))
+(check-synth)"#;
+        let problem = PBEProblem::parse(s).unwrap();
+        assert_eq!(problem.synthfun().cfg.inner[1].1, crate::value::Type::Char);
+        assert!(PBEProblem::parse(&s.replace(r#"(char "x")"#, r#"(char "xy")"#)).is_err());
+    }
+
+    #[test]
+    fn parse_map_test() {
+        let s = r#"(set-logic SLIA)
+(synth-fun f ((name String)) String ((Start String ((map.lookup M name) name)) (M (Map String String) ((map ("CA" "California") ("NY" "New York"))))))
+(constraint (= (f "NY") "New York"))
+(check-synth)"#;
+        let problem = PBEProblem::parse(s).unwrap();
+        assert_eq!(problem.synthfun().cfg.inner[1].1, crate::value::Type::MapStr);
+        let m = problem.synthfun().cfg.inner[1].2[0].const_value().unwrap();
+        assert_eq!(m.as_map().unwrap(), &[("CA", "California"), ("NY", "New York")]);
+        assert_eq!(m.to_string(), r#"(map ("CA" "California") ("NY" "New York"))"#);
+    }
+
+    #[test]
+    fn parse_indexed_test() {
+        let s = r#"(set-logic BV)
+(synth-fun f ((x (_ BitVec 8))) (_ BitVec 4) ((Start (_ BitVec 4) (((_ extract 7 4) B) ((_ extract 3 0) B))) (B (_ BitVec 8) (x ((_ rotate_left 1) B) (bvxor B B)))))
+(constraint (= (f #xa5) #xa))
+(check-synth)"#;
+        let problem = PBEProblem::parse(s).unwrap();
+        let rule = &problem.synthfun().cfg.inner[0].2[0];
+        assert!(matches!(rule, super::ProdRule::Op1(op, _, _) if op == "extract"));
+        assert_eq!((rule.config().get_usize("hi"), rule.config().get_usize("lo")), (Some(7), Some(4)));
+        assert_eq!(problem.synthfun().cfg.inner[1].2[1].config().get_usize("n"), Some(1));
+    }
+
+    #[test]
+    fn parse_scanner_test() {
+        let s = r#"(set-logic SLIA)
+(synth-fun f ((name String)) Int ((Start Int ((problem_ticket S))) (S String (name))))
+(define-scanner problem_ticket "[A-Z]{2,3}-([0-9]+)" Int)
+(constraint (= (f "see AB-123") 123))
+(check-synth)"#;
+        let problem = PBEProblem::parse(s).unwrap();
+        let cfg = crate::expr::cfg::Cfg::from_synthfun(problem.synthfun());
+        assert!(matches!(cfg[0].rules[0], crate::expr::cfg::ProdRule::Op1(op, 1) if op.to_string() == "problem_ticket"));
+        assert!(PBEProblem::parse(&s.replace("[A-Z]{2,3}", "[A-Z")).is_err());
+    }
+
+    #[test]
+    fn parse_tuple_test() {
+        let s = r#"(set-logic SLIA)
+(synth-fun f ((name String)) (Tuple String String) ((Start (Tuple String String) ((tuple.make S S))) (S String (name " " (str.++ S S)))))
+(constraint (= (f "John Smith") (tuple "John" "Smith")))
+(check-synth)"#;
+        let problem = PBEProblem::parse(s).unwrap();
+        assert_eq!(problem.synthfun().sig.rettype, crate::value::Type::Tuple);
+        assert_eq!(problem.examples.output.get_const(0).as_tuple(), Some(("John", "Smith")));
+        assert!(PBEProblem::parse(&s.replacen("(Tuple String String)", "(Tuple String Int)", 1)).is_err());
+    }
 }
@@ -2,7 +2,7 @@ use itertools::Itertools;
 use pest::iterators::Pair;
 
 use crate::{
-    galloc::AllocForStr, utils::TryRetain, value::{ConstValue, Type}
+    galloc::{AllocForExactSizeIter, AllocForStr}, utils::TryRetain, value::{ConstValue, Type}
 };
 
 use super::{problem::{new_custom_error_span, Error, Rule}, config::Config};
@@ -27,7 +27,6 @@ pub enum ProdRule {
 
 impl ConstValue {
     /// Parses a `Pair` of `'_, Rule>` into a `ConstValue`, returning a result with either the parsed constant or an error. 
-
     pub fn parse(pair: Pair<'_, Rule>) -> Result<Self, Error> {
         let [value]: [_; 1] = pair.into_inner().collect_vec().try_into().unwrap();
         match value.as_rule() {
@@ -53,6 +52,27 @@ impl ConstValue {
                 Ok(Self::BitVector(s.len(), f))
             }
             Rule::strlit => Ok(Self::Str(value.as_str()[1..(value.as_str().len() - 1)].galloc_str())),
+            Rule::charlit => {
+                let [s]: [_; 1] = value.clone().into_inner().collect_vec().try_into().unwrap();
+                let mut chars = s.as_str()[1..(s.as_str().len() - 1)].chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Ok(Self::Char(c)),
+                    _ => Err(new_custom_error_span("Expecting a single character".into(), value.as_span())),
+                }
+            }
+            Rule::maplit => {
+                let unquote = |s: &str| s[1..(s.len() - 1)].galloc_str();
+                let entries = value.into_inner().map(|e| e.into_inner().map(|s| unquote(s.as_str())).collect_tuple().unwrap()).collect_vec();
+                Ok(Self::MapStr(entries.into_iter().galloc_scollect()))
+            }
+            Rule::tuplelit => {
+                let (a, b) = value.into_inner().map(|s| s.as_str()[1..(s.as_str().len() - 1)].galloc_str()).collect_tuple().unwrap();
+                Ok(Self::Tuple(a, b))
+            }
+            Rule::listlit => {
+                let elements = value.into_inner().map(|s| s.as_str()[1..(s.as_str().len() - 1)].galloc_str()).collect_vec();
+                Ok(Self::ListStr(elements.into_iter().galloc_scollect()))
+            }
             Rule::boollit => match value.as_str() {
                 "true" => Ok(Self::Bool(true)),
                 "false" => Ok(Self::Bool(false)),
@@ -64,17 +84,46 @@ impl ConstValue {
     }
 }
 
+/// Returns the name of the operator `op`, a symbol or an indexed identifier such as `(_ extract 7 4)`.
+///
+/// The indices of an indexed identifier are stored in `config`, as `#hi` and `#lo` for two indices and `#n` for one, where the operator reads them.
+pub fn operator_name(op: &Pair<'_, Rule>, config: &mut Config) -> Result<String, Error> {
+    if op.as_rule() != Rule::indexed { return Ok(op.as_str().into()); }
+    let mut inner = op.clone().into_inner();
+    let name = inner.next().unwrap().as_str().to_string();
+    let indices = inner.map(|i| i.as_str().parse::<i64>().map_err(|_| new_custom_error_span("Can not parse index".into(), i.as_span()))).collect::<Result<Vec<_>, _>>()?;
+    let keys: &[&str] = match indices.len() {
+        1 => &["n"],
+        2 => &["hi", "lo"],
+        _ => return Err(new_custom_error_span("Unsupported number of indices".into(), op.as_span())),
+    };
+    for (k, i) in keys.iter().zip(indices) {
+        config.set(k, ConstValue::Int(i));
+    }
+    Ok(name)
+}
+
 impl ProdRule {
     /// Returns the constant value associated with a specific production rule, if available. 
-
     pub fn const_value(&self) -> Option<&ConstValue> {
         match self {
             ProdRule::Const(i, _) => Some(i),
             _ => None,
         }
     }
+    /// Returns the configuration attached to the production rule.
+    pub fn config(&self) -> &Config {
+        match self {
+            ProdRule::Var(_, c) | ProdRule::Const(_, c) | ProdRule::Op1(_, _, c) | ProdRule::Op2(_, _, _, c) | ProdRule::Op3(_, _, _, _, c) => c,
+        }
+    }
+    /// Returns a mutable reference to the configuration attached to the production rule.
+    pub fn config_mut(&mut self) -> &mut Config {
+        match self {
+            ProdRule::Var(_, c) | ProdRule::Const(_, c) | ProdRule::Op1(_, _, c) | ProdRule::Op2(_, _, _, c) | ProdRule::Op3(_, _, _, _, c) => c,
+        }
+    }
     /// Parses a `Pair` object into a `ProdRule` variant. 
-
     pub fn parse(pair: Pair<'_, Rule>) -> Result<Self, Error> {
         let mut vec = pair.into_inner().collect_vec();
         let mut config = Config::new();
@@ -92,10 +141,11 @@ impl ProdRule {
                 _ => panic!("should not reach here"),
             }
         } else {
+            let op = operator_name(&vec[0], &mut config)?;
             match vec.as_slice() {
-                [op, a1] => Ok(Self::Op1(op.as_str().into(), a1.as_str().into(), config)),
-                [op, a1, a2] => Ok(Self::Op2(op.as_str().into(), a1.as_str().into(), a2.as_str().into(), config)),
-                [op, a1, a2, a3] => Ok(Self::Op3(op.as_str().into(), a1.as_str().into(), a2.as_str().into(), a3.as_str().into(), config)),
+                [_, a1] => Ok(Self::Op1(op, a1.as_str().into(), config)),
+                [_, a1, a2] => Ok(Self::Op2(op, a1.as_str().into(), a2.as_str().into(), config)),
+                [_, a1, a2, a3] => Ok(Self::Op3(op, a1.as_str().into(), a2.as_str().into(), a3.as_str().into(), config)),
                 _ => panic!("should not reach here"),
             }
         }
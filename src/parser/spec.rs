@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use pest::{iterators::Pair, Span};
+
+use crate::{expr::{context::Context, ops::{Neg, Op1Enum, Op2Enum, Op3Enum}, Expr}, galloc::AllocForAny, value::{ConstValue, Type, Value}};
+
+use super::{config::Config, ioexamples::IOExamples, problem::{new_custom_error_span, Error, FunSig, Rule}};
+
+#[derive(Debug, Clone, PartialEq)]
+/// An argument of the synthesized function in a constraint.
+enum Arg {
+    /// A literal of the type of the argument
+    Const(ConstValue),
+    /// A universally quantified variable, as an index in the declared variables
+    Var(usize),
+}
+
+#[derive(Debug, Clone)]
+/// A constraint `(= (f a1 ... an) t)`, or `(= t (f a1 ... an))`, defining the output of the synthesized function `f`.
+///
+/// Every argument `ai` is a literal or a declared variable, and `t` is a term over the declared variables that does not mention `f`.
+/// Instantiating the variables on a point, i.e. concrete arguments of `f`, gives the expected output there, so such constraints compile to input-output examples.
+pub struct Definition {
+    args: Vec<Arg>,
+    body: &'static Expr,
+}
+
+/// Returns the sub-terms of a `term` that is an application, or `None` for an atom.
+fn subterms<'i>(pair: &Pair<'i, Rule>) -> Option<Vec<Pair<'i, Rule>>> {
+    let inner = pair.clone().into_inner().collect_vec();
+    (inner.first()?.as_rule() == Rule::term).then_some(inner)
+}
+
+/// Converts a `term` over the declared variables `vars` into an expression, in which variable `i` stands for `vars[i]`.
+///
+/// Returns `None` if the term applies `f`, or uses a symbol or an operator that the synthesizer does not know.
+fn to_expr(pair: Pair<'_, Rule>, f: &str, vars: &[(String, Type)]) -> Option<&'static Expr> {
+    let Some(terms) = subterms(&pair) else {
+        let atom = pair.into_inner().next()?;
+        return match atom.as_rule() {
+            Rule::value => Some(Expr::Const(ConstValue::parse(atom).ok()?).galloc()),
+            _ => vars.iter().position(|(v, _)| v == atom.as_str()).map(|i| Expr::Var(i as _).galloc()),
+        };
+    };
+    let head = terms[0].as_str();
+    if head == f { return None; }
+    let args: Vec<_> = terms[1..].iter().map(|t| to_expr(t.clone(), f, vars)).collect::<Option<_>>()?;
+    let config = Config::new();
+    match args.as_slice() {
+        [a1] if head == "-" => Some(Expr::Op1(Op1Enum::from(Neg::default()).galloc(), a1).galloc()),
+        [a1] => Some(Expr::Op1(Op1Enum::try_from_name(head, &config)?.galloc(), a1).galloc()),
+        [a1, a2] => Some(Expr::Op2(Op2Enum::try_from_name(head, &config)?.galloc(), a1, a2).galloc()),
+        [a1, a2, a3] => Some(Expr::Op3(Op3Enum::try_from_name(head, &config)?.galloc(), a1, a2, a3).galloc()),
+        _ => None,
+    }
+}
+
+/// Evaluates `e` on the single example binding variable `i` to `inputs[i]`, if it produces a value of type `ty`.
+fn eval_const(e: &Expr, inputs: Vec<Value>, ty: Type) -> Option<ConstValue> {
+    let v = e.eval(&Context::new(1, inputs, vec![], Value::Null));
+    (v.ty() == ty && v.len() == 1 && !matches!(v, Value::ListInt(_) | Value::ListStr(_))).then(|| v.get_const(0))
+}
+
+impl Definition {
+    /// Recognizes a definition in the body `term` of a `constraint` on the function of `sig`.
+    ///
+    /// Returns `None` if the constraint has another shape (e.g. several applications of `f`, or a relation other than `=`), or if an argument is ill-typed.
+    pub fn parse(term: Pair<'_, Rule>, sig: &FunSig, vars: &[(String, Type)]) -> Option<Self> {
+        let [eq, lhs, rhs]: [_; 3] = subterms(&term)?.try_into().ok()?;
+        if eq.as_str() != "=" { return None; }
+        let is_app = |t: &Pair<'_, Rule>| subterms(t).is_some_and(|ts| ts[0].as_str() == sig.name);
+        let (app, body) = if is_app(&lhs) { (lhs, rhs) } else if is_app(&rhs) { (rhs, lhs) } else { return None };
+        let terms = subterms(&app)?;
+        if terms.len() != sig.args.len() + 1 { return None; }
+        let args = terms[1..].iter().zip(sig.args.iter()).map(|(t, (_, ty))| {
+            match to_expr(t.clone(), &sig.name, vars)? {
+                Expr::Var(i) if vars[*i as usize].1 == *ty => Some(Arg::Var(*i as usize)),
+                e if !e.has_var() => eval_const(e, Vec::new(), *ty).map(Arg::Const),
+                _ => None,
+            }
+        }).collect::<Option<Vec<_>>>()?;
+        let body = to_expr(body, &sig.name, vars)?;
+        Some(Self { args, body })
+    }
+    /// Returns the arguments if they are all literals, i.e. if the constraint is an example.
+    pub fn ground(&self) -> Option<Vec<ConstValue>> {
+        self.args.iter().map(|a| if let Arg::Const(c) = a { Some(*c) } else { None }).collect()
+    }
+    /// Returns the output required at `point`, or `None` if the constraint does not apply to it.
+    ///
+    /// The constraint applies if its literal arguments are equal to the point, and its variables can be bound consistently.
+    /// Variables that do not occur as arguments of `f` are left unbound, so constraints using them are never instantiated.
+    pub fn instantiate(&self, point: &[ConstValue], vars: &[(String, Type)], rettype: Type) -> Option<ConstValue> {
+        let mut binding = vec![None; vars.len()];
+        for (a, c) in self.args.iter().zip(point.iter()) {
+            match a {
+                Arg::Const(k) if k != c => return None,
+                Arg::Var(i) => match binding[*i] {
+                    Some(b) if b != *c => return None,
+                    _ => binding[*i] = Some(*c),
+                },
+                _ => (),
+            }
+        }
+        let inputs = binding.into_iter().zip(vars.iter()).map(|(b, (_, ty))| b.map(|c| Value::from_const(*ty, std::iter::once(c)))).collect::<Option<Vec<_>>>()?;
+        eval_const(self.body, inputs, rettype)
+    }
+}
+
+/// Compiles definitions to examples, by instantiating them on the example points: the inputs of the positive and negative examples, and the arguments of the ground definitions.
+///
+/// A definition contradicting an example, or another definition, is reported as an error on the span of its constraint.
+pub fn compile(examples: &mut IOExamples, definitions: &[(Definition, Span<'_>)], vars: &[(String, Type)], rettype: Type) -> Result<(), Error> {
+    let rows = |inputs: &[Value], len: usize| (0..len).map(|i| inputs.iter().map(|v| v.get_const(i)).collect_vec()).collect_vec();
+    let positive = rows(&examples.inputs, examples.len());
+    let mut known: HashMap<Vec<ConstValue>, ConstValue> = positive.iter().cloned().enumerate().map(|(i, p)| (p, examples.output.get_const(i))).collect();
+    let forbidden = rows(&examples.neg_inputs, examples.neg_len()).into_iter().enumerate().map(|(i, p)| (p, examples.neg_output.get_const(i))).collect_vec();
+    let points = positive.into_iter()
+        .chain(forbidden.iter().map(|(p, _)| p.clone()))
+        .chain(definitions.iter().filter_map(|(d, _)| d.ground()))
+        .unique().collect_vec();
+    for point in points {
+        for (d, span) in definitions {
+            let Some(output) = d.instantiate(&point, vars, rettype) else { continue };
+            if let Some(o) = known.get(&point).filter(|o| **o != output) {
+                return Err(new_custom_error_span(format!("Constraint contradicts the example {:?} -> {:?}", point, o), *span));
+            }
+            if forbidden.iter().any(|(p, o)| *p == point && *o == output) {
+                return Err(new_custom_error_span(format!("Constraint contradicts a negative example on {:?}", point), *span));
+            }
+            if known.insert(point.clone(), output).is_none() {
+                examples.add_example(&point, output);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use crate::parser::problem::PBEProblem;
+
+    #[test]
+    fn test_compile() {
+        let s = r#"(set-logic SLIA)
+(synth-fun f ((name String)) String ((Start String (name "a" (str.++ Start Start)))))
+(declare-var x String)
+(constraint (= (f "b") "ba"))
+(constraint (= (str.++ "c" "a") (f "c")))
+(constraint (not (= (f "d") "d")))
+(constraint (= (f x) (str.++ x "a")))
+(check-synth)"#;
+        let problem = PBEProblem::parse(s).unwrap();
+        assert_eq!(problem.examples.len(), 3);
+        assert_eq!(problem.examples.output.to_str().iter().sorted().collect_vec(), vec![&"ba", &"ca", &"da"]);
+        assert_eq!(problem.spec, vec![r#"(= (f x) (str.++ x "a"))"#.to_string()]);
+
+        let contradiction = s.replace(r#"(f "b") "ba""#, r#"(f "b") "bb""#);
+        assert!(PBEProblem::parse(&contradiction).is_err());
+        let unsupported = s.replace("(declare-var x String)", "(declare-var x String)\n(define-fun g ((y String)) String y)");
+        assert!(PBEProblem::parse(&unsupported).unwrap_err().to_string().contains("define-fun"));
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::{HashMap, HashSet};
+
+use derive_more::Display;
+use itertools::Itertools;
+use pest::{iterators::Pair, Parser, Span};
+
+use crate::{expr::ops::{Op1, Op1Enum, Op2, Op2Enum, Op3, Op3Enum}, value::{ConstValue, Type, Value}};
+
+use super::{config::Config, diagnostics::{format_message, message}, problem::{define_scanner, new_custom_error_span, Error, ProblemParser, Rule}, prod::operator_name};
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+/// Severity of a diagnostic: errors make the grammar unusable, warnings point at rules that can never be used.
+pub enum Severity {
+    #[display(fmt = "error")]
+    Error,
+    #[display(fmt = "warning")]
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+/// A problem found in a grammar, located in the source of the problem file.
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub error: Error,
+}
+
+impl Diagnostic {
+    fn new(severity: Severity, message: String, span: Span<'_>) -> Self {
+        Self { severity, error: new_custom_error_span(message, span) }
+    }
+    /// Formats the diagnostic as `path:line:col: error: message`, followed by the offending line if `snippet` is set.
+    pub fn format(&self, path: &str, snippet: bool) -> String {
+        format_message(&self.error, path, &format!("{}: {}", self.severity, message(&self.error)), snippet)
+    }
+}
+
+/// A non-terminal of a grammar, with the spans of its rules.
+struct Nt<'i> {
+    span: Span<'i>,
+    ty: Type,
+    rules: Vec<Pair<'i, Rule>>,
+}
+
+/// Operators evaluating a function given in their configuration (e.g. `#f` of `list.map`), which cannot be type-checked without it.
+const HIGHER_ORDER: &[&str] = &["list.map", "list.filter", "list.foldl", "list.zip_with"];
+
+/// A value of type `ty` without any example.
+pub(crate) fn empty(ty: Type) -> Value {
+    match ty {
+        Type::ListInt => Value::ListInt(&[]),
+        Type::ListStr => Value::ListStr(&[]),
+        Type::Null => Value::Null,
+        ty => Value::from_const(ty, std::iter::empty()),
+    }
+}
+
+/// Returns the type of the result of operator `name` applied to arguments of types `args`, `Err(false)` for an unknown operator, and `Err(true)` if the operator does not apply to these types.
+/// The result of a higher-order operator is not known, and is returned as `Ok(None)`.
+///
+/// The operator is evaluated on values without any example, which is enough to select the case of its semantics matching the types.
+fn result_type(name: &str, config: &Config, args: &[Type]) -> Result<Option<Type>, bool> {
+    let (ok, v) = match *args {
+        [t1] => {
+            let op = Op1Enum::try_from_name(name, config).ok_or(false)?;
+            if HIGHER_ORDER.contains(&name) { return Ok(None); }
+            op.try_eval(empty(t1))
+        }
+        [t1, t2] => {
+            let op = Op2Enum::try_from_name(name, config).ok_or(false)?;
+            if HIGHER_ORDER.contains(&name) { return Ok(None); }
+            op.try_eval(empty(t1), empty(t2))
+        }
+        [t1, t2, t3] => Op3Enum::try_from_name(name, config).ok_or(false)?.try_eval(empty(t1), empty(t2), empty(t3)),
+        _ => return Err(false),
+    };
+    if ok { Ok(Some(v.ty())) } else { Err(true) }
+}
+
+/// Checks the grammars of the `synth-fun`s of a problem, returning the diagnostics in the order of the source.
+///
+/// Errors are reported for symbols that are neither a non-terminal nor an argument, unknown operators, operators that do not apply to the types of their arguments or produce another type than their non-terminal, and literals of the wrong type.
+/// Warnings are reported for non-terminals that cannot be reached from the start symbol, and for non-terminals and rules that cannot produce any term (e.g. rules only referring to their own non-terminal).
+/// A syntax error of the file is returned as an error, as the problem cannot be parsed at all.
+pub fn validate(input: &str) -> Result<Vec<Diagnostic>, Error> {
+    let [file]: [_; 1] = ProblemParser::parse(Rule::file, input)?.collect_vec().try_into().unwrap();
+    let [_, _, _, synthproblem, examples, _]: [_; 6] = file.into_inner().collect_vec().try_into().unwrap();
+    // Scanners are operators of the grammars, so they are declared first.
+    for pair in examples.into_inner().filter(|p| p.as_rule() == Rule::definescanner) {
+        define_scanner(pair)?;
+    }
+    let mut result = Vec::new();
+    for synthfun in synthproblem.into_inner() {
+        result.append(&mut validate_synthfun(synthfun)?);
+    }
+    result.sort_by_key(|d| match d.error.line_col {
+        pest::error::LineColLocation::Pos(p) | pest::error::LineColLocation::Span(p, _) => p,
+    });
+    Ok(result)
+}
+
+/// Checks the grammar of a single `synth-fun` or `synth-subproblem`.
+fn validate_synthfun(synthfun: Pair<'_, Rule>) -> Result<Vec<Diagnostic>, Error> {
+    let [_, arglist, _, cfg]: [_; 4] = synthfun.into_inner().collect_vec().try_into().unwrap();
+    let args: HashMap<&str, Type> = arglist.into_inner().map(|arg| {
+        let [name, typ]: [_; 2] = arg.into_inner().collect_vec().try_into().unwrap();
+        Ok::<_, Error>((name.as_str(), Type::parse(typ)?))
+    }).try_collect()?;
+    let mut nts: Vec<(&str, Nt<'_>)> = Vec::new();
+    for nt in cfg.into_inner().filter(|p| p.as_rule() == Rule::nt) {
+        let span = nt.as_span();
+        let mut inner = nt.into_inner();
+        let (name, typ, prods) = (inner.next().unwrap(), inner.next().unwrap(), inner.next().unwrap());
+        nts.push((name.as_str(), Nt { span, ty: Type::parse(typ)?, rules: prods.into_inner().collect() }));
+    }
+    let index: HashMap<&str, usize> = nts.iter().enumerate().map(|(i, (name, _))| (*name, i)).collect();
+
+    let mut result = Vec::new();
+    let mut error = |message: String, span: Span<'_>| result.push(Diagnostic::new(Severity::Error, message, span));
+    // Non-terminals referred to by each rule, `None` for an invalid rule.
+    let mut edges: Vec<Vec<Option<Vec<usize>>>> = Vec::new();
+    for (name, nt) in nts.iter() {
+        let mut rule_edges = Vec::new();
+        for prod in nt.rules.iter() {
+            let span = prod.as_span();
+            let mut config = Config::new();
+            let mut items = Vec::new();
+            for p in prod.clone().into_inner() {
+                if p.as_rule() == Rule::config { config.merge(Config::parse(p)?); } else { items.push(p); }
+            }
+            if let [item] = items.as_slice() {
+                match item.as_rule() {
+                    Rule::value => {
+                        let c = ConstValue::parse(item.clone())?;
+                        if c.ty() != nt.ty { error(format!("Literal {} of type {:?} in non-terminal {} of type {:?}", item.as_str(), c.ty(), name, nt.ty), span); }
+                        rule_edges.push(Some(Vec::new()));
+                    }
+                    // Arguments shadow non-terminals, as in `cfg::ProdRule::new`.
+                    _ => match (args.get(item.as_str()), index.get(item.as_str())) {
+                        (Some(ty), _) => {
+                            if *ty != nt.ty { error(format!("Argument {} of type {:?} used in non-terminal {} of type {:?}", item.as_str(), ty, name, nt.ty), span); }
+                            rule_edges.push(Some(Vec::new()));
+                        }
+                        (None, Some(i)) if nts[*i].1.ty == nt.ty => rule_edges.push(Some(vec![*i])),
+                        (None, Some(i)) => {
+                            error(format!("Non-terminal {} of type {:?} used in non-terminal {} of type {:?}", item.as_str(), nts[*i].1.ty, name, nt.ty), span);
+                            rule_edges.push(None);
+                        }
+                        (None, None) => {
+                            error(format!("Unknown symbol {}: neither a non-terminal nor an argument", item.as_str()), span);
+                            rule_edges.push(None);
+                        }
+                    },
+                }
+                continue;
+            }
+            let op = operator_name(&items[0], &mut config)?;
+            let operands = items[1..].iter().map(|a| index.get(a.as_str()).copied().ok_or(a)).collect_vec();
+            let mut valid = true;
+            for a in operands.iter().filter_map(|a| a.err()) {
+                error(format!("Unknown non-terminal {} (operands of {} should be non-terminals)", a.as_str(), op), a.as_span());
+                valid = false;
+            }
+            if !valid { rule_edges.push(None); continue; }
+            let operands = operands.into_iter().map(|a| a.unwrap()).collect_vec();
+            let types = operands.iter().map(|i| nts[*i].1.ty).collect_vec();
+            match result_type(&op, &config, &types) {
+                Err(false) if !(1..=3).contains(&types.len()) => error(format!("Unsupported number of arguments of {}: {}", op, types.len()), span),
+                Err(false) => error(format!("Unknown operator {} with {} argument(s)", op, types.len()), items[0].as_span()),
+                Err(true) => error(format!("Operator {} does not apply to arguments of types {:?}", op, types), span),
+                Ok(Some(ty)) if ty != nt.ty => error(format!("Operator {} produces {:?}, but non-terminal {} has type {:?}", op, ty, name, nt.ty), span),
+                Ok(_) => { rule_edges.push(Some(operands)); continue; }
+            }
+            rule_edges.push(None);
+        }
+        edges.push(rule_edges);
+    }
+
+    let mut reachable = HashSet::from([0]);
+    let mut stack = vec![0];
+    while let Some(i) = stack.pop() {
+        for j in edges[i].iter().flatten().flatten() {
+            if reachable.insert(*j) { stack.push(*j); }
+        }
+    }
+    let mut productive = HashSet::new();
+    while let Some(i) = (0..nts.len()).find(|i| !productive.contains(i) && edges[*i].iter().flatten().any(|e| e.iter().all(|j| productive.contains(j)))) {
+        productive.insert(i);
+    }
+    for (i, (name, nt)) in nts.iter().enumerate() {
+        if !reachable.contains(&i) {
+            result.push(Diagnostic::new(Severity::Warning, format!("Non-terminal {} is unreachable from the start symbol", name), nt.span));
+        } else if !productive.contains(&i) {
+            result.push(Diagnostic::new(Severity::Warning, format!("Non-terminal {} cannot produce any term", name), nt.span));
+        } else {
+            for (prod, e) in nt.rules.iter().zip(edges[i].iter()) {
+                if e.as_ref().is_some_and(|e| e.iter().any(|j| !productive.contains(j))) {
+                    result.push(Diagnostic::new(Severity::Warning, "Rule cannot produce any term".into(), prod.as_span()));
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate, Severity};
+
+    #[test]
+    fn test_validate() {
+        let s = r#"(set-logic SLIA)
+(synth-fun f ((name String)) String ((Start String (name "a" (str.++ Start Start) (str.++ Start L))) (I Int (0 (str.len Start))) (L String ((str.++ L L)))))
+(constraint (= (f "b") "ba"))
+(check-synth)"#;
+        let diagnostics = validate(s).unwrap();
+        assert_eq!(diagnostics.iter().map(|d| d.severity).collect::<Vec<_>>(), vec![Severity::Warning, Severity::Warning, Severity::Warning]);
+        assert!(diagnostics[0].format("f.sl", false).contains("Rule cannot produce any term"));
+        assert!(diagnostics[1].format("f.sl", false).contains("Non-terminal I is unreachable"));
+        assert!(diagnostics[2].format("f.sl", false).contains("Non-terminal L cannot produce any term"));
+
+        let s = s.replace("(str.len Start)", "(str.length Start)").replace(r#""a""#, "1").replace("(str.++ Start L)", "(str.at Start Start)").replace("(str.++ L L)", "(str.++ X L)");
+        let messages = validate(&s).unwrap().into_iter().filter(|d| d.severity == Severity::Error).map(|d| d.format("f.sl", false)).collect::<Vec<_>>();
+        assert_eq!(messages.len(), 4);
+        assert!(messages[0].contains("Literal 1 of type Int"));
+        assert!(messages[1].contains("Operator str.at does not apply to arguments of types [String, String]"));
+        assert!(messages[2].contains("Unknown operator str.length"));
+        assert!(messages[3].contains("Unknown non-terminal X"));
+        assert!(messages[3].starts_with("f.sl:2:"));
+        assert!(messages[3].contains(": error: Unknown non-terminal X"));
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{expr::{cfg::Cfg, context::Context}, parser::problem::PBEProblem, text};
+
+#[derive(Debug)]
+/// A built-in enrichment profile: a default grammar (in enriched SyGuS-IF) merged into the grammar of problems solved without `--cfg`.
+pub struct Profile {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub sygus_if: &'static str,
+    /// Whether the rules of scanners which recognize nothing in the examples are removed (see `text::parsing::prune_undetected`).
+    pub prune_undetected: bool,
+}
+
+/// The built-in profiles, selected with `--profile <name>`. Further profiles are registered by adding them to this list, with their grammar embedded by `include_str!`.
+pub static PROFILES: &[Profile] = &[
+    Profile {
+        name: "text",
+        description: "Text objects (dates, times, months, numbers, amounts, sizes...) with their scanners and formatters",
+        sygus_if: include_str!("../test/test.sl"),
+        prune_undetected: true,
+    },
+    Profile {
+        name: "tabular",
+        description: "Splitting, mapping and filtering delimited fields",
+        sygus_if: include_str!("../test/test2map.sl"),
+        prune_undetected: false,
+    },
+    Profile {
+        name: "numeric",
+        description: "Integer and decimal arithmetic, with number, amount and size formats",
+        sygus_if: include_str!("../test/numeric.sl"),
+        prune_undetected: false,
+    },
+];
+
+impl Profile {
+    /// Merges the grammar of the profile into `cfg`, pruning the scanners that recognize nothing in the examples of `ctx` if the profile asks for it.
+    pub fn apply(&self, cfg: Cfg, ctx: &Context) -> Cfg {
+        let mut cfg = crate::enrich_configuration(&PBEProblem::parse(self.sygus_if).unwrap(), cfg);
+        if self.prune_undetected {
+            text::parsing::prune_undetected(&mut cfg, ctx);
+        }
+        cfg
+    }
+}
+
+/// Returns the built-in profile called `name`.
+pub fn get(name: &str) -> Option<&'static Profile> {
+    PROFILES.iter().find(|p| p.name == name)
+}
+
+#[derive(Debug, Clone)]
+/// Profile chosen on the command line: a built-in one by name, or `custom:<path>` for an enriched SyGuS-IF file.
+pub enum ProfileSpec {
+    Builtin(&'static Profile),
+    Custom(String),
+}
+
+impl ProfileSpec {
+    /// Parses `text`, `tabular`, `numeric` (or another registered name) or `custom:<path>`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        if let Some(path) = s.strip_prefix("custom:") {
+            return Ok(Self::Custom(path.to_string()));
+        }
+        get(s).map(Self::Builtin).ok_or_else(|| {
+            format!("unknown profile {:?}, expected one of {} or custom:<path>", s, PROFILES.iter().map(|p| p.name).collect::<Vec<_>>().join(", "))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr::{cfg::{Cfg, ProdRule}, context::Context}, parser::problem::PBEProblem, value::Type};
+
+    use super::{get, ProfileSpec, PROFILES};
+
+    #[test]
+    fn test_profiles() {
+        for p in PROFILES {
+            assert!(PBEProblem::parse(p.sygus_if).is_ok(), "profile {}", p.name);
+        }
+        assert!(matches!(ProfileSpec::parse("numeric"), Ok(ProfileSpec::Builtin(p)) if p.name == "numeric"));
+        assert!(matches!(ProfileSpec::parse("custom:my.sl"), Ok(ProfileSpec::Custom(p)) if p == "my.sl"));
+        assert!(ProfileSpec::parse("dates").unwrap_err().contains("text, tabular, numeric"));
+    }
+
+    #[test]
+    fn test_mixed_arguments() {
+        let problem = PBEProblem::parse_json(r#"{"inputs": [[3, "a", true], [4, "b", false]], "outputs": ["a3", "b"]}"#).unwrap();
+        let cfg = get("tabular").unwrap().apply(Cfg::from_synthfun(problem.synthfun()), &Context::from_examples(&problem.examples));
+        let vars = |ty| cfg[cfg.find_by_type(ty).unwrap()].rules.iter().filter_map(|r| if let ProdRule::Var(v) = r { Some(*v) } else { None }).collect::<Vec<_>>();
+        assert_eq!((vars(Type::Str), vars(Type::Int), vars(Type::Bool)), (vec![1], vec![0], vec![2]));
+    }
+}
@@ -0,0 +1,96 @@
+use std::{collections::HashMap, fmt::Write, sync::{Mutex, OnceLock}};
+
+use crate::expr::Expr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The mechanism which created an expression.
+pub enum Provenance {
+    /// Forward enumeration, at the given size.
+    Enumerated(usize),
+    /// A deduction rule of a deducer, e.g. `StrDeducer::trim`, or `witness` for the witness functions of the operators.
+    Deduced(&'static str),
+    /// A formatter deduced from the outputs, by the deducer of its non-terminal.
+    Formatter(&'static str),
+    /// The decision tree learned over the partial solutions.
+    TreeLearning,
+}
+
+impl std::fmt::Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Provenance::Enumerated(size) => write!(f, "enumerated at size {size}"),
+            Provenance::Deduced(rule) => write!(f, "{rule}"),
+            Provenance::Formatter(name) => write!(f, "formatter {name}"),
+            Provenance::TreeLearning => write!(f, "tree_learning ite"),
+        }
+    }
+}
+
+/// Provenance of the expressions created since [`enable`], by address. Nothing is recorded while it is not set.
+static PROVENANCE: OnceLock<Mutex<HashMap<usize, Provenance>>> = OnceLock::new();
+
+/// Starts recording the provenance of expressions (`--explain`). Recording costs a lock per expression, so it is off by default.
+pub fn enable() {
+    let _ = PROVENANCE.set(Mutex::new(HashMap::new()));
+}
+
+/// Records that `e` was created by `p`, keeping the first mechanism recorded for it.
+#[inline]
+pub fn record(e: &'static Expr, p: Provenance) -> &'static Expr {
+    if let Some(map) = PROVENANCE.get() {
+        map.lock().unwrap().entry(e as *const Expr as usize).or_insert(p);
+    }
+    e
+}
+
+/// Returns the recorded provenance of `e`.
+pub fn get(e: &'static Expr) -> Option<Provenance> {
+    PROVENANCE.get()?.lock().unwrap().get(&(e as *const Expr as usize)).copied()
+}
+
+/// Prints `e` as an indented tree, one node per line, each annotated with the mechanism which produced it.
+/// Subexpressions without a recorded provenance (e.g. the constants and variables of the grammar) are left unannotated.
+pub fn annotate(e: &'static Expr) -> String {
+    let mut result = String::new();
+    annotate_rec(e, 0, &mut result);
+    result
+}
+
+fn annotate_rec(e: &'static Expr, depth: usize, result: &mut String) {
+    let (head, children): (String, Vec<&'static Expr>) = match e {
+        Expr::Const(c) => (format!("{c:?}"), vec![]),
+        Expr::Var(v) => (format!("<{v}>"), vec![]),
+        Expr::Op1(op, a) => (op.name().to_string(), vec![*a]),
+        Expr::Op2(op, a, b) => (op.name().to_string(), vec![*a, *b]),
+        Expr::Op3(op, a, b, c) => (op.name().to_string(), vec![*a, *b, *c]),
+        Expr::Let(v, a, b) => (format!("let <{v}>"), vec![*a, *b]),
+    };
+    let _ = write!(result, "{}{head}", "  ".repeat(depth));
+    if let Some(p) = get(e) {
+        let _ = write!(result, "  [{p}]");
+    }
+    result.push('\n');
+    // Subterms of an enumerated term were enumerated before it, so their sizes add nothing.
+    if matches!(get(e), Some(Provenance::Enumerated(_))) { return; }
+    for c in children {
+        annotate_rec(c, depth + 1, result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr, expr::Expr, galloc::AllocForAny};
+
+    use super::{annotate, enable, record, Provenance};
+
+    #[test]
+    fn test_annotate() {
+        enable();
+        let left = record(expr!(Concat [0] "-").galloc(), Provenance::Enumerated(2));
+        let right = record(expr!(Concat [1] ".").galloc(), Provenance::Enumerated(3));
+        let e = record(expr!(Concat {left} {right}).galloc(), Provenance::Deduced("witness"));
+        assert_eq!(annotate(e), "str.++  [witness]\n  str.++  [enumerated at size 2]\n  str.++  [enumerated at size 3]\n");
+        let leaf: &'static Expr = expr!([0]).galloc();
+        assert_eq!(annotate(leaf), "<0>\n");
+    }
+}
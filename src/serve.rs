@@ -0,0 +1,193 @@
+use std::{cmp::min, io::{self, BufRead, Write}, time::Duration};
+
+use itertools::Itertools;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{emit, expr::{cfg::Cfg, context::Context}, forward::bridge, info, parser::{ioexamples::IOExamples, json::to_const, problem::FunSig}, solutions::{Solutions, CONDITIONS}, tree_learning::bits::BoxSliceExt, value::{ConstValue, Type}};
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+/// A request of the server mode, one JSON object per line.
+///
+/// * `{"cmd": "add_example", "input": ["John", 3], "output": "J3"}` (`input` may be a single value for one-argument functions)
+/// * `{"cmd": "solve", "timeout": 10}` (`timeout` in seconds is optional)
+/// * `{"cmd": "reset"}`
+pub enum Request {
+    AddExample { input: serde_json::Value, output: serde_json::Value },
+    Solve { #[serde(default)] timeout: Option<u64> },
+    Reset,
+}
+
+/// Incremental solving server, answering each [`Request`] with one JSON line.
+///
+/// The `Solutions` of the last `solve` are kept between requests: when an example is added, the solutions and conditions found so far are re-evaluated on the extended examples instead of being rediscovered, and a following `solve` only searches for what they no longer cover.
+/// The terms up to size `bank` are enumerated once, on the examples of the first `solve`, and shared with the executors of all later requests (see [`bridge::share_bank`](crate::forward::bridge::share_bank)): those of example sets left unchanged by the added examples replay them with their values instead of enumerating them again.
+pub struct Server {
+    sig: FunSig,
+    cfg: Cfg,
+    initial: IOExamples,
+    examples: IOExamples,
+    solutions: Option<Solutions>,
+    nthread: usize,
+    target: emit::Target,
+    timeout: Option<u64>,
+    bank: usize,
+}
+
+/// Size of the terms kept warm across requests, unless set with `--shared-bank`.
+pub const BANK_SIZE: usize = 4;
+
+/// Converts a JSON value into a constant of type `ty`; integers are accepted for floats.
+fn typed_const(v: &serde_json::Value, ty: Type) -> Result<ConstValue, String> {
+    match (to_const(v).map_err(|e| e.to_string())?, ty) {
+        (ConstValue::Int(i), Type::Float) => Ok(ConstValue::Float((i as f64).into())),
+        (c, ty) if c.ty() == ty => Ok(c),
+        (_, ty) => Err(format!("Expecting {:?}, found {}", ty, v)),
+    }
+}
+
+impl Server {
+    /// Creates a server for the problem with signature `sig`, starting from the given examples (which `reset` goes back to).
+    pub fn new(sig: FunSig, cfg: Cfg, examples: IOExamples, nthread: usize, target: emit::Target, timeout: Option<u64>, bank: usize) -> Self {
+        Self { sig, cfg, initial: examples.clone(), examples, solutions: None, nthread, target, timeout, bank }
+    }
+
+    /// Reads requests from `input` line by line until end of file, writing one response line for each to `output`.
+    pub async fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() { continue; }
+            let response = self.handle(&line).await;
+            writeln!(output, "{}", response)?;
+            output.flush()?;
+        }
+        self.reset();
+        Ok(())
+    }
+
+    /// Handles a single request line and returns the response.
+    pub async fn handle(&mut self, line: &str) -> serde_json::Value {
+        let result = match serde_json::from_str::<Request>(line) {
+            Ok(Request::AddExample { input, output }) => self.add_example(&input, &output).map(|n| json!({"ok": true, "examples": n})),
+            Ok(Request::Solve { timeout }) => self.solve(timeout.or(self.timeout)).await,
+            Ok(Request::Reset) => {
+                self.reset();
+                Ok(json!({"ok": true, "examples": self.examples.len()}))
+            }
+            Err(e) => Err(format!("Invalid request: {e}")),
+        };
+        result.unwrap_or_else(|e| json!({"ok": false, "error": e}))
+    }
+
+    /// Adds one example and returns the new number of examples.
+    fn add_example(&mut self, input: &serde_json::Value, output: &serde_json::Value) -> Result<usize, String> {
+        let input = match input {
+            serde_json::Value::Array(args) => args.iter().collect_vec(),
+            v => vec![v],
+        };
+        if input.len() != self.sig.args.len() {
+            return Err(format!("Expecting {} arguments, found {}", self.sig.args.len(), input.len()));
+        }
+        let input: Vec<_> = input.into_iter().zip(self.sig.args.iter()).map(|(v, (_, ty))| typed_const(v, *ty)).try_collect()?;
+        let output = typed_const(output, self.sig.rettype)?;
+        self.examples.add_example(&input, output);
+        if let Some(solutions) = self.solutions.as_mut() {
+            solutions.extend_examples(Context::from_examples(&self.examples));
+        }
+        Ok(self.examples.len())
+    }
+
+    /// Solves the current examples, resuming from the solutions of previous requests.
+    async fn solve(&mut self, timeout: Option<u64>) -> Result<serde_json::Value, String> {
+        if self.examples.len() == 0 {
+            return Err("No examples".into());
+        }
+        let ctx = Context::from_examples(&self.examples);
+        let len = ctx.len as u32;
+        let solutions = self.solutions.get_or_insert_with(|| {
+            let solutions = Solutions::new(self.cfg.clone(), ctx.clone());
+            solutions.share_bank(self.bank);
+            solutions
+        });
+        if let Some(t) = timeout {
+            solutions.set_timeout(Duration::from_secs(t));
+        }
+        let nthread = min(self.nthread, ctx.len);
+        solutions.set_requested_threads(nthread);
+        for _ in 0..nthread {
+            solutions.create_new_thread();
+        }
+        let result = if solutions.threads.is_empty() { None } else { solutions.solve_loop().await };
+        solutions.stop_threads();
+
+        let (e, coverage) = match result {
+            Some(e) => (e, None),
+            None => match solutions.partial_result() {
+                Some((e, bits)) if bits.count_ones() == len => (e, None),
+                Some((e, bits)) => (e, Some(solutions.coverage_report(&bits))),
+                None => return Err("No solution found within the timeout".into()),
+            },
+        };
+        info!("Serving solution {:?}", e);
+        let solution = emit::render(self.target, e, &self.sig).map_err(|e| e.to_string())?;
+        Ok(match coverage {
+            None => json!({"ok": true, "solution": solution}),
+            Some(c) => json!({"ok": true, "solution": solution, "coverage": c.trim_start_matches("; ")}),
+        })
+    }
+
+    /// Goes back to the initial examples, dropping all solutions.
+    fn reset(&mut self) {
+        if let Some(mut solutions) = self.solutions.take() {
+            solutions.stop_threads();
+            *CONDITIONS.lock() = None;
+            bridge::unshare_bank();
+        }
+        self.examples = self.initial.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{emit::Target, expr::cfg::Cfg, parser::problem::PBEProblem, solutions::SOLUTIONS_LOCK};
+
+    use super::{Server, BANK_SIZE};
+
+    #[tokio::test]
+    async fn test_examples() {
+        let problem = PBEProblem::parse_json(r#"{"inputs": [["a", 1]], "outputs": ["a1"]}"#).unwrap();
+        let cfg = Cfg::from_synthfun(problem.synthfun());
+        let mut server = Server::new(problem.synthfun().sig.clone(), cfg, problem.examples.clone(), 1, Target::Sygus, None, BANK_SIZE);
+
+        assert_eq!(server.handle(r#"{"cmd": "add_example", "input": ["b", 2], "output": "b2"}"#).await, json!({"ok": true, "examples": 2}));
+        assert_eq!(server.handle(r#"{"cmd": "add_example", "input": ["b"], "output": "b2"}"#).await["ok"], json!(false));
+        assert_eq!(server.handle(r#"{"cmd": "add_example", "input": ["b", "2"], "output": "b2"}"#).await["ok"], json!(false));
+        assert_eq!(server.handle(r#"{"cmd": "undo"}"#).await["ok"], json!(false));
+        assert_eq!(server.handle(r#"{"cmd": "reset"}"#).await, json!({"ok": true, "examples": 1}));
+    }
+
+    #[tokio::test]
+    async fn test_solve_twice() {
+        let _guard = SOLUTIONS_LOCK.lock().await;
+        let problem = PBEProblem::parse(r#"
+            (set-logic SLIA)
+            (synth-fun f ((x String)) String ((Start String (ntString)) (ntString String (x "-" (str.++ ntString ntString)))))
+            (constraint (= (f "a") "a-"))
+            (check-synth)
+        "#).unwrap();
+        let cfg = Cfg::from_synthfun(problem.synthfun());
+        let mut server = Server::new(problem.synthfun().sig.clone(), cfg, problem.examples.clone(), 1, Target::Sygus, None, BANK_SIZE);
+
+        let first = server.handle(r#"{"cmd": "solve", "timeout": 10}"#).await;
+        assert_eq!(first["ok"], json!(true), "{first}");
+        assert!(first["solution"].is_string());
+        server.handle(r#"{"cmd": "add_example", "input": ["bc"], "output": "bc-"}"#).await;
+        let second = server.handle(r#"{"cmd": "solve", "timeout": 10}"#).await;
+        assert_eq!(second["ok"], json!(true), "{second}");
+        assert!(second.get("coverage").is_none(), "{second}");
+        server.handle(r#"{"cmd": "reset"}"#).await;
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use crate::{expr::{cfg::{Cfg, NonTerminal, ProdRule}, context::Context, ops::Op2Enum, Expr}, forward::executor::Executor, galloc::{AllocForAny, AllocForExactSizeIter}, info, parser::{check::Sketch, config::Config, validate}, value::Value};
+
+/// Returns whether `e` contains a hole, i.e. a variable after the `nargs` arguments of the function.
+fn has_hole(e: &'static Expr, nargs: usize) -> bool {
+    match e {
+        Expr::Const(_) => false,
+        Expr::Var(v) => *v >= nargs as i64,
+        Expr::Op1(_, a) => has_hole(a, nargs),
+        Expr::Op2(_, a, b) | Expr::Let(_, a, b) => has_hole(a, nargs) || has_hole(b, nargs),
+        Expr::Op3(_, a, b, c) => has_hole(a, nargs) || has_hole(b, nargs) || has_hole(c, nargs),
+    }
+}
+
+/// Returns the value each hole of `e` must take for `e` to evaluate to `output` on `ctx`, if the fixed scaffolding around the holes determines them.
+///
+/// The scaffolding is evaluated, and the output is traced back through it to the holes: a concatenation with a fixed side gives its other side by removing a prefix or a suffix of the output.
+/// Returns `None` if a hole is reached through any other operator, or if the scaffolding contradicts the output.
+pub fn invert(e: &'static Expr, nargs: usize, output: Value, ctx: &Context) -> Option<Vec<(usize, Value)>> {
+    match e {
+        Expr::Var(v) if *v >= nargs as i64 => Some(vec![(*v as usize - nargs, output)]),
+        _ if !has_hole(e, nargs) => (e.eval(ctx) == output).then(Vec::new),
+        Expr::Op2(Op2Enum::Concat(_), a, b) if !has_hole(a, nargs) => {
+            let rest = output.to_str().iter().zip(a.eval(ctx).to_str()).map(|(&o, &p)| o.strip_prefix(p)).collect::<Option<Vec<_>>>()?;
+            invert(b, nargs, rest.into_iter().galloc_scollect().into(), ctx)
+        }
+        Expr::Op2(Op2Enum::Concat(_), a, b) if !has_hole(b, nargs) => {
+            let rest = output.to_str().iter().zip(b.eval(ctx).to_str()).map(|(&o, &s)| o.strip_suffix(s)).collect::<Option<Vec<_>>>()?;
+            invert(a, nargs, rest.into_iter().galloc_scollect().into(), ctx)
+        }
+        _ => None,
+    }
+}
+
+/// Replaces each hole of `e` by the term filling it.
+pub fn fill(e: &'static Expr, nargs: usize, fills: &HashMap<usize, &'static Expr>) -> &'static Expr {
+    match e {
+        Expr::Var(v) if *v >= nargs as i64 => fills[&(*v as usize - nargs)],
+        Expr::Const(_) | Expr::Var(_) => e,
+        Expr::Op1(op, a) => Expr::Op1(op, fill(a, nargs, fills)).galloc(),
+        Expr::Op2(op, a, b) => Expr::Op2(op, fill(a, nargs, fills), fill(b, nargs, fills)).galloc(),
+        Expr::Op3(op, a, b, c) => Expr::Op3(op, fill(a, nargs, fills), fill(b, nargs, fills), fill(c, nargs, fills)).galloc(),
+        Expr::Let(v, a, b) => Expr::Let(*v, fill(a, nargs, fills), fill(b, nargs, fills)).galloc(),
+    }
+}
+
+/// Builds a grammar producing the terms of the sketch: each node of the scaffolding becomes a non-terminal whose only rule is that node, added to `cfg`, and the holes refer to the non-terminals of `cfg` they name.
+/// The root of the scaffolding is the start symbol of the result.
+///
+/// Returns `None` if the scaffolding binds variables with `let`, which rules cannot express.
+pub fn scaffold_cfg(sketch: &Sketch, cfg: &Cfg) -> Option<Cfg> {
+    let nargs = sketch.definefun.sig.args.len();
+    let holes = sketch.holes.iter().map(|h| cfg.iter().position(|nt| &nt.name == h)).collect::<Option<Vec<_>>>()?;
+    // The types of the nodes are those of their values on zero examples
+    let inputs = sketch.definefun.sig.args.iter().map(|(_, ty)| validate::empty(*ty)).chain(holes.iter().map(|&h| validate::empty(cfg[h].ty))).collect();
+    let ctx = Context::new(0, inputs, vec![], Value::Null);
+    let mut result = cfg.clone();
+    let root = add_node(sketch.definefun.expr, nargs, &holes, &ctx, &mut result)?;
+    Some(result.change_start(root))
+}
+
+fn add_node(e: &'static Expr, nargs: usize, holes: &[usize], ctx: &Context, cfg: &mut Cfg) -> Option<usize> {
+    let rule = match e {
+        Expr::Var(v) if *v >= nargs as i64 => return Some(holes[*v as usize - nargs]),
+        Expr::Const(c) => ProdRule::Const(*c),
+        Expr::Var(v) => ProdRule::Var(*v),
+        Expr::Op1(op, a) => ProdRule::Op1(op, add_node(a, nargs, holes, ctx, cfg)?),
+        Expr::Op2(op, a, b) => ProdRule::Op2(op, add_node(a, nargs, holes, ctx, cfg)?, add_node(b, nargs, holes, ctx, cfg)?),
+        Expr::Op3(op, a, b, c) => ProdRule::Op3(op, add_node(a, nargs, holes, ctx, cfg)?, add_node(b, nargs, holes, ctx, cfg)?, add_node(c, nargs, holes, ctx, cfg)?),
+        Expr::Let(..) => return None,
+    };
+    let name = format!("sketch{}", cfg.len());
+    cfg.push(NonTerminal { name, ty: e.eval(ctx).ty(), rules: vec![rule], config: Config::new() });
+    Some(cfg.len() - 1)
+}
+
+/// Solves the examples of `ctx` with the terms of `sketch`, filling its holes with terms of the grammar `cfg`.
+///
+/// When the scaffolding determines the value of every hole (see [`invert`]), each hole is solved as a separate problem on its non-terminal; otherwise, the whole sketch is searched with [`scaffold_cfg`].
+pub fn solve(sketch: &Sketch, cfg: Cfg, ctx: Context) -> Option<&'static Expr> {
+    let nargs = sketch.definefun.sig.args.len();
+    if let Some(values) = invert(sketch.definefun.expr, nargs, ctx.output, &ctx) {
+        let mut fills = HashMap::new();
+        for (k, value) in values {
+            let nt = cfg.iter().position(|nt| nt.name == sketch.holes[k])?;
+            info!("Sketch: solving hole {} of {} for {:?}", k, sketch.holes[k], value);
+            let ctx = Context { output: value, ..ctx.clone() };
+            fills.insert(k, Executor::new(ctx, cfg.change_start(nt)).solve_top_with_limit()?);
+        }
+        return Some(fill(sketch.definefun.expr, nargs, &fills));
+    }
+    info!("Sketch: searching the scaffolding");
+    Executor::new(ctx, scaffold_cfg(sketch, &cfg)?).solve_top_with_limit()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr::{cfg::Cfg, context::Context}, parser::problem::PBEProblem};
+
+    use super::{invert, scaffold_cfg, solve};
+
+    #[test]
+    fn test_sketch() {
+        let s = r#"
+            (set-logic SLIA)
+            (synth-fun f ((name String)) String ((Start String (ntString)) (ntString String (name " " "," (str.++ ntString ntString) (str.substr ntString ntInt ntInt))) (ntInt Int (0 1 2 (str.len ntString)))))
+            (define-fun f ((name String)) String (str.++ "Dr. " (?? ntString)))
+            (constraint (= (f "ab") "Dr. ab,"))
+            (constraint (= (f "xyz") "Dr. xyz,"))
+            (check-synth)
+        "#;
+        let problem = PBEProblem::parse(s).unwrap();
+        let sketch = problem.sketch.as_ref().unwrap();
+        let cfg = Cfg::from_synthfun(problem.synthfun());
+        let ctx = Context::from_examples(&problem.examples);
+        let holes = invert(sketch.definefun.expr, 1, ctx.output, &ctx).unwrap();
+        // The examples are not kept in order (see `IOExamples::parse`).
+        assert!(holes[0].1.to_str().iter().zip(ctx[0].to_str()).all(|(h, name)| *h == format!("{name},")));
+        let scaffold = scaffold_cfg(sketch, &cfg).unwrap();
+        assert_eq!(scaffold.len(), cfg.len() + 2);
+        let result = solve(sketch, cfg, ctx.clone()).unwrap();
+        assert_eq!(result.eval(&ctx), ctx.output);
+    }
+}
@@ -0,0 +1,391 @@
+use std::{io::Write, process::{Command, Stdio}};
+
+use derive_more::Display;
+use itertools::Itertools;
+
+use crate::{debg, expr::Expr, galloc::AllocForStr, parser::{ioexamples::IOExamples, problem::{FunSig, PBEProblem}}, value::{ConstValue, Type}};
+
+#[derive(Debug, Display)]
+/// Errors raised while talking to an SMT solver.
+pub enum SmtError {
+    #[display(fmt = "Failed to run the SMT solver: {}", _0)]
+    Io(std::io::Error),
+    #[display(fmt = "SMT solver error: {}", _0)]
+    Solver(String),
+    #[display(fmt = "Unexpected SMT solver output: {}", _0)]
+    Parse(String),
+    #[display(fmt = "Unsupported by the SMT oracle: {}", _0)]
+    Unsupported(String),
+}
+
+impl std::error::Error for SmtError {}
+
+impl From<std::io::Error> for SmtError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Display)]
+/// A minimal S-expression, enough to manipulate SMT-LIB constraints and solver responses.
+pub enum SExpr {
+    #[display(fmt = "{}", _0)]
+    Atom(String),
+    #[display(fmt = "({})", r#"_0.iter().join(" ")"#)]
+    List(Vec<SExpr>),
+}
+
+impl SExpr {
+    /// Parses a sequence of S-expressions. String literals (with `""` escapes), `|quoted|` symbols and `;` comments are supported.
+    pub fn parse_all(input: &str) -> Result<Vec<SExpr>, SmtError> {
+        let mut stack: Vec<Vec<SExpr>> = vec![Vec::new()];
+        let mut chars = input.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '(' => stack.push(Vec::new()),
+                ')' => {
+                    let list = stack.pop().filter(|_| !stack.is_empty()).ok_or_else(|| SmtError::Parse(input.to_string()))?;
+                    stack.last_mut().unwrap().push(SExpr::List(list));
+                }
+                ';' => { for c in chars.by_ref() { if c == '\n' { break; } } }
+                c if c.is_whitespace() => (),
+                '"' => {
+                    let mut atom = String::from('"');
+                    loop {
+                        match chars.next() {
+                            Some('"') if chars.peek() == Some(&'"') => { chars.next(); atom.push_str("\"\""); }
+                            Some('"') => { atom.push('"'); break; }
+                            Some(c) => atom.push(c),
+                            None => return Err(SmtError::Parse(input.to_string())),
+                        }
+                    }
+                    stack.last_mut().unwrap().push(SExpr::Atom(atom));
+                }
+                '|' => {
+                    let mut atom = String::from('|');
+                    for c in chars.by_ref() {
+                        atom.push(c);
+                        if c == '|' { break; }
+                    }
+                    stack.last_mut().unwrap().push(SExpr::Atom(atom));
+                }
+                c => {
+                    let mut atom = String::from(c);
+                    while let Some(&c) = chars.peek() {
+                        if c.is_whitespace() || c == '(' || c == ')' || c == '"' || c == ';' { break; }
+                        atom.push(c);
+                        chars.next();
+                    }
+                    stack.last_mut().unwrap().push(SExpr::Atom(atom));
+                }
+            }
+        }
+        if stack.len() != 1 { return Err(SmtError::Parse(input.to_string())); }
+        Ok(stack.pop().unwrap())
+    }
+    /// Parses exactly one S-expression.
+    pub fn parse(input: &str) -> Result<SExpr, SmtError> {
+        let [e]: [_; 1] = Self::parse_all(input)?.try_into().map_err(|_| SmtError::Parse(input.to_string()))?;
+        Ok(e)
+    }
+    /// Returns the atom, if this is one.
+    pub fn atom(&self) -> Option<&str> {
+        if let Self::Atom(a) = self { Some(a.as_str()) } else { None }
+    }
+    /// Collects every application `(name ...)` within the expression.
+    fn applications<'a>(&'a self, name: &str, result: &mut Vec<&'a [SExpr]>) {
+        if let Self::List(l) = self {
+            if l.first().and_then(|h| h.atom()) == Some(name) {
+                result.push(&l[1..]);
+            }
+            for e in l { e.applications(name, result); }
+        }
+    }
+    /// Replaces every application `(name ...)` by the atom `by`.
+    fn replace_applications(&self, name: &str, by: &str) -> SExpr {
+        match self {
+            Self::Atom(_) => self.clone(),
+            Self::List(l) if l.first().and_then(|h| h.atom()) == Some(name) => Self::Atom(by.to_string()),
+            Self::List(l) => Self::List(l.iter().map(|e| e.replace_applications(name, by)).collect()),
+        }
+    }
+}
+
+/// Returns the SMT-LIB sort of a type.
+pub fn sort(ty: Type) -> Result<String, SmtError> {
+    match ty {
+        Type::Int | Type::Bool | Type::Str | Type::BitVector(_) => Ok(format!("{:?}", ty)),
+        _ => Err(SmtError::Unsupported(format!("type {:?}", ty))),
+    }
+}
+
+/// Prints a constant as an SMT-LIB literal. Non-printable and non-ASCII characters of strings are written as `\u{..}` escapes.
+pub fn literal(c: &ConstValue) -> Result<String, SmtError> {
+    match c {
+        ConstValue::Int(i) if *i < 0 => Ok(format!("(- {})", (*i as i128).abs())),
+        ConstValue::Int(i) => Ok(i.to_string()),
+        ConstValue::Bool(b) => Ok(b.to_string()),
+        ConstValue::Str(s) => {
+            let mut result = String::from('"');
+            for c in s.chars() {
+                match c {
+                    '"' => result.push_str("\"\""),
+                    '\\' => result.push_str("\\u{5c}"),
+                    ' '..='~' => result.push(c),
+                    c => result.push_str(&format!("\\u{{{:x}}}", c as u32)),
+                }
+            }
+            result.push('"');
+            Ok(result)
+        }
+        ConstValue::BitVector(n, v) if n % 4 == 0 => Ok(format!("#x{:0w$x}", v, w = n / 4)),
+        ConstValue::BitVector(n, v) => Ok(format!("#b{:0w$b}", v, w = *n)),
+        _ => Err(SmtError::Unsupported(format!("constant {:?}", c))),
+    }
+}
+
+/// Reads an SMT-LIB value of the given type from a solver's `get-value` response.
+pub fn parse_literal(e: &SExpr, ty: Type) -> Result<ConstValue, SmtError> {
+    let err = || SmtError::Parse(e.to_string());
+    match (ty, e) {
+        (Type::Int, SExpr::Atom(a)) => a.parse::<i64>().map(ConstValue::Int).map_err(|_| err()),
+        (Type::Int, SExpr::List(l)) if l.len() == 2 && l[0].atom() == Some("-") => {
+            parse_literal(&l[1], ty)?.as_i64().map(|i| ConstValue::Int(-i)).ok_or_else(err)
+        }
+        (Type::Bool, SExpr::Atom(a)) => a.parse::<bool>().map(ConstValue::Bool).map_err(|_| err()),
+        (Type::Str, SExpr::Atom(a)) if a.len() >= 2 && a.starts_with('"') && a.ends_with('"') => {
+            Ok(ConstValue::Str(unescape(&a[1..a.len() - 1]).ok_or_else(err)?.galloc_str()))
+        }
+        (Type::BitVector(n), SExpr::Atom(a)) => {
+            let v = if let Some(h) = a.strip_prefix("#x") { u64::from_str_radix(h, 16) }
+                else if let Some(b) = a.strip_prefix("#b") { u64::from_str_radix(b, 2) }
+                else { return Err(err()) };
+            v.map(|v| ConstValue::BitVector(n, v)).map_err(|_| err())
+        }
+        _ => Err(err()),
+    }
+}
+
+/// Decodes the escapes of an SMT-LIB string literal body: `""`, `\u{X}` and `\uXXXX`.
+fn unescape(s: &str) -> Option<String> {
+    let s = s.replace("\"\"", "\"");
+    let mut result = String::new();
+    let mut rest = s.as_str();
+    while let Some(i) = rest.find("\\u") {
+        result.push_str(&rest[..i]);
+        let after = &rest[i + 2..];
+        let (hex, len) = if let Some(inner) = after.strip_prefix('{') {
+            let end = inner.find('}')?;
+            (&inner[..end], end + 2)
+        } else if after.len() >= 4 && after[..4].chars().all(|c| c.is_ascii_hexdigit()) {
+            (&after[..4], 4)
+        } else {
+            result.push_str("\\u");
+            rest = after;
+            continue;
+        };
+        result.push(char::from_u32(u32::from_str_radix(hex, 16).ok()?)?);
+        rest = &after[len..];
+    }
+    result.push_str(rest);
+    Some(result)
+}
+
+/// Prints an expression as an SMT-LIB term over the arguments of `sig`.
+///
+/// Operator names are printed as they are; operators outside of the SMT-LIB theories will be rejected by the solver.
+pub fn expr_to_smt(e: &Expr, sig: &FunSig) -> Result<String, SmtError> {
+    match e {
+        Expr::Const(c) => literal(c),
+        Expr::Var(i) if *i < 0 => Ok(Expr::let_name(*i)),
+        Expr::Var(i) => Ok(sig.args[*i as usize].0.clone()),
+        Expr::Op1(op, a1) => Ok(format!("({} {})", op, expr_to_smt(a1, sig)?)),
+        Expr::Op2(op, a1, a2) => Ok(format!("({} {} {})", op, expr_to_smt(a1, sig)?, expr_to_smt(a2, sig)?)),
+        Expr::Op3(op, a1, a2, a3) => Ok(format!("({} {} {} {})", op, expr_to_smt(a1, sig)?, expr_to_smt(a2, sig)?, expr_to_smt(a3, sig)?)),
+        Expr::Let(v, e, body) => Ok(format!("(let (({} {})) {})", Expr::let_name(*v), expr_to_smt(e, sig)?, expr_to_smt(body, sig)?)),
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An external SMT solver accepting an SMT-LIB script on its standard input.
+pub struct SmtSolver {
+    pub command: String,
+    pub args: Vec<String>,
+}
+
+impl SmtSolver {
+    /// Creates a solver from its name: `z3` and `cvc5` get the flags needed to read a script from stdin, any other name is run without arguments.
+    pub fn new(name: &str) -> Self {
+        let args: &[&str] = match name {
+            "z3" => &["-in"],
+            "cvc5" => &["--lang=smt2", "--strings-exp"],
+            _ => &[],
+        };
+        Self { command: name.to_string(), args: args.iter().map(|s| s.to_string()).collect() }
+    }
+    /// Runs the solver on `script`, returning the responses. Responses of the form `(error ...)` are turned into `SmtError::Solver`.
+    pub fn run(&self, script: &str) -> Result<Vec<SExpr>, SmtError> {
+        debg!("SMT query: {}", script);
+        let mut child = Command::new(&self.command).args(&self.args)
+            .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+        child.stdin.take().unwrap().write_all(script.as_bytes())?;
+        let output = child.wait_with_output()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        debg!("SMT response: {}", stdout);
+        let responses = SExpr::parse_all(&stdout)?;
+        for r in responses.iter() {
+            if let SExpr::List(l) = r {
+                if l.first().and_then(|h| h.atom()) == Some("error") {
+                    return Err(SmtError::Solver(r.to_string()));
+                }
+            }
+        }
+        Ok(responses)
+    }
+    /// Runs a script ending with `(check-sat)` and `(get-value ...)`. Returns `None` if unsatisfiable, or the values otherwise.
+    fn check_and_get(&self, script: &str) -> Result<Option<Vec<SExpr>>, SmtError> {
+        let responses = self.run(script)?;
+        let mut iter = responses.into_iter().filter(|r| r.atom() != Some("success"));
+        match iter.next().as_ref().and_then(|r| r.atom()) {
+            Some("unsat") => Ok(None),
+            Some("sat") => {
+                let Some(SExpr::List(values)) = iter.next() else { return Err(SmtError::Parse("missing model".into())) };
+                values.into_iter().map(|v| match v {
+                    SExpr::List(mut pair) if pair.len() == 2 => Ok(pair.pop().unwrap()),
+                    v => Err(SmtError::Parse(v.to_string())),
+                }).try_collect().map(Some)
+            }
+            r => Err(SmtError::Solver(format!("check-sat returned {:?}", r))),
+        }
+    }
+}
+
+/// Name of the constant standing for the output of the synthesized function in oracle queries.
+const OUTPUT: &str = "__synthphonia_out";
+
+/// An SMT oracle checking candidates against the logical `constraint`s of a SyGuS problem.
+///
+/// Only single-invocation specifications are supported: every application of the synthesized function must be the same, and take declared variables as arguments.
+/// This allows both finding counterexample inputs and computing the expected output for them.
+pub struct Oracle {
+    solver: SmtSolver,
+    logic: String,
+    sig: FunSig,
+    vars: Vec<(String, Type)>,
+    spec: Vec<SExpr>,
+    invocation: Vec<String>,
+}
+
+impl Oracle {
+    /// Creates an oracle for the specification of `problem`.
+    pub fn new(solver: SmtSolver, problem: &PBEProblem) -> Result<Self, SmtError> {
+        let sig = problem.synthfun().sig.clone();
+        let spec: Vec<SExpr> = problem.spec.iter().map(|s| SExpr::parse(s)).try_collect()?;
+        let mut apps = Vec::new();
+        for s in spec.iter() { s.applications(&sig.name, &mut apps); }
+        let apps = apps.into_iter().unique().collect_vec();
+        let invocation: Vec<String> = match apps.as_slice() {
+            [args] => args.iter().map(|a| {
+                a.atom().filter(|a| problem.vars.iter().any(|(v, _)| v == a)).map(|a| a.to_string())
+                    .ok_or_else(|| SmtError::Unsupported(format!("{} is not a declared variable", a)))
+            }).try_collect()?,
+            [] if spec.is_empty() => sig.args.iter().map(|(a, _)| a.clone()).collect(),
+            _ => return Err(SmtError::Unsupported("the specification is not single-invocation".into())),
+        };
+        if let Some((v, _)) = problem.vars.iter().find(|(v, _)| !invocation.contains(v)) {
+            return Err(SmtError::Unsupported(format!("variable {} is not an argument of {}", v, sig.name)));
+        }
+        Ok(Self { solver, logic: problem.logic.clone(), sig, vars: problem.vars.clone(), spec, invocation })
+    }
+    /// Returns the script prefix declaring the variables.
+    fn header(&self) -> Result<String, SmtError> {
+        let mut script = format!("(set-logic {})\n(set-option :produce-models true)\n", self.logic);
+        for (v, ty) in self.vars.iter() {
+            script += &format!("(declare-const {} {})\n", v, sort(*ty)?);
+        }
+        Ok(script)
+    }
+    /// Searches for inputs on which `candidate` violates the specification. Returns `None` if it is verified.
+    pub fn counterexample(&self, candidate: &Expr) -> Result<Option<Vec<ConstValue>>, SmtError> {
+        if self.spec.is_empty() { return Ok(None); }
+        let args = self.sig.args.iter().map(|(a, ty)| Ok(format!("({} {})", a, sort(*ty)?))).collect::<Result<Vec<_>, SmtError>>()?;
+        let mut script = self.header()?;
+        script += &format!("(define-fun {} ({}) {} {})\n", self.sig.name, args.join(" "), sort(self.sig.rettype)?, expr_to_smt(candidate, &self.sig)?);
+        script += &format!("(assert (not (and true {})))\n(check-sat)\n(get-value ({}))\n", self.spec.iter().join(" "), self.invocation.join(" "));
+        let Some(values) = self.solver.check_and_get(&script)? else { return Ok(None) };
+        values.iter().zip(self.sig.args.iter()).map(|(v, (_, ty))| parse_literal(v, *ty)).try_collect().map(Some)
+    }
+    /// Computes an example satisfying the specification, with the arguments fixed to `inputs` if given.
+    pub fn example(&self, inputs: Option<&[ConstValue]>) -> Result<(Vec<ConstValue>, ConstValue), SmtError> {
+        let mut script = self.header()?;
+        script += &format!("(declare-const {} {})\n", OUTPUT, sort(self.sig.rettype)?);
+        if let Some(inputs) = inputs {
+            for (v, c) in self.invocation.iter().zip(inputs.iter()) {
+                script += &format!("(assert (= {} {}))\n", v, literal(c)?);
+            }
+        }
+        for s in self.spec.iter() {
+            script += &format!("(assert {})\n", s.replace_applications(&self.sig.name, OUTPUT));
+        }
+        script += &format!("(check-sat)\n(get-value ({} {}))\n", self.invocation.join(" "), OUTPUT);
+        let values = self.solver.check_and_get(&script)?.ok_or_else(|| SmtError::Solver("the specification is unsatisfiable".into()))?;
+        let types = self.sig.args.iter().map(|(_, ty)| *ty).chain(std::iter::once(self.sig.rettype));
+        let mut values: Vec<ConstValue> = values.iter().zip(types).map(|(v, ty)| parse_literal(v, ty)).try_collect()?;
+        let output = values.pop().ok_or_else(|| SmtError::Parse("missing output".into()))?;
+        Ok((values, output))
+    }
+    /// Checks `candidate` against the specification.
+    ///
+    /// Returns `true` if it is verified; otherwise a counterexample is added to `examples` together with its expected output, and `false` is returned.
+    pub fn refine(&self, candidate: &Expr, examples: &mut IOExamples) -> Result<bool, SmtError> {
+        if let Some(inputs) = self.counterexample(candidate)? {
+            let (inputs, output) = self.example(Some(&inputs))?;
+            examples.add_example(&inputs, output);
+            Ok(false)
+        } else { Ok(true) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr, parser::problem::PBEProblem, value::{ConstValue, Type}};
+
+    use super::{expr_to_smt, literal, parse_literal, Oracle, SExpr, SmtSolver};
+
+    #[test]
+    fn test_sexpr() {
+        let e = SExpr::parse(r#"(= (f x) (str.++ x "a""b" |s y|)) ; comment"#).unwrap();
+        assert_eq!(e.to_string(), r#"(= (f x) (str.++ x "a""b" |s y|))"#);
+        assert!(SExpr::parse("(a (b)").is_err());
+        assert!(SExpr::parse("a)").is_err());
+    }
+
+    #[test]
+    fn test_literal() {
+        for c in [ConstValue::Str("a\"b\\c\u{e9}"), ConstValue::Int(-3), ConstValue::Int(42), ConstValue::Bool(true), ConstValue::BitVector(8, 0x1f)] {
+            let s = literal(&c).unwrap();
+            assert_eq!(parse_literal(&SExpr::parse(&s).unwrap(), c.ty()).unwrap(), c, "{s}");
+        }
+        assert_eq!(literal(&ConstValue::Str("a\"b")).unwrap(), r#""a""b""#);
+        assert_eq!(parse_literal(&SExpr::parse(r#""é""#).unwrap(), Type::Str).unwrap(), ConstValue::Str("\u{e9}"));
+    }
+
+    #[test]
+    fn test_oracle() {
+        let s = r#"(set-logic SLIA)
+(synth-fun f ((name String)) String ((Start String (name "a" (str.++ Start Start)))))
+(declare-var x String)
+(constraint (= (f x) (str.++ x "a")))
+(check-synth)"#;
+        let problem = PBEProblem::parse(s).unwrap();
+        let oracle = Oracle::new(SmtSolver::new("z3"), &problem).unwrap();
+        assert_eq!(oracle.invocation, vec!["x".to_string()]);
+        let e = expr!(Concat [0] "a");
+        assert_eq!(expr_to_smt(&e, &problem.synthfun().sig).unwrap(), r#"(str.++ name "a")"#);
+
+        let s = s.replace("(declare-var x String)", "(declare-var x String)\n(declare-var y String)")
+            .replace("(= (f x)", "(= (f y) (f x))) (constraint (= (f x)");
+        let problem = PBEProblem::parse(&s).unwrap();
+        assert!(Oracle::new(SmtSolver::new("z3"), &problem).is_err());
+    }
+}
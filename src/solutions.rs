@@ -1,13 +1,13 @@
-use std::{collections::{hash_map::Entry, HashMap, VecDeque}, time::{self, Duration, Instant}};
+use std::{collections::{hash_map::Entry, HashMap, HashSet, VecDeque}, time::{self, Duration, Instant}};
 
 use futures::StreamExt;
 use tokio::{select, task::JoinHandle};
 
 use itertools::Itertools;
 use mapped_futures::mapped_futures::MappedFutures;
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use rand::seq::SliceRandom;
-use crate::{backward::Problem, debg, expr::{cfg::Cfg, context::Context, Expr, Expression}, forward::executor::Executor, galloc::{self, AllocForAny}, info, log, never, tree_learning::{bits::BoxSliceExt, tree_learning, Bits}};
+use crate::{backward::Problem, checkpoint, debg, expr::{cfg::Cfg, context::Context, ops::Op3Enum, simplify::simplify, Expr, Expression}, forward::executor::{Executor, DETERMINISTIC, EPOCH}, galloc::{self, AllocForAny, MemPressure}, info, log, never, observer, tree_learning::{bits::BoxSliceExt, tree_learning, Bits, TreeLearning}, value::Value, warn};
 
 
 
@@ -19,6 +19,10 @@ use crate::{backward::Problem, debg, expr::{cfg::Cfg, context::Context, Expr, Ex
 /// Initially empty, it is intended to be populated at runtime with tracking data as needed.
 pub static CONDITIONS: spin::Mutex<Option<ConditionTracker>> = spin::Mutex::new(None);
 
+#[cfg(test)]
+/// `Solutions::new` requires the global `CONDITIONS` to be empty, so tests creating one must not overlap.
+pub(crate) static SOLUTIONS_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
 /// A structure for tracking condition evaluations within a given context. 
 /// 
 /// 
@@ -39,10 +43,24 @@ impl ConditionTracker {
     /// This method calculates the bit signature of the provided expression and, if this signature is not already present in the internal storage, allocates the expression and registers it along with its corresponding bits.
     pub fn insert(&mut self, expr: &Expr) {
         let bits = expr.eval(&self.ctx).to_bits();
+        self.insert_bits(expr, bits);
+    }
+    /// Inserts a condition enumerated with the value `value`, whose bits are packed from `value` instead of evaluating the condition again when it is a value on all the examples of the tracker.
+    /// 
+    /// Example sets are sorted subsets of the examples (see `Solutions::generate_example_set`), so a value on as many examples as the tracker is one on all of them, in the same order.
+    pub fn insert_evaluated(&mut self, expr: &Expr, value: Value) {
+        if value.len() == self.ctx.len {
+            self.insert_bits(expr, value.to_bits());
+        } else {
+            self.insert(expr);
+        }
+    }
+    fn insert_bits(&mut self, expr: &Expr, bits: Bits) {
         if let Entry::Vacant(e) = self.hashmap.entry(bits.clone()) {
             let expr = expr.clone().galloc();
             e.insert(expr);
             self.vec.push((expr, bits));
+            observer::notify(|o| o.on_new_condition(expr));
         }
     }
     /// Returns the number of conditions currently stored in the tracker. 
@@ -73,6 +91,27 @@ pub fn test_tree_hole_contains(tree_hole: &[Box<[u128]>], bits: &[usize]) -> boo
     false
 }
 
+#[derive(Debug, Clone, Default)]
+/// Statistics about how many example-subset threads are actually running. 
+/// 
+/// `generate_example_set` may run out of example sets, in which case `create_new_thread` cannot replace a finished or interrupted thread and the thread pool shrinks silently. 
+/// These counters make such a collapse of parallelism visible.
+pub struct ThreadStats {
+    /// Number of threads requested for the solving loop.
+    pub requested: usize,
+    /// Smallest number of active threads observed while below the requested count.
+    pub min_active: usize,
+    /// Number of times a thread could not be created because no example set was available.
+    pub exhausted: usize,
+}
+
+impl ThreadStats {
+    /// Returns whether the number of active threads has ever dropped below the requested count.
+    pub fn underutilized(&self) -> bool {
+        self.min_active < self.requested
+    }
+}
+
 /// A structure encapsulating the state and configuration for managing synthesis solutions along with multi-threaded search execution. 
 /// 
 /// 
@@ -88,6 +127,25 @@ pub struct Solutions {
     last_update: Instant,
     ite_limit: usize,
     tree_hole: Vec<Box<[u128]>>,
+    /// Thread utilization statistics.
+    pub stats: ThreadStats,
+    deadline: Option<Instant>,
+    /// Random generator choosing example sets, seeded by `set_seed`.
+    rng: StdRng,
+    /// Example sets already tried in the current round of `solve_deterministic`.
+    attempted: HashSet<Vec<usize>>,
+    /// Examples withheld from the search, on which complete solutions must also be correct (`--holdout`).
+    holdout: Option<Context>,
+    /// Number of distinct complete solutions to find before returning (`--top-k`).
+    top_k: usize,
+    /// Complete solutions found so far, in order of discovery.
+    found: Vec<&'static Expr>,
+    /// Simplified forms of `found`, to tell structurally distinct solutions apart.
+    distinct: HashSet<&'static Expr>,
+    /// Whether `partial_result` may combine solutions into a tree that leaves some examples unsatisfied (`--best-effort`).
+    best_effort: bool,
+    /// Solutions rejected for producing a forbidden output on a negative example or for being wrong on a held-out example, ignored when found again.
+    rejected: HashSet<&'static Expr>,
 }
 
 impl Solutions {
@@ -102,9 +160,93 @@ impl Solutions {
         let solutions = Vec::new();
         let solved_examples = Bits::zeros(ctx.len);
         Self { 
-            tree_hole: vec![Bits::ones(ctx.len)],
+            tree_hole: vec![Bits::ones(ctx.len)], stats: ThreadStats::default(), deadline: None, rng: StdRng::from_entropy(), attempted: HashSet::new(), holdout: None, top_k: 1, found: Vec::new(), distinct: HashSet::new(), best_effort: false, rejected: HashSet::new(),
             cfg, ctx, solutions, solved_examples, threads: MappedFutures::new(), start_time: time::Instant::now(), last_update: time::Instant::now(), ite_limit: 1}
     }
+    /// Records the number of threads requested for the solving loop, used to detect when parallelism collapses.
+    pub fn set_requested_threads(&mut self, n: usize) {
+        self.stats.requested = n;
+        self.stats.min_active = n;
+    }
+    /// Sets a global time budget, measured from now, after which `solve_loop` gives up.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.deadline = Some(time::Instant::now() + timeout);
+    }
+    /// Seeds the choice of example sets, which is otherwise random.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+    /// Withholds the examples of `holdout` from the search: a solution covering the examples of the context is only accepted if it is also correct on them.
+    pub fn set_holdout(&mut self, holdout: Context) {
+        self.holdout = Some(holdout);
+    }
+    /// Keeps searching until `k` structurally distinct complete solutions are found, instead of stopping at the first one.
+    /// 
+    /// Complete solutions are then recorded apart from the partial ones, so that they do not subsume them, and the example set of the thread finding one is not tried again.
+    pub fn set_top_k(&mut self, k: usize) {
+        self.top_k = k.max(1);
+    }
+    /// Records a complete solution unless its simplified form was already found, and returns whether `top_k` solutions were found.
+    fn record(&mut self, e: &'static Expr) -> bool {
+        if self.distinct.insert(simplify(e, &self.ctx)) {
+            info!("Complete solution {}/{}: {}", self.found.len() + 1, self.top_k, crate::expr::pretty::log(e));
+            self.found.push(e);
+        }
+        self.found.len() >= self.top_k
+    }
+    /// Returns the distinct complete solutions found so far, smallest first.
+    pub fn top_solutions(&self) -> Vec<&'static Expr> {
+        self.found.iter().copied().sorted_by_key(|e| e.size()).collect()
+    }
+    /// Returns the smallest complete solution found so far.
+    fn best(&self) -> Option<&'static Expr> {
+        self.found.iter().copied().min_by_key(|e| e.size())
+    }
+    /// Lets `partial_result` return decision trees solving only part of the examples, for noisy examples that no expression can satisfy altogether.
+    pub fn set_best_effort(&mut self, best_effort: bool) {
+        self.best_effort = best_effort;
+    }
+    /// Returns whether `e` is correct on the held-out examples, if any, rejecting it for good otherwise.
+    fn generalizes(&mut self, e: &'static Expr) -> bool {
+        let Some(h) = self.holdout.as_ref() else { return true };
+        let ok = h.evaluate(e).is_some_and(|b| b.count_ones() == h.len as u32);
+        if !ok {
+            info!("Rejecting {:?}: wrong on a held-out example", e);
+            self.rejected.insert(e);
+        }
+        ok
+    }
+    /// Returns whether `e` produces a forbidden output on a negative example, rejecting it for good if so.
+    fn matches_negative(&mut self, e: &'static Expr) -> bool {
+        if !self.ctx.matches_negative(e) { return false; }
+        info!("Rejecting {:?}: matches a negative example", e);
+        self.rejected.insert(e);
+        true
+    }
+    /// Drops the solutions at the leaves of the tree `e` that match a negative example, since the tree produces a forbidden output only through one of them.
+    /// 
+    /// The dropped solutions are rejected for good: the examples they solved are searched again, and the next tree has to solve them otherwise.
+    fn drop_negative_leaves(&mut self, e: &'static Expr) {
+        let mut leaves = Vec::new();
+        self.leaves(e, &mut leaves);
+        for l in leaves {
+            self.matches_negative(l);
+        }
+        self.solutions.retain(|(e, _)| !self.rejected.contains(*e));
+        self.solved_examples = Bits::zeros(self.ctx.len);
+        for (_, bits) in self.solutions.iter() {
+            self.solved_examples.union_assign(bits);
+        }
+    }
+    /// Collects the stored solutions at the leaves of a decision tree built from them.
+    fn leaves(&self, e: &'static Expr, result: &mut Vec<&'static Expr>) {
+        if self.solutions.iter().any(|(s, _)| *s == e) {
+            result.push(e);
+        } else if let Expr::Op3(Op3Enum::Ite(_), _, t, f) = e {
+            self.leaves(t, result);
+            self.leaves(f, result);
+        }
+    }
     /// Counts the number of stored synthesis solutions.
     /// 
     /// Returns the total count of solution entries currently maintained within the internal collection.
@@ -120,8 +262,25 @@ impl Solutions {
     /// Continues by assessing whether the inclusive solved example set now covers all required cases, returning the expression if complete. 
     /// In parallel, it iterates over the currently scheduled threads, aborting any whose example sets are fully encompassed by the new evaluation and triggering the launch of new threads. 
     /// Finally, it leverages auxiliary mechanisms to generate a final solution if possible, or returns None if the candidate fails to yield a valid update.
+    /// Complete candidates that produce a forbidden output on a negative example of the context are rejected, as are those that are wrong on a held-out example (see `set_holdout`).
+    /// Partial ones are kept, since a decision tree may not route the input of the negative example to them; see `generate_result`.
     pub fn add_new_solution(&mut self, expr: &'static Expr) -> Option<&'static Expr> {
+        if self.rejected.contains(expr) {
+            debg!("Rejecting {:?}: rejected before", expr);
+            return None;
+        }
+        if expr.size() > self.cfg.config.max_size {
+            debg!("Rejecting {:?}: larger than the maximum size {}", expr, self.cfg.config.max_size);
+            return None;
+        }
         if let Some(b) = self.ctx.evaluate(expr) {
+            // A complete solution overfitting the examples must not shadow later ones
+            if b.count_ones() == self.ctx.len as u32 && (self.matches_negative(expr) || !self.generalizes(expr)) {
+                return None;
+            }
+            if b.count_ones() == self.ctx.len as u32 && self.top_k > 1 {
+                return Some(expr);
+            }
             // Updating solutions
             for (_, bits) in self.solutions.iter() {
                 if b.subset(bits) {
@@ -131,6 +290,7 @@ impl Solutions {
             self.solutions.retain(|(e, bits)| !bits.subset(&b));
             self.solved_examples.union_assign(&b);
             self.solutions.push((expr, b.clone()));
+            observer::notify(|o| o.on_partial_solution(expr, b.count_ones() as usize, self.ctx.len));
             debg!("Solutions [{}/{} {}]: {:?}", self.solved_examples.count_ones(), self.ctx.len, self.threads.len(), self.solutions);
 
             if b.count_ones() == self.ctx.len as u32 {
@@ -157,17 +317,24 @@ impl Solutions {
     /// 
     /// Checks whether the complete set of examples has been addressed; if so, it invokes a tree-learning procedure using a configurable operator limit depending on the provided flag and returns the synthesized solution expression. 
     /// Otherwise, it returns none.
-    pub fn generate_result(&self, limit: bool) -> Option<&'static Expr> {
-        if self.solved_examples.count_ones() == self.ctx.len as u32 {
-            self.learn_tree(if limit { self.cfg.config.ite_limit_rate } else { 1 })
-        } else { None }
+    /// A tree producing a forbidden output on a negative example is not returned, and the leaves responsible for it are dropped (see `drop_negative_leaves`), so that the search moves on.
+    pub fn generate_result(&mut self, limit: bool) -> Option<&'static Expr> {
+        if self.solved_examples.count_ones() != self.ctx.len as u32 { return None; }
+        let tree = self.learn_tree(if limit { self.cfg.config.ite_limit_rate } else { 1 })?;
+        if self.rejected.contains(tree) { return None; }
+        if self.ctx.matches_negative(tree) {
+            info!("Rejecting {:?}: matches a negative example", tree);
+            self.drop_negative_leaves(tree);
+            return None;
+        }
+        self.generalizes(tree).then_some(tree)
     }
     /// Learns a decision tree that synthesizes an expression using the current set of solutions and conditions, dynamically adjusting the iteration limit based on elapsed time and a provided rate parameter.
     /// 
     /// Computes an adaptive limit derived from the runtime duration and toggles a global condition tracker before invoking a tree learning procedure. 
     /// Returns an expression reference if the tree learning process determines that a complete solution has been found, otherwise yields None.
     pub fn learn_tree(&self, ite_limit_rate: usize) -> Option<&'static Expr> {
-        let duration = time::Instant::now() - self.start_time;
+        let duration = if DETERMINISTIC.load(std::sync::atomic::Ordering::Relaxed) { Duration::ZERO } else { time::Instant::now() - self.start_time };
         let ite_limit = if duration.as_secs() as usize >= self.cfg.config.ite_limit_giveup {
             self.ite_limit + (duration.as_millis() as usize - self.cfg.config.ite_limit_giveup * 1000) * 5 / ite_limit_rate + 1
         } else { self.ite_limit };
@@ -179,13 +346,61 @@ impl Solutions {
         }
         debg!("Tree Learning Conditions: {}, Limit: {}", conditions.len(), ite_limit);
         let bump = bumpalo::Bump::new();
-        let result = tree_learning(self.solutions.clone(), &conditions.vec[..], self.ctx.len, &bump, ite_limit);
-        if result.solved {
+        let result = tree_learning(self.solutions.clone(), &conditions.vec[..], self.ctx.len, &bump, ite_limit, self.cfg.config.tree_cost_weight);
+        if result.solved && result.result_size() <= self.cfg.config.max_size {
             Some(result.expr())
         } else {
+            if result.solved { debg!("Tree of size {} exceeds the maximum size {}", result.result_size(), self.cfg.config.max_size); }
             None
         }
     }
+    /// Learns a decision tree solving as many examples as possible with the current solutions and conditions, abstaining on the others (see `TreeLearning::best_effort`).
+    fn learn_best_effort_tree(&self) -> Option<&'static Expr> {
+        let lock = CONDITIONS.lock();
+        let conditions = lock.as_ref()?;
+        if self.solutions.is_empty() { return None; }
+        let bump = bumpalo::Bump::new();
+        let mut tl = TreeLearning::with_cost_weight(self.ctx.len, &conditions.vec[..], self.solutions.clone(), &bump, 2 * self.ctx.len + 1, self.cfg.config.tree_cost_weight).best_effort();
+        (tl.run() && tl.result_size() <= self.cfg.config.max_size).then(|| tl.expr())
+    }
+    /// Assembles the best available result when the search is cut short. 
+    /// 
+    /// A complete result from `generate_result(false)` is preferred; otherwise the stored solution solving the most examples is returned. 
+    /// With `set_best_effort`, a decision tree combining the solutions and abstaining on unsolved examples is returned instead if it solves more examples.
+    /// The expression is paired with the bits of the examples it actually solves.
+    pub fn partial_result(&mut self) -> Option<(&'static Expr, Bits)> {
+        if let Some(e) = self.generate_result(false) {
+            if let Some(bits) = self.ctx.evaluate(e) {
+                return Some((e, bits));
+            }
+        }
+        let best = self.solutions.iter().filter(|(e, _)| !self.ctx.matches_negative(e))
+            .max_by_key(|(_, bits)| bits.count_ones()).map(|(e, bits)| (*e, bits.clone()));
+        let tree = self.learn_best_effort_tree().filter(|_| self.best_effort)
+            .filter(|e| !self.ctx.matches_negative(e))
+            .and_then(|e| Some((e, self.ctx.evaluate(e)?)));
+        match (best, tree) {
+            (Some(b), Some(t)) if t.1.count_ones() > b.1.count_ones() => Some(t),
+            (best, tree) => best.or(tree),
+        }
+    }
+    /// Lists the examples that a result with the given coverage bits does not satisfy, one comment line each with the inputs, the expected output and the actual output of `e`.
+    pub fn unsatisfied_report(&self, e: &'static Expr, bits: &Bits) -> String {
+        let actual = e.eval(&self.ctx);
+        (0..self.ctx.len).filter(|i| !bits.get(*i)).map(|i| {
+            let inputs = self.ctx.inputs().map(|v| v.to_display_string(i)).join(", ");
+            format!("; ({}) -> expected {}, got {}", inputs, self.ctx.output.to_display_string(i), actual.to_display_string(i))
+        }).join("\n")
+    }
+    /// Describes which examples are solved by a result with the given coverage bits.
+    pub fn coverage_report(&self, bits: &Bits) -> String {
+        let unsolved = (0..self.ctx.len).filter(|i| !bits.get(*i)).collect_vec();
+        if unsolved.is_empty() {
+            format!("; Coverage: {}/{} examples", self.ctx.len, self.ctx.len)
+        } else {
+            format!("; Coverage: {}/{} examples, unsolved: {:?}", self.ctx.len - unsolved.len(), self.ctx.len, unsolved)
+        }
+    }
     /// Checks whether any stored solution in the current context fully covers the specified example set. 
     /// 
     /// This function iterates over all solutions and verifies if all indices in the provided example set are included in the corresponding coverage bitmask of any solution. 
@@ -202,10 +417,9 @@ impl Solutions {
     /// Generates a new set of example indices for initiating a synthesis thread. 
     /// This method iterates over potential subset sizes, calculating binomial coefficients to limit enumeration, and constructs candidate subsets based on configured conditions—either filtering through a predefined mask or generating all possible combinations.
     /// 
-    /// It randomizes the order of these candidate subsets and validates each by ensuring that the example set is neither already covered by existing solutions nor in use by running threads. 
+    /// It randomizes the order of these candidate subsets (with the generator seeded by `set_seed`, if any) and validates each by ensuring that the example set is neither already covered by existing solutions nor in use by running threads (or tried in the current round of `solve_deterministic`). 
     /// When a valid subset is found, it returns the set; otherwise, it yields None if no appropriate example set can be generated.
     pub fn generate_example_set(&mut self) -> Option<Vec<usize>> {
-        let mut rng = rand::thread_rng();
         for k in 1..=self.ctx.len {
             if bicoeff(self.ctx.len, k) > 4000000 { break; }
 
@@ -218,9 +432,9 @@ impl Solutions {
                 vec.extend((0..self.ctx.len).combinations(k).collect_vec());
             }
             
-            vec.shuffle(&mut rng);
+            vec.shuffle(&mut self.rng);
             for v in vec {
-                if !self.check_cover(&v) && !self.threads.contains(&v) { return Some(v); }
+                if !self.check_cover(&v) && !self.threads.contains(&v) && !self.attempted.contains(&v) { return Some(v); }
             }
         }
         None
@@ -240,15 +454,32 @@ impl Solutions {
             }
         }
     }
+    /// Adds solutions restored from a checkpoint, returning a result if they already solve all examples.
+    pub fn restore(&mut self, exprs: impl IntoIterator<Item = &'static Expr>) -> Option<&'static Expr> {
+        let mut result = None;
+        for e in exprs {
+            result = result.or(self.add_new_solution(e));
+        }
+        result
+    }
     /// Creates a new asynchronous thread to perform synthesis search using a generated example set. 
     /// This function attempts to generate a candidate example set and, if successful, constructs a new context augmented with these examples to spawn an additional thread executing the synthesis process; otherwise, it logs that no example set is available.
+    /// If the number of active threads then falls below the requested count, a warning is emitted and recorded in `stats`.
     pub fn create_new_thread(&mut self) {
         if let Some(exs) = self.generate_example_set() { 
             info!("Creating new thread with examples {:?}", exs);
-            let ctx2 = self.ctx.with_examples(&exs);
-            self.threads.insert(exs, new_thread(self.cfg.clone(), ctx2));
+            let thread = new_thread_on(self.cfg.clone(), &self.ctx, &exs);
+            self.threads.insert(exs, thread);
         } else {
             info!("No available example set");
+            self.stats.exhausted += 1;
+            let active = self.threads.len();
+            if active < self.stats.requested {
+                if active < self.stats.min_active {
+                    warn!("Only {} of {} threads are active: no example set left to solve.", active, self.stats.requested);
+                }
+                self.stats.min_active = std::cmp::min(self.stats.min_active, active);
+            }
         }
     }
     /// Creates and registers an asynchronous thread that performs exhaustive search over all examples from the current context. 
@@ -261,51 +492,219 @@ impl Solutions {
         // cfg.config.cond_search = true;
         self.threads.insert((0..self.ctx.len).collect_vec(), new_thread(self.cfg.clone(), self.ctx.clone()));
     }
+    /// Creates `n` search threads: one on all the examples, with `all_examples` when there are several, and the others on example sets.
+    /// 
+    /// When checkpoints are written, there is always a thread on all the examples (in addition to a single one on an example set), as only its term bank is recorded (see `checkpoint::record_level`).
+    pub fn create_threads(&mut self, mut n: usize, all_examples: bool) {
+        if n == 0 { return; }
+        if checkpoint::enabled() || (all_examples && n > 1) {
+            self.create_all_search_thread();
+            if n > 1 { n -= 1; }
+        }
+        for _ in 0..n {
+            self.create_new_thread();
+        }
+    }
+    /// Enumerates the terms up to size `k` once on all the examples, and shares them with the threads created from now on, which project them down to their example sets instead of enumerating them again (`--shared-bank`).
+    pub fn share_bank(&self, k: usize) {
+        crate::forward::bridge::share_bank(Executor::enumerate_bank(self.ctx.clone(), self.cfg.clone(), k));
+    }
+    /// Stops all running threads. 
+    /// 
+    /// Aborting a task does not interrupt an executor in the middle of its enumeration, so `EPOCH` is bumped as well to make every executor created so far give up.
+    pub fn stop_threads(&mut self) {
+        EPOCH.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        for v in self.threads.iter() { v.abort(); }
+        self.threads = MappedFutures::new();
+    }
+    /// Replaces the examples with `ctx`, which extends the current ones (e.g. with counterexamples found by CEGIS), so that `solve_loop` can be resumed. 
+    /// 
+    /// Running threads are stopped through `EPOCH`, since their example sets refer to the old context; the caller is expected to create new ones. 
+    /// Stored solutions and conditions are kept, but re-evaluated on the new examples.
+    pub fn extend_examples(&mut self, ctx: Context) {
+        self.stop_threads();
+
+        let solutions = std::mem::take(&mut self.solutions);
+        self.found.clear();
+        self.distinct.clear();
+        self.ctx = ctx;
+        self.solved_examples = Bits::zeros(self.ctx.len);
+        self.tree_hole = vec![Bits::ones(self.ctx.len)];
+        for (e, _) in solutions {
+            if let Some(b) = self.ctx.evaluate(e) {
+                if self.solutions.iter().any(|(_, bits)| b.subset(bits)) { continue; }
+                self.solutions.retain(|(_, bits)| !bits.subset(&b));
+                self.solved_examples.union_assign(&b);
+                self.solutions.push((e, b));
+            }
+        }
+        {
+            let mut lock = CONDITIONS.lock();
+            let old = lock.replace(ConditionTracker::new(self.ctx.clone()));
+            let tracker = lock.as_mut().unwrap();
+            for (e, _) in old.iter().flat_map(|c| c.vec.iter()) {
+                tracker.insert(e);
+            }
+        }
+        self.last_update = time::Instant::now();
+    }
+    /// Interval between two periodic adjustments of `solve_loop`, shortened so that the loop wakes up right at the deadline.
+    fn sleep_duration(&self) -> Duration {
+        let interval = Duration::from_millis(std::cmp::min(self.cfg.config.ite_limit_rate as u64, 2000));
+        match self.deadline {
+            Some(d) => std::cmp::min(interval, d.saturating_duration_since(time::Instant::now())),
+            None => interval,
+        }
+    }
     /// Continuously polls and adapts the synthesis process until a valid expression covering all examples is discovered. 
     /// 
     /// This asynchronous loop concurrently listens for solutions generated by worker threads and performs periodic adaptive adjustments. 
     /// It evaluates incoming candidate expressions, updates and manages the set of current solutions, and dynamically modifies search parameters using time-based adjustments. 
     /// When a complete solution is identified, it aborts remaining threads and returns the synthesized expression.
+    /// With `set_top_k`, threads keep running until enough distinct complete solutions are found, or no example set is left, and the smallest one is returned; `top_solutions` gives all of them.
+    /// If a timeout was set by `set_timeout` and it expires first, the threads are aborted and the smallest complete solution, if any, is returned; otherwise `partial_result` gives the best result found so far.
     /// 
-    pub async fn solve_loop(&mut self) -> &'static Expr {
+    pub async fn solve_loop(&mut self) -> Option<&'static Expr> {
         loop {
+            let sleep = self.sleep_duration();
             select! {
                 result = self.threads.next() => {
                     let (k,v) = result.unwrap();
                     let v = v.expect("Thread Execution Error").alloc_local();
-                    info!("Found a solution {:?} with examples {:?}.", v, k);
+                    info!("Found a solution {} with examples {:?}.", crate::expr::pretty::log(v), k);
                     self.last_update = time::Instant::now();
-                    if let Some(e) = self.add_new_solution(v) {
-                        for v in self.threads.iter() { v.abort(); }
-                        return e;
+                    let result = self.add_new_solution(v);
+                    // The same example set would find the rejected solution again
+                    if self.rejected.contains(v) { self.attempted.insert(k.clone()); }
+                    checkpoint::record_solutions(self.solutions.iter().map(|(e, _)| *e));
+                    log::progress::emit(log::progress::Event::Solution { solutions: self.solutions.len(), solved: self.solved_examples.count_ones() as usize, examples: self.ctx.len });
+                    if let Some(e) = result {
+                        if self.record(e) {
+                            for v in self.threads.iter() { v.abort(); }
+                            return self.best();
+                        }
+                        self.attempted.insert(k);
                     }
                     self.create_new_thread();
+                    if self.threads.is_empty() && !self.found.is_empty() {
+                        info!("No example set left: found {}/{} solutions.", self.found.len(), self.top_k);
+                        return self.best();
+                    }
                 }
-                _ = tokio::time::sleep(Duration::from_millis(std::cmp::min(self.cfg.config.ite_limit_rate as u64, 2000))) => {
+                _ = tokio::time::sleep(sleep) => {
+                    if self.deadline.is_some_and(|d| time::Instant::now() >= d) {
+                        warn!("Timeout: solved {}/{} examples.", self.solved_examples.count_ones(), self.ctx.len);
+                        for v in self.threads.iter() { v.abort(); }
+                        return self.best();
+                    }
+                    if galloc::pressure() >= MemPressure::Hard {
+                        return self.out_of_memory();
+                    }
                     if time::Instant::now() - self.last_update > Duration::from_millis(self.cfg.config.ite_limit_rate as u64 - 10) {
                         info!("Adaptive Adjustment of ITE Limit: {}", self.ite_limit);
                         self.ite_limit += 1;
                         self.last_update = time::Instant::now();
                     }
+                    checkpoint::save_if_due();
+                    log::progress::emit(log::progress::Event::Status {
+                        solutions: self.solutions.len(), solved: self.solved_examples.count_ones() as usize,
+                        examples: self.ctx.len, ite_limit: self.ite_limit, threads: self.threads.len(),
+                    });
                     if let Some(e) = self.generate_result(!self.threads.is_empty()) {
-                        for v in self.threads.iter() { v.abort(); }
-                        return e;
+                        if self.record(e) {
+                            for v in self.threads.iter() { v.abort(); }
+                            return self.best();
+                        }
                     }
                 }
             }
         }
     }
+    /// Gives up enumeration when the memory budget is nearly exhausted (`--mem-limit`). 
+    /// 
+    /// Threads are stopped, and a last tree learning is attempted from the solutions found so far with no ITE limit; if it fails, `None` is returned and `partial_result` gives the best result found so far.
+    fn out_of_memory(&mut self) -> Option<&'static Expr> {
+        warn!("Memory budget exhausted: solved {}/{} examples.", self.solved_examples.count_ones(), self.ctx.len);
+        self.stop_threads();
+        if let Some(e) = self.generate_result(false) {
+            self.record(e);
+        }
+        self.best()
+    }
+    /// Sequential replacement of `solve_loop` for deterministic mode (`--deterministic`).
+    /// 
+    /// Instead of running threads concurrently, each round solves up to the requested number of example sets one after another on the current thread, with a common size limit starting at `DETERMINISTIC_INITIAL_SIZE`.
+    /// Every round without new solution raises both the size limit (up to the one of the grammar) and the ITE limit by one, replacing the time-based adjustments of `solve_loop`.
+    /// Returns `None` when the timeout expires, or when a round at the full size limit finds nothing new; `partial_result` then gives the best result found so far.
+    pub fn solve_deterministic(&mut self) -> Option<&'static Expr> {
+        let mut size_limit = std::cmp::min(DETERMINISTIC_INITIAL_SIZE, self.cfg.config.size_limit);
+        loop {
+            self.attempted.clear();
+            let mut found = false;
+            for _ in 0..std::cmp::max(self.stats.requested, 1) {
+                if self.deadline.is_some_and(|d| time::Instant::now() >= d) {
+                    warn!("Timeout: solved {}/{} examples.", self.solved_examples.count_ones(), self.ctx.len);
+                    return self.best();
+                }
+                if galloc::pressure() >= MemPressure::Hard {
+                    return self.out_of_memory();
+                }
+                let Some(exs) = self.generate_example_set() else { break };
+                info!("Solving examples {:?} up to size {}", exs, size_limit);
+                let mut cfg = self.cfg.clone();
+                cfg.config.size_limit = size_limit;
+                let result = Executor::new(self.ctx.with_examples(&exs), cfg).with_shared_bank(&exs, self.ctx.len).solve_top_with_limit();
+                self.attempted.insert(exs);
+                if let Some(e) = result {
+                    let result = self.add_new_solution(e);
+                    checkpoint::record_solutions(self.solutions.iter().map(|(e, _)| *e));
+                    log::progress::emit(log::progress::Event::Solution { solutions: self.solutions.len(), solved: self.solved_examples.count_ones() as usize, examples: self.ctx.len });
+                    let nfound = self.found.len();
+                    if result.is_some_and(|e| self.record(e)) { return self.best(); }
+                    // A complete solution found again is no progress
+                    found |= result.is_none() || self.found.len() > nfound;
+                }
+            }
+            if self.generate_result(true).is_some_and(|e| self.record(e)) { return self.best(); }
+            if !found {
+                if size_limit >= self.cfg.config.size_limit { return self.best(); }
+                size_limit += 1;
+                self.ite_limit += 1;
+                info!("No new solution: size limit {}, ITE limit {}", size_limit, self.ite_limit);
+            }
+        }
+    }
 }
 
+/// Size limit of the first round of `Solutions::solve_deterministic`.
+const DETERMINISTIC_INITIAL_SIZE: usize = 8;
+
 /// Creates a new asynchronous task that executes a synthesis search using the provided configuration and evaluation context.
 /// 
-/// Spawns a task that initializes a solver executor with the given parameters, logs the deduction configuration, performs a top-blocked search for an expression, and then converts and returns it as the asynchronous task's result.
+/// Spawns a task that initializes a solver executor with the given parameters, logs the deduction configuration, performs a top-level search for an expression, and then converts and returns it as the asynchronous task's result.
+/// If the search is given up (e.g. the examples were extended, see `Solutions::extend_examples`), the task never completes.
 pub fn new_thread(cfg: Cfg, ctx: Context) -> JoinHandle<Expression> {
     tokio::spawn(async move {
-        let exec = Executor::new(ctx, cfg);
-        info!("Deduction Configuration: {:?}", exec.deducers);
-        
-        exec.solve_top_blocked().to_expression()
+        // The executor is not `Send`, so it is dropped before awaiting.
+        let r = {
+            let exec = Executor::new(ctx, cfg);
+            info!("Deduction Configuration: {:?}", exec.deducers);
+            exec.solve_top_with_limit().map(|e| e.to_expression())
+        };
+        if let Some(e) = r { e } else { never!() }
+    })
+}
+
+/// Spawns a search thread on the examples `exs` of `ctx`, replaying the shared term bank computed on `ctx`, if any.
+pub fn new_thread_on(cfg: Cfg, ctx: &Context, exs: &[usize]) -> JoinHandle<Expression> {
+    let (sub, exs, examples) = (ctx.with_examples(exs), exs.to_vec(), ctx.len);
+    tokio::spawn(async move {
+        let r = {
+            let exec = Executor::new(sub, cfg).with_shared_bank(&exs, examples);
+            info!("Deduction Configuration: {:?}", exec.deducers);
+            exec.solve_top_with_limit().map(|e| e.to_expression())
+        };
+        if let Some(e) = r { e } else { never!() }
     })
 }
 
@@ -319,16 +718,224 @@ pub fn cond_search_thread(mut cfg: Cfg, ctx: Context) -> JoinHandle<Expression>
 /// Spawns an asynchronous task that executes a limited search procedure and returns its corresponding expression.
 /// 
 /// Initiates an executor using the provided configuration and context, then attempts to solve the top-level problem with a limit. 
-/// If the search produces a solution, the resulting expression is returned; otherwise, the process is aborted. 
+/// If the search produces a solution, the resulting expression is returned; otherwise, the task ends with `None`. 
 /// The asynchronous execution is managed through the Tokio runtime and the result is encapsulated within a join handle.
-pub fn new_thread_with_limit(cfg: Cfg, ctx: Context) -> JoinHandle<Expression> {
+pub fn new_thread_with_limit(cfg: Cfg, ctx: Context) -> JoinHandle<Option<Expression>> {
     let log_level = log::log_level();
     tokio::spawn(async move {
         log::set_log_level(log_level);
-        if let Some(p) = {     
-            Executor::new(ctx, cfg).solve_top_with_limit().map(|e| e.to_expression())
-        } {
-            p
-        } else { never!() }
+        Executor::new(ctx, cfg).solve_top_with_limit().map(|e| e.to_expression())
     })
-}
\ No newline at end of file
+}
+/// Spawns a limited search as [`new_thread_with_limit`], on a context whose values are allocated in `region`.
+/// 
+/// The region is owned by the thread and popped once the search is over, so that the temporary values are reclaimed even if the deduction waiting for the result is dropped first.
+pub fn new_thread_in_region(cfg: Cfg, ctx: Context, region: galloc::Region) -> JoinHandle<Option<Expression>> {
+    let log_level = log::log_level();
+    tokio::spawn(async move {
+        log::set_log_level(log_level);
+        let result = Executor::new(ctx, cfg).solve_top_with_limit().map(|e| e.to_expression());
+        drop(region);
+        result
+    })
+}
+#[cfg(test)]
+mod tests {
+    use std::{fs, time::Duration};
+
+    use crate::{expr::{cfg::Cfg, context::Context}, galloc::{AllocForAny, AllocForExactSizeIter}, parser::problem::PBEProblem, tree_learning::bits::BoxSliceExt, value::{ConstValue, Value}};
+    use crate::expr;
+
+    use super::{Solutions, CONDITIONS, SOLUTIONS_LOCK};
+
+    #[tokio::test]
+    async fn test_thread_underutilization() {
+        let _guard = SOLUTIONS_LOCK.lock().await;
+        let s = fs::read_to_string("test/test.sl").unwrap();
+        let problem = PBEProblem::parse(s.as_str()).unwrap();
+        let cfg = Cfg::from_synthfun(problem.synthfun());
+        let ctx = Context::new(1, vec![ConstValue::Str("a").value(1)], vec![], ConstValue::Str("b").value(1));
+
+        let mut solutions = Solutions::new(cfg, ctx);
+        solutions.set_requested_threads(2);
+        // Occupy the only example set so that no further thread can be created.
+        solutions.threads.insert(vec![0], tokio::spawn(futures::future::pending()));
+        solutions.create_new_thread();
+
+        assert_eq!(solutions.threads.len(), 1);
+        assert!(solutions.stats.underutilized());
+        assert_eq!(solutions.stats.min_active, 1);
+        assert_eq!(solutions.stats.exhausted, 1);
+
+        for t in solutions.threads.iter() { t.abort(); }
+        *CONDITIONS.lock() = None;
+    }
+
+    #[tokio::test]
+    async fn test_timeout_partial_result() {
+        let _guard = SOLUTIONS_LOCK.lock().await;
+        let s = fs::read_to_string("test/test.sl").unwrap();
+        let problem = PBEProblem::parse(s.as_str()).unwrap();
+        let cfg = Cfg::from_synthfun(problem.synthfun());
+        let input: Value = ["a", "b", "c"].into_iter().galloc_scollect().into();
+        let output: Value = ["a", "x", "c"].into_iter().galloc_scollect().into();
+        let ctx = Context::new(3, vec![input], vec![], output);
+
+        let mut solutions = Solutions::new(cfg, ctx);
+        assert_eq!(solutions.add_new_solution(expr!([0]).galloc()), None);
+        solutions.threads.insert(vec![1], tokio::spawn(futures::future::pending()));
+        solutions.set_timeout(Duration::ZERO);
+        assert_eq!(solutions.solve_loop().await, None);
+
+        let (e, bits) = solutions.partial_result().unwrap();
+        assert_eq!(*e, expr!([0]));
+        assert_eq!(bits.count_ones(), 2);
+        assert_eq!(solutions.coverage_report(&bits), "; Coverage: 2/3 examples, unsolved: [1]");
+
+        for t in solutions.threads.iter() { t.abort(); }
+        *CONDITIONS.lock() = None;
+    }
+
+    #[tokio::test]
+    async fn test_extend_examples() {
+        let _guard = SOLUTIONS_LOCK.lock().await;
+        let s = fs::read_to_string("test/test.sl").unwrap();
+        let problem = PBEProblem::parse(s.as_str()).unwrap();
+        let cfg = Cfg::from_synthfun(problem.synthfun());
+        let input: Value = ["a", "b"].into_iter().galloc_scollect().into();
+        let ctx = Context::new(2, vec![input], vec![], input);
+
+        let mut solutions = Solutions::new(cfg, ctx);
+        assert!(solutions.add_new_solution(expr!([0]).galloc()).is_some());
+        solutions.threads.insert(vec![0], tokio::spawn(futures::future::pending()));
+
+        let input: Value = ["a", "b", "c"].into_iter().galloc_scollect().into();
+        let output: Value = ["a", "b", "x"].into_iter().galloc_scollect().into();
+        solutions.extend_examples(Context::new(3, vec![input], vec![], output));
+        assert!(solutions.threads.is_empty());
+        assert_eq!(solutions.count(), 1);
+        let (_, bits) = solutions.partial_result().unwrap();
+        assert_eq!(solutions.coverage_report(&bits), "; Coverage: 2/3 examples, unsolved: [2]");
+
+        *CONDITIONS.lock() = None;
+    }
+
+    #[tokio::test]
+    async fn test_negative_examples() {
+        let _guard = SOLUTIONS_LOCK.lock().await;
+        let s = fs::read_to_string("test/test.sl").unwrap();
+        let problem = PBEProblem::parse(s.as_str()).unwrap();
+        let cfg = Cfg::from_synthfun(problem.synthfun());
+        let input: Value = ["a", "b"].into_iter().galloc_scollect().into();
+        let neg: Value = ["c"].into_iter().galloc_scollect().into();
+        let ctx = Context::new(2, vec![input], vec![], input).with_negatives(vec![neg], neg);
+        assert!(ctx.matches_negative(&expr!([0])));
+        assert!(!ctx.matches_negative(&expr!(Concat [0] "x")));
+
+        let mut solutions = Solutions::new(cfg, ctx);
+        assert_eq!(solutions.add_new_solution(expr!([0]).galloc()), None);
+        assert_eq!(solutions.count(), 0);
+
+        *CONDITIONS.lock() = None;
+    }
+
+    #[tokio::test]
+    async fn test_negative_tree() {
+        let _guard = SOLUTIONS_LOCK.lock().await;
+        let s = fs::read_to_string("test/test.sl").unwrap();
+        let problem = PBEProblem::parse(s.as_str()).unwrap();
+        let cfg = Cfg::from_synthfun(problem.synthfun());
+        let input: Value = ["a", "b"].into_iter().galloc_scollect().into();
+        let output: Value = ["a", "x"].into_iter().galloc_scollect().into();
+        let neg: Value = ["c"].into_iter().galloc_scollect().into();
+        let ctx = Context::new(2, vec![input], vec![], output).with_negatives(vec![neg], neg);
+
+        let mut solutions = Solutions::new(cfg, ctx);
+        // Room for one `ite`
+        solutions.ite_limit = 3;
+        CONDITIONS.lock().as_mut().unwrap().insert(&expr!(PrefixOf "b" [0]));
+        // Kept although it gives "c" on "c": the tree may not use it there
+        assert_eq!(solutions.add_new_solution(expr!([0]).galloc()), None);
+        assert_eq!(solutions.count(), 1);
+        // The tree (ite (str.prefixof "b" [0]) "x" [0]) gives "c" on "c", so its leaf [0] is dropped and rejected
+        assert_eq!(solutions.add_new_solution(expr!("x").galloc()), None);
+        assert_eq!(solutions.count(), 1);
+        assert_eq!(solutions.add_new_solution(expr!([0]).galloc()), None);
+        assert_eq!(solutions.count(), 1);
+        let e = expr!(Ite (PrefixOf "a" [0]) [0] "x").galloc();
+        assert_eq!(solutions.add_new_solution(e), Some(e));
+
+        *CONDITIONS.lock() = None;
+    }
+
+    #[tokio::test]
+    async fn test_seeded_example_sets() {
+        let _guard = SOLUTIONS_LOCK.lock().await;
+        let s = fs::read_to_string("test/test.sl").unwrap();
+        let problem = PBEProblem::parse(s.as_str()).unwrap();
+        let cfg = Cfg::from_synthfun(problem.synthfun());
+        let input: Value = ["a", "b", "c", "d", "e"].into_iter().galloc_scollect().into();
+        let ctx = Context::new(5, vec![input], vec![], input);
+
+        let mut sets = Vec::new();
+        for _ in 0..2 {
+            let mut solutions = Solutions::new(cfg.clone(), ctx.clone());
+            solutions.set_seed(42);
+            let mut order = Vec::new();
+            while let Some(exs) = solutions.generate_example_set() {
+                solutions.attempted.insert(exs.clone());
+                order.push(exs);
+            }
+            assert_eq!(order.len(), 31);
+            sets.push(order);
+            *CONDITIONS.lock() = None;
+        }
+        assert_eq!(sets[0], sets[1]);
+    }
+
+    #[tokio::test]
+    async fn test_holdout() {
+        let _guard = SOLUTIONS_LOCK.lock().await;
+        let s = fs::read_to_string("test/test.sl").unwrap();
+        let problem = PBEProblem::parse(s.as_str()).unwrap();
+        let cfg = Cfg::from_synthfun(problem.synthfun());
+        let input: Value = ["a", "b"].into_iter().galloc_scollect().into();
+        let ctx = Context::new(2, vec![input], vec![], input);
+        let held: Value = ["ab"].into_iter().galloc_scollect().into();
+        let held_output: Value = ["a"].into_iter().galloc_scollect().into();
+
+        let mut solutions = Solutions::new(cfg, ctx);
+        solutions.set_holdout(Context::new(1, vec![held], vec![], held_output));
+        assert_eq!(solutions.add_new_solution(expr!([0]).galloc()), None);
+        assert_eq!(solutions.count(), 0);
+        assert!(solutions.rejected.contains(&expr!([0])));
+        let e = expr!(SubStr [0] 0 1).galloc();
+        assert_eq!(solutions.add_new_solution(e), Some(e));
+        *CONDITIONS.lock() = None;
+    }
+
+    #[tokio::test]
+    async fn test_top_k() {
+        let _guard = SOLUTIONS_LOCK.lock().await;
+        let s = fs::read_to_string("test/test.sl").unwrap();
+        let problem = PBEProblem::parse(s.as_str()).unwrap();
+        let cfg = Cfg::from_synthfun(problem.synthfun());
+        let input: Value = ["a", "b"].into_iter().galloc_scollect().into();
+        let ctx = Context::new(2, vec![input], vec![], input);
+
+        let mut solutions = Solutions::new(cfg, ctx);
+        solutions.set_top_k(2);
+        let e1 = expr!(Concat [0] "").galloc();
+        let e2 = expr!([0]).galloc();
+        let e3 = expr!(SubStr [0] 0 1).galloc();
+        assert_eq!(solutions.add_new_solution(e1), Some(e1));
+        assert!(!solutions.record(e1));
+        // Same as `e1` once simplified
+        assert!(!solutions.record(e2));
+        assert_eq!(solutions.add_new_solution(e3), Some(e3));
+        assert!(solutions.record(e3));
+        assert_eq!(solutions.top_solutions(), vec![e1, e3]);
+        assert_eq!(solutions.count(), 0);
+        *CONDITIONS.lock() = None;
+    }
+}
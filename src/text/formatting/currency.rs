@@ -0,0 +1,175 @@
+use regex::Regex;
+
+use crate::forward::enumeration::Enumerator1;
+use crate::parser::config::Config;
+use crate::text::parsing::{parse_amount, Amount, AMOUNT_REGEX};
+use crate::utils::F64;
+use crate::value::{ConstValue, Value};
+
+use crate::galloc::{AllocForExactSizeIter, AllocForStr};
+
+use super::FormattingOp;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FormatCurrency {
+    cost: usize,
+    symbol: &'static str,
+    suffix: bool,
+    space: bool,
+    decimal: char,
+    thousands: Option<char>,
+    digits: usize,
+    parens: Option<bool>,
+    /// Whether an amount of at least 1000 was seen, so that the thousands separator is known.
+    large: bool,
+}
+
+impl FormatCurrency {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            cost: config.get_usize("cost").unwrap_or(1),
+            symbol: config.get_str("symbol").unwrap_or("$"),
+            suffix: config.get_bool("suffix").unwrap_or(false),
+            space: config.get_bool("space").unwrap_or(false),
+            decimal: config.get_str("decimal").and_then(|s| s.chars().next()).unwrap_or('.'),
+            thousands: config.get_str("thousands").map_or(Some(','), |s| s.chars().next()),
+            digits: config.get_usize("digits").unwrap_or(2),
+            parens: config.get_bool("parens"),
+            large: true,
+        }
+    }
+    fn get_format(amount: &Amount) -> Self {
+        let decimal = amount.decimal.unwrap_or(if amount.thousands == Some('.') { ',' } else { '.' });
+        Self {
+            cost: 1,
+            symbol: amount.symbol,
+            suffix: amount.suffix,
+            space: amount.space,
+            decimal,
+            thousands: amount.thousands,
+            digits: amount.digits,
+            parens: (amount.value < 0.0).then_some(amount.parens),
+            large: amount.value.abs() >= 1000.0,
+        }
+    }
+    pub fn format_single(&self, value: F64) -> String {
+        let value = *value;
+        let s = format!("{:.*}", self.digits, value.abs());
+        let (int, frac) = s.split_once('.').unwrap_or((s.as_str(), ""));
+        let mut number = String::new();
+        for (i, c) in int.chars().enumerate() {
+            if i > 0 && (int.len() - i) % 3 == 0 {
+                if let Some(t) = self.thousands { number.push(t); }
+            }
+            number.push(c);
+        }
+        if !frac.is_empty() {
+            number.push(self.decimal);
+            number += frac;
+        }
+        let sp = if self.space { " " } else { "" };
+        let body = if self.suffix { format!("{number}{sp}{}", self.symbol) } else { format!("{}{sp}{number}", self.symbol) };
+        match (value < 0.0, self.parens) {
+            (true, Some(true)) => format!("({body})"),
+            (true, _) => format!("-{body}"),
+            (false, _) => body,
+        }
+    }
+    /// Returns the equivalent spreadsheet `TEXT` format code, e.g. `\$#,##0.00;(\$#,##0.00)`.
+    pub fn excel_format(&self) -> String {
+        let int = if self.thousands.is_some() { "#,##0" } else { "0" };
+        let number = if self.digits > 0 { format!("{int}.{}", "0".repeat(self.digits)) } else { int.to_string() };
+        let sp = if self.space { " " } else { "" };
+        let body = if self.suffix { format!("{number}{sp}\\{}", self.symbol) } else { format!("\\{}{sp}{number}", self.symbol) };
+        if self.parens == Some(true) { format!("{body};({body})") } else { format!("{body};-{body}") }
+    }
+    /// The decimal and thousands separators, e.g. `('.', Some(','))` for `$1,234.56`.
+    pub fn separators(&self) -> (char, Option<char>) { (self.decimal, self.thousands) }
+    /// The currency symbol, whether it follows the number, and whether a space separates them.
+    pub fn symbol(&self) -> (&'static str, bool, bool) { (self.symbol, self.suffix, self.space) }
+    /// Number of digits after the decimal separator.
+    pub fn digits(&self) -> usize { self.digits }
+    /// Whether negative amounts are written in parentheses rather than with a minus sign.
+    pub fn parens(&self) -> bool { self.parens == Some(true) }
+    pub fn name() -> &'static str {
+        "currency.fmt"
+    }
+}
+
+impl std::fmt::Display for FormatCurrency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let thousands = self.thousands.map(String::from).unwrap_or_default();
+        write!(f, "currency.fmt #symbol:{:?} #decimal:{:?} #thousands:{:?} #digits:{}", self.symbol, self.decimal.to_string(), thousands, self.digits)?;
+        if self.suffix { write!(f, " #suffix:true")?; }
+        if self.space { write!(f, " #space:true")?; }
+        if let Some(p) = self.parens { write!(f, " #parens:{}", p)?; }
+        Ok(())
+    }
+}
+
+impl Default for FormatCurrency {
+    fn default() -> Self {
+        Self::from_config(&Default::default())
+    }
+}
+
+impl Enumerator1 for FormatCurrency {
+    fn enumerate(&self, this: &'static crate::expr::ops::Op1Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 1]) -> Result<(), ()> { Ok(()) }
+}
+
+crate::impl_formatop!(FormatCurrency, Float, |this: &FormatCurrency| this.cost);
+
+impl FormattingOp for FormatCurrency {
+    fn format(&self, input: &'static str) -> Option<(Self, ConstValue, &'static str)> {
+        let regex = Regex::new(&format!("^(?:{})", AMOUNT_REGEX)).unwrap();
+        let m = regex.find(input)?;
+        let amount = parse_amount(m.as_str())?;
+        let op = Self::get_format(&amount);
+        // Amounts written in another way than `format_single` would (e.g. `$-5`) cannot be produced.
+        if op.format_single(F64::new(amount.value)) != m.as_str() { return None; }
+        Some((op, F64::new(amount.value).into(), &input[m.end()..]))
+    }
+
+    fn union(self, other: Self) -> Option<Self> {
+        if (self.symbol, self.suffix, self.space, self.digits) != (other.symbol, other.suffix, other.space, other.digits) { return None; }
+        if self.digits > 0 && self.decimal != other.decimal { return None; }
+        if self.large && other.large && self.thousands != other.thousands { return None; }
+        let thousands = if self.large { self.thousands } else { other.thousands };
+        if thousands == Some(self.decimal) && self.digits > 0 { return None; }
+        let parens = match (self.parens, other.parens) {
+            (Some(a), Some(b)) if a != b => return None,
+            (a, b) => a.or(b),
+        };
+        Some(Self { cost: 1, thousands, parens, large: self.large || other.large, ..self })
+    }
+
+    fn bad_value() -> ConstValue {
+        ConstValue::Float(0.0.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{text::formatting::FormattingOp, utils::F64};
+
+    use super::FormatCurrency;
+
+    #[test]
+    fn test_format_currency() {
+        for s in ["$1,234.56", "($1,234.56)", "-$0.50", "1.234.567,89 €", "£12"] {
+            let (op, v, rest) = FormatCurrency::default().format(s).unwrap();
+            assert_eq!(op.format_single(v.as_float().unwrap()), s);
+            assert!(rest.is_empty());
+        }
+        assert!(FormatCurrency::default().format("$-5.00").is_none());
+
+        let (a, _, _) = FormatCurrency::default().format("$5.00").unwrap();
+        let (b, _, rest) = FormatCurrency::default().format("$1,000.00 paid").unwrap();
+        assert_eq!(rest, " paid");
+        let op = a.union(b).unwrap();
+        assert_eq!(op.format_single(F64::new(-2500.0)), "-$2,500.00");
+        let (c, _, _) = FormatCurrency::default().format("($7.25)").unwrap();
+        assert_eq!(op.union(c).unwrap().format_single(F64::new(-2500.0)), "($2,500.00)");
+        let (d, _, _) = FormatCurrency::default().format("€1.000,00").unwrap();
+        assert!(op.union(d).is_none());
+    }
+}
@@ -0,0 +1,90 @@
+use regex::Regex;
+
+use crate::forward::enumeration::Enumerator1;
+use crate::parser::config::Config;
+use crate::text::parsing::{find_digit_groups, DIGIT_GROUPS_REGEX};
+use crate::value::{ConstValue, Value};
+
+use crate::galloc::{AllocForExactSizeIter, AllocForStr};
+
+use super::FormattingOp;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Writes a string of digits in a pattern, where each `#` stands for the next digit, e.g. `(###) ###-####` for phone numbers.
+pub struct FormatDigits {
+    cost: usize,
+    pattern: &'static str,
+}
+
+impl FormatDigits {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            cost: config.get_usize("cost").unwrap_or(1),
+            pattern: config.get_str("pattern").unwrap_or("###-###-####"),
+        }
+    }
+    /// Fills the pattern with the digits of `s`, or returns an empty string if `s` does not have as many digits as the pattern.
+    pub fn format_single(&self, s: &str) -> String {
+        if !s.chars().all(|c| c.is_ascii_digit()) || s.len() != self.pattern.matches('#').count() { return String::new(); }
+        let mut digits = s.chars();
+        self.pattern.chars().map(|c| if c == '#' { digits.next().unwrap() } else { c }).collect()
+    }
+    pub fn pattern(&self) -> &'static str { self.pattern }
+    pub fn name() -> &'static str {
+        "digits.fmt"
+    }
+}
+
+impl std::fmt::Display for FormatDigits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "digits.fmt #pattern:{:?}", self.pattern)
+    }
+}
+
+impl Default for FormatDigits {
+    fn default() -> Self {
+        Self::from_config(&Default::default())
+    }
+}
+
+impl Enumerator1 for FormatDigits {
+    fn enumerate(&self, this: &'static crate::expr::ops::Op1Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 1]) -> Result<(), ()> { Ok(()) }
+}
+
+crate::impl_formatop!(FormatDigits, Str, |this: &FormatDigits| this.cost);
+
+impl FormattingOp for FormatDigits {
+    fn format(&self, input: &'static str) -> Option<(Self, ConstValue, &'static str)> {
+        let regex = Regex::new(&format!("^(?:{})", DIGIT_GROUPS_REGEX)).unwrap();
+        let m = regex.find(input)?;
+        let (_, digits, pattern) = find_digit_groups(m.as_str()).next()?;
+        Some((Self { cost: 1, pattern: pattern.galloc_str() }, ConstValue::Str(digits.galloc_str()), &input[m.end()..]))
+    }
+
+    fn union(self, other: Self) -> Option<Self> {
+        if self.pattern != other.pattern { return None; }
+        Some(self)
+    }
+
+    fn bad_value() -> ConstValue {
+        ConstValue::Str("")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{text::formatting::FormattingOp, value::ConstValue};
+
+    use super::FormatDigits;
+
+    #[test]
+    fn test_format_digits() {
+        let (op, v, rest) = FormatDigits::default().format("(555) 123-4567 (home)").unwrap();
+        assert_eq!((v, rest), (ConstValue::Str("5551234567"), " (home)"));
+        assert_eq!(op.pattern(), "(###) ###-####");
+        assert_eq!(op.format_single("9382425041"), "(938) 242-5041");
+        assert_eq!(op.format_single("938242504"), "");
+        let (ssn, _, _) = FormatDigits::default().format("938-24-2504").unwrap();
+        assert!(op.union(ssn).is_none());
+        assert_eq!(ssn.format_single("123456789"), "123-45-6789");
+    }
+}
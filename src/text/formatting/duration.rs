@@ -0,0 +1,142 @@
+use regex::Regex;
+
+use crate::forward::enumeration::Enumerator1;
+use crate::parser::config::Config;
+use crate::value::{ConstValue, Value};
+
+use crate::galloc::{AllocForExactSizeIter, AllocForStr};
+
+use super::FormattingOp;
+
+/// Letters of the units of a duration, from the largest, with their lengths in seconds.
+const UNITS: [(char, i64); 3] = [('h', 3600), ('m', 60), ('s', 1)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Writes a duration in seconds with units, e.g. `1h 23m`.
+pub struct FormatDuration {
+    cost: usize,
+    /// Which of hours, minutes and seconds are written; the largest one takes the whole part of the duration above it (`83m`).
+    units: [bool; 3],
+    /// Whether the parts are separated by a space (unknown until two parts are seen)
+    space: Option<bool>,
+}
+
+impl FormatDuration {
+    pub fn from_config(config: &Config) -> Self {
+        let units = config.get_str("units").unwrap_or("hm");
+        Self {
+            cost: config.get_usize("cost").unwrap_or(1),
+            units: UNITS.map(|(u, _)| units.contains(u)),
+            space: config.get_bool("space"),
+        }
+    }
+    /// Writes `secs` in the units of the format, dropping what is below the smallest one.
+    pub fn format_single(&self, secs: i64) -> String {
+        let mut rest = secs.unsigned_abs() as i64;
+        let mut parts = Vec::new();
+        for ((u, len), shown) in UNITS.into_iter().zip(self.units) {
+            if shown {
+                parts.push(format!("{}{u}", rest / len));
+                rest %= len;
+            }
+        }
+        let body = parts.join(if self.space == Some(false) { "" } else { " " });
+        if secs < 0 { format!("-{body}") } else { body }
+    }
+    /// Letters of the units written, e.g. `hm`.
+    pub fn units(&self) -> String {
+        UNITS.into_iter().zip(self.units).filter(|(_, shown)| *shown).map(|((u, _), _)| u).collect()
+    }
+    /// Whether the parts are separated by a space.
+    pub fn space(&self) -> bool { self.space != Some(false) }
+    pub fn name() -> &'static str {
+        "duration.fmt"
+    }
+}
+
+impl std::fmt::Display for FormatDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "duration.fmt #units:{:?}", self.units())?;
+        if let Some(s) = self.space { write!(f, " #space:{}", s)?; }
+        Ok(())
+    }
+}
+
+impl Default for FormatDuration {
+    fn default() -> Self {
+        Self::from_config(&Default::default())
+    }
+}
+
+impl Enumerator1 for FormatDuration {
+    fn enumerate(&self, this: &'static crate::expr::ops::Op1Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 1]) -> Result<(), ()> { Ok(()) }
+}
+
+crate::impl_formatop!(FormatDuration, Int, |this: &FormatDuration| this.cost);
+
+impl FormattingOp for FormatDuration {
+    fn format(&self, input: &'static str) -> Option<(Self, ConstValue, &'static str)> {
+        let part = Regex::new(r"^(\d+)([hms])").unwrap();
+        let (mut rest, mut value, mut op) = (input, 0, Self { cost: 1, units: [false; 3], space: None });
+        let mut last: Option<usize> = None;
+        loop {
+            let (sp, after) = match rest.strip_prefix(' ') {
+                Some(r) if last.is_some() => (true, r),
+                _ => (false, rest),
+            };
+            let Some(c) = part.captures(after) else { break };
+            let u = UNITS.iter().position(|(l, _)| c[2].starts_with(*l)).unwrap();
+            if last.is_some_and(|l| u <= l) { break; }
+            if last.is_some() {
+                if op.space.is_some_and(|s| s != sp) { break; }
+                op.space = Some(sp);
+            }
+            op.units[u] = true;
+            value += c[1].parse::<i64>().ok()? * UNITS[u].1;
+            last = Some(u);
+            rest = &after[c[0].len()..];
+        }
+        last?;
+        // Not a unit but the start of a word, as in `5 months` or `3min`.
+        if rest.starts_with(char::is_alphabetic) { return None; }
+        let whole = &input[..input.len() - rest.len()];
+        // Parts out of range (`1h 75m`) are not written back by `format_single`.
+        if op.format_single(value) != whole { return None; }
+        Some((op, ConstValue::Int(value), rest))
+    }
+
+    fn union(self, other: Self) -> Option<Self> {
+        if self.units != other.units { return None; }
+        let space = match (self.space, other.space) {
+            (Some(a), Some(b)) if a != b => return None,
+            (a, b) => a.or(b),
+        };
+        Some(Self { space, ..self })
+    }
+
+    fn bad_value() -> ConstValue {
+        ConstValue::Int(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{text::formatting::FormattingOp, value::ConstValue};
+
+    use super::FormatDuration;
+
+    #[test]
+    fn test_format_duration() {
+        let (op, v, rest) = FormatDuration::default().format("1h 23m elapsed").unwrap();
+        assert_eq!((v, rest), (ConstValue::Int(4980), " elapsed"));
+        assert_eq!(op.format_single(7265), "2h 1m");
+        let (minutes, v, _) = FormatDuration::default().format("83m").unwrap();
+        assert_eq!(v, ConstValue::Int(4980));
+        assert!(op.union(minutes).is_none());
+        let (compact, _, _) = FormatDuration::default().format("0h5m").unwrap();
+        assert_eq!(op.union(compact), None);
+        assert!(FormatDuration::default().format("1h 75m").is_none());
+        assert!(FormatDuration::default().format("5 months").is_none());
+        assert!(FormatDuration::default().format("3min").is_none());
+    }
+}
@@ -9,12 +9,63 @@ use crate::{ impl_name, impl_op1, parser::config::Config};
 
 use crate::galloc::{AllocForExactSizeIter, AllocForStr};
 
-use super::FormattingOp;
+use super::{FormattingOp, Locale};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Layout of a number in scientific notation, e.g. `1.20E+03` or `1.2e-3`.
+pub struct Exponent {
+    /// Digits of the mantissa after the decimal point
+    pub digits: usize,
+    /// Whether the exponent is introduced by `E` rather than `e`
+    pub upper: bool,
+    /// Whether a non-negative exponent is written with `+` (unknown until one is seen)
+    pub plus: Option<bool>,
+    /// Number of digits the exponent is padded to with zeros
+    pub width: usize,
+    /// Fewest digits of an exponent seen, which the padding may not exceed
+    min_width: usize,
+}
+
+impl Exponent {
+    fn from_config(config: &Config) -> Option<Self> {
+        Some(Self {
+            digits: config.get_usize("exp_digits")?,
+            upper: config.get_bool("exp_upper").unwrap_or(false),
+            plus: config.get_bool("exp_plus"),
+            width: config.get_usize("exp_width").unwrap_or(0),
+            min_width: usize::MAX,
+        })
+    }
+    fn format_single(&self, value: f64) -> String {
+        let s = format!("{:.*e}", self.digits, value);
+        let (mantissa, exp) = s.split_once('e').unwrap();
+        let (sign, exp) = match exp.strip_prefix('-') {
+            Some(e) => ("-", e),
+            None => (if self.plus == Some(true) { "+" } else { "" }, exp),
+        };
+        format!("{mantissa}{}{sign}{exp:0>width$}", if self.upper { 'E' } else { 'e' }, width = self.width)
+    }
+    fn union(self, other: Self) -> Option<Self> {
+        if (self.digits, self.upper) != (other.digits, other.upper) { return None; }
+        let plus = match (self.plus, other.plus) {
+            (Some(a), Some(b)) if a != b => return None,
+            (a, b) => a.or(b),
+        };
+        let width = conflict(self.width, other.width)?;
+        let min_width = min(self.min_width, other.min_width);
+        if width > min_width { return None; }
+        Some(Self { plus, width, min_width, ..self })
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct FormatFloat{
     cost: usize,
     padding: (usize, usize),
     min_size : (usize, usize),
+    /// Scientific notation, instead of the padded decimal notation
+    exponent: Option<Exponent>,
+    locale: Locale,
 }
 
 impl FormatFloat {
@@ -22,25 +73,35 @@ impl FormatFloat {
         Self{
             cost: config.get_usize("cost").unwrap_or(1),
             padding: (config.get_usize("left").unwrap_or(0), config.get_usize("right").unwrap_or(0)),
-            min_size: (0, 0)
+            min_size: (0, 0),
+            exponent: Exponent::from_config(config),
+            locale: Locale::from_config(config),
         }
     }
     pub fn format_single(&self, value: F64) -> String {
         let value = *value;
+        if let Some(e) = &self.exponent { return e.format_single(value); }
         let value_int = if value >= 0.0 { value.floor() } else { value.ceil() };
         let left = if self.padding.0 > 0 {
             format!("{:0left$}", value_int, left= self.padding.0)
         } else { format!("{}", value_int) };
 
-        if let Some(mut right) = format!("{}", value).split_once('.').map(|x| x.1.to_string()) {
+        let s = if let Some(mut right) = format!("{}", value).split_once('.').map(|x| x.1.to_string()) {
             while right.len() < self.padding.1 {
                 right.push('0');
             }
             left + "." + &right
         } else if self.padding.1 > 0 {
             left + "." + &"0".repeat(self.padding.1)
-        } else { left }
+        } else { left };
+        self.locale.localize(&s)
     }
+    /// Minimum number of digits before and after the decimal point, padded with zeros.
+    pub fn padding(&self) -> (usize, usize) { self.padding }
+    /// Layout of the scientific notation, if the numbers are written in it.
+    pub fn exponent(&self) -> Option<&Exponent> { self.exponent.as_ref() }
+    /// Decimal mark and thousands separator of the numbers in decimal notation.
+    pub fn locale(&self) -> &Locale { &self.locale }
     pub fn get_format(input: &str) -> Self {
         let endzero = input.ends_with("0") && input.contains(".");
         let startzero = input.starts_with("+0") || input.starts_with("-0") || input.starts_with("0");
@@ -48,7 +109,19 @@ impl FormatFloat {
         let min_right = input.chars().position(|x| x == '.').map(|x| input.len() - 1 - x).unwrap_or(0);
         let before_dot = if startzero { min_left } else { 0 };
         let after_dot = if endzero { min_right } else { 0 };
-        Self { cost: 1, padding: (before_dot, after_dot), min_size: (min_left, min_right) }
+        let locale = Locale::read(input.contains('.').then_some('.'), None, min_left - usize::from(input.starts_with(['-', '+'])));
+        Self { cost: 1, padding: (before_dot, after_dot), min_size: (min_left, min_right), exponent: None, locale }
+    }
+    /// Reads the layout of a number in scientific notation, from its mantissa digits after the point, its exponent letter, sign and digits.
+    fn get_scientific_format(frac: &str, letter: &str, sign: &str, exp: &str) -> Self {
+        let exponent = Exponent {
+            digits: frac.len(),
+            upper: letter == "E",
+            plus: match sign { "+" => Some(true), "-" => None, _ => Some(false) },
+            width: if exp.len() > 1 && exp.starts_with('0') { exp.len() } else { 0 },
+            min_width: exp.len(),
+        };
+        Self { cost: 1, padding: (0, 0), min_size: (0, 0), exponent: Some(exponent), locale: Locale::read(None, None, 0) }
     }
 }
 
@@ -60,7 +133,12 @@ impl FormatFloat {
 
 impl std::fmt::Display for FormatFloat {
     fn fmt(&self,f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "float.fmt #left:{} #right:{}", self.padding.0, self.padding.1)
+        write!(f, "float.fmt #left:{} #right:{}", self.padding.0, self.padding.1)?;
+        if let Some(e) = &self.exponent {
+            write!(f, " #exp_digits:{} #exp_upper:{} #exp_width:{}", e.digits, e.upper, e.width)?;
+            if let Some(p) = e.plus { write!(f, " #exp_plus:{}", p)?; }
+        }
+        self.locale.fmt_config(f)
     }
 }
 
@@ -78,6 +156,25 @@ crate::impl_formatop!(FormatFloat, Float, |this: &FormatFloat| this.cost);
 
 impl FormattingOp for FormatFloat {
     fn format(&self, input: &'static str) -> Option<(Self, crate::value::ConstValue, &'static str)> {
+        let scientific = Regex::new(r"^-?\d(?:\.(\d+))?([eE])([-+]?)(\d+)").unwrap();
+        if let Some(caps) = scientific.captures(input) {
+            let op = Self::get_scientific_format(caps.get(1).map_or("", |m| m.as_str()), &caps[2], &caps[3], &caps[4]);
+            let value = F64::new(caps[0].parse::<f64>().ok()?);
+            // Numbers written in another way than `format_single` would (e.g. `12.5e3`, not normalized) cannot be produced.
+            if op.format_single(value) == caps[0] {
+                return Some((op, value.into(), &input[caps[0].len()..]));
+            }
+        }
+        // Without a comma, `1.234` is read as a decimal number rather than as 1234 grouped by a dot.
+        let grouped = Regex::new(r"^\-?\d+(?:[.,]\d+)+").unwrap();
+        if let Some(a) = grouped.find(input).filter(|a| a.as_str().contains(',')) {
+            let start = usize::from(a.as_str().starts_with('-'));
+            if let Some((plain, locale)) = Locale::scan(&a.as_str()[start..]) {
+                let number = a.as_str()[..start].to_string() + &plain;
+                let cv: ConstValue = F64::new(number.parse::<f64>().ok()?).into();
+                return Some((Self { locale, ..Self::get_format(&number) }, cv, &input[a.as_str().len()..]));
+            }
+        }
         let regex = Regex::new(r"^\-?\d+(\.\d*)?".to_string().as_str()).unwrap();
         if let Some(a) = regex.find(input) {
             if a.as_str().ends_with(".") { return None; }
@@ -89,13 +186,19 @@ impl FormattingOp for FormatFloat {
     }
 
     fn union(self, other: Self) -> Option<Self> {
+        match (self.exponent, other.exponent) {
+            (Some(a), Some(b)) => return Some(Self { exponent: Some(a.union(b)?), ..self }),
+            (None, None) => (),
+            _ => return None,
+        }
+        let locale = self.locale.union(other.locale)?;
         let left = conflict(self.padding.0, other.padding.0)?;
         let right = conflict(self.padding.1, other.padding.1)?;
         let min_left = min(self.min_size.0, other.min_size.0);
         let min_right = min(self.min_size.1, other.min_size.1);
         if left > min_left { return None; }
         if right > min_right { return None; }
-        Some(Self{ cost: 1, padding: (left, right), min_size: (min_left, min_right)})
+        Some(Self{ cost: 1, padding: (left, right), min_size: (min_left, min_right), exponent: None, locale })
     }
 
     fn bad_value() -> crate::value::ConstValue {
@@ -110,7 +213,7 @@ fn conflict(a: usize, b: usize) -> Option<usize> {
 
 #[cfg(test)]
 mod tests {
-    use crate::text::formatting::FormatFloat;
+    use crate::{text::formatting::{FormatFloat, FormattingOp}, utils::F64};
 
     #[test]
     fn format() {
@@ -127,4 +230,38 @@ mod tests {
         let a = "-1234000.0101000";
         assert_eq!(FormatFloat::get_format(a).format_single(a.parse::<f64>().unwrap().into()), a);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_scientific() {
+        for s in ["1.2e-3", "1.20E+03", "-4.5E-07", "6e5"] {
+            let (op, v, rest) = FormatFloat::default().format(s).unwrap();
+            assert_eq!(op.format_single(v.as_float().unwrap()), s);
+            assert!(rest.is_empty());
+        }
+        let (a, _, _) = FormatFloat::default().format("1.20E+03").unwrap();
+        let (b, _, rest) = FormatFloat::default().format("3.50E+12 kg").unwrap();
+        assert_eq!(rest, " kg");
+        let op = a.union(b).unwrap();
+        assert_eq!(op.format_single(F64::new(0.5)), "5.00E-01");
+        let (c, _, _) = FormatFloat::default().format("1.2e-3").unwrap();
+        assert!(op.union(c).is_none());
+        let (d, _, _) = FormatFloat::default().format("1.25E+5").unwrap();
+        assert!(a.union(d).is_none());
+        assert!(FormatFloat::default().format("12.5e3").unwrap().0.exponent().is_none());
+    }
+
+    #[test]
+    fn test_locale() {
+        let (a, v, rest) = FormatFloat::default().format("1.234,56 kg").unwrap();
+        assert_eq!((v.as_float().unwrap(), rest), (F64::new(1234.56), " kg"));
+        let (b, _, _) = FormatFloat::default().format("3,5").unwrap();
+        let op = a.union(b).unwrap();
+        assert_eq!(op.format_single(F64::new(1234567.5)), "1.234.567,5");
+        let (c, _, _) = FormatFloat::default().format("2.5").unwrap();
+        assert!(op.union(c).is_none());
+        let (d, _, _) = FormatFloat::default().format("12").unwrap();
+        assert_eq!(b.union(d).unwrap().format_single(F64::new(0.25)), "0,25");
+        assert_eq!(FormatFloat::default().format("1,2,3").unwrap().1.as_float().unwrap(), F64::new(1.0));
+        assert_eq!(FormatFloat::default().format("0.125").unwrap().1.as_float().unwrap(), F64::new(0.125));
+    }
+}
@@ -9,23 +9,28 @@ use crate::{ impl_name, impl_op1, parser::config::Config};
 
 use crate::galloc::{AllocForExactSizeIter, AllocForStr};
 
-use super::FormattingOp;
+use super::{FormattingOp, Locale};
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct FormatInt(usize, usize);
+pub struct FormatInt(usize, usize, Locale);
 
 impl FormatInt {
     pub fn from_config(config: &Config) -> Self {
-        Self(config.get_usize("cost").unwrap_or(1), config.get_usize("width").unwrap_or(1))
+        Self(config.get_usize("cost").unwrap_or(1), config.get_usize("width").unwrap_or(1), Locale::from_config(config))
     }
     pub fn format_single(&self, value: i64) -> String {
-        if self.1 > 0 {
+        let s = if self.1 > 0 {
             format!("{:0left$}", value, left= self.1)
-        } else { format!("{}", value) }
+        } else { format!("{}", value) };
+        self.2.localize(&s)
     }
+    /// Minimum number of digits, padded with zeros.
+    pub fn width(&self) -> usize { self.1 }
+    /// Thousands separator of the numbers (their decimal mark is unused).
+    pub fn locale(&self) -> &Locale { &self.2 }
     pub fn get_format(input: &str) -> Self {
         let startzero = input.starts_with("+0") || input.starts_with("-0") || input.starts_with("0");
         let before_dot = if startzero { input.len() } else { 0 };
-        Self(1, before_dot)
+        Self(1, before_dot, Locale::read(None, None, input.chars().filter(char::is_ascii_digit).count()))
     }
 }
 
@@ -37,7 +42,8 @@ impl FormatInt {
 
 impl std::fmt::Display for FormatInt {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "int.fmt #left:{}", self.1)
+        write!(f, "int.fmt #left:{}", self.1)?;
+        self.2.fmt_config(f)
     }
 }
 
@@ -60,6 +66,15 @@ fn conflict(a: usize, b: usize) -> Option<usize> {
 
 impl FormattingOp for FormatInt {
     fn format(&self, input: &'static str) -> Option<(Self, crate::value::ConstValue, &'static str)> {
+        let grouped = Regex::new(r"^ *(\-|\+)? *\d+(?:[.,]\d{3})+").unwrap();
+        if let Some(a) = grouped.find(input) {
+            let start = a.as_str().find(|c: char| c.is_ascii_digit()).unwrap();
+            if let Some((plain, locale)) = Locale::scan(&a.as_str()[start..]).filter(|(p, _)| !p.contains('.')) {
+                let number = a.as_str()[..start].to_string() + &plain;
+                let cv: ConstValue = number.parse::<i64>().ok()?.into();
+                return Some((Self(1, Self::get_format(&number).1, locale), cv, &input[a.as_str().len()..]));
+            }
+        }
         let regex = Regex::new(r"^ *(\-|\+)? *\d+".to_string().as_str()).unwrap();
         if let Some(a) = regex.find(input) {
             let cv: ConstValue = a.as_str().parse::<i64>().ok()?.into();
@@ -68,7 +83,7 @@ impl FormattingOp for FormatInt {
     }
 
     fn union(self, other: Self) -> Option<Self> {
-        Some(Self(1, conflict(self.1, other.1)?))
+        Some(Self(1, conflict(self.1, other.1)?, self.2.union(other.2)?))
     }
 
     fn bad_value() -> crate::value::ConstValue {
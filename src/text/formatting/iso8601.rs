@@ -0,0 +1,113 @@
+use crate::forward::enumeration::Enumerator1;
+use crate::parser::config::Config;
+use crate::text::parsing::{find_stamps, from_millis};
+use crate::value::{ConstValue, Value};
+
+use crate::galloc::{AllocForExactSizeIter, AllocForStr};
+
+use super::FormattingOp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Writes a timestamp in milliseconds since the Unix epoch (see `iso.parse`) as an ISO 8601 stamp in UTC, e.g. `2021-05-03T12:30:45.250Z`.
+pub struct FormatIso8601 {
+    cost: usize,
+    /// Separator between the date and the time, or `None` to write the date alone
+    sep: Option<char>,
+    seconds: bool,
+    /// Number of digits of the fraction of a second, at most three
+    frac: usize,
+    /// Whether the stamp ends with `Z`
+    zone: bool,
+}
+
+impl FormatIso8601 {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            cost: config.get_usize("cost").unwrap_or(1),
+            sep: config.get_str("sep").unwrap_or("T").chars().next(),
+            seconds: config.get_bool("seconds").unwrap_or(true),
+            frac: config.get_usize("frac").unwrap_or(0).min(3),
+            zone: config.get_bool("zone").unwrap_or(true),
+        }
+    }
+    pub fn format_single(&self, millis: i64) -> String {
+        let Some((date, time)) = from_millis(millis) else { return String::new() };
+        let mut result = date.format("%Y-%m-%d").to_string();
+        if let Some(sep) = self.sep {
+            result.push(sep);
+            result += &time.format(if self.seconds { "%H:%M:%S" } else { "%H:%M" }).to_string();
+            if self.frac > 0 {
+                result += &format!(".{:03}", millis.rem_euclid(1000))[..self.frac + 1];
+            }
+            if self.zone { result.push('Z'); }
+        }
+        result
+    }
+    /// The separator between the date and the time (if written), whether seconds are written, the number of digits of their fraction, and whether the stamp ends with `Z`.
+    pub fn parts(&self) -> (Option<char>, bool, usize, bool) {
+        (self.sep, self.seconds, self.frac, self.zone)
+    }
+    pub fn name() -> &'static str {
+        "iso.fmt"
+    }
+}
+
+impl std::fmt::Display for FormatIso8601 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "iso.fmt #sep:{:?}", self.sep.map(String::from).unwrap_or_default())?;
+        if self.sep.is_some() {
+            write!(f, " #seconds:{} #frac:{} #zone:{}", self.seconds, self.frac, self.zone)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for FormatIso8601 {
+    fn default() -> Self {
+        Self::from_config(&Default::default())
+    }
+}
+
+impl Enumerator1 for FormatIso8601 {
+    fn enumerate(&self, this: &'static crate::expr::ops::Op1Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 1]) -> Result<(), ()> { Ok(()) }
+}
+
+crate::impl_formatop!(FormatIso8601, Int, |this: &FormatIso8601| this.cost);
+
+impl FormattingOp for FormatIso8601 {
+    fn format(&self, input: &'static str) -> Option<(Self, ConstValue, &'static str)> {
+        let (s, stamp) = *find_stamps(input).first()?;
+        if s.as_ptr() != input.as_ptr() { return None; }
+        // Other offsets than `Z` and finer fractions are not written back by `format_single`.
+        if stamp.frac > 3 || stamp.zone.is_some_and(|z| z != "Z") { return None; }
+        let op = Self { cost: 1, sep: stamp.sep, seconds: stamp.seconds, frac: stamp.frac, zone: stamp.zone.is_some() };
+        Some((op, ConstValue::Int(stamp.millis), &input[s.len()..]))
+    }
+
+    fn union(self, other: Self) -> Option<Self> {
+        (self == other).then_some(self)
+    }
+
+    fn bad_value() -> ConstValue {
+        ConstValue::Int(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{text::formatting::FormattingOp, value::ConstValue};
+
+    use super::FormatIso8601;
+
+    #[test]
+    fn test_format_iso8601() {
+        let (op, v, rest) = FormatIso8601::default().format("2021-05-03T12:30:45.250Z GET /").unwrap();
+        assert_eq!((v, rest), (ConstValue::Int(1620045045250), " GET /"));
+        assert_eq!(op.to_string(), "iso.fmt #sep:\"T\" #seconds:true #frac:3 #zone:true");
+        assert_eq!(op.format_single(86400000), "1970-01-02T00:00:00.000Z");
+        let (date, _, _) = FormatIso8601::default().format("2021-05-03").unwrap();
+        assert_eq!(date.format_single(1620045045250), "2021-05-03");
+        assert!(op.union(date).is_none());
+        assert!(FormatIso8601::default().format("2021-05-03T12:30+02:00").is_none());
+    }
+}
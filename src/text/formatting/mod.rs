@@ -1,4 +1,83 @@
-use crate::{expr::ops::Op1Enum, galloc, value::{consts_to_value, ConstValue, Value}};
+use crate::{expr::ops::Op1Enum, galloc, parser::config::Config, text::parsing::split_number, value::{consts_to_value, ConstValue, Value}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Decimal mark and thousands separator of the numbers written by `int.fmt` and `float.fmt`, e.g. `1.234,56` in most of Europe.
+///
+/// Set with `#decimal` and `#thousands` in the configuration of the rule, or read from the outputs by the formatting deduction.
+pub struct Locale {
+    pub decimal: char,
+    pub thousands: Option<char>,
+    /// Whether a decimal mark and a number of at least 1000 were seen, so that the separators are known
+    seen: (bool, bool),
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self { decimal: '.', thousands: None, seen: (true, true) }
+    }
+}
+
+impl Locale {
+    pub fn from_config(config: &Config) -> Self {
+        let decimal = config.get_str("decimal").and_then(|s| s.chars().next()).unwrap_or('.');
+        Self { decimal, thousands: config.get_str("thousands").and_then(|s| s.chars().next()), seen: (true, true) }
+    }
+    /// The locale of a number written with `decimal` and `thousands` (as read by [`split_number`]), whose integer part has `int_digits` digits.
+    fn read(decimal: Option<char>, thousands: Option<char>, int_digits: usize) -> Self {
+        let decimal_seen = decimal.is_some() || thousands.is_some();
+        let decimal = decimal.unwrap_or(if thousands == Some('.') { ',' } else { '.' });
+        Self { decimal, thousands, seen: (decimal_seen, int_digits >= 4) }
+    }
+    /// Reads the digits and separators of a number, e.g. `1.234,56`, returning it as written by Rust (`1234.56`) along with its locale.
+    fn scan(num: &str) -> Option<(String, Self)> {
+        let (int, frac, decimal, thousands) = split_number(num)?;
+        let locale = Self::read(decimal, thousands, int.len());
+        Some((if decimal.is_some() { format!("{int}.{frac}") } else { int }, locale))
+    }
+    /// Whether numbers are written as by Rust, with a dot and no thousands separator.
+    pub fn is_plain(&self) -> bool {
+        self.decimal == '.' && self.thousands.is_none()
+    }
+    /// Rewrites a number written with a dot and without thousands separators in this locale.
+    pub fn localize(&self, s: &str) -> String {
+        if self.is_plain() { return s.to_string(); }
+        let (sign, rest) = s.split_at(if s.starts_with(['-', '+']) { 1 } else { 0 });
+        let (int, frac) = match rest.split_once('.') { Some((i, f)) => (i, Some(f)), None => (rest, None) };
+        let mut result = sign.to_string();
+        for (i, c) in int.chars().enumerate() {
+            if i > 0 && (int.len() - i) % 3 == 0 {
+                if let Some(t) = self.thousands { result.push(t); }
+            }
+            result.push(c);
+        }
+        if let Some(f) = frac {
+            result.push(self.decimal);
+            result += f;
+        }
+        result
+    }
+    fn union(self, other: Self) -> Option<Self> {
+        let decimal = match (self.seen.0, other.seen.0) {
+            (true, true) if self.decimal != other.decimal => return None,
+            (false, true) => other.decimal,
+            _ => self.decimal,
+        };
+        let thousands = match (self.seen.1, other.seen.1) {
+            (true, true) if self.thousands != other.thousands => return None,
+            (false, true) => other.thousands,
+            (false, false) => self.thousands.or(other.thousands),
+            _ => self.thousands,
+        };
+        if thousands == Some(decimal) { return None; }
+        Some(Self { decimal, thousands, seen: (self.seen.0 || other.seen.0, self.seen.1 || other.seen.1) })
+    }
+    /// Writes the configuration keys of a locale other than the plain one.
+    fn fmt_config(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_plain() { return Ok(()); }
+        let thousands = self.thousands.map(String::from).unwrap_or_default();
+        write!(f, " #decimal:{:?} #thousands:{:?}", self.decimal.to_string(), thousands)
+    }
+}
 
 
 trait FormattingOp where Self: Sized {
@@ -41,6 +120,22 @@ pub use month::*;
 
 pub mod weekday;
 pub use weekday::*;
+pub mod currency;
+pub use currency::*;
+pub mod percent;
+pub use percent::*;
+pub mod size;
+pub use size::*;
+pub mod roman;
+pub use roman::*;
+pub mod ordinal;
+pub use ordinal::*;
+pub mod duration;
+pub use duration::*;
+pub mod iso8601;
+pub use iso8601::*;
+pub mod digits;
+pub use digits::*;
 
 #[macro_export]
 macro_rules! for_all_formatting_op {
@@ -50,6 +145,14 @@ macro_rules! for_all_formatting_op {
         _do!(FormatTime);
         _do!(FormatMonth);
         _do!(FormatWeekday);
+        _do!(FormatCurrency);
+        _do!(FormatPercent);
+        _do!(FormatSize);
+        _do!(FormatRoman);
+        _do!(FormatOrdinal);
+        _do!(FormatDuration);
+        _do!(FormatIso8601);
+        _do!(FormatDigits);
     };
 }
 
@@ -26,6 +26,8 @@ impl FormatMonth {
 }
 
 impl FormatMonth {
+    /// Whether month names are abbreviated (`Jan`) rather than full (`January`).
+    pub fn abbreviated(&self) -> bool { self.1 == Some(true) }
     pub fn name() -> &'static str {
         "month.fmt"
     }
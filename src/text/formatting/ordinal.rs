@@ -0,0 +1,93 @@
+use regex::Regex;
+
+use crate::forward::enumeration::Enumerator1;
+use crate::parser::config::Config;
+use crate::text::parsing::{ordinal_suffix, ordinal_value};
+use crate::value::{ConstValue, Value};
+
+use crate::galloc::{AllocForExactSizeIter, AllocForStr};
+
+use super::FormattingOp;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FormatOrdinal(usize, Option<bool>);
+
+impl FormatOrdinal {
+    pub fn from_config(config: &Config) -> Self {
+        Self(
+            config.get_usize("cost").unwrap_or(1),
+            config.get_bool("upper"),
+        )
+    }
+    /// Whether suffixes are written in uppercase (`3RD`) rather than lowercase (`3rd`).
+    pub fn uppercase(&self) -> bool { self.1 == Some(true) }
+    /// Writes `n` with its English ordinal suffix, e.g. `22nd`.
+    pub fn format_single(&self, n: i64) -> String {
+        let suffix = ordinal_suffix(n);
+        if self.uppercase() { format!("{n}{}", suffix.to_ascii_uppercase()) } else { format!("{n}{suffix}") }
+    }
+    pub fn name() -> &'static str {
+        "ordinal.fmt"
+    }
+}
+
+impl std::fmt::Display for FormatOrdinal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(upper) = self.1 {
+            write!(f, "ordinal.fmt #upper:{}", upper)
+        } else {
+            write!(f, "ordinal.fmt")
+        }
+    }
+}
+
+impl Default for FormatOrdinal {
+    fn default() -> Self {
+        Self::from_config(&Default::default())
+    }
+}
+
+impl Enumerator1 for FormatOrdinal {
+    fn enumerate(&self, this: &'static crate::expr::ops::Op1Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 1]) -> Result<(), ()> { Ok(()) }
+}
+
+crate::impl_formatop!(FormatOrdinal, Int, |this: &FormatOrdinal| this.0);
+
+impl FormattingOp for FormatOrdinal {
+    fn format(&self, input: &'static str) -> Option<(Self, ConstValue, &'static str)> {
+        // Leading zeros (`03rd`) are not written by `format_single`.
+        let regex = Regex::new(r"^(?:0|[1-9]\d*)(?:st|nd|rd|th|ST|ND|RD|TH)").unwrap();
+        let m = regex.find(input)?;
+        let value = ordinal_value(m.as_str())?;
+        let upper = m.as_str().ends_with(|c: char| c.is_ascii_uppercase());
+        Some((Self(1, Some(upper)), ConstValue::Int(value), &input[m.end()..]))
+    }
+
+    fn union(self, other: Self) -> Option<Self> {
+        if self.1 != other.1 { return None; }
+        Some(self)
+    }
+
+    fn bad_value() -> ConstValue {
+        ConstValue::Int(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{text::formatting::FormattingOp, value::ConstValue};
+
+    use super::FormatOrdinal;
+
+    #[test]
+    fn test_format_ordinal() {
+        let (op, v, rest) = FormatOrdinal::default().format("3rd, 2021").unwrap();
+        assert_eq!((v, rest), (ConstValue::Int(3), ", 2021"));
+        assert_eq!(op.format_single(112), "112th");
+        assert_eq!(op.format_single(22), "22nd");
+        let (upper, _, _) = FormatOrdinal::default().format("1ST").unwrap();
+        assert!(op.union(upper).is_none());
+        assert_eq!(upper.format_single(3), "3RD");
+        assert!(FormatOrdinal::default().format("2th").is_none());
+        assert!(FormatOrdinal::default().format("03rd").is_none());
+    }
+}
@@ -0,0 +1,101 @@
+use regex::Regex;
+
+use crate::forward::enumeration::Enumerator1;
+use crate::parser::config::Config;
+use crate::utils::F64;
+use crate::value::{ConstValue, Value};
+
+use crate::galloc::{AllocForExactSizeIter, AllocForStr};
+
+use super::FormattingOp;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Formats a ratio as a percentage, e.g. `0.346` as `34.6%`, with a fixed number of decimals (`#digits`, 0 by default).
+pub struct FormatPercent {
+    cost: usize,
+    digits: usize,
+}
+
+impl FormatPercent {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            cost: config.get_usize("cost").unwrap_or(1),
+            digits: config.get_usize("digits").unwrap_or(0),
+        }
+    }
+    pub fn format_single(&self, value: F64) -> String {
+        format!("{:.*}%", self.digits, *value * 100.0)
+    }
+    /// Number of decimals of the percentage.
+    pub fn digits(&self) -> usize { self.digits }
+    pub fn name() -> &'static str {
+        "percent.fmt"
+    }
+}
+
+/// Parses the number of a percentage as the ratio it stands for, moving the decimal point two places to the left in the text so that e.g. `34.6` gives the same float as `0.346`.
+fn ratio(number: &str) -> Option<f64> {
+    let (int, frac) = number.split_once('.').unwrap_or((number, ""));
+    let mantissa = format!("{int}{frac}").parse::<i64>().ok()?;
+    if mantissa.unsigned_abs() >= 1 << 53 { return None; }
+    Some(mantissa as f64 / 10f64.powi(frac.len() as i32 + 2))
+}
+
+impl std::fmt::Display for FormatPercent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "percent.fmt #digits:{}", self.digits)
+    }
+}
+
+impl Default for FormatPercent {
+    fn default() -> Self {
+        Self::from_config(&Default::default())
+    }
+}
+
+impl Enumerator1 for FormatPercent {
+    fn enumerate(&self, this: &'static crate::expr::ops::Op1Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 1]) -> Result<(), ()> { Ok(()) }
+}
+
+crate::impl_formatop!(FormatPercent, Float, |this: &FormatPercent| this.cost);
+
+impl FormattingOp for FormatPercent {
+    fn format(&self, input: &'static str) -> Option<(Self, ConstValue, &'static str)> {
+        let regex = Regex::new(r"^(-?\d+(?:\.(\d+))?)%").unwrap();
+        let caps = regex.captures(input)?;
+        let op = Self { cost: 1, digits: caps.get(2).map_or(0, |m| m.as_str().len()) };
+        let value = F64::new(ratio(&caps[1])?);
+        // Percentages written in another way than `format_single` would (e.g. `-0%`) cannot be produced.
+        if op.format_single(value) != caps[1].to_string() + "%" { return None; }
+        Some((op, value.into(), &input[caps[0].len()..]))
+    }
+
+    fn union(self, other: Self) -> Option<Self> {
+        (self.digits == other.digits).then_some(self)
+    }
+
+    fn bad_value() -> ConstValue {
+        ConstValue::Float(0.0.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{text::formatting::FormattingOp, utils::F64};
+
+    use super::FormatPercent;
+
+    #[test]
+    fn test_format_percent() {
+        for s in ["34.6%", "-5%", "100.00%", "0.5%"] {
+            let (op, v, rest) = FormatPercent::default().format(s).unwrap();
+            assert_eq!(op.format_single(v.as_float().unwrap()), s);
+            assert!(rest.is_empty());
+        }
+        let (op, v, rest) = FormatPercent::default().format("34.6% off").unwrap();
+        assert_eq!((op.digits(), v.as_float().unwrap(), rest), (1, F64::new("0.346".parse().unwrap()), " off"));
+        let (a, _, _) = FormatPercent::default().format("12%").unwrap();
+        assert!(op.union(a).is_none());
+        assert_eq!(FormatPercent::default().format_single(F64::new(0.3456)), "35%");
+        assert!(FormatPercent::default().format("12.5").is_none());
+    }
+}
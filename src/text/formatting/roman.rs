@@ -0,0 +1,91 @@
+use regex::Regex;
+
+use crate::forward::enumeration::Enumerator1;
+use crate::parser::config::Config;
+use crate::text::parsing::{roman_value, to_roman};
+use crate::value::{ConstValue, Value};
+
+use crate::galloc::{AllocForExactSizeIter, AllocForStr};
+
+use super::FormattingOp;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FormatRoman(usize, Option<bool>);
+
+impl FormatRoman {
+    pub fn from_config(config: &Config) -> Self {
+        Self(
+            config.get_usize("cost").unwrap_or(1),
+            config.get_bool("lower"),
+        )
+    }
+    /// Whether numerals are written in lowercase (`xiv`) rather than uppercase (`XIV`).
+    pub fn lowercase(&self) -> bool { self.1 == Some(true) }
+    /// Writes `n` as a Roman numeral, or an empty string if it is not between 1 and 3999.
+    pub fn format_single(&self, n: i64) -> String {
+        let s = to_roman(n).unwrap_or_default();
+        if self.lowercase() { s.to_ascii_lowercase() } else { s }
+    }
+    pub fn name() -> &'static str {
+        "roman.fmt"
+    }
+}
+
+impl std::fmt::Display for FormatRoman {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(lower) = self.1 {
+            write!(f, "roman.fmt #lower:{}", lower)
+        } else {
+            write!(f, "roman.fmt")
+        }
+    }
+}
+
+impl Default for FormatRoman {
+    fn default() -> Self {
+        Self::from_config(&Default::default())
+    }
+}
+
+impl Enumerator1 for FormatRoman {
+    fn enumerate(&self, this: &'static crate::expr::ops::Op1Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 1]) -> Result<(), ()> { Ok(()) }
+}
+
+crate::impl_formatop!(FormatRoman, Int, |this: &FormatRoman| this.0);
+
+impl FormattingOp for FormatRoman {
+    fn format(&self, input: &'static str) -> Option<(Self, ConstValue, &'static str)> {
+        let regex = Regex::new(r"^(?i)[MDCLXVI]+").unwrap();
+        let m = regex.find(input)?;
+        // The longest prefix which is a numeral, so that `XIVth` gives `XIV`.
+        let (len, value) = (1..=m.end()).rev().find_map(|i| Some((i, roman_value(&input[..i])?)))?;
+        let lower = input[..len].chars().all(|c| c.is_ascii_lowercase());
+        Some((Self(1, Some(lower)), ConstValue::Int(value), &input[len..]))
+    }
+
+    fn union(self, other: Self) -> Option<Self> {
+        if self.1 != other.1 { return None; }
+        Some(self)
+    }
+
+    fn bad_value() -> ConstValue {
+        ConstValue::Int(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{text::formatting::FormattingOp, value::ConstValue};
+
+    use super::FormatRoman;
+
+    #[test]
+    fn test_format_roman() {
+        let (op, v, rest) = FormatRoman::default().format("xiv. Epilogue").unwrap();
+        assert_eq!((v, rest), (ConstValue::Int(14), ". Epilogue"));
+        assert_eq!(op.format_single(2024), "mmxxiv");
+        let (upper, _, _) = FormatRoman::default().format("MCM").unwrap();
+        assert!(op.union(upper).is_none());
+        assert_eq!(upper.format_single(4000), "");
+        assert!(FormatRoman::default().format("Hello").is_none());
+    }
+}
@@ -0,0 +1,145 @@
+use std::cmp::max;
+
+use regex::Regex;
+
+use crate::forward::enumeration::Enumerator1;
+use crate::parser::config::Config;
+use crate::text::parsing::{unit_factor, DATA_UNITS, SIZE_REGEX};
+use crate::utils::F64;
+use crate::value::{ConstValue, Value};
+
+use crate::galloc::{AllocForExactSizeIter, AllocForStr};
+
+use super::FormattingOp;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FormatSize {
+    cost: usize,
+    binary: bool,
+    /// `None` for the largest data unit in which the value is at least 1, e.g. `1.5 MB` rather than `1536 KB`.
+    unit: Option<&'static str>,
+    digits: usize,
+    space: bool,
+    /// Whether the units of the examples seen so far are the ones chosen automatically.
+    auto: bool,
+}
+
+/// Returns the largest data unit in which `value` bytes is at least 1.
+fn auto_unit(value: f64, binary: bool) -> &'static str {
+    let base: f64 = if binary { 1024.0 } else { 1000.0 };
+    let i = (1..DATA_UNITS.len()).rev().find(|i| value.abs() >= base.powi(*i as i32)).unwrap_or(0);
+    DATA_UNITS[i]
+}
+
+impl FormatSize {
+    pub fn from_config(config: &Config) -> Self {
+        let unit = config.get_str("unit").filter(|u| *u != "auto");
+        Self {
+            cost: config.get_usize("cost").unwrap_or(1),
+            binary: config.get_bool("binary").unwrap_or(true),
+            unit,
+            digits: config.get_usize("digits").unwrap_or(2),
+            space: config.get_bool("space").unwrap_or(false),
+            auto: unit.is_none(),
+        }
+    }
+    /// Writes `value` (in bytes or meters) in the unit of the format, with at most `digits` decimals and without trailing zeros.
+    pub fn format_single(&self, value: F64) -> String {
+        let unit = self.unit.unwrap_or_else(|| auto_unit(*value, self.binary));
+        let mut number = format!("{:.*}", self.digits, *value / unit_factor(unit, self.binary).unwrap_or(1.0));
+        if number.contains('.') {
+            number = number.trim_end_matches('0').trim_end_matches('.').to_string();
+        }
+        format!("{number}{}{unit}", if self.space { " " } else { "" })
+    }
+    /// The unit of the format, `None` for the automatic choice among data units.
+    pub fn unit(&self) -> Option<&'static str> { self.unit }
+    /// Whether `KB`, `MB`, ... are powers of 1024 rather than 1000.
+    pub fn binary(&self) -> bool { self.binary }
+    /// Maximum number of decimals.
+    pub fn digits(&self) -> usize { self.digits }
+    /// Whether a space separates the number from its unit.
+    pub fn space(&self) -> bool { self.space }
+    pub fn name() -> &'static str {
+        "size.fmt"
+    }
+}
+
+impl std::fmt::Display for FormatSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "size.fmt #unit:{} #digits:{}", self.unit.unwrap_or("auto"), self.digits)?;
+        if !self.binary { write!(f, " #binary:false")?; }
+        if self.space { write!(f, " #space:true")?; }
+        Ok(())
+    }
+}
+
+impl Default for FormatSize {
+    fn default() -> Self {
+        Self::from_config(&Default::default())
+    }
+}
+
+impl Enumerator1 for FormatSize {
+    fn enumerate(&self, this: &'static crate::expr::ops::Op1Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 1]) -> Result<(), ()> { Ok(()) }
+}
+
+crate::impl_formatop!(FormatSize, Float, |this: &FormatSize| this.cost);
+
+impl FormattingOp for FormatSize {
+    fn format(&self, input: &'static str) -> Option<(Self, ConstValue, &'static str)> {
+        let regex = Regex::new(&format!("^(?:{})", SIZE_REGEX)).unwrap();
+        let caps = regex.captures(input)?;
+        let (whole, num, unit) = (caps.get(0).unwrap().as_str(), caps.name("num").unwrap().as_str(), caps.name("unit").unwrap().as_str());
+        let value = num.parse::<f64>().ok()? * unit_factor(unit, self.binary)?;
+        let op = Self {
+            cost: 1,
+            binary: self.binary,
+            unit: Some(unit),
+            digits: num.split_once('.').map_or(0, |(_, d)| d.len()),
+            space: whole.contains(char::is_whitespace),
+            auto: auto_unit(value, self.binary) == unit,
+        };
+        // Numbers with trailing zeros (`1.50 MB`) are not written back by `format_single`.
+        if op.format_single(F64::new(value)) != whole { return None; }
+        Some((op, F64::new(value).into(), &input[whole.len()..]))
+    }
+
+    fn union(self, other: Self) -> Option<Self> {
+        if self.space != other.space { return None; }
+        let unit = match (self.unit, other.unit) {
+            (a, b) if a == b => a,
+            _ if self.auto && other.auto => None,
+            _ => return None,
+        };
+        Some(Self { cost: 1, unit, digits: max(self.digits, other.digits), auto: self.auto && other.auto, ..self })
+    }
+
+    fn bad_value() -> ConstValue {
+        ConstValue::Float(0.0.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{text::{formatting::FormattingOp, parsing::{ParseSize, ParsingOp}}, utils::F64};
+
+    use super::FormatSize;
+
+    #[test]
+    fn test_format_size() {
+        let (a, _, _) = FormatSize::default().format("1.5 MB").unwrap();
+        let (b, _, rest) = FormatSize::default().format("200 KB, approx.").unwrap();
+        assert_eq!(rest, ", approx.");
+        let op = a.union(b).unwrap();
+        assert_eq!(op.unit(), None);
+        let bytes = ParseSize::default().parse_into("1536 KB")[0].1.as_float().unwrap();
+        assert_eq!(op.format_single(bytes), "1.5 MB");
+        assert_eq!(op.format_single(F64::new(3.0 * 1073741824.0)), "3 GB");
+
+        let (c, _, _) = FormatSize::default().format("2048 KB").unwrap();
+        assert!(a.union(c).is_none());
+        let (d, _, _) = FormatSize::default().format("3.2 km").unwrap();
+        assert_eq!(d.format_single(F64::new(12500.0)), "12.5 km");
+        assert!(FormatSize::default().format("1.50 MB").is_none());
+    }
+}
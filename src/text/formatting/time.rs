@@ -10,6 +10,7 @@ use crate::{ impl_name, impl_op1, parser::config::Config};
 use crate::galloc::{AllocForExactSizeIter, AllocForStr};
 
 use super::FormattingOp;
+use crate::text::zone::{self, ZoneFormat};
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum TimeNumberFormat {
     None, Unknown, Padding, Default
@@ -72,6 +73,8 @@ pub struct FormatTime {
     minute: TimeNumberFormat,
     second: TimeNumberFormat,
     pm: Option<bool>,
+    /// Format of the time zone of zoned times, and whether it is separated from the time by a space
+    zone: Option<(ZoneFormat, bool)>,
 }
 
 impl FormatTime {
@@ -81,10 +84,37 @@ impl FormatTime {
             minute: TimeNumberFormat::from_name(config.get_str("m").unwrap_or("default")),
             second: TimeNumberFormat::from_name(config.get_str("s").unwrap_or("default")),
             pm: config.get_bool("pm"),
+            zone: config.get_str("tz").and_then(ZoneFormat::from_name).map(|z| (z, config.get_bool("tzsp").unwrap_or(false))),
         }
     }
 }
 impl FormatTime {
+    /// Returns the equivalent spreadsheet `TEXT` format code, e.g. `hh:mm AM/PM`.
+    pub fn excel_format(&self) -> String {
+        let code = |f: TimeNumberFormat, c: &str| match f {
+            TimeNumberFormat::None => String::new(),
+            TimeNumberFormat::Padding => c.repeat(2),
+            TimeNumberFormat::Default | TimeNumberFormat::Unknown => c.to_string(),
+        };
+        let mut result = code(self.hour, "h");
+        for (f, c) in [(self.minute, "m"), (self.second, "s")] {
+            if f != TimeNumberFormat::None { result += ":"; result += &code(f, c); }
+        }
+        match self.pm {
+            Some(true) => result += "AM/PM",
+            Some(false) => result += "am/pm",
+            None => (),
+        }
+        result
+    }
+    /// Returns the formats of hours, minutes and seconds (`none`, `unknown`, `padding` or `default`), and the AM/PM style (`Some(true)` for `AM`, `Some(false)` for `am`).
+    pub fn formats(&self) -> ([&'static str; 3], Option<bool>) {
+        ([self.hour.to_name(), self.minute.to_name(), self.second.to_name()], self.pm)
+    }
+    /// Returns the format of the time zone (`colon`, `compact`, `iso` or `abbrev`) and whether it follows a space, if zones are printed.
+    pub fn zone(&self) -> Option<(&'static str, bool)> {
+        self.zone.map(|(z, space)| (z.to_name(), space))
+    }
     pub fn name() ->  &'static str {
         "time.fmt"
     }
@@ -95,6 +125,10 @@ impl std::fmt::Display for FormatTime {
         if let Some(a) = self.pm {
             write!(f, " #pm:{}", a)?;
         }
+        if let Some((z, space)) = self.zone {
+            write!(f, " #tz:{}", z.to_name())?;
+            if space { write!(f, " #tzsp:true")?; }
+        }
         Ok(())
     }
 }
@@ -114,6 +148,7 @@ impl crate::expr::ops::Op1 for FormatTime {
     fn try_eval(&self,a1:Value) -> (bool, Value) {
         match a1 {
             Value::Int(s) => (true, Value::Str(s.iter().map(|&s1|{
+                let (s1, offset) = zone::split(s1);
                 let time = NaiveTime::from_num_seconds_from_midnight_opt(s1 as u32, 0).unwrap_or_default();
                 let mut h = time.hour();
                 let mut pm = false;
@@ -131,6 +166,10 @@ impl crate::expr::ops::Op1 for FormatTime {
                 } else if let Some(false) = self.pm {
                     if pm { result.push_str("pm") } else { result.push_str("am")}
                 }
+                if let (Some((z, space)), Some(offset)) = (self.zone, offset) {
+                    if space { result.push(' '); }
+                    result.push_str(&z.format(offset));
+                }
                 result.galloc_str()
             }).galloc_scollect())),
             _ => (false, Value::Null),
@@ -139,7 +178,7 @@ impl crate::expr::ops::Op1 for FormatTime {
 }
 
 lazy_static::lazy_static!{
-    static ref REGEX: Regex = Regex::new(r"^(?<h>\d{1,2})(:(?<m>\d{1,2}))?(:(?<s>\d{1,2}))?((?<pm>pm|PM|am|AM))?").unwrap();
+    static ref REGEX: Regex = Regex::new(&format!(r"^(?<h>\d{{1,2}})(:(?<m>\d{{1,2}}))?(:(?<s>\d{{1,2}}))?((?<pm>pm|PM|am|AM))?((?<tzsp> ?)(?<tz>{}))?", zone::ZONE_REGEX)).unwrap();
 }
 
 impl FormattingOp for FormatTime {
@@ -158,7 +197,14 @@ impl FormattingOp for FormatTime {
                     let mfmt = TimeNumberFormat::get_format(caps.name("m").map(|x| x.as_str()).unwrap_or(""));
                     let sfmt = TimeNumberFormat::get_format(caps.name("s").map(|x| x.as_str()).unwrap_or(""));
                     let pmfmt = caps.name("pm").map(|a| a.as_str() == "AM" || a.as_str() == "PM");
-                    return Some((Self{ hour: hfmt, minute: mfmt, second: sfmt, pm: pmfmt}, a.num_seconds_from_midnight().into(), &input[caps.get(0).unwrap().as_str().len()..]))
+                    let secs = a.num_seconds_from_midnight() as i64;
+                    let tz = caps.name("tz").filter(|z| zone::accepted(z.as_str(), caps.name("s").is_some()));
+                    if let Some((z, offset)) = tz.and_then(|z| Some((z, zone::parse_offset(z.as_str())?))) {
+                        let zfmt = (ZoneFormat::get_format(z.as_str()), caps.name("tzsp").is_some_and(|s| !s.as_str().is_empty()));
+                        return Some((Self{ hour: hfmt, minute: mfmt, second: sfmt, pm: pmfmt, zone: Some(zfmt)}, zone::zoned(secs, offset).into(), &input[z.end()..]))
+                    }
+                    let end = caps.name("tzsp").map(|s| s.start()).unwrap_or(caps.get(0).unwrap().end());
+                    return Some((Self{ hour: hfmt, minute: mfmt, second: sfmt, pm: pmfmt, zone: None}, secs.into(), &input[end..]))
                 }
             }
         }
@@ -171,6 +217,11 @@ impl FormattingOp for FormatTime {
             minute: self.minute.union(other.minute)?,
             second: self.second.union(other.second)?,
             pm: if self.pm == other.pm { self.pm } else { return None },
+            zone: match (self.zone, other.zone) {
+                (Some((z1, s1)), Some((z2, s2))) if s1 == s2 => Some((z1.union(z2)?, s1)),
+                (None, None) => None,
+                _ => return None,
+            },
         })
     }
 
@@ -192,10 +243,27 @@ fn hour_to_pm(h: u32) -> (u32, bool) {
     else { (h - 12, true) }
 }
 
-fn conflict(a: Option<bool>, b: Option<bool>) -> Option<Option<bool>> {
-    match (a, b) {
-        (Some(x), Some(y)) if x != y => { None }
-        (Some(x), _) | (None, Some(x)) => { Some(Some(x)) }
-        (None, None) => { Some(None) }
+#[cfg(test)]
+mod tests {
+    use crate::{expr::ops::Op1, galloc::AllocForExactSizeIter, text::zone::zoned, value::{ConstValue, Value}};
+
+    use super::{FormatTime, FormattingOp};
+
+    #[test]
+    fn test_format_zone() {
+        let (format, value, rest) = FormatTime::default().format("18:05:00+02:00 in Paris").unwrap();
+        assert_eq!(value, ConstValue::Int(zoned(65100, 120)));
+        assert_eq!(rest, " in Paris");
+        assert_eq!(format.to_string(), "time.fmt #h:unknown #m:padding #s:padding #tz:colon");
+        let (format2, _, _) = FormatTime::default().format("19:30:00Z").unwrap();
+        let format = format.union(format2).unwrap();
+        let times = Value::Int([zoned(65100, 120), zoned(3600, 0)].into_iter().galloc_scollect());
+        assert_eq!(format.try_eval(times).1, Value::Str(&["18:05:00+02:00", "1:00:00Z"]));
+
+        let (format, value, rest) = FormatTime::default().format("6:05PM PST").unwrap();
+        assert_eq!((value, rest), (ConstValue::Int(zoned(65100, -480)), ""));
+        assert_eq!(format.to_string(), "time.fmt #h:default #m:padding #s:none #pm:true #tz:abbrev #tzsp:true");
+        let (_, value, rest) = FormatTime::default().format("10:30-11:30").unwrap();
+        assert_eq!((value, rest), (ConstValue::Int(37800), "-11:30"));
     }
-}
\ No newline at end of file
+}
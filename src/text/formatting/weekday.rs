@@ -24,6 +24,8 @@ impl FormatWeekday {
     }
 }
 impl FormatWeekday {
+    /// Whether weekday names are abbreviated (`Mon`) rather than full (`Monday`).
+    pub fn abbreviated(&self) -> bool { self.1 == Some(true) }
     pub fn name() -> &'static str {
         "weekday.fmt"
     }
@@ -1,3 +1,4 @@
 
 pub mod parsing;
-pub mod formatting;
\ No newline at end of file
+pub mod formatting;
+pub mod zone;
@@ -0,0 +1,147 @@
+use itertools::Itertools;
+use regex::Regex;
+
+use crate::utils::F64;
+use crate::value::ConstValue;
+use crate::{expr::ops, galloc::AllocForExactSizeIter, impl_basic, value::Value};
+
+use super::ParsingOp;
+
+/// Currency symbols recognized before or after an amount.
+pub const SYMBOLS: [&str; 4] = ["$", "€", "£", "¥"];
+
+/// Regular expression matching an amount of money: `$1,234.56`, `-$5`, `1.234,56 €`, or an amount in parentheses for a negative one (`($1,234.56)`, accounting format).
+pub const AMOUNT_REGEX: &str = r"\((?:-?[$€£¥]\s?-?\d(?:[\d,.]*\d)?|-?\d(?:[\d,.]*\d)?\s?[$€£¥])\)|-?[$€£¥]\s?-?\d(?:[\d,.]*\d)?|-?\d(?:[\d,.]*\d)?\s?[$€£¥]";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// An amount of money matched by [`AMOUNT_REGEX`], with the way it is written.
+pub struct Amount {
+    pub value: f64,
+    pub symbol: &'static str,
+    /// The symbol follows the number (`1.234,56 €`).
+    pub suffix: bool,
+    /// The symbol is separated from the number by a space.
+    pub space: bool,
+    pub decimal: Option<char>,
+    pub thousands: Option<char>,
+    /// Number of digits after the decimal separator.
+    pub digits: usize,
+    /// The amount is negative and written in parentheses.
+    pub parens: bool,
+}
+
+/// Splits the digits of an amount into its integer part, its fractional part and the separators used.
+///
+/// The last separator is the decimal one, unless the number only uses one kind of separator followed by exactly three digits: `1,234` and `1.234` are both read as 1234.
+/// Thousands separators must group the integer part by three digits, otherwise the number is rejected.
+pub fn split_number(num: &str) -> Option<(String, &str, Option<char>, Option<char>)> {
+    let (int, frac, decimal) = match num.rfind([',', '.']) {
+        Some(i) => {
+            let sep = num.as_bytes()[i] as char;
+            let other = if sep == ',' { '.' } else { ',' };
+            let frac = &num[i + 1..];
+            if !num.contains(other) && frac.len() == 3 { (num, "", None) } else { (&num[..i], frac, Some(sep)) }
+        }
+        None => (num, "", None),
+    };
+    let thousands = int.chars().find(|c| !c.is_ascii_digit());
+    if let Some(t) = thousands {
+        if Some(t) == decimal { return None; }
+        let groups = int.split(t).collect_vec();
+        if groups[0].is_empty() || groups[0].len() > 3 { return None; }
+        if groups[1..].iter().any(|g| g.len() != 3 || !g.chars().all(|c| c.is_ascii_digit())) { return None; }
+    }
+    if !frac.chars().all(|c| c.is_ascii_digit()) { return None; }
+    Some((int.replace([',', '.'], ""), frac, decimal, thousands))
+}
+
+/// Reads an amount matched by [`AMOUNT_REGEX`], or returns `None` if its separators are inconsistent (e.g. `$1,23,4`).
+pub fn parse_amount(s: &str) -> Option<Amount> {
+    let parens = s.starts_with('(') && s.ends_with(')');
+    let inner = if parens { &s[1..s.len() - 1] } else { s };
+    let start = inner.find(|c: char| c.is_ascii_digit())?;
+    let end = inner.rfind(|c: char| c.is_ascii_digit())? + 1;
+    let symbol = *SYMBOLS.iter().find(|sym| inner.contains(**sym))?;
+    let (int, frac, decimal, thousands) = split_number(&inner[start..end])?;
+    let value = if frac.is_empty() { int.parse::<f64>().ok()? } else { format!("{int}.{frac}").parse::<f64>().ok()? };
+    Some(Amount {
+        value: if parens || inner.contains('-') { -value } else { value },
+        symbol,
+        suffix: inner.find(symbol)? > start,
+        space: inner.contains(char::is_whitespace),
+        decimal,
+        thousands,
+        digits: frac.len(),
+        parens,
+    })
+}
+
+impl_basic!(ParseCurrency, "currency.parse");
+impl crate::forward::enumeration::Enumerator1 for ParseCurrency {
+    fn enumerate(&self, this: &'static ops::Op1Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 1]) -> Result<(), ()> { Ok(())}
+}
+
+impl crate::expr::ops::Op1 for ParseCurrency {
+    fn cost(&self) -> usize {
+        self.0
+    }
+    fn try_eval(&self, a1: crate::value::Value) -> (bool, crate::value::Value) {
+        match a1 {
+            crate::value::Value::Str(s1) => {
+                let mut flag = true;
+                let a = s1.iter().map(|s1| {
+                    if let Some((_, c)) = self.parse_into(s1).first() {
+                        c.as_float().unwrap()
+                    } else {
+                        flag = false;
+                        F64::new(0.0)
+                    }
+                }).galloc_scollect();
+                (flag, Value::Float(a))
+            }
+            _ => (false, Value::Null),
+        }
+    }
+}
+
+lazy_static::lazy_static!{
+    static ref REGEX: Regex = Regex::new(AMOUNT_REGEX).unwrap();
+}
+
+impl ParsingOp for ParseCurrency {
+    fn parse_into(&self, input: &'static str) -> std::vec::Vec<(&'static str, ConstValue)> {
+        REGEX.find_iter(input).filter_map(|m| {
+            let amount = parse_amount(m.as_str())?;
+            Some((m.as_str(), F64::new(amount.value).into()))
+        }).collect()
+    }
+}
+
+pub fn detector(input: &str) -> bool {
+    REGEX.find_iter(input).any(|m| parse_amount(m.as_str()).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{text::parsing::ParsingOp, utils::F64, value::ConstValue};
+
+    use super::{parse_amount, ParseCurrency};
+
+    #[test]
+    fn test_currency() {
+        let amount = |s| parse_amount(s).map(|a| a.value);
+        assert_eq!(amount("$1,234.56"), Some(1234.56));
+        assert_eq!(amount("1.234,56 €"), Some(1234.56));
+        assert_eq!(amount("€1.234"), Some(1234.0));
+        assert_eq!(amount("($1,234.56)"), Some(-1234.56));
+        assert_eq!(amount("-£3,5"), Some(-3.5));
+        assert_eq!(amount("$1,23,4"), None);
+        assert_eq!(amount("$1.234.5"), None);
+        let a = parse_amount("1.234,56 €").unwrap();
+        assert_eq!((a.suffix, a.space, a.decimal, a.thousands, a.digits), (true, true, Some(','), Some('.'), 2));
+
+        let parsed = ParseCurrency(1).parse_into("Total: ($12.50) due, paid €1.000,00");
+        assert_eq!(parsed, vec![("($12.50)", ConstValue::Float(F64::new(-12.5))), ("€1.000,00", ConstValue::Float(F64::new(1000.0)))]);
+        assert!(ParseCurrency(1).parse_into("1,234.56").is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+use regex::Regex;
+
+use crate::parser::config::Config;
+use crate::utils::F64;
+use crate::value::{ConstValue, Type, Value};
+use crate::expr::ops;
+
+use super::ParsingOp;
+
+/// A scanner declared in a problem with `(define-scanner name "regex" Type)`.
+///
+/// It reads the text matched by the regular expression, or by its first group if it has one, as a value of its type (`String` by default, `Int` or `Float`).
+pub struct Scanner {
+    pub name: String,
+    pub regex: Regex,
+    pub ty: Type,
+}
+
+/// Scanners declared by the problems parsed so far; `scan` operators refer to them by index.
+static SCANNERS: spin::Mutex<Vec<&'static Scanner>> = spin::Mutex::new(Vec::new());
+
+/// Declares a scanner, replacing any scanner of the same name, and returns its index.
+pub fn define_scanner(name: &str, regex: &str, ty: Type) -> Result<usize, String> {
+    if !matches!(ty, Type::Str | Type::Int | Type::Float) {
+        return Err(format!("Scanner {name} should read a String, an Int or a Float"));
+    }
+    let regex = Regex::new(regex).map_err(|e| format!("Invalid regular expression of scanner {name}: {e}"))?;
+    let scanner: &'static Scanner = Box::leak(Box::new(Scanner { name: name.to_owned(), regex, ty }));
+    let mut scanners = SCANNERS.lock();
+    if let Some(i) = scanners.iter().position(|s| s.name == name) {
+        scanners[i] = scanner;
+        Ok(i)
+    } else {
+        scanners.push(scanner);
+        Ok(scanners.len() - 1)
+    }
+}
+
+/// Returns the index of the scanner declared as `name`, if any.
+pub fn lookup_scanner(name: &str) -> Option<usize> {
+    SCANNERS.lock().iter().position(|s| s.name == name)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Reads text objects with a scanner declared by the problem, written in grammars by the name of the scanner, e.g. `(ticket ntString)`.
+pub struct ParseCustom {
+    cost: usize,
+    scanner: usize,
+}
+
+impl ParseCustom {
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.get_str("scanner").and_then(lookup_scanner).unwrap_or(usize::MAX), config)
+    }
+    pub fn new(scanner: usize, config: &Config) -> Self {
+        Self { cost: config.get_usize("cost").unwrap_or(1), scanner }
+    }
+    pub fn name() -> &'static str {
+        "scan"
+    }
+    pub fn scanner(&self) -> Option<&'static Scanner> {
+        SCANNERS.lock().get(self.scanner).copied()
+    }
+}
+
+impl std::fmt::Display for ParseCustom {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.scanner() {
+            Some(s) => write!(f, "{}", s.name),
+            None => write!(f, "scan"),
+        }
+    }
+}
+
+impl Default for ParseCustom {
+    fn default() -> Self {
+        Self::from_config(&Default::default())
+    }
+}
+
+impl crate::forward::enumeration::Enumerator1 for ParseCustom {
+    fn enumerate(&self, this: &'static ops::Op1Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 1]) -> Result<(), ()> { Ok(())}
+}
+
+impl crate::expr::ops::Op1 for ParseCustom {
+    fn cost(&self) -> usize {
+        self.cost
+    }
+    fn try_eval(&self, a1: crate::value::Value) -> (bool, crate::value::Value) {
+        match (a1, self.scanner()) {
+            (crate::value::Value::Str(s1), Some(scanner)) => {
+                let mut flag = true;
+                let a = s1.iter().map(|s1| {
+                    if let Some((_, v)) = self.parse_into(s1).first() { *v } else {
+                        flag = false;
+                        match scanner.ty {
+                            Type::Int => ConstValue::Int(0),
+                            Type::Float => ConstValue::Float(F64::new(0.0)),
+                            _ => ConstValue::Str(""),
+                        }
+                    }
+                }).collect::<Vec<_>>();
+                (flag, Value::from_const(scanner.ty, a.into_iter()))
+            }
+            _ => (false, Value::Null),
+        }
+    }
+}
+
+impl ParsingOp for ParseCustom {
+    fn parse_into(&self, input: &'static str) -> std::vec::Vec<(&'static str, ConstValue)> {
+        let Some(scanner) = self.scanner() else { return Vec::new() };
+        scanner.regex.captures_iter(input).filter_map(|c| {
+            let whole = c.get(0)?.as_str();
+            let text = c.get(1).map_or(whole, |m| m.as_str());
+            let value = match scanner.ty {
+                Type::Int => ConstValue::Int(text.parse::<i64>().ok()?),
+                Type::Float => ConstValue::Float(F64::new(text.parse::<f64>().ok()?)),
+                _ => ConstValue::Str(text),
+            };
+            Some((whole, value))
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parser::config::Config, text::parsing::ParsingOp, value::{ConstValue, Type}};
+
+    use super::{define_scanner, ParseCustom};
+
+    #[test]
+    fn test_custom_scanner() {
+        let i = define_scanner("test_ticket", "[A-Z]{2,3}-([0-9]+)", Type::Int).unwrap();
+        let scanner = ParseCustom::new(i, &Config::new());
+        assert_eq!(scanner.parse_into("see AB-123 and XYZ-7"), vec![("AB-123", ConstValue::Int(123)), ("XYZ-7", ConstValue::Int(7))]);
+        assert_eq!(scanner.to_string(), "test_ticket");
+        assert_eq!(define_scanner("test_ticket", "[A-Z]+-[0-9]+", Type::Str), Ok(i));
+        assert_eq!(scanner.parse_into("AB-123"), vec![("AB-123", ConstValue::Str("AB-123"))]);
+        assert!(define_scanner("test_broken", "[A-Z", Type::Str).is_err());
+        assert!(define_scanner("test_bool", "yes|no", Type::Bool).is_err());
+    }
+}
@@ -1,23 +1,100 @@
 use std::collections::HashSet;
+use std::sync::atomic::{AtomicI64, Ordering};
 
 use chrono::{NaiveDate, Datelike, Month};
 use itertools::Itertools;
 use regex::Regex;
 
-use crate::{galloc::AllocForExactSizeIter, expr::{Expr, ops}, impl_basic, impl_op1_opt, new_op1_opt, value::{ConstValue, Value}};
+use crate::{galloc::AllocForExactSizeIter, expr::{Expr, ops}, impl_op1_opt, new_op1_opt, parser::config::Config, value::{ConstValue, Value}};
 
 use crate::galloc::TryAllocForExactSizeIter;
 use super::ParsingOp;
 
 
-impl_basic!(ParseDate, "date.parse");
+/// Two-digit years below the pivot are in the 2000s, the others in the 1900s, as with POSIX `strptime`.
+pub const DEFAULT_PIVOT: i64 = 69;
+
+static PIVOT: AtomicI64 = AtomicI64::new(DEFAULT_PIVOT);
+
+/// Sets the pivot of `date.parse` rules without a `#pivot` (`--year-pivot`).
+pub fn set_default_pivot(pivot: i64) {
+    PIVOT.store(pivot, Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParseDate {
+    cost: usize,
+    /// Two-digit years below the pivot are in the 2000s, the others in the 1900s
+    pivot: i64,
+    /// Century of all two-digit years, overriding the pivot: `#century:19` reads both `99` and `05` in the 1900s
+    century: Option<i64>,
+    /// Whether numeric dates such as `03/04/2021` are read day first (April 3) or month first (March 4); month first is preferred if unknown
+    day_first: Option<bool>,
+}
+
+impl ParseDate {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            cost: config.get_usize("cost").unwrap_or(1),
+            pivot: config.get_i64("pivot").unwrap_or_else(|| PIVOT.load(Ordering::Relaxed)),
+            century: config.get_i64("century"),
+            day_first: config.get_bool("day_first"),
+        }
+    }
+    pub fn name() -> &'static str {
+        "date.parse"
+    }
+    /// The pivot of two-digit years and the century they are forced into, if any.
+    pub fn pivot(&self) -> (i64, Option<i64>) { (self.pivot, self.century) }
+    /// Reads a year of two to four digits, placing two-digit years in a century.
+    fn full_year(&self, y: &str) -> Option<i32> {
+        let n = y.parse::<i64>().ok()?;
+        let year = if y.len() != 2 { n } else {
+            match self.century {
+                Some(c) => c * 100 + n,
+                None if n < self.pivot => 2000 + n,
+                None => 1900 + n,
+            }
+        };
+        i32::try_from(year).ok()
+    }
+    /// Whether numeric dates are read day first, if fixed.
+    pub fn day_first(&self) -> Option<bool> { self.day_first }
+    /// The same scanner with the ambiguities it leaves open fixed: two-digit years in the 1900s or in the 2000s, numeric dates month first or day first.
+    ///
+    /// Each alternative reads the dates of all examples the same way, so that the interpretation of `03/04/99` consistent with the outputs can be chosen.
+    pub fn alternatives(&self) -> Vec<Self> {
+        let centuries = if self.century.is_some() { vec![self.century] } else { vec![None, Some(19), Some(20)] };
+        let orders = if self.day_first.is_some() { vec![self.day_first] } else { vec![None, Some(false), Some(true)] };
+        centuries.into_iter().cartesian_product(orders)
+            .map(|(century, day_first)| Self { century, day_first, ..*self })
+            .filter(|alt| alt != self).collect()
+    }
+}
+
+impl std::fmt::Display for ParseDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "date.parse")?;
+        if let Some(c) = self.century { write!(f, " #century:{}", c)?; }
+        else if self.pivot != DEFAULT_PIVOT { write!(f, " #pivot:{}", self.pivot)?; }
+        if let Some(d) = self.day_first { write!(f, " #day_first:{}", d)?; }
+        Ok(())
+    }
+}
+
+impl Default for ParseDate {
+    fn default() -> Self {
+        Self::from_config(&Default::default())
+    }
+}
+
 impl crate::forward::enumeration::Enumerator1 for ParseDate {
     fn enumerate(&self, this: &'static ops::Op1Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 1]) -> Result<(), ()> { Ok(())}
 }
 
 impl crate::expr::ops::Op1 for ParseDate {
     fn cost(&self) -> usize {
-        self.0
+        self.cost
     }
     fn try_eval(&self, a1: crate::value::Value) -> (bool, crate::value::Value) {
         match a1 {
@@ -56,10 +133,11 @@ impl ParsingOp for ParseDate {
     fn parse_into(&self, input: &'static str) -> std::vec::Vec<(&'static str, ConstValue)> {
         let months = [ "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
         let mut result: Vec<(&'static str, ConstValue)> = Vec::new();
-        let [regex1, regex2, regex3, regex4, regex5] = &*REGEXES;
-        let iter = regex1.captures_iter(input).chain(regex2.captures_iter(input)).chain(regex3.captures_iter(input)).chain(regex4.captures_iter(input)).chain(regex5.captures_iter(input));
-        for m in iter {
-            let mut year = if m.name("y").is_none() { 2000 } else { m.name("y").unwrap().as_str().parse::<i32>().unwrap()};
+        let iter = REGEXES.iter().enumerate().flat_map(|(i, r)| r.captures_iter(input).map(move |m| (i, m)));
+        for (i, m) in iter {
+            // Numeric dates read month first by the first regex, and day first by the third one.
+            if m.name("m").is_some() && m.name("d").is_some() && Some(i) == self.day_first.map(|d| if d { 0 } else { 2 }) { continue; }
+            let Some(year) = m.name("y").map_or(Some(2000), |y| self.full_year(y.as_str())) else { continue };
             if m.name("m").is_some() || m.name("month").is_some() {
                 let month = if m.name("m").is_some() {
                     m.name("m").unwrap().as_str().parse::<u32>().unwrap()
@@ -92,12 +170,14 @@ pub fn detector(input: &str) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use crate::{text::parsing::{ParseDate, ParsingOp}};
+    use chrono::{Datelike, NaiveDate};
+
+    use crate::{parser::config::Config, text::parsing::{ParseDate, ParsingOp}, value::ConstValue};
     use super::detector;
 
     #[test]
     fn test1() {
-        let scanner = ParseDate(1);
+        let scanner = ParseDate::default();
         println!("{:?}", scanner.parse_into("Jan"))           ;
         println!("{:?}", scanner.parse_into("Jan 1st, 2034")) ;
         println!("{:?}", scanner.parse_into("03042241"))      ;
@@ -134,6 +214,39 @@ mod tests {
         println!("{:?}", scanner.parse_into("03 Apr 2008"))   ;
     }
 
+    #[test]
+    fn test_two_digit_years() {
+        let day = |y, m, d| ConstValue::Int(NaiveDate::from_ymd_opt(y, m, d).unwrap().num_days_from_ce() as i64);
+        let scanner = ParseDate::default();
+        assert_eq!(scanner.parse_into("May 21, 99")[0].1, day(1999, 5, 21));
+        assert_eq!(scanner.parse_into("May 21, 05")[0].1, day(2005, 5, 21));
+        let mut config = Config::new();
+        config.set("pivot", ConstValue::Int(10));
+        assert_eq!(ParseDate::from_config(&config).parse_into("May 21, 05")[0].1, day(2005, 5, 21));
+        assert_eq!(ParseDate::from_config(&config).parse_into("May 21, 15")[0].1, day(1915, 5, 21));
+        let alternatives = scanner.alternatives();
+        assert_eq!(alternatives.len(), 8);
+        let (c19, c20) = (alternatives[2], alternatives[5]);
+        assert_eq!(c19.parse_into("May 21, 05")[0].1, day(1905, 5, 21));
+        assert_eq!(c20.parse_into("May 21, 99")[0].1, day(2099, 5, 21));
+        assert_eq!(c19.to_string(), "date.parse #century:19");
+        assert_eq!(c19.alternatives().len(), 2);
+    }
+
+    #[test]
+    fn test_day_first() {
+        let day = |y, m, d| ConstValue::Int(NaiveDate::from_ymd_opt(y, m, d).unwrap().num_days_from_ce() as i64);
+        let longest = |p: ParseDate, s| p.parse_into(s).into_iter().min_by_key(|(s, _)| -(s.len() as isize)).unwrap();
+        let scanner = ParseDate::default();
+        assert_eq!(longest(scanner, "03/04/2021"), ("03/04/2021", day(2021, 3, 4)));
+        let mut config = Config::new();
+        config.set("day_first", ConstValue::Bool(true));
+        let day_first = ParseDate::from_config(&config);
+        assert_eq!(longest(day_first, "03/04/2021"), ("03/04/2021", day(2021, 4, 3)));
+        assert_eq!(longest(day_first, "25/04/2021"), ("25/04/2021", day(2021, 4, 25)));
+        assert_eq!(day_first.to_string(), "date.parse #day_first:true");
+    }
+
     #[test]
     fn test_detector() {
         assert!(detector("Jan"));
@@ -0,0 +1,78 @@
+use regex::Regex;
+
+use crate::galloc::AllocForStr;
+use crate::value::ConstValue;
+use crate::{expr::ops, galloc::AllocForExactSizeIter, impl_basic, value::Value};
+
+use super::ParsingOp;
+
+/// Regular expression matching groups of digits separated by `-`, `.`, `/` or spaces, like phone numbers (`(555) 123-4567`, `+1 555.123.4567`) or social security numbers (`123-45-6789`).
+pub const DIGIT_GROUPS_REGEX: &str = r"(?:\+\d{1,3} ?)?(?:\(\d{2,5}\) ?|\d{2,5}[-. /])(?:\d{2,5}[-. /])*\d{2,5}\b";
+
+/// Digit groups with fewer digits are more likely numbers, ranges or times (`10.50`, `10-12`).
+const MIN_DIGITS: usize = 7;
+
+/// Finds the digit groups of `input`, with their digits and their pattern, where each digit is replaced by `#` (e.g. `(###) ###-####`).
+pub fn find_digit_groups(input: &str) -> impl Iterator<Item = (&str, String, String)> {
+    let regex = Regex::new(DIGIT_GROUPS_REGEX).unwrap();
+    regex.find_iter(input).filter_map(|m| {
+        let digits: String = m.as_str().chars().filter(|c| c.is_ascii_digit()).collect();
+        let pattern = m.as_str().chars().map(|c| if c.is_ascii_digit() { '#' } else { c }).collect();
+        (digits.len() >= MIN_DIGITS).then_some((m.as_str(), digits, pattern))
+    }).collect::<Vec<_>>().into_iter()
+}
+
+impl_basic!(ParseDigits, "digits.parse");
+impl crate::forward::enumeration::Enumerator1 for ParseDigits {
+    fn enumerate(&self, this: &'static ops::Op1Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 1]) -> Result<(), ()> { Ok(())}
+}
+
+impl crate::expr::ops::Op1 for ParseDigits {
+    fn cost(&self) -> usize {
+        self.0
+    }
+    fn try_eval(&self, a1: crate::value::Value) -> (bool, crate::value::Value) {
+        match a1 {
+            crate::value::Value::Str(s1) => {
+                let mut flag = true;
+                let a = s1.iter().map(|s1| {
+                    if let Some((_, digits, _)) = find_digit_groups(s1).next() { digits.galloc_str() } else {
+                        flag = false;
+                        ""
+                    }
+                }).galloc_scollect();
+                (flag, Value::Str(a))
+            }
+            _ => (false, Value::Null),
+        }
+    }
+}
+
+impl ParsingOp for ParseDigits {
+    fn parse_into(&self, input: &'static str) -> std::vec::Vec<(&'static str, ConstValue)> {
+        find_digit_groups(input).map(|(s, digits, _)| (s, ConstValue::Str(digits.galloc_str()))).collect()
+    }
+}
+
+pub fn detector(input: &str) -> bool {
+    find_digit_groups(input).next().is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{text::parsing::ParsingOp, value::ConstValue};
+
+    use super::{detector, find_digit_groups, ParseDigits};
+
+    #[test]
+    fn test_digit_groups() {
+        let groups = find_digit_groups("call (555) 123-4567 or +1 555.987.6543").map(|(_, d, p)| (d, p)).collect::<Vec<_>>();
+        assert_eq!(groups, vec![
+            ("5551234567".to_string(), "(###) ###-####".to_string()),
+            ("15559876543".to_string(), "+# ###.###.####".to_string()),
+        ]);
+        assert_eq!(ParseDigits(1).parse_into("SSN 938-242-504"), vec![("938-242-504", ConstValue::Str("938242504"))]);
+        assert!(!detector("10.50 or 10-12"));
+        assert!(detector("123 45 6789"));
+    }
+}
@@ -26,13 +26,15 @@ impl_op1_opt!(ParseFloat, "float.parse",
 impl ParsingOp for ParseFloat {
 
     fn parse_into(&self, input: &'static str) -> std::vec::Vec<(&'static str, ConstValue)> {
-        let regex = Regex::new(r"(\-|\+)?[\d,]+(\.[\d,]+([eE](\-|\+)?\d+)?)?".to_string().as_str()).unwrap();
+        let regex = Regex::new(r"(\-|\+)?[\d,]+(\.[\d,]+)?([eE](\-|\+)?\d+)?".to_string().as_str()).unwrap();
         let iter = regex.captures_iter(input);
         let mut result = Vec::new();
         for m in iter {
             let a = m.get(0).unwrap().as_str();
             if let Ok(i) = a.parse::<f64>() {
                 result.push((a, F64::new(i).into()));
+            } else if let Some(v) = parse_localized(a) {
+                result.push((a, F64::new(v).into()));
             }
         }
         result
@@ -40,6 +42,13 @@ impl ParsingOp for ParseFloat {
 
 }
 
+/// Reads a number written with a thousands separator or a decimal comma, e.g. `1,234.5` or `1.234,56`.
+fn parse_localized(s: &str) -> Option<f64> {
+    let (sign, digits) = s.split_at(usize::from(s.starts_with(['-', '+'])));
+    let (int, frac, _, _) = super::split_number(digits)?;
+    format!("{sign}{int}.{frac}").parse::<f64>().ok()
+}
+
 pub fn detector(input: &str) -> bool {
     let regex = Regex::new(r"(\-|\+)?[\d,]+(\.[\d,]+([eE](\-|\+)?\d+)?)".to_string().as_str()).unwrap();
     regex.is_match(input)
@@ -58,6 +67,19 @@ mod tests {
         println!("{:?}", scan.parse_into("+123.321E3"));
     }
     #[test]
+    fn test_scientific() {
+        let scan = ParseFloat(1);
+        assert_eq!(scan.parse_into("x = 6e5, y = 1.2E-03").into_iter().map(|(s, _)| s).collect::<Vec<_>>(), vec!["6e5", "1.2E-03"]);
+    }
+    #[test]
+    fn test_localized() {
+        let scan = ParseFloat(1);
+        let values = |s| scan.parse_into(s).into_iter().map(|(s, v)| (s, v.as_float().unwrap().0)).collect::<Vec<_>>();
+        assert_eq!(values("1.234,56 and -1,234.5"), vec![("1.234,56", 1234.56), ("-1,234.5", -1234.5)]);
+        assert_eq!(values("3,5"), vec![("3,5", 3.5)]);
+        assert!(values("1,2,3").is_empty());
+    }
+    #[test]
     fn test_detector() {
         assert!(!detector("123"));
         assert!(detector("123.0"));
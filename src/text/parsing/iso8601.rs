@@ -0,0 +1,121 @@
+use chrono::{Datelike, NaiveDate, NaiveTime, Timelike};
+use regex::Regex;
+
+use crate::value::ConstValue;
+use crate::{expr::ops, galloc::AllocForExactSizeIter, impl_basic, value::Value};
+use crate::text::zone;
+
+use super::ParsingOp;
+
+/// Days from January 1, 1 (day 1, see `date.parse`) to January 1, 1970.
+pub const UNIX_EPOCH_DAYS: i64 = 719163;
+const DAY_MILLIS: i64 = 86_400_000;
+
+lazy_static::lazy_static!{
+    /// Matches an ISO 8601 date (`2021-05-03`), or date and time separated by `T` or a space (`2021-05-03T12:30:45.250Z`), with an optional fraction of a second and offset from UTC.
+    pub static ref ISO8601_REGEX: Regex = Regex::new(r"\b(?<y>\d{4})-(?<mo>\d{2})-(?<d>\d{2})(?:(?<sep>[T ])(?<h>\d{2}):(?<mi>\d{2})(?::(?<s>\d{2})(?:[.,](?<frac>\d+))?)?(?<tz>Z|[+-]\d{2}(?::?\d{2})?)?)?").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// An ISO 8601 stamp matched by [`ISO8601_REGEX`], with the way it is written.
+pub struct Stamp<'a> {
+    /// Milliseconds since 1970-01-01 00:00 UTC; stamps without an offset are in UTC
+    pub millis: i64,
+    /// Separator between the date and the time, or `None` for a date alone
+    pub sep: Option<char>,
+    pub seconds: bool,
+    /// Number of digits of the fraction of a second; only the first three are kept
+    pub frac: usize,
+    /// The offset from UTC as written, e.g. `Z` or `+02:00`
+    pub zone: Option<&'a str>,
+}
+
+/// Milliseconds since the Unix epoch of a date (in days since the common era) and a time of day in milliseconds.
+pub fn to_millis(days: i64, millis: i64) -> i64 {
+    (days - UNIX_EPOCH_DAYS) * DAY_MILLIS + millis
+}
+
+/// Splits milliseconds since the Unix epoch into a calendar date and a time of day.
+pub fn from_millis(millis: i64) -> Option<(NaiveDate, NaiveTime)> {
+    let date = NaiveDate::from_num_days_from_ce_opt(i32::try_from(millis.div_euclid(DAY_MILLIS) + UNIX_EPOCH_DAYS).ok()?)?;
+    let ms = millis.rem_euclid(DAY_MILLIS);
+    Some((date, NaiveTime::from_num_seconds_from_midnight_opt((ms / 1000) as u32, (ms % 1000) as u32 * 1_000_000)?))
+}
+
+/// Finds the ISO 8601 stamps of `input`, skipping invalid dates and times such as `2021-02-30`.
+pub fn find_stamps(input: &str) -> Vec<(&str, Stamp<'_>)> {
+    ISO8601_REGEX.captures_iter(input).filter_map(|c| {
+        let num = |n: &str| c.name(n).map_or(Some(0), |m| m.as_str().parse::<u32>().ok());
+        let date = NaiveDate::from_ymd_opt(num("y")? as i32, num("mo")?, num("d")?)?;
+        let frac = c.name("frac").map_or("", |m| m.as_str());
+        let ms = format!("{:0<3}", &frac[..frac.len().min(3)]).parse::<u32>().ok()?;
+        let time = NaiveTime::from_hms_milli_opt(num("h")?, num("mi")?, num("s")?, ms)?;
+        let zone = c.name("tz").map(|z| z.as_str());
+        let offset = zone.map_or(Some(0), zone::parse_offset)?;
+        let day_millis = time.num_seconds_from_midnight() as i64 * 1000 + ms as i64 - offset * 60_000;
+        Some((c.get(0)?.as_str(), Stamp {
+            millis: to_millis(date.num_days_from_ce() as i64, day_millis),
+            sep: c.name("sep").and_then(|s| s.as_str().chars().next()),
+            seconds: c.name("s").is_some(),
+            frac: frac.len(),
+            zone,
+        }))
+    }).collect()
+}
+
+impl_basic!(ParseIso8601, "iso.parse");
+impl crate::forward::enumeration::Enumerator1 for ParseIso8601 {
+    fn enumerate(&self, this: &'static ops::Op1Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 1]) -> Result<(), ()> { Ok(())}
+}
+
+impl crate::expr::ops::Op1 for ParseIso8601 {
+    fn cost(&self) -> usize {
+        self.0
+    }
+    fn try_eval(&self, a1: crate::value::Value) -> (bool, crate::value::Value) {
+        match a1 {
+            crate::value::Value::Str(s1) => {
+                let mut flag = true;
+                let a = s1.iter().map(|s1| {
+                    if let Some((_, stamp)) = find_stamps(s1).first() { stamp.millis } else {
+                        flag = false;
+                        0
+                    }
+                }).galloc_scollect();
+                (flag, a.into())
+            }
+            _ => (false, Value::Null),
+        }
+    }
+}
+
+impl ParsingOp for ParseIso8601 {
+    fn parse_into(&self, input: &'static str) -> std::vec::Vec<(&'static str, ConstValue)> {
+        find_stamps(input).into_iter().map(|(s, stamp)| (s, ConstValue::Int(stamp.millis))).collect()
+    }
+}
+
+/// Dates alone are left to `date.parse`, so only stamps with a time are detected.
+pub fn detector(input: &str) -> bool {
+    find_stamps(input).iter().any(|(_, stamp)| stamp.sep.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{text::parsing::ParsingOp, value::ConstValue};
+
+    use super::{detector, find_stamps, from_millis, ParseIso8601};
+
+    #[test]
+    fn test_iso8601() {
+        let parsed = ParseIso8601(1).parse_into("started 2021-05-03T12:30:45.250Z, ended 2021-05-03 14:30+02:00");
+        assert_eq!(parsed, vec![("2021-05-03T12:30:45.250Z", ConstValue::Int(1620045045250)), ("2021-05-03 14:30+02:00", ConstValue::Int(1620045000000))]);
+        assert_eq!(ParseIso8601(1).parse_into("1970-01-02"), vec![("1970-01-02", ConstValue::Int(86400000))]);
+        let (_, stamp) = find_stamps("2021-05-03T12:30:45.123456")[0];
+        assert_eq!((stamp.sep, stamp.seconds, stamp.frac, stamp.zone), (Some('T'), true, 6, None));
+        assert_eq!(from_millis(stamp.millis).unwrap().1.to_string(), "12:30:45.123");
+        assert!(ParseIso8601(1).parse_into("2021-02-30").is_empty());
+        assert!(detector("GET /index 2021-05-03T12:30:45Z"));
+        assert!(!detector("2021-05-03"));
+    }
+}
@@ -5,15 +5,16 @@ use itertools::Itertools;
 use kv_trie_rs::{Trie, TrieBuilder};
 use derive_more::From;
 
-use crate::{debg, expr::{cfg::ProdRule, context::Context, ops::{Op1, Op1Enum}, Expr}, forward::executor::Executor, utils::UnsafeCellExt, value::{consts_to_value, ConstValue, Value}};
+use crate::{debg, galloc::AllocForAny, expr::{cfg::{Cfg, ProdRule}, context::Context, ops::{Op1, Op1Enum, Op2Enum}, Expr}, forward::executor::Executor, utils::UnsafeCellExt, value::{consts_to_value, ConstValue, Value}};
 
 pub struct TextObjData {
-    trie: UnsafeCell<Vec<(&'static Op1Enum, usize, Trie<u8, ConstValue>)>>,
+    /// Scanner, target non-terminal, texts read by the scanner in the examples, and confidence of the scanner (see [`TextObjData::confidence`]).
+    trie: UnsafeCell<Vec<(&'static Op1Enum, usize, Trie<u8, ConstValue>, f32)>>,
     future_exprs: UnsafeCell<Vec<Vec<(Expr, Value)>>>,
 }
 
 impl TextObjData {
-    pub fn trie(&self) -> &mut Vec<(&'static Op1Enum, usize, Trie<u8, ConstValue>)> {
+    pub fn trie(&self) -> &mut Vec<(&'static Op1Enum, usize, Trie<u8, ConstValue>, f32)> {
         unsafe { self.trie.as_mut() }
     }
     pub fn future_exprs(&self) -> &mut Vec<Vec<(Expr, Value)>> {
@@ -26,36 +27,87 @@ impl TextObjData {
         }
         Ok(())
     }
+    /// Builds the scanner tries for every unary parsing rule of the grammar. Does nothing when text objects are disabled by `CfgConfig::no_text_objects`, in which case the non-terminals have no `TextObjData`.
     pub fn build_trie(exec: &Executor) {
+        if exec.cfg.config.no_text_objects { return; }
         for (nt, ntdata) in exec.cfg.iter().enumerate() {
             for rule in &ntdata.rules {
                 if let ProdRule::Op1(op1, from_nt) = rule {
                     let vec = op1.parse_all(&exec.ctx);
                     if vec.is_empty() { continue; }
-                    let mut triebuilder = TrieBuilder::new();
-                    for (k,v) in vec {
-                        debg!("Found TextObj {} -> {} {}", k, op1.name(), v);
-                        triebuilder.push(k.as_bytes(), v);
+                    // Other readings of the same text (e.g. `99` in the 1900s or 2000s, `03/04` day first) are scanned as well, each by its own operator.
+                    // A trie only matches when all examples are read by it, so that each reading is consistent across the examples.
+                    let alternatives = match op1 {
+                        Op1Enum::ParseDate(p) => p.alternatives(),
+                        _ => Vec::new(),
+                    };
+                    let mut readings = vec![vec];
+                    let mut ops: Vec<&'static Op1Enum> = vec![op1];
+                    for alt in alternatives {
+                        let alt: &'static Op1Enum = Op1Enum::from(alt).galloc();
+                        let altvec = alt.parse_all(&exec.ctx);
+                        if !altvec.is_empty() && !readings.contains(&altvec) {
+                            readings.push(altvec);
+                            ops.push(alt);
+                        }
+                    }
+                    let Some(to) = exec.data[*from_nt].to.as_ref() else { continue };
+                    for (op, reading) in ops.into_iter().zip(readings) {
+                        let trie = to.trie();
+                        let confidence = Self::confidence(op, &reading, &exec.ctx);
+                        // ISO 8601 stamps are unambiguous, so their readings come first.
+                        if let Op1Enum::ParseIso8601(_) = op {
+                            trie.insert(0, (op, nt, Self::build_one(op, reading), confidence));
+                        } else {
+                            trie.push((op, nt, Self::build_one(op, reading), confidence));
+                        }
                     }
-                    let mut trie = triebuilder.build();
-                    exec.data[*from_nt].to.trie().push((op1, nt, trie));
                 }
             }
         }
 
     }
+    /// Confidence of a scanner in its reading of the examples: the fraction of the example strings in which it reads a text object, times the mean length of the texts it reads.
+    ///
+    /// Scanners reading text objects consistently across the examples, and reading longer ones (`05-06-2021` as a date rather than `05` as a number), are preferred when several of them read the same strings.
+    fn confidence(op1: &Op1Enum, reading: &[(&'static str, ConstValue)], ctx: &Context) -> f32 {
+        let (mut total, mut read) = (0, 0);
+        for v in ctx.iter() {
+            if let Value::Str(a) = v {
+                total += a.len();
+                read += a.iter().filter(|input| !op1.parse_into(input).is_empty()).count();
+            }
+        }
+        if total == 0 || reading.is_empty() { return 0.0; }
+        let span = reading.iter().map(|(s, _)| s.len()).sum::<usize>() as f32 / reading.len() as f32;
+        read as f32 / total as f32 * span
+    }
+    fn build_one(op1: &Op1Enum, vec: Vec<(&'static str, ConstValue)>) -> Trie<u8, ConstValue> {
+        let mut triebuilder = TrieBuilder::new();
+        for (k,v) in vec {
+            debg!("Found TextObj {} -> {} {}", k, op1.name(), v);
+            triebuilder.push(k.as_bytes(), v);
+        }
+        triebuilder.build()
+    }
     pub fn new() -> Self {
         Self {
             trie: Vec::new().into(),
             future_exprs: Vec::new().into(),
         }
     }
+    /// Schedules the text objects read from the strings of `v` by the scanners, keeping the `text_objects.top` readings of highest confidence when several scanners read them.
     pub fn update(&self, exec: &'static Executor, e: &'static Expr, v: Value) {
         if let Value::Str(inner) = v {
-            for (scan, nt,  v) in self.read_to(inner) {
+            let mut readings = self.read_to(inner).collect_vec();
+            // Stable, so that ISO 8601 stamps stay first among equally confident readings.
+            readings.sort_by(|a, b| b.3.total_cmp(&a.3));
+            readings.truncate(exec.cfg.config.text_objects_top);
+            for (scan, nt, v, _) in readings {
                 let expr = Expr::Op1(scan, e);
                 let value = consts_to_value(v);
-                let target = exec.data[nt].to.future_exprs();
+                let Some(to) = exec.data[nt].to.as_ref() else { continue };
+                let target = to.future_exprs();
                 let size = exec.size() + scan.cost();
                 while target.len() <= size {
                     target.push(Vec::new());
@@ -64,8 +116,8 @@ impl TextObjData {
             }
         }
     }
-    pub fn read_to(&self, input: &'static [&'static str]) -> impl Iterator<Item= (&'static Op1Enum, usize, Vec<ConstValue>)> + '_ {
-        self.trie().iter().flat_map(|(scan, nt, trie)| {
+    pub fn read_to(&self, input: &'static [&'static str]) -> impl Iterator<Item= (&'static Op1Enum, usize, Vec<ConstValue>, f32)> + '_ {
+        self.trie().iter().flat_map(|(scan, nt, trie, confidence)| {
             if trie.exact_match(input[0].as_bytes()) {
                 let mut value = vec![*trie.get(input[0].as_bytes()).unwrap()];
                 
@@ -77,7 +129,7 @@ impl TextObjData {
                     } else { Some(()) }
                 });
                 if r.is_none() {
-                    return Some((*scan, *nt, value));
+                    return Some((*scan, *nt, value, *confidence));
                 }
             }
             None
@@ -110,7 +162,7 @@ pub fn detector(ctx: &Context) -> bool {
     for v in ctx.iter().chain(ctx.outputs()) {
         if let Value::Str(a) = v {
             for input in a {
-                if float::detector(input) || date::detector(input) || time::detector(input) {
+                if float::detector(input) || date::detector(input) || time::detector(input) || currency::detector(input) || size::detector(input) || roman::detector(input) || ordinal::detector(input) || quarter::detector(input) || time::duration_detector(input) || iso8601::detector(input) || digits::detector(input) || input.contains('@') || input.contains("://") {
                     return true;
                 }
             }
@@ -119,6 +171,38 @@ pub fn detector(ctx: &Context) -> bool {
     false
 }
 
+/// Selects the built-in enrichment profile of a problem solved without `--profile` or `--cfg`: `text` if the examples contain text objects, `tabular` otherwise.
+pub fn default_profile(ctx: &Context) -> &'static str {
+    if detector(ctx) { "text" } else { "tabular" }
+}
+
+/// Removes the rules of scanners whose literals do not appear in the examples, so that e.g. `size.parse` and `size.fmt` are only part of the default grammar when the examples contain sizes such as `1.5 MB`.
+pub fn prune_undetected(cfg: &mut Cfg, ctx: &Context) {
+    let mut inputs = Vec::new();
+    for v in ctx.iter().chain(ctx.outputs()) {
+        if let Value::Str(a) = v { inputs.extend_from_slice(a); }
+    }
+    let detected = |detector: fn(&str) -> bool| inputs.iter().any(|input| detector(input));
+    let (sizes, romans, ordinals, digits) = (detected(size::detector), detected(roman::detector), detected(ordinal::detector), detected(digits::detector));
+    let (quarters, durations, stamps) = (detected(quarter::detector), detected(time::duration_detector), detected(iso8601::detector));
+    let (emails, urls) = (detected(|s| s.contains('@')), detected(|s| s.contains("://")));
+    for nt in cfg.iter_mut() {
+        nt.rules.retain(|r| match r {
+            ProdRule::Op1(Op1Enum::ParseSize(_) | Op1Enum::FormatSize(_), _) => sizes,
+            ProdRule::Op1(Op1Enum::ParseRoman(_) | Op1Enum::FormatRoman(_), _) => romans,
+            ProdRule::Op1(Op1Enum::ParseOrdinal(_) | Op1Enum::FormatOrdinal(_), _) => ordinals,
+            ProdRule::Op1(Op1Enum::ParseQuarter(_) | Op1Enum::FormatQuarter(_), _) => quarters,
+            ProdRule::Op1(Op1Enum::ParseDuration(_) | Op1Enum::FormatDuration(_) | Op1Enum::DurationToHms(_), _) => durations,
+            ProdRule::Op2(Op2Enum::TimeDiff(_), _, _) => durations,
+            ProdRule::Op1(Op1Enum::ParseIso8601(_) | Op1Enum::FormatIso8601(_), _) => stamps,
+            ProdRule::Op1(Op1Enum::ParseDigits(_) | Op1Enum::FormatDigits(_), _) => digits,
+            ProdRule::Op1(Op1Enum::EmailUser(_) | Op1Enum::EmailDomain(_), _) => emails,
+            ProdRule::Op1(Op1Enum::UrlHost(_) | Op1Enum::UrlPath(_), _) => urls,
+            _ => true,
+        });
+    }
+}
+
 pub mod date;
 pub use date::*;
 pub mod int;
@@ -131,6 +215,22 @@ mod time;
 pub use time::*;
 mod float;
 pub use float::*;
+mod currency;
+pub use currency::*;
+mod size;
+pub use size::*;
+mod roman;
+pub use roman::*;
+mod ordinal;
+pub use ordinal::*;
+mod quarter;
+pub use quarter::*;
+mod iso8601;
+pub use iso8601::*;
+mod digits;
+pub use digits::*;
+mod custom;
+pub use custom::*;
 
 impl ParsingOp for Op1Enum {
     fn parse_into(&self, input: &'static str) -> Vec<(&'static str, ConstValue)> {
@@ -140,10 +240,58 @@ impl ParsingOp for Op1Enum {
             Op1Enum::ParseMonth(p) => p.parse_into(input),
             Op1Enum::ParseInt(p) => p.parse_into(input),
             Op1Enum::ParseWeekday(p) => p.parse_into(input),
+            Op1Enum::ParseCurrency(p) => p.parse_into(input),
+            Op1Enum::ParseSize(p) => p.parse_into(input),
+            Op1Enum::ParseRoman(p) => p.parse_into(input),
+            Op1Enum::ParseOrdinal(p) => p.parse_into(input),
+            Op1Enum::ParseQuarter(p) => p.parse_into(input),
+            Op1Enum::ParseDuration(p) => p.parse_into(input),
+            Op1Enum::ParseIso8601(p) => p.parse_into(input),
+            Op1Enum::ParseDigits(p) => p.parse_into(input),
+            Op1Enum::ParseCustom(p) => p.parse_into(input),
             _ => Vec::new(),
         }
     }
 }
 
 
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{expr::{cfg::Cfg, context::Context, ops::Op1Enum}, forward::executor::Executor, galloc::AllocForExactSizeIter, parser::problem::PBEProblem, value::Value};
+
+    use super::{ParseDate, ParseInt, ParsingOp, TextObjData};
+
+    fn date_problem() -> (Cfg, Context) {
+        let s = fs::read_to_string("test/test.sl").unwrap();
+        let problem = PBEProblem::parse(s.as_str()).unwrap();
+        let cfg = Cfg::from_synthfun(problem.synthfun());
+        let input: Value = ["Jan 1st, 2034", "Mar 30 2002"].into_iter().galloc_scollect().into();
+        let output: Value = ["Jan 1st, 2034 ", "Mar 30 2002 "].into_iter().galloc_scollect().into();
+        (cfg, Context::new(2, vec![input], vec![], output))
+    }
+
+    #[test]
+    fn test_no_text_objects() {
+        let (cfg, ctx) = date_problem();
+        let exec = Executor::new(ctx, cfg);
+        assert!(exec.data.iter().any(|d| d.to.as_ref().is_some_and(|to| !to.trie().is_empty())));
+
+        let (mut cfg, ctx) = date_problem();
+        cfg.config.no_text_objects = true;
+        let exec = Executor::new(ctx.clone(), cfg);
+        assert!(exec.data.iter().all(|d| d.to.is_none()));
+
+        let result = exec.solve_top_blocked();
+        assert_eq!(result.eval(&ctx), ctx.output);
+    }
 
+    #[test]
+    fn test_confidence() {
+        let input: Value = ["05-06-2021", "on 07-08-2020"].into_iter().galloc_scollect().into();
+        let ctx = Context::new(2, vec![input], vec![], input);
+        let confidence = |op: Op1Enum| TextObjData::confidence(&op, &op.parse_all(&ctx), &ctx);
+        assert!(confidence(ParseDate::default().into()) > confidence(ParseInt::default().into()));
+    }
+}
@@ -0,0 +1,90 @@
+use regex::Regex;
+
+use crate::value::ConstValue;
+use crate::{expr::ops, galloc::AllocForExactSizeIter, impl_basic, value::Value};
+
+use super::ParsingOp;
+
+/// Regular expression matching an ordinal number such as `1st`, `22nd` or `13TH`; see [`ordinal_value`] for the accepted suffixes.
+const ORDINAL_REGEX: &str = r"(?i)\b\d+(?:st|nd|rd|th)\b";
+
+/// The English suffix of the ordinal `n`: `st`, `nd`, `rd` or `th` (`11th` to `13th` included).
+pub fn ordinal_suffix(n: i64) -> &'static str {
+    let n = n.unsigned_abs();
+    if (11..=13).contains(&(n % 100)) { return "th"; }
+    match n % 10 {
+        1 => "st",
+        2 => "nd",
+        3 => "rd",
+        _ => "th",
+    }
+}
+
+/// Reads an ordinal number whose suffix, all lowercase or all uppercase, agrees with its value: `3rd` and `3RD`, but not `3th` or `3Rd`.
+pub fn ordinal_value(s: &str) -> Option<i64> {
+    let split = s.find(|c: char| !c.is_ascii_digit())?;
+    let (digits, suffix) = s.split_at(split);
+    let n = digits.parse::<i64>().ok()?;
+    let expected = ordinal_suffix(n);
+    (suffix == expected || suffix == expected.to_ascii_uppercase()).then_some(n)
+}
+
+/// Finds the ordinal numbers of `input`, with their values.
+pub fn find_ordinals(input: &str) -> impl Iterator<Item = (&str, i64)> {
+    let regex = Regex::new(ORDINAL_REGEX).unwrap();
+    regex.find_iter(input).filter_map(|m| Some((m.as_str(), ordinal_value(m.as_str())?))).collect::<Vec<_>>().into_iter()
+}
+
+impl_basic!(ParseOrdinal, "ordinal.parse");
+impl crate::forward::enumeration::Enumerator1 for ParseOrdinal {
+    fn enumerate(&self, this: &'static ops::Op1Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 1]) -> Result<(), ()> { Ok(())}
+}
+
+impl crate::expr::ops::Op1 for ParseOrdinal {
+    fn cost(&self) -> usize {
+        self.0
+    }
+    fn try_eval(&self, a1: crate::value::Value) -> (bool, crate::value::Value) {
+        match a1 {
+            crate::value::Value::Str(s1) => {
+                let mut flag = true;
+                let a = s1.iter().map(|s1| {
+                    if let Some((_, v)) = find_ordinals(s1).next() { v } else {
+                        flag = false;
+                        0
+                    }
+                }).galloc_scollect();
+                (flag, a.into())
+            }
+            _ => (false, Value::Null),
+        }
+    }
+}
+
+impl ParsingOp for ParseOrdinal {
+    fn parse_into(&self, input: &'static str) -> std::vec::Vec<(&'static str, ConstValue)> {
+        find_ordinals(input).map(|(s, v)| (s, ConstValue::Int(v))).collect()
+    }
+}
+
+pub fn detector(input: &str) -> bool {
+    find_ordinals(input).next().is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{text::parsing::ParsingOp, value::ConstValue};
+
+    use super::{detector, ordinal_suffix, ordinal_value, ParseOrdinal};
+
+    #[test]
+    fn test_ordinal() {
+        assert_eq!([1, 2, 3, 4, 11, 12, 13, 21, 102, 111].map(ordinal_suffix), ["st", "nd", "rd", "th", "th", "th", "th", "st", "nd", "th"]);
+        assert_eq!(ordinal_value("23RD"), Some(23));
+        assert_eq!(ordinal_value("3th"), None);
+        assert_eq!(ordinal_value("1St"), None);
+        assert_eq!(ParseOrdinal(1).parse_into("March 3rd, the 11th and 2th"), vec![("3rd", ConstValue::Int(3)), ("11th", ConstValue::Int(11))]);
+        assert!(detector("the 21st century"));
+        assert!(!detector("1stop"));
+    }
+}
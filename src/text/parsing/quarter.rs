@@ -0,0 +1,74 @@
+use chrono::{Datelike, NaiveDate};
+use regex::Regex;
+
+use crate::value::ConstValue;
+use crate::{expr::ops, galloc::AllocForExactSizeIter, impl_basic, value::Value};
+
+use super::ParsingOp;
+
+/// Regular expression matching a quarter of a year, with the quarter first (`Q3 2021`, `Q3-2021`) or the year first (`2021 Q3`, `2021-Q3`).
+const QUARTER_REGEX: &str = r"\bQ(?<q1>[1-4])[ /-]?(?<y1>\d{4})\b|\b(?<y2>\d{4})[ /-]?Q(?<q2>[1-4])\b";
+
+/// Finds the quarters of `input`, with the first day of each quarter as a date (see `date.parse`).
+pub fn find_quarters(input: &str) -> impl Iterator<Item = (&str, i64)> {
+    let regex = Regex::new(QUARTER_REGEX).unwrap();
+    regex.captures_iter(input).filter_map(|c| {
+        let quarter = c.name("q1").or(c.name("q2"))?.as_str().parse::<u32>().ok()?;
+        let year = c.name("y1").or(c.name("y2"))?.as_str().parse::<i32>().ok()?;
+        let date = NaiveDate::from_ymd_opt(year, quarter * 3 - 2, 1)?;
+        Some((c.get(0)?.as_str(), date.num_days_from_ce() as i64))
+    }).collect::<Vec<_>>().into_iter()
+}
+
+impl_basic!(ParseQuarter, "quarter.parse");
+impl crate::forward::enumeration::Enumerator1 for ParseQuarter {
+    fn enumerate(&self, this: &'static ops::Op1Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 1]) -> Result<(), ()> { Ok(())}
+}
+
+impl crate::expr::ops::Op1 for ParseQuarter {
+    fn cost(&self) -> usize {
+        self.0
+    }
+    fn try_eval(&self, a1: crate::value::Value) -> (bool, crate::value::Value) {
+        match a1 {
+            crate::value::Value::Str(s1) => {
+                let mut flag = true;
+                let a = s1.iter().map(|s1| {
+                    if let Some((_, v)) = find_quarters(s1).next() { v } else {
+                        flag = false;
+                        0
+                    }
+                }).galloc_scollect();
+                (flag, a.into())
+            }
+            _ => (false, Value::Null),
+        }
+    }
+}
+
+impl ParsingOp for ParseQuarter {
+    fn parse_into(&self, input: &'static str) -> std::vec::Vec<(&'static str, ConstValue)> {
+        find_quarters(input).map(|(s, v)| (s, ConstValue::Int(v))).collect()
+    }
+}
+
+pub fn detector(input: &str) -> bool {
+    find_quarters(input).next().is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, NaiveDate};
+
+    use crate::{text::parsing::ParsingOp, value::ConstValue};
+
+    use super::{detector, ParseQuarter};
+
+    #[test]
+    fn test_quarter() {
+        let day = |y, m| ConstValue::Int(NaiveDate::from_ymd_opt(y, m, 1).unwrap().num_days_from_ce() as i64);
+        assert_eq!(ParseQuarter(1).parse_into("Q3 2021 to 2022-Q1"), vec![("Q3 2021", day(2021, 7)), ("2022-Q1", day(2022, 1))]);
+        assert!(detector("sales (Q4/2019)"));
+        assert!(!detector("Q5 2021"));
+    }
+}
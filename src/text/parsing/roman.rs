@@ -0,0 +1,106 @@
+use regex::Regex;
+
+use crate::value::ConstValue;
+use crate::{expr::ops, galloc::AllocForExactSizeIter, impl_basic, value::Value};
+
+use super::ParsingOp;
+
+/// Regular expression matching a Roman numeral between 1 and 3999, possibly empty; see [`roman_value`] for the accepted case.
+const ROMAN_REGEX: &str = r"(?i)\bM{0,3}(?:CM|CD|D?C{0,3})(?:XC|XL|L?X{0,3})(?:IX|IV|V?I{0,3})\b";
+
+const NUMERALS: [(i64, &str); 13] = [
+    (1000, "M"), (900, "CM"), (500, "D"), (400, "CD"), (100, "C"), (90, "XC"),
+    (50, "L"), (40, "XL"), (10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I"),
+];
+
+/// Writes `n` as an uppercase Roman numeral, or returns `None` if it is not between 1 and 3999.
+pub fn to_roman(mut n: i64) -> Option<String> {
+    if !(1..=3999).contains(&n) { return None; }
+    let mut result = String::new();
+    for (v, s) in NUMERALS {
+        while n >= v {
+            result += s;
+            n -= v;
+        }
+    }
+    Some(result)
+}
+
+/// Reads a Roman numeral written in canonical form, all uppercase (`XIV`) or all lowercase (`xiv`).
+pub fn roman_value(s: &str) -> Option<i64> {
+    let upper = s.to_ascii_uppercase();
+    if s != upper && s != s.to_ascii_lowercase() { return None; }
+    let mut rest = upper.as_str();
+    let mut value = 0;
+    for (v, numeral) in NUMERALS {
+        while let Some(r) = rest.strip_prefix(numeral) {
+            value += v;
+            rest = r;
+        }
+    }
+    // Only the canonical spelling is accepted, e.g. not `IIII` or `VX`.
+    (rest.is_empty() && to_roman(value).as_deref() == Some(upper.as_str())).then_some(value)
+}
+
+/// Finds the Roman numerals of `input`, with their values.
+pub fn find_romans(input: &str) -> impl Iterator<Item = (&str, i64)> {
+    let regex = Regex::new(ROMAN_REGEX).unwrap();
+    regex.find_iter(input).filter_map(|m| Some((m.as_str(), roman_value(m.as_str())?))).collect::<Vec<_>>().into_iter()
+}
+
+impl_basic!(ParseRoman, "roman.parse");
+impl crate::forward::enumeration::Enumerator1 for ParseRoman {
+    fn enumerate(&self, this: &'static ops::Op1Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 1]) -> Result<(), ()> { Ok(())}
+}
+
+impl crate::expr::ops::Op1 for ParseRoman {
+    fn cost(&self) -> usize {
+        self.0
+    }
+    fn try_eval(&self, a1: crate::value::Value) -> (bool, crate::value::Value) {
+        match a1 {
+            crate::value::Value::Str(s1) => {
+                let mut flag = true;
+                let a = s1.iter().map(|s1| {
+                    if let Some((_, v)) = find_romans(s1).next() { v } else {
+                        flag = false;
+                        0
+                    }
+                }).galloc_scollect();
+                (flag, a.into())
+            }
+            _ => (false, Value::Null),
+        }
+    }
+}
+
+impl ParsingOp for ParseRoman {
+    fn parse_into(&self, input: &'static str) -> std::vec::Vec<(&'static str, ConstValue)> {
+        find_romans(input).map(|(s, v)| (s, ConstValue::Int(v))).collect()
+    }
+}
+
+/// Single letters are too often words (`I`, `a`) to be taken as Roman numerals on their own.
+pub fn detector(input: &str) -> bool {
+    find_romans(input).any(|(s, _)| s.len() >= 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{text::parsing::ParsingOp, value::ConstValue};
+
+    use super::{detector, roman_value, to_roman, ParseRoman};
+
+    #[test]
+    fn test_roman() {
+        assert_eq!(roman_value("XIV"), Some(14));
+        assert_eq!(roman_value("mcmxcix"), Some(1999));
+        assert_eq!(roman_value("IIII"), None);
+        assert_eq!(roman_value("Xiv"), None);
+        assert_eq!(to_roman(3999).as_deref(), Some("MMMCMXCIX"));
+        assert_eq!(to_roman(0), None);
+        assert_eq!(ParseRoman(1).parse_into("Chapter XIV, part ii"), vec![("XIV", ConstValue::Int(14)), ("ii", ConstValue::Int(2))]);
+        assert!(detector("Louis XVI"));
+        assert!(!detector("I did it"));
+    }
+}
@@ -0,0 +1,127 @@
+use regex::Regex;
+
+use crate::utils::F64;
+use crate::value::ConstValue;
+use crate::{expr::ops, galloc::AllocForExactSizeIter, parser::config::Config, value::Value};
+
+use super::ParsingOp;
+
+/// Units of data sizes, in increasing order; each one is 1024 times (or 1000 times for decimal sizes) the previous one.
+pub const DATA_UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+/// Units of lengths, with their value in meters.
+pub const LENGTH_UNITS: &[(&str, f64)] = &[("mm", 0.001), ("cm", 0.01), ("m", 1.0), ("km", 1000.0), ("ft", 0.3048), ("yd", 0.9144), ("mi", 1609.344)];
+
+/// Regular expression matching a number followed by a unit, e.g. `1536 KB`, `2.5GiB` or `10 km`.
+pub const SIZE_REGEX: &str = r"(?<num>\d+(?:\.\d+)?)\s?(?<unit>[kKMGT]i?B|B|km|cm|mm|mi|m|ft|yd)\b";
+
+/// Returns the value of one `unit` in its base unit (bytes or meters).
+/// Data units are powers of 1024 when `binary` is set, and of 1000 otherwise, except for the explicitly binary `KiB`, `MiB`, ...
+pub fn unit_factor(unit: &str, binary: bool) -> Option<f64> {
+    if unit == "B" { return Some(1.0); }
+    if unit.ends_with('B') {
+        let exp = "KMGT".find(unit[..1].to_ascii_uppercase().as_str())? as i32 + 1;
+        let base = if binary || unit.contains('i') { 1024.0 } else { 1000.0 };
+        return Some(f64::powi(base, exp));
+    }
+    LENGTH_UNITS.iter().find(|(u, _)| *u == unit).map(|(_, f)| *f)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Reads a number with a unit into its value in the base unit of the dimension: bytes for data sizes, meters for lengths.
+pub struct ParseSize {
+    cost: usize,
+    binary: bool,
+}
+
+impl ParseSize {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            cost: config.get_usize("cost").unwrap_or(1),
+            binary: config.get_bool("binary").unwrap_or(true),
+        }
+    }
+    /// Whether `KB`, `MB`, ... are powers of 1024 rather than 1000.
+    pub fn binary(&self) -> bool { self.binary }
+    pub fn name() -> &'static str {
+        "size.parse"
+    }
+}
+
+impl std::fmt::Display for ParseSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.binary { write!(f, "size.parse") } else { write!(f, "size.parse #binary:false") }
+    }
+}
+
+impl Default for ParseSize {
+    fn default() -> Self {
+        Self::from_config(&Default::default())
+    }
+}
+
+impl crate::forward::enumeration::Enumerator1 for ParseSize {
+    fn enumerate(&self, this: &'static ops::Op1Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 1]) -> Result<(), ()> { Ok(())}
+}
+
+impl crate::expr::ops::Op1 for ParseSize {
+    fn cost(&self) -> usize {
+        self.cost
+    }
+    fn try_eval(&self, a1: crate::value::Value) -> (bool, crate::value::Value) {
+        match a1 {
+            crate::value::Value::Str(s1) => {
+                let mut flag = true;
+                let a = s1.iter().map(|s1| {
+                    if let Some((_, c)) = self.parse_into(s1).first() {
+                        c.as_float().unwrap()
+                    } else {
+                        flag = false;
+                        F64::new(0.0)
+                    }
+                }).galloc_scollect();
+                (flag, Value::Float(a))
+            }
+            _ => (false, Value::Null),
+        }
+    }
+}
+
+impl ParsingOp for ParseSize {
+    fn parse_into(&self, input: &'static str) -> std::vec::Vec<(&'static str, ConstValue)> {
+        let regex = Regex::new(SIZE_REGEX).unwrap();
+        regex.captures_iter(input).filter_map(|caps| {
+            let num = caps.name("num").unwrap().as_str().parse::<f64>().ok()?;
+            let factor = unit_factor(caps.name("unit").unwrap().as_str(), self.binary)?;
+            Some((caps.get(0).unwrap().as_str(), F64::new(num * factor).into()))
+        }).collect()
+    }
+}
+
+pub fn detector(input: &str) -> bool {
+    let regex = Regex::new(SIZE_REGEX).unwrap();
+    regex.is_match(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{text::parsing::ParsingOp, utils::F64, value::ConstValue};
+
+    use super::{detector, unit_factor, ParseSize};
+
+    #[test]
+    fn test_size() {
+        assert_eq!(unit_factor("MB", true), Some(1048576.0));
+        assert_eq!(unit_factor("MB", false), Some(1000000.0));
+        assert_eq!(unit_factor("KiB", false), Some(1024.0));
+        assert_eq!(unit_factor("mi", true), Some(1609.344));
+        let parsed = ParseSize::default().parse_into("1536 KB to 10km, 1 mi");
+        assert_eq!(parsed, vec![
+            ("1536 KB", ConstValue::Float(F64::new(1572864.0))),
+            ("10km", ConstValue::Float(F64::new(10000.0))),
+            ("1 mi", ConstValue::Float(F64::new(1609.344))),
+        ]);
+        assert!(detector("2.5GB"));
+        assert!(!detector("5 miles"));
+    }
+}
@@ -14,6 +14,7 @@ use crate::{
 };
 
 use super::ParsingOp;
+use crate::text::zone;
 
 use chrono::Timelike;
 
@@ -57,7 +58,7 @@ impl crate::expr::ops::Op1 for ParseTime {
 impl ParsingOp for ParseTime {
     fn parse_into(&self, input: &'static str) -> std::vec::Vec<(&'static str, ConstValue)> {
         let mut result: Vec<(&'static str, ConstValue)> = Vec::new();
-        let regex1 = Regex::new(r"(?<h>\d{1,2})(:(?<m>\d{1,2}))?(:(?<s>\d{1,2}))?(\s*(?<pm>p\.?m\.?|P\.?M\.?|a\.?m\.?|A\.?M\.?))?").unwrap();
+        let regex1 = Regex::new(&format!(r"(?<h>\d{{1,2}})(:(?<m>\d{{1,2}}))?(:(?<s>\d{{1,2}}))?(\s*(?<pm>p\.?m\.?|P\.?M\.?|a\.?m\.?|A\.?M\.?))?(\s*(?<tz>{}))?", zone::ZONE_REGEX)).unwrap();
         for caps in regex1.captures_iter(input) {
             let mut h = caps.name("h").unwrap().as_str().parse::<u32>().unwrap();
             let m = caps.name("m").map(|a| a.as_str().parse::<u32>().unwrap()).unwrap_or(0);
@@ -69,10 +70,19 @@ impl ParsingOp for ParseTime {
             }
             if caps.name("m").is_some() || caps.name("s").is_some() || caps.name("pm").is_some() {
                 if let Some(a) = NaiveTime::from_hms_opt(h, m, s) {
-                    result.push((
-                        caps.get(0).unwrap().as_str(),
-                        a.num_seconds_from_midnight().into(),
-                    ))
+                    let secs = a.num_seconds_from_midnight() as i64;
+                    // Times with a time zone also carry its offset (see `zone::zoned`).
+                    let tz = caps.name("tz").filter(|z| zone::accepted(z.as_str(), caps.name("s").is_some()));
+                    let value = match tz.and_then(|z| zone::parse_offset(z.as_str())) {
+                        Some(offset) => zone::zoned(secs, offset),
+                        None => secs,
+                    };
+                    let whole = caps.get(0).unwrap();
+                    let text = match (caps.name("tz"), tz) {
+                        (Some(z), None) => input[whole.start()..z.start()].trim_end(),
+                        _ => whole.as_str(),
+                    };
+                    result.push((text, value.into()))
                 }
             }
         }
@@ -85,10 +95,69 @@ pub fn detector(input: &'static str) -> bool {
     !scanner.parse_into(input).is_empty()
 }
 
+/// Units of durations written in words, e.g. `83 min` or `2 hours`.
+const DURATION_UNIT: &str = r"(?<unit>hours?|hrs?|h|minutes?|mins?|m|seconds?|secs?|s)\b";
+
+/// Finds the durations of `input` in seconds: clock durations (`01:23:45`, hours may exceed 24) and amounts of units (`83 min`, `1h 23m`, `2 hours, 5 minutes`).
+pub fn find_durations(input: &str) -> impl Iterator<Item = (&str, i64)> {
+    let part = format!(r"\d+\s?{}", DURATION_UNIT.replace("?<unit>", "?:"));
+    let regex = Regex::new(&format!(r"\b(?<h>\d+):(?<m>[0-5]\d):(?<s>[0-5]\d)\b|\b{part}(?:,?\s*(?:and\s+)?{part})*")).unwrap();
+    let part = Regex::new(&format!(r"(?<n>\d+)\s?{DURATION_UNIT}")).unwrap();
+    regex.captures_iter(input).filter_map(|c| {
+        let whole = c.get(0)?.as_str();
+        if let Some(h) = c.name("h") {
+            let [h, m, s] = [h, c.name("m")?, c.name("s")?].map(|x| x.as_str().parse::<i64>().unwrap_or(0));
+            return Some((whole, h * 3600 + m * 60 + s));
+        }
+        let secs = part.captures_iter(whole).map(|p| {
+            let n = p["n"].parse::<i64>().unwrap_or(0);
+            n * match &p["unit"][..1] { "h" => 3600, "m" => 60, _ => 1 }
+        }).sum();
+        Some((whole, secs))
+    }).collect::<Vec<_>>().into_iter()
+}
+
+impl_basic!(ParseDuration, "duration.parse");
+impl crate::forward::enumeration::Enumerator1 for ParseDuration {
+    fn enumerate(&self, this: &'static ops::Op1Enum, exec: &'static crate::forward::executor::Executor, opnt: [usize; 1]) -> Result<(), ()> { Ok(()) }
+}
+
+impl crate::expr::ops::Op1 for ParseDuration {
+    fn cost(&self) -> usize {
+        self.0
+    }
+    fn try_eval(&self, a1: crate::value::Value) -> (bool, crate::value::Value) {
+        match a1 {
+            crate::value::Value::Str(s1) => {
+                let mut flag = true;
+                let a = s1.iter().map(|s1| {
+                    if let Some((_, v)) = find_durations(s1).next() { v } else {
+                        flag = false;
+                        0
+                    }
+                }).galloc_scollect();
+                (flag, a.into())
+            }
+            _ => (false, Value::Null),
+        }
+    }
+}
+
+impl ParsingOp for ParseDuration {
+    fn parse_into(&self, input: &'static str) -> std::vec::Vec<(&'static str, ConstValue)> {
+        find_durations(input).map(|(s, v)| (s, ConstValue::Int(v))).collect()
+    }
+}
+
+/// Clock durations look like times of day, so only durations written with units are detected.
+pub fn duration_detector(input: &str) -> bool {
+    find_durations(input).any(|(s, _)| !s.contains(':'))
+}
+
 #[cfg(test)]
 mod tests {
 
-    use crate::text::parsing::{ParseTime, ParsingOp};
+    use crate::{text::parsing::{ParseDuration, ParseTime, ParsingOp}, value::ConstValue};
 
     #[test]
     fn test1() {
@@ -99,4 +168,13 @@ mod tests {
         println!("{:?}", scanner.parse_into("12:0:1 AM"));
         println!("{:?}", scanner.parse_into("12am"));
     }
+
+    #[test]
+    fn test_durations() {
+        let scanner = ParseDuration(1);
+        assert_eq!(scanner.parse_into("took 01:23:45, then 83 min"), vec![("01:23:45", ConstValue::Int(5025)), ("83 min", ConstValue::Int(4980))]);
+        assert_eq!(scanner.parse_into("1h 23m")[0].1, ConstValue::Int(4980));
+        assert_eq!(scanner.parse_into("2 hours and 5 minutes")[0], ("2 hours and 5 minutes", ConstValue::Int(7500)));
+        assert!(scanner.parse_into("3 months").is_empty());
+    }
 }
@@ -0,0 +1,143 @@
+use crate::galloc::AllocForExactSizeIter;
+use crate::new_op1;
+
+/// Time-zone abbreviations, with their offsets in minutes from UTC. The first abbreviation of an offset is used when formatting it.
+pub const ABBREVIATIONS: &[(&str, i64)] = &[
+    ("UTC", 0), ("GMT", 0), ("EST", -300), ("EDT", -240), ("CST", -360), ("CDT", -300), ("MST", -420), ("MDT", -360),
+    ("PST", -480), ("PDT", -420), ("CET", 60), ("CEST", 120), ("BST", 60), ("JST", 540),
+];
+
+/// Regular expression matching a time-zone designator: `Z`, an abbreviation, or an offset such as `+02:00`, `+0200` or `-08`.
+pub const ZONE_REGEX: &str = r"(?:Z|UTC|GMT|CEST|CET|BST|JST|[ECMP][SD]T|[+-]\d{2}(?::?\d{2})?)\b";
+
+const DAY: i64 = 86400;
+/// Added to offsets (in minutes) so that the encoding of a zoned time is never a plain time of day.
+const BIAS: i64 = 1440;
+
+/// Encodes a time of day (in seconds) together with its offset from UTC (in minutes).
+///
+/// Times are integers: plain times of day are in `0..86400`, and zoned times store the offset in multiples of a day, so that operators on times ignoring zones are unchanged.
+pub fn zoned(secs: i64, offset: i64) -> i64 {
+    secs + DAY * (offset + BIAS)
+}
+
+/// Splits an encoded time into its time of day (in seconds) and its offset from UTC (in minutes), if it has one.
+pub fn split(t: i64) -> (i64, Option<i64>) {
+    if (0..DAY).contains(&t) { (t, None) } else { (t.rem_euclid(DAY), Some(t.div_euclid(DAY) - BIAS)) }
+}
+
+/// Returns the offset in minutes of a designator matched by [`ZONE_REGEX`].
+pub fn parse_offset(s: &str) -> Option<i64> {
+    if s == "Z" { return Some(0); }
+    if let Some((_, o)) = ABBREVIATIONS.iter().find(|(a, _)| *a == s) { return Some(*o); }
+    let sign = match s.chars().next()? { '+' => 1, '-' => -1, _ => return None };
+    let digits = s[1..].replace(':', "");
+    let h = digits.get(0..2)?.parse::<i64>().ok()?;
+    let m = if digits.len() > 2 { digits.get(2..4)?.parse::<i64>().ok()? } else { 0 };
+    (h <= 14 && m < 60).then_some(sign * (h * 60 + m))
+}
+
+/// Returns whether a designator following a time is a time zone: numeric offsets are only accepted after seconds (as in `12:30:00-08:00`), since `10:30-11:30` is more likely a range of times.
+pub fn accepted(designator: &str, has_seconds: bool) -> bool {
+    has_seconds || !designator.starts_with(['+', '-'])
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// How the offset of a zoned time is written.
+pub enum ZoneFormat {
+    /// `+02:00`
+    Colon,
+    /// `+0200`
+    Compact,
+    /// `Z` for UTC, `+02:00` otherwise (ISO 8601)
+    Iso,
+    /// `PST`, or `+02:00` for offsets without an abbreviation
+    Abbrev,
+}
+
+impl ZoneFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "colon" => Some(Self::Colon),
+            "compact" => Some(Self::Compact),
+            "iso" => Some(Self::Iso),
+            "abbrev" => Some(Self::Abbrev),
+            _ => None,
+        }
+    }
+    pub fn to_name(self) -> &'static str {
+        match self {
+            Self::Colon => "colon",
+            Self::Compact => "compact",
+            Self::Iso => "iso",
+            Self::Abbrev => "abbrev",
+        }
+    }
+    /// Returns the format of a designator matched by [`ZONE_REGEX`].
+    pub fn get_format(s: &str) -> Self {
+        match s {
+            "Z" => Self::Iso,
+            s if s.starts_with(['+', '-']) && s.contains(':') => Self::Colon,
+            s if s.starts_with(['+', '-']) => Self::Compact,
+            _ => Self::Abbrev,
+        }
+    }
+    /// Offsets written `+02:00` may also be in ISO format.
+    pub fn union(self, other: Self) -> Option<Self> {
+        match (self, other) {
+            (a, b) if a == b => Some(a),
+            (Self::Colon, Self::Iso) | (Self::Iso, Self::Colon) => Some(Self::Iso),
+            _ => None,
+        }
+    }
+    /// Writes an offset in minutes from UTC.
+    pub fn format(self, offset: i64) -> String {
+        let sign = if offset < 0 { '-' } else { '+' };
+        let (h, m) = (offset.abs() / 60, offset.abs() % 60);
+        match self {
+            Self::Iso if offset == 0 => "Z".into(),
+            Self::Abbrev => match ABBREVIATIONS.iter().find(|(_, o)| *o == offset) {
+                Some((a, _)) => a.to_string(),
+                None => format!("{sign}{h:02}:{m:02}"),
+            },
+            Self::Compact => format!("{sign}{h:02}{m:02}"),
+            Self::Colon | Self::Iso => format!("{sign}{h:02}:{m:02}"),
+        }
+    }
+}
+
+new_op1!(ToUtc, "time.to_utc",
+    Int -> Int { |t| {
+        match split(*t) {
+            (secs, Some(offset)) => (secs - offset * 60).rem_euclid(DAY),
+            (secs, None) => secs,
+        }
+    }}
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr::ops::Op1, galloc::AllocForExactSizeIter, text::parsing::{ParseTime, ParsingOp}, value::{ConstValue, Value}};
+
+    use super::{parse_offset, split, zoned, ToUtc, ZoneFormat};
+
+    #[test]
+    fn test_zones() {
+        assert_eq!(parse_offset("+05:30"), Some(330));
+        assert_eq!(parse_offset("-0800"), Some(-480));
+        assert_eq!(parse_offset("PST"), Some(-480));
+        assert_eq!(parse_offset("+25:00"), None);
+        assert_eq!(split(zoned(3600, -480)), (3600, Some(-480)));
+        assert_eq!(split(zoned(0, 0)), (0, Some(0)));
+        assert_eq!(ZoneFormat::Iso.format(0), "Z");
+        assert_eq!(ZoneFormat::Compact.format(-570), "-0930");
+
+        let parsed = ParseTime(1).parse_into("at 10:30 PST");
+        assert_eq!(parsed[0], ("10:30 PST", ConstValue::Int(zoned(37800, -480))));
+        assert_eq!(ParseTime(1).parse_into("10:30 Zurich")[0], ("10:30", ConstValue::Int(37800)));
+        assert_eq!(ParseTime(1).parse_into("10:30-11:30")[0], ("10:30", ConstValue::Int(37800)));
+        assert_eq!(ParseTime(1).parse_into("10:30:00-08:00")[0].1, ConstValue::Int(zoned(37800, -480)));
+        let times = Value::Int([zoned(37800, -480), zoned(3600, 120), 3600].into_iter().galloc_scollect());
+        assert_eq!(ToUtc::default().try_eval(times), (true, Value::Int(&[66600, 82800, 3600])));
+    }
+}
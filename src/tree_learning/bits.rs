@@ -1,5 +1,3 @@
-use std::u128;
-
 pub type Bits = Box<[u128]>;
 
 /// A trait defining extended operations on box slices, particularly focused on bit manipulation. 
@@ -18,7 +16,10 @@ pub trait BoxSliceExt {
     fn difference_assign(&mut self, other: &Self);
     fn subset(&self, other: &Self) -> bool;
     fn get(&self, index: usize) -> bool;
-    
+    fn from_fn(len: usize, f: impl FnMut(usize) -> bool) -> Self;
+    fn and_count(&self, other: &Self) -> u32;
+    fn diff_count(&self, other: &Self) -> u32;
+    fn intersects(&self, other: &Self) -> bool;
 }
 /// Calculates the ceiling of the division of two unsigned integers. 
 /// 
@@ -47,7 +48,7 @@ impl BoxSliceExt for Box<[u128]> {
     fn ones(len: usize) -> Self {
         let mut result: Self = (0..ceildiv(len, u128::BITS as usize)).map(|_| u128::MAX).collect();
         if len % u128::BITS as usize != 0 {
-            result.last_mut().map(|x| *x &= (1 << (len % u128::BITS as usize)) - 1);
+            if let Some(x) = result.last_mut() { *x &= (1 << (len % u128::BITS as usize)) - 1; }
         }
         result
     }
@@ -103,14 +104,8 @@ impl BoxSliceExt for Box<[u128]> {
     /// As it iterates over the boolean items, it appropriately sets bits in the `u128` segments depending on their indexes. 
     /// This results in a bit-packed representation of the iterator's boolean sequence, which is then converted into a boxed slice for returned storage efficiency.
     /// 
-    fn from_bit_siter(t: impl ExactSizeIterator<Item = bool>) -> Self {
-        let mut res = vec![0u128; ceildiv(t.len(), 128)];
-        for (i, b) in t.enumerate() {
-            if b {
-                res[i / 128] |= 1 << (i % 128);
-            }
-        }
-        res.into_boxed_slice()
+    fn from_bit_siter(mut t: impl ExactSizeIterator<Item = bool>) -> Self {
+        Self::from_fn(t.len(), |_| t.next().unwrap_or(false))
     }
     
     /// Returns the boolean value of the bit at the given index for a boxed slice of `u128` integers. 
@@ -121,6 +116,31 @@ impl BoxSliceExt for Box<[u128]> {
     fn get(&self, index: usize) -> bool {
         self[index / 128] & (1 << (index % 128)) != 0
     }
+
+    /// Packs the bits `f(0)`, ..., `f(len - 1)`, filling each `u128` word in a register before storing it.
+    /// 
+    /// This is the fast path of the comparisons of values on many examples (e.g. `Value::eq_bits`), which would otherwise index the slice for every bit.
+    fn from_fn(len: usize, mut f: impl FnMut(usize) -> bool) -> Self {
+        (0..ceildiv(len, 128)).map(|w| {
+            let start = w * 128;
+            (start..std::cmp::min(start + 128, len)).fold(0u128, |word, i| word | ((f(i) as u128) << (i - start)))
+        }).collect()
+    }
+
+    /// Counts the bits set in both slices, without allocating their conjunction.
+    fn and_count(&self, other: &Self) -> u32 {
+        self.iter().zip(other.iter()).map(|(i, j)| (i & j).count_ones()).sum()
+    }
+
+    /// Counts the bits set in `self` but not in `other`, without allocating their difference.
+    fn diff_count(&self, other: &Self) -> u32 {
+        self.iter().zip(other.iter()).map(|(i, j)| (i & !j).count_ones()).sum()
+    }
+
+    /// Returns whether a bit is set in both slices.
+    fn intersects(&self, other: &Self) -> bool {
+        self.iter().zip(other.iter()).any(|(i, j)| i & j != 0)
+    }
 }
 
 /// Creates a boxed slice of `u128` filled with binary ones, with the specified length in bits. 
@@ -140,6 +160,25 @@ pub fn boxed_ones(size: usize) -> Box<[u128]> {
 mod test {
     use super::BoxSliceExt;
 
+    #[test]
+    fn test_packed() {
+        for len in [0, 1, 127, 128, 129, 255, 256, 300] {
+            let a = Box::from_fn(len, |i| i % 3 == 0);
+            let b = Box::from_bit_siter((0..len).map(|i| i % 5 < 2));
+            for i in 0..len {
+                assert_eq!(a.get(i), i % 3 == 0);
+                assert_eq!(b.get(i), i % 5 < 2);
+            }
+            let mut and = a.clone();
+            and.conjunction_assign(&b);
+            let mut diff = a.clone();
+            diff.difference_assign(&b);
+            assert_eq!(a.and_count(&b), and.count_ones());
+            assert_eq!(a.diff_count(&b), diff.count_ones());
+            assert_eq!(a.intersects(&b), and.count_ones() > 0);
+        }
+    }
+
     #[test]
     fn test() {
         for i in 0..=256 {
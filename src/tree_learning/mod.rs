@@ -1,4 +1,4 @@
-use std::{cell::RefCell, borrow::{Borrow, BorrowMut}};
+use std::{cell::RefCell, borrow::{Borrow, BorrowMut}, collections::HashMap, sync::{Mutex, OnceLock}};
 
 use bumpalo::Bump;
 
@@ -8,12 +8,14 @@ pub mod bits;
 use bits::BoxSliceExt;
 pub use bits::Bits;
 
-use crate::{debg, debg2, expr::Expr};
+use crate::{debg, debg2, expr::Expr, provenance::{self, Provenance}};
 
 /// An enum representing subproblems within a decision tree learning process for string synthesis. 
 pub enum SubProblem<'a> {
     Unsolved(Bits, f32),
     Accept(usize),
+    /// A leaf with the option solving most of the examples, leaving the others unsatisfied (see `TreeLearning::best_effort`).
+    Abstain(usize),
     Ite{ expr: usize, entropy: f32, t: SubProb<'a>, f: SubProb<'a> }
 }
 
@@ -40,6 +42,12 @@ pub struct TreeLearning<'a, 'b> {
     pub options: Vec<(&'static Expr, Bits)>,
     pub bump: &'a Bump,
     pub solved: bool,
+    /// Weight of the costs of expressions against the conditional entropy when selecting a condition (see `select`).
+    cost_weight: f32,
+    /// Costs of the conditions, computed once if `cost_weight` is positive.
+    condition_costs: Vec<f32>,
+    /// Whether subproblems that cannot be split further end with an `Abstain` leaf instead of failing.
+    abstain: bool,
 }
 
 /// An enum that captures the outcomes of decision-making processes for solving subproblems in decision trees. 
@@ -52,6 +60,7 @@ pub struct TreeLearning<'a, 'b> {
 /// The `Failed` variant signifies that a subproblem could not be resolved under the current conditions, indicating a failure in the decision-making process.
 pub enum SelectResult {
     Accept(usize),
+    Abstain(usize),
     Ite(usize, f32, (Bits, f32), (Bits, f32)),
     Failed,
 }
@@ -63,7 +72,14 @@ impl<'a, 'b> TreeLearning<'a, 'b> {
     // }
     /// Creates a new instance with specified parameters including size, conditions, options, memory allocator, and limit. 
     pub fn new_in(size: usize, conditions: &'b [(&'static Expr, Bits)], options: Vec<(&'static Expr, Bits)>, bump: &'a Bump, limit: usize) -> Self {
+        Self::with_cost_weight(size, conditions, options, bump, limit, 0.0)
+    }
+    /// Same as `new_in`, weighing the costs of expressions by `cost_weight` when selecting conditions.
+    pub fn with_cost_weight(size: usize, conditions: &'b [(&'static Expr, Bits)], options: Vec<(&'static Expr, Bits)>, bump: &'a Bump, limit: usize, cost_weight: f32) -> Self {
+        let condition_costs = if cost_weight > 0.0 { conditions.iter().map(|(e, _)| e.cost() as f32).collect() } else { Vec::new() };
         let mut this = Self {
+            cost_weight,
+            condition_costs,
             size,
             root: bump.alloc(RefCell::new(SubProblem::Unsolved(bits::boxed_ones(size), 0.0))),
             subproblems: Vec::new(),
@@ -71,6 +87,7 @@ impl<'a, 'b> TreeLearning<'a, 'b> {
             options,
             bump,
             solved: false,
+            abstain: false,
             limit
         };
         let root_entro = this.entropy(& bits::boxed_ones(size));
@@ -81,23 +98,31 @@ impl<'a, 'b> TreeLearning<'a, 'b> {
         this
     }
 
+    /// Turns the learner into a best-effort one, for examples that no option solves: they are left out of the tree, and subproblems that no condition can split end with the option solving most of their examples.
+    /// 
+    /// The resulting tree solves as many examples as the conditions allow, instead of failing.
+    pub fn best_effort(mut self) -> Self {
+        let covered = self.options.iter().fold(Bits::zeros(self.size), |mut acc, (_, b)| { acc.union_assign(b); acc });
+        let entropy = self.entropy(&covered);
+        *self.root.borrow_mut() = SubProblem::Unsolved(covered, entropy);
+        self.abstain = true;
+        self
+    }
+
     #[inline]
     /// Calculates the entropy of a given set of bits within the context of the `TreeLearning` algorithm's options. 
     pub fn entropy(&self, bits: & Bits) -> f32 {
         
-        let mut vec: Vec<_> = self.options.iter().enumerate().map(|(i, b)| {
-            let mut res = b.1.clone();
-            res.conjunction_assign(bits);
-            (i, res.count_ones(), res)
-        }).collect();
-        vec.sort_by_key(|a| u32::MAX - a.1);
+        // Counted without allocating the conjunctions: removing an option from `rest`, a subset of `bits`, removes its conjunction with `bits`
+        let mut vec: Vec<_> = self.options.iter().map(|b| (b.1.and_count(bits), &b.1)).collect();
+        vec.sort_by_key(|a| u32::MAX - a.0);
 
         let total = bits.count_ones();
         let mut rest = bits.clone();
         let mut rest_count = rest.count_ones();
         let mut res = 0.0;
-        for (_, _, b) in vec {
-            rest.difference_assign(&b);
+        for (_, b) in vec {
+            rest.difference_assign(b);
             let count = rest_count - rest.count_ones();
             let p = count as f32 / total as f32;
             if p > 0.0 {
@@ -129,24 +154,51 @@ impl<'a, 'b> TreeLearning<'a, 'b> {
         }
     }
     
+    /// Estimates the cost of the branch solving `bits`: the cheapest option covering it, or otherwise the cheapest option solving part of it, as a lower bound.
+    fn branch_cost(&self, bits: &Bits) -> f32 {
+        let cheapest = |covering: bool| self.options.iter()
+            .filter(|(_, b)| if covering { bits.subset(b) } else { b.intersects(bits) })
+            .map(|(e, _)| e.cost()).min();
+        cheapest(true).or_else(|| cheapest(false)).unwrap_or(0) as f32
+    }
+
     #[inline]
     /// Determines the next action for an unsolved subproblem in the tree learning process. 
+    /// 
+    /// By default, the condition minimizing the conditional entropy is selected. 
+    /// With a positive `cost_weight` λ, the selected condition minimizes `entropy + λ * (cost(condition) + cost(then) + cost(else))` among those not increasing the entropy, where the costs of branches are estimated by `branch_cost`; accepted options are then the cheapest ones. 
+    /// This favors shallow trees with cheap conditions over entropy-optimal but expensive ones.
     pub fn select(&self, unsolved: &SubProblem<'a>) -> SelectResult {
         if let SubProblem::Unsolved(bits, entro) = unsolved {
             if *entro <= 0.0001 {
-                if let Some((i, _)) = self.options.iter().enumerate().find(|(_, x)| bits.subset(&x.1) ) {
+                let mut covering = self.options.iter().enumerate().filter(|(_, x)| bits.subset(&x.1));
+                let accepted = if self.cost_weight > 0.0 { covering.min_by_key(|(_, x)| x.0.cost()) } else { covering.next() };
+                if let Some((i, _)) = accepted {
                     return SelectResult::Accept(i)
                 }
             }
-            let (i, (centro, tb, fb)) = self.conditions.iter().enumerate()
+            // Conditions not splitting `bits` are skipped from their bits, before computing any entropy
+            let candidates = self.conditions.iter().enumerate()
+                .filter(|(_, (_, cb))| cb.intersects(bits) && bits.diff_count(cb) > 0)
                 .map(|(i, (e, cb))| {
                     let ce = self.cond_entropy(bits, cb);
                     (i, ce)
-                })
-                .min_by(|a, b| a.1.0.partial_cmp(&b.1.0).unwrap())
-                .expect("At least have one condition.");
-            if centro - 0.00001 < *entro {
+                });
+            let selected = if self.cost_weight > 0.0 {
+                candidates.filter(|(_, ce)| ce.0 - 0.00001 < *entro)
+                    .map(|(i, ce)| {
+                        let cost = self.condition_costs[i] + self.branch_cost(&ce.1.0) + self.branch_cost(&ce.2.0);
+                        (ce.0 + self.cost_weight * cost, i, ce)
+                    })
+                    .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+                    .map(|(_, i, ce)| (i, ce))
+            } else {
+                candidates.min_by(|a, b| a.1.0.partial_cmp(&b.1.0).unwrap()).filter(|(_, ce)| ce.0 - 0.00001 < *entro)
+            };
+            if let Some((i, (centro, tb, fb))) = selected {
                 SelectResult::Ite(i, centro, tb, fb)
+            } else if self.abstain {
+                self.options.iter().enumerate().max_by_key(|(_, x)| x.1.and_count(bits)).map_or(SelectResult::Failed, |(i, _)| SelectResult::Abstain(i))
             } else {
                 SelectResult::Failed
             }
@@ -162,6 +214,9 @@ impl<'a, 'b> TreeLearning<'a, 'b> {
                 SelectResult::Accept(i) => {
                     *last.borrow_mut() = SubProblem::Accept(i);
                 }
+                SelectResult::Abstain(i) => {
+                    *last.borrow_mut() = SubProblem::Abstain(i);
+                }
                 SelectResult::Ite(expr, entropy, t, f) => {
                     let tb = self.bump.alloc(SubProblem::Unsolved(t.0, t.1).into());
                     let fb = self.bump.alloc(SubProblem::Unsolved(f.0, f.1).into());
@@ -192,6 +247,8 @@ impl<'a, 'b> TreeLearning<'a, 'b> {
                 writeln!(f, "{indent}?? {} {:x?}", entropy, bits),
             SubProblem::Accept(i) => 
                 writeln!(f, "{indent}{:?}", self.options[*i].0),
+            SubProblem::Abstain(i) => 
+                writeln!(f, "{indent}abstain {:?}", self.options[*i].0),
             SubProblem::Ite { expr, entropy, t: tb, f: fb } => {
                 writeln!(f, "{indent}ite {:?} {:x?}", self.conditions[*expr].0, self.conditions[*expr].1)?;
                 indent.push_str("  ");
@@ -206,15 +263,15 @@ impl<'a, 'b> TreeLearning<'a, 'b> {
     fn size_recursive(&self, node: SubProb<'a>) -> usize {
         match &*node.borrow() {
             SubProblem::Unsolved(bits, entropy) => 1,
-            SubProblem::Accept(i) => 1,
-            SubProblem::Ite { expr, entropy, t: tb, f: fb } => 1 + self.size_recursive(tb) + self.size_recursive(fb),
+            SubProblem::Accept(i) | SubProblem::Abstain(i) => self.options[*i].0.size(),
+            SubProblem::Ite { expr, entropy, t: tb, f: fb } => 1 + self.conditions[*expr].0.size() + self.size_recursive(tb) + self.size_recursive(fb),
         }
     }
     /// Covers a decision tree recursively starting from a given node and determining the set of bits covered by the tree structure. 
     fn cover_recursive(&self, node: SubProb<'a>) -> Bits {
         match &*node.borrow() {
             SubProblem::Unsolved(bits, entropy) => bits.clone(),
-            SubProblem::Accept(i) => self.options[*i].1.clone(),
+            SubProblem::Accept(i) | SubProblem::Abstain(i) => self.options[*i].1.clone(),
             SubProblem::Ite { expr, entropy, t: tb, f: fb } => {
                 let mut t = self.cover_recursive(tb);
                 let mut f = self.cover_recursive(fb);
@@ -230,42 +287,65 @@ impl<'a, 'b> TreeLearning<'a, 'b> {
     fn expr_recursizve(&self, node: SubProb<'a>) -> &'static Expr {
         match &*node.borrow() {
             SubProblem::Unsolved(bits, entropy) => panic!("Still subproblem remain."),
-            SubProblem::Accept(i) => self.options[*i].0,
+            SubProblem::Accept(i) | SubProblem::Abstain(i) => self.options[*i].0,
             SubProblem::Ite { expr, entropy, t: tb, f: fb } => {
                 let t = self.expr_recursizve(tb);
                 let f = self.expr_recursizve(fb);
                 let cond = self.conditions[*expr].0;
-                cond.ite(t, f)
+                provenance::record(cond.ite(t, f), Provenance::TreeLearning)
             }
         }
     }
-    /// Recursively traverses through a decision tree to collect bits from unsolved subproblems. 
-    fn unsolved_recursive(&self, node: SubProb<'a>, result: &mut Vec<Box<[u128]>>) {
+    /// Returns the expression associated with the root of the decision tree. 
+    /// This function utilizes a recursive approach by invoking `expr_recursizve` on the tree's root node to retrieve the expression efficiently, leveraging the recursive structure to navigate through potentially complex tree configurations within the `TreeLearning` context.
+    /// The tree is recorded for the expression if [`record_trees`] was called.
+    pub fn expr(&self) -> &'static Expr {
+        let e = self.expr_recursizve(self.root);
+        if let Some(trees) = TREES.get() {
+            trees.lock().unwrap().entry(e).or_insert_with(|| self.tree());
+        }
+        e
+    }
+
+    /// Builds the node `node`, reached by `examples`.
+    fn tree_recursive(&self, node: SubProb<'a>, examples: Bits) -> DecisionNode {
         match &*node.borrow() {
-            SubProblem::Unsolved(bits, entropy) => {
-                result.push(bits.clone());
-            }
-            SubProblem::Accept(i) => {}
-            SubProblem::Ite { expr, entropy, t: tb, f: fb } => {
-                self.unsolved_recursive(tb, result);
-                self.unsolved_recursive(fb, result);
+            SubProblem::Unsolved(_, entropy) => DecisionNode::Unsolved { entropy: *entropy, examples },
+            SubProblem::Accept(i) => DecisionNode::Leaf { expr: self.options[*i].0, abstain: false, examples },
+            SubProblem::Abstain(i) => DecisionNode::Leaf { expr: self.options[*i].0, abstain: true, examples },
+            SubProblem::Ite { expr, entropy, t, f } => {
+                let (cond, bits) = &self.conditions[*expr];
+                let mut tb = examples.clone();
+                tb.conjunction_assign(bits);
+                let mut fb = examples.clone();
+                fb.difference_assign(bits);
+                DecisionNode::Ite {
+                    cond,
+                    entropy: *entropy,
+                    t: self.tree_recursive(t, tb).into(),
+                    f: self.tree_recursive(f, fb).into(),
+                    examples,
+                }
             }
         }
     }
-    /// Returns a vector of boxed slices containing `u128` values that represent unsolved components of a decision tree. 
-    fn unsolved(&self) -> Vec<Box<[u128]>> {
-        let mut result = Vec::new();
-        self.unsolved_recursive(self.root, &mut result);
-        result
+
+    /// Returns an owned copy of the decision tree, with the examples reaching each node.
+    pub fn tree(&self) -> DecisionTree {
+        let examples = if self.abstain {
+            self.options.iter().fold(Bits::zeros(self.size), |mut acc, (_, b)| { acc.union_assign(b); acc })
+        } else { bits::boxed_ones(self.size) };
+        DecisionTree { size: self.size, root: self.tree_recursive(self.root, examples) }
     }
-    /// Returns the expression associated with the root of the decision tree. 
-    /// This function utilizes a recursive approach by invoking `expr_recursizve` on the tree's root node to retrieve the expression efficiently, leveraging the recursive structure to navigate through potentially complex tree configurations within the `TreeLearning` context.
-    pub fn expr(&self) -> &'static Expr {
-        self.expr_recursizve(self.root)
+    
+    /// Returns the examples solved by the tree, which are all of them unless it has `Abstain` leaves.
+    pub fn cover(&self) -> Bits {
+        self.cover_recursive(self.root)
     }
     
     /// Calculates the result size of a decision tree by recursively determining the size starting from the root node. 
     /// This implementation utilizes the `size_recursive` function on the `root` to compute the cumulative size of the tree structure, which includes all subproblems, branches, and accepted solutions present in the tree.
+    /// Conditions and solutions count with their number of nodes, so that the size is that of the resulting expression.
     pub fn result_size(&self) -> usize {
         self.size_recursive(self.root)
     }
@@ -278,10 +358,131 @@ impl<'a, 'b> std::fmt::Debug for TreeLearning<'a, 'b> {
     }
 }
 
+#[derive(Debug, Clone)]
+/// A node of a [`DecisionTree`], with the examples reaching it.
+pub enum DecisionNode {
+    /// A subproblem left unsolved, with the entropy of its examples
+    Unsolved { entropy: f32, examples: Bits },
+    /// An option solving the examples of the node, or only most of them if `abstain` (see `TreeLearning::best_effort`)
+    Leaf { expr: &'static Expr, abstain: bool, examples: Bits },
+    /// A split on `cond`, with the conditional entropy of the split
+    Ite { cond: &'static Expr, entropy: f32, examples: Bits, t: Box<DecisionNode>, f: Box<DecisionNode> },
+}
+
+#[derive(Debug, Clone)]
+/// A decision tree learned by [`TreeLearning`], owned so that it outlives the arena of the learner (`--emit dot`).
+pub struct DecisionTree {
+    /// Number of examples
+    pub size: usize,
+    pub root: DecisionNode,
+}
+
+/// Decision trees of the expressions built by tree learning since [`record_trees`]. Nothing is recorded while it is not set.
+static TREES: OnceLock<Mutex<HashMap<&'static Expr, DecisionTree>>> = OnceLock::new();
+
+/// Starts recording the decision trees from which `TreeLearning::expr` builds expressions.
+pub fn record_trees() {
+    let _ = TREES.set(Mutex::new(HashMap::new()));
+}
+
+/// Returns the decision tree recorded for `e`, if it was built by tree learning.
+pub fn recorded_tree(e: &'static Expr) -> Option<DecisionTree> {
+    TREES.get()?.lock().unwrap().get(e).cloned()
+}
+
 #[inline(always)]
-pub fn tree_learning<'a, 'b>(options: Vec<(&'static Expr, Bits)>, conditions: &'b [(&'static Expr, Bits)], size: usize, bump: &'a Bump, limit: usize) -> TreeLearning<'a, 'b> {
-    let mut tl = TreeLearning::new_in(size, conditions, options, bump, limit);
+pub fn tree_learning<'a, 'b>(options: Vec<(&'static Expr, Bits)>, conditions: &'b [(&'static Expr, Bits)], size: usize, bump: &'a Bump, limit: usize, cost_weight: f32) -> TreeLearning<'a, 'b> {
+    let mut tl = TreeLearning::with_cost_weight(size, conditions, options, bump, limit, cost_weight);
     tl.run();
     tl
 }
 
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use std::fs;
+
+    use bumpalo::Bump;
+    use itertools::Itertools;
+
+    use crate::{expr, expr::{context::Context, Expr}, galloc::{AllocForAny, AllocForStr}, parser::problem::PBEProblem, value::ConstValue};
+
+    use super::{bits::BoxSliceExt, record_trees, recorded_tree, tree_learning, Bits, DecisionNode, TreeLearning};
+
+    /// The 240 examples of `test/bench/many-examples.sl`, with two options solving the examples with and without a `-`, and conditions testing their prefixes, digits and dashes.
+    fn many_examples() -> (Context, Vec<(&'static Expr, Bits)>, Vec<(&'static Expr, Bits)>) {
+        let s = fs::read_to_string("test/bench/many-examples.sl").unwrap();
+        let problem = PBEProblem::parse(s.as_str()).unwrap();
+        let ctx = Context::from_examples(&problem.examples);
+        let options = [expr!("none"), expr!(At (Split [0] "-") 1)].into_iter()
+            .map(|e| { let e = e.galloc(); (e, e.eval(&ctx).eq_bits(&ctx.output).unwrap()) }).collect_vec();
+        let str = |s: &str| Expr::Const(ConstValue::Str(s.galloc_str()));
+        let words = ["alpha", "beta", "gamma", "delta", "omega", "sigma", "kappa", "theta"].map(|w| expr!(PrefixOf {str(w)} [0]));
+        let digits = (0..10).map(|d| str(&d.to_string())).flat_map(|d| [expr!(Contains [0] {d.clone()}), expr!(SuffixOf {d} [0])]);
+        let conditions = words.into_iter().chain(digits).chain([expr!(Contains [0] "-")])
+            .map(|e| { let e = e.galloc(); (e, e.eval(&ctx).to_bits()) }).collect_vec();
+        (ctx, options, conditions)
+    }
+
+    #[bench]
+    fn bench_eq_bits(b: &mut test::Bencher) {
+        let (ctx, options, _) = many_examples();
+        let v = options[1].0.eval(&ctx);
+        assert_eq!(v.eq_bits(&ctx.output).unwrap().count_ones(), 160);
+        b.iter(|| v.eq_bits(&ctx.output));
+    }
+
+    #[bench]
+    fn bench_tree_learning(b: &mut test::Bencher) {
+        let (ctx, options, conditions) = many_examples();
+        let bump = Bump::new();
+        assert!(tree_learning(options.clone(), &conditions, ctx.len, &bump, 10, 0.0).solved);
+        b.iter(|| tree_learning(options.clone(), &conditions, ctx.len, &Bump::new(), 10, 0.0).solved);
+    }
+
+    #[test]
+    fn test_cost_weight() {
+        let bits = |v: [bool; 4]| Bits::from_bit_siter(v.into_iter());
+        let options = vec![(expr!([0]).galloc(), bits([true, true, false, false])), (expr!("x").galloc(), bits([false, false, true, true]))];
+        let expensive = expr!(PrefixOf (Concat [0] "a") (Concat [0] "b")).galloc();
+        let cheap = expr!(PrefixOf [0] "a").galloc();
+        let conditions = [(expensive, bits([true, true, false, false])), (cheap, bits([true, true, false, false]))];
+        let bump = Bump::new();
+        let tl = tree_learning(options.clone(), &conditions, 4, &bump, 10, 0.0);
+        assert!(tl.solved);
+        assert_eq!(tl.expr(), expr!(Ite {expensive} [0] "x").galloc());
+        let tl = tree_learning(options, &conditions, 4, &bump, 10, 0.1);
+        assert_eq!(tl.expr(), expr!(Ite {cheap} [0] "x").galloc());
+        assert_eq!(tl.result_size(), tl.expr().size());
+    }
+
+    #[test]
+    fn test_best_effort() {
+        let bits = |v: [bool; 4]| Bits::from_bit_siter(v.into_iter());
+        let options = vec![(expr!([0]).galloc(), bits([true, false, false, false])), (expr!("x").galloc(), bits([false, true, true, false]))];
+        let conditions = [(expr!(PrefixOf [0] "a").galloc(), bits([true, false, true, false]))];
+        let bump = Bump::new();
+        assert!(!tree_learning(options.clone(), &conditions, 4, &bump, 10, 0.0).solved);
+        let mut tl = TreeLearning::with_cost_weight(4, &conditions, options, &bump, 10, 0.0).best_effort();
+        assert!(tl.run());
+        assert_eq!(tl.cover().count_ones(), 2);
+    }
+
+    #[test]
+    fn test_tree() {
+        let bits = |v: [bool; 4]| Bits::from_bit_siter(v.into_iter());
+        let options = vec![(expr!([0]).galloc(), bits([true, true, false, false])), (expr!("x").galloc(), bits([false, false, true, true]))];
+        let cond = expr!(PrefixOf [0] "a").galloc();
+        let conditions = [(cond, bits([true, true, false, false]))];
+        let bump = Bump::new();
+        record_trees();
+        let e = tree_learning(options, &conditions, 4, &bump, 10, 0.0).expr();
+        let tree = recorded_tree(e).unwrap();
+        let DecisionNode::Ite { cond: c, examples, t, f, .. } = tree.root else { panic!() };
+        assert_eq!((c, examples.count_ones()), (cond, 4));
+        assert!(matches!(*t, DecisionNode::Leaf { abstain: false, ref examples, .. } if examples.count_ones() == 2));
+        assert!(matches!(*f, DecisionNode::Leaf { expr, .. } if *expr == expr!("x")));
+    }
+}
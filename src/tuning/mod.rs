@@ -0,0 +1,152 @@
+use std::{borrow::Cow, collections::{BTreeMap, BTreeSet}, fs, path::Path};
+
+use derive_more::{Display, From};
+use itertools::Itertools;
+use pest::Parser;
+use serde::{Deserialize, Serialize};
+
+use crate::{expr::Expr, parser::{check::DefineFun, config::Config, input, problem::{self, PBEProblem, ProblemParser, Rule}, prod}, value::ConstValue};
+
+/// Cost table loaded with `--costs`, consulted whenever an operator is created by name.
+pub static COST_TABLE: spin::Mutex<Option<CostTable>> = spin::Mutex::new(None);
+
+#[derive(Debug, Display, From)]
+/// Errors raised while learning or loading a cost table.
+pub enum TuneError {
+    #[display(fmt = "{}", _0)]
+    Io(std::io::Error),
+    #[display(fmt = "{}", _0)]
+    Json(serde_json::Error),
+    #[display(fmt = "{}: {}", _0, _1)]
+    Parse(String, Box<problem::Error>),
+    #[display(fmt = "No solved problem found in {}", _0)]
+    Empty(String),
+}
+
+impl std::error::Error for TuneError {}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+/// Enumeration cost of each operator, indexed by operator name and stored as a JSON object.
+pub struct CostTable(pub BTreeMap<String, usize>);
+
+impl CostTable {
+    /// Reads a cost table from a JSON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TuneError> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+    /// Writes the cost table as a JSON file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), TuneError> {
+        Ok(fs::write(path, serde_json::to_string_pretty(self)? + "\n")?)
+    }
+    /// Derives costs from operator frequencies.
+    ///
+    /// `usage[op]` counts the solutions using `op`; every operator offered by a grammar should be present, possibly with a count of zero.
+    /// Counts are smoothed by one, and every halving of the frequency relative to the most used operator adds one to the cost, the most used operator having cost 1.
+    pub fn from_usage(usage: &BTreeMap<String, usize>) -> Self {
+        let max = usage.values().copied().max().unwrap_or(0) + 1;
+        Self(usage.iter().map(|(op, n)| (op.clone(), 1 + (max as f64 / (n + 1) as f64).log2().round() as usize)).collect())
+    }
+}
+
+/// Returns `config` with the `cost` of operator `name` taken from the loaded cost table, if any.
+///
+/// The table takes precedence over `#cost` annotations of the grammar, since it is meant to adapt the built-in grammars to a workload.
+pub fn tuned_config<'a>(name: &str, config: &'a Config) -> Cow<'a, Config> {
+    match COST_TABLE.lock().as_ref().and_then(|t| t.0.get(name)) {
+        Some(cost) => {
+            let mut config = config.clone();
+            config.set("cost", ConstValue::Int(*cost as i64));
+            Cow::Owned(config)
+        }
+        None => Cow::Borrowed(config),
+    }
+}
+
+/// Collects the names of the operators used in an expression.
+fn used_operators(e: &Expr, result: &mut BTreeSet<String>) {
+    match e {
+        Expr::Const(_) | Expr::Var(_) => (),
+        Expr::Op1(op, a1) => {
+            result.insert(op.name().to_string());
+            used_operators(a1, result);
+        }
+        Expr::Op2(op, a1, a2) => {
+            result.insert(op.name().to_string());
+            used_operators(a1, result);
+            used_operators(a2, result);
+        }
+        Expr::Op3(op, a1, a2, a3) => {
+            result.insert(op.name().to_string());
+            used_operators(a1, result);
+            used_operators(a2, result);
+            used_operators(a3, result);
+        }
+        Expr::Let(_, a1, a2) => {
+            used_operators(a1, result);
+            used_operators(a2, result);
+        }
+    }
+}
+
+/// Parses a solution file: a `define-fun`, possibly preceded by `;` comment lines.
+pub fn parse_solution(s: &str) -> Result<DefineFun, problem::Error> {
+    let s = s.lines().skip_while(|l| l.trim().is_empty() || l.trim_start().starts_with(';')).join("\n");
+    let [definefun]: [_; 1] = ProblemParser::parse(Rule::definefun, s.trim())?.collect_vec().try_into().unwrap();
+    DefineFun::parse(definefun)
+}
+
+/// Learns a cost table from a directory of solved problems.
+///
+/// Each problem `name.sl` is paired with its solution `name.sol`, a `define-fun` as printed by Synthphonia; problems without a solution are skipped.
+/// Operators offered by the grammars of the problems but never used by a solution get the highest costs.
+pub fn tune(dir: impl AsRef<Path>) -> Result<CostTable, TuneError> {
+    let mut usage: BTreeMap<String, usize> = BTreeMap::new();
+    let mut solved = 0;
+    for path in fs::read_dir(dir.as_ref())?.map_ok(|e| e.path()).try_collect::<_, Vec<_>, _>()?.into_iter().sorted() {
+        let solution = path.with_extension("sol");
+        if path.extension().is_none_or(|e| e != "sl") || !solution.exists() { continue; }
+        let parse_error = |e| TuneError::Parse(path.display().to_string(), Box::new(e));
+
+        let problem = PBEProblem::parse(&input::read_to_string(&path)?).map_err(parse_error)?;
+        for nt in problem.synthfun().cfg.inner.iter() {
+            for rule in nt.2.iter() {
+                if let prod::ProdRule::Op1(op, ..) | prod::ProdRule::Op2(op, ..) | prod::ProdRule::Op3(op, ..) = rule {
+                    usage.entry(op.clone()).or_default();
+                }
+            }
+        }
+        let solution = parse_solution(&input::read_to_string(&solution)?).map_err(parse_error)?;
+        let mut ops = BTreeSet::new();
+        used_operators(solution.expr, &mut ops);
+        for op in ops {
+            *usage.entry(op).or_default() += 1;
+        }
+        solved += 1;
+    }
+    if solved == 0 {
+        return Err(TuneError::Empty(dir.as_ref().display().to_string()));
+    }
+    Ok(CostTable::from_usage(&usage))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{parse_solution, CostTable};
+
+    #[test]
+    fn test_cost_table() {
+        let usage: BTreeMap<String, usize> = [("str.++", 15), ("str.substr", 7), ("str.replace", 0)].map(|(k, v)| (k.to_string(), v)).into();
+        let table = CostTable::from_usage(&usage);
+        assert_eq!(table.0["str.++"], 1);
+        assert_eq!(table.0["str.substr"], 2);
+        assert_eq!(table.0["str.replace"], 5);
+        let json = serde_json::to_string(&table).unwrap();
+        assert_eq!(serde_json::from_str::<CostTable>(&json).unwrap(), table);
+
+        let solution = parse_solution("; found in 2s\n(define-fun f ((name String)) String (str.++ name \"a\"))\n; Coverage: 3/3 examples").unwrap();
+        assert_eq!(solution.sig.name, "f");
+    }
+}
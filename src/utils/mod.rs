@@ -1,11 +1,13 @@
 
 use derive_more::{From, Into, Deref, DerefMut, Display, DebugCustom};
+use serde::{Deserialize, Serialize};
 use futures::{future::select, FutureExt};
 use futures_core::Future;
 
 pub mod join;
 pub mod nested;
-#[derive(From, Into, Deref, DerefMut, DebugCustom, Display, PartialEq, PartialOrd, Clone, Copy)]
+#[derive(From, Into, Deref, DerefMut, DebugCustom, Display, PartialEq, PartialOrd, Clone, Copy, Serialize, Deserialize)]
+#[serde(transparent)]
 #[debug(fmt = "{:?}", _0)]
 #[display(fmt = "{:?}", _0)]
 /// A newtype wrapper encapsulating a 64-bit floating-point value. 
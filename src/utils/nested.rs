@@ -62,7 +62,7 @@ impl<T: Clone> NestedIntervalTree<T> {
     /// 
     /// 
     /// Uses a vector of interval range vectors to represent the nesting levels, delegating recursive insertion to a helper that traverses and builds intermediate nodes as needed, while using a no-op update function for branch nodes.
-    pub fn insert_multiple<'a: 'b, 'b>(&'a mut self, ranges: &Vec<Vec<Range<usize>>>, value: T) {
+    pub fn insert_multiple<'a: 'b, 'b>(&'a mut self, ranges: &[Vec<Range<usize>>], value: T) {
         self.insert_using_iter(ranges.iter().map(|x| x.iter().cloned()), &|_| (), value)
     }
     /// Inserts a value into the nested interval tree using a provided slice of ranges. 
@@ -145,14 +145,14 @@ impl<T> NestedIntervalTree<T> {
     /// Returns an iterator over all values whose associated intervals contain the specified super ranges.
     /// 
     /// Delegates to a lower-level iterator implementation by mapping the provided vector of range vectors into an appropriate iterator form for traversing the nested interval structure, ultimately yielding references to values that satisfy the super-range condition.
-    pub fn superrange_multiple<'a: 'b, 'b>(&'a self, ranges: &'b Vec<Vec<Range<usize>>>) -> Box<dyn Iterator<Item=&'b T> + 'b> {
+    pub fn superrange_multiple<'a: 'b, 'b>(&'a self, ranges: &'b [Vec<Range<usize>>]) -> Box<dyn Iterator<Item=&'b T> + 'b> {
         self.superrange_using_iter(ranges.iter().map(|x| x.iter().cloned()))
     }
     /// Returns an iterator over elements from nested intervals that are enclosed by the specified multiple subrange lists provided as a vector of vectors. 
     /// 
     /// 
     /// Invokes an internal subrange retrieval function by mapping the provided vector of range lists into an iterator of cloned ranges, thereby allowing the caller to iterate over all matching elements in the tree that fall within the prescribed nested subranges.
-    pub fn subrange_multiple<'a: 'b, 'b>(&'a self, ranges: &'b Vec<Vec<Range<usize>>>) -> Box<dyn Iterator<Item=&'b T> + 'b> {
+    pub fn subrange_multiple<'a: 'b, 'b>(&'a self, ranges: &'b [Vec<Range<usize>>]) -> Box<dyn Iterator<Item=&'b T> + 'b> {
         self.subrange_using_iter(ranges.iter().map(|x| x.iter().cloned()))
     }
     /// Return an iterator yielding references to values whose associated intervals form a superrange of the provided sequence of index ranges. 
@@ -3,20 +3,24 @@ use derive_more::Display;
 use derive_more::TryInto;
 use derive_more::From;
 use itertools::Itertools;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::expr::Expr;
+use crate::expr::{Expr, ExprData, OpTable};
 use crate::galloc::AllocForExactSizeIter;
 use crate::galloc::AllocForIter;
+use crate::galloc::AllocForStr;
+use crate::galloc::Region;
 use crate::tree_learning::bits::BoxSliceExt;
 use crate::tree_learning::Bits;
 use crate::utils::F64;
 
 
-#[derive(DebugCustom, PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(DebugCustom, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
 /// Represents a comprehensive set of distinct type variants including basic and list-based types. 
 /// 
 /// 
-/// Defines available kinds such as null, integer, boolean, string, float, and their corresponding list forms for integers and strings, with each variant accompanied by custom formatting annotations intended for debugging and display purposes.
+/// Defines available kinds such as null, integer, boolean, string, float, character, and their corresponding list forms for integers and strings, with each variant accompanied by custom formatting annotations intended for debugging and display purposes.
 pub enum Type {
     #[debug(fmt = "Null")]
     Null,
@@ -28,12 +32,18 @@ pub enum Type {
     Str,
     #[debug(fmt = "Float")]
     Float,
+    #[debug(fmt = "Char")]
+    Char,
     #[debug(fmt = "(List Int)")]
     ListInt,
     #[debug(fmt = "(List String)")]
     ListStr,
     #[debug(fmt = "(_ BitVec {_0})")]
     BitVector(usize),
+    #[debug(fmt = "(Map String String)")]
+    MapStr,
+    #[debug(fmt = "(Tuple String String)")]
+    Tuple,
 }
 
 impl Type {
@@ -61,10 +71,10 @@ impl Type {
     }
 }
 
-#[derive(DebugCustom, Clone, TryInto, Copy, PartialEq, Eq, Hash, From)]
+#[derive(DebugCustom, Clone, TryInto, Copy, PartialEq, Eq, Hash, From, Serialize)]
 /// A collection of constant values representing various primitive and collection types. 
 /// 
-/// This enumeration encapsulates integers, floats, booleans, and strings as well as lists of integers and strings, with each variant storing its data as a static slice to ensure efficient access. 
+/// This enumeration encapsulates integers, floats, booleans, strings, and characters as well as lists of integers and strings and string-to-string maps (lookup tables), with each variant storing its data as a static slice to ensure efficient access. 
 /// Additionally, a null variant is provided to denote the absence of a value.
 /// 
 pub enum Value {
@@ -77,11 +87,17 @@ pub enum Value {
     #[debug(fmt = "{:?}", _0)]
     Str(&'static [&'static str]),
     #[debug(fmt = "{:?}", _0)]
+    Char(&'static [char]),
+    #[debug(fmt = "{:?}", _0)]
     ListInt(&'static [&'static [i64]]),
     #[debug(fmt = "{:?}", _0)]
     ListStr(&'static [&'static [&'static str]]),
     #[debug(fmt = "{:x?}", _1)]
     BitVector(usize, &'static [u64]),
+    #[debug(fmt = "{:?}", _0)]
+    MapStr(&'static [&'static [(&'static str, &'static str)]]),
+    #[debug(fmt = "{:?}", _0)]
+    Tuple(&'static [(&'static str, &'static str)]),
     #[debug(fmt = "null")]
     Null,
 }
@@ -99,9 +115,12 @@ impl Value {
             Value::Float(a) => Value::Float(exs.iter().cloned().map(|i| a[i]).galloc_scollect()),
             Value::Bool(a) => Value::Bool(exs.iter().cloned().map(|i| a[i]).galloc_scollect()),
             Value::Str(a) => Value::Str(exs.iter().cloned().map(|i| a[i]).galloc_scollect()),
+            Value::Char(a) => Value::Char(exs.iter().cloned().map(|i| a[i]).galloc_scollect()),
             Value::ListInt(a) => Value::ListInt(exs.iter().cloned().map(|i| a[i]).galloc_scollect()),
             Value::ListStr(a) => Value::ListStr(exs.iter().cloned().map(|i| a[i]).galloc_scollect()),
             Value::BitVector(i, a) => Value::BitVector(i, exs.iter().cloned().map(|j| a[j]).galloc_scollect()),
+            Value::MapStr(a) => Value::MapStr(exs.iter().cloned().map(|i| a[i]).galloc_scollect()),
+            Value::Tuple(a) => Value::Tuple(exs.iter().cloned().map(|i| a[i]).galloc_scollect()),
             Value::Null => Value::Null,
         }
     }
@@ -116,9 +135,12 @@ impl Value {
             Self::Bool(_) => Type::Bool,
             Self::Str(_) => Type::Str,
             Self::Float(_) => Type::Float,
+            Self::Char(_) => Type::Char,
             Self::ListInt(_) => Type::ListInt,
             Self::ListStr(_) => Type::ListStr,
             Self::BitVector(i, _) => Type::BitVector(*i),
+            Self::MapStr(_) => Type::MapStr,
+            Self::Tuple(_) => Type::Tuple,
             Self::Null => Type::Null,
         }
     }
@@ -133,9 +155,12 @@ impl Value {
             Value::Bool(b) => b.len(),
             Value::Str(s) => s.len(),
             Value::Float(s) => s.len(),
+            Value::Char(s) => s.len(),
             Value::ListInt(l) => l.len(),
             Value::ListStr(l) => l.len(),
             Value::BitVector(_, b) => b.len(),
+            Value::MapStr(m) => m.len(),
+            Value::Tuple(t) => t.len(),
             Value::Null => 0,
         }
     }
@@ -147,11 +172,14 @@ impl Value {
             Value::Int(a) => None,
             Value::Bool(b) => None,
             Value::Float(s) => None,
+            Value::Char(s) => None,
             Value::Null => None,
             Value::Str(s) => Some(s.iter().map(|x| x.len()).collect_vec()),
             Value::ListInt(l) => Some(l.iter().map(|x| x.len()).collect_vec()),
             Value::ListStr(l) => Some(l.iter().map(|x| x.len()).collect_vec()),
             Value::BitVector(_, _) => None,
+            Value::MapStr(_) => None,
+            Value::Tuple(_) => None,
         }
     }
     #[inline(always)]
@@ -159,15 +187,23 @@ impl Value {
     /// 
     /// This function operates by taking a value holding either a singular string or a list of strings and returns a reference to a static slice where each element corresponds to a single-character substring from the original strings. 
     /// It panics if the value is of any other type, ensuring that only supported string types are processed.
-    /// 
+    /// The slice stays in the thread arena until the end of the program; see `flatten_in` for temporary ones.
     pub fn flatten_leak(&self) -> &'static [&'static str] {
         // Memory Leak !!!
         match self {
-            Value::Str(s) => s.iter().flat_map(|x| (0..x.len()).map(|i| &x[i..i+1]) ).galloc_collect(),
+            Value::Str(s) => s.iter().flat_map(|x| x.char_indices().map(|(i, c)| &x[i..i + c.len_utf8()])).galloc_collect(),
             Value::ListStr(l) => l.iter().flat_map(|x| x.iter().copied()).galloc_collect(),
             _ => panic!("Mismatched type: to_liststr_leak")
         }
     }
+    /// Flattens the contained string(s) as `flatten_leak`, into a slice allocated in `region`, which is reclaimed when the region is popped.
+    pub fn flatten_in(&self, region: &Region) -> &'static [&'static str] {
+        match self {
+            Value::Str(s) => region.collect(s.iter().flat_map(|x| x.char_indices().map(|(i, c)| &x[i..i + c.len_utf8()]))),
+            Value::ListStr(l) => region.collect(l.iter().flat_map(|x| x.iter().copied())),
+            _ => panic!("Mismatched type: flatten_in")
+        }
+    }
     #[inline(always)]
     /// Converts a value holding strings into an optional flattened representation as a static slice of string slices. 
     /// 
@@ -177,7 +213,7 @@ impl Value {
     pub fn try_flatten_leak(&self) -> Option<&'static [&'static str]> {
         // Memory Leak !!!
         match self {
-            Value::Str(s) => Some(s.iter().flat_map(|x| (0..x.len()).map(|i| &x[i..i+1]) ).galloc_collect()),
+            Value::Str(s) => Some(s.iter().flat_map(|x| x.char_indices().map(|(i, c)| &x[i..i + c.len_utf8()])).galloc_collect()),
             Value::ListStr(l) => Some(l.iter().flat_map(|x| x.iter().copied()).galloc_collect()),
             _ => None,
         }
@@ -194,7 +230,11 @@ impl Value {
             Type::Int => Value::Int(constants.map(|p| p.as_i64().unwrap()).galloc_scollect()),
             Type::Str => Value::Str(constants.map(|p| p.as_str().unwrap()).galloc_scollect()),
             Type::Float => Value::Float(constants.map(|p| p.as_float().unwrap()).galloc_scollect()),
+            Type::Char => Value::Char(constants.map(|p| p.as_char().unwrap()).galloc_scollect()),
+            Type::MapStr => Value::MapStr(constants.map(|p| p.as_map().unwrap()).galloc_scollect()),
             Type::BitVector(i) => Value::BitVector(i, constants.map(|p| p.as_bv().unwrap()).galloc_scollect()),
+            Type::Tuple => Value::Tuple(constants.map(|p| p.as_tuple().unwrap()).galloc_scollect()),
+            Type::ListStr => Value::ListStr(constants.map(|p| p.as_liststr().unwrap()).galloc_scollect()),
             _ => panic!("should not reach here"),
         }
     }
@@ -226,6 +266,10 @@ impl Value {
     pub fn to_int(self) -> &'static [i64] {
         self.try_into().unwrap()
     }
+    /// Converts the value into a static slice of characters, panicking if it does not hold characters.
+    pub fn to_char(self) -> &'static [char] {
+        self.try_into().unwrap()
+    }
     /// Converts a value into a static list of string slices by performing a conversion using the TryInto trait. 
     /// Panics if the conversion fails, returning the resulting list of string slices upon success.
     pub fn to_liststr(self) -> &'static [&'static [&'static str]] {
@@ -278,42 +322,74 @@ impl Value {
     }
     /// Computes the number of pairwise equal elements shared between two values. 
     /// 
-    /// This function compares the corresponding elements of two instances by iterating over their internal collections and counting pairs that are equal. 
-    /// It supports several variants (such as integers, strings, floats, booleans, and lists) by aligning elements in parallel; when the compared types do not match, it returns zero.
+    /// The elements are compared as by `eq_bits`, whose bits are counted; when the compared types do not match, it returns zero.
     /// 
     pub fn eq_count(&self, other: &Self) -> usize {
-        match (self, other) {
-            (Self::Int(a1), Self::Int(a2)) => a1.iter().zip(a2.iter()).filter(|(a, b)| a == b).count(),
-            (Self::Str(a1), Self::Str(a2)) => a1.iter().zip(a2.iter()).filter(|(a, b)| a == b).count(),
-            (Self::Float(a1), Self::Float(a2)) => a1.iter().zip(a2.iter()).filter(|(a, b)| a == b).count(),
-            (Self::Bool(a1), Self::Bool(a2)) => a1.iter().zip(a2.iter()).filter(|(a, b)| a == b).count(),
-            (Self::ListInt(a1), Self::ListInt(a2)) => a1.iter().zip(a2.iter()).filter(|(a, b)| a == b).count(),
-            (Self::ListStr(a1), Self::ListStr(a2)) => a1.iter().zip(a2.iter()).filter(|(a, b)| a == b).count(),
-            _ => 0,
-        }
+        self.eq_bits(other).map_or(0, |b| b.count_ones() as usize)
     }
     /// Compares two values and computes a bit mask representing elementwise equality. 
     /// 
     /// This function performs an elementwise comparison between the contents of two values of the same specific variant and returns an optional bit mask where each bit indicates whether corresponding elements are equal. 
-    /// If the two values do not belong to a matching variant that supports elementwise comparison (e.g., comparing an integer sequence to a boolean sequence), the function returns None.
-    /// 
+    /// The bits are packed 128 at a time (see `BoxSliceExt::from_fn`), and strings are first compared by address, since equal strings of the term bank are often the same allocation.
     pub fn eq_bits(&self, other: &Self) -> Option<Bits> {
+        fn bits<T: PartialEq>(a1: &[T], a2: &[T]) -> Bits {
+            Bits::from_fn(a1.len().min(a2.len()), |i| a1[i] == a2[i])
+        }
         match (self, other) {
-            (Self::Int(a1), Self::Int(a2)) => Some(Bits::from_bit_siter(a1.iter().zip(a2.iter()).map(|(a, b)| a == b))),
-            (Self::Str(a1), Self::Str(a2)) => Some(Bits::from_bit_siter(a1.iter().zip(a2.iter()).map(|(a, b)| a == b))),
-            (Self::Float(a1), Self::Float(a2)) => Some(Bits::from_bit_siter(a1.iter().zip(a2.iter()).map(|(a, b)| a == b))),
-            (Self::Bool(a1), Self::Bool(a2)) => Some(Bits::from_bit_siter(a1.iter().zip(a2.iter()).map(|(a, b)| a == b))),
-            (Self::ListInt(a1), Self::ListInt(a2)) => Some(Bits::from_bit_siter(a1.iter().zip(a2.iter()).map(|(a, b)| a == b))),
-            (Self::ListStr(a1), Self::ListStr(a2)) => Some(Bits::from_bit_siter(a1.iter().zip(a2.iter()).map(|(a, b)| a == b))),
+            (Self::Int(a1), Self::Int(a2)) => Some(bits(a1, a2)),
+            (Self::Str(a1), Self::Str(a2)) => Some(Bits::from_fn(a1.len().min(a2.len()), |i| std::ptr::eq(a1[i], a2[i]) || a1[i] == a2[i])),
+            (Self::Float(a1), Self::Float(a2)) => Some(bits(a1, a2)),
+            (Self::Bool(a1), Self::Bool(a2)) => Some(bits(a1, a2)),
+            (Self::Char(a1), Self::Char(a2)) => Some(bits(a1, a2)),
+            (Self::ListInt(a1), Self::ListInt(a2)) => Some(bits(a1, a2)),
+            (Self::ListStr(a1), Self::ListStr(a2)) => Some(bits(a1, a2)),
+            (Self::MapStr(a1), Self::MapStr(a2)) => Some(bits(a1, a2)),
+            (Self::Tuple(a1), Self::Tuple(a2)) => Some(bits(a1, a2)),
             _ => None,
         }
     }
+    /// Returns the `i`-th example of the value as a constant. 
+    /// 
+    /// Panics for lists of integers, which have no constant representation.
+    pub fn get_const(&self, i: usize) -> ConstValue {
+        match self {
+            Value::Int(a) => ConstValue::Int(a[i]),
+            Value::Float(a) => ConstValue::Float(a[i]),
+            Value::Bool(a) => ConstValue::Bool(a[i]),
+            Value::Str(a) => ConstValue::Str(a[i]),
+            Value::Char(a) => ConstValue::Char(a[i]),
+            Value::BitVector(n, a) => ConstValue::BitVector(*n, a[i]),
+            Value::MapStr(a) => ConstValue::MapStr(a[i]),
+            Value::Tuple(a) => ConstValue::Tuple(a[i].0, a[i].1),
+            Value::ListStr(a) => ConstValue::ListStr(a[i]),
+            Value::Null => ConstValue::Null,
+            Value::ListInt(_) => panic!("Unable to convert a list of integers to a constant"),
+        }
+    }
+    /// Renders the `i`-th example of the value as a human-readable cell. 
+    /// 
+    /// Strings and lists are shown with their debug representation so that whitespace and delimiters stay visible, numbers and booleans are shown plainly, and bit vectors are printed in hexadecimal.
+    pub fn to_display_string(&self, i: usize) -> String {
+        match self {
+            Value::Int(a) => a[i].to_string(),
+            Value::Float(a) => a[i].0.to_string(),
+            Value::Bool(a) => a[i].to_string(),
+            Value::Str(a) => format!("{:?}", a[i]),
+            Value::Char(a) => format!("{:?}", a[i]),
+            Value::ListInt(a) => format!("{:?}", a[i]),
+            Value::ListStr(a) => format!("{:?}", a[i]),
+            Value::BitVector(_, a) => format!("#x{:x}", a[i]),
+            Value::MapStr(a) => format_map(a[i]),
+            Value::Tuple(a) => format!("(tuple {:?} {:?})", a[i].0, a[i].1),
+            Value::Null => "null".to_string(),
+        }
+    }
 }
 
 
-#[derive(DebugCustom, Display, PartialEq, Eq, Hash, Clone, Copy, From)]
+#[derive(DebugCustom, Display, PartialEq, Eq, Hash, Clone, Copy, From, Serialize)]
 /// Represents a constant value that abstracts various literal types and expressions. 
-/// This type encapsulates null, boolean, integer, string, floating-point, and expression values, each with respective formatting behavior for debugging and display purposes.
+/// This type encapsulates null, boolean, integer, string, floating-point, character, and expression values, each with respective formatting behavior for debugging and display purposes.
 pub enum ConstValue {
     #[debug(fmt = "null")]
     #[display(fmt = "null")]
@@ -331,13 +407,35 @@ pub enum ConstValue {
     #[display(fmt = "{:?}", _0)]
     Float(F64),
     #[debug(fmt = "{:?}", _0)]
+    #[display(fmt = "(char {:?})", "_0.to_string()")]
+    Char(char),
+    #[debug(fmt = "{:?}", _0)]
     #[display(fmt = "{:?}", _0)]
     BitVector(usize, u64),
+    #[debug(fmt = "{}", "format_map(_0)")]
+    #[display(fmt = "{}", "format_map(_0)")]
+    MapStr(&'static [(&'static str, &'static str)]),
+    #[debug(fmt = "(tuple {:?} {:?})", _0, _1)]
+    #[display(fmt = "(tuple {:?} {:?})", _0, _1)]
+    Tuple(&'static str, &'static str),
+    #[debug(fmt = "{}", "format_list(_0)")]
+    #[display(fmt = "{}", "format_list(_0)")]
+    ListStr(&'static [&'static str]),
     #[debug(fmt = "{:?}", _0)]
     #[display(fmt = "{:?}", _0)]
     Expr(&'static Expr)
 }
 
+/// Formats a lookup table as a map literal: `(map ("CA" "California") ("NY" "New York"))`.
+pub fn format_map(m: &[(&str, &str)]) -> String {
+    std::iter::once("(map".to_string()).chain(m.iter().map(|(k, v)| format!(" ({:?} {:?})", k, v))).chain(std::iter::once(")".to_string())).collect()
+}
+
+/// Formats a list of strings as a `(list "a" "b")` literal.
+pub fn format_list(l: &[&str]) -> String {
+    std::iter::once("(list".to_string()).chain(l.iter().map(|s| format!(" {:?}", s))).chain(std::iter::once(")".to_string())).collect()
+}
+
 impl From<usize> for ConstValue {
     /// Converts a usize value into a constant integer by casting it into a 64-bit integer. 
     /// 
@@ -365,7 +463,11 @@ impl ConstValue {
             Self::Bool(_) => Type::Bool,
             Self::Str(_) => Type::Str,
             Self::Float(_) => Type::Float,
+            Self::Char(_) => Type::Char,
             Self::BitVector(i, _) => Type::BitVector(*i),
+            Self::MapStr(_) => Type::MapStr,
+            Self::Tuple(_, _) => Type::Tuple,
+            Self::ListStr(_) => Type::ListStr,
             Self::Null => Type::Null,
             Self::Expr(_) => Type::Null,
         }
@@ -393,6 +495,14 @@ impl ConstValue {
     /// 
     /// Checks whether the constant is of the float variant, and if so, returns its underlying value wrapped in an Option; otherwise, it produces None.
     pub fn as_float(&self) -> Option<F64> { if let Self::Float(b) = self { Some(*b) } else { None }}
+    /// Returns the character held by the constant, if any.
+    pub fn as_char(&self) -> Option<char> { if let Self::Char(b) = self { Some(*b) } else { None }}
+    /// Returns the entries of the lookup table held by the constant, if any.
+    pub fn as_map(&self) -> Option<&'static [(&'static str, &'static str)]> { if let Self::MapStr(b) = self { Some(*b) } else { None }}
+    /// Returns the components of the pair held by the constant, if any.
+    pub fn as_tuple(&self) -> Option<(&'static str, &'static str)> { if let Self::Tuple(a, b) = self { Some((*a, *b)) } else { None }}
+    /// Returns the elements of the list of strings held by the constant, if any.
+    pub fn as_liststr(&self) -> Option<&'static [&'static str]> { if let Self::ListStr(l) = self { Some(*l) } else { None }}
     /// Returns an optional static reference to an expression if the constant value represents an expression variant. 
     /// This function checks whether the constant holds an expression and, if so, returns it wrapped in an option; otherwise, it returns None.
     pub fn as_expr(&self) -> Option<&'static Expr> { if let Self::Expr(b) = self { Some(*b) } else { None }}
@@ -415,7 +525,11 @@ impl ConstValue {
             ConstValue::Int(t) => Value::Int((0..len).map(|_| *t).galloc_scollect()),
             ConstValue::Str(t) => Value::Str((0..len).map(|_| *t).galloc_scollect()),
             ConstValue::Float(f) => Value::Float((0..len).map(|_| *f).galloc_scollect()),
+            ConstValue::Char(c) => Value::Char((0..len).map(|_| *c).galloc_scollect()),
+            ConstValue::MapStr(m) => Value::MapStr((0..len).map(|_| *m).galloc_scollect()),
             ConstValue::BitVector(i, a) => Value::BitVector(*i, (0..len).map(|_| *a).galloc_scollect()),
+            ConstValue::Tuple(a, b) => Value::Tuple((0..len).map(|_| (*a, *b)).galloc_scollect()),
+            ConstValue::ListStr(l) => Value::ListStr((0..len).map(|_| *l).galloc_scollect()),
             ConstValue::Null => panic!("Unable to convert Null to Value"),
             ConstValue::Expr(_) => panic!("Unable to convert Expr to Value"),
         }
@@ -435,7 +549,11 @@ pub fn consts_to_value(consts: Vec<ConstValue>) -> Value {
         ConstValue::Int(_) => Value::Int(consts.into_iter().map(|a| a.as_i64().unwrap()).galloc_scollect()),
         ConstValue::Str(_) => Value::Str(consts.into_iter().map(|a| a.as_str().unwrap()).galloc_scollect()),
         ConstValue::Float(_) => Value::Float(consts.into_iter().map(|a| a.as_float().unwrap()).galloc_scollect()),
+        ConstValue::Char(_) => Value::Char(consts.into_iter().map(|a| a.as_char().unwrap()).galloc_scollect()),
+        ConstValue::MapStr(_) => Value::MapStr(consts.into_iter().map(|a| a.as_map().unwrap()).galloc_scollect()),
         ConstValue::BitVector(i, _) => Value::BitVector(i, consts.into_iter().map(|a| a.as_bv().unwrap()).galloc_scollect()),
+        ConstValue::Tuple(_, _) => Value::Tuple(consts.into_iter().map(|a| a.as_tuple().unwrap()).galloc_scollect()),
+        ConstValue::ListStr(_) => Value::ListStr(consts.into_iter().map(|a| a.as_liststr().unwrap()).galloc_scollect()),
         ConstValue::Expr(_) => todo!(),
     }
 }
@@ -459,3 +577,83 @@ macro_rules! const_value {
         } else { panic!("Invalid literal {}", $l) }
     };
 }
+
+#[derive(Deserialize)]
+#[serde(rename = "Value")]
+/// Owned counterpart of [`Value`], with the variants in the same order so that both share one serialized form.
+enum ValueData {
+    Int(Vec<i64>),
+    Float(Vec<F64>),
+    Bool(Vec<bool>),
+    Str(Vec<String>),
+    Char(Vec<char>),
+    ListInt(Vec<Vec<i64>>),
+    ListStr(Vec<Vec<String>>),
+    BitVector(usize, Vec<u64>),
+    MapStr(Vec<Vec<(String, String)>>),
+    Tuple(Vec<(String, String)>),
+    Null,
+}
+
+/// Allocates the entries of a deserialized lookup table.
+fn alloc_map(m: Vec<(String, String)>) -> &'static [(&'static str, &'static str)] {
+    m.into_iter().map(|(k, v)| (k.galloc_str(), v.galloc_str())).collect_vec().into_iter().galloc_scollect()
+}
+
+impl<'de> Deserialize<'de> for Value {
+    /// Deserializes a value, allocating its content in the arena of the current thread.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let strs = |v: Vec<String>| v.into_iter().map(|s| s.galloc_str()).collect_vec().into_iter().galloc_scollect();
+        Ok(match ValueData::deserialize(deserializer)? {
+            ValueData::Int(a) => Value::Int(a.into_iter().galloc_scollect()),
+            ValueData::Float(a) => Value::Float(a.into_iter().galloc_scollect()),
+            ValueData::Bool(a) => Value::Bool(a.into_iter().galloc_scollect()),
+            ValueData::Str(a) => Value::Str(strs(a)),
+            ValueData::Char(a) => Value::Char(a.into_iter().galloc_scollect()),
+            ValueData::ListInt(a) => Value::ListInt(a.into_iter().map(|l| l.into_iter().galloc_scollect()).collect_vec().into_iter().galloc_scollect()),
+            ValueData::ListStr(a) => Value::ListStr(a.into_iter().map(strs).collect_vec().into_iter().galloc_scollect()),
+            ValueData::BitVector(n, a) => Value::BitVector(n, a.into_iter().galloc_scollect()),
+            ValueData::MapStr(a) => Value::MapStr(a.into_iter().map(alloc_map).collect_vec().into_iter().galloc_scollect()),
+            ValueData::Tuple(a) => Value::Tuple(alloc_map(a)),
+            ValueData::Null => Value::Null,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "ConstValue")]
+/// Owned counterpart of [`ConstValue`], with the variants in the same order so that both share one serialized form.
+enum ConstData {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Str(String),
+    Float(F64),
+    Char(char),
+    BitVector(usize, u64),
+    MapStr(Vec<(String, String)>),
+    Tuple(String, String),
+    ListStr(Vec<String>),
+    Expr(ExprData),
+}
+
+impl<'de> Deserialize<'de> for ConstValue {
+    /// Deserializes a constant, allocating its content in the arena of the current thread.
+    /// 
+    /// Operators of expression constants are rebuilt with their default configuration.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match ConstData::deserialize(deserializer)? {
+            ConstData::Null => ConstValue::Null,
+            ConstData::Bool(b) => ConstValue::Bool(b),
+            ConstData::Int(i) => ConstValue::Int(i),
+            ConstData::Str(s) => ConstValue::Str(s.galloc_str()),
+            ConstData::Float(f) => ConstValue::Float(f),
+            ConstData::Char(c) => ConstValue::Char(c),
+            ConstData::BitVector(n, v) => ConstValue::BitVector(n, v),
+            ConstData::MapStr(m) => ConstValue::MapStr(alloc_map(m)),
+            ConstData::Tuple(a, b) => ConstValue::Tuple(a.galloc_str(), b.galloc_str()),
+            ConstData::ListStr(l) => ConstValue::ListStr(l.into_iter().map(|s| s.galloc_str()).collect_vec().into_iter().galloc_scollect()),
+            ConstData::Expr(e) => ConstValue::Expr(e.alloc(&OpTable::default()).map_err(D::Error::custom)?),
+        })
+    }
+}